@@ -8,6 +8,8 @@
 
 /// This is a module for benchmarking file operations.
 mod bench_file;
+/// This is a module for benchmarking synthetic fixture sites.
+mod bench_fixtures;
 /// This is a module for benchmarking yaml operations.
 mod bench_utilities;
 
@@ -20,6 +22,7 @@
     // Targets of the group.
     targets =
         bench_file::bench_file,
+        bench_fixtures::bench_fixtures,
         bench_utilities::bench_utilities,
 }
 
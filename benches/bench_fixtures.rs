@@ -0,0 +1,55 @@
+// Copyright © 2023-2025 Shokunin Static Site Generator. All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Benchmarks over synthetic fixture sites.
+//!
+//! Generates a multilingual content tree once and measures how directory
+//! traversal and copying scale with the number of pages.
+
+use criterion::{black_box, Criterion};
+use ssg::{
+    collect_files_recursive, copy_dir_all,
+    fixtures::{generate_fixture, FixtureOptions},
+};
+use tempfile::tempdir;
+
+/// Number of pages in the benchmark fixture.
+const FIXTURE_PAGES: usize = 1_000;
+
+/// Runs traversal and copy benchmarks against a generated fixture.
+///
+/// # Arguments
+///
+/// * `c` - A reference to a `Criterion` instance used for benchmark configuration
+///         and measurement.
+#[allow(dead_code)]
+pub(crate) fn bench_fixtures(c: &mut Criterion) {
+    let source = tempdir().expect("Failed to create fixture directory");
+    let options = FixtureOptions {
+        pages: FIXTURE_PAGES,
+        languages: vec!["en".to_string(), "fr".to_string()],
+        ..FixtureOptions::default()
+    };
+    let _ = generate_fixture(source.path(), &options)
+        .expect("Failed to generate fixture");
+
+    let mut group = c.benchmark_group("fixture");
+    let _ = group.bench_function("collect_files_recursive", |b| {
+        b.iter(|| {
+            let mut files = Vec::new();
+            collect_files_recursive(source.path(), &mut files)
+                .expect("Failed to collect fixture files");
+            black_box(files)
+        })
+    });
+    let _ = group.bench_function("copy_dir_all", |b| {
+        b.iter_with_setup(
+            || tempdir().expect("Failed to create destination"),
+            |destination| {
+                copy_dir_all(source.path(), destination.path())
+                    .expect("Failed to copy fixture");
+            },
+        )
+    });
+    group.finish();
+}
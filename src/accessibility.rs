@@ -0,0 +1,984 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Accessibility Statement
+//!
+//! Audits the generated pages and writes an accessibility statement,
+//! `accessibility/index.html`, from the `[accessibility]` table of the
+//! configuration file:
+//!
+//! ```toml
+//! [accessibility]
+//! enabled = true
+//! standard = "WCAG 2.1"
+//! level = "AA"
+//! contact_email = "accessibility@example.com"
+//! known_issues = ["Videos recorded before 2024 have no captions."]
+//! audit_report = "reports/axe.json"
+//! ```
+//!
+//! Every build runs a built-in audit of the generated HTML (page
+//! language, titles, image alternatives, link names and heading order),
+//! so the statement always lists the issues of the current site. Issues
+//! from an external tool can be added with `audit_report`, a JSON list
+//! of `{ "page", "rule", "message" }` objects.
+//!
+//! The audit results are also written to `accessibility.json` for
+//! themes that render their own statement.
+//!
+//! With `audit = true`, the audit also runs as a build stage of its own,
+//! whether or not the statement is enabled. It writes a per-page report,
+//! `accessibility-audit.json`, logs a summary of each page with issues,
+//! and fails the build on issues of the `fail_on` severity or worse:
+//!
+//! ```toml
+//! [accessibility]
+//! audit = true
+//! fail_on = "error"
+//! ```
+//!
+//! Missing page languages, titles, image alternatives and link names
+//! are errors, skipped heading levels and low contrast between inline
+//! `color` and `background-color` styles are warnings, and inline text
+//! colours without a background to compare with are notices.
+
+use anyhow::{bail, ensure, Context, Result};
+use dtt::datetime::DateTime;
+use log::warn;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write as _},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    auxiliary::AuxiliaryGenerator, collect_files_recursive,
+    content::ContentSet, template::escape_html,
+};
+
+/// Path of the statement page, relative to the site directory.
+pub const STATEMENT_PATH: &str = "accessibility/index.html";
+
+/// Path of the audit results, relative to the site directory.
+pub const AUDIT_PATH: &str = "accessibility.json";
+
+/// Path of the per-page report of the audit stage, relative to the site
+/// directory.
+pub const AUDIT_REPORT_PATH: &str = "accessibility-audit.json";
+
+/// Lowest contrast ratio of normal text at level AA.
+const MIN_CONTRAST_RATIO: f64 = 4.5;
+
+/// Pages listed per rule in the statement.
+const MAX_PAGES_PER_RULE: usize = 5;
+
+static HTML_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)<html\b[^>]*>")
+        .unwrap_or_else(|e| panic!("Invalid html regex: {}", e))
+});
+
+static LANG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\slang\s*=\s*(?:"[^"\s]+"|'[^'\s]+'|[^\s>]+)"#)
+        .unwrap_or_else(|e| panic!("Invalid lang regex: {}", e))
+});
+
+static TITLE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<title\b[^>]*>(.*?)</title>")
+        .unwrap_or_else(|e| panic!("Invalid title regex: {}", e))
+});
+
+static IMG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)<img\b[^>]*>")
+        .unwrap_or_else(|e| panic!("Invalid img regex: {}", e))
+});
+
+static ALT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\salt\s*=")
+        .unwrap_or_else(|e| panic!("Invalid alt regex: {}", e))
+});
+
+static LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<a\b([^>]*)>(.*?)</a>")
+        .unwrap_or_else(|e| panic!("Invalid link regex: {}", e))
+});
+
+static LABEL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\s(?:aria-label|aria-labelledby|title)\s*=\s*(?:"[^"]*\S[^"]*"|'[^']*\S[^']*')"#)
+        .unwrap_or_else(|e| panic!("Invalid label regex: {}", e))
+});
+
+static IMG_ALT_TEXT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)<img\b[^>]*\salt\s*=\s*(?:"[^"]*\S[^"]*"|'[^']*\S[^']*')"#)
+        .unwrap_or_else(|e| panic!("Invalid img alt regex: {}", e))
+});
+
+static TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<[^>]*>")
+        .unwrap_or_else(|e| panic!("Invalid tag regex: {}", e))
+});
+
+static HEADING_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)<h([1-6])\b")
+        .unwrap_or_else(|e| panic!("Invalid heading regex: {}", e))
+});
+
+static STYLE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\sstyle\s*=\s*(?:"([^"]*)"|'([^']*)')"#)
+        .unwrap_or_else(|e| panic!("Invalid style regex: {}", e))
+});
+
+static RGB_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^rgba?\(\s*(\d{1,3})\s*,\s*(\d{1,3})\s*,\s*(\d{1,3})\s*(?:,\s*(1|1\.0+)\s*)?\)$",
+    )
+    .unwrap_or_else(|e| panic!("Invalid rgb regex: {}", e))
+});
+
+/// `[accessibility]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilityOptions {
+    /// Writes the statement and audit results.
+    pub enabled: bool,
+    /// Standard the site aims to conform to.
+    pub standard: String,
+    /// Targeted conformance level: `A`, `AA` or `AAA`.
+    pub level: String,
+    /// Address for accessibility feedback.
+    pub contact_email: Option<String>,
+    /// Page for accessibility feedback.
+    pub contact_url: Option<String>,
+    /// Known issues listed in addition to the audit results.
+    pub known_issues: Vec<String>,
+    /// JSON report of an external audit to merge into the results.
+    pub audit_report: Option<PathBuf>,
+    /// Runs the audit as a build stage and writes its per-page report.
+    pub audit: bool,
+    /// Fails the audit stage on issues of this severity or worse.
+    pub fail_on: Option<Severity>,
+}
+
+impl Default for AccessibilityOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            standard: "WCAG 2.1".to_string(),
+            level: "AA".to_string(),
+            contact_email: None,
+            contact_url: None,
+            known_issues: Vec::new(),
+            audit_report: None,
+            audit: false,
+            fail_on: None,
+        }
+    }
+}
+
+impl AccessibilityOptions {
+    /// Checks the conformance level and contact details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            matches!(self.level.as_str(), "A" | "AA" | "AAA"),
+            "level must be A, AA or AAA, not `{}`",
+            self.level
+        );
+        ensure!(!self.standard.trim().is_empty(), "standard is empty");
+        if let Some(email) = &self.contact_email {
+            ensure!(
+                email.contains('@')
+                    && !email.contains(char::is_whitespace),
+                "Invalid contact_email `{}`",
+                email
+            );
+        }
+        if let Some(url) = &self.contact_url {
+            ensure!(
+                url.starts_with("https://")
+                    || url.starts_with("http://")
+                    || url.starts_with('/'),
+                "contact_url `{}` must be absolute",
+                url
+            );
+        }
+        Ok(())
+    }
+}
+
+/// How serious an accessibility issue is, from least to most.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Worth a manual check, e.g. a colour whose contrast is unknown.
+    Notice,
+    /// Likely to hinder some visitors.
+    Warning,
+    /// Fails a WCAG success criterion.
+    Error,
+}
+
+impl Default for Severity {
+    /// Issues of external reports without a severity are warnings.
+    fn default() -> Self {
+        Self::Warning
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Notice => "notice",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// An accessibility problem on a page.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct AuditIssue {
+    /// Site-relative page path, e.g. `blog/index.html`.
+    pub page: String,
+    /// Rule identifier, e.g. `image-alt`.
+    pub rule: String,
+    /// How serious the problem is.
+    #[serde(default)]
+    pub severity: Severity,
+    /// Description of the problem.
+    pub message: String,
+}
+
+/// Results of an audit.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct AuditReport {
+    /// Number of pages audited.
+    pub pages: usize,
+    /// Issues found, sorted by page and rule.
+    pub issues: Vec<AuditIssue>,
+}
+
+impl AuditReport {
+    /// Returns the number of issues of `severity`.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .count()
+    }
+
+    /// Returns the issues grouped by page.
+    pub fn by_page(&self) -> BTreeMap<&str, Vec<&AuditIssue>> {
+        let mut pages: BTreeMap<&str, Vec<&AuditIssue>> =
+            BTreeMap::new();
+        for issue in &self.issues {
+            pages.entry(&issue.page).or_default().push(issue);
+        }
+        pages
+    }
+
+    /// Returns the per-page report written by the audit stage.
+    pub fn to_json(&self) -> Value {
+        let pages: BTreeMap<&str, Vec<Value>> = self
+            .by_page()
+            .into_iter()
+            .map(|(page, issues)| {
+                let issues = issues
+                    .into_iter()
+                    .map(|issue| {
+                        json!({
+                            "rule": issue.rule,
+                            "severity": issue.severity,
+                            "criterion": criterion(&issue.rule),
+                            "message": issue.message,
+                        })
+                    })
+                    .collect();
+                (page, issues)
+            })
+            .collect();
+        json!({
+            "pages": self.pages,
+            "errors": self.count(Severity::Error),
+            "warnings": self.count(Severity::Warning),
+            "notices": self.count(Severity::Notice),
+            "issues": pages,
+        })
+    }
+}
+
+/// Returns the WCAG success criterion checked by a built-in rule.
+pub fn criterion(rule: &str) -> Option<&'static str> {
+    Some(match rule {
+        "image-alt" => "1.1.1 Non-text Content",
+        "heading-order" => "1.3.1 Info and Relationships",
+        "contrast-hint" => "1.4.3 Contrast (Minimum)",
+        "document-title" => "2.4.2 Page Titled",
+        "link-name" => "2.4.4 Link Purpose (In Context)",
+        "html-lang" => "3.1.1 Language of Page",
+        _ => return None,
+    })
+}
+
+/// Audits one HTML page with the built-in rules.
+pub fn audit_html(page: &str, html: &str) -> Vec<AuditIssue> {
+    let mut issues = Vec::new();
+    let mut issue =
+        |rule: &str, severity: Severity, message: String| {
+            issues.push(AuditIssue {
+                page: page.to_string(),
+                rule: rule.to_string(),
+                severity,
+                message,
+            });
+        };
+
+    if let Some(tag) = HTML_RE.find(html) {
+        if !LANG_RE.is_match(tag.as_str()) {
+            issue(
+                "html-lang",
+                Severity::Error,
+                "The page has no `lang`".to_string(),
+            );
+        }
+    }
+    let titled = TITLE_RE
+        .captures(html)
+        .map_or(false, |c| !c[1].trim().is_empty());
+    if HTML_RE.is_match(html) && !titled {
+        issue(
+            "document-title",
+            Severity::Error,
+            "The page has no title".to_string(),
+        );
+    }
+    for img in IMG_RE.find_iter(html) {
+        if !ALT_RE.is_match(img.as_str()) {
+            issue(
+                "image-alt",
+                Severity::Error,
+                format!(
+                    "Image without alternative text: {}",
+                    img.as_str()
+                ),
+            );
+        }
+    }
+    for link in LINK_RE.captures_iter(html) {
+        let text = TAG_RE.replace_all(&link[2], "");
+        if text.trim().is_empty()
+            && !LABEL_RE.is_match(&link[1])
+            && !IMG_ALT_TEXT_RE.is_match(&link[2])
+        {
+            issue(
+                "link-name",
+                Severity::Error,
+                format!("Link without a name: {}", &link[0]),
+            );
+        }
+    }
+    let mut previous = 0;
+    for heading in HEADING_RE.captures_iter(html) {
+        let level: usize = heading[1].parse().unwrap_or(1);
+        if previous > 0 && level > previous + 1 {
+            issue(
+                "heading-order",
+                Severity::Warning,
+                format!("<h{}> follows <h{}>", level, previous),
+            );
+        }
+        previous = level;
+    }
+    for style in STYLE_RE.captures_iter(html) {
+        let declarations = style
+            .get(1)
+            .or_else(|| style.get(2))
+            .map_or("", |m| m.as_str());
+        let (mut color, mut background) = (None, None);
+        for declaration in declarations.split(';') {
+            if let Some((property, value)) = declaration.split_once(':')
+            {
+                match property.trim().to_ascii_lowercase().as_str() {
+                    "color" => color = parse_color(value),
+                    "background-color" => {
+                        background = parse_color(value)
+                    }
+                    "background" => {
+                        background = value
+                            .split_whitespace()
+                            .find_map(parse_color)
+                            .or(background);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        match (color, background) {
+            (Some(color), Some(background)) => {
+                let ratio = contrast_ratio(color, background);
+                if ratio < MIN_CONTRAST_RATIO {
+                    issue(
+                        "contrast-hint",
+                        Severity::Warning,
+                        format!(
+                            "Text colour {} on {} has a contrast ratio of \
+                             {:.2}:1, below {}:1",
+                            hex_color(color),
+                            hex_color(background),
+                            ratio,
+                            MIN_CONTRAST_RATIO
+                        ),
+                    );
+                }
+            }
+            (Some(color), None) => issue(
+                "contrast-hint",
+                Severity::Notice,
+                format!(
+                    "Text colour {} is set without a background colour; \
+                     check its contrast",
+                    hex_color(color)
+                ),
+            ),
+            _ => {}
+        }
+    }
+    issues
+}
+
+/// Parses a CSS colour written as `#rgb`, `#rrggbb`, an opaque `rgb()`,
+/// `black` or `white`.
+fn parse_color(value: &str) -> Option<[u8; 3]> {
+    let value = value
+        .trim()
+        .trim_end_matches("!important")
+        .trim()
+        .to_ascii_lowercase();
+    if let Some(hex) = value.strip_prefix('#') {
+        let digits: Vec<u8> = hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8))
+            .collect::<Option<_>>()?;
+        return match digits.as_slice() {
+            [r, g, b] => Some([r * 17, g * 17, b * 17]),
+            [r1, r2, g1, g2, b1, b2] => {
+                Some([r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2])
+            }
+            _ => None,
+        };
+    }
+    if let Some(caps) = RGB_RE.captures(&value) {
+        let channel = |i: usize| caps[i].parse::<u8>().ok();
+        return Some([channel(1)?, channel(2)?, channel(3)?]);
+    }
+    match value.as_str() {
+        "black" => Some([0, 0, 0]),
+        "white" => Some([255, 255, 255]),
+        _ => None,
+    }
+}
+
+/// Returns the WCAG contrast ratio of two colours, from 1 to 21.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::accessibility::contrast_ratio;
+///
+/// assert_eq!(contrast_ratio([0, 0, 0], [255, 255, 255]), 21.0);
+/// assert!(contrast_ratio([119, 119, 119], [255, 255, 255]) < 4.5);
+/// ```
+pub fn contrast_ratio(a: [u8; 3], b: [u8; 3]) -> f64 {
+    let luminance = |color: [u8; 3]| {
+        let [r, g, b] = color.map(|channel| {
+            let c = f64::from(channel) / 255.0;
+            if c <= 0.039_28 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        });
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    };
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+fn hex_color(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// Audits every HTML page of `site_dir` except the statement itself.
+///
+/// # Errors
+///
+/// Returns an error if the site cannot be read.
+pub fn audit_site(site_dir: &Path) -> Result<AuditReport> {
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    files.sort();
+
+    let mut report = AuditReport::default();
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let page = path
+            .strip_prefix(site_dir)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if page == STATEMENT_PATH {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        report.pages += 1;
+        report.issues.extend(audit_html(&page, &html));
+    }
+    Ok(report)
+}
+
+/// Runs the audit stage: audits `site_dir`, writes
+/// [`AUDIT_REPORT_PATH`] and logs a summary of each page with issues.
+///
+/// # Errors
+///
+/// Returns an error if the site cannot be read, the report cannot be
+/// written, or issues of the `fail_on` severity or worse are found.
+pub fn run_audit(
+    site_dir: &Path,
+    options: &AccessibilityOptions,
+) -> Result<AuditReport> {
+    if !options.audit {
+        return Ok(AuditReport::default());
+    }
+    let report = audit_site(site_dir)?;
+    let path = site_dir.join(AUDIT_REPORT_PATH);
+    let json = serde_json::to_string_pretty(&report.to_json())
+        .context("Failed to serialize the audit report")?;
+    fs::write(&path, json).with_context(|| {
+        format!("Failed to write {}", path.display())
+    })?;
+
+    for (page, issues) in report.by_page() {
+        let count = |severity: Severity| {
+            issues.iter().filter(|i| i.severity == severity).count()
+        };
+        warn!(
+            "Accessibility: {} has {} errors, {} warnings and {} notices",
+            page,
+            count(Severity::Error),
+            count(Severity::Warning),
+            count(Severity::Notice)
+        );
+    }
+    if let Some(threshold) = options.fail_on {
+        let failing = report
+            .issues
+            .iter()
+            .filter(|issue| issue.severity >= threshold)
+            .count();
+        if failing > 0 {
+            bail!(
+                "{} accessibility issues of severity {} or worse; see {}",
+                failing,
+                threshold,
+                path.display()
+            );
+        }
+    }
+    Ok(report)
+}
+
+/// Reads the issues of an external audit report.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or is not a JSON list of
+/// issues.
+pub fn load_audit_report(path: &Path) -> Result<Vec<AuditIssue>> {
+    let json = fs::read_to_string(path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    serde_json::from_str(&json).with_context(|| {
+        format!("Invalid audit report {}", path.display())
+    })
+}
+
+/// Renders the statement page.
+pub fn statement_html(
+    options: &AccessibilityOptions,
+    site_title: &str,
+    language: &str,
+    report: &AuditReport,
+    date: &str,
+) -> String {
+    let title = escape_html(site_title);
+    let conformance = if report.issues.is_empty()
+        && options.known_issues.is_empty()
+    {
+        "fully conformant"
+    } else {
+        "partially conformant"
+    };
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"{lang}\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>Accessibility statement - {title}</title>\n</head>\n<body>\n<main>\n\
+         <h1>Accessibility statement for {title}</h1>\n\
+         <p>{title} aims to conform to {standard} level {level}. \
+         This site is {conformance} with that standard.</p>\n",
+        lang = escape_html(language),
+        title = title,
+        standard = escape_html(&options.standard),
+        level = escape_html(&options.level),
+        conformance = conformance,
+    );
+
+    html.push_str("<h2>Known issues</h2>\n");
+    if report.issues.is_empty() && options.known_issues.is_empty() {
+        html.push_str("<p>No known issues.</p>\n");
+    } else {
+        html.push_str("<ul>\n");
+        for issue in &options.known_issues {
+            let _ = writeln!(html, "<li>{}</li>", escape_html(issue));
+        }
+        let mut by_rule: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for issue in &report.issues {
+            let pages = by_rule.entry(&issue.rule).or_default();
+            if !pages.contains(&issue.page.as_str()) {
+                pages.push(&issue.page);
+            }
+        }
+        for (rule, pages) in by_rule {
+            let occurrences =
+                report.issues.iter().filter(|i| i.rule == rule).count();
+            let _ = write!(
+                html,
+                "<li>{}: {} occurrence{} on {} page{}",
+                escape_html(criterion(rule).unwrap_or(rule)),
+                occurrences,
+                if occurrences == 1 { "" } else { "s" },
+                pages.len(),
+                if pages.len() == 1 { "" } else { "s" },
+            );
+            let links: Vec<String> = pages
+                .iter()
+                .take(MAX_PAGES_PER_RULE)
+                .map(|page| {
+                    let page = escape_html(page);
+                    format!("<a href=\"/{}\">{}</a>", page, page)
+                })
+                .collect();
+            let _ = writeln!(html, " ({})</li>", links.join(", "));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    if options.contact_email.is_some() || options.contact_url.is_some()
+    {
+        html.push_str(
+            "<h2>Feedback</h2>\n<p>If you find a barrier on this site, please tell us",
+        );
+        if let Some(email) = &options.contact_email {
+            let email = escape_html(email);
+            let _ = write!(
+                html,
+                " at <a href=\"mailto:{0}\">{0}</a>",
+                email
+            );
+        }
+        if let Some(url) = &options.contact_url {
+            let _ = write!(
+                html,
+                " using <a href=\"{}\">our feedback page</a>",
+                escape_html(url)
+            );
+        }
+        html.push_str(".</p>\n");
+    }
+
+    let _ = write!(
+        html,
+        "<p>This statement was last updated on {} from an automated audit of {} page{}.</p>\n\
+         </main>\n</body>\n</html>\n",
+        escape_html(date),
+        report.pages,
+        if report.pages == 1 { "" } else { "s" },
+    );
+    html
+}
+
+/// Writes the accessibility statement and audit results.
+#[derive(Debug, Clone)]
+pub struct AccessibilityGenerator {
+    /// The `[accessibility]` options.
+    pub options: AccessibilityOptions,
+    /// Site title used in the statement.
+    pub site_title: String,
+    /// Language of the statement page.
+    pub language: String,
+}
+
+impl AuxiliaryGenerator for AccessibilityGenerator {
+    fn name(&self) -> &str {
+        "accessibility"
+    }
+
+    fn generate(
+        &self,
+        _content: &ContentSet,
+        site_dir: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        if !self.options.enabled {
+            return Ok(Vec::new());
+        }
+        let mut report = audit_site(site_dir)?;
+        // Notices ask for a manual check rather than report a barrier.
+        report
+            .issues
+            .retain(|issue| issue.severity > Severity::Notice);
+        if let Some(path) = &self.options.audit_report {
+            report.issues.extend(load_audit_report(path)?);
+        }
+        report.issues.sort();
+        report.issues.dedup();
+
+        let date = DateTime::new()
+            .format("[year]-[month]-[day]")
+            .unwrap_or_default();
+        let statement = site_dir.join(STATEMENT_PATH);
+        if let Some(parent) = statement.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        fs::write(
+            &statement,
+            statement_html(
+                &self.options,
+                &self.site_title,
+                &self.language,
+                &report,
+                &date,
+            ),
+        )
+        .with_context(|| {
+            format!("Failed to write {}", statement.display())
+        })?;
+
+        let audit = site_dir.join(AUDIT_PATH);
+        let json = serde_json::to_string_pretty(&report)
+            .context("Failed to serialize the audit report")?;
+        fs::write(&audit, json).with_context(|| {
+            format!("Failed to write {}", audit.display())
+        })?;
+        Ok(vec![statement, audit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn rules(html: &str) -> Vec<String> {
+        audit_html("index.html", html)
+            .into_iter()
+            .map(|issue| issue.rule)
+            .collect()
+    }
+
+    #[test]
+    fn test_audit_rules() {
+        let good = r#"<html lang="en"><head><title>Home</title></head><body>
+            <h1>Home</h1><h2>News</h2>
+            <img src="a.png" alt=""><a href="/">Home</a>
+            <a href="/x" aria-label="Close"></a>
+            <a href="/y"><img src="y.png" alt="Next"></a>
+            </body></html>"#;
+        assert!(rules(good).is_empty());
+
+        let bad = r#"<html><head><title> </title></head><body>
+            <h1>Home</h1><h3>Skipped</h3>
+            <img src="a.png"><a href="/"><span></span></a>
+            </body></html>"#;
+        assert_eq!(
+            rules(bad),
+            [
+                "html-lang",
+                "document-title",
+                "image-alt",
+                "link-name",
+                "heading-order"
+            ]
+        );
+        assert!(rules("<p>fragment</p>").is_empty());
+    }
+
+    #[test]
+    fn test_generator_writes_statement() -> Result<()> {
+        let site = tempdir()?;
+        let external = site.path().join("external.json");
+        fs::write(
+            site.path().join("index.html"),
+            r#"<html lang="en"><head><title>Home</title></head><body><img src="a.png"></body></html>"#,
+        )?;
+        fs::write(
+            &external,
+            r#"[{"page": "index.html", "rule": "color-contrast", "message": "Low contrast"}]"#,
+        )?;
+
+        let options = AccessibilityOptions {
+            enabled: true,
+            contact_email: Some("a11y@example.com".into()),
+            known_issues: vec![
+                "Old videos lack captions & transcripts.".into(),
+            ],
+            audit_report: Some(external),
+            ..AccessibilityOptions::default()
+        };
+        options.validate()?;
+        let generator = AccessibilityGenerator {
+            options,
+            site_title: "Example".into(),
+            language: "en-GB".into(),
+        };
+        let outputs =
+            generator.generate(&ContentSet::default(), site.path())?;
+        assert_eq!(outputs.len(), 2);
+
+        let html =
+            fs::read_to_string(site.path().join(STATEMENT_PATH))?;
+        assert!(html.contains("<html lang=\"en-GB\">"));
+        assert!(html.contains("aims to conform to WCAG 2.1 level AA"));
+        assert!(html.contains("partially conformant"));
+        assert!(html.contains("captions &amp; transcripts"));
+        assert!(html.contains(
+            "1.1.1 Non-text Content: 1 occurrence on 1 page (<a href=\"/index.html\">index.html</a>)"
+        ));
+        assert!(html.contains("color-contrast: 1 occurrence"));
+        assert!(html.contains("mailto:a11y@example.com"));
+        assert!(html.contains("automated audit of 1 page."));
+
+        // The statement is not audited, so a rerun gives the same result.
+        let report: AuditReport = serde_json::from_str(
+            &fs::read_to_string(site.path().join(AUDIT_PATH))?,
+        )?;
+        assert_eq!(report.issues.len(), 2);
+        assert_eq!(audit_site(site.path())?.pages, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_contrast_hints() {
+        let issues = audit_html(
+            "index.html",
+            r#"<p style="color: #777; background-color: #fff">Grey</p>
+            <p style='color:rgb(0, 0, 0);background:url(a.png) #ffffff'>Black</p>
+            <span style="color: white">Unknown</span>
+            <span style="margin: 0">Plain</span>"#,
+        );
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(
+            issues[0].message,
+            "Text colour #777777 on #ffffff has a contrast ratio of 4.48:1, below 4.5:1"
+        );
+        assert_eq!(issues[1].severity, Severity::Notice);
+        assert!(issues[1].message.contains("#ffffff"));
+        assert_eq!(parse_color("#12"), None);
+        assert_eq!(parse_color("rgba(0, 0, 0, 0.5)"), None);
+    }
+
+    #[test]
+    fn test_run_audit() -> Result<()> {
+        let site = tempdir()?;
+        fs::write(
+            site.path().join("index.html"),
+            r#"<html lang="en"><head><title>Home</title></head><body><h1>A</h1><h3>B</h3></body></html>"#,
+        )?;
+        fs::write(
+            site.path().join("about.html"),
+            r#"<html><head><title>About</title></head><body></body></html>"#,
+        )?;
+
+        let mut options = AccessibilityOptions::default();
+        assert_eq!(run_audit(site.path(), &options)?.pages, 0);
+        assert!(!site.path().join(AUDIT_REPORT_PATH).exists());
+
+        options.audit = true;
+        options.fail_on = Some(Severity::Error);
+        let error = run_audit(site.path(), &options).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("1 accessibility issues of severity error"));
+
+        let report: Value = serde_json::from_str(&fs::read_to_string(
+            site.path().join(AUDIT_REPORT_PATH),
+        )?)?;
+        assert_eq!(report["pages"], 2);
+        assert_eq!(report["errors"], 1);
+        assert_eq!(report["warnings"], 1);
+        assert_eq!(
+            report["issues"]["about.html"][0]["rule"],
+            "html-lang"
+        );
+        assert_eq!(
+            report["issues"]["index.html"][0]["criterion"],
+            "1.3.1 Info and Relationships"
+        );
+        assert_eq!(
+            report["issues"]["index.html"][0]["severity"],
+            "warning"
+        );
+
+        options.fail_on = Some(Severity::Warning);
+        assert!(run_audit(site.path(), &options).is_err());
+        options.fail_on = None;
+        assert_eq!(run_audit(site.path(), &options)?.issues.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() {
+        let invalid = AccessibilityOptions {
+            level: "AAAA".into(),
+            ..AccessibilityOptions::default()
+        };
+        assert!(invalid.validate().is_err());
+        assert!(AccessibilityOptions::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,448 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Text Analysis
+//!
+//! Language-specific analyzers used by the search index. An analyzer
+//! splits text into words, drops the stop words of its language, reduces
+//! each word to a stem and folds accents, so that "Learning", "learned"
+//! and "learns" all index as `learn`, and "Straße" matches "strasse".
+//!
+//! English, French, German and Spanish have stop word lists and light
+//! suffix-stripping stemmers in the spirit of the Lucene light stemmers.
+//! Other languages are split and lowercased only.
+//!
+//! ```
+//! use ssg::analysis::Analyzer;
+//!
+//! let analyzer = Analyzer::for_language("fr-FR");
+//! assert_eq!(analyzer.analyze("Les chevaux rapides"), vec!["cheval", "rapid"]);
+//! ```
+
+use crate::locales::LanguageTag;
+
+/// A language with a dedicated analyzer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Language {
+    /// English (`en`).
+    English,
+    /// French (`fr`).
+    French,
+    /// German (`de`).
+    German,
+    /// Spanish (`es`).
+    Spanish,
+}
+
+const ENGLISH_STOP_WORDS: &[&str] = &[
+    "a", "about", "an", "and", "are", "as", "at", "be", "been", "but",
+    "by", "can", "do", "for", "from", "had", "has", "have", "he",
+    "her", "his", "how", "i", "if", "in", "into", "is", "it", "its",
+    "not", "of", "on", "or", "our", "she", "so", "than", "that", "the",
+    "their", "them", "then", "there", "these", "they", "this", "to",
+    "was", "we", "were", "what", "when", "which", "who", "will",
+    "with", "you", "your",
+];
+
+const FRENCH_STOP_WORDS: &[&str] = &[
+    "au", "aux", "avec", "ce", "ces", "cette", "dans", "de", "des",
+    "du", "elle", "en", "est", "et", "eux", "il", "ils", "je", "la",
+    "le", "les", "leur", "lui", "ma", "mais", "me", "mes", "moi",
+    "mon", "ne", "nos", "notre", "nous", "on", "ou", "par", "pas",
+    "pour", "qu", "que", "qui", "sa", "se", "ses", "son", "sont",
+    "sur", "ta", "te", "tes", "toi", "ton", "tu", "un", "une", "vos",
+    "votre", "vous",
+];
+
+const GERMAN_STOP_WORDS: &[&str] = &[
+    "aber", "als", "am", "an", "auch", "auf", "aus", "bei", "bin",
+    "bis", "das", "dass", "dem", "den", "der", "des", "die", "du",
+    "ein", "eine", "einem", "einen", "einer", "es", "für", "hat",
+    "ich", "ihr", "im", "in", "ist", "ja", "kann", "mit", "nach",
+    "nicht", "noch", "nur", "oder", "sich", "sie", "sind", "so", "und",
+    "uns", "von", "vor", "war", "was", "wie", "wir", "wird", "zu",
+    "zum", "zur",
+];
+
+const SPANISH_STOP_WORDS: &[&str] = &[
+    "a", "al", "como", "con", "de", "del", "el", "ella", "en", "es",
+    "esta", "este", "fue", "ha", "la", "las", "le", "les", "lo", "los",
+    "mas", "me", "mi", "muy", "no", "nos", "o", "para", "pero", "por",
+    "que", "se", "si", "sin", "su", "sus", "te", "tu", "un", "una",
+    "uno", "y", "ya", "yo",
+];
+
+impl Language {
+    /// Returns the language of a code such as `fr`, `de-AT` or `en_GB`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::analysis::Language;
+    ///
+    /// assert_eq!(Language::from_code("de-AT"), Some(Language::German));
+    /// assert_eq!(Language::from_code("ja"), None);
+    /// ```
+    pub fn from_code(code: &str) -> Option<Self> {
+        match primary_subtag(code).as_str() {
+            "en" => Some(Self::English),
+            "fr" => Some(Self::French),
+            "de" => Some(Self::German),
+            "es" => Some(Self::Spanish),
+            _ => None,
+        }
+    }
+
+    /// The two-letter ISO 639-1 code.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::French => "fr",
+            Self::German => "de",
+            Self::Spanish => "es",
+        }
+    }
+
+    /// Words that are not indexed.
+    pub fn stop_words(self) -> &'static [&'static str] {
+        match self {
+            Self::English => ENGLISH_STOP_WORDS,
+            Self::French => FRENCH_STOP_WORDS,
+            Self::German => GERMAN_STOP_WORDS,
+            Self::Spanish => SPANISH_STOP_WORDS,
+        }
+    }
+
+    /// Returns `true` if the lowercase `word` is a stop word.
+    pub fn is_stop_word(self, word: &str) -> bool {
+        self.stop_words().contains(&word)
+    }
+
+    /// Reduces a lowercase word to its stem.
+    pub fn stem(self, word: &str) -> String {
+        match self {
+            Self::English => stem_english(word),
+            Self::French => stem_french(word),
+            Self::German => stem_german(&fold_accents(word)),
+            Self::Spanish => stem_spanish(&fold_accents(word)),
+        }
+    }
+}
+
+/// Turns text into index terms for one language.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Analyzer {
+    language: Option<Language>,
+}
+
+impl Analyzer {
+    /// Creates an analyzer for `language`.
+    pub fn new(language: Language) -> Self {
+        Self {
+            language: Some(language),
+        }
+    }
+
+    /// Creates an analyzer for a language code.
+    ///
+    /// Unsupported languages split and lowercase text only.
+    pub fn for_language(code: &str) -> Self {
+        Self {
+            language: Language::from_code(code),
+        }
+    }
+
+    /// The language of this analyzer, if it has a dedicated one.
+    pub fn language(&self) -> Option<Language> {
+        self.language
+    }
+
+    /// Splits `text` into stemmed, accent-folded terms without stop
+    /// words.
+    pub fn analyze(&self, text: &str) -> Vec<String> {
+        words(text)
+            .filter(|word| {
+                !matches!(
+                    self.language,
+                    Some(language) if language.is_stop_word(word)
+                )
+            })
+            .map(|word| match self.language {
+                Some(language) => fold_accents(&language.stem(&word)),
+                None => word,
+            })
+            .collect()
+    }
+}
+
+/// Splits text into lowercase words of at least two characters.
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(str::to_lowercase)
+}
+
+/// Returns the lowercase primary subtag of a language code.
+fn primary_subtag(code: &str) -> String {
+    LanguageTag::parse_lenient(code)
+        .map(|tag| tag.language().to_string())
+        .unwrap_or_default()
+}
+
+/// Replaces accented Latin letters with their base letters.
+pub fn fold_accents(word: &str) -> String {
+    let mut folded = String::with_capacity(word.len());
+    for c in word.chars() {
+        match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => folded.push('a'),
+            'ç' => folded.push('c'),
+            'è' | 'é' | 'ê' | 'ë' => folded.push('e'),
+            'ì' | 'í' | 'î' | 'ï' => folded.push('i'),
+            'ñ' => folded.push('n'),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' => folded.push('o'),
+            'ù' | 'ú' | 'û' | 'ü' => folded.push('u'),
+            'ý' | 'ÿ' => folded.push('y'),
+            'ß' => folded.push_str("ss"),
+            'œ' => folded.push_str("oe"),
+            'æ' => folded.push_str("ae"),
+            c => folded.push(c),
+        }
+    }
+    folded
+}
+
+fn len(word: &str) -> usize {
+    word.chars().count()
+}
+
+fn strip<'a>(word: &'a str, suffix: &str) -> &'a str {
+    word.strip_suffix(suffix).unwrap_or(word)
+}
+
+fn has_vowel(word: &str) -> bool {
+    word.chars().any(|c| "aeiouy".contains(c))
+}
+
+fn stem_english(word: &str) -> String {
+    if len(word) <= 3 {
+        return word.to_string();
+    }
+    let word = if let Some(stem) = word.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if word.ends_with("sses") {
+        strip(word, "es").to_string()
+    } else if word.ends_with('s')
+        && !word.ends_with("ss")
+        && !word.ends_with("us")
+        && !word.ends_with("is")
+    {
+        strip(word, "s").to_string()
+    } else {
+        word.to_string()
+    };
+
+    for suffix in ["ingly", "edly", "ing", "ed"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if len(stem) >= 3 && has_vowel(stem) {
+                return undouble(stem);
+            }
+        }
+    }
+    for suffix in ["ness", "ly"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if len(stem) >= 3 {
+                return stem.to_string();
+            }
+        }
+    }
+    word
+}
+
+/// Removes a doubled final consonant, as in "running" → "run".
+fn undouble(stem: &str) -> String {
+    let chars: Vec<char> = stem.chars().collect();
+    match chars.as_slice() {
+        [.., a, b]
+            if a == b
+                && !"aeiouylsz".contains(*a)
+                && a.is_alphabetic() =>
+        {
+            chars[..chars.len() - 1].iter().collect()
+        }
+        _ => stem.to_string(),
+    }
+}
+
+fn stem_french(word: &str) -> String {
+    let mut word = word.to_string();
+    if len(&word) > 5 && word.ends_with("aux") {
+        word = format!("{}al", strip(&word, "aux"));
+    } else if len(&word) > 3
+        && (word.ends_with('s') || word.ends_with('x'))
+    {
+        let _ = word.pop();
+    }
+    if len(&word) > 6 && word.ends_with("ement") {
+        word = strip(&word, "ment").to_string();
+    }
+    for (suffix, replacement) in [
+        ("euse", "eux"),
+        ("ive", "if"),
+        ("ère", "er"),
+        ("ienne", "ien"),
+    ] {
+        if len(&word) > 5 && word.ends_with(suffix) {
+            word = format!("{}{}", strip(&word, suffix), replacement);
+            break;
+        }
+    }
+    if len(&word) > 4 && word.ends_with("er") {
+        word = strip(&word, "er").to_string();
+    }
+    for suffix in ["ée", "é", "e"] {
+        if len(&word) > 4 && word.ends_with(suffix) {
+            word = strip(&word, suffix).to_string();
+            break;
+        }
+    }
+    word
+}
+
+fn stem_german(word: &str) -> String {
+    let valid_s = |c: Option<char>| matches!(c, Some(c) if "bdfghklmnrt".contains(c));
+    let valid_st = |c: Option<char>| matches!(c, Some(c) if "bdfghklmnt".contains(c));
+    let before =
+        |word: &str, suffix: usize| word.chars().rev().nth(suffix);
+
+    let mut word = word.to_string();
+    if len(&word) > 5 && word.ends_with("ern") {
+        word = strip(&word, "ern").to_string();
+    } else if len(&word) > 4
+        && ["em", "en", "er", "es"].iter().any(|s| word.ends_with(s))
+    {
+        word.truncate(word.len() - 2);
+    } else if len(&word) > 3
+        && (word.ends_with('e')
+            || word.ends_with('s') && valid_s(before(&word, 1)))
+    {
+        let _ = word.pop();
+    }
+
+    if len(&word) > 5 && word.ends_with("est") {
+        word = strip(&word, "est").to_string();
+    } else if len(&word) > 4
+        && (word.ends_with("er")
+            || word.ends_with("en")
+            || word.ends_with("st") && valid_st(before(&word, 2)))
+    {
+        word.truncate(word.len() - 2);
+    }
+    word
+}
+
+fn stem_spanish(word: &str) -> String {
+    if len(word) < 5 {
+        return word.to_string();
+    }
+    if let Some(stem) = word.strip_suffix("eses") {
+        return format!("{}es", stem);
+    }
+    if let Some(stem) = word.strip_suffix("ces") {
+        return format!("{}z", stem);
+    }
+    for suffix in ["os", "as", "es", "o", "a", "e"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            return stem.to_string();
+        }
+    }
+    word.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stems(language: Language, words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| language.stem(word)).collect()
+    }
+
+    #[test]
+    fn test_language_codes() {
+        assert_eq!(
+            Language::from_code("EN_us"),
+            Some(Language::English)
+        );
+        assert_eq!(Language::from_code("fr"), Some(Language::French));
+        assert_eq!(
+            Language::from_code("es-MX"),
+            Some(Language::Spanish)
+        );
+        assert_eq!(Language::from_code(""), None);
+        assert_eq!(Language::German.code(), "de");
+    }
+
+    #[test]
+    fn test_english_stemmer() {
+        assert_eq!(
+            stems(
+                Language::English,
+                &[
+                    "learning", "learned", "learns", "running",
+                    "studies", "classes", "quickly", "rust", "status"
+                ]
+            ),
+            vec![
+                "learn", "learn", "learn", "run", "study", "class",
+                "quick", "rust", "status"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_other_stemmers() {
+        assert_eq!(
+            stems(
+                Language::French,
+                &[
+                    "chevaux",
+                    "rapidement",
+                    "chanteuse",
+                    "aimée",
+                    "aimer"
+                ]
+            ),
+            vec!["cheval", "rapid", "chanteux", "aim", "aim"]
+        );
+        assert_eq!(
+            stems(
+                Language::German,
+                &["häuser", "kindern", "straße", "gärten"]
+            ),
+            vec!["haus", "kind", "strass", "gart"]
+        );
+        assert_eq!(
+            stems(
+                Language::Spanish,
+                &["luces", "libros", "meses", "canción"]
+            ),
+            vec!["luz", "libr", "mes", "cancion"]
+        );
+    }
+
+    #[test]
+    fn test_analyzer_drops_stop_words() {
+        assert_eq!(
+            Analyzer::new(Language::German)
+                .analyze("Die Häuser und der Garten"),
+            vec!["haus", "gart"]
+        );
+        assert_eq!(
+            Analyzer::for_language("es").analyze("El perro y la casa"),
+            vec!["perr", "casa"]
+        );
+        assert_eq!(
+            Analyzer::for_language("ja").analyze("The Rust book"),
+            vec!["the", "rust", "book"]
+        );
+    }
+}
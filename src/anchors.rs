@@ -0,0 +1,283 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Heading Anchors
+//!
+//! Adds a link to each heading of the generated pages that points at
+//! the heading itself, so readers can copy a deep link to a section.
+//! Options live in the `[markdown.anchors]` table of the configuration
+//! file:
+//!
+//! ```toml
+//! [markdown.anchors]
+//! enabled = true
+//! symbol = "¶"          # the default is "#"
+//! position = "after"    # or "before"
+//! class = "anchor"
+//! min_level = 2
+//! max_level = 6
+//! ```
+//!
+//! A heading from `<h2>` to `<h6>` (by default) gets
+//! `<a class="anchor" href="#id" aria-label="Link to this section: …">#</a>`
+//! after or before its text, pointing at the unique ID the heading got
+//! from [`assign_heading_ids`], so the anchors agree with tables of
+//! contents and cross-references built from the same IDs.
+//!
+//! Anchors carry their class, so code reading heading text back from
+//! the page, such as a table of contents, can leave them out. Headings
+//! that already have an anchor are left alone.
+
+use anyhow::{ensure, Context, Result};
+use regex::Captures;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+use crate::{
+    collect_files_recursive,
+    heading_ids::{assign_heading_ids, HEADING_RE},
+    template::escape_html,
+};
+
+/// Where the anchor goes in the heading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnchorPosition {
+    /// Before the heading text.
+    Before,
+    /// After the heading text.
+    After,
+}
+
+impl Default for AnchorPosition {
+    fn default() -> Self {
+        Self::After
+    }
+}
+
+/// `[markdown.anchors]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnchorOptions {
+    /// Adds anchor links to headings.
+    pub enabled: bool,
+    /// Text of the anchor links.
+    pub symbol: String,
+    /// Where the anchor goes in the heading.
+    pub position: AnchorPosition,
+    /// Class of the anchor links.
+    pub class: String,
+    /// Highest heading level given an anchor, 1 for `<h1>`.
+    pub min_level: u8,
+    /// Lowest heading level given an anchor, 6 for `<h6>`.
+    pub max_level: u8,
+}
+
+impl Default for AnchorOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            symbol: "#".to_string(),
+            position: AnchorPosition::default(),
+            class: "anchor".to_string(),
+            min_level: 2,
+            max_level: 6,
+        }
+    }
+}
+
+impl AnchorOptions {
+    /// Checks that the symbol is set, the class is a single CSS class
+    /// name and the levels are a range of heading levels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(!self.symbol.trim().is_empty(), "symbol must be set");
+        ensure!(
+            !self.class.is_empty()
+                && self.class.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+                }),
+            "class `{}` must be a single CSS class name",
+            self.class
+        );
+        ensure!(
+            (1..=self.max_level).contains(&self.min_level)
+                && self.max_level <= 6,
+            "min_level {} and max_level {} must be heading levels from 1 \
+             to 6, in order",
+            self.min_level,
+            self.max_level
+        );
+        Ok(())
+    }
+}
+
+/// Adds anchor links to the headings of `html`, giving the headings
+/// without an ID a unique one. Returns `None` if no heading changed.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::anchors::{add_heading_anchors, AnchorOptions};
+///
+/// let html = add_heading_anchors(
+///     "<h2>Getting started</h2>",
+///     &AnchorOptions::default(),
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     html,
+///     "<h2 id=\"getting-started\">Getting started \
+///      <a class=\"anchor\" href=\"#getting-started\" \
+///      aria-label=\"Link to this section: Getting started\">#</a></h2>"
+/// );
+/// ```
+pub fn add_heading_anchors(
+    html: &str,
+    options: &AnchorOptions,
+) -> Option<String> {
+    let (assigned, headings) = assign_heading_ids(html);
+    let anchor_class = format!("class=\"{}\"", options.class);
+    // The headings are listed in the order `HEADING_RE` matches them.
+    let mut headings = headings.into_iter();
+    let anchored =
+        HEADING_RE.replace_all(&assigned, |caps: &Captures<'_>| {
+            if caps[1] != caps[4] {
+                return caps[0].to_string();
+            }
+            let heading = match headings.next() {
+                Some(heading) => heading,
+                None => return caps[0].to_string(),
+            };
+            if heading.level < options.min_level
+                || heading.level > options.max_level
+                || caps[3].contains(&anchor_class)
+            {
+                return caps[0].to_string();
+            }
+            let anchor =
+                anchor_link(&heading.id, &heading.text, options);
+            let content = match options.position {
+                AnchorPosition::Before => {
+                    format!("{} {}", anchor, &caps[3])
+                }
+                AnchorPosition::After => {
+                    format!("{} {}", &caps[3], anchor)
+                }
+            };
+            format!(
+                "<h{level}{}>{}</h{level}>",
+                caps.get(2).map_or("", |m| m.as_str()),
+                content,
+                level = heading.level
+            )
+        });
+    if anchored == html {
+        None
+    } else {
+        Some(anchored.into_owned())
+    }
+}
+
+/// Applies [`add_heading_anchors`] to every HTML file below
+/// `site_dir`.
+///
+/// Returns the number of files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written.
+pub fn add_heading_anchors_site(
+    site_dir: &Path,
+    options: &AnchorOptions,
+) -> Result<usize> {
+    if !options.enabled || !site_dir.exists() {
+        return Ok(0);
+    }
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+
+    let mut rewritten = 0;
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let anchored = match add_heading_anchors(&html, options) {
+            Some(anchored) => anchored,
+            None => continue,
+        };
+        fs::write(&path, anchored).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+/// Returns the anchor link to the heading `id` reading `text`, both
+/// already escaped as they are taken from the page.
+fn anchor_link(
+    id: &str,
+    text: &str,
+    options: &AnchorOptions,
+) -> String {
+    format!(
+        "<a class=\"{}\" href=\"#{}\" \
+         aria-label=\"Link to this section: {}\">{}</a>",
+        options.class,
+        id,
+        text.replace('"', "&quot;"),
+        escape_html(&options.symbol)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchors_use_unique_ids() {
+        let html = "<h1>Title</h1>\
+                    <h2 id=\"setup\" class=\"x\">Setup</h2>\
+                    <h2>Usage</h2><p id=\"usage\"></p>\
+                    <h3>Usage</h3>";
+        let options = AnchorOptions {
+            symbol: "¶".into(),
+            position: AnchorPosition::Before,
+            ..AnchorOptions::default()
+        };
+        let anchored = add_heading_anchors(html, &options).unwrap();
+        assert!(anchored.starts_with("<h1 id=\"title\">Title</h1>"));
+        assert!(anchored.contains(
+            "<h2 id=\"setup\" class=\"x\"><a class=\"anchor\" href=\"#setup\""
+        ));
+        assert!(anchored.contains("<h2 id=\"usage-1\">"));
+        assert!(anchored.contains("<h3 id=\"usage-2\">"));
+        assert!(anchored.contains(">¶</a> Usage</h3>"));
+        assert_eq!(add_heading_anchors(&anchored, &options), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut options = AnchorOptions::default();
+        assert!(options.validate().is_ok());
+        options.min_level = 4;
+        options.max_level = 3;
+        assert!(options.validate().is_err());
+        options.max_level = 7;
+        assert!(options.validate().is_err());
+        options.max_level = 6;
+        options.class = "anchor link".into();
+        assert!(options.validate().is_err());
+    }
+}
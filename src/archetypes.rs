@@ -0,0 +1,228 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Content Archetypes
+//!
+//! Backs `ssg new post "My Title"`, which creates a Markdown file with its
+//! frontmatter already filled in. The file is rendered from an archetype,
+//! a template in `archetypes/`:
+//!
+//! ```text
+//! archetypes/
+//! ├── default.md        # used when no kind-specific archetype exists
+//! └── post.md           # used by `ssg new post`
+//! ```
+//!
+//! Archetypes are rendered with the [template engine](crate::template)
+//! and these variables:
+//!
+//! | Variable | Value |
+//! |----------|-------|
+//! | `title`  | The title passed on the command line |
+//! | `slug`   | The slugified title, also used as the file name |
+//! | `date`   | The current date and time in RFC 3339 format |
+//! | `draft`  | `true` |
+//! | `kind`   | The content kind, e.g. `post` |
+//!
+//! The `quote` filter turns a value into a double-quoted YAML string, so
+//! `title: {{ title | quote }}` is safe for titles containing quotes.
+//!
+//! Without an archetype, [`DEFAULT_ARCHETYPE`] is used.
+
+use anyhow::{anyhow, ensure, Context, Result};
+use dtt::datetime::DateTime;
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::template::{slugify, Engine};
+
+/// Default directory containing archetypes, relative to the project root.
+pub const ARCHETYPES_DIR: &str = "archetypes";
+
+/// Archetype used when the project defines none.
+pub const DEFAULT_ARCHETYPE: &str = "\
+---
+title: {{ title | quote }}
+date: {{ date | quote }}
+draft: {{ draft }}
+description: \"\"
+tags: []
+---
+
+# {{ title }}
+";
+
+/// Returns the archetype for `kind`: `<kind>.md`, then `default.md` in
+/// `archetypes_dir`, then [`DEFAULT_ARCHETYPE`].
+///
+/// # Errors
+///
+/// Returns an error if an archetype file exists but cannot be read.
+pub fn load_archetype(
+    archetypes_dir: &Path,
+    kind: &str,
+) -> Result<String> {
+    for name in [format!("{}.md", kind), "default.md".to_string()] {
+        let path = archetypes_dir.join(name);
+        if path.is_file() {
+            return fs::read_to_string(&path).with_context(|| {
+                format!("Failed to read archetype {}", path.display())
+            });
+        }
+    }
+    Ok(DEFAULT_ARCHETYPE.to_string())
+}
+
+/// Creates a new `kind` content file titled `title` in `dir`, named after
+/// the slugified title.
+///
+/// Returns the path of the new file.
+///
+/// # Errors
+///
+/// Returns an error if the title has no usable characters, the file
+/// already exists, or the archetype cannot be read or rendered.
+///
+/// # Examples
+///
+/// ```rust
+/// use ssg::archetypes::create_content;
+///
+/// fn main() -> anyhow::Result<()> {
+///     let project = tempfile::tempdir()?;
+///     let path = create_content(
+///         &project.path().join("content"),
+///         &project.path().join("archetypes"),
+///         "post",
+///         "My First Post",
+///     )?;
+///     assert!(path.ends_with("my-first-post.md"));
+///     let source = std::fs::read_to_string(path)?;
+///     assert!(source.contains("title: \"My First Post\""));
+///     assert!(source.contains("draft: true"));
+///     Ok(())
+/// }
+/// ```
+pub fn create_content(
+    dir: &Path,
+    archetypes_dir: &Path,
+    kind: &str,
+    title: &str,
+) -> Result<PathBuf> {
+    let slug = slugify(title);
+    ensure!(!slug.is_empty(), "`{}` cannot be used as a title", title);
+
+    let path = dir.join(format!("{}.md", slug));
+    ensure!(!path.exists(), "{} already exists", path.display());
+
+    let date = DateTime::new()
+        .format_rfc3339()
+        .map_err(|e| anyhow!("Failed to format the date: {}", e))?;
+    let context = json!({
+        "title": title,
+        "slug": slug,
+        "date": date,
+        "draft": true,
+        "kind": kind,
+    });
+    let archetype = load_archetype(archetypes_dir, kind)?;
+    let file = archetypes_dir.join(format!("{}.md", kind));
+    let mut engine = Engine::new();
+    // JSON strings are valid double-quoted YAML scalars.
+    engine.register_filter("quote", |value, _| {
+        Ok(Value::String(value.to_string()))
+    });
+    let contents = engine.render_file(&file, &archetype, &context)?;
+
+    fs::create_dir_all(dir).with_context(|| {
+        format!("Failed to create {}", dir.display())
+    })?;
+    fs::write(&path, contents).with_context(|| {
+        format!("Failed to write {}", path.display())
+    })?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontmatter;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_default_archetype_has_valid_frontmatter() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let path = create_content(
+            temp_dir.path(),
+            &temp_dir.path().join(ARCHETYPES_DIR),
+            "post",
+            "Say \"Hello\", World!",
+        )?;
+        assert_eq!(path, temp_dir.path().join("say-hello-world.md"));
+
+        let source = fs::read_to_string(&path)?;
+        let (frontmatter, body) = frontmatter::parse(&source)?;
+        assert_eq!(
+            frontmatter.get_str("title").as_deref(),
+            Some("Say \"Hello\", World!")
+        );
+        assert_eq!(frontmatter.get_bool("draft"), Some(true));
+        let date = frontmatter.get_str("date").unwrap_or_default();
+        assert!(DateTime::parse(&date).is_ok(), "{}", date);
+        assert!(body.contains("# Say"));
+
+        let error = create_content(
+            temp_dir.path(),
+            temp_dir.path(),
+            "post",
+            "Say Hello World",
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("already exists"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_kind_archetype_overrides_default() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let archetypes = temp_dir.path().join(ARCHETYPES_DIR);
+        fs::create_dir_all(&archetypes)?;
+        fs::write(archetypes.join("default.md"), "default {{ slug }}")?;
+        fs::write(
+            archetypes.join("post.md"),
+            "{{ kind }}: {{ title }}",
+        )?;
+
+        assert_eq!(
+            load_archetype(&archetypes, "post")?,
+            "{{ kind }}: {{ title }}"
+        );
+        assert_eq!(
+            load_archetype(&archetypes, "page")?,
+            "default {{ slug }}"
+        );
+        assert_eq!(
+            load_archetype(&temp_dir.path().join("none"), "post")?,
+            DEFAULT_ARCHETYPE
+        );
+
+        let path = create_content(
+            &temp_dir.path().join("content/posts"),
+            &archetypes,
+            "post",
+            "Launch",
+        )?;
+        assert_eq!(fs::read_to_string(path)?, "post: Launch");
+        assert!(create_content(
+            temp_dir.path(),
+            &archetypes,
+            "post",
+            "!!"
+        )
+        .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,416 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Auxiliary Output Phase
+//!
+//! Site-wide outputs such as `related.json` and `search-index.json` only
+//! read the shared content model and write their own files, so they do
+//! not depend on each other. The [`AuxiliaryPhase`] runs every registered
+//! [`AuxiliaryGenerator`] concurrently, waits for all of them and returns
+//! an [`AuxiliaryReport`] with the files, timing and error of each.
+//!
+//! A failing generator does not stop the others; all failures are
+//! reported together by [`AuxiliaryReport::into_result`].
+//!
+//! Outputs that hash the finished site, like `etags.json`, must run after
+//! this phase.
+//!
+//! ```rust
+//! use ssg::{auxiliary::AuxiliaryPhase, content::ContentSet};
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let site = tempfile::tempdir()?;
+//!     let report = AuxiliaryPhase::with_defaults("en-GB")
+//!         .run(&ContentSet::default(), site.path())
+//!         .into_result()?;
+//!     assert_eq!(report.outputs().len(), 2);
+//!     Ok(())
+//! }
+//! ```
+
+use anyhow::{anyhow, Result};
+use log::debug;
+use rayon::prelude::*;
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    cache_crypto::CacheCipher,
+    content::ContentSet,
+    diagnostics::WarningLog,
+    related::{RelatedIndex, DEFAULT_RELATED_LIMIT},
+    search::{IncrementalIndex, SearchIndex},
+};
+
+/// Produces site-wide files from the content model.
+///
+/// Generators run in parallel, so implementations must be `Send + Sync`
+/// and write only their own files.
+pub trait AuxiliaryGenerator: Debug + Send + Sync {
+    /// A short, unique name used in reports.
+    fn name(&self) -> &str;
+
+    /// Writes the generator's files to `site_dir`, returning their
+    /// paths.
+    ///
+    /// # Errors
+    ///
+    /// Any error is recorded in the report for this generator.
+    fn generate(
+        &self,
+        content: &ContentSet,
+        site_dir: &Path,
+    ) -> Result<Vec<PathBuf>>;
+}
+
+/// Writes `related.json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelatedPagesGenerator {
+    /// Number of related pages kept per page.
+    pub limit: usize,
+}
+
+impl Default for RelatedPagesGenerator {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_RELATED_LIMIT,
+        }
+    }
+}
+
+impl AuxiliaryGenerator for RelatedPagesGenerator {
+    fn name(&self) -> &str {
+        "related"
+    }
+
+    fn generate(
+        &self,
+        content: &ContentSet,
+        site_dir: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        let path =
+            RelatedIndex::build(content, self.limit).write(site_dir)?;
+        Ok(vec![path])
+    }
+}
+
+/// Writes `search-index.json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchIndexGenerator {
+    /// Language of pages that do not declare one.
+    pub default_language: String,
+    /// Cache of the previous build's index, updated instead of rebuilt
+    /// when set. See [`IncrementalIndex`].
+    pub cache: Option<PathBuf>,
+    /// Cipher sealing the cache, if it is encrypted.
+    pub cipher: Option<CacheCipher>,
+}
+
+impl AuxiliaryGenerator for SearchIndexGenerator {
+    fn name(&self) -> &str {
+        "search"
+    }
+
+    fn generate(
+        &self,
+        content: &ContentSet,
+        site_dir: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        let index = match &self.cache {
+            None => SearchIndex::build(content, &self.default_language),
+            Some(cache) => {
+                let cipher = self.cipher.as_ref();
+                let updated =
+                    match IncrementalIndex::load(cache, cipher)? {
+                        Some(previous) => {
+                            let (updated, analyzed) = previous.update(
+                                content,
+                                &self.default_language,
+                            );
+                            debug!(
+                                "Search index: {} of {} pages analyzed",
+                                analyzed,
+                                updated.hashes.len()
+                            );
+                            updated
+                        }
+                        None => IncrementalIndex::build(
+                            content,
+                            &self.default_language,
+                        ),
+                    };
+                updated.save(cache, cipher)?;
+                updated.index
+            }
+        };
+        Ok(vec![index.write(site_dir)?])
+    }
+}
+
+/// Outcome of one generator.
+#[derive(Debug)]
+pub struct GeneratorReport {
+    /// Generator name.
+    pub name: String,
+    /// Files written, empty on failure.
+    pub outputs: Vec<PathBuf>,
+    /// Time the generator took.
+    pub duration: Duration,
+    /// The error, if the generator failed.
+    pub error: Option<anyhow::Error>,
+}
+
+/// Outcome of an auxiliary phase, in registration order.
+#[derive(Debug, Default)]
+pub struct AuxiliaryReport {
+    /// One report per generator.
+    pub generators: Vec<GeneratorReport>,
+}
+
+impl AuxiliaryReport {
+    /// Returns `true` if every generator succeeded.
+    pub fn is_ok(&self) -> bool {
+        self.generators.iter().all(|report| report.error.is_none())
+    }
+
+    /// Returns the failed generators.
+    pub fn failures(&self) -> impl Iterator<Item = &GeneratorReport> {
+        self.generators
+            .iter()
+            .filter(|report| report.error.is_some())
+    }
+
+    /// Returns every file written, in generator order.
+    pub fn outputs(&self) -> Vec<&Path> {
+        self.generators
+            .iter()
+            .flat_map(|report| {
+                report.outputs.iter().map(PathBuf::as_path)
+            })
+            .collect()
+    }
+
+    /// Returns the report, or an error listing every failed generator.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any generator failed.
+    pub fn into_result(self) -> Result<Self> {
+        if self.is_ok() {
+            return Ok(self);
+        }
+        let messages: Vec<String> = self
+            .failures()
+            .filter_map(|report| {
+                report.error.as_ref().map(|error| {
+                    format!("{}: {:#}", report.name, error)
+                })
+            })
+            .collect();
+        Err(anyhow!(
+            "{} auxiliary generator(s) failed:\n  {}",
+            messages.len(),
+            messages.join("\n  ")
+        ))
+    }
+}
+
+/// The auxiliary phase and its registered generators.
+#[derive(Debug, Default)]
+pub struct AuxiliaryPhase {
+    generators: Vec<Box<dyn AuxiliaryGenerator>>,
+}
+
+impl AuxiliaryPhase {
+    /// Creates a phase without generators.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a phase with the built-in related pages and search index
+    /// generators.
+    pub fn with_defaults(default_language: &str) -> Self {
+        Self::new()
+            .with_generator(RelatedPagesGenerator::default())
+            .with_generator(SearchIndexGenerator {
+                default_language: default_language.to_string(),
+                cache: None,
+                cipher: None,
+            })
+    }
+
+    /// Like [`with_defaults`](Self::with_defaults), updating the search
+    /// index cached in `search_cache` instead of rebuilding it. The
+    /// cache is sealed with `cipher` if there is one.
+    pub fn incremental(
+        default_language: &str,
+        search_cache: &Path,
+        cipher: Option<CacheCipher>,
+    ) -> Self {
+        Self::new()
+            .with_generator(RelatedPagesGenerator::default())
+            .with_generator(SearchIndexGenerator {
+                default_language: default_language.to_string(),
+                cache: Some(search_cache.to_path_buf()),
+                cipher,
+            })
+    }
+
+    /// Registers a generator, returning the phase for chaining.
+    pub fn with_generator<G: AuxiliaryGenerator + 'static>(
+        mut self,
+        generator: G,
+    ) -> Self {
+        self.register_generator(Box::new(generator));
+        self
+    }
+
+    /// Registers a boxed generator.
+    pub fn register_generator(
+        &mut self,
+        generator: Box<dyn AuxiliaryGenerator>,
+    ) {
+        self.generators.push(generator);
+    }
+
+    /// Returns the names of the registered generators.
+    pub fn generator_names(&self) -> Vec<&str> {
+        self.generators.iter().map(|g| g.name()).collect()
+    }
+
+    /// Runs every generator concurrently and waits for all of them.
+    ///
+    /// Warnings are logged afterwards, ordered by generator name.
+    pub fn run(
+        &self,
+        content: &ContentSet,
+        site_dir: &Path,
+    ) -> AuxiliaryReport {
+        let warnings = WarningLog::new();
+        let generators = self
+            .generators
+            .par_iter()
+            .map(|generator| {
+                let start = Instant::now();
+                let result = warnings
+                    .scope(Path::new(generator.name()), || {
+                        generator.generate(content, site_dir)
+                    });
+                let duration = start.elapsed();
+                let (outputs, error) = match result {
+                    Ok(outputs) => (outputs, None),
+                    Err(error) => (Vec::new(), Some(error)),
+                };
+                GeneratorReport {
+                    name: generator.name().to_string(),
+                    outputs,
+                    duration,
+                    error,
+                }
+            })
+            .collect();
+        let _ = warnings.flush();
+        AuxiliaryReport { generators }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, thread};
+    use tempfile::tempdir;
+
+    #[derive(Debug)]
+    struct Robots;
+
+    impl AuxiliaryGenerator for Robots {
+        fn name(&self) -> &str {
+            "robots"
+        }
+
+        fn generate(
+            &self,
+            _content: &ContentSet,
+            site_dir: &Path,
+        ) -> Result<Vec<PathBuf>> {
+            thread::sleep(Duration::from_millis(20));
+            let path = site_dir.join("robots.txt");
+            fs::write(&path, "User-agent: *\n")?;
+            Ok(vec![path])
+        }
+    }
+
+    #[derive(Debug)]
+    struct Broken;
+
+    impl AuxiliaryGenerator for Broken {
+        fn name(&self) -> &str {
+            "broken"
+        }
+
+        fn generate(
+            &self,
+            _content: &ContentSet,
+            _site_dir: &Path,
+        ) -> Result<Vec<PathBuf>> {
+            Err(anyhow!("feed template missing"))
+        }
+    }
+
+    #[test]
+    fn test_runs_all_generators_in_order() -> Result<()> {
+        let site = tempdir()?;
+        let phase =
+            AuxiliaryPhase::with_defaults("en").with_generator(Robots);
+        assert_eq!(
+            phase.generator_names(),
+            vec!["related", "search", "robots"]
+        );
+
+        let report = phase
+            .run(&ContentSet::default(), site.path())
+            .into_result()?;
+        assert_eq!(report.generators[2].name, "robots");
+        assert!(
+            report.generators[2].duration >= Duration::from_millis(20)
+        );
+        assert_eq!(report.outputs().len(), 3);
+        assert!(site.path().join("search-index.json").is_file());
+        assert!(site.path().join("robots.txt").is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental_phase_keeps_search_cache() -> Result<()> {
+        let site = tempdir()?;
+        let cache = site.path().join(".ssg-cache/search.json");
+        for _ in 0..2 {
+            let _ = AuxiliaryPhase::incremental("en", &cache, None)
+                .run(&ContentSet::default(), site.path())
+                .into_result()?;
+        }
+        assert!(cache.is_file());
+        assert!(site.path().join("search-index.json").is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn test_failures_do_not_stop_other_generators() -> Result<()> {
+        let site = tempdir()?;
+        let report = AuxiliaryPhase::new()
+            .with_generator(Broken)
+            .with_generator(Robots)
+            .run(&ContentSet::default(), site.path());
+
+        assert!(!report.is_ok());
+        assert_eq!(report.failures().count(), 1);
+        assert!(site.path().join("robots.txt").is_file());
+
+        let error = report.into_result().unwrap_err().to_string();
+        assert!(error.contains("broken: feed template missing"));
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Breadcrumbs
+//!
+//! Derives the breadcrumb trail of a page from its URL: the home page,
+//! then one crumb per directory, then the page itself. A directory is
+//! named after the title of its section page (`docs/index.md` for
+//! `/docs/`) and otherwise after its URL segment, so
+//! `/docs/getting-started/install/` without section pages reads
+//! `Home › Docs › Getting started › Install`.
+//!
+//! A `breadcrumb_title` frontmatter key gives a page a shorter name in
+//! trails than its title; on the home page it replaces `Home`.
+//!
+//! Templates rendered by the compiler get two functions:
+//!
+//! | Function | Value |
+//! |----------|-------|
+//! | `breadcrumbs(url)` | The trail of the page at `url`, as a list of `name` and `url` pairs |
+//! | `breadcrumbs_html(url)` | The trail as a `<nav>` of links, the page itself unlinked |
+//!
+//! The home page has no trail. With
+//! [structured data](crate::structured_data) enabled, the trail is also
+//! written as a `BreadcrumbList`.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt::Write as _};
+
+use crate::{
+    content::{ContentSet, Page},
+    template::{escape_html, Engine},
+};
+
+/// Frontmatter key naming a page in breadcrumb trails.
+pub const BREADCRUMB_TITLE_KEY: &str = "breadcrumb_title";
+
+/// Name of the home page crumb without a `breadcrumb_title`.
+pub const HOME_TITLE: &str = "Home";
+
+/// Name of the template function returning a trail.
+pub const BREADCRUMBS_FUNCTION: &str = "breadcrumbs";
+
+/// Name of the template function rendering a trail as HTML.
+pub const BREADCRUMBS_HTML_FUNCTION: &str = "breadcrumbs_html";
+
+/// A step of a breadcrumb trail.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Crumb {
+    /// Link text.
+    pub name: String,
+    /// Site-relative URL.
+    pub url: String,
+}
+
+/// The names of the pages of a site by URL, for building trails.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Breadcrumbs {
+    titles: BTreeMap<String, String>,
+}
+
+impl Breadcrumbs {
+    /// Collects the trail names of the pages of `content`.
+    pub fn new(content: &ContentSet) -> Self {
+        let titles = content
+            .iter()
+            .filter_map(|page| {
+                let url = page.url_path();
+                let name =
+                    page.frontmatter.get_str(BREADCRUMB_TITLE_KEY);
+                if url == "/" {
+                    name.map(|name| (url, name))
+                } else {
+                    Some((url, name.unwrap_or_else(|| page.title())))
+                }
+            })
+            .collect();
+        Self { titles }
+    }
+
+    /// Returns the trail of the page at the site-relative `url`, from
+    /// the home page down to the page. The home page has none.
+    pub fn trail(&self, url: &str) -> Vec<Crumb> {
+        let segments: Vec<&str> =
+            url.split('/').filter(|s| !s.is_empty()).collect();
+        if segments.is_empty() {
+            return Vec::new();
+        }
+
+        let mut trail = vec![self.crumb("/", HOME_TITLE.to_string())];
+        let mut prefix = String::from("/");
+        for segment in &segments[..segments.len() - 1] {
+            prefix.push_str(segment);
+            prefix.push('/');
+            trail.push(self.crumb(&prefix, segment_title(segment)));
+        }
+        let last = segments[segments.len() - 1];
+        trail.push(self.crumb(url, segment_title(last)));
+        trail
+    }
+
+    /// Returns the trail of `page`.
+    pub fn page_trail(&self, page: &Page) -> Vec<Crumb> {
+        self.trail(&page.url_path())
+    }
+
+    /// Renders the trail of the page at `url` as a `<nav>` with an
+    /// ordered list, or nothing for the home page.
+    pub fn render_html(&self, url: &str) -> String {
+        let trail = self.trail(url);
+        let (current, ancestors) = match trail.split_last() {
+            Some(split) => split,
+            None => return String::new(),
+        };
+        let mut html = String::from(
+            "<nav class=\"breadcrumbs\" aria-label=\"Breadcrumb\"><ol>",
+        );
+        for crumb in ancestors {
+            let _ = write!(
+                html,
+                "<li><a href=\"{}\">{}</a></li>",
+                escape_html(&crumb.url),
+                escape_html(&crumb.name)
+            );
+        }
+        let _ = write!(
+            html,
+            "<li aria-current=\"page\">{}</li></ol></nav>",
+            escape_html(&current.name)
+        );
+        html
+    }
+
+    /// Makes the `breadcrumbs` and `breadcrumbs_html` functions
+    /// available to templates rendered by `engine`.
+    pub fn register(&self, engine: &mut Engine) {
+        let breadcrumbs = self.clone();
+        engine.register_function(BREADCRUMBS_FUNCTION, move |args| {
+            let url = url_arg(BREADCRUMBS_FUNCTION, args)?;
+            Ok(serde_json::to_value(breadcrumbs.trail(url))?)
+        });
+        let breadcrumbs = self.clone();
+        engine.register_function(
+            BREADCRUMBS_HTML_FUNCTION,
+            move |args| {
+                let url = url_arg(BREADCRUMBS_HTML_FUNCTION, args)?;
+                Ok(Value::String(breadcrumbs.render_html(url)))
+            },
+        );
+    }
+
+    fn crumb(&self, url: &str, fallback: String) -> Crumb {
+        Crumb {
+            name: self.titles.get(url).cloned().unwrap_or(fallback),
+            url: url.to_string(),
+        }
+    }
+}
+
+/// Turns a URL segment such as `getting-started` into `Getting started`.
+pub(crate) fn segment_title(segment: &str) -> String {
+    let words = segment.replace(&['-', '_'][..], " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Returns the page URL argument of `function`.
+fn url_arg<'a>(function: &str, args: &'a [Value]) -> Result<&'a str> {
+    args.first()
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("`{}` expects a page URL", function))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn page(relative: &str, raw: &str) -> Page {
+        Page::from_source(
+            PathBuf::from("content").join(relative),
+            PathBuf::from(relative),
+            raw,
+        )
+        .unwrap()
+    }
+
+    fn docs() -> Breadcrumbs {
+        Breadcrumbs::new(&ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("index.md", "---\ntitle: Welcome\n---\n"),
+                page(
+                    "docs/index.md",
+                    "---\ntitle: Documentation\nbreadcrumb_title: Docs\n---\n",
+                ),
+                page(
+                    "docs/getting-started/install.md",
+                    "---\ntitle: Installing Shokunin\n---\n",
+                ),
+            ],
+        })
+    }
+
+    #[test]
+    fn test_trail_uses_section_titles() {
+        let breadcrumbs = docs();
+        let names: Vec<String> = breadcrumbs
+            .trail("/docs/getting-started/install/")
+            .into_iter()
+            .map(|crumb| crumb.name)
+            .collect();
+        assert_eq!(
+            names,
+            ["Home", "Docs", "Getting started", "Installing Shokunin"]
+        );
+        assert_eq!(
+            breadcrumbs.trail("/docs/getting-started/install/")[2].url,
+            "/docs/getting-started/"
+        );
+        assert!(breadcrumbs.trail("/").is_empty());
+    }
+
+    #[test]
+    fn test_register_renders_trail() -> Result<()> {
+        let mut engine = Engine::new();
+        docs().register(&mut engine);
+        let context = json!({ "url": "/docs/<b>/" });
+        assert_eq!(
+            engine.render("{{ breadcrumbs_html(url) }}", &context)?,
+            "<nav class=\"breadcrumbs\" aria-label=\"Breadcrumb\"><ol>\
+             <li><a href=\"/\">Home</a></li>\
+             <li><a href=\"/docs/\">Docs</a></li>\
+             <li aria-current=\"page\">&lt;b&gt;</li></ol></nav>"
+        );
+        assert_eq!(
+            engine.render("{{ breadcrumbs_html(\"/\") }}", &context)?,
+            ""
+        );
+        Ok(())
+    }
+}
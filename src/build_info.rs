@@ -0,0 +1,318 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Build Variables
+//!
+//! Exposes facts about the running build to templates, so a footer can
+//! carry a build stamp or a staging site a banner without a wrapper
+//! script. Templates rendered by the compiler get two globals:
+//!
+//! | Global | Value |
+//! |--------|-------|
+//! | `build.id` | Identifier of the build: `SSG_BUILD_ID` if set, otherwise a random UUID |
+//! | `build.time` | Start of the build in RFC 3339, UTC |
+//! | `build.timestamp` | Start of the build in seconds since the Unix epoch |
+//! | `build.version` | Version of the generator |
+//! | `env.NAME` | Value of each allowlisted environment variable that is set |
+//!
+//! ```text
+//! <footer>Build {{ build.id }}, {{ build.time | date("[year]-[month]-[day]") }}</footer>
+//! <body data-env="{{ env.DEPLOY_ENV | default("local") }}">
+//! ```
+//!
+//! A variable that is not set has no value, so templates reading it
+//! need `default`.
+//!
+//! Only the variables listed in the `[build_info]` table of the
+//! configuration file are read, so secrets in the environment of a CI
+//! job never reach the output. Names that look like credentials are
+//! rejected outright:
+//!
+//! ```toml
+//! [build_info]
+//! env = ["DEPLOY_ENV", "GIT_COMMIT"]   # the default is ["DEPLOY_ENV"]
+//! public = ["ALGOLIA_SEARCH_KEY"]
+//! ```
+//!
+//! Some keys are meant to be public, such as the search-only API key
+//! of a client-side widget. Names listed in `public` are exposed like
+//! those of `env`, even when they look like credentials; list only
+//! keys the browser is meant to see.
+//!
+//! When `SOURCE_DATE_EPOCH` is set, `build.time` is taken from it, so
+//! reproducible builds stay reproducible. The same values are passed
+//! to [WebAssembly plugins](crate::plugins) in the page JSON.
+
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    env,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{package::SOURCE_DATE_EPOCH, template::Engine};
+
+/// Environment variable overriding the build identifier.
+pub const BUILD_ID_ENV: &str = "SSG_BUILD_ID";
+
+/// Name of the template global holding the build facts.
+pub const BUILD_VARIABLE: &str = "build";
+
+/// Name of the template global holding the allowlisted variables.
+pub const ENV_VARIABLE: &str = "env";
+
+/// Variable exposed when the configuration lists none.
+pub const DEFAULT_ENV: &[&str] = &["DEPLOY_ENV"];
+
+/// Parts of variable names that are never exposed.
+const SECRET_WORDS: &[&str] =
+    &["KEY", "SECRET", "TOKEN", "PASSWORD", "CREDENTIAL"];
+
+/// Options of the `[build_info]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildInfoOptions {
+    /// Environment variables exposed to templates as `env`.
+    pub env: Vec<String>,
+    /// Environment variables exposed as `env` although their name looks
+    /// like a credential, such as public API keys.
+    pub public: Vec<String>,
+}
+
+impl Default for BuildInfoOptions {
+    fn default() -> Self {
+        Self {
+            env: DEFAULT_ENV
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            public: Vec::new(),
+        }
+    }
+}
+
+impl BuildInfoOptions {
+    /// Checks that every listed name is an environment variable name,
+    /// and that those of `env` do not look like secrets.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first invalid variable.
+    pub fn validate(&self) -> Result<()> {
+        for (key, name) in self
+            .env
+            .iter()
+            .map(|name| ("env", name))
+            .chain(self.public.iter().map(|name| ("public", name)))
+        {
+            ensure!(
+                !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                "{} `{}` must be an environment variable name",
+                key,
+                name
+            );
+        }
+        for name in &self.env {
+            let upper = name.to_ascii_uppercase();
+            ensure!(
+                !SECRET_WORDS.iter().any(|word| upper.contains(word)),
+                "env `{}` looks like a secret and cannot be exposed",
+                name
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The facts of one build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildInfo {
+    /// Identifier of the build.
+    pub id: String,
+    /// Start of the build in RFC 3339, UTC.
+    pub time: String,
+    /// Start of the build in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Version of the generator.
+    pub version: String,
+    /// Values of the allowlisted variables that are set.
+    #[serde(skip)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl BuildInfo {
+    /// Captures the facts of the build starting now.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `SOURCE_DATE_EPOCH` is set but is not a
+    /// number of seconds.
+    pub fn capture(options: &BuildInfoOptions) -> Result<Self> {
+        let timestamp = match env::var(SOURCE_DATE_EPOCH) {
+            Ok(value) => value.trim().parse().with_context(|| {
+                format!(
+                    "{} must be a number of seconds",
+                    SOURCE_DATE_EPOCH
+                )
+            })?,
+            Err(_) => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+        };
+        let id = match env::var(BUILD_ID_ENV) {
+            Ok(id) if !id.trim().is_empty() => id.trim().to_string(),
+            _ => uuid::Uuid::new_v4().to_string(),
+        };
+        let env = options
+            .env
+            .iter()
+            .chain(&options.public)
+            .filter_map(|name| {
+                env::var(name).ok().map(|value| (name.clone(), value))
+            })
+            .collect();
+        Ok(Self {
+            id,
+            time: format_rfc3339(timestamp),
+            timestamp,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            env,
+        })
+    }
+
+    /// Returns the value of the `build` global.
+    pub fn build_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// Returns the value of the `env` global.
+    pub fn env_json(&self) -> Value {
+        serde_json::to_value(&self.env).unwrap_or_default()
+    }
+
+    /// Makes `build` and `env` available to templates rendered by
+    /// `engine`.
+    pub fn register(&self, engine: &mut Engine) {
+        engine.set_global(BUILD_VARIABLE, self.build_json());
+        engine.set_global(ENV_VARIABLE, self.env_json());
+    }
+}
+
+/// Formats `timestamp`, in seconds since the Unix epoch, as an RFC 3339
+/// date and time in UTC.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::build_info::format_rfc3339;
+///
+/// assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+/// assert_eq!(format_rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+/// ```
+pub fn format_rfc3339(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86_400, timestamp % 86_400);
+    // Civil date of a day count, after Howard Hinnant's
+    // `civil_from_days`, with eras of 400 years starting in March.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_rfc3339() {
+        assert_eq!(format_rfc3339(315_532_800), "1980-01-01T00:00:00Z");
+        assert_eq!(
+            format_rfc3339(1_735_689_599),
+            "2024-12-31T23:59:59Z"
+        );
+        assert_eq!(
+            format_rfc3339(4_107_542_400),
+            "2100-03-01T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn test_capture_exposes_allowlisted_variables() -> Result<()> {
+        env::set_var("SSG_TEST_BUILD_INFO_STAGE", "staging");
+        env::set_var("SSG_TEST_BUILD_INFO_OTHER", "hidden");
+        env::set_var("SSG_TEST_BUILD_INFO_SEARCH_KEY", "public");
+        let options = BuildInfoOptions {
+            env: vec![
+                "SSG_TEST_BUILD_INFO_STAGE".into(),
+                "SSG_TEST_BUILD_INFO_UNSET".into(),
+            ],
+            public: vec!["SSG_TEST_BUILD_INFO_SEARCH_KEY".into()],
+        };
+        options.validate()?;
+        let info = BuildInfo::capture(&options)?;
+        assert_eq!(info.time, format_rfc3339(info.timestamp));
+        assert!(!info.id.is_empty());
+        assert_eq!(
+            info.env_json(),
+            serde_json::json!({
+                "SSG_TEST_BUILD_INFO_SEARCH_KEY": "public",
+                "SSG_TEST_BUILD_INFO_STAGE": "staging",
+            })
+        );
+        assert!(info.build_json().get("env").is_none());
+        assert_eq!(
+            info.build_json()["version"],
+            env!("CARGO_PKG_VERSION")
+        );
+
+        let mut engine = Engine::new();
+        info.register(&mut engine);
+        assert_eq!(
+            engine.render(
+                "{{ env.SSG_TEST_BUILD_INFO_STAGE }}",
+                &serde_json::json!({})
+            )?,
+            "staging"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_rejects_secrets() {
+        assert!(BuildInfoOptions::default().validate().is_ok());
+        for name in
+            ["AWS_SECRET_ACCESS_KEY", "github_token", "DEPLOY ENV"]
+        {
+            let options = BuildInfoOptions {
+                env: vec![name.to_string()],
+                public: Vec::new(),
+            };
+            assert!(options.validate().is_err(), "{}", name);
+        }
+        let options = BuildInfoOptions {
+            env: Vec::new(),
+            public: vec!["ALGOLIA_SEARCH_KEY".into()],
+        };
+        assert!(options.validate().is_ok());
+    }
+}
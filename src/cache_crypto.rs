@@ -0,0 +1,325 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Cache Encryption
+//!
+//! Encrypts the build cache at rest, for private sites built on shared
+//! CI runners where `.ssg-cache/` outlives the job. The incremental
+//! search cache holds the text of every page, so it is the file that
+//! gets sealed. The `[cache]` table of the configuration file turns it
+//! on:
+//!
+//! ```toml
+//! [cache]
+//! encrypt = true
+//! key_env = "SSG_CACHE_KEY"   # the default
+//! ```
+//!
+//! The key is never written to the configuration: it is read from the
+//! environment variable named by `key_env` when the build starts, and
+//! the build fails if it is unset. Any secret string works: the
+//! AES-256-GCM key is derived from it with PBKDF2-HMAC-SHA256 and a
+//! random salt stored in each file, so a passphrase is slow to guess.
+//! A long random value such as the output of `openssl rand -hex 32` is
+//! still best.
+//!
+//! Each file is sealed with a fresh nonce. A cache written before
+//! encryption was turned on is still read and is sealed when it is next
+//! saved. A cache that cannot be opened, for instance after the key was
+//! rotated, is ignored with a warning and rebuilt.
+//!
+//! ```rust
+//! use ssg::cache_crypto::{self, CacheCipher};
+//!
+//! let cipher = CacheCipher::new("correct horse battery staple");
+//! let sealed = cache_crypto::encode(b"{}".to_vec(), Some(&cipher))?;
+//! assert!(cache_crypto::is_sealed(&sealed));
+//! assert_eq!(cache_crypto::decode(sealed, Some(&cipher))?, b"{}");
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{anyhow, bail, ensure, Result};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{env, fmt};
+
+use crate::protect::DEFAULT_ITERATIONS;
+
+/// Default environment variable holding the cache key.
+pub const DEFAULT_KEY_ENV: &str = "SSG_CACHE_KEY";
+
+/// Marker at the start of a sealed cache file.
+const MAGIC: &[u8] = b"SSGENC1\0";
+
+/// Length of the PBKDF2 salt in bytes.
+const SALT_LEN: usize = 16;
+
+/// Length of an AES-GCM nonce in bytes.
+const NONCE_LEN: usize = 12;
+
+/// Options of the `[cache]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheOptions {
+    /// Whether cache files are encrypted.
+    pub encrypt: bool,
+    /// Environment variable holding the key.
+    pub key_env: String,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            encrypt: false,
+            key_env: DEFAULT_KEY_ENV.to_string(),
+        }
+    }
+}
+
+impl CacheOptions {
+    /// Checks that `key_env` is a valid environment variable name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it is empty or has characters other than
+    /// ASCII letters, digits and underscores.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            !self.key_env.is_empty()
+                && self
+                    .key_env
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            "key_env `{}` must be an environment variable name",
+            self.key_env
+        );
+        Ok(())
+    }
+
+    /// Returns the cipher to seal the cache with, or `None` if
+    /// encryption is off.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if encryption is on and the key variable is
+    /// unset or empty.
+    pub fn cipher(&self) -> Result<Option<CacheCipher>> {
+        if !self.encrypt {
+            return Ok(None);
+        }
+        match env::var(&self.key_env) {
+            Ok(secret) if !secret.is_empty() => {
+                Ok(Some(CacheCipher::new(&secret)))
+            }
+            _ => bail!(
+                "Cache encryption is on but `{}` is not set; export the key or set `encrypt = false` in `[cache]`",
+                self.key_env
+            ),
+        }
+    }
+}
+
+/// Seals and opens cache files with keys derived from a secret.
+///
+/// Files are sealed under one salt, and the key for it, drawn when the
+/// cipher is created; files sealed under other salts are opened with a
+/// key derived for theirs.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CacheCipher {
+    secret: String,
+    iterations: u32,
+    salt: [u8; SALT_LEN],
+    key: [u8; 32],
+}
+
+impl CacheCipher {
+    /// Derives the cipher key from `secret` with
+    /// [`DEFAULT_ITERATIONS`] PBKDF2 iterations.
+    pub fn new(secret: &str) -> Self {
+        Self::with_iterations(secret, DEFAULT_ITERATIONS)
+    }
+
+    /// Derives the cipher key from `secret` with `iterations` PBKDF2
+    /// iterations, under a fresh salt.
+    pub fn with_iterations(secret: &str, iterations: u32) -> Self {
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            secret: secret.to_string(),
+            iterations,
+            salt,
+            key: derive_key(secret, &salt, iterations),
+        }
+    }
+
+    /// Encrypts `plaintext` under a fresh nonce.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if encryption fails.
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = aead(&self.key)
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("Failed to encrypt cache"))?;
+        let mut sealed = Vec::with_capacity(
+            MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len(),
+        );
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&self.salt);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Decrypts data written by [`seal`](Self::seal).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not sealed, was sealed with
+    /// another key or was tampered with.
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        ensure!(
+            is_sealed(sealed)
+                && sealed.len() >= MAGIC.len() + SALT_LEN + NONCE_LEN,
+            "not an encrypted cache file"
+        );
+        let (salt, rest) = sealed[MAGIC.len()..].split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let key = if salt == self.salt {
+            self.key
+        } else {
+            derive_key(&self.secret, salt, self.iterations)
+        };
+        aead(&key)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("wrong key or corrupted file"))
+    }
+}
+
+impl fmt::Debug for CacheCipher {
+    /// Keeps the key out of logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheCipher").finish_non_exhaustive()
+    }
+}
+
+/// Derives an AES key from `secret`.
+fn derive_key(secret: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(
+        secret.as_bytes(),
+        salt,
+        iterations,
+        &mut key,
+    );
+    key
+}
+
+/// Returns the AES-GCM cipher of `key`.
+fn aead(key: &[u8; 32]) -> Aes256Gcm {
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))
+}
+
+/// Returns `true` if `data` starts like a sealed cache file.
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Seals the bytes of a cache file with `cipher`, or returns them as
+/// they are without one.
+///
+/// # Errors
+///
+/// Returns an error if encryption fails.
+pub fn encode(
+    data: Vec<u8>,
+    cipher: Option<&CacheCipher>,
+) -> Result<Vec<u8>> {
+    match cipher {
+        Some(cipher) => cipher.seal(&data),
+        None => Ok(data),
+    }
+}
+
+/// Returns the plain bytes of a cache file read from disk. Unsealed
+/// data is returned as it is, with or without a cipher.
+///
+/// # Errors
+///
+/// Returns an error if the data is sealed and there is no cipher or it
+/// cannot open it.
+pub fn decode(
+    data: Vec<u8>,
+    cipher: Option<&CacheCipher>,
+) -> Result<Vec<u8>> {
+    match cipher {
+        _ if !is_sealed(&data) => Ok(data),
+        Some(cipher) => cipher.open(&data),
+        None => bail!("the cache is encrypted and no key is set"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Iterations that keep the tests fast.
+    const TEST_ITERATIONS: u32 = 1_000;
+
+    #[test]
+    fn test_seal_and_open() -> Result<()> {
+        let cipher =
+            CacheCipher::with_iterations("secret", TEST_ITERATIONS);
+        let sealed = cipher.seal(b"page text")?;
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.windows(9).any(|w| w == b"page text"));
+        assert_ne!(cipher.seal(b"page text")?, sealed);
+        assert_eq!(cipher.open(&sealed)?, b"page text");
+
+        // Another cipher from the same secret draws another salt but
+        // still opens the file.
+        let reopened =
+            CacheCipher::with_iterations("secret", TEST_ITERATIONS);
+        assert_eq!(reopened.open(&sealed)?, b"page text");
+        let error =
+            CacheCipher::with_iterations("rotated", TEST_ITERATIONS)
+                .open(&sealed);
+        assert!(error.unwrap_err().to_string().contains("wrong key"));
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(cipher.open(&tampered).is_err());
+        assert_eq!(format!("{:?}", cipher), "CacheCipher { .. }");
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_and_decode() -> Result<()> {
+        let cipher =
+            CacheCipher::with_iterations("secret", TEST_ITERATIONS);
+        assert_eq!(encode(b"{}".to_vec(), None)?, b"{}");
+        assert_eq!(decode(b"{}".to_vec(), Some(&cipher))?, b"{}");
+        let sealed = encode(b"{}".to_vec(), Some(&cipher))?;
+        assert_eq!(decode(sealed.clone(), Some(&cipher))?, b"{}");
+        assert!(decode(sealed, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_options() -> Result<()> {
+        let mut options = CacheOptions::default();
+        options.validate()?;
+        assert_eq!(options.cipher()?, None);
+
+        options.encrypt = true;
+        options.key_env = "SSG_TEST_CACHE_KEY_UNSET".into();
+        assert!(options.cipher().is_err());
+        options.key_env = "SSG CACHE KEY".into();
+        assert!(options.validate().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Stale Output Cleaning
+//!
+//! Builds write into the site directory without emptying it first, so
+//! the page of a renamed or deleted post stayed online until someone
+//! removed it by hand. Every build now records the files it created or
+//! rewrote in a manifest, [`MANIFEST_FILE`]. With `--clean`, or
+//! `clean = true` in the configuration file, the files of the manifest
+//! are removed before the site is written again, so only what the new
+//! build produces is left:
+//!
+//! - files no build wrote, such as a `CNAME` added by hand, are kept;
+//! - directories left empty by the removal are removed too;
+//! - files of the site directory that were there before the first
+//!   manifest and that no build rewrites since are never recorded, and
+//!   are kept as well.
+//!
+//! The manifest names the site directory it describes, and is ignored
+//! when the site is written somewhere else.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::collect_files_recursive;
+
+/// File recording the outputs of the builds.
+pub const MANIFEST_FILE: &str = ".ssg-cache/output-manifest.json";
+
+/// Files of a site directory, relative with `/` separators, and when
+/// they were last modified.
+pub type Snapshot = BTreeMap<String, Option<SystemTime>>;
+
+/// The files of a site directory written by builds.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct OutputManifest {
+    /// The site directory.
+    pub site_dir: PathBuf,
+    /// Files written by builds, relative with `/` separators.
+    pub files: BTreeSet<String>,
+}
+
+impl OutputManifest {
+    /// Loads the manifest at `path`, or returns an empty one for
+    /// `site_dir` when there is none or it describes another directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest cannot be read or parsed.
+    pub fn load(path: &Path, site_dir: &Path) -> Result<Self> {
+        let empty = Self {
+            site_dir: site_dir.to_path_buf(),
+            files: BTreeSet::new(),
+        };
+        if !path.is_file() {
+            return Ok(empty);
+        }
+        let json = fs::read_to_string(path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let manifest: Self =
+            serde_json::from_str(&json).with_context(|| {
+                format!("Invalid output manifest {}", path.display())
+            })?;
+        Ok(if manifest.site_dir == site_dir {
+            manifest
+        } else {
+            empty
+        })
+    }
+
+    /// Writes the manifest to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest cannot be written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .context("Failed to serialize the output manifest")?;
+        fs::write(path, json).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })
+    }
+
+    /// Returns the manifest after a build that found the site directory
+    /// as in `before`: the files created or modified since, and the
+    /// files of this manifest that are still there.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the site directory cannot be read.
+    pub fn record(&self, before: &Snapshot) -> Result<Self> {
+        let files = snapshot(&self.site_dir)?
+            .into_iter()
+            .filter(|(file, modified)| {
+                self.files.contains(file)
+                    || before.get(file) != Some(modified)
+            })
+            .map(|(file, _)| file)
+            .collect();
+        Ok(Self {
+            site_dir: self.site_dir.clone(),
+            files,
+        })
+    }
+
+    /// Removes the files of the manifest from the site directory, and
+    /// the directories this leaves empty. Entries that would resolve
+    /// outside the site directory are skipped.
+    ///
+    /// Returns the removed files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be removed.
+    pub fn remove_outputs(&self) -> Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        for file in &self.files {
+            let relative = Path::new(file);
+            if !relative
+                .components()
+                .all(|part| matches!(part, Component::Normal(_)))
+            {
+                continue;
+            }
+            let path = self.site_dir.join(relative);
+            if !path.is_file() {
+                continue;
+            }
+            fs::remove_file(&path).with_context(|| {
+                format!("Failed to remove {}", path.display())
+            })?;
+            let mut dir = path.parent();
+            while let Some(parent) = dir {
+                if parent == self.site_dir
+                    || fs::remove_dir(parent).is_err()
+                {
+                    break;
+                }
+                dir = parent.parent();
+            }
+            removed.push(path);
+        }
+        Ok(removed)
+    }
+}
+
+/// Returns the files of `site_dir` and when they were last modified.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be read.
+pub fn snapshot(site_dir: &Path) -> Result<Snapshot> {
+    if !site_dir.is_dir() {
+        return Ok(Snapshot::new());
+    }
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    Ok(files
+        .into_iter()
+        .map(|path| {
+            let modified =
+                fs::metadata(&path).and_then(|m| m.modified()).ok();
+            let relative = path
+                .strip_prefix(site_dir)
+                .unwrap_or(&path)
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            (relative, modified)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_clean_keeps_files_no_build_wrote() -> Result<()> {
+        let temp = tempdir()?;
+        let site = temp.path().join("public");
+        let manifest_file = temp.path().join("manifest.json");
+        fs::create_dir_all(site.join("old"))?;
+        fs::write(site.join("CNAME"), "example.com")?;
+
+        // The first build writes two pages next to the CNAME.
+        let manifest = OutputManifest::load(&manifest_file, &site)?;
+        let before = snapshot(&site)?;
+        fs::write(site.join("index.html"), "home")?;
+        fs::write(site.join("old/index.html"), "old")?;
+        let manifest = manifest.record(&before)?;
+        assert_eq!(
+            manifest.files,
+            ["index.html", "old/index.html"]
+                .iter()
+                .map(|file| file.to_string())
+                .collect::<BTreeSet<_>>()
+        );
+        manifest.save(&manifest_file)?;
+
+        // The next one, with `--clean`, no longer writes `old/`.
+        let manifest = OutputManifest::load(&manifest_file, &site)?;
+        assert_eq!(manifest.remove_outputs()?.len(), 2);
+        let before = snapshot(&site)?;
+        fs::write(site.join("index.html"), "home")?;
+        let manifest = manifest.record(&before)?;
+
+        assert!(site.join("CNAME").is_file());
+        assert!(site.join("index.html").is_file());
+        assert!(!site.join("old").exists());
+        assert_eq!(manifest.files.len(), 1);
+
+        let other = OutputManifest::load(&manifest_file, temp.path())?;
+        assert!(other.files.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_outputs_stays_in_the_site() -> Result<()> {
+        let temp = tempdir()?;
+        let site = temp.path().join("public");
+        fs::create_dir_all(&site)?;
+        fs::write(temp.path().join("secret.txt"), "keep")?;
+        let manifest = OutputManifest {
+            site_dir: site,
+            files: ["../secret.txt".to_string()].into_iter().collect(),
+        };
+        assert!(manifest.remove_outputs()?.is_empty());
+        assert!(temp.path().join("secret.txt").is_file());
+        Ok(())
+    }
+}
@@ -0,0 +1,1251 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Compile Service
+//!
+//! Wraps the core `staticdatagen` compile step and exposes registration
+//! points so library users can add their own per-page outputs (for example
+//! an `.amp.html` variant or an `.ics` calendar entry) without modifying the
+//! core modules.
+//!
+//! ```rust,no_run
+//! use anyhow::Result;
+//! use ssg::{
+//!     compiler::{Compiler, GeneratedOutput, OutputGenerator},
+//!     content::Page,
+//!     Paths,
+//! };
+//!
+//! #[derive(Debug)]
+//! struct PlainText;
+//!
+//! impl OutputGenerator for PlainText {
+//!     fn name(&self) -> &str {
+//!         "plain-text"
+//!     }
+//!
+//!     fn generate(&self, page: &Page) -> Result<Vec<GeneratedOutput>> {
+//!         let path = page.output_path().with_extension("txt");
+//!         Ok(vec![GeneratedOutput::new(path, page.body.clone())])
+//!     }
+//! }
+//!
+//! fn main() -> Result<()> {
+//!     let summary = Compiler::new(Paths::default_paths())
+//!         .with_generator(PlainText)
+//!         .compile()?;
+//!     println!("{} extra files", summary.generated.len());
+//!     Ok(())
+//! }
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use rayon::prelude::*;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+use tempfile::TempDir;
+
+use crate::{
+    breadcrumbs::Breadcrumbs,
+    build_info::BuildInfo,
+    collect_files_recursive,
+    content::{ContentSet, Page},
+    content_files::{
+        self, NonContentFile, NonContentOptions, NonContentPolicy,
+    },
+    data::SiteData,
+    dates::{DateContext, DEFAULT_DATE_LANGUAGE},
+    diagnostics::WarningLog,
+    emoji::{self, EmojiOptions},
+    fetch::JsonFetcher,
+    file_limits::FileLimits,
+    i18n::Translator,
+    ignore,
+    lint::{LintOptions, LintRule, Linter},
+    markdown::ExtensionOptions,
+    math::{self, MathOptions},
+    menus::{self, MenuEntry, Menus, NAVIGATION_VARIABLE},
+    permalinks::{self, PermalinkOptions},
+    pipeline::{self, RenderedSite, StreamSummary},
+    plugins::{Plugin, PluginRegistry},
+    progress::Progress,
+    redirects::{self, RedirectOptions, RedirectRule},
+    render_cache::{CachedBackend, RenderCache},
+    sections::{self, SectionOptions, Sections},
+    taxonomies::{self, TaxonomyOptions},
+    template::{NativeBackend, TemplateBackend},
+    themes::{TemplateResolver, Theme},
+    Paths,
+};
+
+/// Layout of every page in the core compile, whose pages the pipeline
+/// replaces.
+const CORE_LAYOUT: &str = "<!-- rendered by the page pipeline -->";
+
+/// A file produced by an [`OutputGenerator`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedOutput {
+    /// Path relative to the site output directory.
+    pub path: PathBuf,
+    /// File contents.
+    pub contents: Vec<u8>,
+}
+
+impl GeneratedOutput {
+    /// Creates a new output at `path` (relative to the site directory).
+    pub fn new(
+        path: impl Into<PathBuf>,
+        contents: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            contents: contents.into(),
+        }
+    }
+}
+
+/// Produces additional output files for each page.
+///
+/// Generators are run in parallel across pages after the core compile step,
+/// so implementations must be `Send + Sync`.
+pub trait OutputGenerator: Debug + Send + Sync {
+    /// A short, unique name used in error messages.
+    fn name(&self) -> &str;
+
+    /// Returns the extra files to write for `page`.
+    ///
+    /// # Errors
+    ///
+    /// Any error aborts the build and is reported with the generator name
+    /// and page path.
+    fn generate(&self, page: &Page) -> Result<Vec<GeneratedOutput>>;
+}
+
+/// Summary of a compile run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompileSummary {
+    /// Number of content pages seen by the generators.
+    pub pages: usize,
+    /// Site-relative paths written by registered generators, in order.
+    pub generated: Vec<PathBuf>,
+    /// Redirects from the `aliases` frontmatter of the pages.
+    pub redirects: Vec<RedirectRule>,
+    /// Files of the content directory that are not Markdown.
+    pub non_content: Vec<NonContentFile>,
+    /// The content model the pages were rendered from, after the
+    /// plugins and permalinks, for the later stages of the build.
+    pub content: ContentSet,
+}
+
+/// The compile service with its registered output generators.
+#[derive(Debug)]
+pub struct Compiler {
+    paths: Paths,
+    theme: Option<Theme>,
+    memory_budget: Option<usize>,
+    render_cache: Option<Arc<RenderCache>>,
+    language: String,
+    fetcher: Option<JsonFetcher>,
+    translator: Option<Translator>,
+    data: Option<SiteData>,
+    build_info: Option<BuildInfo>,
+    math: MathOptions,
+    emoji: EmojiOptions,
+    extensions: ExtensionOptions,
+    redirects: RedirectOptions,
+    permalinks: PermalinkOptions,
+    taxonomies: BTreeMap<String, TaxonomyOptions>,
+    menus: BTreeMap<String, Vec<MenuEntry>>,
+    sections: SectionOptions,
+    non_content: NonContentOptions,
+    file_limits: FileLimits,
+    drafts: bool,
+    linter: Linter,
+    plugins: PluginRegistry,
+    generators: Vec<Box<dyn OutputGenerator>>,
+}
+
+impl Compiler {
+    /// Creates a compiler for the given directories.
+    pub fn new(paths: Paths) -> Self {
+        Self {
+            paths,
+            theme: None,
+            memory_budget: None,
+            render_cache: None,
+            language: DEFAULT_DATE_LANGUAGE.to_string(),
+            fetcher: None,
+            translator: None,
+            data: None,
+            build_info: None,
+            math: MathOptions::default(),
+            emoji: EmojiOptions::default(),
+            extensions: ExtensionOptions::default(),
+            redirects: RedirectOptions::default(),
+            permalinks: PermalinkOptions::default(),
+            taxonomies: BTreeMap::new(),
+            menus: BTreeMap::new(),
+            sections: SectionOptions::default(),
+            non_content: NonContentOptions::default(),
+            file_limits: FileLimits::default(),
+            drafts: true,
+            linter: Linter::default(),
+            plugins: PluginRegistry::new(),
+            generators: Vec::new(),
+        }
+    }
+
+    /// Returns the directories this compiler works with.
+    pub fn paths(&self) -> &Paths {
+        &self.paths
+    }
+
+    /// Builds with `theme`, returning the compiler for chaining.
+    ///
+    /// Templates are looked up in the site template directory first,
+    /// then in the theme, and theme assets are copied to the site.
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Returns the theme, if one is set.
+    pub fn theme(&self) -> Option<&Theme> {
+        self.theme.as_ref()
+    }
+
+    /// Checks that the theme's `[requires]` table is met by this build
+    /// and by the template engine with this compiler's functions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing every unmet requirement.
+    pub fn check_theme(&self) -> Result<()> {
+        match &self.theme {
+            Some(theme) => theme
+                .check_compatibility(self.native_backend().engine()),
+            None => Ok(()),
+        }
+    }
+
+    /// Renders pages through `cache`, returning the compiler for
+    /// chaining. Pages whose template, context and the state their
+    /// template reads are unchanged are served from the cache, and
+    /// [`compile`](Self::compile) prunes the entries it did not use.
+    pub fn with_render_cache(mut self, cache: RenderCache) -> Self {
+        self.render_cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Sets the memory budget of [`stream_pages`](Self::stream_pages)
+    /// in bytes, returning the compiler for chaining. With a budget,
+    /// [`compile`](Self::compile) streams the pages instead of holding
+    /// them all.
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Returns the memory budget of [`stream_pages`](Self::stream_pages)
+    /// in bytes, [`pipeline::DEFAULT_MEMORY_BUDGET`] unless one was set.
+    pub fn memory_budget(&self) -> usize {
+        self.memory_budget
+            .unwrap_or(pipeline::DEFAULT_MEMORY_BUDGET)
+    }
+
+    /// Sets the language page dates are formatted in when a page does
+    /// not set one, returning the compiler for chaining.
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.language = language.to_string();
+        self
+    }
+
+    /// Makes `fetch_json` available to page templates, returning the
+    /// compiler for chaining.
+    pub fn with_fetcher(mut self, fetcher: JsonFetcher) -> Self {
+        self.fetcher = Some(fetcher);
+        self
+    }
+
+    /// Makes `t` available to page templates, returning the compiler
+    /// for chaining.
+    pub fn with_translator(mut self, translator: Translator) -> Self {
+        self.translator = Some(translator);
+        self
+    }
+
+    /// Makes the site's data files available to page templates as
+    /// `data`, returning the compiler for chaining.
+    pub fn with_data(mut self, data: SiteData) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Makes the build facts and allowlisted environment variables
+    /// available to page templates as `build` and `env`, returning the
+    /// compiler for chaining.
+    pub fn with_build_info(mut self, build_info: BuildInfo) -> Self {
+        self.build_info = Some(build_info);
+        self
+    }
+
+    /// Sets whether the formulas of the content are protected from the
+    /// Markdown renderer, returning the compiler for chaining.
+    pub fn with_math(mut self, options: MathOptions) -> Self {
+        self.math = options;
+        self
+    }
+
+    /// Sets whether the emoji shortcodes of the content are replaced
+    /// before it is rendered, returning the compiler for chaining.
+    pub fn with_emoji(mut self, options: EmojiOptions) -> Self {
+        self.emoji = options;
+        self
+    }
+
+    /// Sets the Markdown extensions page bodies are rendered with,
+    /// returning the compiler for chaining.
+    pub fn with_markdown_extensions(
+        mut self,
+        options: ExtensionOptions,
+    ) -> Self {
+        self.extensions = options;
+        self
+    }
+
+    /// Sets the formats alias redirects are written in, returning the
+    /// compiler for chaining.
+    pub fn with_redirects(mut self, options: RedirectOptions) -> Self {
+        self.redirects = options;
+        self
+    }
+
+    /// Sets the permalink templates pages are moved to after the core
+    /// compile, returning the compiler for chaining.
+    pub fn with_permalinks(
+        mut self,
+        options: PermalinkOptions,
+    ) -> Self {
+        self.permalinks = options;
+        self
+    }
+
+    /// Sets the taxonomies whose term pages are written after the core
+    /// compile, returning the compiler for chaining.
+    pub fn with_taxonomies(
+        mut self,
+        taxonomies: BTreeMap<String, TaxonomyOptions>,
+    ) -> Self {
+        self.taxonomies = taxonomies;
+        self
+    }
+
+    /// Sets the configured menu entries, which templates see together
+    /// with the pages' `menu` frontmatter, returning the compiler for
+    /// chaining.
+    pub fn with_menus(
+        mut self,
+        menus: BTreeMap<String, Vec<MenuEntry>>,
+    ) -> Self {
+        self.menus = menus;
+        self
+    }
+
+    /// Sets the order of section entries and whether sections without
+    /// an `index.md` get a listing page, returning the compiler for
+    /// chaining.
+    pub fn with_sections(mut self, options: SectionOptions) -> Self {
+        self.sections = options;
+        self
+    }
+
+    /// Sets what happens to the files of the content directory that are
+    /// not Markdown, returning the compiler for chaining.
+    pub fn with_non_content(
+        mut self,
+        options: NonContentOptions,
+    ) -> Self {
+        self.non_content = options;
+        self
+    }
+
+    /// Sets the size from which passed-through content files are copied
+    /// in chunks, returning the compiler for chaining.
+    pub fn with_file_limits(mut self, limits: FileLimits) -> Self {
+        self.file_limits = limits;
+        self
+    }
+
+    /// Sets whether pages marked `draft: true` are built, returning the
+    /// compiler for chaining. They are by default.
+    pub fn with_drafts(mut self, drafts: bool) -> Self {
+        self.drafts = drafts;
+        self
+    }
+
+    /// Sets the built-in lint rules run on the content before the core
+    /// compile, returning the compiler for chaining.
+    pub fn with_lint(mut self, options: LintOptions) -> Self {
+        self.linter.set_options(options);
+        self
+    }
+
+    /// Registers a lint rule run on the content before the core
+    /// compile, returning the compiler for chaining.
+    pub fn with_lint_rule<R: LintRule + 'static>(
+        mut self,
+        rule: R,
+    ) -> Self {
+        self.linter.register_rule(Box::new(rule));
+        self
+    }
+
+    /// Registers an output generator, returning the compiler for chaining.
+    pub fn with_generator<G: OutputGenerator + 'static>(
+        mut self,
+        generator: G,
+    ) -> Self {
+        self.register_generator(Box::new(generator));
+        self
+    }
+
+    /// Registers a boxed output generator.
+    pub fn register_generator(
+        &mut self,
+        generator: Box<dyn OutputGenerator>,
+    ) {
+        self.generators.push(generator);
+    }
+
+    /// Registers a build plugin, returning the compiler for chaining.
+    pub fn with_plugin<P: Plugin + 'static>(
+        mut self,
+        plugin: P,
+    ) -> Self {
+        self.plugins.register(Box::new(plugin));
+        self
+    }
+
+    /// Replaces the build plugins, returning the compiler for chaining.
+    pub fn with_plugins(mut self, plugins: PluginRegistry) -> Self {
+        self.plugins = plugins;
+        self
+    }
+
+    /// Returns the registered build plugins.
+    pub fn plugins(&self) -> &PluginRegistry {
+        &self.plugins
+    }
+
+    /// Returns the names of the registered generators.
+    pub fn generator_names(&self) -> Vec<&str> {
+        self.generators.iter().map(|g| g.name()).collect()
+    }
+
+    /// Compiles the site, moves pages to their permalinks, writes the
+    /// taxonomy term pages and the listings of sections without an
+    /// `index.md`, runs every registered generator and writes the
+    /// redirects of page aliases.
+    ///
+    /// The core compile writes the feeds, sitemap and other site-wide
+    /// files from the frontmatter of the pages, with placeholder
+    /// layouts. The pages are rendered once, from the content model on
+    /// the [parallel pipeline](crate::pipeline), in the waves of their
+    /// dependency graph, and written at their permalinks over the
+    /// placeholders; with a [memory budget](Self::with_memory_budget),
+    /// in batches that fit in it.
+    ///
+    /// Build plugins run before the core compile, over the loaded pages
+    /// before permalinks are applied, and over the HTML of each page
+    /// once it is at its permalink.
+    ///
+    /// With a theme, theme assets are copied into the site directory
+    /// after the core compile, without replacing site files.
+    ///
+    /// Files of the content directory that are not Markdown are handled
+    /// by the [non-content policy](Self::with_non_content) and listed in
+    /// the summary. Without [drafts](Self::with_drafts), pages marked
+    /// `draft: true` are neither compiled nor seen by later stages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a lint rule fails, the non-content policy is
+    /// `error` and the content directory holds other files, the core
+    /// compile fails, a page fails to render, a permalink cannot be
+    /// expanded, a term page clashes with another page, a generator
+    /// fails, an alias is invalid, a generated file cannot be written,
+    /// or the theme is not compatible with this build.
+    pub fn compile(&self) -> Result<CompileSummary> {
+        self.check_theme()?;
+        self.plugins.before_build(&self.paths)?;
+        if self.linter.is_enabled() {
+            let _ = self
+                .linter
+                .run(&ContentSet::load(&self.paths.content)?)?;
+        }
+        let non_content = content_files::scan(
+            &self.paths.content,
+            &self.non_content,
+        )?;
+        content_files::check(&non_content, &self.non_content)?;
+
+        // The core compile writes the files it derives from
+        // frontmatter; the pages themselves are rendered once, from the
+        // content model below. With a theme, its templates are merged
+        // over the site's first.
+        let ignored_paths = ignore::ignored_paths(&self.paths.content)?;
+        let merged = match &self.theme {
+            Some(theme) => {
+                let dir = tempfile::tempdir()
+                    .context("Failed to create template directory")?;
+                let _ = TemplateResolver::new(
+                    &self.paths.template,
+                    Some(theme),
+                )
+                .merge_into(dir.path())?;
+                Some(dir)
+            }
+            None => None,
+        };
+        let template_dir = merged
+            .as_ref()
+            .map_or(self.paths.template.as_path(), |dir| dir.path());
+        let (core_content, core_templates) =
+            self.stage_core(template_dir)?;
+
+        // The progress covers the pages, the theme assets and the other
+        // content files copied as they are.
+        let mut sources = Vec::new();
+        collect_files_recursive(core_content.path(), &mut sources)
+            .context("Failed to read the staged content")?;
+        let pages = sources.len();
+        let assets = match &self.theme {
+            Some(theme) => theme.asset_files()?.len(),
+            None => 0,
+        };
+        let copies = match self.non_content.policy {
+            NonContentPolicy::Passthrough => non_content.len(),
+            _ => 0,
+        };
+        let progress = Progress::new(
+            "Compiling",
+            (pages + assets + copies) as u64,
+        );
+
+        staticdatagen::compile(
+            &self.paths.build,
+            core_content.path(),
+            &self.paths.site,
+            core_templates.path(),
+        )
+        .map_err(|e| anyhow!("Failed to compile site: {:?}", e))?;
+        progress.inc(pages as u64);
+
+        if let Some(theme) = &self.theme {
+            let _ = theme.copy_assets(&self.paths.site)?;
+            progress.inc(assets as u64);
+        }
+        let copied = content_files::apply(
+            &non_content,
+            &self.paths.content,
+            &self.paths.site,
+            &self.non_content,
+            &self.file_limits,
+        )?;
+        progress.inc(copied as u64);
+        progress.finish();
+
+        let mut content = ContentSet::load(&self.paths.content)?;
+        content.pages.retain(|page| {
+            !ignored_paths
+                .iter()
+                .any(|path| page.source.starts_with(path))
+        });
+        if !self.drafts {
+            let _ = content.remove_drafts();
+        }
+        self.plugins.on_pages(&mut content)?;
+        let moves = permalinks::apply(&mut content, &self.permalinks)?;
+        let _ = permalinks::move_pages(&self.paths.site, &moves)?;
+        if self.memory_budget.is_some() {
+            let _ = self.stream_pages(&content)?;
+        } else {
+            let _ = self.render_pages(&content)?;
+        }
+        let _ = self.plugins.after_render(&content, &self.paths)?;
+        if !self.taxonomies.is_empty() || self.sections.listings {
+            let backend = self.page_backend(&content)?;
+            let _ = taxonomies::write_taxonomies(
+                &content,
+                &self.taxonomies,
+                &*backend,
+                &self.paths.site,
+            )?;
+            let _ = sections::write_sections(
+                &content,
+                &self.sections,
+                &*backend,
+                &self.paths.site,
+            )?;
+        }
+        let mut summary = self.run_generators(&content)?;
+        summary.redirects = redirects::collect_aliases(&content)?;
+        let _ = redirects::write_redirects(
+            &self.paths.site,
+            &summary.redirects,
+            &self.redirects,
+        )?;
+        if let Some(cache) = &self.render_cache {
+            let _ = cache.prune()?;
+        }
+        summary.non_content = non_content;
+        summary.content = content;
+        Ok(summary)
+    }
+
+    /// Compiles the site without blocking the async runtime, for
+    /// servers that embed the generator.
+    ///
+    /// Unlike [`compile`](Self::compile), the core compile is not run,
+    /// so no feeds or sitemap are written, and pages are rendered only
+    /// with the native [pipeline](crate::pipeline): content is loaded
+    /// with async file IO, the pages of each wave are rendered in
+    /// concurrent tasks and written through the
+    /// [output write queue](crate::writer). The other stages run on
+    /// tokio's blocking pool, in the same order as in `compile`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`compile`](Self::compile) and of
+    /// [`render_pages`](Self::render_pages), or an error if a build task
+    /// panics.
+    #[cfg(feature = "async")]
+    pub async fn compile_async(
+        self: Arc<Self>,
+    ) -> Result<CompileSummary> {
+        let compiler = Arc::clone(&self);
+        let (backend, non_content) =
+            tokio::task::spawn_blocking(move || {
+                compiler.check_theme()?;
+                compiler.plugins.before_build(&compiler.paths)?;
+                let non_content = content_files::scan(
+                    &compiler.paths.content,
+                    &compiler.non_content,
+                )?;
+                content_files::check(
+                    &non_content,
+                    &compiler.non_content,
+                )?;
+                Ok::<_, anyhow::Error>((
+                    compiler.template_backend()?,
+                    non_content,
+                ))
+            })
+            .await
+            .context("The template loading task panicked")??;
+
+        let content =
+            ContentSet::load_async(&self.paths.content).await?;
+        let compiler = Arc::clone(&self);
+        let content = tokio::task::spawn_blocking(move || {
+            let mut content = content;
+            if !compiler.drafts {
+                let _ = content.remove_drafts();
+            }
+            if compiler.linter.is_enabled() {
+                let _ = compiler.linter.run(&content)?;
+            }
+            compiler.plugins.on_pages(&mut content)?;
+            let _ =
+                permalinks::apply(&mut content, &compiler.permalinks)?;
+            Ok::<_, anyhow::Error>(content)
+        })
+        .await
+        .context("The content preparation task panicked")??;
+
+        let mut backend = backend;
+        self.register_navigation(&mut backend, &content)?;
+        let content = Arc::new(content);
+        let backend: Arc<dyn TemplateBackend> = Arc::new(backend);
+        let dates =
+            Arc::new(DateContext::load(&content, &self.language));
+        let mut site = pipeline::render_pages_async(
+            Arc::new(self.render_source(&content)),
+            Arc::clone(&backend),
+            dates,
+        )
+        .await?;
+        for (page, rendered) in
+            content.pages.iter().zip(&mut site.pages)
+        {
+            self.plugins.after_render_page(page, &mut rendered.html)?;
+        }
+        let _ = site.write_async(&self.paths.site).await?;
+
+        let compiler = Arc::clone(&self);
+        tokio::task::spawn_blocking(move || {
+            if let Some(theme) = &compiler.theme {
+                let _ = theme.copy_assets(&compiler.paths.site)?;
+            }
+            let _ = content_files::apply(
+                &non_content,
+                &compiler.paths.content,
+                &compiler.paths.site,
+                &compiler.non_content,
+                &compiler.file_limits,
+            )?;
+            let _ = taxonomies::write_taxonomies(
+                &content,
+                &compiler.taxonomies,
+                backend.as_ref(),
+                &compiler.paths.site,
+            )?;
+            let _ = sections::write_sections(
+                &content,
+                &compiler.sections,
+                backend.as_ref(),
+                &compiler.paths.site,
+            )?;
+            let mut summary = compiler.run_generators(&content)?;
+            summary.redirects = redirects::collect_aliases(&content)?;
+            let _ = redirects::write_redirects(
+                &compiler.paths.site,
+                &summary.redirects,
+                &compiler.redirects,
+            )?;
+            summary.non_content = non_content;
+            summary.content = Arc::try_unwrap(content)
+                .unwrap_or_else(|content| (*content).clone());
+            Ok(summary)
+        })
+        .await
+        .context("The output generation task panicked")?
+    }
+
+    /// Renders `content` with the site templates (and the theme's) on the
+    /// [parallel pipeline](crate::pipeline), then writes the pages into
+    /// the site directory.
+    ///
+    /// With [math](Self::with_math) or [emoji](Self::with_emoji) on, the
+    /// page bodies are rendered with their formulas protected and their
+    /// shortcodes replaced, as in the core compile.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the templates cannot be read, a menu is
+    /// invalid, a page fails to render, or a page cannot be written.
+    pub fn render_pages(
+        &self,
+        content: &ContentSet,
+    ) -> Result<RenderedSite> {
+        let backend = self.page_backend(content)?;
+        let site = pipeline::render_pages_with_dates(
+            &self.render_source(content),
+            &*backend,
+            &DateContext::load(content, &self.language),
+        )?;
+        site.write(&self.paths.site)?;
+        Ok(site)
+    }
+
+    /// Renders and writes `content` like
+    /// [`render_pages`](Self::render_pages), but in batches bounded by
+    /// the [memory budget](Self::with_memory_budget), for sites too
+    /// large to hold in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the templates cannot be read, a menu is
+    /// invalid, a page fails to render, or a page cannot be written.
+    pub fn stream_pages(
+        &self,
+        content: &ContentSet,
+    ) -> Result<StreamSummary> {
+        let backend = self.page_backend(content)?;
+        pipeline::stream_pages_with_dates(
+            &self.render_source(content),
+            &*backend,
+            &self.paths.site,
+            self.memory_budget(),
+            &DateContext::load(content, &self.language),
+        )
+    }
+
+    /// Returns `content` with the formulas of its page bodies protected
+    /// and their emoji shortcodes replaced, as configured, for the
+    /// Markdown renderer.
+    fn render_source(&self, content: &ContentSet) -> ContentSet {
+        let mut content = content.clone();
+        for page in &mut content.pages {
+            if self.math.enabled {
+                page.body = math::protect_math(&page.body);
+            }
+            if self.emoji.enabled {
+                page.body =
+                    emoji::replace_emoji(&page.body, &self.emoji);
+            }
+        }
+        content
+    }
+
+    /// Stages the inputs of the core compile, which writes the feeds,
+    /// sitemaps and other files of each page from its frontmatter: the
+    /// Markdown files of the content directory without their bodies,
+    /// leaving out drafts unless they are built and the files of the
+    /// ignore rules, and the files of `template_dir` with every layout
+    /// reduced to [`CORE_LAYOUT`]. The placeholder pages it writes are
+    /// replaced by the pages of the pipeline.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read or written.
+    fn stage_core(
+        &self,
+        template_dir: &Path,
+    ) -> Result<(TempDir, TempDir)> {
+        let content = tempfile::tempdir()
+            .context("Failed to create content directory")?;
+        let _ = content_files::stage_markdown(
+            &self.paths.content,
+            content.path(),
+        )?;
+        let _ = content_files::rewrite_bodies(content.path(), |_| {
+            String::new()
+        })?;
+        if !self.drafts {
+            for page in ContentSet::load(&self.paths.content)?
+                .iter()
+                .filter(|page| page.is_draft())
+            {
+                let path = content.path().join(&page.relative);
+                if path.exists() {
+                    fs::remove_file(&path).with_context(|| {
+                        format!("Failed to remove {}", path.display())
+                    })?;
+                }
+            }
+        }
+
+        let templates = tempfile::tempdir()
+            .context("Failed to create template directory")?;
+        let mut files = Vec::new();
+        collect_files_recursive(template_dir, &mut files)
+            .with_context(|| {
+                format!("Failed to read {}", template_dir.display())
+            })?;
+        for file in &files {
+            let target = templates
+                .path()
+                .join(file.strip_prefix(template_dir).unwrap_or(file));
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).with_context(|| {
+                    format!("Failed to create {}", parent.display())
+                })?;
+            }
+            let written = if file
+                .extension()
+                .map_or(false, |ext| ext == "html")
+            {
+                fs::write(&target, CORE_LAYOUT)
+            } else {
+                fs::copy(file, &target).map(|_| ())
+            };
+            written.with_context(|| {
+                format!("Failed to write {}", target.display())
+            })?;
+        }
+        Ok((content, templates))
+    }
+
+    /// Loads the site templates, layered over the theme's, into a
+    /// native backend.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the theme is not compatible with this build
+    /// or the templates cannot be read.
+    pub fn template_backend(&self) -> Result<NativeBackend> {
+        self.check_theme()?;
+        let templates = tempfile::tempdir()
+            .context("Failed to create template directory")?;
+        let _ = TemplateResolver::new(
+            &self.paths.template,
+            self.theme.as_ref(),
+        )
+        .merge_into(templates.path())?;
+        let mut backend = self.native_backend();
+        let _ = backend.add_dir(templates.path())?;
+        Ok(backend)
+    }
+
+    /// Returns the [template backend](Self::template_backend) with the
+    /// menus, breadcrumbs and sections of `content` registered, behind
+    /// the [render cache](Self::with_render_cache) if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the templates cannot be read, or a menu or
+    /// the order of a section is invalid.
+    fn page_backend(
+        &self,
+        content: &ContentSet,
+    ) -> Result<Box<dyn TemplateBackend>> {
+        let mut backend = self.template_backend()?;
+        self.register_navigation(&mut backend, content)?;
+        Ok(match &self.render_cache {
+            Some(cache) => {
+                Box::new(CachedBackend::new(backend, Arc::clone(cache)))
+            }
+            None => Box::new(backend),
+        })
+    }
+
+    /// Registers the [menus](crate::menus) and generated navigation,
+    /// [breadcrumbs](crate::breadcrumbs) and
+    /// [sections](crate::sections) of `content` with `backend`.
+    fn register_navigation(
+        &self,
+        backend: &mut NativeBackend,
+        content: &ContentSet,
+    ) -> Result<()> {
+        Menus::build(&self.menus, content)?
+            .register(backend.engine_mut());
+        backend.engine_mut().set_global(
+            NAVIGATION_VARIABLE,
+            Value::String(menus::navigation_html(content)),
+        );
+        Breadcrumbs::new(content).register(backend.engine_mut());
+        Sections::build(content, &self.sections)?
+            .register(backend.engine_mut());
+        Ok(())
+    }
+
+    /// Returns a native backend with no templates, the Markdown
+    /// extensions set, the functions of the fetcher and translator
+    /// registered and the data and build variables set.
+    fn native_backend(&self) -> NativeBackend {
+        let mut backend = NativeBackend::default();
+        backend.set_markdown_extensions(self.extensions);
+        if let Some(fetcher) = &self.fetcher {
+            fetcher.register(backend.engine_mut());
+        }
+        if let Some(translator) = &self.translator {
+            translator.register(backend.engine_mut());
+        }
+        if let Some(data) = &self.data {
+            data.register(backend.engine_mut());
+        }
+        if let Some(build_info) = &self.build_info {
+            build_info.register(backend.engine_mut());
+        }
+        backend
+    }
+
+    /// Runs the registered generators over an already loaded content set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a generator fails or an output path escapes the
+    /// site directory.
+    pub fn run_generators(
+        &self,
+        content: &ContentSet,
+    ) -> Result<CompileSummary> {
+        let mut summary = CompileSummary {
+            pages: content.len(),
+            ..CompileSummary::default()
+        };
+        if self.generators.is_empty() {
+            return Ok(summary);
+        }
+
+        let warnings = WarningLog::new();
+        let outputs = content
+            .pages
+            .par_iter()
+            .map(|page| {
+                warnings.scope(&page.relative, || {
+                    let mut outputs = Vec::new();
+                    for generator in &self.generators {
+                        let generated = generator
+                            .generate(page)
+                            .with_context(|| {
+                                format!(
+                                    "Output generator '{}' failed for {}",
+                                    generator.name(),
+                                    page.relative.display()
+                                )
+                            })?;
+                        outputs.extend(generated);
+                    }
+                    Ok(outputs)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let _ = warnings.flush();
+
+        for output in outputs.into_iter().flatten() {
+            if output.path.is_absolute()
+                || output.path.components().any(|c| {
+                    matches!(
+                        c,
+                        Component::ParentDir | Component::Prefix(_)
+                    )
+                })
+            {
+                anyhow::bail!(
+                    "Generated output escapes the site directory: {}",
+                    output.path.display()
+                );
+            }
+
+            let target = self.paths.site.join(&output.path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).with_context(|| {
+                    format!("Failed to create {}", parent.display())
+                })?;
+            }
+            fs::write(&target, &output.contents).with_context(
+                || format!("Failed to write {}", target.display()),
+            )?;
+            summary.generated.push(output.path);
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[derive(Debug)]
+    struct Calendar;
+
+    impl OutputGenerator for Calendar {
+        fn name(&self) -> &str {
+            "ics"
+        }
+
+        fn generate(
+            &self,
+            page: &Page,
+        ) -> Result<Vec<GeneratedOutput>> {
+            match page.frontmatter.get_str("event_date") {
+                Some(date) => Ok(vec![GeneratedOutput::new(
+                    page.output_path().with_file_name("event.ics"),
+                    format!(
+                        "BEGIN:VEVENT\nSUMMARY:{}\nDTSTART:{}\nEND:VEVENT\n",
+                        page.title(),
+                        date
+                    ),
+                )]),
+                None => Ok(Vec::new()),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct Escaping;
+
+    impl OutputGenerator for Escaping {
+        fn name(&self) -> &str {
+            "escaping"
+        }
+
+        fn generate(
+            &self,
+            _page: &Page,
+        ) -> Result<Vec<GeneratedOutput>> {
+            Ok(vec![GeneratedOutput::new("../outside.txt", "x")])
+        }
+    }
+
+    fn paths_in(base: &Path) -> Paths {
+        Paths {
+            site: base.join("public"),
+            content: base.join("content"),
+            build: base.join("build"),
+            template: base.join("templates"),
+        }
+    }
+
+    #[test]
+    fn test_run_generators_writes_outputs() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let paths = paths_in(temp_dir.path());
+        fs::create_dir_all(&paths.content)?;
+        fs::write(
+            paths.content.join("meetup.md"),
+            "---\ntitle: Meetup\nevent_date: 20250101T180000Z\n---\nJoin us",
+        )?;
+        fs::write(paths.content.join("about.md"), "About")?;
+
+        let compiler =
+            Compiler::new(paths.clone()).with_generator(Calendar);
+        assert_eq!(compiler.generator_names(), vec!["ics"]);
+
+        let content = ContentSet::load(&paths.content)?;
+        let summary = compiler.run_generators(&content)?;
+
+        assert_eq!(summary.pages, 2);
+        assert_eq!(
+            summary.generated,
+            vec![PathBuf::from("meetup/event.ics")]
+        );
+        let ics =
+            fs::read_to_string(paths.site.join("meetup/event.ics"))?;
+        assert!(ics.contains("SUMMARY:Meetup"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_generators_rejects_escaping_paths() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let paths = paths_in(temp_dir.path());
+        fs::create_dir_all(&paths.content)?;
+        fs::write(paths.content.join("index.md"), "Home")?;
+
+        let compiler =
+            Compiler::new(paths.clone()).with_generator(Escaping);
+        let content = ContentSet::load(&paths.content)?;
+        assert!(compiler.run_generators(&content).is_err());
+        assert!(!temp_dir.path().join("outside.txt").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_generators_without_generators() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let compiler = Compiler::new(paths_in(temp_dir.path()));
+        let summary = compiler.run_generators(&ContentSet::default())?;
+        assert_eq!(summary, CompileSummary::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_pages_uses_site_templates() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let paths = paths_in(temp_dir.path());
+        fs::create_dir_all(&paths.content)?;
+        fs::create_dir_all(&paths.template)?;
+        fs::write(
+            paths.template.join("page.html"),
+            "<h1>{{ title }}</h1>",
+        )?;
+        fs::write(
+            paths.content.join("about.md"),
+            "---\ntitle: About\n---\n",
+        )?;
+
+        let compiler = Compiler::new(paths.clone());
+        let content = ContentSet::load(&paths.content)?;
+        let site = compiler.render_pages(&content)?;
+        assert_eq!(site.pages.len(), 1);
+        fs::remove_dir_all(&paths.site)?;
+
+        let compiler = compiler.with_memory_budget(1024);
+        assert_eq!(compiler.memory_budget(), 1024);
+        assert_eq!(compiler.stream_pages(&content)?.pages, 1);
+        assert_eq!(
+            fs::read_to_string(paths.site.join("about/index.html"))?,
+            "<h1>About</h1>"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_pages_through_render_cache() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let paths = paths_in(temp_dir.path());
+        fs::create_dir_all(&paths.content)?;
+        fs::create_dir_all(&paths.template)?;
+        fs::write(
+            paths.template.join("page.html"),
+            "<h1>{{ title }}</h1>",
+        )?;
+        fs::write(
+            paths.content.join("about.md"),
+            "---\ntitle: About\n---\n",
+        )?;
+        let cache_dir = temp_dir.path().join("cache");
+
+        let content = ContentSet::load(&paths.content)?;
+        let mut hits = Vec::new();
+        for _ in 0..2 {
+            let compiler = Compiler::new(paths.clone())
+                .with_render_cache(RenderCache::open(&cache_dir)?);
+            let site = compiler.render_pages(&content)?;
+            assert_eq!(site.pages[0].html, "<h1>About</h1>");
+            let cache = compiler.render_cache.as_ref().expect("cache");
+            hits.push(cache.stats().hits);
+        }
+        assert_eq!(hits, [0, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_pages_replaces_emoji() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let paths = paths_in(temp_dir.path());
+        fs::create_dir_all(&paths.content)?;
+        fs::create_dir_all(&paths.template)?;
+        fs::write(paths.template.join("page.html"), "{{ content }}")?;
+        fs::write(paths.content.join("launch.md"), "Shipped :rocket:")?;
+
+        let compiler =
+            Compiler::new(paths.clone()).with_emoji(EmojiOptions {
+                enabled: true,
+                ..EmojiOptions::default()
+            });
+        let content = ContentSet::load(&paths.content)?;
+        let site = compiler.render_pages(&content)?;
+        assert!(site.pages[0].html.contains("Shipped 🚀"));
+        assert!(content.pages[0].body.contains(":rocket:"));
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_compile_async_renders_pages() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let paths = paths_in(temp_dir.path());
+        fs::create_dir_all(paths.content.join("blog"))?;
+        fs::create_dir_all(&paths.template)?;
+        fs::write(
+            paths.template.join("page.html"),
+            "<h1>{{ title }}</h1>",
+        )?;
+        fs::write(
+            paths.content.join("blog/meetup.md"),
+            "---\ntitle: Meetup\nevent_date: 20250101T180000Z\naliases: [/meetup/]\n---\n",
+        )?;
+
+        let summary = Arc::new(
+            Compiler::new(paths.clone()).with_generator(Calendar),
+        )
+        .compile_async()
+        .await?;
+        assert_eq!(summary.pages, 1);
+        assert_eq!(summary.content.len(), 1);
+        assert_eq!(
+            summary.generated,
+            vec![PathBuf::from("blog/meetup/event.ics")]
+        );
+        assert_eq!(summary.redirects.len(), 1);
+        assert_eq!(
+            fs::read_to_string(
+                paths.site.join("blog/meetup/index.html")
+            )?,
+            "<h1>Meetup</h1>"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Precompression
+//!
+//! Writes gzip (`.gz`) and Brotli (`.br`) copies of the text files in the
+//! site output next to the originals, so static hosts and CDNs that
+//! support precompressed files (nginx `gzip_static`, Caddy
+//! `precompressed`, most object storage CDNs) can serve them without
+//! compressing on every request:
+//!
+//! ```toml
+//! [compress]
+//! enabled = true
+//! gzip_level = 9
+//! brotli_level = 11
+//! extensions = ["html", "css", "js", "json", "xml"]
+//! ```
+//!
+//! Files smaller than `min_bytes` are left alone, since the compressed
+//! copy would save next to nothing. The bytes saved are added to the
+//! build report.
+
+use anyhow::{ensure, Context, Result};
+use flate2::{write::GzEncoder, Compression};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    ffi::OsString,
+    fs,
+    io::Write,
+    ops::AddAssign,
+    path::{Path, PathBuf},
+};
+
+use crate::collect_files_recursive;
+
+/// Extension of the gzip copies.
+pub const GZIP_EXTENSION: &str = "gz";
+
+/// Extension of the Brotli copies.
+pub const BROTLI_EXTENSION: &str = "br";
+
+/// Size of the Brotli encoder's internal buffer.
+const BROTLI_BUFFER: usize = 4096;
+
+/// Brotli window size, as a base-2 logarithm.
+const BROTLI_WINDOW: u32 = 22;
+
+/// `[compress]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressOptions {
+    /// Writes the precompressed copies.
+    pub enabled: bool,
+    /// Writes `.gz` copies.
+    pub gzip: bool,
+    /// Writes `.br` copies.
+    pub brotli: bool,
+    /// gzip compression level, from 0 to 9.
+    pub gzip_level: u32,
+    /// Brotli quality, from 0 to 11.
+    pub brotli_level: u32,
+    /// Smallest file, in bytes, that is compressed.
+    pub min_bytes: u64,
+    /// Extensions of the files to compress, without the dot.
+    pub extensions: Vec<String>,
+}
+
+impl Default for CompressOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            gzip: true,
+            brotli: true,
+            gzip_level: 9,
+            brotli_level: 11,
+            min_bytes: 1024,
+            extensions: ["html", "css", "js", "json", "xml"]
+                .iter()
+                .map(|extension| extension.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl CompressOptions {
+    /// Checks the compression levels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a level is out of range.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.gzip_level <= 9,
+            "gzip_level must be between 0 and 9, got {}",
+            self.gzip_level
+        );
+        ensure!(
+            self.brotli_level <= 11,
+            "brotli_level must be between 0 and 11, got {}",
+            self.brotli_level
+        );
+        Ok(())
+    }
+
+    /// Returns `true` if `path` should be compressed, judging by its
+    /// extension.
+    pub fn compresses(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .map_or(false, |extension| {
+                self.extensions.iter().any(|allowed| {
+                    allowed.eq_ignore_ascii_case(extension)
+                })
+            })
+    }
+}
+
+/// Sizes of the files compressed by a build.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct CompressionStats {
+    /// Number of files compressed.
+    pub files: usize,
+    /// Total size of the original files.
+    pub original_bytes: u64,
+    /// Total size of the `.gz` copies.
+    pub gzip_bytes: u64,
+    /// Total size of the `.br` copies.
+    pub brotli_bytes: u64,
+}
+
+impl CompressionStats {
+    /// Returns `true` if no file was compressed.
+    pub fn is_empty(&self) -> bool {
+        self.files == 0
+    }
+
+    /// Returns the share of the original bytes saved by `compressed`
+    /// bytes, as a percentage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::compress::CompressionStats;
+    ///
+    /// let stats = CompressionStats {
+    ///     files: 1,
+    ///     original_bytes: 1000,
+    ///     gzip_bytes: 250,
+    ///     brotli_bytes: 200,
+    /// };
+    /// assert_eq!(stats.savings(stats.gzip_bytes), 75.0);
+    /// ```
+    pub fn savings(&self, compressed: u64) -> f64 {
+        if self.original_bytes == 0 || compressed == 0 {
+            return 0.0;
+        }
+        (1.0 - compressed as f64 / self.original_bytes as f64) * 100.0
+    }
+}
+
+impl AddAssign for CompressionStats {
+    fn add_assign(&mut self, other: Self) {
+        self.files += other.files;
+        self.original_bytes += other.original_bytes;
+        self.gzip_bytes += other.gzip_bytes;
+        self.brotli_bytes += other.brotli_bytes;
+    }
+}
+
+/// Returns `true` if `path` is a `.gz` or `.br` copy of a file next to
+/// it.
+pub fn is_precompressed(path: &Path) -> bool {
+    let is_copy = path.extension().map_or(false, |extension| {
+        extension == GZIP_EXTENSION || extension == BROTLI_EXTENSION
+    });
+    is_copy && path.with_extension("").is_file()
+}
+
+/// Writes the `.gz` and `.br` copies of every matching file below
+/// `site_dir`, in parallel.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read, compressed or written.
+pub fn compress_site(
+    site_dir: &Path,
+    options: &CompressOptions,
+) -> Result<CompressionStats> {
+    if !options.enabled || !(options.gzip || options.brotli) {
+        return Ok(CompressionStats::default());
+    }
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    files.retain(|path| options.compresses(path));
+
+    let stats = files
+        .par_iter()
+        .map(|path| compress_file(path, options))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(stats.into_iter().fold(
+        CompressionStats::default(),
+        |mut total, stats| {
+            total += stats;
+            total
+        },
+    ))
+}
+
+/// Compresses one file, returning empty stats if it is too small.
+fn compress_file(
+    path: &Path,
+    options: &CompressOptions,
+) -> Result<CompressionStats> {
+    let data = fs::read(path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    let mut stats = CompressionStats::default();
+    if (data.len() as u64) < options.min_bytes {
+        return Ok(stats);
+    }
+    stats.files = 1;
+    stats.original_bytes = data.len() as u64;
+    if options.gzip {
+        let mut encoder = GzEncoder::new(
+            Vec::new(),
+            Compression::new(options.gzip_level),
+        );
+        let compressed = encoder
+            .write_all(&data)
+            .and_then(|()| encoder.finish())
+            .with_context(|| {
+                format!("Failed to gzip {}", path.display())
+            })?;
+        stats.gzip_bytes =
+            write_copy(path, GZIP_EXTENSION, &compressed)?;
+    }
+    if options.brotli {
+        let mut encoder = brotli::CompressorWriter::new(
+            Vec::new(),
+            BROTLI_BUFFER,
+            options.brotli_level,
+            BROTLI_WINDOW,
+        );
+        encoder.write_all(&data).with_context(|| {
+            format!("Failed to compress {} with Brotli", path.display())
+        })?;
+        let compressed = encoder.into_inner();
+        stats.brotli_bytes =
+            write_copy(path, BROTLI_EXTENSION, &compressed)?;
+    }
+    Ok(stats)
+}
+
+/// Writes `data` next to `path` with `extension` appended, returning
+/// its size.
+fn write_copy(
+    path: &Path,
+    extension: &str,
+    data: &[u8],
+) -> Result<u64> {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".");
+    name.push(extension);
+    let copy = PathBuf::from(name);
+    fs::write(&copy, data).with_context(|| {
+        format!("Failed to write {}", copy.display())
+    })?;
+    Ok(data.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_compress_site_writes_copies() -> Result<()> {
+        let dir = tempdir()?;
+        let site = dir.path();
+        let page = "<p>Hello, world!</p>\n".repeat(100);
+        fs::write(site.join("index.html"), &page)?;
+        fs::write(site.join("small.css"), "a{}")?;
+        fs::write(site.join("logo.png"), page.as_bytes())?;
+
+        let options = CompressOptions {
+            enabled: true,
+            ..CompressOptions::default()
+        };
+        let stats = compress_site(site, &options)?;
+        assert_eq!(stats.files, 1);
+        assert_eq!(stats.original_bytes, page.len() as u64);
+        assert!(stats.savings(stats.gzip_bytes) > 50.0);
+        assert!(stats.brotli_bytes > 0);
+
+        let mut decoded = String::new();
+        let _ =
+            GzDecoder::new(fs::File::open(site.join("index.html.gz"))?)
+                .read_to_string(&mut decoded)?;
+        assert_eq!(decoded, page);
+        assert!(site.join("index.html.br").is_file());
+        assert!(is_precompressed(&site.join("index.html.br")));
+        assert!(!site.join("small.css.gz").exists());
+        assert!(!site.join("logo.png.gz").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_levels() {
+        assert!(CompressOptions::default().validate().is_ok());
+        let options = CompressOptions {
+            brotli_level: 12,
+            ..CompressOptions::default()
+        };
+        assert!(options.validate().is_err());
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Concurrency Limits
+//!
+//! Runs the CPU-bound and the IO-bound phases of a build on separate
+//! thread pools, so copying and hashing tens of thousands of small files
+//! does not flood a filesystem with one request per core. The
+//! `[concurrency]` table of the configuration file sets their sizes:
+//!
+//! ```toml
+//! [concurrency]
+//! render = 8   # rendering, highlighting, minifying, compressing
+//! io = 2       # moving media, hashing files for ETags
+//! ```
+//!
+//! A size left out is taken from the `SSG_RENDER_THREADS` or
+//! `SSG_IO_THREADS` environment variable, then detected: rendering uses
+//! every core rayon sees (which honours `RAYON_NUM_THREADS`), IO at most
+//! [`DEFAULT_MAX_IO_THREADS`] of them.
+//!
+//! ```rust
+//! use ssg::concurrency::ConcurrencyOptions;
+//!
+//! let options = ConcurrencyOptions { io: Some(2), ..Default::default() };
+//! let pools = options.pools()?;
+//! assert_eq!(pools.io(rayon::current_num_threads), 2);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{bail, ensure, Context, Result};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+use std::{env, fmt};
+
+/// Environment variable setting the number of render threads.
+pub const RENDER_THREADS_ENV: &str = "SSG_RENDER_THREADS";
+
+/// Environment variable setting the number of IO threads.
+pub const IO_THREADS_ENV: &str = "SSG_IO_THREADS";
+
+/// Most IO threads used when their number is detected.
+pub const DEFAULT_MAX_IO_THREADS: usize = 4;
+
+/// Options of the `[concurrency]` config table.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct ConcurrencyOptions {
+    /// Threads of the CPU-bound phases, detected when unset.
+    pub render: Option<usize>,
+    /// Threads of the IO-bound phases, detected when unset.
+    pub io: Option<usize>,
+}
+
+impl ConcurrencyOptions {
+    /// Checks that the sizes set are positive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(self.render != Some(0), "render must be positive");
+        ensure!(self.io != Some(0), "io must be positive");
+        Ok(())
+    }
+
+    /// Returns the number of render threads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if [`RENDER_THREADS_ENV`] is set to something
+    /// other than a positive number.
+    pub fn render_threads(&self) -> Result<usize> {
+        match self.render {
+            Some(threads) => Ok(threads),
+            None => Ok(env_threads(RENDER_THREADS_ENV)?
+                .unwrap_or_else(detected_threads)),
+        }
+    }
+
+    /// Returns the number of IO threads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if [`IO_THREADS_ENV`] is set to something
+    /// other than a positive number.
+    pub fn io_threads(&self) -> Result<usize> {
+        match self.io {
+            Some(threads) => Ok(threads),
+            None => {
+                Ok(env_threads(IO_THREADS_ENV)?.unwrap_or_else(|| {
+                    detected_threads().min(DEFAULT_MAX_IO_THREADS)
+                }))
+            }
+        }
+    }
+
+    /// Starts the thread pools.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a size cannot be read or a pool cannot be
+    /// started.
+    pub fn pools(&self) -> Result<ConcurrencyPools> {
+        Ok(ConcurrencyPools {
+            render: pool("render", self.render_threads()?)?,
+            io: pool("io", self.io_threads()?)?,
+        })
+    }
+}
+
+/// The thread pools of a build.
+pub struct ConcurrencyPools {
+    render: ThreadPool,
+    io: ThreadPool,
+}
+
+impl ConcurrencyPools {
+    /// Runs `phase` on the render threads: parallel iterators inside it
+    /// use at most that many threads.
+    pub fn render<R: Send>(
+        &self,
+        phase: impl FnOnce() -> R + Send,
+    ) -> R {
+        self.render.install(phase)
+    }
+
+    /// Runs `phase` on the IO threads.
+    pub fn io<R: Send>(&self, phase: impl FnOnce() -> R + Send) -> R {
+        self.io.install(phase)
+    }
+}
+
+impl fmt::Debug for ConcurrencyPools {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConcurrencyPools")
+            .field("render", &self.render.current_num_threads())
+            .field("io", &self.io.current_num_threads())
+            .finish()
+    }
+}
+
+/// Reads a thread count from the environment variable `name`.
+fn env_threads(name: &str) -> Result<Option<usize>> {
+    let value = match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => value,
+        _ => return Ok(None),
+    };
+    match value.trim().parse::<usize>() {
+        Ok(threads) if threads > 0 => Ok(Some(threads)),
+        _ => bail!(
+            "{} must be a positive number of threads, not `{}`",
+            name,
+            value
+        ),
+    }
+}
+
+/// Returns the number of threads rayon would use by default.
+fn detected_threads() -> usize {
+    rayon::current_num_threads().max(1)
+}
+
+/// Starts a pool of `threads` threads named after `phase`.
+fn pool(phase: &'static str, threads: usize) -> Result<ThreadPool> {
+    ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(move |i| format!("ssg-{}-{}", phase, i))
+        .build()
+        .with_context(|| {
+            format!("Failed to start the {} threads", phase)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+
+    #[test]
+    fn test_pools_limit_parallel_phases() -> Result<()> {
+        let options = ConcurrencyOptions {
+            render: Some(3),
+            io: Some(1),
+        };
+        let pools = options.pools()?;
+        assert_eq!(pools.render(rayon::current_num_threads), 3);
+        assert_eq!(pools.io(rayon::current_num_threads), 1);
+
+        let names: Vec<String> = pools.io(|| {
+            (0..8)
+                .into_par_iter()
+                .map(|_| {
+                    std::thread::current()
+                        .name()
+                        .unwrap_or_default()
+                        .to_string()
+                })
+                .collect()
+        });
+        assert!(names.iter().all(|name| name == "ssg-io-0"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_defaults_and_validate() -> Result<()> {
+        let options = ConcurrencyOptions::default();
+        options.validate()?;
+        assert!(options.render_threads()? >= 1);
+        assert!(options.io_threads()? <= DEFAULT_MAX_IO_THREADS);
+
+        let invalid = ConcurrencyOptions {
+            io: Some(0),
+            ..ConcurrencyOptions::default()
+        };
+        assert!(invalid.validate().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,619 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Consent Mode
+//!
+//! Holds back third-party scripts and embeds until the visitor agrees
+//! to their category, from the `[consent]` table of the configuration
+//! file:
+//!
+//! ```toml
+//! [consent]
+//! enabled = true
+//! message = "We use cookies for analytics and comments."
+//! privacy_url = "/privacy/"
+//!
+//! [[consent.scripts]]
+//! category = "analytics"
+//! src = "https://plausible.io/js/script.js"
+//!
+//! [[consent.scripts]]
+//! category = "embeds"
+//! hosts = ["www.youtube.com", "*.vimeo.com"]
+//! ```
+//!
+//! After the build, every page gets:
+//!
+//! - the scripts of `[[consent.scripts]]` with a `src` or `inline`
+//!   body, injected as inert `type="text/plain"` scripts;
+//! - its own `<script>` and `<iframe>` tags from a gated host (the
+//!   `hosts` of a category and the host of its `src`) made inert the
+//!   same way, with the `src` of iframes moved to `data-consent-src`;
+//! - the banner partial and the `/consent.js` loader before `</body>`.
+//!
+//! The loader stores the visitor's choice in `localStorage` and
+//! activates the elements of the accepted categories. Themes can place
+//! their own banner by adding an element with a `data-consent-banner`
+//! attribute; its `data-consent-accept`, `data-consent-reject` and
+//! `data-consent-save` buttons and `name="consent"` checkboxes are
+//! wired up by the loader. Any element with `data-consent-open`
+//! reopens the banner. Tags that already carry `data-consent` are left
+//! to the loader as they are.
+
+use anyhow::{ensure, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write as _, fs, path::Path};
+
+use crate::{collect_files_recursive, template::escape_html};
+
+/// Path of the consent loader, relative to the site directory.
+pub const LOADER_PATH: &str = "consent.js";
+
+/// The consent loader.
+pub const LOADER_JS: &str = r#"(function () {
+  var KEY = "ssg-consent";
+  var loader = document.currentScript;
+  var categories = loader.getAttribute("data-categories").split(" ");
+  var banner = document.querySelector("[data-consent-banner]");
+
+  function read() {
+    try {
+      var stored = JSON.parse(localStorage.getItem(KEY));
+      if (stored && stored.categories.join(" ") === categories.join(" ")) {
+        return stored.granted;
+      }
+    } catch (e) {}
+    return null;
+  }
+
+  function activate(granted) {
+    var gated = document.querySelectorAll("[data-consent]");
+    Array.prototype.forEach.call(gated, function (element) {
+      if (granted.indexOf(element.getAttribute("data-consent")) < 0) {
+        return;
+      }
+      var src = element.getAttribute("data-consent-src");
+      if (element.tagName === "SCRIPT") {
+        var script = document.createElement("script");
+        Array.prototype.forEach.call(element.attributes, function (a) {
+          if (a.name !== "type" && a.name.indexOf("data-consent") !== 0) {
+            script.setAttribute(a.name, a.value);
+          }
+        });
+        var type = element.getAttribute("data-consent-type");
+        if (type) {
+          script.type = type;
+        }
+        if (src) {
+          script.src = src;
+        }
+        script.text = element.text;
+        element.parentNode.replaceChild(script, element);
+      } else {
+        if (src) {
+          element.setAttribute("src", src);
+        }
+        element.removeAttribute("data-consent");
+      }
+    });
+  }
+
+  function choose(granted) {
+    localStorage.setItem(
+      KEY,
+      JSON.stringify({ categories: categories, granted: granted })
+    );
+    if (banner) {
+      banner.hidden = true;
+    }
+    activate(granted);
+  }
+
+  function boxes() {
+    return banner
+      ? banner.querySelectorAll('input[name="consent"]')
+      : [];
+  }
+
+  function on(selector, handler) {
+    var elements = document.querySelectorAll(selector);
+    Array.prototype.forEach.call(elements, function (element) {
+      element.addEventListener("click", handler);
+    });
+  }
+
+  on("[data-consent-accept]", function () {
+    choose(categories);
+  });
+  on("[data-consent-reject]", function () {
+    choose([]);
+  });
+  on("[data-consent-save]", function () {
+    var granted = [];
+    Array.prototype.forEach.call(boxes(), function (box) {
+      if (box.checked) {
+        granted.push(box.value);
+      }
+    });
+    choose(granted);
+  });
+  on("[data-consent-open]", function (event) {
+    event.preventDefault();
+    var granted = read() || [];
+    Array.prototype.forEach.call(boxes(), function (box) {
+      box.checked = granted.indexOf(box.value) >= 0;
+    });
+    if (banner) {
+      banner.hidden = false;
+    }
+  });
+
+  var granted = read();
+  if (granted) {
+    activate(granted);
+  } else if (banner) {
+    banner.hidden = false;
+  }
+})();
+"#;
+
+/// Matches a `<script>` or `<iframe>` opening tag.
+static TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)<(script|iframe)\b[^>]*>")
+        .unwrap_or_else(|e| panic!("Invalid tag regex: {}", e))
+});
+
+/// Matches the `src` attribute of a tag.
+static SRC_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\ssrc\s*=\s*(?:"([^"]*)"|'([^']*)')"#)
+        .unwrap_or_else(|e| panic!("Invalid src regex: {}", e))
+});
+
+/// Matches the `type` attribute of a tag.
+static TYPE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\stype\s*=\s*(?:"([^"]*)"|'([^']*)')"#)
+        .unwrap_or_else(|e| panic!("Invalid type regex: {}", e))
+});
+
+/// Matches a `data-consent` attribute.
+static GATED_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\sdata-consent\s*=")
+        .unwrap_or_else(|e| panic!("Invalid data-consent regex: {}", e))
+});
+
+/// Matches the closing `</body>` tag.
+static BODY_END_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)</body\s*>")
+        .unwrap_or_else(|e| panic!("Invalid body regex: {}", e))
+});
+
+/// A script or embed held back until its category is accepted.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct ConsentScript {
+    /// Consent category, e.g. `analytics`.
+    pub category: String,
+    /// Script URL injected into every page.
+    pub src: Option<String>,
+    /// Inline script body injected into every page.
+    pub inline: Option<String>,
+    /// Hosts whose scripts and iframes in the pages are gated. A
+    /// `*.example.com` entry matches the subdomains of `example.com`.
+    pub hosts: Vec<String>,
+}
+
+impl ConsentScript {
+    /// Returns whether tags loading from `host` belong to this script.
+    pub fn gates(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.src
+            .as_deref()
+            .and_then(url_host)
+            .map_or(false, |src_host| src_host == host)
+            || self.hosts.iter().any(|gated| {
+                let gated = gated.to_ascii_lowercase();
+                match gated.strip_prefix("*.") {
+                    Some(domain) => host
+                        .strip_suffix(domain)
+                        .map_or(false, |sub| sub.ends_with('.')),
+                    None => host == gated,
+                }
+            })
+    }
+}
+
+/// `[consent]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConsentOptions {
+    /// Gates the configured scripts and adds the banner.
+    pub enabled: bool,
+    /// Text of the banner.
+    pub message: String,
+    /// Link to the privacy policy shown in the banner.
+    pub privacy_url: Option<String>,
+    /// Label of the button accepting every category.
+    pub accept_label: String,
+    /// Label of the button rejecting every category.
+    pub reject_label: String,
+    /// Label of the button saving the selected categories.
+    pub save_label: String,
+    /// Gated scripts and embeds.
+    pub scripts: Vec<ConsentScript>,
+}
+
+impl Default for ConsentOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            message: "This site uses cookies for optional features. \
+                      Choose which ones to allow."
+                .to_string(),
+            privacy_url: None,
+            accept_label: "Accept all".to_string(),
+            reject_label: "Reject all".to_string(),
+            save_label: "Save choices".to_string(),
+            scripts: Vec::new(),
+        }
+    }
+}
+
+impl ConsentOptions {
+    /// Checks the categories and script URLs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid script.
+    pub fn validate(&self) -> Result<()> {
+        for script in &self.scripts {
+            ensure!(
+                !script.category.is_empty()
+                    && script.category.chars().all(|c| {
+                        c.is_ascii_lowercase()
+                            || c.is_ascii_digit()
+                            || c == '-'
+                    }),
+                "Invalid category `{}`: use lowercase letters, digits \
+                 and '-'",
+                script.category
+            );
+            ensure!(
+                !(script.src.is_some() && script.inline.is_some()),
+                "`{}` script has both `src` and `inline`",
+                script.category
+            );
+            ensure!(
+                script.src.is_some()
+                    || script.inline.is_some()
+                    || !script.hosts.is_empty(),
+                "`{}` script needs `src`, `inline` or `hosts`",
+                script.category
+            );
+            if let Some(src) = &script.src {
+                ensure!(
+                    src.starts_with("https://") || src.starts_with('/'),
+                    "`{}` script src `{}` must be https or site-relative",
+                    script.category,
+                    src
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the distinct categories in configuration order.
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories: Vec<&str> = Vec::new();
+        for script in &self.scripts {
+            if !categories.contains(&script.category.as_str()) {
+                categories.push(&script.category);
+            }
+        }
+        categories
+    }
+
+    fn category_for(&self, src: &str) -> Option<&str> {
+        let host = url_host(src)?;
+        self.scripts
+            .iter()
+            .find(|script| script.gates(&host))
+            .map(|script| script.category.as_str())
+    }
+}
+
+/// Returns the lowercased host of an absolute or protocol-relative URL.
+fn url_host(url: &str) -> Option<String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .or_else(|| url.strip_prefix("//"))?;
+    let authority = rest.split(&['/', '?', '#'][..]).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Renders the banner partial.
+pub fn banner_html(options: &ConsentOptions) -> String {
+    let mut html = String::from(
+        "<div class=\"ssg-consent\" data-consent-banner role=\"dialog\" \
+         aria-label=\"Cookie consent\" hidden>\n",
+    );
+    let _ = write!(html, "<p>{}", escape_html(&options.message));
+    if let Some(url) = &options.privacy_url {
+        let _ = write!(
+            html,
+            " <a href=\"{}\">Privacy policy</a>",
+            escape_html(url)
+        );
+    }
+    html.push_str("</p>\n");
+    for category in options.categories() {
+        let _ = writeln!(
+            html,
+            "<label><input type=\"checkbox\" name=\"consent\" \
+             value=\"{0}\"> {0}</label>",
+            escape_html(category)
+        );
+    }
+    for (attribute, label) in [
+        ("data-consent-accept", &options.accept_label),
+        ("data-consent-reject", &options.reject_label),
+        ("data-consent-save", &options.save_label),
+    ] {
+        let _ = writeln!(
+            html,
+            "<button type=\"button\" {}>{}</button>",
+            attribute,
+            escape_html(label)
+        );
+    }
+    html.push_str("</div>\n");
+    html
+}
+
+/// Makes the gated tags of a page inert and injects the configured
+/// scripts, the banner (unless the page has its own) and the loader.
+///
+/// Returns `None` for pages without `</body>` or already gated.
+pub fn gate_page(
+    html: &str,
+    options: &ConsentOptions,
+) -> Option<String> {
+    let loader = format!("src=\"/{}\"", LOADER_PATH);
+    if html.contains(&loader) {
+        return None;
+    }
+    let body_end = BODY_END_RE.find(html)?.start();
+
+    let mut page = TAG_RE
+        .replace_all(&html[..body_end], |caps: &regex::Captures<'_>| {
+            gate_tag(&caps[0], &caps[1], options)
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned();
+
+    for script in &options.scripts {
+        let category = escape_html(&script.category);
+        if let Some(src) = &script.src {
+            let _ = writeln!(
+                page,
+                "<script type=\"text/plain\" data-consent=\"{}\" \
+                 src=\"{}\" async></script>",
+                category,
+                escape_html(src)
+            );
+        } else if let Some(inline) = &script.inline {
+            let _ = writeln!(
+                page,
+                "<script type=\"text/plain\" data-consent=\"{}\">{}</script>",
+                category,
+                inline.replace("</script", "<\\/script")
+            );
+        }
+    }
+    if !page.contains("data-consent-banner") {
+        page.push_str(&banner_html(options));
+    }
+    let _ = writeln!(
+        page,
+        "<script {} data-categories=\"{}\"></script>",
+        loader,
+        escape_html(&options.categories().join(" "))
+    );
+    page.push_str(&html[body_end..]);
+    Some(page)
+}
+
+fn gate_tag(
+    tag: &str,
+    name: &str,
+    options: &ConsentOptions,
+) -> Option<String> {
+    if GATED_RE.is_match(tag) {
+        return None;
+    }
+    let src = SRC_RE.captures(tag)?;
+    let url = src.get(1).or_else(|| src.get(2))?.as_str();
+    let category = escape_html(options.category_for(url)?);
+
+    let (open, rest) = tag.split_at(1 + name.len());
+    if name.eq_ignore_ascii_case("iframe") {
+        let moved = format!(" data-consent-src=\"{}\"", url);
+        let rest = SRC_RE.replace(rest, regex::NoExpand(&moved));
+        return Some(format!(
+            "{} data-consent=\"{}\"{}",
+            open, category, rest
+        ));
+    }
+    let (rest, original_type) = match TYPE_RE.captures(rest) {
+        Some(caps) => {
+            let value = caps.get(1).or_else(|| caps.get(2))?.as_str();
+            let attribute = format!(" data-consent-type=\"{}\"", value);
+            (TYPE_RE.replace(rest, "").into_owned(), attribute)
+        }
+        None => (rest.to_string(), String::new()),
+    };
+    Some(format!(
+        "{} type=\"text/plain\" data-consent=\"{}\"{}{}",
+        open, category, original_type, rest
+    ))
+}
+
+/// Applies [`gate_page`] to every HTML file below `site_dir` and writes
+/// the loader, when consent mode is on.
+///
+/// Returns the number of pages that were gated.
+///
+/// # Errors
+///
+/// Returns an error if the options are invalid or a file cannot be read
+/// or written.
+pub fn gate_site(
+    site_dir: &Path,
+    options: &ConsentOptions,
+) -> Result<usize> {
+    if !options.enabled || !site_dir.exists() {
+        return Ok(0);
+    }
+    options.validate()?;
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+
+    let mut gated = 0;
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        if let Some(updated) = gate_page(&html, options) {
+            fs::write(&path, updated).with_context(|| {
+                format!("Failed to write {}", path.display())
+            })?;
+            gated += 1;
+        }
+    }
+
+    let loader = site_dir.join(LOADER_PATH);
+    fs::write(&loader, LOADER_JS).with_context(|| {
+        format!("Failed to write {}", loader.display())
+    })?;
+    Ok(gated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn options() -> ConsentOptions {
+        ConsentOptions {
+            enabled: true,
+            privacy_url: Some("/privacy/".into()),
+            scripts: vec![
+                ConsentScript {
+                    category: "analytics".into(),
+                    src: Some("https://stats.example.com/s.js".into()),
+                    ..ConsentScript::default()
+                },
+                ConsentScript {
+                    category: "embeds".into(),
+                    hosts: vec!["*.youtube.com".into()],
+                    ..ConsentScript::default()
+                },
+                ConsentScript {
+                    category: "analytics".into(),
+                    inline: Some("track('</script>')".into()),
+                    ..ConsentScript::default()
+                },
+            ],
+            ..ConsentOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_gate_page() {
+        let html = r#"<html><body>
+<script src="/app.js"></script>
+<script type="module" src="https://stats.example.com/s.js"></script>
+<iframe width="560" src="https://www.youtube.com/embed/x"></iframe>
+<iframe src="https://youtube.com/embed/y"></iframe>
+</body></html>"#;
+        let page = gate_page(html, &options()).unwrap();
+
+        assert!(page.contains("<script src=\"/app.js\"></script>"));
+        assert!(page.contains(
+            "<script type=\"text/plain\" data-consent=\"analytics\" \
+             data-consent-type=\"module\" src=\"https://stats.example.com/s.js\">"
+        ));
+        assert!(page.contains(
+            "<iframe data-consent=\"embeds\" width=\"560\" \
+             data-consent-src=\"https://www.youtube.com/embed/x\">"
+        ));
+        assert!(page
+            .contains("<iframe src=\"https://youtube.com/embed/y\">"));
+        assert!(page.contains(
+            "<script type=\"text/plain\" data-consent=\"analytics\">\
+             track('<\\/script>')</script>"
+        ));
+        assert!(page.contains("value=\"embeds\""));
+        assert!(
+            page.contains("<a href=\"/privacy/\">Privacy policy</a>")
+        );
+        assert!(page.ends_with(
+            "<script src=\"/consent.js\" data-categories=\"analytics embeds\"></script>\n</body></html>"
+        ));
+
+        // Gating is idempotent and pages without a body are left alone.
+        assert_eq!(gate_page(&page, &options()), None);
+        assert_eq!(gate_page("<p>fragment</p>", &options()), None);
+    }
+
+    #[test]
+    fn test_theme_banner_is_kept() {
+        let html =
+            "<body><div data-consent-banner hidden></div></body>";
+        let page = gate_page(html, &options()).unwrap();
+        assert_eq!(page.matches("data-consent-banner").count(), 1);
+    }
+
+    #[test]
+    fn test_gate_site() -> Result<()> {
+        let site = tempdir()?;
+        fs::write(site.path().join("index.html"), "<body></body>")?;
+        assert_eq!(
+            gate_site(site.path(), &ConsentOptions::default())?,
+            0
+        );
+        assert_eq!(gate_site(site.path(), &options())?, 1);
+        assert_eq!(
+            fs::read_to_string(site.path().join(LOADER_PATH))?,
+            LOADER_JS
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut options = options();
+        assert!(options.validate().is_ok());
+        options.scripts[0].src =
+            Some("http://stats.example.com".into());
+        assert!(options.validate().is_err());
+        options.scripts[0] = ConsentScript {
+            category: "Ads".into(),
+            hosts: vec!["ads.example.com".into()],
+            ..ConsentScript::default()
+        };
+        assert!(options.validate().is_err());
+    }
+}
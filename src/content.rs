@@ -0,0 +1,559 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Content Model
+//!
+//! Loads the Markdown files of a content directory into [`Page`] values with
+//! parsed frontmatter and their output location. Generators, plugins and
+//! auxiliary outputs work from a [`ContentSet`] instead of re-walking the
+//! filesystem.
+//!
+//! Output paths follow the compiler's layout: `index.md` becomes
+//! `index.html`, and any other `name.md` becomes `name/index.html`. A
+//! `url_path` frontmatter key, set by hand or from a
+//! [permalink template](crate::permalinks), moves the page elsewhere:
+//! `url_path: /2025/01/hello/` is written to `2025/01/hello/index.html`.
+//!
+//! A `route` frontmatter key overrides all of the above with the exact
+//! output path, for files such as `/.well-known/security.txt` or legacy
+//! URLs that must match to the letter. Routes are never rewritten by a
+//! permalink template, and a route clashing with another page's output
+//! fails the build.
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    frontmatter::Frontmatter,
+    query::Query,
+    vfs::{RealFs, Vfs},
+};
+
+/// Frontmatter key moving a page to another site-relative URL.
+pub const URL_PATH_KEY: &str = "url_path";
+
+/// Frontmatter key setting the exact output path of a page.
+pub const ROUTE_KEY: &str = "route";
+
+/// Frontmatter key marking a page as a draft.
+pub const DRAFT_KEY: &str = "draft";
+
+/// A single Markdown page from the content directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    /// Path of the source file.
+    pub source: PathBuf,
+    /// Path of the source file relative to the content directory.
+    pub relative: PathBuf,
+    /// Parsed frontmatter.
+    pub frontmatter: Frontmatter,
+    /// Markdown body without the frontmatter block.
+    pub body: String,
+}
+
+impl Page {
+    /// Parses a page from its raw content.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frontmatter is malformed. YAML errors are
+    /// reported as a [`Diagnostic`](crate::diagnostics::Diagnostic)
+    /// pointing at the offending line.
+    pub fn from_source(
+        source: PathBuf,
+        relative: PathBuf,
+        raw: &str,
+    ) -> Result<Self> {
+        let (frontmatter, body) = match crate::frontmatter::parse(raw) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                return Err(
+                    match e.downcast_ref::<serde_yaml::Error>() {
+                        Some(yaml) => anyhow::Error::new(
+                            crate::diagnostics::frontmatter_error(
+                                &source, raw, yaml,
+                            ),
+                        ),
+                        None => e.context(format!(
+                            "Invalid frontmatter in {}",
+                            source.display()
+                        )),
+                    },
+                )
+            }
+        };
+
+        Ok(Self {
+            source,
+            relative,
+            frontmatter,
+            body: body.to_string(),
+        })
+    }
+
+    /// Returns the page title, falling back to the file stem.
+    pub fn title(&self) -> String {
+        self.frontmatter.get_str("title").unwrap_or_else(|| {
+            self.relative
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Returns the page description, if any.
+    pub fn description(&self) -> Option<String> {
+        self.frontmatter.get_str("description")
+    }
+
+    /// Returns `true` if the page is marked `draft: true`.
+    pub fn is_draft(&self) -> bool {
+        self.frontmatter.get_bool(DRAFT_KEY) == Some(true)
+    }
+
+    /// Returns the first directory of the page's relative path, e.g.
+    /// `blog` for `blog/hello.md`; `None` for top-level pages.
+    pub fn section(&self) -> Option<String> {
+        let mut components = self.relative.components();
+        let first = components.next()?;
+        let _ = components.next()?;
+        Some(first.as_os_str().to_string_lossy().into_owned())
+    }
+
+    /// Returns the `url_path` frontmatter value if it is a safe
+    /// site-relative path such as `/blog/post/` or `/about.html`.
+    pub fn custom_url(&self) -> Option<String> {
+        safe_url(self.frontmatter.get_str(URL_PATH_KEY)?)
+    }
+
+    /// Returns the `route` frontmatter value if it is a safe
+    /// site-relative path such as `/.well-known/security.txt`.
+    pub fn route(&self) -> Option<String> {
+        safe_url(self.frontmatter.get_str(ROUTE_KEY)?)
+    }
+
+    /// Returns the output file path relative to the site directory.
+    pub fn output_path(&self) -> PathBuf {
+        match self.route() {
+            Some(route) => url_output_path(&route),
+            None => self.computed_output_path(),
+        }
+    }
+
+    /// Returns the output file path the page has without its `route`,
+    /// which is where the compiler writes it.
+    pub fn computed_output_path(&self) -> PathBuf {
+        if let Some(url) = self.custom_url() {
+            return url_output_path(&url);
+        }
+        let parent = self.relative.parent().unwrap_or(Path::new(""));
+        match self.relative.file_stem().and_then(|s| s.to_str()) {
+            Some("index") | None => parent.join("index.html"),
+            Some(stem) => parent.join(stem).join("index.html"),
+        }
+    }
+
+    /// Returns the site-relative URL of the page, e.g. `/blog/post/`.
+    pub fn url_path(&self) -> String {
+        if let Some(url) = self.route().or_else(|| self.custom_url()) {
+            return url;
+        }
+        let mut url = String::from("/");
+        if let Some(dir) = self.output_path().parent() {
+            for component in dir.components() {
+                url.push_str(&component.as_os_str().to_string_lossy());
+                url.push('/');
+            }
+        }
+        url
+    }
+}
+
+/// Returns `url` if it is a site-relative path without empty, `.` or
+/// `..` segments.
+fn safe_url(url: String) -> Option<String> {
+    let path = url.strip_prefix('/')?;
+    if url.contains("//")
+        || path.contains('\\')
+        || path.split('/').any(|segment| matches!(segment, "." | ".."))
+    {
+        return None;
+    }
+    Some(url)
+}
+
+/// Returns the output file of a site-relative URL: `index.html` in the
+/// directory for URLs ending in `/`, the named file otherwise.
+fn url_output_path(url: &str) -> PathBuf {
+    let path = PathBuf::from(url.trim_start_matches('/'));
+    if url.ends_with('/') {
+        path.join("index.html")
+    } else {
+        path
+    }
+}
+
+/// All pages of a content directory.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContentSet {
+    /// The content directory the pages were loaded from.
+    pub root: PathBuf,
+    /// Pages sorted by their relative path.
+    pub pages: Vec<Page>,
+}
+
+impl ContentSet {
+    /// Loads every Markdown file below `root`.
+    ///
+    /// Files are parsed in parallel; the resulting pages are sorted by path
+    /// so downstream outputs are deterministic.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read or a page has
+    /// malformed frontmatter.
+    pub fn load(root: &Path) -> Result<Self> {
+        Self::load_from(&RealFs, root)
+    }
+
+    /// Loads every Markdown file below `root` in the file system `vfs`,
+    /// like [`load`](Self::load).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read or a page has
+    /// malformed frontmatter.
+    pub fn load_from(vfs: &dyn Vfs, root: &Path) -> Result<Self> {
+        let mut files = Vec::new();
+        if vfs.exists(root) {
+            files = vfs.files(root).with_context(|| {
+                format!("Failed to read content in {}", root.display())
+            })?;
+        }
+        files.retain(|path| is_markdown(path));
+
+        let mut pages = files
+            .into_par_iter()
+            .map(|source| {
+                let raw = vfs.read_to_string(&source)?;
+                let relative = source
+                    .strip_prefix(root)
+                    .unwrap_or(&source)
+                    .to_path_buf();
+                Page::from_source(source, relative, &raw)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        pages.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            pages,
+        })
+    }
+
+    /// Loads every Markdown file below `root` like [`load`](Self::load),
+    /// with tokio file IO: the directory is walked asynchronously and the
+    /// files are read and parsed in concurrent tasks.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read, a page has
+    /// malformed frontmatter, or a load task panics.
+    #[cfg(feature = "async")]
+    pub async fn load_async(root: &Path) -> Result<Self> {
+        let mut files = Vec::new();
+        let mut dirs = Vec::new();
+        if tokio::fs::metadata(root).await.is_ok() {
+            dirs.push(root.to_path_buf());
+        }
+        let rules = crate::ignore::IgnoreRules::for_root(root)?;
+        while let Some(dir) = dirs.pop() {
+            let mut entries =
+                tokio::fs::read_dir(&dir).await.with_context(|| {
+                    format!(
+                        "Failed to read content in {}",
+                        dir.display()
+                    )
+                })?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                let is_dir = entry.file_type().await?.is_dir();
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                if rules.is_ignored(relative, is_dir) {
+                    continue;
+                } else if is_dir {
+                    dirs.push(path);
+                } else if is_markdown(&path) {
+                    files.push(path);
+                }
+            }
+        }
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for source in files {
+            let relative = source
+                .strip_prefix(root)
+                .unwrap_or(&source)
+                .to_path_buf();
+            let _ = tasks.spawn(async move {
+                let raw = tokio::fs::read_to_string(&source)
+                    .await
+                    .with_context(|| {
+                        format!("Failed to read {}", source.display())
+                    })?;
+                Page::from_source(source, relative, &raw)
+            });
+        }
+        let mut pages = Vec::new();
+        while let Some(page) = tasks.join_next().await {
+            pages.push(page.context("A content load task panicked")??);
+        }
+        pages.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            pages,
+        })
+    }
+
+    /// Returns the number of pages.
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Returns `true` if there are no pages.
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    /// Iterates over the pages in path order.
+    pub fn iter(&self) -> impl Iterator<Item = &Page> {
+        self.pages.iter()
+    }
+
+    /// Removes the pages marked `draft: true`, returning how many were
+    /// removed.
+    pub fn remove_drafts(&mut self) -> usize {
+        let before = self.pages.len();
+        self.pages.retain(|page| !page.is_draft());
+        before - self.pages.len()
+    }
+
+    /// Starts a [`Query`] selecting pages by frontmatter predicates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::content::ContentSet;
+    ///
+    /// let content = ContentSet::default();
+    /// assert!(content.query().where_eq("section", "blog").pages().is_empty());
+    /// ```
+    pub fn query(&self) -> Query<'_> {
+        Query::new(self)
+    }
+}
+
+/// Returns `true` if the path has a Markdown extension.
+pub fn is_markdown(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("md") | Some("markdown")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_output_path_and_url() -> Result<()> {
+        let index = Page::from_source(
+            PathBuf::from("content/index.md"),
+            PathBuf::from("index.md"),
+            "---\ntitle: Home\n---\n",
+        )?;
+        assert_eq!(index.output_path(), PathBuf::from("index.html"));
+        assert_eq!(index.url_path(), "/");
+
+        let nested = Page::from_source(
+            PathBuf::from("content/blog/hello.md"),
+            PathBuf::from("blog/hello.md"),
+            "Hello",
+        )?;
+        assert_eq!(
+            nested.output_path(),
+            PathBuf::from("blog/hello/index.html")
+        );
+        assert_eq!(nested.url_path(), "/blog/hello/");
+        assert_eq!(nested.title(), "hello");
+        assert_eq!(nested.section().as_deref(), Some("blog"));
+        assert_eq!(index.section(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_drafts() -> Result<()> {
+        let page = |relative: &str, source: &str| {
+            Page::from_source(
+                PathBuf::from("content").join(relative),
+                PathBuf::from(relative),
+                source,
+            )
+        };
+        let mut content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page(
+                    "index.md",
+                    "---
+title: Home
+---
+",
+                )?,
+                page(
+                    "draft.md",
+                    "---
+title: Draft
+draft: true
+---
+",
+                )?,
+            ],
+        };
+        assert!(content.pages[1].is_draft());
+        assert_eq!(content.remove_drafts(), 1);
+        assert_eq!(content.len(), 1);
+        assert!(!content.pages[0].is_draft());
+        Ok(())
+    }
+
+    #[test]
+    fn test_url_path_frontmatter_moves_page() -> Result<()> {
+        let page = |url: &str| {
+            Page::from_source(
+                PathBuf::from("content/blog/hello.md"),
+                PathBuf::from("blog/hello.md"),
+                &format!("---\nurl_path: \"{}\"\n---\n", url),
+            )
+        };
+        let moved = page("/2025/01/hello/")?;
+        assert_eq!(
+            moved.output_path(),
+            PathBuf::from("2025/01/hello/index.html")
+        );
+        assert_eq!(moved.url_path(), "/2025/01/hello/");
+        assert_eq!(
+            page("/hello.html")?.output_path(),
+            PathBuf::from("hello.html")
+        );
+
+        for unsafe_url in ["../../etc/", "/a/../../b/", "//host/", "x/"]
+        {
+            assert_eq!(page(unsafe_url)?.custom_url(), None);
+            assert_eq!(page(unsafe_url)?.url_path(), "/blog/hello/");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_route_frontmatter_sets_exact_output() -> Result<()> {
+        let page = |frontmatter: &str| {
+            Page::from_source(
+                PathBuf::from("content/security.md"),
+                PathBuf::from("security.md"),
+                &format!("---\n{}\n---\n", frontmatter),
+            )
+        };
+        let routed = page(
+            "route: /.well-known/security.txt\nurl_path: /security/",
+        )?;
+        assert_eq!(
+            routed.output_path(),
+            PathBuf::from(".well-known/security.txt")
+        );
+        assert_eq!(routed.url_path(), "/.well-known/security.txt");
+        assert_eq!(
+            routed.computed_output_path(),
+            PathBuf::from("security/index.html")
+        );
+
+        let unsafe_route = page("route: /../security.txt")?;
+        assert_eq!(unsafe_route.route(), None);
+        assert_eq!(unsafe_route.url_path(), "/security/");
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_set_load_sorted() -> Result<()> {
+        let temp_dir = tempdir()?;
+        fs::create_dir_all(temp_dir.path().join("blog"))?;
+        fs::write(
+            temp_dir.path().join("blog/b.md"),
+            "---\ntitle: B\n---\nB",
+        )?;
+        fs::write(
+            temp_dir.path().join("a.md"),
+            "---\ntitle: A\n---\nA",
+        )?;
+        fs::write(temp_dir.path().join("logo.png"), [0u8, 1, 2])?;
+
+        let set = ContentSet::load(temp_dir.path())?;
+        let titles: Vec<_> = set.iter().map(Page::title).collect();
+        assert_eq!(titles, vec!["A", "B"]);
+        assert_eq!(set.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_set_skips_ignored_files() -> Result<()> {
+        let temp_dir = tempdir()?;
+        fs::create_dir_all(temp_dir.path().join("notes"))?;
+        fs::write(
+            temp_dir.path().join(".ssgignore"),
+            "/notes/
+",
+        )?;
+        fs::write(temp_dir.path().join("notes/todo.md"), "Todo")?;
+        fs::write(temp_dir.path().join("index.md"), "Home")?;
+
+        let set = ContentSet::load(temp_dir.path())?;
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.pages[0].relative, Path::new("index.md"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_set_invalid_frontmatter() -> Result<()> {
+        let temp_dir = tempdir()?;
+        fs::write(temp_dir.path().join("bad.md"), "---\n[oops\n---\n")?;
+        assert!(ContentSet::load(temp_dir.path()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_set_missing_root() -> Result<()> {
+        let set = ContentSet::load(Path::new("does/not/exist"))?;
+        assert!(set.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_set_load_from_memory() -> Result<()> {
+        let fs = crate::vfs::MemoryFs::new()
+            .with_file("content/blog/b.md", "---\ntitle: B\n---\nB")
+            .with_file("content/a.md", "---\ntitle: A\n---\nA")
+            .with_file("content/logo.png", [0u8, 1, 2].to_vec());
+
+        let set = ContentSet::load_from(&fs, Path::new("content"))?;
+        let titles: Vec<_> = set.iter().map(Page::title).collect();
+        assert_eq!(titles, vec!["A", "B"]);
+        assert_eq!(set.pages[1].relative, PathBuf::from("blog/b.md"));
+        assert!(ContentSet::load_from(&fs, Path::new("missing"))?
+            .is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,422 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Non-Markdown Content Files
+//!
+//! Decides what happens to files in the content directory that are not
+//! Markdown, such as images dropped next to a post or a stray PDF. The
+//! `[non_content]` table of the configuration file picks a policy:
+//!
+//! ```toml
+//! [non_content]
+//! policy = "passthrough"   # or "warn" (the default) or "error"
+//! ignore = ["desktop.ini"]
+//! ```
+//!
+//! | Policy | Effect |
+//! |--------|--------|
+//! | `passthrough` | Copied to the site at the same relative path |
+//! | `warn` | Left out of the site, with a warning per file |
+//! | `error` | The build fails, listing the files |
+//!
+//! Hidden files such as `.gitkeep` and names listed in `ignore` are
+//! skipped silently. Whenever other files are found, the core compile
+//! only sees the Markdown files, so a binary file in the content tree
+//! cannot make it fail.
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write as _,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    collect_files_recursive, content::is_markdown, diagnostics,
+    file_limits::FileLimits, frontmatter,
+};
+
+/// Bytes read from a file to tell text from binary.
+const SNIFF_BYTES: u64 = 8192;
+
+/// What happens to the non-Markdown files of the content directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NonContentPolicy {
+    /// The files are copied to the site as they are.
+    Passthrough,
+    /// The files are left out, with a warning each.
+    Warn,
+    /// The build fails.
+    Error,
+}
+
+impl Default for NonContentPolicy {
+    fn default() -> Self {
+        Self::Warn
+    }
+}
+
+/// Options of the `[non_content]` config table.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct NonContentOptions {
+    /// What happens to the files.
+    pub policy: NonContentPolicy,
+    /// File names skipped silently, like hidden files.
+    pub ignore: Vec<String>,
+}
+
+impl NonContentOptions {
+    /// Checks that `ignore` lists plain file names.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first entry that is empty or has a
+    /// path separator.
+    pub fn validate(&self) -> Result<()> {
+        for name in &self.ignore {
+            ensure!(
+                !name.is_empty() && !name.contains(['/', '\\']),
+                "ignore entry `{}` must be a file name",
+                name
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the file at `path` is skipped silently.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        path.file_name().and_then(|name| name.to_str()).map_or(
+            false,
+            |name| {
+                name.starts_with('.')
+                    || self.ignore.iter().any(|ignored| ignored == name)
+            },
+        )
+    }
+}
+
+/// A file of the content directory that is not Markdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonContentFile {
+    /// Path relative to the content directory.
+    pub relative: PathBuf,
+    /// Size in bytes.
+    pub size: u64,
+    /// Whether the file looks binary rather than UTF-8 text.
+    pub binary: bool,
+}
+
+/// Returns the non-Markdown files below `root` that are not ignored, in
+/// path order. A missing directory has none.
+///
+/// # Errors
+///
+/// Returns an error if the directory or a file cannot be read.
+pub fn scan(
+    root: &Path,
+    options: &NonContentOptions,
+) -> Result<Vec<NonContentFile>> {
+    let mut paths = Vec::new();
+    if root.exists() {
+        collect_files_recursive(root, &mut paths).with_context(
+            || format!("Failed to read content in {}", root.display()),
+        )?;
+    }
+    paths
+        .retain(|path| !is_markdown(path) && !options.is_ignored(path));
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let size = fs::metadata(&path)
+                .with_context(|| {
+                    format!("Failed to read {}", path.display())
+                })?
+                .len();
+            Ok(NonContentFile {
+                binary: is_binary(&path)?,
+                relative: path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .to_path_buf(),
+                size,
+            })
+        })
+        .collect()
+}
+
+/// Fails under the `error` policy if there are any `files`.
+///
+/// # Errors
+///
+/// Returns an error listing the files.
+pub fn check(
+    files: &[NonContentFile],
+    options: &NonContentOptions,
+) -> Result<()> {
+    if options.policy == NonContentPolicy::Error && !files.is_empty() {
+        bail!(
+            "{}Set `policy` in `[non_content]` to \"passthrough\" or \"warn\", or move the files out of the content directory",
+            summary(files)
+        );
+    }
+    Ok(())
+}
+
+/// Copies `files` from `root` to `site_dir` under the `passthrough`
+/// policy, streaming those over the
+/// [`stream_bytes`](FileLimits::stream_bytes) of `limits`, or warns
+/// about each one under `warn`. Returns the number of files copied.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be copied.
+pub fn apply(
+    files: &[NonContentFile],
+    root: &Path,
+    site_dir: &Path,
+    options: &NonContentOptions,
+    limits: &FileLimits,
+) -> Result<usize> {
+    match options.policy {
+        NonContentPolicy::Passthrough => {
+            for file in files {
+                let target = site_dir.join(&file.relative);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create {}", parent.display())
+                    })?;
+                }
+                let _ = limits
+                    .copy_file(&root.join(&file.relative), &target)
+                    .with_context(|| {
+                        format!("Failed to write {}", target.display())
+                    })?;
+            }
+            Ok(files.len())
+        }
+        NonContentPolicy::Warn => {
+            for file in files {
+                diagnostics::warn(format!(
+                    "Skipping {}: not a Markdown file",
+                    file.relative.display()
+                ));
+            }
+            Ok(0)
+        }
+        NonContentPolicy::Error => Ok(0),
+    }
+}
+
+/// Copies the Markdown files below `root` to `dest`, keeping their
+/// relative paths, so the core compile can run on them alone. Returns
+/// the number of files copied.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written.
+pub fn stage_markdown(root: &Path, dest: &Path) -> Result<usize> {
+    let mut paths = Vec::new();
+    collect_files_recursive(root, &mut paths).with_context(|| {
+        format!("Failed to read content in {}", root.display())
+    })?;
+    paths.retain(|path| is_markdown(path));
+    for path in &paths {
+        let target = dest.join(path.strip_prefix(root).unwrap_or(path));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        let _ = fs::copy(path, &target).with_context(|| {
+            format!("Failed to write {}", target.display())
+        })?;
+    }
+    Ok(paths.len())
+}
+
+/// Applies `f` to the body of every Markdown file below `dir` in place,
+/// leaving frontmatter alone. Used on the staged copy of the content.
+///
+/// Returns the number of files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written.
+pub(crate) fn rewrite_bodies(
+    dir: &Path,
+    f: impl Fn(&str) -> String,
+) -> Result<usize> {
+    let mut files = Vec::new();
+    collect_files_recursive(dir, &mut files)
+        .with_context(|| format!("Failed to read {}", dir.display()))?;
+
+    let mut rewritten = 0;
+    for path in files.iter().filter(|path| is_markdown(path)) {
+        let source = fs::read_to_string(path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let (_, body) = frontmatter::split(&source);
+        let head = &source[..source.len() - body.len()];
+        let mapped = format!("{}{}", head, f(body));
+        if mapped == source {
+            continue;
+        }
+        fs::write(path, mapped).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+/// Lists `files` with their kind and size, one per line, under a
+/// heading with their count.
+pub fn summary(files: &[NonContentFile]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{} non-Markdown file{} in the content directory:",
+        files.len(),
+        if files.len() == 1 { "" } else { "s" }
+    );
+    for file in files {
+        let _ = writeln!(
+            out,
+            "  {} ({}, {} bytes)",
+            file.relative.display(),
+            if file.binary { "binary" } else { "text" },
+            file.size
+        );
+    }
+    out
+}
+
+/// Returns `true` if the start of the file has a NUL byte or is not
+/// UTF-8.
+fn is_binary(path: &Path) -> Result<bool> {
+    let mut head = Vec::new();
+    let _ = File::open(path)
+        .and_then(|file| file.take(SNIFF_BYTES).read_to_end(&mut head))
+        .with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+    if head.contains(&0) {
+        return Ok(true);
+    }
+    Ok(match std::str::from_utf8(&head) {
+        Ok(_) => false,
+        // A multi-byte character cut off by the sniff limit is text.
+        Err(e) => e.error_len().is_some(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn content() -> Result<tempfile::TempDir> {
+        let dir = tempdir()?;
+        fs::create_dir_all(dir.path().join("blog"))?;
+        fs::write(dir.path().join("index.md"), "# Home")?;
+        fs::write(dir.path().join("blog/post.md"), "# Post")?;
+        fs::write(dir.path().join("blog/photo.png"), [0x89, b'P', 0])?;
+        fs::write(dir.path().join("notes.txt"), "todo")?;
+        fs::write(dir.path().join(".gitkeep"), "")?;
+        fs::write(dir.path().join("desktop.ini"), [0u8; 4])?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn test_scan_lists_non_markdown_files() -> Result<()> {
+        let dir = content()?;
+        let options = NonContentOptions {
+            ignore: vec!["desktop.ini".into()],
+            ..NonContentOptions::default()
+        };
+        let files = scan(dir.path(), &options)?;
+        assert_eq!(
+            files,
+            [
+                NonContentFile {
+                    relative: PathBuf::from("blog/photo.png"),
+                    size: 3,
+                    binary: true,
+                },
+                NonContentFile {
+                    relative: PathBuf::from("notes.txt"),
+                    size: 4,
+                    binary: false,
+                },
+            ]
+        );
+        assert_eq!(
+            summary(&files),
+            "2 non-Markdown files in the content directory:\n  \
+             blog/photo.png (binary, 3 bytes)\n  \
+             notes.txt (text, 4 bytes)\n"
+        );
+        assert!(scan(&dir.path().join("missing"), &options)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_policies() -> Result<()> {
+        let dir = content()?;
+        let site = tempdir()?;
+        let mut options = NonContentOptions::default();
+        let files = scan(dir.path(), &options)?;
+        assert_eq!(files.len(), 3);
+        let limits = FileLimits {
+            stream_bytes: 1,
+            ..FileLimits::default()
+        };
+
+        check(&files, &options)?;
+        assert_eq!(
+            apply(&files, dir.path(), site.path(), &options, &limits)?,
+            0
+        );
+        assert!(!site.path().join("notes.txt").exists());
+
+        options.policy = NonContentPolicy::Passthrough;
+        assert_eq!(
+            apply(&files, dir.path(), site.path(), &options, &limits)?,
+            3
+        );
+        assert_eq!(
+            fs::read_to_string(site.path().join("notes.txt"))?,
+            "todo"
+        );
+
+        options.policy = NonContentPolicy::Error;
+        let error = check(&files, &options).unwrap_err();
+        assert!(error.to_string().contains("  blog/photo.png (binary"));
+        check(&[], &options)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_markdown_and_validate() -> Result<()> {
+        let dir = content()?;
+        let staged = tempdir()?;
+        assert_eq!(stage_markdown(dir.path(), staged.path())?, 2);
+        assert!(staged.path().join("blog/post.md").is_file());
+        assert!(!staged.path().join("blog/photo.png").exists());
+
+        let options = NonContentOptions {
+            ignore: vec!["blog/photo.png".into()],
+            ..NonContentOptions::default()
+        };
+        assert!(options.validate().is_err());
+        Ok(())
+    }
+}
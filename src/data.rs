@@ -0,0 +1,281 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Data Files
+//!
+//! Loads the files of the `data/` directory and exposes them to
+//! templates as `data.<filename>`, for menus, team lists or product
+//! catalogs that are not pages:
+//!
+//! ```text
+//! <a href="{{ data.menu.main.0.url }}">{{ data.menu.main.0.title }}</a>
+//! <p>{{ data.team | length }} people</p>
+//! ```
+//!
+//! A file is named after its stem, and subdirectories nest, so
+//! `data/shop/products.csv` is `data.shop.products`. Four formats are
+//! read:
+//!
+//! | Extension | Value |
+//! |-----------|-------|
+//! | `.yml`, `.yaml` | The YAML document |
+//! | `.toml` | The TOML table |
+//! | `.json` | The JSON document |
+//! | `.csv` | A list of objects keyed by the header row, with string values |
+//!
+//! Other files are ignored. Two files with the same stem in one
+//! directory, such as `team.yml` and `team.json`, are an error.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{collect_files_recursive, template::Engine};
+
+/// Default data directory, relative to the project root.
+pub const DATA_DIR: &str = "data";
+
+/// Name of the template variable holding the data.
+pub const DATA_VARIABLE: &str = "data";
+
+/// The parsed data files of a site, by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SiteData {
+    values: Map<String, Value>,
+}
+
+impl SiteData {
+    /// Loads the data files below `dir`. A missing directory gives no
+    /// data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read or parsed, or two files
+    /// share a name.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut data = Self::default();
+        if !dir.exists() {
+            return Ok(data);
+        }
+        let mut files = Vec::new();
+        collect_files_recursive(dir, &mut files).with_context(
+            || format!("Failed to read {}", dir.display()),
+        )?;
+        files.sort();
+
+        for path in files {
+            let format = match Format::of(&path) {
+                Some(format) => format,
+                None => continue,
+            };
+            let source =
+                fs::read_to_string(&path).with_context(|| {
+                    format!("Failed to read {}", path.display())
+                })?;
+            let value = format.parse(&source).with_context(|| {
+                format!("Invalid data file {}", path.display())
+            })?;
+            data.insert(&data_key(dir, &path), value, &path)?;
+        }
+        Ok(data)
+    }
+
+    /// Returns `true` if no data file was loaded.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the value at a dotted path such as `team.0.name`.
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        let mut parts = path.split('.');
+        let first = self.values.get(parts.next()?)?;
+        parts.try_fold(first, |value, key| match value {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => {
+                key.parse::<usize>().ok().and_then(|i| items.get(i))
+            }
+            _ => None,
+        })
+    }
+
+    /// Returns the data as a JSON object.
+    pub fn to_json(&self) -> Value {
+        Value::Object(self.values.clone())
+    }
+
+    /// Makes the data available to templates rendered by `engine` as
+    /// `data`.
+    pub fn register(&self, engine: &mut Engine) {
+        engine.set_global(DATA_VARIABLE, self.to_json());
+    }
+
+    /// Inserts `value` at `key`, creating the objects of its
+    /// directories.
+    fn insert(
+        &mut self,
+        key: &[String],
+        value: Value,
+        path: &Path,
+    ) -> Result<()> {
+        let (name, dirs) = match key.split_last() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+        let mut map = &mut self.values;
+        for dir in dirs {
+            let entry = map
+                .entry(dir.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            map = match entry {
+                Value::Object(map) => map,
+                _ => bail!(
+                    "{} clashes with the data file `{}`",
+                    path.display(),
+                    dir
+                ),
+            };
+        }
+        if map.contains_key(name) {
+            bail!(
+                "{} clashes with another data file named `{}`",
+                path.display(),
+                name
+            );
+        }
+        let _ = map.insert(name.clone(), value);
+        Ok(())
+    }
+}
+
+/// A data file format, by extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Yaml,
+    Toml,
+    Json,
+    Csv,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "yml" | "yaml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    fn parse(self, source: &str) -> Result<Value> {
+        Ok(match self {
+            Self::Yaml => serde_yaml::from_str(source)?,
+            Self::Toml => toml::from_str(source)?,
+            Self::Json => serde_json::from_str(source)?,
+            Self::Csv => parse_csv(source)?,
+        })
+    }
+}
+
+/// Parses CSV with a header row into a list of objects.
+fn parse_csv(source: &str) -> Result<Value> {
+    let mut reader = csv::Reader::from_reader(source.as_bytes());
+    let headers = reader.headers()?.clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let row: Map<String, Value> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(header, field)| {
+                (header.to_string(), Value::String(field.to_string()))
+            })
+            .collect();
+        rows.push(Value::Object(row));
+    }
+    Ok(Value::Array(rows))
+}
+
+/// Returns the key of the data file at `path`: its directories below
+/// `dir`, then its stem.
+fn data_key(dir: &Path, path: &Path) -> Vec<String> {
+    let relative: PathBuf =
+        path.strip_prefix(dir).unwrap_or(path).with_extension("");
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_formats() -> Result<()> {
+        let dir = tempdir()?;
+        fs::create_dir_all(dir.path().join("shop"))?;
+        fs::write(
+            dir.path().join("menu.yml"),
+            "main:\n  - title: Home\n    url: /\n",
+        )?;
+        fs::write(
+            dir.path().join("site.toml"),
+            "name = \"Shokunin\"\nyear = 2025\n",
+        )?;
+        fs::write(
+            dir.path().join("team.json"),
+            r#"[{"name": "Ada"}]"#,
+        )?;
+        fs::write(
+            dir.path().join("shop/products.csv"),
+            "sku,price\nA1,9.99\nB2,\"1,299\"\n",
+        )?;
+        fs::write(dir.path().join("notes.txt"), "ignored")?;
+
+        let data = SiteData::load(dir.path())?;
+        assert_eq!(data.get("menu.main.0.url"), Some(&json!("/")));
+        assert_eq!(data.get("site.year"), Some(&json!(2025)));
+        assert_eq!(data.get("team.0.name"), Some(&json!("Ada")));
+        assert_eq!(
+            data.get("shop.products"),
+            Some(&json!([
+                {"sku": "A1", "price": "9.99"},
+                {"sku": "B2", "price": "1,299"}
+            ]))
+        );
+        assert_eq!(data.get("notes"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_rejects_clashing_names() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("team.json"), "[]")?;
+        fs::write(dir.path().join("team.yml"), "[]")?;
+        let error = SiteData::load(dir.path()).unwrap_err();
+        assert!(error.to_string().contains("`team`"));
+
+        let missing = SiteData::load(&dir.path().join("missing"))?;
+        assert!(missing.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_register_exposes_data() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("site.yml"), "name: Shokunin")?;
+        let mut engine = Engine::new();
+        SiteData::load(dir.path())?.register(&mut engine);
+        assert_eq!(
+            engine.render("{{ data.site.name }}", &json!({}))?,
+            "Shokunin"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,578 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Page Dates
+//!
+//! Gives templates ready-to-use publication and update dates, so themes
+//! do not each parse and format frontmatter dates their own way. Every
+//! page rendered on the [pipeline](crate::pipeline) gets a `page` context
+//! value:
+//!
+//! ```text
+//! page.published.iso        2025-03-12
+//! page.published.formatted  12 March 2025
+//! page.published.relative   3 days ago
+//! page.updated.iso          ...
+//! ```
+//!
+//! `published` comes from the `published` or `date` frontmatter.
+//! `updated` comes from the `updated` or `lastmod` frontmatter, then from
+//! the date of the last git commit touching the page, then falls back to
+//! `published`. Either is `null` when nothing provides it.
+//!
+//! Dates are formatted in the page's language (the `language` or `lang`
+//! frontmatter, or the site language). English, French, German and
+//! Spanish have their own month names and relative times; other
+//! languages use English.
+//!
+//! [`Date::parse`] accepts ISO 8601 dates (`2025-03-12`,
+//! `2025-03-12T09:00:00Z`) and dates with English month names
+//! (`March 12, 2025`, `Wed, 12 Mar 2025 09:00:00 +0000`).
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::content::{ContentSet, Page};
+
+/// Language used for pages and sites without a supported one.
+pub const DEFAULT_DATE_LANGUAGE: &str = "en";
+
+/// Matches an ISO 8601 date, e.g. `2025-01-31` or `2025-01-31T09:00`.
+static ISO_DATE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(\d{4})-(\d{2})-(\d{2})")
+        .unwrap_or_else(|e| panic!("Invalid ISO date regex: {}", e))
+});
+
+/// Matches a date with an English month name, e.g. `January 31, 2025`
+/// or `Fri, 31 Jan 2025 09:00:00 +0000`.
+static NAMED_DATE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\b(?:(\d{1,2})\s+([a-z]{3,9})\.?|([a-z]{3,9})\.?\s+(\d{1,2}),?)\s+(\d{4})\b",
+    )
+    .unwrap_or_else(|e| panic!("Invalid named date regex: {}", e))
+});
+
+/// A calendar date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    /// Year, e.g. `2025`.
+    pub year: i32,
+    /// Month from 1 to 12.
+    pub month: u32,
+    /// Day of the month from 1 to 31.
+    pub day: u32,
+}
+
+impl Date {
+    /// Parses a frontmatter date.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::dates::Date;
+    ///
+    /// let date = Date { year: 2025, month: 1, day: 31 };
+    /// assert_eq!(Date::parse("2025-01-31T09:00:00Z"), Some(date));
+    /// assert_eq!(Date::parse("January 31, 2025"), Some(date));
+    /// assert_eq!(Date::parse("Fri, 31 Jan 2025 09:00:00 +0000"), Some(date));
+    /// assert_eq!(Date::parse("soon"), None);
+    /// ```
+    pub fn parse(text: &str) -> Option<Self> {
+        let (year, month, day) = match ISO_DATE_RE.captures(text) {
+            Some(caps) => (
+                caps[1].parse().ok()?,
+                caps[2].parse().ok()?,
+                caps[3].parse().ok()?,
+            ),
+            None => {
+                let caps = NAMED_DATE_RE.captures(text)?;
+                let (day, month) = match (caps.get(1), caps.get(2)) {
+                    (Some(day), Some(month)) => {
+                        (day.as_str(), month.as_str())
+                    }
+                    _ => (caps.get(4)?.as_str(), caps.get(3)?.as_str()),
+                };
+                (
+                    caps[5].parse().ok()?,
+                    month_number(month)?,
+                    day.parse().ok()?,
+                )
+            }
+        };
+        if (1..=12).contains(&month) && (1..=31).contains(&day) {
+            Some(Self { year, month, day })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the current date in UTC.
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self::from_days((seconds / 86_400) as i64)
+    }
+
+    /// Returns the number of days since 1970-01-01.
+    pub fn days(&self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = i64::from(self.month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5
+                + i64::from(self.day)
+                - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4
+            - year_of_era / 100
+            + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// Returns the date `days` days after 1970-01-01.
+    pub fn from_days(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1460
+            + day_of_era / 36_524
+            - day_of_era / 146_096)
+            / 365;
+        let day_of_year = day_of_era
+            - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self {
+            year: year as i32,
+            month,
+            day,
+        }
+    }
+
+    /// Formats the date in `language`, e.g. `12 March 2025` in `en-GB`,
+    /// `March 12, 2025` in `en-US` or `12. März 2025` in `de`.
+    pub fn format(&self, language: &str) -> String {
+        let (primary, region) = split_language(language);
+        let words = Words::for_language(&primary);
+        let month = words.months[self.month as usize - 1];
+        match (primary.as_str(), region.as_deref()) {
+            ("en", Some("US")) => {
+                format!("{} {}, {}", month, self.day, self.year)
+            }
+            ("de", _) => {
+                format!("{}. {} {}", self.day, month, self.year)
+            }
+            ("es", _) => {
+                format!("{} de {} de {}", self.day, month, self.year)
+            }
+            _ => format!("{} {} {}", self.day, month, self.year),
+        }
+    }
+
+    /// Describes the date relative to `today` in `language`, e.g.
+    /// `yesterday`, `3 days ago` or `in 2 months`.
+    pub fn relative_to(&self, today: Date, language: &str) -> String {
+        let words = Words::for_language(&split_language(language).0);
+        let days = today.days() - self.days();
+        let (count, unit) = match days.abs() {
+            0 => return words.today.to_string(),
+            1 if days > 0 => return words.yesterday.to_string(),
+            1 => return words.tomorrow.to_string(),
+            n if n < 31 => (n, 0),
+            n if n < 365 => (n / 30, 1),
+            n => (n / 365, 2),
+        };
+        let (singular, plural) = words.units[unit];
+        let amount = format!(
+            "{} {}",
+            count,
+            if count == 1 { singular } else { plural }
+        );
+        let pattern = if days > 0 { words.ago } else { words.ahead };
+        pattern.replace("{}", &amount)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Serializes as an ISO 8601 date, e.g. `"2025-03-12"`.
+impl Serialize for Date {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Builds the `page` date context of pages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateContext {
+    /// Language of pages without one.
+    pub language: String,
+    /// The date relative times are measured from.
+    pub today: Date,
+    /// Last commit dates by path relative to the content directory.
+    pub git: HashMap<PathBuf, Date>,
+}
+
+impl DateContext {
+    /// Creates a context without git dates.
+    pub fn new(language: &str, today: Date) -> Self {
+        Self {
+            language: language.to_string(),
+            today,
+            git: HashMap::new(),
+        }
+    }
+
+    /// Creates a context for `content` measured from today, with the
+    /// last commit date of each page when the content directory is in a
+    /// git repository.
+    pub fn load(content: &ContentSet, language: &str) -> Self {
+        Self {
+            git: git_dates(&content.root),
+            ..Self::new(language, Date::today())
+        }
+    }
+
+    /// Returns the `published` and `updated` dates of `page`.
+    pub fn page_dates(
+        &self,
+        page: &Page,
+    ) -> (Option<Date>, Option<Date>) {
+        let frontmatter = |keys: &[&str]| {
+            keys.iter().find_map(|key| {
+                page.frontmatter
+                    .get_str(key)
+                    .and_then(|date| Date::parse(&date))
+            })
+        };
+        let published = frontmatter(&["published", "date"]);
+        let updated = frontmatter(&["updated", "lastmod"])
+            .or_else(|| self.git.get(&page.relative).copied())
+            .or(published);
+        (published, updated)
+    }
+
+    /// Returns the `page` context value of `page`.
+    pub fn page_context(&self, page: &Page) -> Value {
+        let language = page
+            .frontmatter
+            .get_str("language")
+            .or_else(|| page.frontmatter.get_str("lang"))
+            .unwrap_or_else(|| self.language.clone());
+        let value = |date: Option<Date>| match date {
+            Some(date) => json!({
+                "iso": date.to_string(),
+                "formatted": date.format(&language),
+                "relative": date.relative_to(self.today, &language),
+            }),
+            None => Value::Null,
+        };
+        let (published, updated) = self.page_dates(page);
+        json!({
+            "published": value(published),
+            "updated": value(updated),
+        })
+    }
+}
+
+impl Default for DateContext {
+    fn default() -> Self {
+        Self::new(DEFAULT_DATE_LANGUAGE, Date::today())
+    }
+}
+
+/// Returns the date of the last commit touching each file below `root`,
+/// or nothing if `root` is not in a git repository.
+pub fn git_dates(root: &Path) -> HashMap<PathBuf, Date> {
+    let mut dates = HashMap::new();
+    if !root.is_dir() {
+        return dates;
+    }
+    let output = match Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args([
+            "log",
+            "--format=%x00%cI",
+            "--name-only",
+            "--relative",
+            "--",
+            ".",
+        ])
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return dates,
+    };
+
+    let mut current = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        match line.strip_prefix('\0') {
+            Some(date) => current = Date::parse(date),
+            None if !line.is_empty() => {
+                if let Some(date) = current {
+                    let _ = dates
+                        .entry(PathBuf::from(line))
+                        .or_insert(date);
+                }
+            }
+            None => {}
+        }
+    }
+    dates
+}
+
+/// Localised words for dates.
+struct Words {
+    months: [&'static str; 12],
+    today: &'static str,
+    yesterday: &'static str,
+    tomorrow: &'static str,
+    ago: &'static str,
+    ahead: &'static str,
+    units: [(&'static str, &'static str); 3],
+}
+
+impl Words {
+    fn for_language(primary: &str) -> Self {
+        match primary {
+            "fr" => Self {
+                months: [
+                    "janvier",
+                    "février",
+                    "mars",
+                    "avril",
+                    "mai",
+                    "juin",
+                    "juillet",
+                    "août",
+                    "septembre",
+                    "octobre",
+                    "novembre",
+                    "décembre",
+                ],
+                today: "aujourd’hui",
+                yesterday: "hier",
+                tomorrow: "demain",
+                ago: "il y a {}",
+                ahead: "dans {}",
+                units: [
+                    ("jour", "jours"),
+                    ("mois", "mois"),
+                    ("an", "ans"),
+                ],
+            },
+            "de" => Self {
+                months: [
+                    "Januar",
+                    "Februar",
+                    "März",
+                    "April",
+                    "Mai",
+                    "Juni",
+                    "Juli",
+                    "August",
+                    "September",
+                    "Oktober",
+                    "November",
+                    "Dezember",
+                ],
+                today: "heute",
+                yesterday: "gestern",
+                tomorrow: "morgen",
+                ago: "vor {}",
+                ahead: "in {}",
+                units: [
+                    ("Tag", "Tagen"),
+                    ("Monat", "Monaten"),
+                    ("Jahr", "Jahren"),
+                ],
+            },
+            "es" => Self {
+                months: [
+                    "enero",
+                    "febrero",
+                    "marzo",
+                    "abril",
+                    "mayo",
+                    "junio",
+                    "julio",
+                    "agosto",
+                    "septiembre",
+                    "octubre",
+                    "noviembre",
+                    "diciembre",
+                ],
+                today: "hoy",
+                yesterday: "ayer",
+                tomorrow: "mañana",
+                ago: "hace {}",
+                ahead: "dentro de {}",
+                units: [
+                    ("día", "días"),
+                    ("mes", "meses"),
+                    ("año", "años"),
+                ],
+            },
+            _ => Self {
+                months: [
+                    "January",
+                    "February",
+                    "March",
+                    "April",
+                    "May",
+                    "June",
+                    "July",
+                    "August",
+                    "September",
+                    "October",
+                    "November",
+                    "December",
+                ],
+                today: "today",
+                yesterday: "yesterday",
+                tomorrow: "tomorrow",
+                ago: "{} ago",
+                ahead: "in {}",
+                units: [
+                    ("day", "days"),
+                    ("month", "months"),
+                    ("year", "years"),
+                ],
+            },
+        }
+    }
+}
+
+/// Splits a language tag into its lowercase primary language and
+/// uppercase region, e.g. `en_us` into `en` and `US`.
+fn split_language(language: &str) -> (String, Option<String>) {
+    let mut parts = language.split(['-', '_']);
+    let primary = parts.next().unwrap_or_default().to_lowercase();
+    let region = parts
+        .find(|part| {
+            part.len() == 2 && part.chars().all(char::is_alphabetic)
+        })
+        .map(str::to_uppercase);
+    (primary, region)
+}
+
+/// Returns the number of an English month name or its abbreviation.
+fn month_number(name: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep",
+        "oct", "nov", "dec",
+    ];
+    let name = name.to_lowercase();
+    let prefix = name.get(..3)?;
+    MONTHS
+        .iter()
+        .position(|month| *month == prefix)
+        .map(|i| i as u32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date { year, month, day }
+    }
+
+    #[test]
+    fn test_days_round_trip() {
+        assert_eq!(date(1970, 1, 1).days(), 0);
+        assert_eq!(date(2000, 3, 1).days(), 11_017);
+        assert_eq!(date(1969, 12, 31).days(), -1);
+        for days in [-1000, 0, 11_016, 11_017, 20_000, 20_513] {
+            assert_eq!(Date::from_days(days).days(), days);
+        }
+        assert_eq!(Date::from_days(20_159), date(2025, 3, 12));
+    }
+
+    #[test]
+    fn test_format_in_languages() {
+        let day = date(2025, 3, 12);
+        assert_eq!(day.format("en-GB"), "12 March 2025");
+        assert_eq!(day.format("en-US"), "March 12, 2025");
+        assert_eq!(day.format("fr"), "12 mars 2025");
+        assert_eq!(day.format("de-DE"), "12. März 2025");
+        assert_eq!(day.format("es"), "12 de marzo de 2025");
+        assert_eq!(day.format("ja"), "12 March 2025");
+        assert_eq!(day.to_string(), "2025-03-12");
+        assert_eq!(json!(day), json!("2025-03-12"));
+    }
+
+    #[test]
+    fn test_relative_times() {
+        let today = date(2025, 3, 12);
+        let relative =
+            |d: Date, language: &str| d.relative_to(today, language);
+        assert_eq!(relative(today, "en"), "today");
+        assert_eq!(relative(date(2025, 3, 11), "en"), "yesterday");
+        assert_eq!(relative(date(2025, 3, 9), "en"), "3 days ago");
+        assert_eq!(relative(date(2025, 3, 13), "fr"), "demain");
+        assert_eq!(relative(date(2025, 1, 10), "fr"), "il y a 2 mois");
+        assert_eq!(relative(date(2024, 3, 1), "de"), "vor 1 Jahr");
+        assert_eq!(
+            relative(date(2025, 3, 20), "es"),
+            "dentro de 8 días"
+        );
+    }
+
+    #[test]
+    fn test_page_context() -> anyhow::Result<()> {
+        let page = Page::from_source(
+            PathBuf::from("content/blog/post.md"),
+            PathBuf::from("blog/post.md"),
+            "---\ndate: 2025-03-01\nlang: fr-FR\n---\n",
+        )?;
+        let mut context = DateContext::new("en-GB", date(2025, 3, 12));
+        let value = context.page_context(&page);
+        assert_eq!(value["published"]["iso"], "2025-03-01");
+        assert_eq!(value["published"]["formatted"], "1 mars 2025");
+        assert_eq!(value["updated"]["relative"], "il y a 11 jours");
+
+        let _ = context
+            .git
+            .insert(PathBuf::from("blog/post.md"), date(2025, 3, 11));
+        assert_eq!(
+            context.page_context(&page)["updated"]["relative"],
+            "hier"
+        );
+
+        let undated = Page::from_source(
+            PathBuf::from("content/about.md"),
+            PathBuf::from("about.md"),
+            "---\nupdated: March 10, 2025\n---\n",
+        )?;
+        let value = context.page_context(&undated);
+        assert_eq!(value["published"], Value::Null);
+        assert_eq!(value["updated"]["formatted"], "10 March 2025");
+        Ok(())
+    }
+
+    #[test]
+    fn test_git_dates_outside_repository() {
+        assert!(git_dates(Path::new("does/not/exist")).is_empty());
+    }
+}
@@ -0,0 +1,1102 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Deployment
+//!
+//! Backs `ssg deploy [TARGET]`, which publishes the built site to a
+//! target declared in the `[deploy]` table of the configuration file:
+//!
+//! ```toml
+//! [deploy.production]
+//! type = "s3"
+//! bucket = "example-site"
+//! region = "eu-west-1"
+//! cloudfront_distribution = "E2QWRUHAPOMQZL"
+//!
+//! [deploy.pages]
+//! type = "github-pages"
+//! repository = "git@github.com:example/example.github.io.git"
+//!
+//! [deploy.staging]
+//! type = "rsync"
+//! destination = "deploy@staging.example.com:/var/www/site"
+//!
+//! [deploy.preview]
+//! type = "netlify"
+//! site_id = "3970e0fe-8564-4903-9a55-c5f8de49fb8b"
+//! draft = true
+//! ```
+//!
+//! Each backend drives the tool its service provides: `rsync`, the AWS
+//! CLI (`aws`), `git` and the Netlify CLI (`netlify`). Credentials are
+//! taken from those tools' usual configuration, never from the site
+//! config.
+//!
+//! The content hash of every deployed file is kept in
+//! `.ssg-cache/deploy/<target>.json`. The next deploy compares the
+//! build manifest (`etags.json`, see [`crate::etags`]) against it, so
+//! the site is not hashed again, and only uploads added and modified
+//! files and removes deleted ones (S3 and rsync). GitHub Pages commits
+//! only the changes and Netlify uploads only files it does not have. A
+//! deploy with no changes is skipped. If the site changed after the
+//! build, its files are hashed instead of trusting the manifest.
+//!
+//! `ssg deploy --force-full` ignores the saved state: it uploads every
+//! file and removes anything the site does not have from the target,
+//! for a target that was changed by hand or a lost state file.
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    collect_files_recursive,
+    etags::{etag_for, ETAGS_FILE},
+};
+
+/// Directory of the per-target deploy state, relative to the project
+/// root.
+pub const DEPLOY_STATE_DIR: &str = ".ssg-cache/deploy";
+
+/// Default branch pushed by the `github-pages` backend.
+pub const DEFAULT_PAGES_BRANCH: &str = "gh-pages";
+
+/// CloudFront accepts at most this many paths per invalidation; larger
+/// change sets invalidate `/*`.
+const MAX_INVALIDATION_PATHS: usize = 100;
+
+fn default_pages_branch() -> String {
+    DEFAULT_PAGES_BRANCH.to_string()
+}
+
+/// Where and how a site is deployed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum DeployTarget {
+    /// Syncs the site to a local or SSH destination with `rsync`.
+    Rsync {
+        /// Destination, e.g. `user@host:/var/www/site`.
+        destination: String,
+        /// SSH port, if not 22.
+        #[serde(default)]
+        port: Option<u16>,
+    },
+    /// Uploads to an S3 bucket with the AWS CLI, then invalidates the
+    /// changed paths in CloudFront.
+    S3 {
+        /// Bucket name.
+        bucket: String,
+        /// Key prefix inside the bucket.
+        #[serde(default)]
+        prefix: String,
+        /// AWS region of the bucket.
+        #[serde(default)]
+        region: Option<String>,
+        /// CloudFront distribution to invalidate.
+        #[serde(default)]
+        cloudfront_distribution: Option<String>,
+    },
+    /// Commits the site to a branch of a git repository and pushes it.
+    GithubPages {
+        /// Repository URL.
+        repository: String,
+        /// Branch to push, `gh-pages` by default.
+        #[serde(default = "default_pages_branch")]
+        branch: String,
+    },
+    /// Deploys with the Netlify CLI.
+    Netlify {
+        /// Netlify site ID.
+        site_id: String,
+        /// Creates a draft deploy instead of publishing.
+        #[serde(default)]
+        draft: bool,
+    },
+}
+
+impl DeployTarget {
+    /// Returns the backend name, as used for `type`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Rsync { .. } => "rsync",
+            Self::S3 { .. } => "s3",
+            Self::GithubPages { .. } => "github-pages",
+            Self::Netlify { .. } => "netlify",
+        }
+    }
+
+    /// Checks that the required settings are present and cannot be
+    /// mistaken for command-line options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid setting.
+    pub fn validate(&self) -> Result<()> {
+        let required: Vec<(&str, &str)> = match self {
+            Self::Rsync { destination, .. } => {
+                vec![("destination", destination)]
+            }
+            Self::S3 {
+                bucket,
+                cloudfront_distribution,
+                ..
+            } => {
+                ensure!(
+                    bucket.chars().all(|c| c.is_ascii_lowercase()
+                        || c.is_ascii_digit()
+                        || c == '-'
+                        || c == '.'),
+                    "Invalid S3 bucket name `{}`",
+                    bucket
+                );
+                let mut required = vec![("bucket", bucket.as_str())];
+                if let Some(id) = cloudfront_distribution {
+                    required.push(("cloudfront_distribution", id));
+                }
+                required
+            }
+            Self::GithubPages { repository, branch } => {
+                vec![("repository", repository), ("branch", branch)]
+            }
+            Self::Netlify { site_id, .. } => vec![("site_id", site_id)],
+        };
+        for (name, value) in required {
+            ensure!(
+                !value.trim().is_empty(),
+                "{} needs a `{}`",
+                self.kind(),
+                name
+            );
+            ensure!(
+                !value.starts_with('-'),
+                "Invalid {} `{}`",
+                name,
+                value
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the steps deploying `plan` from `site_dir`. `work_dir` is
+    /// an empty scratch directory.
+    pub fn steps(
+        &self,
+        site_dir: &Path,
+        plan: &DeployPlan,
+        work_dir: &Path,
+    ) -> Vec<DeployStep> {
+        match self {
+            Self::Rsync { destination, port } => {
+                let mut steps = Vec::new();
+                let mut args = vec!["-az".to_string()];
+                if plan.full {
+                    args.push("--delete".to_string());
+                    args.push("--checksum".to_string());
+                } else {
+                    // Files missing from the site are deleted from the
+                    // destination.
+                    let list = work_dir.join("rsync-files.txt");
+                    let mut contents = String::new();
+                    for path in plan.upload.iter().chain(&plan.delete) {
+                        contents.push_str(path);
+                        contents.push('\n');
+                    }
+                    steps.push(DeployStep::Write {
+                        path: list.clone(),
+                        contents,
+                    });
+                    args.push(format!(
+                        "--files-from={}",
+                        list.display()
+                    ));
+                    args.push("--delete-missing-args".to_string());
+                }
+                if let Some(port) = port {
+                    args.push("-e".to_string());
+                    args.push(format!("ssh -p {}", port));
+                }
+                args.push("--".to_string());
+                args.push(format!("{}/", site_dir.display()));
+                args.push(destination.clone());
+                steps.push(DeployStep::run("rsync", args));
+                steps
+            }
+            Self::S3 {
+                bucket,
+                prefix,
+                region,
+                cloudfront_distribution,
+            } => {
+                let prefix = prefix.trim_matches('/');
+                let object = |path: &str| {
+                    if prefix.is_empty() {
+                        format!("s3://{}/{}", bucket, path)
+                    } else {
+                        format!("s3://{}/{}/{}", bucket, prefix, path)
+                    }
+                };
+                let region_args: Vec<String> = region
+                    .iter()
+                    .flat_map(|r| ["--region".to_string(), r.clone()])
+                    .collect();
+
+                let mut steps = Vec::new();
+                if plan.full {
+                    let mut args = vec![
+                        "s3".to_string(),
+                        "sync".to_string(),
+                        "--only-show-errors".to_string(),
+                        "--delete".to_string(),
+                    ];
+                    args.extend(region_args.iter().cloned());
+                    args.push(format!("{}/", site_dir.display()));
+                    args.push(object(""));
+                    steps.push(DeployStep::run("aws", args));
+                }
+                // A full deploy synced every file above.
+                let upload: &[String] =
+                    if plan.full { &[] } else { &plan.upload };
+                for path in upload {
+                    let mut args = vec![
+                        "s3".to_string(),
+                        "cp".to_string(),
+                        "--only-show-errors".to_string(),
+                    ];
+                    args.extend(region_args.iter().cloned());
+                    args.push(
+                        site_dir.join(path).display().to_string(),
+                    );
+                    args.push(object(path));
+                    steps.push(DeployStep::run("aws", args));
+                }
+                for path in &plan.delete {
+                    let mut args = vec![
+                        "s3".to_string(),
+                        "rm".to_string(),
+                        "--only-show-errors".to_string(),
+                    ];
+                    args.extend(region_args.iter().cloned());
+                    args.push(object(path));
+                    steps.push(DeployStep::run("aws", args));
+                }
+                if let Some(id) = cloudfront_distribution {
+                    let mut args = vec![
+                        "cloudfront".to_string(),
+                        "create-invalidation".to_string(),
+                        "--distribution-id".to_string(),
+                        id.clone(),
+                        "--paths".to_string(),
+                    ];
+                    args.extend(plan.invalidation_paths());
+                    steps.push(DeployStep::run("aws", args));
+                }
+                steps
+            }
+            Self::GithubPages { repository, branch } => {
+                let repo = work_dir.join("pages");
+                let git = |args: &[&str]| {
+                    let mut all = vec![
+                        "-C".to_string(),
+                        repo.display().to_string(),
+                    ];
+                    all.extend(args.iter().map(|a| (*a).to_string()));
+                    DeployStep::run("git", all)
+                };
+                vec![
+                    DeployStep::run(
+                        "git",
+                        vec![
+                            "init".to_string(),
+                            "--quiet".to_string(),
+                            repo.display().to_string(),
+                        ],
+                    ),
+                    git(&["checkout", "--quiet", "--orphan", branch]),
+                    // The branch may not exist yet.
+                    git(&[
+                        "fetch", "--quiet", "--depth", "1", "--",
+                        repository, branch,
+                    ])
+                    .optional(),
+                    git(&["reset", "--quiet", "--soft", "FETCH_HEAD"])
+                        .optional(),
+                    DeployStep::Mirror {
+                        from: site_dir.to_path_buf(),
+                        to: repo.clone(),
+                    },
+                    DeployStep::Write {
+                        path: repo.join(".nojekyll"),
+                        contents: String::new(),
+                    },
+                    git(&["add", "--all"]),
+                    git(&[
+                        "-c",
+                        "user.name=ssg",
+                        "-c",
+                        "user.email=ssg@localhost",
+                        "commit",
+                        "--quiet",
+                        "--allow-empty",
+                        "-m",
+                        "Deploy site",
+                    ]),
+                    git(&[
+                        "push",
+                        "--quiet",
+                        "--",
+                        repository,
+                        &format!("HEAD:refs/heads/{}", branch),
+                    ]),
+                ]
+            }
+            Self::Netlify { site_id, draft } => {
+                let mut args = vec![
+                    "deploy".to_string(),
+                    "--dir".to_string(),
+                    site_dir.display().to_string(),
+                    "--site".to_string(),
+                    site_id.clone(),
+                ];
+                if !draft {
+                    args.push("--prod".to_string());
+                }
+                vec![DeployStep::run("netlify", args)]
+            }
+        }
+    }
+}
+
+/// One step of a deployment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeployStep {
+    /// Runs a program.
+    Run {
+        /// Program name, looked up on `PATH`.
+        program: String,
+        /// Arguments.
+        args: Vec<String>,
+        /// Continue if the program fails.
+        optional: bool,
+    },
+    /// Replaces everything in `to` except `.git` with the files of
+    /// `from`.
+    Mirror {
+        /// Source directory.
+        from: PathBuf,
+        /// Destination directory.
+        to: PathBuf,
+    },
+    /// Writes a file.
+    Write {
+        /// File path.
+        path: PathBuf,
+        /// File contents.
+        contents: String,
+    },
+}
+
+impl DeployStep {
+    fn run(program: &str, args: Vec<String>) -> Self {
+        Self::Run {
+            program: program.to_string(),
+            args,
+            optional: false,
+        }
+    }
+
+    fn optional(self) -> Self {
+        match self {
+            Self::Run { program, args, .. } => Self::Run {
+                program,
+                args,
+                optional: true,
+            },
+            step => step,
+        }
+    }
+
+    /// Performs the step.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required program cannot be run or fails,
+    /// or a file cannot be copied or written.
+    pub fn execute(&self) -> Result<()> {
+        match self {
+            Self::Run {
+                program,
+                args,
+                optional,
+            } => {
+                let status = Command::new(program)
+                    .args(args)
+                    .status()
+                    .with_context(|| {
+                        format!(
+                            "Failed to run `{}`; is it installed?",
+                            program
+                        )
+                    })?;
+                if !status.success() && !optional {
+                    bail!("`{}` failed ({})", self, status);
+                }
+                Ok(())
+            }
+            Self::Mirror { from, to } => mirror(from, to),
+            Self::Write { path, contents } => fs::write(path, contents)
+                .with_context(|| {
+                    format!("Failed to write {}", path.display())
+                }),
+        }
+    }
+}
+
+impl fmt::Display for DeployStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Run { program, args, .. } => {
+                write!(f, "{}", program)?;
+                for arg in args {
+                    if arg.is_empty()
+                        || arg.contains(char::is_whitespace)
+                    {
+                        write!(f, " '{}'", arg)?;
+                    } else {
+                        write!(f, " {}", arg)?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Mirror { from, to } => {
+                write!(
+                    f,
+                    "mirror {} -> {}",
+                    from.display(),
+                    to.display()
+                )
+            }
+            Self::Write { path, .. } => {
+                write!(f, "write {}", path.display())
+            }
+        }
+    }
+}
+
+/// Files to upload and delete, from comparing the site with the last
+/// deploy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeployPlan {
+    /// New and changed files, relative to the site directory.
+    pub upload: Vec<String>,
+    /// Files deployed last time that no longer exist.
+    pub delete: Vec<String>,
+    /// Content hash of every file of the site.
+    pub hashes: BTreeMap<String, String>,
+    /// Deploys the whole site and removes every other file from the
+    /// target.
+    pub full: bool,
+}
+
+impl DeployPlan {
+    /// Compares the files of `site_dir` with the `previous` hashes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the site cannot be read.
+    pub fn new(
+        site_dir: &Path,
+        previous: &BTreeMap<String, String>,
+    ) -> Result<Self> {
+        let hashes = hash_site(site_dir)?;
+        let upload = hashes
+            .iter()
+            .filter(|(path, hash)| previous.get(*path) != Some(*hash))
+            .map(|(path, _)| path.clone())
+            .collect();
+        let delete = previous
+            .keys()
+            .filter(|path| !hashes.contains_key(*path))
+            .cloned()
+            .collect();
+        Ok(Self {
+            upload,
+            delete,
+            hashes,
+            full: false,
+        })
+    }
+
+    /// Plans a full deploy of `site_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the site cannot be read.
+    pub fn full(site_dir: &Path) -> Result<Self> {
+        let hashes = hash_site(site_dir)?;
+        Ok(Self {
+            upload: hashes.keys().cloned().collect(),
+            delete: Vec::new(),
+            hashes,
+            full: true,
+        })
+    }
+
+    /// Returns `true` if nothing changed since the last deploy.
+    pub fn is_empty(&self) -> bool {
+        self.upload.is_empty() && self.delete.is_empty()
+    }
+
+    /// Returns the URL paths to invalidate in a CDN: every changed or
+    /// deleted file, plus the directory URL of changed `index.html`
+    /// files; `/*` for a full deploy or if there are too many.
+    pub fn invalidation_paths(&self) -> Vec<String> {
+        if self.full {
+            return vec!["/*".to_string()];
+        }
+        let mut paths = Vec::new();
+        for path in self.upload.iter().chain(&self.delete) {
+            paths.push(format!("/{}", path));
+            if let Some(dir) = path.strip_suffix("index.html") {
+                paths.push(format!("/{}", dir));
+            }
+        }
+        if paths.len() > MAX_INVALIDATION_PATHS {
+            return vec!["/*".to_string()];
+        }
+        paths
+    }
+}
+
+/// Outcome of [`deploy`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeployReport {
+    /// The plan that was deployed.
+    pub plan: DeployPlan,
+    /// The steps, as shown to the user.
+    pub steps: Vec<String>,
+    /// `false` if nothing changed and the deploy was skipped.
+    pub deployed: bool,
+}
+
+/// Options of a [`deploy`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeployOptions {
+    /// Lists the steps without running them or saving state.
+    pub dry_run: bool,
+    /// Deploys every file and removes every other file from the
+    /// target, ignoring the saved state.
+    pub force_full: bool,
+}
+
+/// Deploys `site_dir` to the target `name`, keeping its state in
+/// `state_dir`.
+///
+/// # Errors
+///
+/// Returns an error if the target is invalid, the site does not exist,
+/// or a step fails. The state is only saved after every step succeeded.
+pub fn deploy(
+    name: &str,
+    target: &DeployTarget,
+    site_dir: &Path,
+    state_dir: &Path,
+    options: DeployOptions,
+) -> Result<DeployReport> {
+    target
+        .validate()
+        .with_context(|| format!("Invalid deploy target `{}`", name))?;
+    ensure!(
+        site_dir.is_dir(),
+        "{} does not exist; build the site first",
+        site_dir.display()
+    );
+
+    let state_file = state_dir.join(format!("{}.json", name));
+    let plan = if options.force_full {
+        DeployPlan::full(site_dir)?
+    } else {
+        DeployPlan::new(site_dir, &load_state(&state_file)?)?
+    };
+    if plan.is_empty() {
+        return Ok(DeployReport {
+            plan,
+            ..DeployReport::default()
+        });
+    }
+
+    let work_dir = tempfile::tempdir()
+        .context("Failed to create a deploy directory")?;
+    let steps = target.steps(site_dir, &plan, work_dir.path());
+    let report = DeployReport {
+        steps: steps.iter().map(ToString::to_string).collect(),
+        deployed: !options.dry_run,
+        plan,
+    };
+    if options.dry_run {
+        return Ok(report);
+    }
+
+    for step in &steps {
+        step.execute()?;
+    }
+    fs::create_dir_all(state_dir).with_context(|| {
+        format!("Failed to create {}", state_dir.display())
+    })?;
+    let json = serde_json::to_string_pretty(&report.plan.hashes)
+        .context("Failed to serialize the deploy state")?;
+    fs::write(&state_file, json).with_context(|| {
+        format!("Failed to write {}", state_file.display())
+    })?;
+    Ok(report)
+}
+
+/// Picks the target to deploy: `name` if given, otherwise the only
+/// configured target.
+///
+/// # Errors
+///
+/// Returns an error if `name` is not configured, or no name is given
+/// and there is not exactly one target.
+pub fn select_target<'a>(
+    targets: &'a BTreeMap<String, DeployTarget>,
+    name: Option<&'a str>,
+) -> Result<(&'a str, &'a DeployTarget)> {
+    let names =
+        || targets.keys().cloned().collect::<Vec<_>>().join(", ");
+    match name {
+        Some(name) => targets
+            .get(name)
+            .map(|target| (name, target))
+            .with_context(|| {
+                format!(
+                    "Unknown deploy target `{}` (configured: {})",
+                    name,
+                    names()
+                )
+            }),
+        None if targets.len() == 1 => targets
+            .iter()
+            .next()
+            .map(|(name, target)| (name.as_str(), target))
+            .context("No deploy target configured"),
+        None if targets.is_empty() => {
+            bail!("No deploy target configured; add a [deploy.<name>] table")
+        }
+        None => bail!("Choose a deploy target: {}", names()),
+    }
+}
+
+fn load_state(path: &Path) -> Result<BTreeMap<String, String>> {
+    if !path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    let json = fs::read_to_string(path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    serde_json::from_str(&json).with_context(|| {
+        format!("Invalid deploy state {}", path.display())
+    })
+}
+
+/// Returns the content hash of every file of `site_dir`, from its build
+/// manifest when it is up to date.
+fn hash_site(site_dir: &Path) -> Result<BTreeMap<String, String>> {
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    if let Some(hashes) = build_manifest(site_dir, &files) {
+        return Ok(hashes);
+    }
+    files
+        .iter()
+        .map(|path| {
+            let contents = fs::read(path).with_context(|| {
+                format!("Failed to read {}", path.display())
+            })?;
+            Ok((relative_path(site_dir, path), etag_for(&contents)))
+        })
+        .collect()
+}
+
+/// Reads the hashes of `files` from the `etags.json` the build wrote,
+/// or returns `None` if it is missing or a file was added or changed
+/// after it was written.
+fn build_manifest(
+    site_dir: &Path,
+    files: &[PathBuf],
+) -> Option<BTreeMap<String, String>> {
+    let manifest = site_dir.join(ETAGS_FILE);
+    let contents = fs::read(&manifest).ok()?;
+    let written =
+        fs::metadata(&manifest).and_then(|m| m.modified()).ok()?;
+    let etags: BTreeMap<String, String> =
+        serde_json::from_slice(&contents).ok()?;
+
+    let mut hashes = BTreeMap::new();
+    for file in files.iter().filter(|file| **file != manifest) {
+        let modified =
+            fs::metadata(file).and_then(|m| m.modified()).ok()?;
+        let relative = relative_path(site_dir, file);
+        let etag = etags.get(&format!("/{}", relative))?;
+        if modified > written {
+            return None;
+        }
+        let _ = hashes.insert(relative, etag.clone());
+    }
+    if hashes.len() != etags.len() {
+        return None;
+    }
+    let _ = hashes.insert(ETAGS_FILE.to_string(), etag_for(&contents));
+    Some(hashes)
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn mirror(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to).with_context(|| {
+        format!("Failed to create {}", to.display())
+    })?;
+    let entries = fs::read_dir(to)
+        .with_context(|| format!("Failed to read {}", to.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.file_name().map_or(false, |name| name == ".git") {
+            continue;
+        }
+        if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        }
+        .with_context(|| {
+            format!("Failed to remove {}", path.display())
+        })?;
+    }
+
+    let mut files = Vec::new();
+    collect_files_recursive(from, &mut files).with_context(|| {
+        format!("Failed to read {}", from.display())
+    })?;
+    for file in files {
+        let target = to.join(file.strip_prefix(from).unwrap_or(&file));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        let _ = fs::copy(&file, &target).with_context(|| {
+            format!("Failed to copy {}", file.display())
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn site() -> Result<tempfile::TempDir> {
+        let site = tempdir()?;
+        fs::create_dir_all(site.path().join("blog"))?;
+        fs::write(site.path().join("index.html"), "home")?;
+        fs::write(site.path().join("blog/index.html"), "blog")?;
+        Ok(site)
+    }
+
+    #[test]
+    fn test_targets_from_config() -> Result<()> {
+        let targets: BTreeMap<String, DeployTarget> = toml::from_str(
+            r#"
+            [production]
+            type = "s3"
+            bucket = "example-site"
+            cloudfront_distribution = "E2QWRUHAPOMQZL"
+
+            [pages]
+            type = "github-pages"
+            repository = "git@github.com:example/site.git"
+            "#,
+        )?;
+        assert_eq!(
+            targets["pages"],
+            DeployTarget::GithubPages {
+                repository: "git@github.com:example/site.git".into(),
+                branch: DEFAULT_PAGES_BRANCH.into(),
+            }
+        );
+        assert_eq!(targets["production"].kind(), "s3");
+        for target in targets.values() {
+            target.validate()?;
+        }
+
+        let (name, _) = select_target(&targets, Some("pages"))?;
+        assert_eq!(name, "pages");
+        assert!(select_target(&targets, None).is_err());
+        assert!(select_target(&targets, Some("nope")).is_err());
+        let one: BTreeMap<_, _> = targets.into_iter().take(1).collect();
+        assert_eq!(select_target(&one, None)?.0, "pages");
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_rejects_option_like_values() {
+        let invalid = [
+            DeployTarget::Rsync {
+                destination: "--rsh=evil".into(),
+                port: None,
+            },
+            DeployTarget::S3 {
+                bucket: "Bad Bucket".into(),
+                prefix: String::new(),
+                region: None,
+                cloudfront_distribution: None,
+            },
+            DeployTarget::Netlify {
+                site_id: " ".into(),
+                draft: false,
+            },
+        ];
+        for target in invalid {
+            assert!(target.validate().is_err(), "{:?}", target);
+        }
+    }
+
+    #[test]
+    fn test_plan_uploads_only_changes() -> Result<()> {
+        let site = site()?;
+        let first = DeployPlan::new(site.path(), &BTreeMap::new())?;
+        assert_eq!(first.upload, ["blog/index.html", "index.html"]);
+
+        fs::write(site.path().join("index.html"), "new home")?;
+        fs::remove_file(site.path().join("blog/index.html"))?;
+        fs::write(site.path().join("about.html"), "about")?;
+        let plan = DeployPlan::new(site.path(), &first.hashes)?;
+        assert_eq!(plan.upload, ["about.html", "index.html"]);
+        assert_eq!(plan.delete, ["blog/index.html"]);
+        assert_eq!(
+            plan.invalidation_paths(),
+            [
+                "/about.html",
+                "/index.html",
+                "/",
+                "/blog/index.html",
+                "/blog/"
+            ]
+        );
+        assert!(DeployPlan::new(site.path(), &plan.hashes)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_reads_the_build_manifest() -> Result<()> {
+        let site = site()?;
+        fs::write(
+            site.path().join(ETAGS_FILE),
+            r#"{"/index.html": "\"a\"", "/blog/index.html": "\"b\""}"#,
+        )?;
+        let plan = DeployPlan::new(site.path(), &BTreeMap::new())?;
+        assert_eq!(plan.hashes["index.html"], "\"a\"");
+        assert_eq!(
+            plan.upload,
+            ["blog/index.html", ETAGS_FILE, "index.html"]
+        );
+
+        // A file the manifest does not know about means it is stale.
+        fs::write(site.path().join("about.html"), "about")?;
+        let plan = DeployPlan::new(site.path(), &BTreeMap::new())?;
+        assert_eq!(plan.hashes["index.html"], etag_for(b"home"));
+
+        let full = DeployPlan::full(site.path())?;
+        assert!(full.full && full.delete.is_empty());
+        assert_eq!(full.upload.len(), 4);
+        assert_eq!(full.invalidation_paths(), ["/*"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_s3_steps() -> Result<()> {
+        let site = site()?;
+        let plan = DeployPlan {
+            upload: vec!["index.html".into()],
+            delete: vec!["old.html".into()],
+            ..DeployPlan::default()
+        };
+        let target = DeployTarget::S3 {
+            bucket: "example-site".into(),
+            prefix: "/www/".into(),
+            region: Some("eu-west-1".into()),
+            cloudfront_distribution: Some("E2QW".into()),
+        };
+        let steps: Vec<String> = target
+            .steps(site.path(), &plan, site.path())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(steps.len(), 3);
+        assert!(steps[0].starts_with(
+            "aws s3 cp --only-show-errors --region eu-west-1 "
+        ));
+        assert!(steps[0].ends_with("s3://example-site/www/index.html"));
+        assert!(steps[1].ends_with("s3://example-site/www/old.html"));
+        assert_eq!(
+            steps[2],
+            "aws cloudfront create-invalidation --distribution-id E2QW \
+             --paths /index.html / /old.html"
+        );
+
+        let plan = DeployPlan::full(site.path())?;
+        let steps: Vec<String> = target
+            .steps(site.path(), &plan, site.path())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(steps.len(), 2);
+        assert!(steps[0].starts_with(
+            "aws s3 sync --only-show-errors --delete --region eu-west-1 "
+        ));
+        assert!(steps[0].ends_with("/ s3://example-site/www/"));
+        assert!(steps[1].ends_with("--paths /*"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rsync_and_netlify_steps() {
+        let mut plan = DeployPlan {
+            upload: vec!["index.html".into()],
+            delete: vec!["old.html".into()],
+            ..DeployPlan::default()
+        };
+        let target = DeployTarget::Rsync {
+            destination: "deploy@host:/var/www".into(),
+            port: Some(2222),
+        };
+        let rsync =
+            target.steps(Path::new("public"), &plan, Path::new("work"));
+        assert_eq!(
+            rsync[0],
+            DeployStep::Write {
+                path: PathBuf::from("work/rsync-files.txt"),
+                contents: "index.html\nold.html\n".into(),
+            }
+        );
+        assert_eq!(
+            rsync[1].to_string(),
+            "rsync -az --files-from=work/rsync-files.txt \
+             --delete-missing-args -e 'ssh -p 2222' -- public/ \
+             deploy@host:/var/www"
+        );
+
+        plan.full = true;
+        let rsync =
+            target.steps(Path::new("public"), &plan, Path::new("work"));
+        assert_eq!(
+            rsync[0].to_string(),
+            "rsync -az --delete --checksum -e 'ssh -p 2222' -- public/ \
+             deploy@host:/var/www"
+        );
+        let netlify = DeployTarget::Netlify {
+            site_id: "abc".into(),
+            draft: false,
+        }
+        .steps(Path::new("public"), &plan, Path::new("work"));
+        assert_eq!(
+            netlify[0].to_string(),
+            "netlify deploy --dir public --site abc --prod"
+        );
+    }
+
+    #[test]
+    fn test_deploy_dry_run_and_unchanged_site() -> Result<()> {
+        let site = site()?;
+        let state = tempdir()?;
+        let target = DeployTarget::Netlify {
+            site_id: "abc".into(),
+            draft: true,
+        };
+        let report = deploy(
+            "preview",
+            &target,
+            site.path(),
+            state.path(),
+            DeployOptions {
+                dry_run: true,
+                force_full: false,
+            },
+        )?;
+        assert!(!report.deployed);
+        assert_eq!(report.plan.upload.len(), 2);
+        assert_eq!(report.steps.len(), 1);
+        assert!(!state.path().join("preview.json").exists());
+
+        fs::write(
+            state.path().join("preview.json"),
+            serde_json::to_string(&report.plan.hashes)?,
+        )?;
+        let report = deploy(
+            "preview",
+            &target,
+            site.path(),
+            state.path(),
+            DeployOptions::default(),
+        )?;
+        assert!(!report.deployed && report.steps.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_github_pages_pushes_to_a_repository() -> Result<()> {
+        let site = site()?;
+        let remote = tempdir()?;
+        let state = tempdir()?;
+        let status = Command::new("git")
+            .args(["init", "--quiet", "--bare"])
+            .arg(remote.path())
+            .status()?;
+        assert!(status.success());
+
+        let target = DeployTarget::GithubPages {
+            repository: remote.path().display().to_string(),
+            branch: DEFAULT_PAGES_BRANCH.into(),
+        };
+        for (contents, uploaded) in [("first", 2), ("second", 1)] {
+            fs::write(site.path().join("index.html"), contents)?;
+            let report = deploy(
+                "pages",
+                &target,
+                site.path(),
+                state.path(),
+                DeployOptions::default(),
+            )?;
+            assert!(report.deployed);
+            assert_eq!(report.plan.upload.len(), uploaded);
+        }
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(remote.path())
+            .args(["show", "gh-pages:index.html"])
+            .output()?;
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "second");
+        let log = Command::new("git")
+            .arg("-C")
+            .arg(remote.path())
+            .args(["rev-list", "--count", "gh-pages"])
+            .output()?;
+        assert_eq!(String::from_utf8_lossy(&log.stdout).trim(), "2");
+        Ok(())
+    }
+}
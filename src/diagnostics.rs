@@ -0,0 +1,1134 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Build Diagnostics
+//!
+//! Human-friendly rendering of build errors in the style of `cargo`'s
+//! diagnostics: the offending line of the frontmatter, config file or
+//! template is shown with a caret underline, followed by "did you mean"
+//! suggestions where a close match exists.
+//!
+//! ```text
+//! error: unknown config key `site_tile`
+//!  --> shokunin.toml:3:1
+//!   |
+//! 3 | site_tile = "Blog"
+//!   | ^^^^^^^^^
+//!   = help: did you mean `site_title`?
+//! ```
+//!
+//! A [`Diagnostic`] is a regular error type, so it can travel through
+//! `anyhow` and be recovered with [`report`] at the top level.
+//!
+//! Warnings about a file go through [`warn`]. Phases that process files
+//! on rayon workers run each file in a [`WarningLog::scope`], so the
+//! warnings are logged in path order once the phase is done instead of
+//! in whatever order the workers finished.
+
+use colored::Colorize;
+use regex::Regex;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt, mem,
+    path::{Component, Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{cmd::ShokuninConfig, interpolate};
+
+/// Config keys that are valid but absent from the serialized defaults,
+/// as dotted paths.
+const OPTIONAL_CONFIG_KEYS: &[&str] = &[
+    "serve_dir",
+    "theme",
+    "memory_budget",
+    "accessibility.contact_email",
+    "accessibility.contact_url",
+    "accessibility.audit_report",
+    "accessibility.fail_on",
+    "concurrency.render",
+    "concurrency.io",
+    "consent.privacy_url",
+    "external_links.icon_class",
+    "humans.last_updated",
+    "lint.max_heading_depth",
+    "lint.max_image_bytes",
+    "log.file",
+    "permalinks.default",
+    "preview.base_url",
+    "profiles.dev.base_url",
+    "profiles.dev.minify",
+    "profiles.dev.drafts",
+    "profiles.dev.compress",
+    "profiles.dev.source_maps",
+    "profiles.prod.base_url",
+    "profiles.prod.minify",
+    "profiles.prod.drafts",
+    "profiles.prod.compress",
+    "profiles.prod.source_maps",
+    "security_headers.report_uri",
+    "security_txt.expires",
+    "security_txt.encryption",
+    "security_txt.acknowledgments",
+    "security_txt.canonical",
+    "security_txt.policy",
+    "security_txt.hiring",
+    "security_txt.sign_command",
+    "serve.cert",
+    "serve.key",
+    "performance.budgets.total",
+    "performance.budgets.html",
+    "performance.budgets.css",
+    "performance.budgets.js",
+    "performance.budgets.images",
+    "performance.budgets.fonts",
+];
+
+/// Config tables whose keys are user-defined.
+const FREE_FORM_CONFIG_TABLES: &[&str] = &[
+    "deploy",
+    "file_limits.types",
+    "lint.required_frontmatter",
+    "markdown.diagrams.commands",
+    "markdown.emoji.custom",
+    "markdown.language_aliases",
+    "menus",
+    "permalinks.sections",
+    "sections.dirs",
+    "security_headers.csp_extra",
+    "security_headers.custom",
+    "taxonomies",
+];
+
+/// Severity of a diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The build cannot continue.
+    Error,
+    /// The build continues, but the input is probably wrong.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+thread_local! {
+    /// Warnings of the file processed on this thread, inside a
+    /// [`WarningLog::scope`].
+    static SCOPED_WARNINGS: RefCell<Option<Vec<String>>> =
+        RefCell::new(None);
+}
+
+/// Logs a warning about the file being processed, or keeps it for its
+/// [`WarningLog`] inside a [`WarningLog::scope`].
+pub fn warn(message: impl Into<String>) {
+    let message = message.into();
+    let unscoped = SCOPED_WARNINGS.with(|scoped| {
+        match scoped.borrow_mut().as_mut() {
+            Some(warnings) => {
+                warnings.push(message);
+                None
+            }
+            None => Some(message),
+        }
+    });
+    if let Some(message) = unscoped {
+        log::warn!("{}", message);
+    }
+}
+
+/// Warnings of files processed in parallel, logged in path order.
+///
+/// Warnings still held when the log is dropped are logged then, so an
+/// error ending a phase early does not lose them.
+///
+/// ```
+/// use rayon::prelude::*;
+/// use ssg::diagnostics::{self, WarningLog};
+/// use std::path::Path;
+///
+/// let log = WarningLog::new();
+/// ["b.md", "a.md"].par_iter().for_each(|file| {
+///     log.scope(Path::new(file), || diagnostics::warn("no title"));
+/// });
+/// let files: Vec<_> = log.take().into_iter().map(|(file, _)| file).collect();
+/// assert_eq!(files, [Path::new("a.md"), Path::new("b.md")]);
+/// ```
+#[derive(Debug, Default)]
+pub struct WarningLog {
+    warnings: Mutex<BTreeMap<PathBuf, Vec<String>>>,
+}
+
+impl WarningLog {
+    /// Creates an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `f` for `file`, keeping the warnings it emits through
+    /// [`warn`] on this thread until the log is flushed.
+    pub fn scope<T>(&self, file: &Path, f: impl FnOnce() -> T) -> T {
+        let outer = SCOPED_WARNINGS
+            .with(|scoped| scoped.replace(Some(Vec::new())));
+        let value = f();
+        let warnings = SCOPED_WARNINGS
+            .with(|scoped| scoped.replace(outer))
+            .unwrap_or_default();
+        if !warnings.is_empty() {
+            self.warnings
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(file.to_path_buf())
+                .or_default()
+                .extend(warnings);
+        }
+        value
+    }
+
+    /// Removes and returns the warnings, sorted by file path and in the
+    /// order they were emitted for each file.
+    pub fn take(&self) -> Vec<(PathBuf, String)> {
+        let warnings = mem::take(
+            &mut *self
+                .warnings
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        warnings
+            .into_iter()
+            .flat_map(|(file, messages)| {
+                messages
+                    .into_iter()
+                    .map(move |message| (file.clone(), message))
+            })
+            .collect()
+    }
+
+    /// Logs the warnings in path order and returns how many there were.
+    pub fn flush(&self) -> usize {
+        let warnings = self.take();
+        for (file, message) in &warnings {
+            log::warn!("{}: {}", file.display(), message);
+        }
+        warnings.len()
+    }
+}
+
+impl Drop for WarningLog {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// A build error or warning pointing at a location in a source file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Severity of the diagnostic.
+    pub severity: Severity,
+    /// Short description of the problem.
+    pub message: String,
+    /// File the problem was found in.
+    pub file: Option<PathBuf>,
+    /// The offending source line, without its line ending.
+    pub snippet: Option<String>,
+    /// One-based line number.
+    pub line: usize,
+    /// One-based column of the first underlined character.
+    pub column: usize,
+    /// Number of characters to underline.
+    pub length: usize,
+    /// Suggested fix, e.g. "did you mean `title`?".
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    /// Creates an error diagnostic with the given message.
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            file: None,
+            snippet: None,
+            line: 0,
+            column: 0,
+            length: 0,
+            help: None,
+        }
+    }
+
+    /// Creates a warning diagnostic with the given message.
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(message)
+        }
+    }
+
+    /// Points the diagnostic at `line`/`column` of `source`.
+    ///
+    /// Lines and columns are one-based; a line outside `source` keeps the
+    /// location but shows no snippet.
+    pub fn with_location(
+        mut self,
+        file: impl Into<PathBuf>,
+        source: &str,
+        line: usize,
+        column: usize,
+        length: usize,
+    ) -> Self {
+        self.file = Some(file.into());
+        self.snippet = line
+            .checked_sub(1)
+            .and_then(|index| source.lines().nth(index))
+            .map(|text| text.trim_end_matches('\r').to_string());
+        self.line = line;
+        self.column = column.max(1);
+        self.length = length.max(1);
+        self
+    }
+
+    /// Adds a help line.
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Adds a "did you mean" help line if `name` is close to a candidate.
+    pub fn with_suggestion(
+        self,
+        name: &str,
+        candidates: &[&str],
+    ) -> Self {
+        match suggest(name, candidates.iter().copied()) {
+            Some(best) => {
+                self.with_help(format!("did you mean `{}`?", best))
+            }
+            None => self,
+        }
+    }
+
+    /// Renders the diagnostic, optionally with terminal colours.
+    pub fn render(&self, color: bool) -> String {
+        let paint = |text: &str, style: fn(&str) -> String| {
+            if color {
+                style(text)
+            } else {
+                text.to_string()
+            }
+        };
+        let accent: fn(&str) -> String = match self.severity {
+            Severity::Error => |s| s.red().bold().to_string(),
+            Severity::Warning => |s| s.yellow().bold().to_string(),
+        };
+        let blue: fn(&str) -> String = |s| s.blue().bold().to_string();
+
+        let mut out = format!(
+            "{}{} {}\n",
+            paint(&self.severity.to_string(), accent),
+            paint(":", |s| s.bold().to_string()),
+            paint(&self.message, |s| s.bold().to_string())
+        );
+
+        let gutter = " ".repeat(self.line.to_string().len());
+        if let Some(file) = &self.file {
+            out.push_str(&format!(
+                "{}{} {}:{}:{}\n",
+                gutter,
+                paint("-->", blue),
+                file.display(),
+                self.line,
+                self.column
+            ));
+        }
+
+        if let Some(snippet) = &self.snippet {
+            let bar = paint("|", blue);
+            let underline = format!(
+                "{}{}",
+                " ".repeat(self.column - 1),
+                "^".repeat(self.length)
+            );
+            out.push_str(&format!("{} {}\n", gutter, bar));
+            out.push_str(&format!(
+                "{} {} {}\n",
+                paint(&self.line.to_string(), blue),
+                bar,
+                snippet
+            ));
+            out.push_str(&format!(
+                "{} {} {}\n",
+                gutter,
+                bar,
+                paint(&underline, accent)
+            ));
+        }
+
+        if let Some(help) = &self.help {
+            out.push_str(&format!(
+                "{} {} help: {}\n",
+                gutter,
+                paint("=", blue),
+                help
+            ));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(false).trim_end())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Renders an error for the terminal.
+///
+/// If a [`Diagnostic`] is found in the error chain it is rendered with
+/// its snippet, preceded by the outer context messages. Other errors are
+/// rendered as a single `error:` line.
+pub fn report(error: &anyhow::Error, color: bool) -> String {
+    let mut context = Vec::new();
+    for cause in error.chain() {
+        if let Some(diagnostic) = cause.downcast_ref::<Diagnostic>() {
+            let mut out = String::new();
+            for message in context {
+                out.push_str(&format!("{}\n", message));
+            }
+            out.push_str(&diagnostic.render(color));
+            return out;
+        }
+        context.push(cause.to_string());
+    }
+    Diagnostic::error(format!("{:#}", error)).render(color)
+}
+
+/// Renders an [`Error`](crate::Error) of the public API like
+/// [`report`], with the diagnostics of a failed build stage.
+pub fn report_error(error: &crate::Error, color: bool) -> String {
+    match error {
+        crate::Error::Compile(error) => report(error, color),
+        error => Diagnostic::error(error.to_string()).render(color),
+    }
+}
+
+/// Returns the candidate closest to `name`, if any is close enough.
+///
+/// A candidate qualifies when its edit distance is at most a third of
+/// the name's length (and at least one edit).
+///
+/// # Examples
+///
+/// ```
+/// use ssg::diagnostics::suggest;
+///
+/// let keys = ["site_title", "site_name", "language"];
+/// assert_eq!(suggest("site_tile", keys), Some("site_title"));
+/// assert_eq!(suggest("colour", keys), None);
+/// ```
+pub fn suggest<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)))
+        .map(|(_, candidate)| candidate)
+}
+
+/// Edit distance between two strings, counting insertions, deletions,
+/// substitutions and transpositions of adjacent characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1
+                && j > 1
+                && a[i - 1] == b[j - 2]
+                && a[i - 2] == b[j - 1]
+            {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Builds a diagnostic for a YAML error in a frontmatter block.
+///
+/// `source` is the whole content file; the frontmatter is assumed to
+/// start on its second line, after the opening `---`.
+pub fn frontmatter_error(
+    file: &Path,
+    source: &str,
+    error: &serde_yaml::Error,
+) -> Diagnostic {
+    let message = error.to_string();
+    let message = message
+        .split(" at line ")
+        .next()
+        .unwrap_or(&message)
+        .to_string();
+    let diagnostic =
+        Diagnostic::error(format!("invalid frontmatter: {}", message));
+    match error.location() {
+        Some(location) => diagnostic.with_location(
+            file,
+            source,
+            location.line() + 1,
+            location.column(),
+            1,
+        ),
+        None => Diagnostic {
+            file: Some(file.to_path_buf()),
+            ..diagnostic
+        },
+    }
+}
+
+/// Reports a template variable that has no value.
+///
+/// Points at the first `{{name}}` tag in `source`, including tags with
+/// spaces or filters such as `{{ name | upper }}`, and suggests the
+/// closest known variable.
+pub fn unknown_template_variable(
+    file: &Path,
+    source: &str,
+    name: &str,
+    known: &[&str],
+) -> Diagnostic {
+    let diagnostic = Diagnostic::warning(format!(
+        "unknown template variable `{}`",
+        name
+    ))
+    .with_suggestion(name, known);
+
+    let tag = Regex::new(&format!(
+        r"\{{\{{\s*{}\s*(\|[^}}]*)?\}}\}}",
+        regex::escape(name)
+    ));
+    for (index, line) in source.lines().enumerate() {
+        if let Some(found) =
+            tag.as_ref().ok().and_then(|re| re.find(line))
+        {
+            let column = line[..found.start()].chars().count() + 1;
+            return diagnostic.with_location(
+                file,
+                source,
+                index + 1,
+                column,
+                found.as_str().chars().count(),
+            );
+        }
+    }
+    Diagnostic {
+        file: Some(file.to_path_buf()),
+        ..diagnostic
+    }
+}
+
+/// Checks a TOML config file against the configuration schema.
+///
+/// Reports unknown keys (warnings), syntax errors and values of the wrong
+/// type, failed validation, and conflicting directory settings such as an
+/// output directory that is also the content directory. Relative
+/// directories are compared as written, and `${NAME}` variables are
+/// replaced from the environment as when the config is loaded.
+pub fn check_config(file: &Path, source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = check_config_keys(file, source);
+
+    let parsed = interpolate::from_toml_str::<ShokuninConfig>(source);
+    let config = match parsed {
+        Ok(config) => config,
+        Err(e) => {
+            let diagnostic = Diagnostic::error(format!(
+                "invalid config: {}",
+                e.message()
+            ));
+            diagnostics.push(match e.span() {
+                Some(span) => {
+                    let (line, column) = line_col(source, span.start);
+                    let length = source
+                        .get(span.clone())
+                        .and_then(|text| text.lines().next())
+                        .map_or(1, |text| text.chars().count());
+                    diagnostic.with_location(
+                        file, source, line, column, length,
+                    )
+                }
+                None => Diagnostic {
+                    file: Some(file.to_path_buf()),
+                    ..diagnostic
+                },
+            });
+            return diagnostics;
+        }
+    };
+
+    if let Err(e) = config.validate() {
+        diagnostics.push(Diagnostic {
+            file: Some(file.to_path_buf()),
+            ..Diagnostic::error(e.to_string())
+        });
+    }
+
+    let mut directories = vec![
+        ("content_dir", &config.content_dir),
+        ("template_dir", &config.template_dir),
+        ("output_dir", &config.output_dir),
+    ];
+    if let Some(serve_dir) = &config.serve_dir {
+        directories.push(("serve_dir", serve_dir));
+    }
+    for (i, (key, dir)) in directories.iter().enumerate().skip(1) {
+        for (other, other_dir) in &directories[..i] {
+            let same =
+                normal_components(dir).eq(normal_components(other_dir));
+            if !same || (*key == "serve_dir" && *other == "output_dir")
+            {
+                continue;
+            }
+            let diagnostic = Diagnostic::error(format!(
+                "`{}` and `{}` must be different directories",
+                key, other
+            ))
+            .with_help(format!(
+                "both point to `{}`; a build would overwrite its inputs",
+                dir.display()
+            ));
+            diagnostics.push(match find_toml_key(source, "", key) {
+                Some((line, column)) => diagnostic.with_location(
+                    file,
+                    source,
+                    line,
+                    column,
+                    key.len(),
+                ),
+                None => diagnostic,
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// Path components without `.` segments.
+fn normal_components(
+    path: &Path,
+) -> impl Iterator<Item = Component<'_>> {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+}
+
+/// Converts a byte offset in `source` to a one-based line and column.
+fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = source.get(..offset).unwrap_or(source);
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map_or(0, |text| text.chars().count())
+        + 1;
+    (line, column)
+}
+
+/// Checks a TOML config file for keys the configuration does not know.
+///
+/// Unknown keys are otherwise silently ignored, so each one is reported
+/// as a warning with a suggestion where possible. Malformed TOML yields no
+/// diagnostics; it is reported when the config is loaded.
+pub fn check_config_keys(file: &Path, source: &str) -> Vec<Diagnostic> {
+    let (document, schema) = match (
+        source.parse::<toml::Table>(),
+        toml::Value::try_from(ShokuninConfig::default()),
+    ) {
+        (Ok(document), Ok(toml::Value::Table(schema))) => {
+            (document, schema)
+        }
+        _ => return Vec::new(),
+    };
+
+    let mut diagnostics = Vec::new();
+    check_table(file, source, "", &document, &schema, &mut diagnostics);
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+    diagnostics
+}
+
+fn check_table(
+    file: &Path,
+    source: &str,
+    prefix: &str,
+    table: &toml::Table,
+    schema: &toml::Table,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if FREE_FORM_CONFIG_TABLES.contains(&prefix) {
+        return;
+    }
+
+    let mut known: BTreeSet<&str> =
+        schema.keys().map(String::as_str).collect();
+    known.extend(OPTIONAL_CONFIG_KEYS.iter().filter_map(|optional| {
+        match optional.rsplit_once('.') {
+            Some((parent, key)) if parent == prefix => Some(key),
+            None if prefix.is_empty() => Some(*optional),
+            _ => None,
+        }
+    }));
+
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match (value, schema.get(key)) {
+            (
+                toml::Value::Table(nested),
+                Some(toml::Value::Table(inner)),
+            ) => {
+                check_table(
+                    file,
+                    source,
+                    &path,
+                    nested,
+                    inner,
+                    diagnostics,
+                );
+            }
+            (_, Some(_)) => {}
+            _ if known.contains(key.as_str()) => {}
+            _ => {
+                let diagnostic = Diagnostic::warning(format!(
+                    "unknown config key `{}`",
+                    path
+                ))
+                .with_suggestion(
+                    key,
+                    &known.iter().copied().collect::<Vec<_>>(),
+                );
+                diagnostics.push(
+                    match find_toml_key(source, prefix, key) {
+                        Some((line, column)) => diagnostic
+                            .with_location(
+                                file,
+                                source,
+                                line,
+                                column,
+                                key.chars().count(),
+                            ),
+                        None => Diagnostic {
+                            file: Some(file.to_path_buf()),
+                            ..diagnostic
+                        },
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Finds the line and column of `key` inside the `[table]` section.
+fn find_toml_key(
+    source: &str,
+    table: &str,
+    key: &str,
+) -> Option<(usize, usize)> {
+    let mut current = String::new();
+    for (index, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(header) = trimmed.strip_prefix('[') {
+            current = header
+                .trim_start_matches('[')
+                .split(']')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_string();
+            continue;
+        }
+        if current != table {
+            continue;
+        }
+        if let Some(rest) = trimmed.strip_prefix(key) {
+            if rest.trim_start().starts_with('=') {
+                let column = line.len() - trimmed.len() + 1;
+                return Some((index + 1, column));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+
+    #[test]
+    fn test_warning_log_orders_by_path() {
+        let log = WarningLog::new();
+        let files: Vec<String> = (0..64)
+            .rev()
+            .map(|i| format!("page-{:02}.md", i))
+            .collect();
+        files.par_iter().for_each(|file| {
+            log.scope(Path::new(file), || {
+                warn(format!("first in {}", file));
+                let nested = WarningLog::new();
+                nested.scope(Path::new("nested.md"), || warn("inner"));
+                assert_eq!(nested.take().len(), 1);
+                warn(format!("second in {}", file));
+            });
+        });
+
+        let warnings = log.take();
+        assert_eq!(warnings.len(), 128);
+        assert_eq!(
+            warnings[0],
+            (PathBuf::from("page-00.md"), "first in page-00.md".into())
+        );
+        assert_eq!(warnings[1].1, "second in page-00.md");
+        assert_eq!(warnings[127].0, PathBuf::from("page-63.md"));
+        assert!(log.take().is_empty());
+        assert_eq!(log.flush(), 0);
+    }
+
+    #[test]
+    fn test_suggest_and_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("tilte", "title"), 1);
+        assert_eq!(suggest("tilte", ["title", "date"]), Some("title"));
+        assert_eq!(suggest("xyz", ["title", "date"]), None);
+    }
+
+    #[test]
+    fn test_render_plain_with_caret() {
+        let source = "site_name = \"a\"\nsite_tile = \"Blog\"\n";
+        let rendered =
+            Diagnostic::error("unknown config key `site_tile`")
+                .with_location("shokunin.toml", source, 2, 1, 9)
+                .with_suggestion(
+                    "site_tile",
+                    &["site_title", "site_name"],
+                )
+                .render(false);
+
+        assert_eq!(
+            rendered,
+            "error: unknown config key `site_tile`\n \
+             --> shokunin.toml:2:1\n  \
+             |\n\
+             2 | site_tile = \"Blog\"\n  \
+             | ^^^^^^^^^\n  \
+             = help: did you mean `site_title`?\n"
+        );
+    }
+
+    #[test]
+    fn test_check_config_keys() {
+        let source = "site_name = \"a\"\n\
+            site_tile = \"Blog\"\n\
+            serve_dir = \"public\"\n\
+            \n\
+            [markdown]\n\
+            line_numbrs = true\n\
+            \n\
+            [markdown.language_aliases]\n\
+            shell = \"bash\"\n\
+            \n\
+            [accessibility]\n\
+            contact_email = \"a@example.com\"\n";
+        let diagnostics =
+            check_config_keys(Path::new("shokunin.toml"), source);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].line, 2);
+        assert_eq!(
+            diagnostics[0].help.as_deref(),
+            Some("did you mean `site_title`?")
+        );
+        assert_eq!(
+            diagnostics[1].message,
+            "unknown config key `markdown.line_numbrs`"
+        );
+        assert_eq!(diagnostics[1].line, 6);
+    }
+
+    /// Returns the value at the dotted `key` of `table`.
+    fn lookup<'a>(
+        table: &'a toml::Table,
+        key: &str,
+    ) -> Option<&'a toml::Value> {
+        match key.split_once('.') {
+            Some((head, rest)) => {
+                lookup(table.get(head)?.as_table()?, rest)
+            }
+            None => table.get(key),
+        }
+    }
+
+    #[test]
+    fn test_config_key_lists_cover_a_full_config() {
+        use crate::{
+            accessibility, deploy::DeployTarget, menus::MenuEntry,
+            sections::SectionOrder, taxonomies::TaxonomyOptions,
+        };
+
+        let text = || Some("x".to_string());
+        let mut config = ShokuninConfig {
+            serve_dir: Some("public".into()),
+            theme: text(),
+            memory_budget: Some(64),
+            ..Default::default()
+        };
+        config.accessibility.contact_email = text();
+        config.accessibility.contact_url = text();
+        config.accessibility.audit_report = Some("a11y.json".into());
+        config.accessibility.fail_on =
+            Some(accessibility::Severity::Error);
+        config.concurrency.render = Some(2);
+        config.concurrency.io = Some(2);
+        config.consent.privacy_url = text();
+        config.external_links.icon_class = text();
+        config.humans.last_updated = text();
+        config.lint.max_heading_depth = Some(3);
+        config.lint.max_image_bytes = Some(1);
+        config.log.file = Some("build.log".into());
+        config.permalinks.default = text();
+        config.preview.base_url = text();
+        for profile in
+            [&mut config.profiles.dev, &mut config.profiles.prod]
+        {
+            profile.base_url = text();
+            profile.minify = Some(true);
+            profile.drafts = Some(true);
+            profile.compress = Some(true);
+            profile.source_maps = Some(true);
+        }
+        config.security_headers.report_uri = text();
+        let security_txt = &mut config.security_txt;
+        security_txt.expires = text();
+        security_txt.encryption = text();
+        security_txt.acknowledgments = text();
+        security_txt.canonical = text();
+        security_txt.policy = text();
+        security_txt.hiring = text();
+        security_txt.sign_command = Some(vec!["gpg".into()]);
+        config.serve.cert = Some("cert.pem".into());
+        config.serve.key = Some("key.pem".into());
+        let budgets = &mut config.performance.budgets;
+        budgets.total = Some(1);
+        budgets.html = Some(1);
+        budgets.css = Some(1);
+        budgets.js = Some(1);
+        budgets.images = Some(1);
+        budgets.fonts = Some(1);
+
+        let _ = config.deploy.insert(
+            "production".into(),
+            DeployTarget::Rsync {
+                destination: "host:/srv".into(),
+                port: Some(22),
+            },
+        );
+        config.file_limits = config.file_limits.with_type("mp4", 1);
+        let _ = config
+            .lint
+            .required_frontmatter
+            .insert("blog".into(), vec!["title".into()]);
+        let markdown = &mut config.markdown;
+        let _ = markdown
+            .diagrams
+            .commands
+            .insert("graphviz".into(), vec!["dot".into()]);
+        let _ = markdown.emoji.custom.insert("ok".into(), "x".into());
+        let _ = markdown
+            .language_aliases
+            .insert("shell".into(), "bash".into());
+        let _ = config.menus.insert(
+            "main".into(),
+            vec![MenuEntry {
+                identifier: text(),
+                parent: text(),
+                ..MenuEntry::default()
+            }],
+        );
+        let _ = config
+            .permalinks
+            .sections
+            .insert("blog".into(), ":slug/".into());
+        let _ = config.sections.dirs.insert(
+            "blog".into(),
+            SectionOrder {
+                limit: Some(1),
+                ..SectionOrder::default()
+            },
+        );
+        let _ = config
+            .security_headers
+            .csp_extra
+            .insert("connect-src".into(), vec!["'self'".into()]);
+        let _ = config
+            .security_headers
+            .custom
+            .insert("X-Test".into(), "1".into());
+        let _ = config.taxonomies.insert(
+            "tags".into(),
+            TaxonomyOptions {
+                key: text(),
+                ..TaxonomyOptions::default()
+            },
+        );
+
+        let source = toml::to_string(&config).unwrap();
+        let diagnostics =
+            check_config_keys(Path::new("shokunin.toml"), &source);
+        assert!(
+            diagnostics.is_empty(),
+            "{:?}",
+            diagnostics
+                .iter()
+                .map(|diagnostic| &diagnostic.message)
+                .collect::<Vec<_>>()
+        );
+
+        let document: toml::Table = source.parse().unwrap();
+        for key in
+            OPTIONAL_CONFIG_KEYS.iter().chain(FREE_FORM_CONFIG_TABLES)
+        {
+            assert!(
+                lookup(&document, key).is_some(),
+                "`{}` is not a config key",
+                key
+            );
+        }
+    }
+
+    const VALID_CONFIG: &str = "site_name = \"a\"\n\
+        content_dir = \"content\"\n\
+        output_dir = \"public\"\n\
+        template_dir = \"templates\"\n\
+        base_url = \"http://example.com\"\n\
+        site_title = \"A\"\n\
+        site_description = \"B\"\n\
+        language = \"en-GB\"\n";
+
+    #[test]
+    fn test_check_config_valid() {
+        let file = Path::new("shokunin.toml");
+        assert!(check_config(file, VALID_CONFIG).is_empty());
+    }
+
+    #[test]
+    fn test_check_config_wrong_type() {
+        let source = format!(
+            "{}[markdown]\nline_numbers = \"yes\"\n",
+            VALID_CONFIG
+        );
+        let diagnostics =
+            check_config(Path::new("shokunin.toml"), &source);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert!(diagnostics[0].message.starts_with("invalid config"));
+        assert_eq!(
+            (diagnostics[0].line, diagnostics[0].column),
+            (10, 16)
+        );
+        assert_eq!(diagnostics[0].length, 5);
+    }
+
+    #[test]
+    fn test_check_config_conflicting_directories() {
+        let source = VALID_CONFIG.replace(
+            "output_dir = \"public\"",
+            "output_dir = \"./content\"",
+        );
+        let diagnostics =
+            check_config(Path::new("shokunin.toml"), &source);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "`output_dir` and `content_dir` must be different directories"
+        );
+        assert_eq!(diagnostics[0].line, 3);
+    }
+
+    #[test]
+    fn test_frontmatter_error_points_at_line() {
+        let source = "---\ntitle: Hello\ntags: [unclosed\n---\nBody";
+        let yaml = "title: Hello\ntags: [unclosed";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(yaml)
+            .unwrap_err();
+        let diagnostic =
+            frontmatter_error(Path::new("post.md"), source, &error);
+
+        assert!(diagnostic.message.starts_with("invalid frontmatter"));
+        assert!(diagnostic.line >= 3);
+        assert!(diagnostic.snippet.is_some());
+    }
+
+    #[test]
+    fn test_unknown_template_variable_and_report() {
+        let source = "<h1>{{title}}</h1>\n<p>{{descripton}}</p>";
+        let diagnostic = unknown_template_variable(
+            Path::new("page.html"),
+            source,
+            "descripton",
+            &["title", "description"],
+        );
+        assert_eq!((diagnostic.line, diagnostic.column), (2, 4));
+        assert_eq!(diagnostic.length, 14);
+
+        let error = anyhow::Error::new(diagnostic)
+            .context("Failed to render page.html");
+        let report = report(&error, false);
+        assert!(report.starts_with("Failed to render page.html\n"));
+        assert!(report.contains("did you mean `description`?"));
+
+        let plain = anyhow::anyhow!("inner").context("outer");
+        assert_eq!(
+            super::report(&plain, false),
+            "error: outer: inner\n"
+        );
+
+        let error = crate::Error::from(error);
+        assert!(report_error(&error, false)
+            .contains("did you mean `description`?"));
+        let error =
+            crate::Error::validation("../site", "Path is unsafe");
+        assert_eq!(
+            report_error(&error, false),
+            "error: Path is unsafe: ../site\n"
+        );
+    }
+}
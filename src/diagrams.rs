@@ -0,0 +1,450 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Diagrams
+//!
+//! Turns fenced code blocks of diagram languages into diagrams:
+//!
+//! ````markdown
+//! ```mermaid
+//! graph LR
+//!     Markdown --> HTML
+//! ```
+//! ````
+//!
+//! Options live in the `[markdown.diagrams]` table of the configuration
+//! file:
+//!
+//! ```toml
+//! [markdown.diagrams]
+//! enabled = true
+//! mermaid_url = "https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.min.js"
+//!
+//! [markdown.diagrams.commands]
+//! graphviz = ["dot", "-Tsvg"]
+//! plantuml = ["plantuml", "-tsvg", "-pipe"]
+//! ```
+//!
+//! A block whose language has a command is rendered at build time: the
+//! command is run with the block on its standard input, and the SVG it
+//! writes to its standard output replaces the block in a
+//! `<figure class="diagram diagram-<language>">`. A command is a program
+//! followed by its arguments, run without a shell. Rendered diagrams are
+//! cached in `.ssg-cache/diagrams/` by command and source, so unchanged
+//! diagrams do not run the tool again.
+//!
+//! A `mermaid` block without a command is left for the browser: it
+//! becomes a `<pre class="mermaid">`, and pages holding one load Mermaid
+//! from `mermaid_url`. Blocks of other languages without a command stay
+//! code blocks.
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write as _,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+};
+
+use crate::{
+    collect_files_recursive,
+    markdown::{
+        decode_entities, extract_code, CodeBlockInfo, CODE_BLOCK_RE,
+    },
+    template::{escape_html, hex},
+};
+
+/// Default cache directory, relative to the project root.
+pub const DIAGRAM_CACHE_DIR: &str = ".ssg-cache/diagrams";
+
+/// Default location of the Mermaid script.
+pub const DEFAULT_MERMAID_URL: &str =
+    "https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.min.js";
+
+/// Id of the injected Mermaid script.
+pub const MERMAID_SCRIPT_ID: &str = "ssg-mermaid";
+
+/// Fence language rendered in the browser without a command.
+pub const MERMAID_LANGUAGE: &str = "mermaid";
+
+/// Matches the closing `</body>` tag.
+static BODY_END_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)</body\s*>")
+        .unwrap_or_else(|e| panic!("Invalid body regex: {}", e))
+});
+
+/// `[markdown.diagrams]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiagramOptions {
+    /// Renders diagram code blocks.
+    pub enabled: bool,
+    /// Location of the Mermaid script for blocks rendered in the
+    /// browser.
+    pub mermaid_url: String,
+    /// Commands rendering a fence language to SVG, as a program and its
+    /// arguments, e.g. `graphviz = ["dot", "-Tsvg"]`.
+    pub commands: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for DiagramOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mermaid_url: DEFAULT_MERMAID_URL.to_string(),
+            commands: BTreeMap::new(),
+        }
+    }
+}
+
+impl DiagramOptions {
+    /// Checks that the Mermaid location is an HTTPS URL or a site path
+    /// and that every command has a language and a program.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.mermaid_url.starts_with("https://")
+                || self.mermaid_url.starts_with('/'),
+            "mermaid_url `{}` must be an https:// URL or start with /",
+            self.mermaid_url
+        );
+        for (language, command) in &self.commands {
+            ensure!(
+                !language.is_empty()
+                    && language.chars().all(|c| {
+                        c.is_ascii_alphanumeric()
+                            || c == '-'
+                            || c == '_'
+                    }),
+                "commands: `{}` is not a fence language",
+                language
+            );
+            let program = command.first().map_or("", String::as_str);
+            ensure!(
+                !program.trim().is_empty(),
+                "commands.{} must name a program",
+                language
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Renders the diagram code blocks of `html`, with rendered diagrams
+/// cached in `cache_dir`. Returns `None` if the page has none.
+///
+/// # Errors
+///
+/// Returns an error if a diagram command cannot be run, fails or does
+/// not write SVG.
+pub fn render_diagrams(
+    html: &str,
+    options: &DiagramOptions,
+    cache_dir: &Path,
+) -> Result<Option<String>> {
+    let mut output = String::with_capacity(html.len());
+    let mut last_end = 0;
+    let mut mermaid = false;
+    for caps in CODE_BLOCK_RE.captures_iter(html) {
+        let whole = match caps.get(0) {
+            Some(whole) => whole,
+            None => continue,
+        };
+        let diagram = match render_block(&caps, options, cache_dir)? {
+            Some(diagram) => diagram,
+            None => continue,
+        };
+        mermaid |= diagram.starts_with("<pre class=\"mermaid\">");
+        output.push_str(&html[last_end..whole.start()]);
+        output.push_str(&diagram);
+        last_end = whole.end();
+    }
+    if last_end == 0 {
+        return Ok(None);
+    }
+    output.push_str(&html[last_end..]);
+
+    if mermaid && !output.contains(MERMAID_SCRIPT_ID) {
+        if let Some(body) = BODY_END_RE.find(&output) {
+            output.insert_str(
+                body.start(),
+                &format!(
+                    "<script src=\"{}\" id=\"{}\"></script>\n\
+                     <script>mermaid.initialize({{ startOnLoad: true }});</script>\n",
+                    escape_html(&options.mermaid_url),
+                    MERMAID_SCRIPT_ID
+                ),
+            );
+        }
+    }
+    Ok(Some(output))
+}
+
+/// Applies [`render_diagrams`] to every HTML file below `site_dir`.
+///
+/// Returns the number of files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written, or a diagram
+/// fails to render.
+pub fn render_diagrams_site(
+    site_dir: &Path,
+    options: &DiagramOptions,
+    cache_dir: &Path,
+) -> Result<usize> {
+    if !options.enabled || !site_dir.exists() {
+        return Ok(0);
+    }
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+
+    let mut rewritten = 0;
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let rendered = match render_diagrams(&html, options, cache_dir)
+            .with_context(|| {
+                format!(
+                    "Failed to render diagrams in {}",
+                    path.display()
+                )
+            })? {
+            Some(rendered) => rendered,
+            None => continue,
+        };
+        fs::write(&path, rendered).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+/// Renders one code block, or returns `None` if its language is not a
+/// diagram language.
+fn render_block(
+    caps: &Captures<'_>,
+    options: &DiagramOptions,
+    cache_dir: &Path,
+) -> Result<Option<String>> {
+    let language =
+        CodeBlockInfo::parse(&decode_entities(&caps[1])).language;
+    let source = extract_code(&caps[2]);
+    match options.commands.get(&language) {
+        Some(command) => {
+            let svg = render_svg(command, &source, cache_dir)?;
+            Ok(Some(format!(
+                "<figure class=\"diagram diagram-{}\">{}</figure>",
+                language, svg
+            )))
+        }
+        None if language == MERMAID_LANGUAGE => Ok(Some(format!(
+            "<pre class=\"mermaid\">{}</pre>",
+            escape_html(&source)
+        ))),
+        None => Ok(None),
+    }
+}
+
+/// Renders `source` to SVG with `command`, through the cache.
+fn render_svg(
+    command: &[String],
+    source: &str,
+    cache_dir: &Path,
+) -> Result<String> {
+    let entry = cache_entry(cache_dir, command, source);
+    if let Ok(svg) = fs::read_to_string(&entry) {
+        return Ok(svg);
+    }
+
+    let svg = run_command(command, source)?;
+    fs::create_dir_all(cache_dir).with_context(|| {
+        format!("Failed to create {}", cache_dir.display())
+    })?;
+    fs::write(&entry, &svg).with_context(|| {
+        format!("Failed to write {}", entry.display())
+    })?;
+    Ok(svg)
+}
+
+/// Returns the cache file of the SVG of `source` rendered by `command`.
+fn cache_entry(
+    cache_dir: &Path,
+    command: &[String],
+    source: &str,
+) -> PathBuf {
+    let mut hasher = Sha256::new();
+    for arg in command {
+        hasher.update(arg.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update([0]);
+    hasher.update(source.as_bytes());
+    cache_dir.join(format!("{}.svg", hex(&hasher.finalize())))
+}
+
+/// Runs `command` with `source` on its standard input, returning the
+/// `<svg>` element it writes without any XML declaration or doctype
+/// before it.
+fn run_command(command: &[String], source: &str) -> Result<String> {
+    let stdout = pipe_command(command, source)?;
+    match stdout.find("<svg") {
+        Some(start) => Ok(stdout[start..].trim_end().to_string()),
+        None => bail!("`{}` did not write an SVG", command.join(" ")),
+    }
+}
+
+/// Runs `command`, a program and its arguments, without a shell and
+/// with `input` on its standard input, returning its standard output.
+///
+/// The input is written from another thread while the output is read,
+/// so a command that writes before it has read all of its input does
+/// not block on a full pipe.
+pub(crate) fn pipe_command(
+    command: &[String],
+    input: &str,
+) -> Result<String> {
+    let (program, args) = match command.split_first() {
+        Some((program, args)) if !program.trim().is_empty() => {
+            (program, args)
+        }
+        _ => bail!("The command is empty"),
+    };
+    let display = command.join(" ");
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| {
+            format!("Failed to run `{}`; is it installed?", program)
+        })?;
+    let writer = child.stdin.take().map(|mut stdin| {
+        let input = input.as_bytes().to_vec();
+        thread::spawn(move || stdin.write_all(&input))
+    });
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to run `{}`", display))?;
+    if !output.status.success() {
+        bail!(
+            "`{}` failed ({}): {}",
+            display,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    if let Some(writer) = writer {
+        writer
+            .join()
+            .map_err(|_| anyhow!("Writing to `{}` panicked", display))?
+            .with_context(|| {
+                format!("Failed to write to `{}`", display)
+            })?;
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_mermaid_rendered_in_the_browser() -> Result<()> {
+        let html = "<html><body>\
+                    <pre><code class=\"language-mermaid\">graph LR\nA --&gt; B\n</code></pre>\
+                    <pre><code class=\"language-rust\">fn main() {}</code></pre>\
+                    </body></html>";
+        let options = DiagramOptions {
+            enabled: true,
+            ..DiagramOptions::default()
+        };
+        let cache = tempdir()?;
+        let rendered =
+            render_diagrams(html, &options, cache.path())?.unwrap();
+        assert!(rendered.contains(
+            "<pre class=\"mermaid\">graph LR\nA --&gt; B\n</pre>"
+        ));
+        assert!(rendered.contains("<code class=\"language-rust\">"));
+        assert!(
+            rendered.find(MERMAID_SCRIPT_ID) < rendered.find("</body>")
+        );
+        assert_eq!(
+            render_diagrams(&rendered, &options, cache.path())?,
+            None
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_renders_and_caches_svg() -> Result<()> {
+        let cache = tempdir()?;
+        let mut options = DiagramOptions {
+            enabled: true,
+            ..DiagramOptions::default()
+        };
+        let _ =
+            options.commands.insert("svg".into(), vec!["cat".into()]);
+        let html = "<pre><code class=\"language-svg\">&lt;?xml version=\"1.0\"?&gt;\n&lt;svg&gt;&lt;/svg&gt;\n</code></pre>";
+        assert_eq!(
+            render_diagrams(html, &options, cache.path())?.unwrap(),
+            "<figure class=\"diagram diagram-svg\"><svg></svg></figure>"
+        );
+        assert_eq!(fs::read_dir(cache.path())?.count(), 1);
+
+        let _ =
+            options.commands.insert("svg".into(), vec!["false".into()]);
+        assert!(render_diagrams(html, &options, cache.path()).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pipe_command_large_input() -> Result<()> {
+        let input = "<svg></svg>\n".repeat(100_000);
+        assert_eq!(pipe_command(&["cat".into()], &input)?, input);
+        assert!(pipe_command(&[], &input).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut options = DiagramOptions::default();
+        assert!(options.validate().is_ok());
+        let _ = options.commands.insert(
+            "graphviz".into(),
+            vec!["dot".into(), "-Tsvg".into()],
+        );
+        assert!(options.validate().is_ok());
+        let _ = options
+            .commands
+            .insert("plantuml".into(), vec![" ".into()]);
+        assert!(options.validate().is_err());
+        let _ = options.commands.insert("plantuml".into(), Vec::new());
+        assert!(options.validate().is_err());
+        options.mermaid_url =
+            "http://cdn.example.com/mermaid.js".into();
+        assert!(options.validate().is_err());
+    }
+}
@@ -0,0 +1,473 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # oEmbed Embeds
+//!
+//! Turns a YouTube, Vimeo or Twitter/X URL on its own line in Markdown
+//! into embed markup at build time:
+//!
+//! ```markdown
+//! Here is the talk:
+//!
+//! https://www.youtube.com/watch?v=dQw4w9WgXcQ
+//! ```
+//!
+//! Options live in the `[embeds]` table of the configuration file:
+//!
+//! ```toml
+//! [embeds]
+//! enabled = true
+//! timeout_secs = 10
+//! cache_ttl_secs = 604800
+//! ```
+//!
+//! Videos become lazy-loaded iframes on the privacy-enhanced players
+//! (`youtube-nocookie.com` and Vimeo with `dnt=1`), titled from the
+//! provider's oEmbed response. Posts become the provider's static
+//! `<blockquote>` without the widget script. Responses are cached in
+//! `.ssg-cache/oembed/` like [`fetch_json`](crate::fetch) responses.
+//!
+//! Without network access (or without the `fetch` feature) and without
+//! a cached response, videos are still embedded from the ID in the URL
+//! and posts fall back to a plain quoted link, so builds never fail on
+//! an unreachable provider.
+
+use anyhow::{ensure, Context, Result};
+use log::warn;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use url::Url;
+
+use crate::{
+    collect_files_recursive,
+    fetch::{FetchOptions, JsonFetcher},
+    template::escape_html,
+};
+
+/// Default cache directory, relative to the project root.
+pub const OEMBED_CACHE_DIR: &str = ".ssg-cache/oembed";
+
+/// Hosts of the oEmbed endpoints.
+const ENDPOINT_HOSTS: &[&str] =
+    &["www.youtube.com", "vimeo.com", "publish.twitter.com"];
+
+/// Matches a paragraph holding only a URL, bare or as a link to itself.
+static EMBED_PARAGRAPH_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)<p>\s*(?:<a\s+href="(https?://[^"\s]+)"\s*>\s*(https?://[^<\s]+)\s*</a>|(https?://[^<\s]+))\s*</p>"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid embed regex: {}", e))
+});
+
+/// `[embeds]` options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbedOptions {
+    /// Replaces embeddable URLs on their own line with embed markup.
+    pub enabled: bool,
+    /// Connect and read timeout of oEmbed requests in seconds.
+    pub timeout_secs: u64,
+    /// Age in seconds after which a cached response is fetched again.
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for EmbedOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_secs: 10,
+            cache_ttl_secs: 7 * 24 * 3600,
+        }
+    }
+}
+
+impl EmbedOptions {
+    /// Checks that the timeout is not zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(self.timeout_secs > 0, "timeout_secs must be positive");
+        Ok(())
+    }
+}
+
+/// An embeddable resource recognised from its URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Embed {
+    /// A YouTube video, by ID.
+    YouTube(String),
+    /// A Vimeo video, by ID.
+    Vimeo(String),
+    /// A post on Twitter/X, by its canonical URL.
+    Tweet(String),
+}
+
+impl Embed {
+    /// Recognises a YouTube, Vimeo or Twitter/X URL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::embeds::Embed;
+    ///
+    /// assert_eq!(
+    ///     Embed::parse("https://youtu.be/dQw4w9WgXcQ"),
+    ///     Some(Embed::YouTube("dQw4w9WgXcQ".into()))
+    /// );
+    /// assert_eq!(Embed::parse("https://example.com/video"), None);
+    /// ```
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = Url::parse(url).ok()?;
+        let host = url.host_str()?.to_ascii_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|s| s.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+
+        fn id(id: &str, valid: fn(char) -> bool) -> Option<String> {
+            (!id.is_empty() && id.chars().all(valid))
+                .then(|| id.to_string())
+        }
+        fn youtube_id(c: char) -> bool {
+            c.is_ascii_alphanumeric() || c == '-' || c == '_'
+        }
+
+        match (host, segments.as_slice()) {
+            ("youtube.com" | "m.youtube.com", ["watch"]) => url
+                .query_pairs()
+                .find(|(name, _)| name == "v")
+                .and_then(|(_, value)| id(&value, youtube_id))
+                .map(Self::YouTube),
+            ("youtube.com", ["shorts" | "embed" | "live", video])
+            | ("youtu.be", [video]) => {
+                id(video, youtube_id).map(Self::YouTube)
+            }
+            ("vimeo.com", [video]) => {
+                id(video, |c| c.is_ascii_digit()).map(Self::Vimeo)
+            }
+            ("twitter.com" | "x.com", [user, "status", post]) => {
+                id(post, |c| c.is_ascii_digit()).map(|post| {
+                    Self::Tweet(format!(
+                        "https://twitter.com/{}/status/{}",
+                        user, post
+                    ))
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the provider's oEmbed endpoint for this resource.
+    pub fn endpoint(&self) -> String {
+        let (base, url, extra) = match self {
+            Self::YouTube(id) => (
+                "https://www.youtube.com/oembed",
+                format!("https://www.youtube.com/watch?v={}", id),
+                "",
+            ),
+            Self::Vimeo(id) => (
+                "https://vimeo.com/api/oembed.json",
+                format!("https://vimeo.com/{}", id),
+                "",
+            ),
+            Self::Tweet(url) => (
+                "https://publish.twitter.com/oembed",
+                url.clone(),
+                "&omit_script=true&dnt=true",
+            ),
+        };
+        let mut endpoint = Url::parse(base).unwrap_or_else(|e| {
+            panic!("Invalid oEmbed endpoint: {}", e)
+        });
+        let _ = endpoint
+            .query_pairs_mut()
+            .append_pair("url", &url)
+            .append_pair("format", "json");
+        format!("{}{}", endpoint, extra)
+    }
+
+    fn provider(&self) -> &'static str {
+        match self {
+            Self::YouTube(_) => "youtube",
+            Self::Vimeo(_) => "vimeo",
+            Self::Tweet(_) => "twitter",
+        }
+    }
+
+    /// Renders the embed markup, using the provider's oEmbed `response`
+    /// when there is one.
+    pub fn to_html(&self, response: Option<&Value>) -> String {
+        let field = |name: &str| {
+            response
+                .and_then(|response| response.get(name))
+                .and_then(Value::as_str)
+        };
+        let size = |name: &str, default: u64| {
+            response
+                .and_then(|response| response.get(name))
+                .and_then(|value| {
+                    value
+                        .as_u64()
+                        .or_else(|| value.as_str()?.parse().ok())
+                })
+                .unwrap_or(default)
+        };
+
+        let player = match self {
+            Self::YouTube(id) => {
+                format!("https://www.youtube-nocookie.com/embed/{}", id)
+            }
+            Self::Vimeo(id) => {
+                format!("https://player.vimeo.com/video/{}?dnt=1", id)
+            }
+            Self::Tweet(url) => {
+                let body = field("html")
+                    .filter(|html| html.trim_start().starts_with("<blockquote"))
+                    .map(str::to_string)
+                    .unwrap_or_else(|| {
+                        format!(
+                            "<blockquote><a href=\"{0}\">{0}</a></blockquote>",
+                            escape_html(url)
+                        )
+                    });
+                return format!(
+                    "<figure class=\"ssg-embed ssg-embed-twitter\">{}</figure>",
+                    body.trim()
+                );
+            }
+        };
+        let title = field("title").map_or_else(
+            || match self {
+                Self::Vimeo(_) => "Vimeo video".to_string(),
+                _ => "YouTube video".to_string(),
+            },
+            str::to_string,
+        );
+        format!(
+            "<figure class=\"ssg-embed ssg-embed-{}\"><iframe src=\"{}\" \
+             title=\"{}\" width=\"{}\" height=\"{}\" loading=\"lazy\" \
+             allow=\"encrypted-media; picture-in-picture; fullscreen\" \
+             referrerpolicy=\"strict-origin-when-cross-origin\" \
+             allowfullscreen></iframe></figure>",
+            self.provider(),
+            escape_html(&player),
+            escape_html(&title),
+            size("width", 560),
+            size("height", 315)
+        )
+    }
+}
+
+/// Resolves embeds with cached oEmbed responses.
+#[derive(Debug, Clone)]
+pub struct EmbedResolver {
+    fetcher: JsonFetcher,
+}
+
+impl EmbedResolver {
+    /// Creates a resolver caching responses in `cache_dir`.
+    pub fn new(
+        options: &EmbedOptions,
+        cache_dir: impl Into<PathBuf>,
+    ) -> Self {
+        let fetch = FetchOptions {
+            allow: ENDPOINT_HOSTS
+                .iter()
+                .map(|h| h.to_string())
+                .collect(),
+            timeout_secs: options.timeout_secs,
+            cache_ttl_secs: options.cache_ttl_secs,
+            ..FetchOptions::default()
+        };
+        Self {
+            fetcher: JsonFetcher::new(fetch, cache_dir),
+        }
+    }
+
+    /// Returns the embed markup for `embed`, falling back to markup
+    /// built from the URL alone if the provider cannot be reached.
+    pub fn resolve(&self, embed: &Embed) -> String {
+        let endpoint = embed.endpoint();
+        match self.fetcher.fetch_json(&endpoint) {
+            Ok(response) => embed.to_html(Some(&response)),
+            Err(error) => {
+                warn!("Embedding without oEmbed data: {:#}", error);
+                embed.to_html(None)
+            }
+        }
+    }
+
+    /// Replaces every paragraph holding only an embeddable URL in
+    /// `html`. Returns `None` if there is none.
+    pub fn embed_page(&self, html: &str) -> Option<String> {
+        let mut found = false;
+        let page = EMBED_PARAGRAPH_RE.replace_all(
+            html,
+            |caps: &Captures<'_>| {
+                let url = match (caps.get(1), caps.get(2), caps.get(3))
+                {
+                    (Some(href), Some(text), _)
+                        if href.as_str() == text.as_str() =>
+                    {
+                        href.as_str()
+                    }
+                    (_, _, Some(bare)) => bare.as_str(),
+                    _ => return caps[0].to_string(),
+                };
+                match Embed::parse(&url.replace("&amp;", "&")) {
+                    Some(embed) => {
+                        found = true;
+                        self.resolve(&embed)
+                    }
+                    None => caps[0].to_string(),
+                }
+            },
+        );
+        found.then(|| page.into_owned())
+    }
+}
+
+/// Applies [`EmbedResolver::embed_page`] to every HTML file below
+/// `site_dir`, when embeds are enabled.
+///
+/// Returns the number of pages with embeds.
+///
+/// # Errors
+///
+/// Returns an error if the options are invalid or a file cannot be read
+/// or written. Unreachable providers are not errors.
+pub fn embed_site(
+    site_dir: &Path,
+    options: &EmbedOptions,
+    cache_dir: &Path,
+) -> Result<usize> {
+    if !options.enabled || !site_dir.exists() {
+        return Ok(0);
+    }
+    options.validate()?;
+    let resolver = EmbedResolver::new(options, cache_dir);
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+
+    let mut embedded = 0;
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        if let Some(updated) = resolver.embed_page(&html) {
+            fs::write(&path, updated).with_context(|| {
+                format!("Failed to write {}", path.display())
+            })?;
+            embedded += 1;
+        }
+    }
+    Ok(embedded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_urls() {
+        for url in [
+            "https://www.youtube.com/watch?v=abc_123-XY&t=10",
+            "https://youtu.be/abc_123-XY",
+            "https://youtube.com/shorts/abc_123-XY",
+        ] {
+            assert_eq!(
+                Embed::parse(url),
+                Some(Embed::YouTube("abc_123-XY".into())),
+                "{}",
+                url
+            );
+        }
+        assert_eq!(
+            Embed::parse("https://vimeo.com/76979871"),
+            Some(Embed::Vimeo("76979871".into()))
+        );
+        assert_eq!(
+            Embed::parse("https://x.com/rustlang/status/12345"),
+            Some(Embed::Tweet(
+                "https://twitter.com/rustlang/status/12345".into()
+            ))
+        );
+        assert_eq!(
+            Embed::parse("https://vimeo.com/channels/staff"),
+            None
+        );
+        assert_eq!(Embed::parse("https://www.youtube.com/watch"), None);
+    }
+
+    #[test]
+    fn test_to_html() {
+        let video = Embed::YouTube("abc".into());
+        let html = video.to_html(Some(&json!({
+            "title": "Talk <live>",
+            "width": 640,
+            "height": "360",
+        })));
+        assert!(html.contains(
+            "src=\"https://www.youtube-nocookie.com/embed/abc\""
+        ));
+        assert!(html.contains("title=\"Talk &lt;live&gt;\""));
+        assert!(html.contains("width=\"640\" height=\"360\""));
+        assert!(html.contains("loading=\"lazy\""));
+
+        let tweet =
+            Embed::Tweet("https://twitter.com/a/status/1".into());
+        assert!(tweet
+            .to_html(Some(&json!({ "html": "<script>x</script>" })))
+            .contains(
+                "<blockquote><a href=\"https://twitter.com/a/status/1\">"
+            ));
+    }
+
+    #[cfg(not(feature = "fetch"))]
+    #[test]
+    fn test_embed_site_without_network() -> Result<()> {
+        let site = tempdir()?;
+        let cache = tempdir()?;
+        let page = site.path().join("index.html");
+        fs::write(
+            &page,
+            "<p>Watch:</p>\n\
+             <p><a href=\"https://vimeo.com/42\">https://vimeo.com/42</a></p>\n\
+             <p>https://example.com/</p>\n\
+             <p>See https://youtu.be/abc here</p>\n",
+        )?;
+        let options = EmbedOptions {
+            enabled: true,
+            ..EmbedOptions::default()
+        };
+
+        assert_eq!(embed_site(site.path(), &options, cache.path())?, 1);
+        let html = fs::read_to_string(&page)?;
+        assert!(html.contains(
+            "src=\"https://player.vimeo.com/video/42?dnt=1\" \
+             title=\"Vimeo video\""
+        ));
+        assert!(html.contains("<p>https://example.com/</p>"));
+        assert!(html.contains("<p>See https://youtu.be/abc here</p>"));
+        assert_eq!(embed_site(site.path(), &options, cache.path())?, 0);
+        Ok(())
+    }
+}
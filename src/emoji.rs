@@ -0,0 +1,266 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Emoji Shortcodes
+//!
+//! Replaces `:rocket:`-style shortcodes in Markdown with emoji before
+//! the core compile, so `Shipped :rocket:` reads `Shipped 🚀`. The
+//! standard names are the GitHub (gemoji) shortcodes.
+//!
+//! Shortcodes are off by default, since a colon pair such as `10:30:`
+//! can appear in prose. The `[markdown.emoji]` table of the
+//! configuration file turns them on:
+//!
+//! ```toml
+//! [markdown.emoji]
+//! enabled = true
+//! style = "unicode"   # or "image"
+//! image_url = "https://cdn.jsdelivr.net/gh/jdecked/twemoji@15.1.0/assets/svg"
+//!
+//! [markdown.emoji.custom]
+//! shipit = "🐿️"
+//! ferris = "/images/ferris.svg"
+//! ```
+//!
+//! With the `image` style, emoji become
+//! `<img class="emoji" src="<image_url>/<codepoints>.svg">`, named the
+//! way Twemoji names its files, so they look the same on every
+//! platform. Custom shortcodes map to emoji text, or to an image when
+//! the value is an `https://` URL or a site path.
+//!
+//! Unknown shortcodes, code blocks and inline code are left alone.
+
+use anyhow::{ensure, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{
+    content_files::rewrite_bodies, markdown::map_prose,
+    template::escape_html,
+};
+
+/// Default location of the emoji images of the `image` style.
+pub const DEFAULT_IMAGE_URL: &str =
+    "https://cdn.jsdelivr.net/gh/jdecked/twemoji@15.1.0/assets/svg";
+
+/// Matches, in Markdown, inline code or a shortcode.
+static SHORTCODE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)``.+?``|`[^`]*`|:([a-z0-9_+-]+):")
+        .unwrap_or_else(|e| panic!("Invalid shortcode regex: {}", e))
+});
+
+/// How emoji are written to the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmojiStyle {
+    /// As Unicode text.
+    Unicode,
+    /// As SVG images.
+    Image,
+}
+
+impl Default for EmojiStyle {
+    fn default() -> Self {
+        Self::Unicode
+    }
+}
+
+/// `[markdown.emoji]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmojiOptions {
+    /// Replaces shortcodes with emoji.
+    pub enabled: bool,
+    /// How emoji are written to the page.
+    pub style: EmojiStyle,
+    /// Location of the emoji images of the `image` style.
+    pub image_url: String,
+    /// Extra shortcodes, mapped to emoji text or to an image.
+    pub custom: BTreeMap<String, String>,
+}
+
+impl Default for EmojiOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            style: EmojiStyle::default(),
+            image_url: DEFAULT_IMAGE_URL.to_string(),
+            custom: BTreeMap::new(),
+        }
+    }
+}
+
+impl EmojiOptions {
+    /// Checks that the image location is an HTTPS URL or a site path
+    /// and that every custom shortcode has a name and a value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            is_image(&self.image_url),
+            "image_url `{}` must be an https:// URL or start with /",
+            self.image_url
+        );
+        for (name, value) in &self.custom {
+            ensure!(
+                !name.is_empty()
+                    && name.chars().all(|c| {
+                        c.is_ascii_lowercase()
+                            || c.is_ascii_digit()
+                            || "_+-".contains(c)
+                    }),
+                "custom: `{}` must be lowercase letters, digits, _, + or -",
+                name
+            );
+            ensure!(
+                !value.trim().is_empty(),
+                "custom.{} must be an emoji or an image",
+                name
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the known shortcodes of a Markdown body, outside code, with
+/// emoji.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::emoji::{replace_emoji, EmojiOptions};
+///
+/// let options = EmojiOptions::default();
+/// assert_eq!(replace_emoji("Shipped :rocket:", &options), "Shipped 🚀");
+/// assert_eq!(replace_emoji("`:rocket:` :nope:", &options), "`:rocket:` :nope:");
+/// ```
+pub fn replace_emoji(markdown: &str, options: &EmojiOptions) -> String {
+    map_prose(markdown, |text| {
+        SHORTCODE_RE
+            .replace_all(text, |caps: &Captures<'_>| {
+                caps.get(1)
+                    .and_then(|name| emoji(name.as_str(), options))
+                    .unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned()
+    })
+}
+
+/// Replaces the shortcodes of every Markdown file below `dir` in place,
+/// leaving their frontmatter alone.
+///
+/// Returns the number of files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written.
+pub fn replace_emoji_dir(
+    dir: &Path,
+    options: &EmojiOptions,
+) -> Result<usize> {
+    rewrite_bodies(dir, |body| replace_emoji(body, options))
+}
+
+/// Returns the markup of the emoji named `name`, custom shortcodes
+/// first.
+fn emoji(name: &str, options: &EmojiOptions) -> Option<String> {
+    if let Some(value) = options.custom.get(name) {
+        if is_image(value) {
+            return Some(image(value, &format!(":{}:", name)));
+        }
+        return Some(value.clone());
+    }
+    let emoji = emojis::get_by_shortcode(name)?.as_str();
+    Some(match options.style {
+        EmojiStyle::Unicode => emoji.to_string(),
+        EmojiStyle::Image => image(
+            &format!(
+                "{}/{}.svg",
+                options.image_url.trim_end_matches('/'),
+                image_name(emoji)
+            ),
+            emoji,
+        ),
+    })
+}
+
+/// Returns an inline emoji image.
+fn image(src: &str, alt: &str) -> String {
+    format!(
+        "<img class=\"emoji\" src=\"{}\" alt=\"{}\" draggable=\"false\">",
+        escape_html(src),
+        escape_html(alt)
+    )
+}
+
+/// Returns the Twemoji file name of `emoji`: its code points in hex,
+/// joined with `-`, without variation selectors unless it is a
+/// zero-width-joiner sequence.
+fn image_name(emoji: &str) -> String {
+    let zwj = emoji.contains('\u{200d}');
+    emoji
+        .chars()
+        .filter(|&c| zwj || c != '\u{fe0f}')
+        .map(|c| format!("{:x}", u32::from(c)))
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Returns `true` if `value` is an `https://` URL or a site path.
+fn is_image(value: &str) -> bool {
+    value.starts_with("https://") || value.starts_with('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_emoji_outside_code() {
+        let mut options = EmojiOptions::default();
+        let _ = options.custom.insert("shipit".into(), "🐿️".into());
+        let markdown =
+            "Done :tada: :shipit:\n\n```\n:tada:\n```\nAt 10:30:45\n";
+        assert_eq!(
+            replace_emoji(markdown, &options),
+            "Done 🎉 🐿️\n\n```\n:tada:\n```\nAt 10:30:45\n"
+        );
+    }
+
+    #[test]
+    fn test_image_style() {
+        let mut options = EmojiOptions {
+            style: EmojiStyle::Image,
+            image_url: "/emoji/".into(),
+            ..EmojiOptions::default()
+        };
+        let _ = options
+            .custom
+            .insert("ferris".into(), "/images/ferris.svg".into());
+        assert_eq!(
+            replace_emoji(":heart: :ferris:", &options),
+            "<img class=\"emoji\" src=\"/emoji/2764.svg\" alt=\"❤️\" draggable=\"false\"> \
+             <img class=\"emoji\" src=\"/images/ferris.svg\" alt=\":ferris:\" draggable=\"false\">"
+        );
+        assert_eq!(
+            image_name("\u{1f3f3}\u{fe0f}\u{200d}\u{1f308}"),
+            "1f3f3-fe0f-200d-1f308"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut options = EmojiOptions::default();
+        assert!(options.validate().is_ok());
+        let _ =
+            options.custom.insert("Party Parrot".into(), "🦜".into());
+        assert!(options.validate().is_err());
+        options.custom.clear();
+        options.image_url = "http://example.com/emoji".into();
+        assert!(options.validate().is_err());
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Errors
+//!
+//! [`Error`] is the error of the public functions of the crate root, so
+//! callers can tell failure kinds apart:
+//!
+//! ```rust,no_run
+//! use ssg::{verify_and_copy_files, Error};
+//! use std::path::Path;
+//!
+//! match verify_and_copy_files(Path::new("content"), Path::new("public")) {
+//!     Ok(()) => {}
+//!     Err(Error::Validation { path, reason }) => {
+//!         eprintln!("refusing {}: {}", path.display(), reason)
+//!     }
+//!     Err(error) => eprintln!("{}", error),
+//! }
+//! ```
+//!
+//! The build stages keep using [`anyhow`] internally; a failed build is
+//! an [`Error::Compile`] wrapping that error, with its context and
+//! [diagnostics](crate::diagnostics) intact.
+
+use std::{io, path::PathBuf};
+use thiserror::Error;
+
+use crate::cmd::CliError;
+
+/// Result of the public functions of the crate root.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Failure kinds of the public API.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// A file system operation failed.
+    #[error("I/O error{}: {source}", on(.path))]
+    Io {
+        /// The file or directory operated on, if known.
+        path: Option<PathBuf>,
+        /// The underlying I/O error.
+        #[source]
+        source: io::Error,
+    },
+
+    /// A path or file was refused by a safety check.
+    #[error("{reason}: {}", .path.display())]
+    Validation {
+        /// The refused path.
+        path: PathBuf,
+        /// Why the path was refused.
+        reason: String,
+    },
+
+    /// A build stage failed.
+    #[error(transparent)]
+    Compile(anyhow::Error),
+
+    /// The development server could not be set up.
+    #[error("Server error on {address}: {message}")]
+    Server {
+        /// The address the server was to listen on.
+        address: String,
+        /// What went wrong.
+        message: String,
+    },
+
+    /// The configuration or command line is invalid.
+    #[error(transparent)]
+    Config(#[from] CliError),
+}
+
+impl Error {
+    /// Creates an [`Error::Io`] for an operation on `path`.
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Self::Io {
+            path: Some(path.into()),
+            source,
+        }
+    }
+
+    /// Creates an [`Error::Validation`] refusing `path`.
+    pub fn validation(
+        path: impl Into<PathBuf>,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self::Validation {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Self {
+        Self::Io { path: None, source }
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    /// Keeps configuration errors apart; any other error is a failed
+    /// build stage.
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<CliError>() {
+            Ok(error) => Self::Config(error),
+            Err(error) => Self::Compile(error),
+        }
+    }
+}
+
+/// Formats the ` on <path>` part of an I/O error.
+fn on(path: &Option<PathBuf>) -> String {
+    path.as_ref()
+        .map(|path| format!(" on {}", path.display()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn test_error_display() {
+        let error = Error::io(
+            "public/index.html",
+            io::Error::new(io::ErrorKind::NotFound, "missing"),
+        );
+        assert_eq!(
+            error.to_string(),
+            "I/O error on public/index.html: missing"
+        );
+        let error = Error::validation("../etc", "Path is unsafe");
+        assert_eq!(error.to_string(), "Path is unsafe: ../etc");
+    }
+
+    #[test]
+    fn test_error_from_anyhow() {
+        let error = Error::from(anyhow!(CliError::MissingArgument(
+            "content".into()
+        )));
+        assert!(matches!(
+            error,
+            Error::Config(CliError::MissingArgument(_))
+        ));
+
+        let error = Error::from(
+            Err::<(), _>(anyhow!("template missing"))
+                .context("Failed to render about.md")
+                .unwrap_err(),
+        );
+        assert!(matches!(error, Error::Compile(_)));
+        assert_eq!(error.to_string(), "Failed to render about.md");
+    }
+}
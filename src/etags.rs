@@ -0,0 +1,159 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Content Hash ETags
+//!
+//! Writes an `etags.json` file mapping every file in the site output to a
+//! strong ETag derived from its content hash. Static hosts and config
+//! generators for nginx or Caddy can use the map to serve correct
+//! validators without hashing files at request time.
+//!
+//! ```json
+//! {
+//!   "/index.html": "\"3a7bd3e2360a3d29eea436fcfb7e44c7\"",
+//!   "/style.css": "\"8f434346648f6b96df89dda901c5176b\""
+//! }
+//! ```
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::collect_files_recursive;
+
+/// File name of the ETag map written to the site root.
+pub const ETAGS_FILE: &str = "etags.json";
+
+/// Number of hash bytes kept in each ETag.
+const ETAG_BYTES: usize = 16;
+
+/// Returns the strong ETag for `contents`, including the quotes.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::etags::etag_for;
+///
+/// let etag = etag_for(b"hello");
+/// assert!(etag.starts_with('"') && etag.ends_with('"'));
+/// assert_eq!(etag, etag_for(b"hello"));
+/// ```
+pub fn etag_for(contents: &[u8]) -> String {
+    let digest = Sha256::digest(contents);
+    let mut etag = String::with_capacity(ETAG_BYTES * 2 + 2);
+    etag.push('"');
+    for byte in &digest[..ETAG_BYTES] {
+        let _ = write!(etag, "{:02x}", byte);
+    }
+    etag.push('"');
+    etag
+}
+
+/// Computes the ETag of every file below `site_dir`.
+///
+/// Keys are URL paths such as `/blog/post/index.html`. An existing
+/// `etags.json` in the site root is skipped.
+///
+/// # Errors
+///
+/// Returns an error if the directory or a file cannot be read.
+pub fn compute_etags(
+    site_dir: &Path,
+) -> Result<BTreeMap<String, String>> {
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    let manifest = site_dir.join(ETAGS_FILE);
+    files.retain(|path| path != &manifest);
+
+    files
+        .into_par_iter()
+        .map(|path| {
+            let contents = fs::read(&path).with_context(|| {
+                format!("Failed to read {}", path.display())
+            })?;
+            Ok((url_path(site_dir, &path), etag_for(&contents)))
+        })
+        .collect()
+}
+
+/// Writes `etags.json` to the root of `site_dir`.
+///
+/// Returns the path of the written file.
+///
+/// # Errors
+///
+/// Returns an error if the site cannot be hashed or the file cannot be
+/// written.
+pub fn write_etags(site_dir: &Path) -> Result<PathBuf> {
+    let etags = compute_etags(site_dir)?;
+    let path = site_dir.join(ETAGS_FILE);
+    let json = serde_json::to_string_pretty(&etags)
+        .context("Failed to serialize ETags")?;
+    fs::write(&path, json).with_context(|| {
+        format!("Failed to write {}", path.display())
+    })?;
+    Ok(path)
+}
+
+/// Returns the URL path of `file` relative to `site_dir`.
+fn url_path(site_dir: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(site_dir).unwrap_or(file);
+    let mut url = String::new();
+    for component in relative.components() {
+        url.push('/');
+        url.push_str(&component.as_os_str().to_string_lossy());
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_etag_depends_on_content() {
+        assert_eq!(etag_for(b"a"), etag_for(b"a"));
+        assert_ne!(etag_for(b"a"), etag_for(b"b"));
+        assert_eq!(etag_for(b"").len(), ETAG_BYTES * 2 + 2);
+    }
+
+    #[test]
+    fn test_write_etags() -> Result<()> {
+        let temp_dir = tempdir()?;
+        fs::create_dir_all(temp_dir.path().join("blog/post"))?;
+        fs::write(temp_dir.path().join("index.html"), "home")?;
+        fs::write(
+            temp_dir.path().join("blog/post/index.html"),
+            "post",
+        )?;
+
+        let path = write_etags(temp_dir.path())?;
+        let written: BTreeMap<String, String> =
+            serde_json::from_str(&fs::read_to_string(path)?)?;
+
+        assert_eq!(written.len(), 2);
+        assert_eq!(written["/index.html"], etag_for(b"home"));
+        assert_eq!(written["/blog/post/index.html"], etag_for(b"post"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compute_etags_skips_previous_manifest() -> Result<()> {
+        let temp_dir = tempdir()?;
+        fs::write(temp_dir.path().join("a.txt"), "a")?;
+        let _ = write_etags(temp_dir.path())?;
+
+        let etags = compute_etags(temp_dir.path())?;
+        assert_eq!(etags.keys().collect::<Vec<_>>(), vec!["/a.txt"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,358 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # External Links
+//!
+//! Marks the links of the generated pages that lead to other sites, so
+//! they can open in a new tab, carry `rel` hints and show an icon.
+//! Options live in the `[external_links]` table of the configuration
+//! file:
+//!
+//! ```toml
+//! [external_links]
+//! enabled = true
+//! new_tab = true
+//! rel = ["noopener", "nofollow"]
+//! icon_class = "external-link"
+//! internal_hosts = ["docs.example.com"]
+//! ```
+//!
+//! A link is external when its `href` is an `http`, `https` or
+//! protocol-relative URL whose host is neither the host of `base_url`
+//! nor one of `internal_hosts`. Relative links, fragments, `mailto:`
+//! and other schemes are internal and left untouched.
+//!
+//! External links get `target="_blank"` unless they already have a
+//! target, the `rel` values they are missing, and `icon_class` added to
+//! their classes for a stylesheet to draw an icon with.
+
+use anyhow::{ensure, Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+use url::Url;
+
+use crate::{collect_files_recursive, template::escape_html};
+
+/// Matches the opening tag of a link.
+static ANCHOR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)<a\s[^>]*>")
+        .unwrap_or_else(|e| panic!("Invalid anchor regex: {}", e))
+});
+
+/// Matches an attribute of a tag, with a quoted or bare value.
+static ATTRIBUTE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"\s([a-zA-Z][a-zA-Z0-9-]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid attribute regex: {}", e))
+});
+
+/// `[external_links]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalLinkOptions {
+    /// Marks links to other sites.
+    pub enabled: bool,
+    /// Opens external links in a new tab with `target="_blank"`.
+    pub new_tab: bool,
+    /// Values added to the `rel` attribute of external links.
+    pub rel: Vec<String>,
+    /// Class added to external links, e.g. for an icon.
+    pub icon_class: Option<String>,
+    /// Other hosts whose links are internal.
+    pub internal_hosts: Vec<String>,
+}
+
+impl Default for ExternalLinkOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            new_tab: true,
+            rel: vec!["noopener".to_string(), "nofollow".to_string()],
+            icon_class: None,
+            internal_hosts: Vec::new(),
+        }
+    }
+}
+
+impl ExternalLinkOptions {
+    /// Checks that the `rel` values and icon class are single tokens
+    /// and that the internal hosts are host names.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        for value in &self.rel {
+            ensure!(
+                is_token(value),
+                "rel `{}` must be a single link type such as noopener",
+                value
+            );
+        }
+        if let Some(class) = &self.icon_class {
+            ensure!(
+                is_token(class),
+                "icon_class `{}` must be a single CSS class name",
+                class
+            );
+        }
+        for host in &self.internal_hosts {
+            ensure!(
+                !host.is_empty()
+                    && host.chars().all(|c| {
+                        c.is_ascii_alphanumeric() || c == '.' || c == '-'
+                    }),
+                "internal_hosts: `{}` must be a host name without a scheme",
+                host
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if `href` leads to a host other than
+    /// `site_host` and the internal hosts.
+    fn is_external(&self, href: &str, site_host: Option<&str>) -> bool {
+        let href = href.trim();
+        let url = if href.starts_with("//") {
+            Url::parse(&format!("https:{}", href))
+        } else {
+            Url::parse(href)
+        };
+        let host = match url {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {
+                match url.host_str() {
+                    Some(host) => host.to_ascii_lowercase(),
+                    None => return false,
+                }
+            }
+            _ => return false,
+        };
+        site_host.map_or(true, |site| !site.eq_ignore_ascii_case(&host))
+            && !self
+                .internal_hosts
+                .iter()
+                .any(|internal| internal.eq_ignore_ascii_case(&host))
+    }
+}
+
+/// Marks the external links of `html`, treating links to the host of
+/// `base_url` as internal. Returns `None` if no link changed.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::external_links::{mark_external_links, ExternalLinkOptions};
+///
+/// let html = r#"<a href="https://rust-lang.org">Rust</a> <a href="/about/">About</a>"#;
+/// let marked = mark_external_links(
+///     html,
+///     &ExternalLinkOptions::default(),
+///     "https://example.com",
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     marked,
+///     r#"<a href="https://rust-lang.org" target="_blank" rel="noopener nofollow">Rust</a> <a href="/about/">About</a>"#
+/// );
+/// ```
+pub fn mark_external_links(
+    html: &str,
+    options: &ExternalLinkOptions,
+    base_url: &str,
+) -> Option<String> {
+    let site_host = Url::parse(base_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_ascii_lowercase));
+    let mut changed = false;
+    let marked = ANCHOR_RE.replace_all(html, |caps: &Captures<'_>| {
+        let tag = &caps[0];
+        let external = attribute(tag, "href").map_or(false, |href| {
+            options.is_external(&href, site_host.as_deref())
+        });
+        if !external {
+            return tag.to_string();
+        }
+        let marked = mark_tag(tag, options);
+        changed |= marked != tag;
+        marked
+    });
+    if changed {
+        Some(marked.into_owned())
+    } else {
+        None
+    }
+}
+
+/// Applies [`mark_external_links`] to every HTML file below `site_dir`.
+///
+/// Returns the number of files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written.
+pub fn mark_external_links_site(
+    site_dir: &Path,
+    options: &ExternalLinkOptions,
+    base_url: &str,
+) -> Result<usize> {
+    if !options.enabled || !site_dir.exists() {
+        return Ok(0);
+    }
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+
+    let mut rewritten = 0;
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let marked = match mark_external_links(&html, options, base_url)
+        {
+            Some(marked) => marked,
+            None => continue,
+        };
+        fs::write(&path, marked).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+/// Adds the configured target, `rel` values and class to the opening
+/// tag of an external link.
+fn mark_tag(tag: &str, options: &ExternalLinkOptions) -> String {
+    let mut tag = tag.to_string();
+    if options.new_tab && attribute(&tag, "target").is_none() {
+        tag = set_attribute(&tag, "target", "_blank");
+    }
+    tag = add_tokens(&tag, "rel", &options.rel);
+    if let Some(class) = &options.icon_class {
+        tag = add_tokens(&tag, "class", std::slice::from_ref(class));
+    }
+    tag
+}
+
+/// Adds the `tokens` missing from the space-separated attribute `name`.
+fn add_tokens(tag: &str, name: &str, tokens: &[String]) -> String {
+    let current = attribute(tag, name).unwrap_or_default();
+    let mut values: Vec<&str> = current.split_whitespace().collect();
+    for token in tokens {
+        if !values.iter().any(|value| value.eq_ignore_ascii_case(token))
+        {
+            values.push(token);
+        }
+    }
+    let value = values.join(" ");
+    if value == current || value.is_empty() {
+        return tag.to_string();
+    }
+    set_attribute(tag, name, &value)
+}
+
+/// Returns the value of the attribute `name` of `tag`.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    ATTRIBUTE_RE
+        .captures_iter(tag)
+        .find(|caps| caps[1].eq_ignore_ascii_case(name))
+        .and_then(|caps| {
+            caps.get(2).or_else(|| caps.get(3)).or_else(|| caps.get(4))
+        })
+        .map(|value| value.as_str().to_string())
+}
+
+/// Sets the attribute `name` of `tag` to `value`, replacing it in place
+/// or adding it before the end of the tag.
+fn set_attribute(tag: &str, name: &str, value: &str) -> String {
+    let attribute = format!(" {}=\"{}\"", name, escape_html(value));
+    if let Some(caps) = ATTRIBUTE_RE
+        .captures_iter(tag)
+        .find(|caps| caps[1].eq_ignore_ascii_case(name))
+    {
+        if let Some(whole) = caps.get(0) {
+            return format!(
+                "{}{}{}",
+                &tag[..whole.start()],
+                attribute,
+                &tag[whole.end()..]
+            );
+        }
+    }
+    let end =
+        tag.trim_end_matches('>').trim_end_matches('/').trim_end();
+    format!("{}{}{}", end, attribute, &tag[end.len()..])
+}
+
+/// Returns `true` if `value` is a single HTML token.
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_external_links() {
+        let options = ExternalLinkOptions {
+            icon_class: Some("external".into()),
+            internal_hosts: vec!["docs.example.com".into()],
+            ..ExternalLinkOptions::default()
+        };
+        let html = "<a href=\"https://EXAMPLE.com/x\">Home</a>\
+                    <a href=\"https://docs.example.com/\">Docs</a>\
+                    <a href=\"mailto:me@example.org\">Mail</a>\
+                    <a class='button' rel=\"me\" target=\"_self\" href=\"//github.com/me\">GitHub</a>";
+        assert_eq!(
+            mark_external_links(html, &options, "https://example.com/")
+                .unwrap(),
+            "<a href=\"https://EXAMPLE.com/x\">Home</a>\
+             <a href=\"https://docs.example.com/\">Docs</a>\
+             <a href=\"mailto:me@example.org\">Mail</a>\
+             <a class=\"button external\" rel=\"me noopener nofollow\" target=\"_self\" href=\"//github.com/me\">GitHub</a>"
+        );
+    }
+
+    #[test]
+    fn test_marked_links_are_left_alone() {
+        let options = ExternalLinkOptions::default();
+        let html = "<a href=\"https://rust-lang.org\">Rust</a>";
+        let marked =
+            mark_external_links(html, &options, "https://example.com")
+                .unwrap();
+        assert_eq!(
+            mark_external_links(
+                &marked,
+                &options,
+                "https://example.com"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut options = ExternalLinkOptions::default();
+        assert!(options.validate().is_ok());
+        options.rel.push("no follow".into());
+        assert!(options.validate().is_err());
+        options.rel.clear();
+        options
+            .internal_hosts
+            .push("https://docs.example.com".into());
+        assert!(options.validate().is_err());
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # File Size Limits
+//!
+//! [`verify_file_safety`](crate::verify_file_safety) refuses files over
+//! a size limit, so a stray multi-gigabyte file is never read into
+//! memory. The limit is 10 MB by default, too little for videos, PDFs
+//! or large images, so [`FileLimits`] sets other limits per extension:
+//!
+//! ```
+//! use ssg::file_limits::{FileLimits, DEFAULT_MAX_BYTES};
+//! use std::path::Path;
+//!
+//! let limits = FileLimits::default()
+//!     .with_type("mp4", 2 * 1024 * 1024 * 1024)
+//!     .with_type(".pdf", 100 * 1024 * 1024);
+//! assert_eq!(
+//!     limits.max_bytes(Path::new("talk.MP4")),
+//!     2 * 1024 * 1024 * 1024
+//! );
+//! assert_eq!(
+//!     limits.max_bytes(Path::new("notes.txt")),
+//!     DEFAULT_MAX_BYTES
+//! );
+//! ```
+//!
+//! A site sets its limits in the `[file_limits]` table of the
+//! configuration file, in bytes:
+//!
+//! ```toml
+//! [file_limits]
+//! default_bytes = 10485760
+//! stream_bytes = 10485760
+//!
+//! [file_limits.types]
+//! mp4 = 2147483648
+//! pdf = 104857600
+//! ```
+//!
+//! Copies never hold a whole file in memory, so
+//! [`copy_dir_all`](crate::copy_dir_all), its async counterpart and the
+//! passthrough of [non-Markdown content](crate::content_files) copy
+//! files of any size: those over [`FileLimits::stream_bytes`] are
+//! copied in chunks, with their [progress](crate::progress) reported.
+
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use crate::progress::Progress;
+
+/// Size limit of the files without a limit of their own: 10 MB.
+pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Size from which copies are streamed by default: 10 MB.
+pub const DEFAULT_STREAM_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Size of the chunks of a streamed copy.
+const CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Size limits of the files processed, by extension: the
+/// `[file_limits]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileLimits {
+    /// Limit of the files whose extension has no limit of its own.
+    pub default_bytes: u64,
+    /// Limits by lowercase extension, without the dot.
+    pub types: BTreeMap<String, u64>,
+    /// Size from which copies are streamed in chunks.
+    pub stream_bytes: u64,
+}
+
+impl Default for FileLimits {
+    fn default() -> Self {
+        Self {
+            default_bytes: DEFAULT_MAX_BYTES,
+            types: BTreeMap::new(),
+            stream_bytes: DEFAULT_STREAM_BYTES,
+        }
+    }
+}
+
+impl FileLimits {
+    /// Checks that the sizes are positive and that `types` lists
+    /// lowercase extensions without the dot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.default_bytes > 0,
+            "default_bytes must be positive"
+        );
+        ensure!(self.stream_bytes > 0, "stream_bytes must be positive");
+        for (extension, max_bytes) in &self.types {
+            ensure!(
+                !extension.is_empty()
+                    && extension.chars().all(|c| {
+                        c.is_ascii_lowercase() || c.is_ascii_digit()
+                    }),
+                "types: `{}` must be a lowercase extension without the dot",
+                extension
+            );
+            ensure!(
+                *max_bytes > 0,
+                "types.{} must be positive",
+                extension
+            );
+        }
+        Ok(())
+    }
+
+    /// Sets the limit of the files with `extension`, with or without
+    /// the dot, in any case.
+    pub fn with_type(
+        mut self,
+        extension: &str,
+        max_bytes: u64,
+    ) -> Self {
+        let _ = self.types.insert(
+            extension.trim_start_matches('.').to_lowercase(),
+            max_bytes,
+        );
+        self
+    }
+
+    /// Returns the size limit of the file at `path`.
+    pub fn max_bytes(&self, path: &Path) -> u64 {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| {
+                self.types.get(&extension.to_lowercase())
+            })
+            .copied()
+            .unwrap_or(self.default_bytes)
+    }
+
+    /// Copies the file `src` to `dst` like [`fs::copy`], in chunks and
+    /// with a progress bar when it is over
+    /// [`stream_bytes`](Self::stream_bytes). Returns the bytes copied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `src` cannot be read or `dst` written.
+    pub fn copy_file(&self, src: &Path, dst: &Path) -> io::Result<u64> {
+        let metadata = fs::metadata(src)?;
+        if metadata.len() <= self.stream_bytes {
+            return fs::copy(src, dst);
+        }
+
+        let progress = Progress::bytes(
+            &src.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            metadata.len(),
+        );
+
+        let mut reader = File::open(src)?;
+        let mut writer = File::create(dst)?;
+        let mut buffer = vec![0; CHUNK_BYTES];
+        let mut copied = 0;
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            writer.write_all(&buffer[..read])?;
+            copied += read as u64;
+            progress.inc(read as u64);
+        }
+        writer.flush()?;
+        fs::set_permissions(dst, metadata.permissions())?;
+        progress.finish();
+        Ok(copied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_max_bytes_by_extension() {
+        let limits = FileLimits::default().with_type(".PDF", 1);
+        assert_eq!(limits.max_bytes(Path::new("a/b.pdf")), 1);
+        assert_eq!(limits.max_bytes(Path::new("b.Pdf")), 1);
+        assert_eq!(
+            limits.max_bytes(Path::new("pdf")),
+            DEFAULT_MAX_BYTES
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let limits = FileLimits::default().with_type(".PDF", 1);
+        assert!(limits.validate().is_ok());
+        let config: FileLimits =
+            toml::from_str("stream_bytes = 16\n[types]\nmp4 = 2\n")
+                .unwrap();
+        assert_eq!(config.stream_bytes, 16);
+        assert_eq!(config.max_bytes(Path::new("a.mp4")), 2);
+        assert!(config.validate().is_ok());
+
+        let mut invalid = config.clone();
+        let _ = invalid.types.insert(".MP4".into(), 2);
+        assert!(invalid.validate().is_err());
+        let invalid = config.with_type("webm", 0);
+        assert!(invalid.validate().is_err());
+        let invalid = FileLimits {
+            stream_bytes: 0,
+            ..FileLimits::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_copy_file_streams_large_files() -> io::Result<()> {
+        let temp = tempdir()?;
+        let src = temp.path().join("video.mp4");
+        let dst = temp.path().join("copy.mp4");
+        let bytes: Vec<u8> =
+            (0..CHUNK_BYTES * 2 + 7).map(|i| i as u8).collect();
+        fs::write(&src, &bytes)?;
+
+        let limits = FileLimits {
+            stream_bytes: 16,
+            ..FileLimits::default()
+        };
+        assert_eq!(limits.copy_file(&src, &dst)?, bytes.len() as u64);
+        assert_eq!(fs::read(&dst)?, bytes);
+
+        fs::write(&src, "small")?;
+        assert_eq!(limits.copy_file(&src, &dst)?, 5);
+        assert_eq!(fs::read_to_string(&dst)?, "small");
+        Ok(())
+    }
+}
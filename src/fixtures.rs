@@ -0,0 +1,464 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Synthetic Site Fixtures
+//!
+//! Generates large, deterministic content trees with realistic frontmatter,
+//! images and cross-links. Fixtures are used by the benchmark harness and let
+//! users check how a build scales before adopting the generator.
+//!
+//! The same options always produce byte-identical output, so fixtures can be
+//! regenerated instead of committed.
+
+use anyhow::{ensure, Context, Result};
+use clap::ArgMatches;
+use std::{fs, path::Path};
+
+/// Default number of pages generated when `--pages` is not given.
+pub const DEFAULT_FIXTURE_PAGES: usize = 100;
+
+/// Number of internal links added to each generated page.
+const LINKS_PER_PAGE: usize = 3;
+
+/// Vocabulary used to build titles, descriptions and body text.
+const WORDS: &[&str] = &[
+    "static",
+    "site",
+    "generator",
+    "rust",
+    "content",
+    "template",
+    "performance",
+    "accessibility",
+    "search",
+    "markdown",
+    "layout",
+    "feed",
+    "sitemap",
+    "theme",
+    "build",
+    "deploy",
+    "cache",
+    "asset",
+    "image",
+    "navigation",
+    "language",
+    "metadata",
+    "render",
+    "page",
+];
+
+/// Tags assigned to generated pages.
+const TAGS: &[&str] = &[
+    "rust",
+    "web",
+    "seo",
+    "accessibility",
+    "performance",
+    "design",
+    "tutorial",
+    "release",
+];
+
+/// Options for generating a synthetic site.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixtureOptions {
+    /// Total number of pages, spread evenly across languages.
+    pub pages: usize,
+    /// Language codes (e.g. `en`, `fr`), one content subtree per language.
+    pub languages: Vec<String>,
+    /// Seed for the deterministic pseudo-random generator.
+    pub seed: u64,
+}
+
+impl Default for FixtureOptions {
+    fn default() -> Self {
+        Self {
+            pages: DEFAULT_FIXTURE_PAGES,
+            languages: vec!["en".to_string()],
+            seed: 0x5EED,
+        }
+    }
+}
+
+impl FixtureOptions {
+    /// Builds fixture options from the `gen-fixture` subcommand arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no languages are given.
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self> {
+        let mut options = Self::default();
+
+        if let Some(pages) = matches.get_one::<usize>("pages") {
+            options.pages = *pages;
+        }
+        if let Some(langs) = matches.get_one::<String>("langs") {
+            options.languages = langs
+                .split(',')
+                .map(|lang| lang.trim().to_string())
+                .filter(|lang| !lang.is_empty())
+                .collect();
+        }
+        if let Some(seed) = matches.get_one::<u64>("seed") {
+            options.seed = *seed;
+        }
+
+        ensure!(
+            !options.languages.is_empty(),
+            "At least one language is required to generate a fixture"
+        );
+        Ok(options)
+    }
+}
+
+/// Summary of a generated fixture.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FixtureSummary {
+    /// Number of Markdown pages written.
+    pub pages: usize,
+    /// Number of image files written.
+    pub images: usize,
+    /// Number of internal links across all pages.
+    pub links: usize,
+}
+
+/// Small xorshift generator so fixtures are reproducible without a
+/// dependency on a random number crate.
+#[derive(Debug, Clone, Copy)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound.max(1) as u64) as usize
+    }
+
+    fn words(&mut self, count: usize) -> Vec<&'static str> {
+        (0..count).map(|_| WORDS[self.below(WORDS.len())]).collect()
+    }
+}
+
+/// Generates a synthetic content tree under `root`.
+///
+/// Pages are written to `root/<lang>/posts/post-NNNNN.md`, each with a
+/// shared SVG image under `root/<lang>/images/` and links to other pages in
+/// the same language.
+///
+/// # Arguments
+///
+/// * `root` - The content directory to populate
+/// * `options` - Page count, languages and seed
+///
+/// # Returns
+///
+/// * `Ok(FixtureSummary)` - Counts of what was generated
+/// * `Err` - If a language code is invalid or files cannot be written
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::path::Path;
+/// use ssg::fixtures::{generate_fixture, FixtureOptions};
+///
+/// fn main() -> anyhow::Result<()> {
+///     let options = FixtureOptions {
+///         pages: 10_000,
+///         languages: vec!["en".into(), "fr".into()],
+///         ..FixtureOptions::default()
+///     };
+///     let summary = generate_fixture(Path::new("fixture"), &options)?;
+///     println!("Generated {} pages", summary.pages);
+///     Ok(())
+/// }
+/// ```
+pub fn generate_fixture(
+    root: &Path,
+    options: &FixtureOptions,
+) -> Result<FixtureSummary> {
+    ensure!(
+        !options.languages.is_empty(),
+        "At least one language is required to generate a fixture"
+    );
+
+    let mut rng = Rng::new(options.seed);
+    let mut summary = FixtureSummary::default();
+    let lang_count = options.languages.len();
+
+    for (index, lang) in options.languages.iter().enumerate() {
+        ensure!(
+            lang.len() == 2
+                && lang.chars().all(|c| c.is_ascii_lowercase()),
+            "Invalid fixture language '{}': expected a two-letter code",
+            lang
+        );
+
+        // Spread the remainder over the first languages.
+        let pages = options.pages / lang_count
+            + usize::from(index < options.pages % lang_count);
+        if pages == 0 {
+            continue;
+        }
+
+        let lang_dir = root.join(lang);
+        let posts_dir = lang_dir.join("posts");
+        let images_dir = lang_dir.join("images");
+        for dir in [&posts_dir, &images_dir] {
+            fs::create_dir_all(dir).with_context(|| {
+                format!(
+                    "Failed to create fixture directory: {}",
+                    dir.display()
+                )
+            })?;
+        }
+
+        let images = (pages / 10).max(1);
+        for image in 0..images {
+            write_file(
+                &images_dir.join(image_name(image)),
+                &image_svg(image),
+            )?;
+        }
+        summary.images += images;
+
+        for page in 0..pages {
+            let links = (0..LINKS_PER_PAGE.min(pages - 1))
+                .map(|offset| (page + 1 + offset * 7) % pages)
+                .filter(|target| *target != page)
+                .collect::<Vec<_>>();
+            let markdown = page_markdown(
+                &mut rng,
+                lang,
+                page,
+                page % images,
+                &links,
+            );
+            write_file(&posts_dir.join(page_name(page)), &markdown)?;
+            summary.links += links.len();
+        }
+        summary.pages += pages;
+    }
+
+    Ok(summary)
+}
+
+fn page_name(page: usize) -> String {
+    format!("post-{:05}.md", page)
+}
+
+fn image_name(image: usize) -> String {
+    format!("fixture-{:04}.svg", image)
+}
+
+fn image_svg(image: usize) -> String {
+    let hue = (image * 37) % 360;
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"1200\" height=\"630\">\
+         <rect width=\"100%\" height=\"100%\" fill=\"hsl({}, 60%, 60%)\"/></svg>\n",
+        hue
+    )
+}
+
+fn locale_for(lang: &str) -> String {
+    let region = match lang {
+        "en" => "GB".to_string(),
+        other => other.to_ascii_uppercase(),
+    };
+    format!("{}-{}", lang, region)
+}
+
+fn title_case(words: &[&str]) -> String {
+    words
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_uppercase().chain(chars).collect()
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn page_markdown(
+    rng: &mut Rng,
+    lang: &str,
+    page: usize,
+    image: usize,
+    links: &[usize],
+) -> String {
+    let language = locale_for(lang);
+    let title = format!("{} {}", title_case(&rng.words(4)), page);
+    let description = format!("{}.", rng.words(12).join(" "));
+    let tags = (0..3)
+        .map(|_| TAGS[rng.below(TAGS.len())])
+        .collect::<Vec<_>>()
+        .join(", ");
+    let day = 1 + page % 28;
+    let month = 1 + (page / 28) % 12;
+
+    let mut md = String::with_capacity(2048);
+    md.push_str("---\n");
+    md.push_str(&format!("title: \"{}\"\n", title));
+    md.push_str(&format!("description: \"{}\"\n", description));
+    md.push_str(&format!("date: \"2025-{:02}-{:02}\"\n", month, day));
+    md.push_str("author: \"fixture@example.com (Fixture Author)\"\n");
+    md.push_str("layout: \"post\"\n");
+    md.push_str(&format!("language: \"{}\"\n", language));
+    md.push_str(&format!(
+        "locale: \"{}\"\n",
+        language.replace('-', "_")
+    ));
+    md.push_str(&format!("tags: \"{}\"\n", tags));
+    md.push_str(&format!("keywords: \"{}\"\n", tags));
+    md.push_str(&format!(
+        "permalink: \"/{}/posts/{}/\"\n",
+        lang,
+        page_name(page).trim_end_matches(".md")
+    ));
+    md.push_str(&format!(
+        "image: \"../images/{}\"\n",
+        image_name(image)
+    ));
+    md.push_str("---\n\n");
+
+    md.push_str(&format!("# {}\n\n", title));
+    md.push_str(&format!(
+        "![{}](../images/{})\n\n",
+        title,
+        image_name(image)
+    ));
+    for _ in 0..3 {
+        md.push_str(&rng.words(40).join(" "));
+        md.push_str(".\n\n");
+    }
+
+    if !links.is_empty() {
+        md.push_str("## Related\n\n");
+        for target in links {
+            md.push_str(&format!(
+                "- [Post {}](./{})\n",
+                target,
+                page_name(*target).replace(".md", ".html")
+            ));
+        }
+    }
+
+    md
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    fs::write(path, content).with_context(|| {
+        format!("Failed to write fixture file: {}", path.display())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{Cli, LanguageCode};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_generate_fixture_counts() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let options = FixtureOptions {
+            pages: 25,
+            languages: vec!["en".to_string(), "fr".to_string()],
+            ..FixtureOptions::default()
+        };
+
+        let summary = generate_fixture(temp_dir.path(), &options)?;
+        assert_eq!(summary.pages, 25);
+        assert_eq!(summary.links, 25 * LINKS_PER_PAGE);
+
+        let mut files = Vec::new();
+        crate::collect_files_recursive(
+            &temp_dir.path().join("en/posts"),
+            &mut files,
+        )?;
+        assert_eq!(files.len(), 13);
+        assert!(temp_dir
+            .path()
+            .join("fr/posts/post-00011.md")
+            .exists());
+        assert!(!temp_dir
+            .path()
+            .join("fr/posts/post-00012.md")
+            .exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_fixture_is_deterministic() -> Result<()> {
+        let first = tempdir()?;
+        let second = tempdir()?;
+        let options = FixtureOptions {
+            pages: 5,
+            ..FixtureOptions::default()
+        };
+
+        let _ = generate_fixture(first.path(), &options)?;
+        let _ = generate_fixture(second.path(), &options)?;
+
+        let a = fs::read_to_string(
+            first.path().join("en/posts/post-00003.md"),
+        )?;
+        let b = fs::read_to_string(
+            second.path().join("en/posts/post-00003.md"),
+        )?;
+        assert_eq!(a, b);
+        assert!(a.starts_with("---\ntitle: \""));
+        assert!(a.contains("language: \"en-GB\""));
+        assert!(a.contains("](./post-00004.html)"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_fixture_rejects_invalid_language() {
+        let temp_dir = tempdir().unwrap();
+        let options = FixtureOptions {
+            languages: vec!["english".to_string()],
+            ..FixtureOptions::default()
+        };
+        assert!(generate_fixture(temp_dir.path(), &options).is_err());
+    }
+
+    #[test]
+    fn test_fixture_options_from_matches() -> Result<()> {
+        let matches = Cli::build().get_matches_from(vec![
+            "ssg",
+            "gen-fixture",
+            "--pages",
+            "10000",
+            "--langs",
+            "en, fr",
+        ]);
+        let (_, sub_matches) =
+            matches.subcommand().expect("subcommand is present");
+        let options = FixtureOptions::from_matches(sub_matches)?;
+
+        assert_eq!(options.pages, 10_000);
+        assert_eq!(options.languages, vec!["en", "fr"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_locale_for() {
+        assert_eq!(locale_for("en"), "en-GB");
+        assert_eq!(locale_for("fr"), "fr-FR");
+        assert!(LanguageCode::new(&locale_for("de")).is_ok());
+    }
+}
@@ -0,0 +1,251 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Frontmatter Parsing
+//!
+//! Splits Markdown documents into their YAML frontmatter and body, and
+//! exposes the frontmatter as typed values. Values are stored as
+//! [`serde_json::Value`] so they can be passed straight to templates and
+//! JSON outputs.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Frontmatter delimiter line.
+const DELIMITER: &str = "---";
+
+/// Marker inserted by the content preprocessor after the frontmatter.
+const PROCESSED_MARKER: &str = "<!--frontmatter-processed-->";
+
+/// Parsed frontmatter of a content file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frontmatter {
+    values: BTreeMap<String, Value>,
+}
+
+impl Frontmatter {
+    /// Parses YAML frontmatter (without the `---` delimiters).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the YAML is malformed or is not a mapping.
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        if yaml.trim().is_empty() {
+            return Ok(Self::default());
+        }
+
+        let parsed: serde_yaml::Value = serde_yaml::from_str(yaml)
+            .context("Failed to parse frontmatter")?;
+        let value = serde_json::to_value(parsed)
+            .context("Failed to convert frontmatter")?;
+
+        match value {
+            Value::Object(map) => Ok(Self {
+                values: map.into_iter().collect(),
+            }),
+            Value::Null => Ok(Self::default()),
+            _ => {
+                anyhow::bail!("Frontmatter must be a key/value mapping")
+            }
+        }
+    }
+
+    /// Returns the raw value for `key`.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    /// Returns the value for `key` as a string.
+    ///
+    /// Numbers and booleans are converted to their string form.
+    pub fn get_str(&self, key: &str) -> Option<String> {
+        match self.values.get(key)? {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value for `key` as a boolean.
+    ///
+    /// The strings `"true"`/`"yes"` and `"false"`/`"no"` are accepted too.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.values.get(key)? {
+            Value::Bool(b) => Some(*b),
+            Value::String(s) => match s.to_ascii_lowercase().as_str() {
+                "true" | "yes" => Some(true),
+                "false" | "no" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the value for `key` as a list of strings.
+    ///
+    /// Accepts both YAML sequences and comma-separated strings such as
+    /// `tags: "rust, web"`.
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        match self.values.get(key) {
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(|item| match item {
+                    Value::String(s) => Some(s.trim().to_string()),
+                    Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                })
+                .filter(|item| !item.is_empty())
+                .collect(),
+            Some(Value::String(s)) => s
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Sets `key` to `value`, replacing any previous value.
+    pub fn insert(&mut self, key: impl Into<String>, value: Value) {
+        let _ = self.values.insert(key.into(), value);
+    }
+
+    /// Returns `true` if `key` is present.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// Returns `true` if there is no frontmatter.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Iterates over all keys and values in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.values.iter()
+    }
+
+    /// Returns the frontmatter as a JSON object.
+    pub fn to_json(&self) -> Value {
+        Value::Object(
+            self.values
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        )
+    }
+}
+
+/// Splits a document into its frontmatter block and body.
+///
+/// Returns `None` for the frontmatter if the document does not start with a
+/// `---` line. The preprocessing marker is removed from the body.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::frontmatter::split;
+///
+/// let (frontmatter, body) = split("---\ntitle: Hi\n---\n# Hello\n");
+/// assert_eq!(frontmatter, Some("title: Hi"));
+/// assert_eq!(body, "# Hello\n");
+/// ```
+pub fn split(content: &str) -> (Option<&str>, &str) {
+    let trimmed = content.trim_start_matches('\u{feff}');
+    let rest = match trimmed.strip_prefix(DELIMITER) {
+        Some(rest)
+            if rest.starts_with('\n') || rest.starts_with("\r\n") =>
+        {
+            rest.trim_start_matches('\r').trim_start_matches('\n')
+        }
+        _ => return (None, content),
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == DELIMITER {
+            let frontmatter = rest[..offset].trim_end();
+            let body = rest[offset + line.len()..]
+                .trim_start_matches(PROCESSED_MARKER)
+                .trim_start_matches(['\r', '\n']);
+            return (Some(frontmatter), body);
+        }
+        offset += line.len();
+    }
+
+    (None, content)
+}
+
+/// Parses a document into frontmatter and body.
+///
+/// # Errors
+///
+/// Returns an error if the frontmatter block is not valid YAML.
+pub fn parse(content: &str) -> Result<(Frontmatter, &str)> {
+    match split(content) {
+        (Some(yaml), body) => Ok((Frontmatter::from_yaml(yaml)?, body)),
+        (None, body) => Ok((Frontmatter::default(), body)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_without_frontmatter() {
+        let (frontmatter, body) = split("# Title\n---\nText");
+        assert!(frontmatter.is_none());
+        assert_eq!(body, "# Title\n---\nText");
+    }
+
+    #[test]
+    fn test_split_removes_processed_marker() {
+        let content =
+            "---\ntitle: A\n---\n<!--frontmatter-processed-->\nBody";
+        let (frontmatter, body) = split(content);
+        assert_eq!(frontmatter, Some("title: A"));
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn test_parse_values_and_comments() -> Result<()> {
+        let content = "---\n\
+            title: \"Hello\" ## The title\n\
+            draft: yes\n\
+            weight: 3\n\
+            tags: \"rust, web\"\n\
+            aliases: [/old-url/, /2019/old-post/]\n\
+            ---\nBody";
+        let (frontmatter, body) = parse(content)?;
+
+        assert_eq!(
+            frontmatter.get_str("title").as_deref(),
+            Some("Hello")
+        );
+        assert_eq!(frontmatter.get_str("weight").as_deref(), Some("3"));
+        assert_eq!(frontmatter.get_bool("draft"), Some(true));
+        assert_eq!(frontmatter.get_list("tags"), vec!["rust", "web"]);
+        assert_eq!(
+            frontmatter.get_list("aliases"),
+            vec!["/old-url/", "/2019/old-post/"]
+        );
+        assert_eq!(body, "Body");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_yaml() {
+        assert!(parse("---\ntitle: [unclosed\n---\nBody").is_err());
+        assert!(parse("---\n- a\n- b\n---\nBody").is_err());
+    }
+
+    #[test]
+    fn test_to_json() -> Result<()> {
+        let (frontmatter, _) = parse("---\ntitle: A\n---\n")?;
+        assert_eq!(frontmatter.to_json()["title"], "A");
+        Ok(())
+    }
+}
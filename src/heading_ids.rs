@@ -0,0 +1,267 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Heading IDs
+//!
+//! Makes the heading IDs of each generated page unique. The Markdown
+//! renderer slugs every heading on its own, so two `## Setup` headings
+//! both became `#setup` and links to the second one led to the first.
+//! After this stage they are `#setup` and `#setup-1`:
+//!
+//! | Heading | ID |
+//! |---------|----|
+//! | With an ID no other element uses | Kept |
+//! | With an ID used earlier in the page | The ID with the first free `-1`, `-2`… suffix |
+//! | Without an ID | Its text, slugged, made unique the same way |
+//!
+//! IDs of other elements, such as footnotes, are never taken, and the
+//! same page always gets the same IDs. [`assign_heading_ids`] returns
+//! the headings with their final IDs, so tables of contents and
+//! cross-references can be built from the same map.
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::{collections::BTreeSet, fs, ops::Range, path::Path};
+
+use crate::{collect_files_recursive, template::slugify};
+
+/// Matches a heading with its level, attributes, content and closing
+/// level.
+pub(crate) static HEADING_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<h([1-6])(\s[^>]*)?>(.*?)</h([1-6])\s*>")
+        .unwrap_or_else(|e| panic!("Invalid heading regex: {}", e))
+});
+
+/// Matches an `id` attribute.
+static ID_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\sid\s*=\s*(?:"([^"]*)"|'([^']*)')"#)
+        .unwrap_or_else(|e| panic!("Invalid id regex: {}", e))
+});
+
+static TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<[^>]*>")
+        .unwrap_or_else(|e| panic!("Invalid tag regex: {}", e))
+});
+
+/// A heading of a page with its final ID.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HeadingId {
+    /// Level of the heading, 1 for `<h1>`.
+    pub level: u8,
+    /// ID of the heading, unique in the page.
+    pub id: String,
+    /// Text of the heading, as HTML without tags.
+    pub text: String,
+}
+
+/// The IDs used in a page, handing out unused ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdMap {
+    used: BTreeSet<String>,
+}
+
+impl IdMap {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if `id` is used.
+    pub fn contains(&self, id: &str) -> bool {
+        self.used.contains(id)
+    }
+
+    /// Records `id` as used, returning `false` if it already was.
+    pub fn claim(&mut self, id: &str) -> bool {
+        self.used.insert(id.to_string())
+    }
+
+    /// Returns `slug`, or `slug` with the first free `-N` suffix when
+    /// it is used, and records it as used. An empty slug becomes
+    /// `section`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::heading_ids::IdMap;
+    ///
+    /// let mut ids = IdMap::new();
+    /// assert_eq!(ids.unique("setup"), "setup");
+    /// assert_eq!(ids.unique("setup"), "setup-1");
+    /// assert_eq!(ids.unique("setup"), "setup-2");
+    /// ```
+    pub fn unique(&mut self, slug: &str) -> String {
+        let slug = if slug.is_empty() { "section" } else { slug };
+        let mut id = slug.to_string();
+        let mut n = 0;
+        while self.contains(&id) {
+            n += 1;
+            id = format!("{}-{}", slug, n);
+        }
+        let _ = self.claim(&id);
+        id
+    }
+}
+
+/// Gives every heading of `html` an ID no other element of the page
+/// uses, returning the page and its headings in order.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::heading_ids::assign_heading_ids;
+///
+/// let (html, headings) = assign_heading_ids(
+///     "<h2 id=\"setup\">Setup</h2><h2 id=\"setup\">Setup</h2>",
+/// );
+/// assert_eq!(
+///     html,
+///     "<h2 id=\"setup\">Setup</h2><h2 id=\"setup-1\">Setup</h2>"
+/// );
+/// assert_eq!(headings[1].id, "setup-1");
+/// ```
+pub fn assign_heading_ids(html: &str) -> (String, Vec<HeadingId>) {
+    let own_ids: Vec<Range<usize>> = HEADING_RE
+        .captures_iter(html)
+        .filter_map(|caps| {
+            caps.get(2).map(|attributes| attributes.range())
+        })
+        .collect();
+    let mut ids = IdMap::new();
+    for caps in ID_RE.captures_iter(html) {
+        let (whole, id) =
+            match (caps.get(0), caps.get(1).or_else(|| caps.get(2))) {
+                (Some(whole), Some(id)) => (whole, id),
+                _ => continue,
+            };
+        if !own_ids.iter().any(|range| range.contains(&whole.start())) {
+            let _ = ids.claim(id.as_str());
+        }
+    }
+
+    let mut headings = Vec::new();
+    let html = HEADING_RE.replace_all(html, |caps: &Captures<'_>| {
+        if caps[1] != caps[4] {
+            return caps[0].to_string();
+        }
+        let level: u8 = caps[1].parse().unwrap_or_default();
+        let attributes = caps.get(2).map_or("", |m| m.as_str());
+        let text = TAG_RE.replace_all(&caps[3], "").trim().to_string();
+        let current = ID_RE
+            .captures(attributes)
+            .and_then(|id| id.get(1).or_else(|| id.get(2)))
+            .map(|id| id.as_str().to_string());
+        let (id, attributes) = match current {
+            Some(id) if !id.is_empty() && ids.claim(&id) => {
+                (id, attributes.to_string())
+            }
+            Some(id) => {
+                let slug =
+                    if id.is_empty() { slugify(&text) } else { id };
+                let unique = ids.unique(&slug);
+                let attribute = format!(" id=\"{}\"", unique);
+                let attributes =
+                    ID_RE.replace(attributes, attribute.as_str());
+                (unique, attributes.into_owned())
+            }
+            None => {
+                let id = ids.unique(&slugify(&text));
+                let attributes =
+                    format!(" id=\"{}\"{}", id, attributes);
+                (id, attributes)
+            }
+        };
+        let heading = format!(
+            "<h{level}{}>{}</h{level}>",
+            attributes,
+            &caps[3],
+            level = level
+        );
+        headings.push(HeadingId { level, id, text });
+        heading
+    });
+    (html.into_owned(), headings)
+}
+
+/// Applies [`assign_heading_ids`] to every HTML file below `site_dir`.
+///
+/// Returns the number of files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written.
+pub fn assign_heading_ids_site(site_dir: &Path) -> Result<usize> {
+    if !site_dir.exists() {
+        return Ok(0);
+    }
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+
+    let mut rewritten = 0;
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let (assigned, _) = assign_heading_ids(&html);
+        if assigned == html {
+            continue;
+        }
+        fs::write(&path, assigned).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_and_missing_ids() {
+        let html = "<h1>Guide</h1>\
+                    <h2 id=\"setup\" class=\"x\">Setup</h2>\
+                    <h3 id=\"setup\">Setup</h3>\
+                    <h2>Usage <code>run</code></h2>\
+                    <p id=\"usage\">See <a href=\"#setup\">setup</a>.</p>";
+        let (assigned, headings) = assign_heading_ids(html);
+        assert_eq!(
+            assigned,
+            "<h1 id=\"guide\">Guide</h1>\
+             <h2 id=\"setup\" class=\"x\">Setup</h2>\
+             <h3 id=\"setup-1\">Setup</h3>\
+             <h2 id=\"usage-run\">Usage <code>run</code></h2>\
+             <p id=\"usage\">See <a href=\"#setup\">setup</a>.</p>"
+        );
+        assert_eq!(
+            headings
+                .iter()
+                .map(|heading| heading.id.as_str())
+                .collect::<Vec<_>>(),
+            ["guide", "setup", "setup-1", "usage-run"]
+        );
+        assert_eq!(headings[3].text, "Usage run");
+        assert_eq!(assign_heading_ids(&assigned).0, assigned);
+    }
+
+    #[test]
+    fn test_ids_of_other_elements_are_not_taken() {
+        let (assigned, _) = assign_heading_ids(
+            "<h2>Notes</h2><section id=\"notes\"></section>",
+        );
+        assert_eq!(
+            assigned,
+            "<h2 id=\"notes-1\">Notes</h2><section id=\"notes\"></section>"
+        );
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # humans.txt
+//!
+//! Writes [`humans.txt`](https://humanstxt.org/) from the `[humans]`
+//! table of the configuration file, instead of from the humans fields
+//! repeated in the frontmatter of every page:
+//!
+//! ```toml
+//! [humans]
+//! enabled = true
+//! git = true
+//! thanks = ["The Rust community"]
+//! standards = ["HTML5", "CSS3", "RSS"]
+//! components = ["Kaishi"]
+//! software = ["Shokunin", "Rust"]
+//!
+//! [[humans.team]]
+//! name = "Jane Doe"
+//! role = "Author"
+//! website = "https://example.com"
+//! twitter = "@janedoe"
+//! location = "London, UK"
+//! ```
+//!
+//! With `git`, the authors of the commits to the content directory are
+//! listed after the team as contributors, most commits first, and the
+//! date of the last commit is the last update of the site. Bots are
+//! left out, and so are email addresses. `last_updated` in the table
+//! takes precedence over the git date.
+//!
+//! Without `enabled`, the `humans.txt` written by the core compile is
+//! kept.
+
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    auxiliary::AuxiliaryGenerator, content::ContentSet, dates::Date,
+};
+
+/// Role of the people found in the git history.
+pub const CONTRIBUTOR_ROLE: &str = "Contributor";
+
+/// A person listed in `humans.txt`.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct Human {
+    /// Name of the person.
+    pub name: String,
+    /// Role in the site, e.g. `Author`.
+    pub role: Option<String>,
+    /// Website of the person.
+    pub website: Option<String>,
+    /// Twitter handle of the person.
+    pub twitter: Option<String>,
+    /// Location of the person.
+    pub location: Option<String>,
+}
+
+/// Options of the `[humans]` config table.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct HumansOptions {
+    /// Writes `humans.txt` from these options.
+    pub enabled: bool,
+    /// Adds the contributors and last update from the git history.
+    pub git: bool,
+    /// People behind the site.
+    pub team: Vec<Human>,
+    /// People and projects thanked.
+    pub thanks: Vec<String>,
+    /// Last update of the site, overriding the git date.
+    pub last_updated: Option<String>,
+    /// Standards the site follows.
+    pub standards: Vec<String>,
+    /// Components the site is built with.
+    pub components: Vec<String>,
+    /// Software the site is built with.
+    pub software: Vec<String>,
+}
+
+impl HumansOptions {
+    /// Checks that every team member has a name and that
+    /// `last_updated` is a date.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        for (i, human) in self.team.iter().enumerate() {
+            ensure!(
+                !human.name.trim().is_empty(),
+                "team[{}] needs a name",
+                i
+            );
+        }
+        if let Some(date) = &self.last_updated {
+            ensure!(
+                Date::parse(date).is_some(),
+                "last_updated `{}` must be a date such as 2025-03-12",
+                date
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The contents of `humans.txt`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HumansData {
+    /// People behind the site, the configured team first.
+    pub team: Vec<Human>,
+    /// People and projects thanked.
+    pub thanks: Vec<String>,
+    /// Last update of the site.
+    pub last_updated: Option<Date>,
+    /// Standards the site follows.
+    pub standards: Vec<String>,
+    /// Components the site is built with.
+    pub components: Vec<String>,
+    /// Software the site is built with.
+    pub software: Vec<String>,
+}
+
+impl HumansData {
+    /// Creates the data from the `[humans]` options alone.
+    pub fn from_config(options: &HumansOptions) -> Self {
+        Self {
+            team: options.team.clone(),
+            thanks: options.thanks.clone(),
+            last_updated: options
+                .last_updated
+                .as_deref()
+                .and_then(Date::parse),
+            standards: options.standards.clone(),
+            components: options.components.clone(),
+            software: options.software.clone(),
+        }
+    }
+
+    /// Adds the authors of the commits to `root` that are not in the
+    /// team yet as contributors, and takes the last update from the
+    /// last commit when it is not set. Leaves the data alone if `root`
+    /// is not in a git repository.
+    pub fn with_git_history(self, root: &Path) -> Self {
+        match git_log(root) {
+            Some(log) => self.with_history(&log),
+            None => self,
+        }
+    }
+
+    /// Renders `humans.txt`, leaving out empty sections.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::humans::{Human, HumansData};
+    ///
+    /// let data = HumansData {
+    ///     team: vec![Human {
+    ///         name: "Jane Doe".into(),
+    ///         role: Some("Author".into()),
+    ///         ..Human::default()
+    ///     }],
+    ///     software: vec!["Shokunin".into(), "Rust".into()],
+    ///     ..HumansData::default()
+    /// };
+    /// assert_eq!(
+    ///     data.to_text(),
+    ///     "/* TEAM */\n    Author: Jane Doe\n\n\
+    ///      /* SITE */\n    Software: Shokunin, Rust\n"
+    /// );
+    /// ```
+    pub fn to_text(&self) -> String {
+        let mut sections = Vec::new();
+        if !self.team.is_empty() {
+            let mut out = String::from("/* TEAM */\n");
+            for (i, human) in self.team.iter().enumerate() {
+                if i > 0 {
+                    out.push('\n');
+                }
+                let role = human.role.as_deref().unwrap_or("Name");
+                let _ = writeln!(out, "    {}: {}", role, human.name);
+                for (label, value) in [
+                    ("Website", &human.website),
+                    ("Twitter", &human.twitter),
+                    ("Location", &human.location),
+                ] {
+                    if let Some(value) = value {
+                        let _ =
+                            writeln!(out, "    {}: {}", label, value);
+                    }
+                }
+            }
+            sections.push(out);
+        }
+        if !self.thanks.is_empty() {
+            let mut out = String::from("/* THANKS */\n");
+            for name in &self.thanks {
+                let _ = writeln!(out, "    {}", name);
+            }
+            sections.push(out);
+        }
+
+        let mut site = Vec::new();
+        if let Some(date) = self.last_updated {
+            site.push(format!(
+                "    Last update: {:04}/{:02}/{:02}",
+                date.year, date.month, date.day
+            ));
+        }
+        for (label, values) in [
+            ("Standards", &self.standards),
+            ("Components", &self.components),
+            ("Software", &self.software),
+        ] {
+            if !values.is_empty() {
+                site.push(format!(
+                    "    {}: {}",
+                    label,
+                    values.join(", ")
+                ));
+            }
+        }
+        if !site.is_empty() {
+            sections.push(format!("/* SITE */\n{}\n", site.join("\n")));
+        }
+        sections.join("\n")
+    }
+
+    /// Applies a git log of `name\tdate` lines, newest first.
+    fn with_history(mut self, log: &str) -> Self {
+        let mut commits: Vec<(String, usize)> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut last = None;
+        for line in log.lines() {
+            let (name, date) = match line.split_once('\t') {
+                Some((name, date)) => (name.trim(), date.trim()),
+                None => continue,
+            };
+            if last.is_none() {
+                last = Date::parse(date);
+            }
+            if name.is_empty() || name.ends_with("[bot]") {
+                continue;
+            }
+            let i = *index.entry(name.to_lowercase()).or_insert_with(
+                || {
+                    commits.push((name.to_string(), 0));
+                    commits.len() - 1
+                },
+            );
+            commits[i].1 += 1;
+        }
+        // A stable sort keeps the earliest listed first among equals.
+        commits.sort_by_key(|(_, count)| Reverse(*count));
+
+        for (name, _) in commits {
+            if !self
+                .team
+                .iter()
+                .any(|human| human.name.eq_ignore_ascii_case(&name))
+            {
+                self.team.push(Human {
+                    name,
+                    role: Some(CONTRIBUTOR_ROLE.to_string()),
+                    ..Human::default()
+                });
+            }
+        }
+        self.last_updated = self.last_updated.or(last);
+        self
+    }
+}
+
+/// Returns the author and date of each commit to `root`, newest first,
+/// or `None` if `root` is not in a git repository.
+fn git_log(root: &Path) -> Option<String> {
+    if !root.is_dir() {
+        return None;
+    }
+    match Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["log", "--format=%aN%x09%cI", "--", "."])
+        .output()
+    {
+        Ok(output) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        _ => None,
+    }
+}
+
+/// Writes `humans.txt` from the `[humans]` options.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HumansGenerator {
+    /// The `[humans]` options.
+    pub options: HumansOptions,
+}
+
+impl AuxiliaryGenerator for HumansGenerator {
+    fn name(&self) -> &str {
+        "humans"
+    }
+
+    fn generate(
+        &self,
+        content: &ContentSet,
+        site_dir: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        if !self.options.enabled {
+            return Ok(Vec::new());
+        }
+        let mut data = HumansData::from_config(&self.options);
+        if self.options.git {
+            data = data.with_git_history(&content.root);
+        }
+        let path = site_dir.join("humans.txt");
+        fs::write(&path, data.to_text()).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        Ok(vec![path])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config_with_history() {
+        let options: HumansOptions = toml::from_str(
+            r#"
+            enabled = true
+            git = true
+            thanks = ["The Rust community"]
+            standards = ["HTML5", "CSS3"]
+
+            [[team]]
+            name = "Jane Doe"
+            role = "Author"
+            twitter = "@janedoe"
+            "#,
+        )
+        .unwrap();
+        assert!(options.validate().is_ok());
+
+        let log = "Sam Roe\t2025-03-12T09:00:00+00:00\n\
+                   dependabot[bot]\t2025-03-11T09:00:00+00:00\n\
+                   Alex Poe\t2025-03-10T09:00:00+00:00\n\
+                   jane doe\t2025-03-09T09:00:00+00:00\n\
+                   Alex Poe\t2025-03-08T09:00:00+00:00\n";
+        let data = HumansData::from_config(&options).with_history(log);
+        assert_eq!(
+            data.to_text(),
+            "/* TEAM */\n\
+             \x20   Author: Jane Doe\n\
+             \x20   Twitter: @janedoe\n\n\
+             \x20   Contributor: Alex Poe\n\n\
+             \x20   Contributor: Sam Roe\n\n\
+             /* THANKS */\n\
+             \x20   The Rust community\n\n\
+             /* SITE */\n\
+             \x20   Last update: 2025/03/12\n\
+             \x20   Standards: HTML5, CSS3\n"
+        );
+    }
+
+    #[test]
+    fn test_configured_date_wins() {
+        let options = HumansOptions {
+            last_updated: Some("2024-12-31".into()),
+            ..HumansOptions::default()
+        };
+        let data = HumansData::from_config(&options)
+            .with_history("Sam Roe\t2025-03-12T09:00:00+00:00\n");
+        assert_eq!(
+            data.last_updated,
+            Some(Date {
+                year: 2024,
+                month: 12,
+                day: 31
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut options = HumansOptions {
+            team: vec![Human::default()],
+            ..HumansOptions::default()
+        };
+        assert!(options.validate().is_err());
+        options.team.clear();
+        options.last_updated = Some("soon".into());
+        assert!(options.validate().is_err());
+    }
+}
@@ -0,0 +1,710 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Translation Catalogs
+//!
+//! Loads user-provided translation catalogs from the `locales/`
+//! directory and exposes them to templates as `t(key, language)`:
+//!
+//! ```text
+//! <a href="/">{{ t("nav.home") }}</a>
+//! <p>{{ t("footer.copyright", language) }}</p>
+//! ```
+//!
+//! A catalog is named after its language, either as a file
+//! (`locales/fr.ftl`) or as a directory of files (`locales/fr/main.ftl`,
+//! `locales/fr/blog.po`). Three formats are read:
+//!
+//! | Extension | Format |
+//! |-----------|--------|
+//! | `.ftl` | Fluent messages, attributes (`key.attr`) and terms |
+//! | `.po` | gettext entries; fuzzy and untranslated entries are skipped |
+//! | `.yml`, `.yaml` | Nested maps, flattened to dotted keys |
+//!
+//! Only simple Fluent messages are supported: `{ -term }` references are
+//! expanded, other placeables are kept as written.
+//!
+//! A key is looked up in the requested language, then its
+//! [fallbacks](LanguageTag::fallbacks) (`fr-CA`, then `fr`), then the
+//! site language, and finally in the built-in dictionaries.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    collect_files_recursive, diagnostics::Diagnostic,
+    locales::LanguageTag, template::Engine,
+};
+
+/// Default catalog directory, relative to the project root.
+pub const LOCALES_DIR: &str = "locales";
+
+/// Name of the template function.
+pub const TRANSLATE_FUNCTION: &str = "t";
+
+/// Messages of one language, by key.
+pub type Catalog = BTreeMap<String, String>;
+
+/// Translates message keys with user catalogs over the built-in
+/// dictionaries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Translator {
+    language: String,
+    catalogs: BTreeMap<String, Catalog>,
+}
+
+impl Translator {
+    /// Creates a translator falling back to `language`, the site
+    /// language, with no catalogs.
+    pub fn new(language: impl Into<String>) -> Self {
+        Self {
+            language: canonical(&language.into()),
+            catalogs: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a translator with the catalogs of `dir`. A missing
+    /// directory gives a translator with no catalogs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a catalog cannot be read or parsed, or is not
+    /// named after a valid language tag.
+    pub fn load(dir: &Path, language: &str) -> Result<Self> {
+        let mut translator = Self::new(language);
+        if dir.exists() {
+            let _ = translator.load_dir(dir)?;
+        }
+        Ok(translator)
+    }
+
+    /// Returns the fallback language.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Returns the languages with a catalog, sorted.
+    pub fn languages(&self) -> Vec<&str> {
+        self.catalogs.keys().map(String::as_str).collect()
+    }
+
+    /// Returns the catalog of `language`, if one was loaded.
+    pub fn catalog(&self, language: &str) -> Option<&Catalog> {
+        self.catalogs.get(&canonical(language))
+    }
+
+    /// Adds the catalogs found in `dir`, in path order, so later files
+    /// override earlier ones for the same key.
+    ///
+    /// Returns the number of messages read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a catalog cannot be read or parsed, or is not
+    /// named after a valid language tag.
+    pub fn load_dir(&mut self, dir: &Path) -> Result<usize> {
+        let mut files = Vec::new();
+        collect_files_recursive(dir, &mut files).with_context(
+            || format!("Failed to read {}", dir.display()),
+        )?;
+        files.sort();
+
+        let mut loaded = 0;
+        for path in files {
+            let format = match Format::of(&path) {
+                Some(format) => format,
+                None => continue,
+            };
+            let language = catalog_language(dir, &path)?;
+            let source =
+                fs::read_to_string(&path).with_context(|| {
+                    format!("Failed to read {}", path.display())
+                })?;
+            let messages = format.parse(&path, &source)?;
+            loaded += messages.len();
+            self.catalogs.entry(language).or_default().extend(messages);
+        }
+        Ok(loaded)
+    }
+
+    /// Adds one message to the catalog of `language`.
+    pub fn insert(
+        &mut self,
+        language: &str,
+        key: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        let _ = self
+            .catalogs
+            .entry(canonical(language))
+            .or_default()
+            .insert(key.into(), message.into());
+    }
+
+    /// Returns the message for `key` in `language`, or in the fallback
+    /// language when `language` is `None`.
+    pub fn translate(
+        &self,
+        key: &str,
+        language: Option<&str>,
+    ) -> Option<String> {
+        let mut candidates = Vec::new();
+        for tag in language.into_iter().chain([self.language.as_str()])
+        {
+            match LanguageTag::parse(tag) {
+                Ok(tag) => candidates.extend(tag.fallbacks()),
+                Err(_) => candidates.push(tag.to_string()),
+            }
+        }
+
+        candidates
+            .iter()
+            .find_map(|candidate| {
+                self.catalogs.get(candidate)?.get(key).cloned()
+            })
+            .or_else(|| {
+                candidates.iter().find_map(|candidate| {
+                    langweave::translate(candidate, key)
+                        .ok()
+                        .filter(|message| message != key)
+                })
+            })
+    }
+
+    /// Registers `t(key, language)` with `engine`. Without a language,
+    /// the fallback language is used.
+    pub fn register(&self, engine: &mut Engine) {
+        let translator = self.clone();
+        engine.register_function(TRANSLATE_FUNCTION, move |args| {
+            let key = args
+                .first()
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("`t` expects a message key"))?;
+            let language = args.get(1).and_then(Value::as_str);
+            translator
+                .translate(key, language)
+                .map(Value::String)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "no translation for `{}` in `{}`",
+                        key,
+                        language.unwrap_or(&translator.language)
+                    )
+                })
+        });
+        engine.set_function_state(
+            TRANSLATE_FUNCTION,
+            format!("{:?}", self),
+        );
+    }
+}
+
+/// A catalog file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Fluent,
+    Gettext,
+    Yaml,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "ftl" => Some(Self::Fluent),
+            "po" => Some(Self::Gettext),
+            "yml" | "yaml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    fn parse(self, path: &Path, source: &str) -> Result<Catalog> {
+        match self {
+            Self::Fluent => parse_ftl(path, source),
+            Self::Gettext => parse_po(path, source),
+            Self::Yaml => parse_yaml(path, source),
+        }
+    }
+}
+
+/// Returns the canonical form of `language`, or `language` itself if it
+/// is not a valid tag.
+fn canonical(language: &str) -> String {
+    LanguageTag::parse(language)
+        .map(|tag| tag.to_string())
+        .unwrap_or_else(|_| language.to_string())
+}
+
+/// Returns the language of the catalog at `path`: its file stem at the
+/// top of `dir`, or the name of its top-level directory.
+fn catalog_language(dir: &Path, path: &Path) -> Result<String> {
+    let relative = path.strip_prefix(dir).unwrap_or(path);
+    let mut components = relative.components();
+    let first = components
+        .next()
+        .map(|c| PathBuf::from(c.as_os_str()))
+        .unwrap_or_default();
+    let name = if components.next().is_some() {
+        first.to_string_lossy().into_owned()
+    } else {
+        first
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    LanguageTag::parse(&name.replace('_', "-"))
+        .map(|tag| tag.to_string())
+        .with_context(|| {
+            format!(
+                "{} is not named after a language, e.g. `fr.ftl` or \
+                 `fr/main.ftl`",
+                path.display()
+            )
+        })
+}
+
+/// Returns an error pointing at `line` of a catalog.
+fn syntax_error(
+    path: &Path,
+    source: &str,
+    line: usize,
+    message: &str,
+) -> anyhow::Error {
+    let length = source
+        .lines()
+        .nth(line - 1)
+        .map_or(1, |text| text.trim_end().chars().count());
+    Diagnostic::error(message)
+        .with_location(path, source, line, 1, length)
+        .into()
+}
+
+/// Parses the messages, attributes and terms of a Fluent resource.
+fn parse_ftl(path: &Path, source: &str) -> Result<Catalog> {
+    // (key, value, is_term) in source order.
+    let mut entries: Vec<(String, String, bool)> = Vec::new();
+    let mut message: Option<String> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line.starts_with('#') {
+            message = None;
+            continue;
+        }
+
+        if line.starts_with(char::is_whitespace) {
+            let text = line.trim();
+            let id = message.as_ref().ok_or_else(|| {
+                syntax_error(
+                    path,
+                    source,
+                    index + 1,
+                    "indented line outside a message",
+                )
+            })?;
+            if let Some(attribute) = text.strip_prefix('.') {
+                let (name, value) =
+                    split_entry(attribute).ok_or_else(|| {
+                        syntax_error(
+                            path,
+                            source,
+                            index + 1,
+                            "expected `.attribute = value`",
+                        )
+                    })?;
+                entries.push((
+                    format!("{}.{}", id, name),
+                    value.to_string(),
+                    false,
+                ));
+            } else if let Some((key, value, _)) = entries.last_mut() {
+                if key == id {
+                    if !value.is_empty() {
+                        value.push('\n');
+                    }
+                    value.push_str(text);
+                }
+            }
+            continue;
+        }
+
+        let (id, value) = split_entry(line).ok_or_else(|| {
+            syntax_error(
+                path,
+                source,
+                index + 1,
+                "expected `key = value`",
+            )
+        })?;
+        let term = id.starts_with('-');
+        let name = id.trim_start_matches('-');
+        if name.is_empty()
+            || !name.chars().all(|c| {
+                c.is_ascii_alphanumeric() || c == '-' || c == '_'
+            })
+        {
+            return Err(syntax_error(
+                path,
+                source,
+                index + 1,
+                &format!("invalid message identifier `{}`", id),
+            ));
+        }
+        message = Some(id.to_string());
+        entries.push((id.to_string(), value.to_string(), term));
+    }
+
+    let terms: BTreeMap<String, String> = entries
+        .iter()
+        .filter(|(_, _, term)| *term)
+        .map(|(key, value, _)| (key.clone(), value.clone()))
+        .collect();
+    Ok(entries
+        .into_iter()
+        .filter(|(key, _, _)| !key.starts_with('-'))
+        .map(|(key, value, _)| (key, expand_terms(&value, &terms)))
+        .collect())
+}
+
+/// Splits `name = value`, trimming both sides.
+fn split_entry(line: &str) -> Option<(&str, &str)> {
+    let (name, value) = line.split_once('=')?;
+    let name = name.trim();
+    (!name.is_empty()).then(|| (name, value.trim()))
+}
+
+/// Replaces `{ -term }` references with the term's value.
+fn expand_terms(
+    value: &str,
+    terms: &BTreeMap<String, String>,
+) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => end,
+            None => break,
+        };
+        let reference = rest[start + 1..start + end].trim();
+        expanded.push_str(&rest[..start]);
+        match terms.get(reference) {
+            Some(term) => expanded.push_str(term),
+            None => expanded.push_str(&rest[start..=start + end]),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Parses the translated entries of a gettext catalog. Plural entries
+/// use their first form.
+fn parse_po(path: &Path, source: &str) -> Result<Catalog> {
+    #[derive(Default)]
+    struct Entry {
+        fuzzy: bool,
+        msgid: Option<String>,
+        msgstr: Option<String>,
+    }
+
+    fn finish(entry: Entry, catalog: &mut Catalog) {
+        if let (Some(msgid), Some(msgstr)) = (entry.msgid, entry.msgstr)
+        {
+            if !entry.fuzzy && !msgid.is_empty() && !msgstr.is_empty() {
+                let _ = catalog.insert(msgid, msgstr);
+            }
+        }
+    }
+
+    let mut catalog = Catalog::new();
+    let mut entry = Entry::default();
+    // The string a continuation line appends to.
+    let mut field: Option<&str> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            if entry.msgstr.is_some() {
+                finish(std::mem::take(&mut entry), &mut catalog);
+            }
+            if let Some(flags) = comment.strip_prefix(',') {
+                entry.fuzzy |=
+                    flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            }
+            field = None;
+            continue;
+        }
+
+        let (keyword, rest) = match line.find(char::is_whitespace) {
+            Some(split) if !line.starts_with('"') => {
+                (&line[..split], line[split..].trim())
+            }
+            _ => ("", line),
+        };
+        let text = unquote(rest).ok_or_else(|| {
+            syntax_error(
+                path,
+                source,
+                index + 1,
+                "expected a quoted string",
+            )
+        })?;
+        match keyword {
+            "" => match field {
+                Some("msgid") => entry
+                    .msgid
+                    .get_or_insert_with(String::new)
+                    .push_str(&text),
+                Some("msgstr") => entry
+                    .msgstr
+                    .get_or_insert_with(String::new)
+                    .push_str(&text),
+                Some(_) => {}
+                None => {
+                    return Err(syntax_error(
+                        path,
+                        source,
+                        index + 1,
+                        "string outside an entry",
+                    ))
+                }
+            },
+            "msgctxt" | "msgid" => {
+                if entry.msgstr.is_some() {
+                    finish(std::mem::take(&mut entry), &mut catalog);
+                }
+                if keyword == "msgid" {
+                    entry.msgid = Some(text);
+                    field = Some("msgid");
+                } else {
+                    field = Some("msgctxt");
+                }
+            }
+            "msgid_plural" => field = Some("msgid_plural"),
+            "msgstr" | "msgstr[0]" => {
+                entry.msgstr = Some(text);
+                field = Some("msgstr");
+            }
+            keyword if keyword.starts_with("msgstr[") => {
+                field = Some("msgstr_plural");
+            }
+            keyword => {
+                return Err(syntax_error(
+                    path,
+                    source,
+                    index + 1,
+                    &format!("unknown keyword `{}`", keyword),
+                ))
+            }
+        }
+    }
+    finish(entry, &mut catalog);
+    Ok(catalog)
+}
+
+/// Unquotes and unescapes a gettext string.
+fn unquote(text: &str) -> Option<String> {
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+    let mut unescaped = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => unescaped.push('\n'),
+            't' => unescaped.push('\t'),
+            other => unescaped.push(other),
+        }
+    }
+    Some(unescaped)
+}
+
+/// Parses a YAML map, flattening nested maps to dotted keys.
+fn parse_yaml(path: &Path, source: &str) -> Result<Catalog> {
+    fn flatten(
+        prefix: &str,
+        value: &serde_yaml::Value,
+        catalog: &mut Catalog,
+    ) -> Result<()> {
+        let text = match value {
+            serde_yaml::Value::Mapping(map) => {
+                for (key, value) in map {
+                    let key = match key {
+                        serde_yaml::Value::String(key) => key.clone(),
+                        serde_yaml::Value::Number(key) => {
+                            key.to_string()
+                        }
+                        _ => bail!(
+                            "`{}` has a key that is not text",
+                            prefix
+                        ),
+                    };
+                    let key = if prefix.is_empty() {
+                        key
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    flatten(&key, value, catalog)?;
+                }
+                return Ok(());
+            }
+            serde_yaml::Value::String(text) => text.clone(),
+            serde_yaml::Value::Number(number) => number.to_string(),
+            serde_yaml::Value::Bool(flag) => flag.to_string(),
+            serde_yaml::Value::Null => return Ok(()),
+            _ => bail!("`{}` must be text or a map", prefix),
+        };
+        let _ = catalog.insert(prefix.to_string(), text);
+        Ok(())
+    }
+
+    let document: serde_yaml::Value = serde_yaml::from_str(source)
+        .with_context(|| {
+            format!("Failed to parse {}", path.display())
+        })?;
+    let mut catalog = Catalog::new();
+    if !document.is_null() {
+        if !document.is_mapping() {
+            bail!("{} must contain a map of messages", path.display());
+        }
+        flatten("", &document, &mut catalog).with_context(|| {
+            format!("Invalid catalog {}", path.display())
+        })?;
+    }
+    Ok(catalog)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_ftl() -> Result<()> {
+        let catalog = parse_ftl(
+            Path::new("fr.ftl"),
+            "# Navigation\n\
+             -brand = Shokunin\n\
+             nav-home = Accueil\n\
+             \x20   .title = Retour à { -brand }\n\
+             welcome = Bienvenue sur { -brand },\n\
+             \x20   { $name } !\n",
+        )?;
+        assert_eq!(catalog["nav-home"], "Accueil");
+        assert_eq!(catalog["nav-home.title"], "Retour à Shokunin");
+        assert_eq!(
+            catalog["welcome"],
+            "Bienvenue sur Shokunin,\n{ $name } !"
+        );
+        assert!(!catalog.contains_key("-brand"));
+
+        assert!(parse_ftl(Path::new("fr.ftl"), "nav home\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_po() -> Result<()> {
+        let catalog = parse_po(
+            Path::new("de.po"),
+            r#"msgid ""
+msgstr ""
+"Language: de\n"
+
+msgid "Read more"
+msgstr "Weiterlesen"
+
+#, fuzzy
+msgid "Archive"
+msgstr "Archiv"
+
+msgid "Untranslated"
+msgstr ""
+
+msgid "Say \"hi\""
+msgstr ""
+"Sag \"hallo\""
+
+msgid "post"
+msgid_plural "posts"
+msgstr[0] "Beitrag"
+msgstr[1] "Beiträge"
+"#,
+        )?;
+        assert_eq!(catalog["Read more"], "Weiterlesen");
+        assert_eq!(catalog["Say \"hi\""], "Sag \"hallo\"");
+        assert_eq!(catalog["post"], "Beitrag");
+        assert_eq!(catalog.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_yaml() -> Result<()> {
+        let catalog = parse_yaml(
+            Path::new("es.yml"),
+            "nav:\n  home: Inicio\n  posts: Artículos\nyear: 2025\n",
+        )?;
+        assert_eq!(catalog["nav.home"], "Inicio");
+        assert_eq!(catalog["year"], "2025");
+        assert!(parse_yaml(Path::new("es.yml"), "- a\n- b\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_dir_and_fallbacks() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("en-gb.ftl"), "nav-home = Home\n")?;
+        fs::create_dir(dir.path().join("fr"))?;
+        fs::write(
+            dir.path().join("fr/main.ftl"),
+            "nav-home = Accueil\n",
+        )?;
+        fs::write(dir.path().join("README.md"), "ignored")?;
+
+        let translator = Translator::load(dir.path(), "en-GB")?;
+        assert_eq!(translator.languages(), ["en-GB", "fr"]);
+        assert_eq!(
+            translator.translate("nav-home", Some("fr-CA")).as_deref(),
+            Some("Accueil")
+        );
+        assert_eq!(
+            translator.translate("nav-home", Some("de")).as_deref(),
+            Some("Home")
+        );
+
+        fs::write(dir.path().join("english.ftl"), "a = b\n")?;
+        assert!(Translator::load(dir.path(), "en-GB").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_function() -> Result<()> {
+        let mut translator = Translator::new("en");
+        translator.insert("en", "nav.home", "Home");
+        translator.insert("fr", "nav.home", "Accueil");
+
+        let mut engine = Engine::new();
+        translator.register(&mut engine);
+        let html = engine.render(
+            r#"{{ t("nav.home") }} / {{ t("nav.home", language) }}"#,
+            &json!({ "language": "fr" }),
+        )?;
+        assert_eq!(html, "Home / Accueil");
+        assert!(engine
+            .render(r#"{{ t("nav.missing_key") }}"#, &json!({}))
+            .is_err());
+        Ok(())
+    }
+}
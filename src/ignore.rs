@@ -0,0 +1,315 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Ignore Rules
+//!
+//! Keeps files such as editor swap files, `.DS_Store` or source-only
+//! folders out of the site. The rules come from three places, later
+//! ones winning:
+//!
+//! 1. the defaults, [`DEFAULT_PATTERNS`];
+//! 2. a [`.ssgignore`](IGNORE_FILE) file at the root of the directory
+//!    being read, such as the content directory or theme assets;
+//! 3. the `ignore` list of the configuration file:
+//!
+//! ```toml
+//! ignore = ["*.psd", "/sources/", "!keep.psd"]
+//! ```
+//!
+//! The patterns follow `.gitignore`:
+//!
+//! | Pattern | Ignores |
+//! |---------|---------|
+//! | `*.swp` | Files and directories with a matching name, at any depth |
+//! | `/notes.md`, `drafts/old/*` | Paths from the root, as a pattern with a `/` |
+//! | `sources/` | Directories only |
+//! | `!keep.psd` | Nothing; keeps what an earlier pattern ignores |
+//!
+//! `*` stays within one path segment and `**` matches any number of
+//! them. Nothing below an ignored directory is read, so `!` cannot keep
+//! a file inside one.
+//!
+//! [`collect_files_recursive`](crate::collect_files_recursive),
+//! [`copy_dir_all`](crate::copy_dir_all),
+//! [`copy_dir_all_async`](crate::copy_dir_all_async) and the compiler
+//! all skip the ignored files.
+
+use anyhow::{ensure, Context, Result};
+use glob::{MatchOptions, Pattern};
+use once_cell::sync::Lazy;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::RwLock,
+};
+
+/// File holding the ignore rules of a directory.
+pub const IGNORE_FILE: &str = ".ssgignore";
+
+/// Patterns ignored unless a later rule keeps them.
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    IGNORE_FILE,
+    ".DS_Store",
+    "Thumbs.db",
+    "*.swp",
+    "*.swo",
+    "*~",
+    ".#*",
+    "#*#",
+];
+
+/// Patterns of the configuration file, see [`configure`].
+static CONFIGURED: Lazy<RwLock<Vec<String>>> =
+    Lazy::new(RwLock::default);
+
+/// How patterns match names and paths.
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Makes `patterns`, the `ignore` list of the configuration file, apply
+/// to every directory read from now on.
+pub fn configure(patterns: &[String]) {
+    if let Ok(mut configured) = CONFIGURED.write() {
+        *configured = patterns.to_vec();
+    }
+}
+
+/// Returns the patterns set by [`configure`].
+pub fn configured() -> Vec<String> {
+    CONFIGURED
+        .read()
+        .map(|configured| configured.clone())
+        .unwrap_or_default()
+}
+
+/// One pattern of the rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    /// The pattern, without `!`, the leading and the trailing slash.
+    pattern: Pattern,
+    /// Keeps the matching paths rather than ignoring them.
+    keep: bool,
+    /// Matches the whole relative path rather than the name.
+    anchored: bool,
+    /// Matches directories only.
+    dir_only: bool,
+}
+
+/// Ignore rules of a directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreRules {
+    /// The rules, in the order they apply.
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    /// Creates rules from `patterns`. Blank lines and lines starting
+    /// with `#` are skipped, so the lines of a file can be passed as
+    /// they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first invalid pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::ignore::IgnoreRules;
+    /// use std::path::Path;
+    ///
+    /// let rules = IgnoreRules::new(["*.psd", "/sources/"]).unwrap();
+    /// assert!(rules.is_ignored(Path::new("img/logo.psd"), false));
+    /// assert!(rules.is_ignored(Path::new("sources/a.md"), false));
+    /// assert!(!rules.is_ignored(Path::new("blog/sources"), true));
+    /// ```
+    pub fn new<I, S>(patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut rules = Vec::new();
+        for line in patterns {
+            let line = line.as_ref().trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (keep, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (true, pattern),
+                None => (false, line),
+            };
+            let dir_only = pattern.ends_with('/');
+            let pattern = pattern.trim_end_matches('/');
+            let anchored = pattern.contains('/');
+            let pattern = pattern.trim_start_matches('/');
+            ensure!(
+                !pattern.is_empty(),
+                "ignore pattern `{}` matches nothing",
+                line
+            );
+            rules.push(Rule {
+                pattern: Pattern::new(pattern).with_context(|| {
+                    format!("Invalid ignore pattern `{}`", line)
+                })?,
+                keep,
+                anchored,
+                dir_only,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    /// Returns the rules of `root`: the defaults, the patterns of its
+    /// [`IGNORE_FILE`] and the [configured](configure) ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or has an invalid
+    /// pattern.
+    pub fn for_root(root: &Path) -> Result<Self> {
+        let mut patterns: Vec<String> =
+            DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect();
+        let file = root.join(IGNORE_FILE);
+        if file.is_file() {
+            let text =
+                fs::read_to_string(&file).with_context(|| {
+                    format!("Failed to read {}", file.display())
+                })?;
+            patterns.extend(text.lines().map(str::to_string));
+        }
+        patterns.extend(configured());
+        Self::new(&patterns).with_context(|| {
+            format!("Invalid ignore rules for {}", root.display())
+        })
+    }
+
+    /// Returns `true` if the rules ignore `relative`, a path below their
+    /// root, or one of the directories it is in.
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        let names: Vec<_> = relative
+            .components()
+            .filter_map(|part| match part {
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            })
+            .collect();
+        let mut path = String::new();
+        for (i, name) in names.iter().enumerate() {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(name);
+            let dir = is_dir || i + 1 < names.len();
+            if self.matches(&path, name, dir) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns `true` if the last rule matching the entry at `path`,
+    /// named `name`, ignores it.
+    fn matches(&self, path: &str, name: &str, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                (is_dir || !rule.dir_only)
+                    && rule.pattern.matches_with(
+                        if rule.anchored { path } else { name },
+                        MATCH_OPTIONS,
+                    )
+            })
+            .map_or(false, |rule| !rule.keep)
+    }
+}
+
+/// Returns the files and directories below `root` that its rules
+/// ignore, without those inside an ignored directory, in path order. A
+/// missing directory has none.
+///
+/// # Errors
+///
+/// Returns an error if a directory cannot be read or the rules are
+/// invalid.
+pub fn ignored_paths(root: &Path) -> Result<Vec<PathBuf>> {
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+    let rules = IgnoreRules::for_root(root)?;
+    let mut ignored = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir).with_context(|| {
+            format!("Failed to read {}", dir.display())
+        })? {
+            let path = entry?.path();
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            if rules.is_ignored(relative, path.is_dir()) {
+                ignored.push(path);
+            } else if path.is_dir() {
+                dirs.push(path);
+            }
+        }
+    }
+    ignored.sort();
+    Ok(ignored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_gitignore_patterns() -> Result<()> {
+        let rules = IgnoreRules::new([
+            "# comments and blank lines are skipped",
+            "",
+            "*.psd",
+            "!keep.psd",
+            "/notes.md",
+            "build/",
+            "docs/**/*.tmp",
+        ])?;
+        let ignored = |path: &str, is_dir| {
+            rules.is_ignored(Path::new(path), is_dir)
+        };
+        assert!(ignored("a/b/logo.psd", false));
+        assert!(!ignored("a/keep.psd", false));
+        assert!(ignored("notes.md", false));
+        assert!(!ignored("blog/notes.md", false));
+        assert!(ignored("blog/build", true));
+        assert!(ignored("blog/build/index.html", false));
+        assert!(!ignored("blog/build", false));
+        assert!(ignored("docs/a/b/x.tmp", false));
+        assert!(!ignored("x.tmp", false));
+
+        assert!(IgnoreRules::new(["/"]).is_err());
+        assert!(IgnoreRules::new(["[a"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignored_paths_of_a_root() -> Result<()> {
+        let temp = tempdir()?;
+        let root = temp.path();
+        fs::create_dir_all(root.join("src/psd"))?;
+        fs::write(root.join(IGNORE_FILE), "# sources\nsrc/\n")?;
+        fs::write(root.join("index.md"), "# Home")?;
+        fs::write(root.join(".index.md.swp"), "")?;
+        fs::write(root.join(".DS_Store"), "")?;
+        fs::write(root.join("src/psd/logo.psd"), "")?;
+
+        assert_eq!(
+            ignored_paths(root)?,
+            [".DS_Store", ".index.md.swp", IGNORE_FILE, "src"]
+                .iter()
+                .map(|name| root.join(name))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,458 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Image CDN Rewriting
+//!
+//! For sites whose images are served by an image service, `cdn` mode
+//! rewrites local `<img>` sources in the built pages to a CDN URL pattern
+//! instead of leaving them to be served from the site. Options live in
+//! the `[images]` table of the configuration file:
+//!
+//! ```toml
+//! [images]
+//! mode = "cdn"
+//! cdn_url = "https://images.example.com/{path}?w={width}&fm={format}"
+//! widths = [480, 960, 1440]
+//! format = "webp"
+//! ```
+//!
+//! | Placeholder | Value |
+//! |-------------|-------|
+//! | `{path}`    | The image path relative to the site root, e.g. `blog/cat.jpg` |
+//! | `{width}`   | A width from `widths` |
+//! | `{format}`  | The `format` option |
+//!
+//! With `{width}` in the pattern, `src` uses the largest width and a
+//! `srcset` with every width is added unless the tag already has one.
+//! Remote, `data:` and SVG images are left alone.
+
+use anyhow::{ensure, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Component, Path},
+};
+
+use crate::collect_files_recursive;
+
+/// Image extensions rewritten in `cdn` mode.
+pub const IMAGE_EXTENSIONS: &[&str] =
+    &["jpg", "jpeg", "png", "gif", "webp", "avif"];
+
+/// Matches an `<img>` tag.
+static IMG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)<img\b[^>]*>")
+        .unwrap_or_else(|e| panic!("Invalid img regex: {}", e))
+});
+
+/// Matches the `src` attribute of a tag.
+static SRC_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\ssrc\s*=\s*(?:"([^"]*)"|'([^']*)')"#)
+        .unwrap_or_else(|e| panic!("Invalid src regex: {}", e))
+});
+
+/// Matches a `srcset` attribute.
+static SRCSET_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\ssrcset\s*=")
+        .unwrap_or_else(|e| panic!("Invalid srcset regex: {}", e))
+});
+
+/// How local images are served.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageMode {
+    /// Images are served from the site as they are.
+    Local,
+    /// Image sources are rewritten to the CDN URL pattern.
+    Cdn,
+}
+
+impl Default for ImageMode {
+    fn default() -> Self {
+        Self::Local
+    }
+}
+
+/// Options of the `[images]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageOptions {
+    /// How local images are served.
+    pub mode: ImageMode,
+    /// CDN URL pattern with `{path}`, `{width}` and `{format}`
+    /// placeholders.
+    pub cdn_url: String,
+    /// Widths requested from the CDN, for `{width}`.
+    pub widths: Vec<u32>,
+    /// Format requested from the CDN, for `{format}`.
+    pub format: String,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            mode: ImageMode::Local,
+            cdn_url: String::new(),
+            widths: Vec::new(),
+            format: "auto".to_string(),
+        }
+    }
+}
+
+impl ImageOptions {
+    /// Checks that `cdn` mode has a usable URL pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is not an `http(s)` URL with a
+    /// `{path}` placeholder, or uses `{width}` without any widths.
+    pub fn validate(&self) -> Result<()> {
+        if self.mode == ImageMode::Local {
+            return Ok(());
+        }
+        ensure!(
+            self.cdn_url.starts_with("https://")
+                || self.cdn_url.starts_with("http://"),
+            "cdn_url must be an http(s) URL, got `{}`",
+            self.cdn_url
+        );
+        ensure!(
+            self.cdn_url.contains("{path}"),
+            "cdn_url must contain a {{path}} placeholder"
+        );
+        ensure!(
+            !self.cdn_url.contains("{width}")
+                || (!self.widths.is_empty()
+                    && self.widths.iter().all(|&width| width > 0)),
+            "cdn_url uses {{width}}, so widths must list positive widths"
+        );
+        Ok(())
+    }
+
+    /// Returns the CDN URL of the image at site-relative `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::images::{ImageMode, ImageOptions};
+    ///
+    /// let options = ImageOptions {
+    ///     mode: ImageMode::Cdn,
+    ///     cdn_url: "https://cdn.example.com/{path}?w={width}&fm={format}".into(),
+    ///     ..ImageOptions::default()
+    /// };
+    /// assert_eq!(
+    ///     options.cdn_url("blog/cat.jpg", Some(640)),
+    ///     "https://cdn.example.com/blog/cat.jpg?w=640&fm=auto"
+    /// );
+    /// ```
+    pub fn cdn_url(&self, path: &str, width: Option<u32>) -> String {
+        self.cdn_url
+            .replace("{path}", path)
+            .replace(
+                "{width}",
+                &width.map(|w| w.to_string()).unwrap_or_default(),
+            )
+            .replace("{format}", &self.format)
+    }
+
+    fn uses_widths(&self) -> bool {
+        self.cdn_url.contains("{width}") && !self.widths.is_empty()
+    }
+}
+
+/// Rewrites the local image sources of the page at site-relative
+/// `page_path` to CDN URLs. Returns `None` if nothing changed.
+pub fn rewrite_images(
+    html: &str,
+    page_path: &Path,
+    options: &ImageOptions,
+) -> Option<String> {
+    let mut changed = false;
+    let rewritten =
+        IMG_RE.replace_all(html, |caps: &regex::Captures<'_>| {
+            let tag = &caps[0];
+            match rewrite_tag(tag, page_path, options) {
+                Some(updated) => {
+                    changed = true;
+                    updated
+                }
+                None => tag.to_string(),
+            }
+        });
+    if changed {
+        Some(rewritten.into_owned())
+    } else {
+        None
+    }
+}
+
+/// Applies [`rewrite_images`] to every HTML file below `site_dir` when
+/// `cdn` mode is on.
+///
+/// Returns the number of files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if the options are invalid or a file cannot be read
+/// or written.
+pub fn rewrite_site(
+    site_dir: &Path,
+    options: &ImageOptions,
+) -> Result<usize> {
+    if options.mode == ImageMode::Local || !site_dir.exists() {
+        return Ok(0);
+    }
+    options.validate()?;
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+
+    let mut rewritten = 0;
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let relative = path.strip_prefix(site_dir).unwrap_or(&path);
+        if let Some(updated) = rewrite_images(&html, relative, options)
+        {
+            fs::write(&path, updated).with_context(|| {
+                format!("Failed to write {}", path.display())
+            })?;
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+fn rewrite_tag(
+    tag: &str,
+    page_path: &Path,
+    options: &ImageOptions,
+) -> Option<String> {
+    let caps = SRC_RE.captures(tag)?;
+    let value = caps.get(1).or_else(|| caps.get(2))?;
+    let image = resolve_image(page_path, value.as_str())?;
+
+    let (src, srcset) = if options.uses_widths() {
+        let largest = options.widths.iter().copied().max();
+        let srcset = options
+            .widths
+            .iter()
+            .map(|&width| {
+                format!(
+                    "{} {}w",
+                    options.cdn_url(&image, Some(width)),
+                    width
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        (options.cdn_url(&image, largest), Some(srcset))
+    } else {
+        (options.cdn_url(&image, None), None)
+    };
+
+    let mut updated = String::with_capacity(tag.len() + 128);
+    updated.push_str(&tag[..value.start()]);
+    updated.push_str(&escape_attribute(&src));
+    updated.push_str(&tag[value.end()..]);
+    if let Some(srcset) = srcset {
+        if !SRCSET_RE.is_match(tag) {
+            let end = updated.len()
+                - if updated.ends_with("/>") { 2 } else { 1 };
+            let end = updated[..end].trim_end().len();
+            updated.insert_str(
+                end,
+                &format!(" srcset=\"{}\"", escape_attribute(&srcset)),
+            );
+        }
+    }
+    Some(updated)
+}
+
+/// Resolves `src` on the page at `page_path` to a path relative to the
+/// site root, or `None` for remote, `data:` and non-raster images and
+/// paths leaving the site.
+fn resolve_image(page_path: &Path, src: &str) -> Option<String> {
+    let path = resolve_local(page_path, src)?;
+    let extension = Path::new(&path)
+        .extension()
+        .and_then(|ext| ext.to_str())?
+        .to_ascii_lowercase();
+    if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Resolves the reference `src` on the page at site-relative
+/// `page_path` to a path relative to the site root, without any query
+/// or fragment. Returns `None` for URLs with a scheme or host and for
+/// paths leaving the site.
+pub(crate) fn resolve_local(
+    page_path: &Path,
+    src: &str,
+) -> Option<String> {
+    let src = src.split(['?', '#']).next().unwrap_or_default();
+    if src.is_empty() || src.starts_with("//") || src.contains(':') {
+        return None;
+    }
+
+    let mut parts: Vec<String> = Vec::new();
+    let (base, src) = match src.strip_prefix('/') {
+        Some(rest) => (None, rest),
+        None => (page_path.parent(), src),
+    };
+    if let Some(base) = base {
+        for component in base.components() {
+            if let Component::Normal(part) = component {
+                parts.push(part.to_string_lossy().into_owned());
+            }
+        }
+    }
+    for part in src.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                let _ = parts.pop()?;
+            }
+            part => parts.push(part.to_string()),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+pub(crate) fn escape_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn cdn(widths: Vec<u32>) -> ImageOptions {
+        ImageOptions {
+            mode: ImageMode::Cdn,
+            cdn_url:
+                "https://cdn.example.com/{path}?w={width}&fm={format}"
+                    .to_string(),
+            widths,
+            format: "webp".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_image_paths() {
+        let page = Path::new("blog/post/index.html");
+        assert_eq!(
+            resolve_image(page, "cat.JPG?v=2").as_deref(),
+            Some("blog/post/cat.JPG")
+        );
+        assert_eq!(
+            resolve_image(page, "../../images/dog.png").as_deref(),
+            Some("images/dog.png")
+        );
+        assert_eq!(
+            resolve_image(page, "/images/dog.png").as_deref(),
+            Some("images/dog.png")
+        );
+        for src in [
+            "https://example.com/a.png",
+            "//example.com/a.png",
+            "data:image/png;base64,AAAA",
+            "logo.svg",
+            "../../../outside.png",
+        ] {
+            assert_eq!(resolve_image(page, src), None, "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_rewrite_images_adds_srcset() {
+        let html = "<p><img alt=\"Cat\" src=\"cat.jpg\" /> \
+                    <img src='https://example.com/x.png'></p>";
+        let rewritten = rewrite_images(
+            html,
+            Path::new("blog/index.html"),
+            &cdn(vec![480, 960]),
+        )
+        .unwrap();
+        assert_eq!(
+            rewritten,
+            "<p><img alt=\"Cat\" \
+             src=\"https://cdn.example.com/blog/cat.jpg?w=960&amp;fm=webp\" \
+             srcset=\"https://cdn.example.com/blog/cat.jpg?w=480&amp;fm=webp 480w, \
+             https://cdn.example.com/blog/cat.jpg?w=960&amp;fm=webp 960w\" /> \
+             <img src='https://example.com/x.png'></p>"
+        );
+
+        let existing = "<img src=\"/a.png\" srcset=\"a.png 1x\">";
+        let options = ImageOptions {
+            cdn_url: "https://cdn.example.com/{path}".into(),
+            ..cdn(Vec::new())
+        };
+        assert_eq!(
+            rewrite_images(existing, Path::new("index.html"), &options)
+                .unwrap(),
+            "<img src=\"https://cdn.example.com/a.png\" srcset=\"a.png 1x\">"
+        );
+        assert!(rewrite_images(
+            "<img src=\"logo.svg\">",
+            Path::new("index.html"),
+            &options
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ImageOptions::default().validate().is_ok());
+        assert!(cdn(vec![640]).validate().is_ok());
+        assert!(cdn(Vec::new()).validate().is_err());
+        assert!(cdn(vec![0]).validate().is_err());
+        let options = ImageOptions {
+            cdn_url: "cdn.example.com/{path}".into(),
+            ..cdn(vec![640])
+        };
+        assert!(options.validate().is_err());
+        let options = ImageOptions {
+            cdn_url: "https://cdn.example.com/image".into(),
+            ..cdn(vec![640])
+        };
+        assert!(options.validate().is_err());
+    }
+
+    #[test]
+    fn test_rewrite_site_only_in_cdn_mode() -> Result<()> {
+        let site = tempdir()?;
+        fs::create_dir_all(site.path().join("about"))?;
+        fs::write(
+            site.path().join("about/index.html"),
+            "<img src=\"me.png\">",
+        )?;
+        fs::write(site.path().join("index.html"), "<p>No images</p>")?;
+
+        assert_eq!(
+            rewrite_site(site.path(), &ImageOptions::default())?,
+            0
+        );
+        assert_eq!(rewrite_site(site.path(), &cdn(vec![320]))?, 1);
+        assert_eq!(
+            fs::read_to_string(site.path().join("about/index.html"))?,
+            "<img src=\"https://cdn.example.com/about/me.png?w=320&amp;fm=webp\" \
+             srcset=\"https://cdn.example.com/about/me.png?w=320&amp;fm=webp 320w\">"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Environment Variables in the Configuration
+//!
+//! Replaces `${NAME}` in the string values of the configuration file
+//! with the value of the environment variable `NAME` when it is loaded,
+//! so one file serves every environment the site is built in:
+//!
+//! ```toml
+//! base_url = "${SITE_URL:-http://127.0.0.1:8000}"
+//!
+//! [preview]
+//! base_url = "https://${DEPLOY_PREVIEW_HOST}"
+//! ```
+//!
+//! | Syntax | Value |
+//! |--------|-------|
+//! | `${NAME}` | The variable; loading fails if it is not set |
+//! | `${NAME:-default}` | The variable, or `default` if it is not set or empty |
+//! | `$${NAME}` | `${NAME}` as written |
+//!
+//! Only the variables a value names are read, and keys, comments and
+//! values that are not strings are left alone. A value read this way is
+//! as public as the setting it lands in, so name no secrets in settings
+//! that end up in the site. Templates see environment variables only
+//! through the allowlist of [`build_info`](crate::build_info).
+
+use anyhow::{bail, Context, Result};
+use serde::de::{DeserializeOwned, Error as _};
+use std::env;
+use toml::Value;
+
+/// Opening of a variable.
+const OPEN: &str = "${";
+
+/// Separator of the default value of a variable.
+const DEFAULT_SEPARATOR: &str = ":-";
+
+/// Returns `true` if `source` holds a variable, or an escaped one.
+pub fn has_variables(source: &str) -> bool {
+    source.contains(OPEN)
+}
+
+/// Replaces the variables of `text` with the values `lookup` returns.
+///
+/// # Errors
+///
+/// Returns an error if a variable is not closed, its name is not an
+/// environment variable name, or it is not set and has no default.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::interpolate::interpolate;
+///
+/// let lookup = |name: &str| {
+///     (name == "HOST").then(|| "example.com".to_string())
+/// };
+/// assert_eq!(
+///     interpolate("https://${HOST}/${BASE:-blog}/", lookup).unwrap(),
+///     "https://example.com/blog/"
+/// );
+/// assert_eq!(interpolate("$${HOST}", lookup).unwrap(), "${HOST}");
+/// assert!(interpolate("${TOKEN}", lookup).is_err());
+/// ```
+pub fn interpolate(
+    text: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(OPEN) {
+        if rest[..start].ends_with('$') {
+            out.push_str(&rest[..start - 1]);
+            out.push_str(OPEN);
+            rest = &rest[start + OPEN.len()..];
+            continue;
+        }
+        out.push_str(&rest[..start]);
+        let after = &rest[start + OPEN.len()..];
+        let end = match after.find('}') {
+            Some(end) => end,
+            None => bail!("`${{` is not closed in `{}`", text),
+        };
+        let (name, default) =
+            match after[..end].split_once(DEFAULT_SEPARATOR) {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+        if !is_variable_name(name) {
+            bail!("`{}` is not an environment variable name", name);
+        }
+        match (lookup(name), default) {
+            (Some(value), Some(default)) if value.is_empty() => {
+                out.push_str(default)
+            }
+            (Some(value), _) => out.push_str(&value),
+            (None, Some(default)) => out.push_str(default),
+            (None, None) => {
+                bail!("environment variable `{}` is not set", name)
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Parses a TOML document, replacing the variables of its string
+/// values with those of the environment first.
+///
+/// Documents without variables are parsed as they are, so their
+/// errors keep their location.
+///
+/// # Errors
+///
+/// Returns the errors of [`toml::from_str`], and the errors of
+/// [`interpolate`] with the key of the value.
+pub fn from_toml_str<T: DeserializeOwned>(
+    source: &str,
+) -> Result<T, toml::de::Error> {
+    from_toml_str_with(source, |name| env::var(name).ok())
+}
+
+/// Parses a TOML document like [`from_toml_str`], with the variables
+/// `lookup` returns.
+///
+/// # Errors
+///
+/// Returns the errors of [`from_toml_str`].
+pub fn from_toml_str_with<T: DeserializeOwned>(
+    source: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<T, toml::de::Error> {
+    if !has_variables(source) {
+        return toml::from_str(source);
+    }
+    let table: toml::Table = toml::from_str(source)?;
+    let mut value = Value::Table(table);
+    interpolate_value(&mut value, "", &lookup)
+        .map_err(|e| toml::de::Error::custom(format!("{:#}", e)))?;
+    value.try_into()
+}
+
+/// Replaces the variables of every string in `value`, at dotted `key`.
+fn interpolate_value(
+    value: &mut Value,
+    key: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<()> {
+    match value {
+        Value::String(text) if has_variables(text) => {
+            *text = interpolate(text, lookup)
+                .with_context(|| format!("`{}`", key))?;
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter_mut().enumerate() {
+                interpolate_value(
+                    value,
+                    &format!("{}[{}]", key, i),
+                    lookup,
+                )?;
+            }
+        }
+        Value::Table(table) => {
+            for (name, value) in table.iter_mut() {
+                let key = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", key, name)
+                };
+                interpolate_value(value, &key, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Returns `true` if `name` is a portable environment variable name.
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Site {
+        base_url: String,
+        port: u16,
+        tags: Vec<String>,
+    }
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "SITE_URL" => Some("https://example.com".into()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_from_toml_str_interpolates_strings() {
+        let site: Site = from_toml_str_with(
+            r#"
+            # ${NOT_A_VALUE} in a comment
+            base_url = "${SITE_URL}"
+            port = 8000
+            tags = ["${EMPTY:-none}", "$${SITE_URL}"]
+            "#,
+            lookup,
+        )
+        .unwrap();
+        assert_eq!(
+            site,
+            Site {
+                base_url: "https://example.com".into(),
+                port: 8000,
+                tags: vec!["none".into(), "${SITE_URL}".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_errors_name_the_key() {
+        let error = from_toml_str_with::<Site>(
+            "base_url = \"x\"\nport = 1\ntags = [\"a\", \"${MISSING}\"]",
+            lookup,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("`tags[1]`"), "{}", error);
+        assert!(error.to_string().contains("MISSING"), "{}", error);
+
+        assert!(interpolate("${SITE_URL", lookup).is_err());
+        assert!(interpolate("${1X}", lookup).is_err());
+        assert_eq!(interpolate("$5 ${EMPTY}", lookup).unwrap(), "$5 ");
+    }
+}
@@ -0,0 +1,687 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Link Checking
+//!
+//! Checks the links of the generated pages once the site is built.
+//! Internal links (`href` and `src` attributes pointing into the site)
+//! must resolve to a file in the site directory. With `external` on,
+//! `http` and `https` links to other sites are checked too:
+//!
+//! ```toml
+//! [links]
+//! enabled = true
+//! external = true
+//! concurrency = 8
+//! retries = 2
+//! cache_ttl_secs = 86400
+//! exclude = ["localhost", "*.example.com"]
+//! fail_on_broken = true
+//! ```
+//!
+//! External links are checked with `HEAD` requests, falling back to
+//! `GET` for servers that refuse `HEAD`, at most `concurrency` at a time.
+//! Network errors, `429` and `5xx` responses are retried `retries` times
+//! with exponential backoff starting at `backoff_ms`. A link is broken
+//! when it cannot be reached or answers `404`, `410` or another error
+//! status other than `401`, `403` and `429`, which usually mean the
+//! server turns away automated clients.
+//!
+//! Working links are remembered in `.ssg-cache/links.json` and not
+//! checked again until they are older than `cache_ttl_secs`, so builds
+//! do not request the same URLs every time. Broken links are always
+//! checked again.
+//!
+//! Network access needs the `fetch` feature; without it, uncached
+//! external links are reported as unchecked rather than broken.
+
+use anyhow::{bail, ensure, Context, Result};
+use log::warn;
+use once_cell::sync::Lazy;
+use percent_encoding::percent_decode_str;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::Path,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use url::Url;
+
+use crate::{collect_files_recursive, fetch::Request};
+
+/// Default cache file of external link results, relative to the
+/// project root.
+pub const LINKS_CACHE_FILE: &str = ".ssg-cache/links.json";
+
+/// Base URL internal links are resolved against.
+const SITE_BASE: &str = "http://site.invalid/";
+
+/// Matches the `href` or `src` attribute of a tag.
+static LINK_ATTR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)<[a-z][a-z0-9]*\s[^>]*?\b(?:href|src)\s*=\s*["']([^"']*)["']"#)
+        .unwrap_or_else(|e| panic!("Invalid link attribute regex: {}", e))
+});
+
+/// `[links]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkOptions {
+    /// Checks the links of the generated pages.
+    pub enabled: bool,
+    /// Checks links to other sites as well.
+    pub external: bool,
+    /// Number of external links checked at the same time.
+    pub concurrency: usize,
+    /// Times a failed external check is retried.
+    pub retries: u32,
+    /// Delay before the first retry in milliseconds, doubled for each
+    /// further retry.
+    pub backoff_ms: u64,
+    /// Request timeout in seconds.
+    pub timeout_secs: u64,
+    /// Age in seconds after which a working link is checked again.
+    pub cache_ttl_secs: u64,
+    /// Hosts not checked, e.g. `localhost` or `*.example.com`.
+    pub exclude: Vec<String>,
+    /// Fails the build when a broken link is found, instead of warning.
+    pub fail_on_broken: bool,
+}
+
+impl Default for LinkOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            external: false,
+            concurrency: 8,
+            retries: 2,
+            backoff_ms: 500,
+            timeout_secs: 10,
+            cache_ttl_secs: 24 * 60 * 60,
+            exclude: Vec::new(),
+            fail_on_broken: false,
+        }
+    }
+}
+
+impl LinkOptions {
+    /// Checks the limits and excluded hosts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(self.concurrency > 0, "concurrency must be positive");
+        ensure!(self.timeout_secs > 0, "timeout_secs must be positive");
+        for host in &self.exclude {
+            let name = host.strip_prefix("*.").unwrap_or(host);
+            ensure!(
+                !name.is_empty()
+                    && name.chars().all(|c| c.is_ascii_alphanumeric()
+                        || c == '-'
+                        || c == '.'),
+                "exclude entry `{}` must be a host name such as \
+                 `example.com` or `*.example.com`",
+                host
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if links to `host` are not checked.
+    pub fn excludes(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.exclude.iter().any(|excluded| {
+            let excluded = excluded.to_ascii_lowercase();
+            match excluded.strip_prefix("*.") {
+                Some(domain) => host
+                    .strip_suffix(domain)
+                    .map_or(false, |sub| sub.ends_with('.')),
+                None => host == excluded,
+            }
+        })
+    }
+}
+
+/// Outcome of checking one external link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStatus {
+    /// The server answered with a working status.
+    Ok(u16),
+    /// The server answered with a status meaning the link is dead.
+    Broken(u16),
+    /// The server turned the request away, e.g. with `403`.
+    Refused(u16),
+    /// The server could not be reached.
+    Unreachable,
+    /// The link was not checked, e.g. without network access.
+    Unchecked,
+}
+
+impl LinkStatus {
+    /// Classifies an HTTP status.
+    pub fn from_code(status: u16) -> Self {
+        match status {
+            100..=399 => Self::Ok(status),
+            401 | 403 | 429 => Self::Refused(status),
+            _ => Self::Broken(status),
+        }
+    }
+
+    /// Returns `true` for broken and unreachable links.
+    pub fn is_broken(&self) -> bool {
+        matches!(self, Self::Broken(_) | Self::Unreachable)
+    }
+}
+
+/// A broken link found on a page.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BrokenLink {
+    /// Site-relative URL of the page with the link.
+    pub page: String,
+    /// The link as written.
+    pub link: String,
+    /// Why the link is broken.
+    pub reason: String,
+}
+
+impl fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.page, self.link, self.reason)
+    }
+}
+
+/// Results of a link check.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkReport {
+    /// Number of distinct internal links checked.
+    pub internal: usize,
+    /// Number of distinct external links checked or taken from the
+    /// cache.
+    pub external: usize,
+    /// Number of external links answered from the cache.
+    pub cached: usize,
+    /// Number of external links that could not be checked.
+    pub unchecked: usize,
+    /// Broken links, sorted by page.
+    pub broken: Vec<BrokenLink>,
+}
+
+/// Cached result of a working external link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheEntry {
+    status: u16,
+    checked_at: u64,
+}
+
+/// Checks the links of every page in `site_dir`, caching external
+/// results in `cache_file`.
+///
+/// Broken links are logged as warnings, or fail the build with
+/// `fail_on_broken`.
+///
+/// # Errors
+///
+/// Returns an error if the site cannot be read, the cache cannot be
+/// written, or broken links are found with `fail_on_broken` set.
+pub fn check_site(
+    site_dir: &Path,
+    options: &LinkOptions,
+    cache_file: &Path,
+) -> Result<LinkReport> {
+    if !options.enabled {
+        return Ok(LinkReport::default());
+    }
+    let timeout = Duration::from_secs(options.timeout_secs);
+    let report =
+        check_site_with(site_dir, options, cache_file, |url| {
+            let request = Request::get(url, timeout, 0)
+                .header("User-Agent", "ssg-link-checker".to_string())
+                .redirects(5);
+            match request.status("HEAD")? {
+                405 | 403 | 501 => request.status("GET"),
+                status => Ok(status),
+            }
+        })?;
+
+    for link in &report.broken {
+        warn!("Broken link on {}", link);
+    }
+    if options.fail_on_broken && !report.broken.is_empty() {
+        bail!(
+            "{} broken links found:\n{}",
+            report.broken.len(),
+            report
+                .broken
+                .iter()
+                .map(|link| format!("  {}", link))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    Ok(report)
+}
+
+/// Like [`check_site`] with `probe` returning the HTTP status of an
+/// external URL, without logging or failing on broken links.
+///
+/// # Errors
+///
+/// Returns an error if the site cannot be read or the cache cannot be
+/// written.
+pub fn check_site_with<F>(
+    site_dir: &Path,
+    options: &LinkOptions,
+    cache_file: &Path,
+    probe: F,
+) -> Result<LinkReport>
+where
+    F: Fn(&str) -> Result<u16> + Sync,
+{
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    files.retain(|file| {
+        file.extension().and_then(|ext| ext.to_str()) == Some("html")
+    });
+    files.sort();
+
+    let mut report = LinkReport::default();
+    let mut internal: BTreeMap<String, bool> = BTreeMap::new();
+    let mut external: BTreeMap<String, Vec<(String, String)>> =
+        BTreeMap::new();
+
+    for file in &files {
+        let html = fs::read_to_string(file).with_context(|| {
+            format!("Failed to read {}", file.display())
+        })?;
+        let page = page_url(site_dir, file);
+        let base = Url::parse(SITE_BASE)
+            .and_then(|base| base.join(&page))
+            .with_context(|| format!("Invalid page URL {}", page))?;
+
+        for link in extract_links(&html) {
+            let resolved = match base.join(&link) {
+                Ok(resolved) => resolved,
+                Err(_) => {
+                    report.broken.push(BrokenLink {
+                        page: page.clone(),
+                        link,
+                        reason: "invalid URL".into(),
+                    });
+                    continue;
+                }
+            };
+            match resolved.scheme() {
+                "http"
+                    if resolved.host_str() == Some("site.invalid") =>
+                {
+                    let path = resolved.path().to_string();
+                    let exists =
+                        *internal.entry(path.clone()).or_insert_with(
+                            || resolve_file(site_dir, &path),
+                        );
+                    if !exists {
+                        report.broken.push(BrokenLink {
+                            page: page.clone(),
+                            link,
+                            reason: "not found in the site".into(),
+                        });
+                    }
+                }
+                "http" | "https" if options.external => {
+                    let host = resolved.host_str().unwrap_or_default();
+                    if options.excludes(host) {
+                        continue;
+                    }
+                    let mut url = resolved.clone();
+                    url.set_fragment(None);
+                    external
+                        .entry(url.to_string())
+                        .or_default()
+                        .push((page.clone(), link));
+                }
+                _ => {}
+            }
+        }
+    }
+    report.internal = internal.len();
+    report.external = external.len();
+
+    if !external.is_empty() {
+        let mut cache = load_cache(cache_file);
+        let now = unix_now();
+        let ttl = options.cache_ttl_secs;
+        let (fresh, stale): (Vec<&String>, Vec<&String>) =
+            external.keys().partition(|url| {
+                cache.get(*url).map_or(false, |entry| {
+                    now.saturating_sub(entry.checked_at) < ttl
+                })
+            });
+        report.cached = fresh.len();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(options.concurrency)
+            .build()
+            .context("Failed to start the link checker")?;
+        let results: Vec<(&String, LinkStatus)> = pool.install(|| {
+            stale
+                .par_iter()
+                .map(|url| (*url, check_url(url, options, &probe)))
+                .collect()
+        });
+
+        for (url, status) in results {
+            match status {
+                LinkStatus::Ok(code) => {
+                    let _ = cache.insert(
+                        url.clone(),
+                        CacheEntry {
+                            status: code,
+                            checked_at: now,
+                        },
+                    );
+                }
+                LinkStatus::Unchecked => report.unchecked += 1,
+                LinkStatus::Broken(_) | LinkStatus::Unreachable => {
+                    let _ = cache.remove(url);
+                    let reason = match status {
+                        LinkStatus::Broken(code) => {
+                            format!("HTTP {}", code)
+                        }
+                        _ => "unreachable".to_string(),
+                    };
+                    for (page, link) in &external[url] {
+                        report.broken.push(BrokenLink {
+                            page: page.clone(),
+                            link: link.clone(),
+                            reason: reason.clone(),
+                        });
+                    }
+                }
+                LinkStatus::Refused(_) => {}
+            }
+        }
+        write_cache(cache_file, &cache)?;
+    }
+
+    report.broken.sort();
+    Ok(report)
+}
+
+/// Checks one external URL with retries.
+fn check_url<F>(
+    url: &str,
+    options: &LinkOptions,
+    probe: &F,
+) -> LinkStatus
+where
+    F: Fn(&str) -> Result<u16> + Sync,
+{
+    let mut attempt = 0;
+    loop {
+        let status = match probe(url) {
+            Ok(code) => LinkStatus::from_code(code),
+            Err(error)
+                if error.to_string().contains("`fetch` feature") =>
+            {
+                return LinkStatus::Unchecked;
+            }
+            Err(_) => LinkStatus::Unreachable,
+        };
+        let retry = matches!(
+            status,
+            LinkStatus::Unreachable | LinkStatus::Refused(429)
+        ) || matches!(status, LinkStatus::Broken(code) if code >= 500);
+        if !retry || attempt >= options.retries {
+            return status;
+        }
+        thread::sleep(Duration::from_millis(
+            options.backoff_ms.saturating_mul(1 << attempt.min(16)),
+        ));
+        attempt += 1;
+    }
+}
+
+/// Returns the `href` and `src` values of `html` that are worth
+/// checking, with HTML entities in them decoded.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::links::extract_links;
+///
+/// let html = r##"<a href="/about/">About</a> <a href="#top">Top</a>
+///                <img src="https://example.com/a.png?x=1&amp;y=2">"##;
+/// assert_eq!(
+///     extract_links(html),
+///     vec!["/about/", "https://example.com/a.png?x=1&y=2"]
+/// );
+/// ```
+pub fn extract_links(html: &str) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    LINK_ATTR_RE
+        .captures_iter(html)
+        .filter_map(|caps| {
+            let link = caps[1]
+                .trim()
+                .replace("&amp;", "&")
+                .replace("&#38;", "&");
+            let skip = link.is_empty()
+                || link.starts_with('#')
+                || ["mailto:", "tel:", "javascript:", "data:"]
+                    .iter()
+                    .any(|scheme| {
+                        link.to_ascii_lowercase().starts_with(scheme)
+                    });
+            (!skip && seen.insert(link.clone())).then(|| link)
+        })
+        .collect()
+}
+
+/// Returns `true` if the site-relative `path` names a file in
+/// `site_dir`, directly or through its `index.html`.
+fn resolve_file(site_dir: &Path, path: &str) -> bool {
+    let decoded = percent_decode_str(path).decode_utf8_lossy();
+    let relative = decoded.trim_start_matches('/');
+    if relative.split('/').any(|segment| segment == "..") {
+        return false;
+    }
+    let target = site_dir.join(relative);
+    if relative.is_empty() || decoded.ends_with('/') {
+        return target.join("index.html").is_file();
+    }
+    target.is_file()
+        || target.join("index.html").is_file()
+        || target.with_extension("html").is_file()
+}
+
+/// Returns the site-relative URL of an HTML file.
+fn page_url(site_dir: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(site_dir).unwrap_or(file);
+    let mut url = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/");
+    if url == "index.html" {
+        url.clear();
+    } else if let Some(dir) = url.strip_suffix("/index.html") {
+        url = format!("{}/", dir);
+    }
+    format!("/{}", url)
+}
+
+fn load_cache(path: &Path) -> BTreeMap<String, CacheEntry> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn write_cache(
+    path: &Path,
+    cache: &BTreeMap<String, CacheEntry>,
+) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            format!("Failed to create {}", parent.display())
+        })?;
+    }
+    fs::write(path, serde_json::to_vec_pretty(cache)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::tempdir;
+
+    fn site() -> Result<tempfile::TempDir> {
+        let site = tempdir()?;
+        fs::create_dir_all(site.path().join("blog/post"))?;
+        fs::write(
+            site.path().join("index.html"),
+            "<a href=\"/blog/post/\">Post</a><a href=\"/missing/\">x</a>\
+             <link href=\"style.css\" rel=\"stylesheet\">\
+             <a href=\"https://ok.example/\">ok</a>\
+             <a href=\"https://gone.example/page#part\">gone</a>\
+             <a href=\"https://skip.test/\">skip</a>",
+        )?;
+        fs::write(site.path().join("style.css"), "")?;
+        fs::write(
+            site.path().join("blog/post/index.html"),
+            "<a href=\"../../\">Home</a><img src=\"../../logo.png\">\
+             <a href=\"https://flaky.example/\">flaky</a>",
+        )?;
+        fs::write(site.path().join("logo.png"), "")?;
+        Ok(site)
+    }
+
+    fn options() -> LinkOptions {
+        LinkOptions {
+            enabled: true,
+            external: true,
+            backoff_ms: 0,
+            exclude: vec!["skip.test".into()],
+            ..LinkOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_internal_and_external_links() -> Result<()> {
+        let site = site()?;
+        let cache = site.path().join("cache/links.json");
+        let flaky = AtomicUsize::new(0);
+        let probe = |url: &str| match url {
+            "https://ok.example/" => Ok(200),
+            "https://gone.example/page" => Ok(404),
+            "https://flaky.example/" => {
+                match flaky.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(anyhow!("connection reset")),
+                    1 => Ok(503),
+                    _ => Ok(200),
+                }
+            }
+            other => panic!("unexpected request to {}", other),
+        };
+
+        let report =
+            check_site_with(site.path(), &options(), &cache, probe)?;
+        let broken: Vec<_> = report
+            .broken
+            .iter()
+            .map(|link| (link.page.as_str(), link.link.as_str()))
+            .collect();
+        assert_eq!(
+            broken,
+            vec![
+                ("/", "/missing/"),
+                ("/", "https://gone.example/page#part"),
+            ]
+        );
+        assert_eq!(report.broken[1].reason, "HTTP 404");
+        assert_eq!((report.internal, report.external), (5, 3));
+        assert_eq!(flaky.load(Ordering::SeqCst), 3);
+
+        // Working links come from the cache, broken ones are rechecked.
+        let requests = AtomicUsize::new(0);
+        let report =
+            check_site_with(site.path(), &options(), &cache, |_| {
+                let _ = requests.fetch_add(1, Ordering::SeqCst);
+                Ok(404)
+            })?;
+        assert_eq!(report.cached, 2);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_refused_and_unchecked_links_are_not_broken() -> Result<()> {
+        let site = site()?;
+        let cache = site.path().join("links.json");
+        let report =
+            check_site_with(site.path(), &options(), &cache, |url| {
+                if url.contains("gone") {
+                    Ok(403)
+                } else {
+                    Err(anyhow!("build with the `fetch` feature"))
+                }
+            })?;
+        assert_eq!(report.broken.len(), 1);
+        assert_eq!(report.unchecked, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_disabled_and_failing_builds() -> Result<()> {
+        let site = site()?;
+        let cache = site.path().join("links.json");
+        assert_eq!(
+            check_site(site.path(), &LinkOptions::default(), &cache)?,
+            LinkReport::default()
+        );
+
+        let options = LinkOptions {
+            enabled: true,
+            fail_on_broken: true,
+            ..LinkOptions::default()
+        };
+        let error =
+            check_site(site.path(), &options, &cache).unwrap_err();
+        assert!(error.to_string().contains("/: /missing/"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_and_exclude() {
+        let options = options();
+        assert!(options.validate().is_ok());
+        assert!(options.excludes("SKIP.test"));
+        assert!(!options.excludes("ok.example"));
+        assert!(LinkOptions {
+            concurrency: 0,
+            ..LinkOptions::default()
+        }
+        .validate()
+        .is_err());
+        assert!(LinkOptions {
+            exclude: vec!["http://x".into()],
+            ..LinkOptions::default()
+        }
+        .validate()
+        .is_err());
+    }
+}
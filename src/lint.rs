@@ -0,0 +1,673 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Content Linting
+//!
+//! Checks the Markdown sources before the site is compiled. Built-in
+//! rules are configured in the `[lint]` table of the configuration file:
+//!
+//! ```toml
+//! [lint]
+//! enabled = true
+//! max_heading_depth = 3
+//! max_image_bytes = 500000
+//! forbidden_words = ["simply", "obviously"]
+//! deny_warnings = false
+//!
+//! [lint.required_frontmatter]
+//! "*" = ["title"]
+//! blog = ["date", "description"]
+//! ```
+//!
+//! `required_frontmatter` is keyed by section, the first directory of a
+//! page's path, with `*` applying to every page. Images are the local
+//! `![alt](path)` images of a page, resolved against its directory or,
+//! for paths starting with `/`, the content directory.
+//!
+//! Plugins add their own checks by implementing [`LintRule`] and
+//! registering it with [`Linter::with_rule`] or
+//! [`Compiler::with_lint_rule`](crate::compiler::Compiler::with_lint_rule).
+//! Problems are reported as [`Diagnostic`]s pointing at the offending
+//! line. Warnings are logged and errors fail the build, as do warnings
+//! with `deny_warnings` on.
+
+use anyhow::{ensure, Context, Result};
+use log::warn;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    content::{ContentSet, Page},
+    diagnostics::{Diagnostic, Severity},
+};
+
+/// Matches a Markdown image, capturing its path.
+static IMAGE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"!\[[^\]]*\]\(\s*<?([^)\s>]+)")
+        .unwrap_or_else(|e| panic!("Invalid image regex: {}", e))
+});
+
+/// `[lint]` options.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct LintOptions {
+    /// Runs the built-in rules.
+    pub enabled: bool,
+    /// Deepest heading level allowed, from 1 to 6.
+    pub max_heading_depth: Option<usize>,
+    /// Frontmatter keys every page of a section must set, by section;
+    /// `*` applies to every page.
+    pub required_frontmatter: BTreeMap<String, Vec<String>>,
+    /// Largest size of a local image in bytes.
+    pub max_image_bytes: Option<u64>,
+    /// Words that may not appear in page bodies, ignoring case.
+    pub forbidden_words: Vec<String>,
+    /// Fails the build on warnings too.
+    pub deny_warnings: bool,
+}
+
+impl LintOptions {
+    /// Checks the limits and words.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(depth) = self.max_heading_depth {
+            ensure!(
+                (1..=6).contains(&depth),
+                "max_heading_depth must be between 1 and 6"
+            );
+        }
+        ensure!(
+            self.max_image_bytes != Some(0),
+            "max_image_bytes must be positive"
+        );
+        for word in &self.forbidden_words {
+            ensure!(
+                !word.trim().is_empty(),
+                "forbidden_words must not contain empty words"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A check run on every page.
+///
+/// Rules are run for each page with its raw source, frontmatter
+/// included, so diagnostics can point at a line of it.
+pub trait LintRule: Debug + Send + Sync {
+    /// A short, unique name shown with each diagnostic.
+    fn name(&self) -> &str;
+
+    /// Returns the problems found in `page`, whose file contains
+    /// `source`.
+    fn check(&self, page: &Page, source: &str) -> Vec<Diagnostic>;
+}
+
+/// Flags headings deeper than a maximum level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadingDepthRule {
+    /// Deepest heading level allowed.
+    pub max_depth: usize,
+}
+
+impl LintRule for HeadingDepthRule {
+    fn name(&self) -> &str {
+        "heading-depth"
+    }
+
+    fn check(&self, page: &Page, source: &str) -> Vec<Diagnostic> {
+        body_lines(source)
+            .into_iter()
+            .filter_map(|(line, text)| {
+                let depth =
+                    text.chars().take_while(|c| *c == '#').count();
+                let heading = depth > self.max_depth
+                    && depth <= 6
+                    && text[depth..].starts_with([' ', '\t']);
+                if !heading {
+                    return None;
+                }
+                Some(
+                    Diagnostic::warning(format!(
+                        "heading level {} is deeper than the maximum of {}",
+                        depth, self.max_depth
+                    ))
+                    .with_location(&page.source, source, line, 1, depth)
+                    .with_help(format!(
+                        "use at most {} `#` characters",
+                        self.max_depth
+                    )),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Flags pages missing frontmatter their section requires.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequiredFrontmatterRule {
+    /// Required keys by section; `*` applies to every page.
+    pub sections: BTreeMap<String, Vec<String>>,
+}
+
+impl LintRule for RequiredFrontmatterRule {
+    fn name(&self) -> &str {
+        "required-frontmatter"
+    }
+
+    fn check(&self, page: &Page, source: &str) -> Vec<Diagnostic> {
+        let section = page.section();
+        let mut diagnostics = Vec::new();
+        for (name, keys) in &self.sections {
+            if name != "*" && Some(name) != section.as_ref() {
+                continue;
+            }
+            for key in keys {
+                if page.frontmatter.contains_key(key) {
+                    continue;
+                }
+                let message = if name == "*" {
+                    format!("missing frontmatter `{}` required on every page", key)
+                } else {
+                    format!(
+                        "missing frontmatter `{}` required in section `{}`",
+                        key, name
+                    )
+                };
+                let diagnostic = Diagnostic::warning(message);
+                diagnostics.push(if source.starts_with("---") {
+                    diagnostic.with_location(
+                        &page.source,
+                        source,
+                        1,
+                        1,
+                        3,
+                    )
+                } else {
+                    Diagnostic {
+                        file: Some(page.source.clone()),
+                        ..diagnostic
+                    }
+                });
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Flags local images larger than a limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageSizeRule {
+    /// Directory paths starting with `/` are resolved against.
+    pub root: PathBuf,
+    /// Largest size allowed in bytes.
+    pub max_bytes: u64,
+}
+
+impl LintRule for ImageSizeRule {
+    fn name(&self) -> &str {
+        "image-size"
+    }
+
+    fn check(&self, page: &Page, source: &str) -> Vec<Diagnostic> {
+        let dir = page.source.parent().unwrap_or_else(|| Path::new(""));
+        let mut diagnostics = Vec::new();
+        for (line, text) in body_lines(source) {
+            for caps in IMAGE_RE.captures_iter(text) {
+                let image = match caps.get(1) {
+                    Some(image) => image,
+                    None => continue,
+                };
+                let target = image.as_str();
+                if target.contains("://") || target.starts_with("data:")
+                {
+                    continue;
+                }
+                let target =
+                    target.split(['?', '#']).next().unwrap_or(target);
+                let path = match target.strip_prefix('/') {
+                    Some(rooted) => self.root.join(rooted),
+                    None => dir.join(target),
+                };
+                let size = match fs::metadata(&path) {
+                    Ok(metadata) => metadata.len(),
+                    Err(_) => continue,
+                };
+                if size > self.max_bytes {
+                    diagnostics.push(
+                        Diagnostic::warning(format!(
+                            "image `{}` is {} bytes, over the limit of {} bytes",
+                            target, size, self.max_bytes
+                        ))
+                        .with_location(
+                            &page.source,
+                            source,
+                            line,
+                            text[..image.start()].chars().count() + 1,
+                            image.as_str().chars().count(),
+                        ),
+                    );
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Flags forbidden words in page bodies.
+#[derive(Debug, Clone)]
+pub struct ForbiddenWordsRule {
+    pattern: Regex,
+}
+
+impl ForbiddenWordsRule {
+    /// Creates the rule for `words`, matched as whole words ignoring
+    /// case.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the words cannot be compiled into a pattern.
+    pub fn new(words: &[String]) -> Result<Self> {
+        let alternatives: Vec<String> = words
+            .iter()
+            .map(|word| regex::escape(word.trim()))
+            .collect();
+        let pattern = Regex::new(&format!(
+            r"(?i)\b(?:{})\b",
+            alternatives.join("|")
+        ))
+        .context("Invalid forbidden words")?;
+        Ok(Self { pattern })
+    }
+}
+
+impl LintRule for ForbiddenWordsRule {
+    fn name(&self) -> &str {
+        "forbidden-words"
+    }
+
+    fn check(&self, page: &Page, source: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (line, text) in body_lines(source) {
+            for found in self.pattern.find_iter(text) {
+                diagnostics.push(
+                    Diagnostic::warning(format!(
+                        "forbidden word `{}`",
+                        found.as_str()
+                    ))
+                    .with_location(
+                        &page.source,
+                        source,
+                        line,
+                        text[..found.start()].chars().count() + 1,
+                        found.as_str().chars().count(),
+                    )
+                    .with_help("rephrase without it"),
+                );
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Runs the configured and registered lint rules over a content set.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::{
+///     content::{ContentSet, Page},
+///     lint::{HeadingDepthRule, Linter},
+/// };
+/// use std::path::PathBuf;
+///
+/// let page = Page::from_source(
+///     PathBuf::from("guide.md"),
+///     PathBuf::from("guide.md"),
+///     "# Guide\n\n#### Too deep\n",
+/// )
+/// .unwrap();
+/// let linter = Linter::default().with_rule(HeadingDepthRule { max_depth: 3 });
+///
+/// let diagnostics = linter.check_page(&page, "# Guide\n\n#### Too deep\n");
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].line, 3);
+/// ```
+#[derive(Debug, Default)]
+pub struct Linter {
+    options: LintOptions,
+    rules: Vec<Box<dyn LintRule>>,
+}
+
+impl Linter {
+    /// Creates a linter running the built-in rules of `options`.
+    pub fn new(options: LintOptions) -> Self {
+        Self {
+            options,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Replaces the options of the built-in rules, keeping registered
+    /// rules.
+    pub fn set_options(&mut self, options: LintOptions) {
+        self.options = options;
+    }
+
+    /// Registers a rule, returning the linter for chaining.
+    pub fn with_rule<R: LintRule + 'static>(mut self, rule: R) -> Self {
+        self.register_rule(Box::new(rule));
+        self
+    }
+
+    /// Registers a boxed rule.
+    pub fn register_rule(&mut self, rule: Box<dyn LintRule>) {
+        self.rules.push(rule);
+    }
+
+    /// Returns `true` if the built-in rules are enabled or a rule is
+    /// registered.
+    pub fn is_enabled(&self) -> bool {
+        self.options.enabled || !self.rules.is_empty()
+    }
+
+    /// Runs the registered rules on one page, without the built-in
+    /// ones.
+    pub fn check_page(
+        &self,
+        page: &Page,
+        source: &str,
+    ) -> Vec<Diagnostic> {
+        let rules: Vec<&dyn LintRule> =
+            self.rules.iter().map(|rule| rule.as_ref()).collect();
+        run_rules(&rules, page, source)
+    }
+
+    /// Runs every rule on every page of `content`, returning the
+    /// diagnostics in page order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the forbidden words are invalid or a page
+    /// cannot be read.
+    pub fn check(
+        &self,
+        content: &ContentSet,
+    ) -> Result<Vec<Diagnostic>> {
+        let builtin = self.builtin_rules(&content.root)?;
+        let rules: Vec<&dyn LintRule> = builtin
+            .iter()
+            .chain(&self.rules)
+            .map(|rule| rule.as_ref())
+            .collect();
+        let mut diagnostics = Vec::new();
+        for page in content.iter() {
+            let source = fs::read_to_string(&page.source)
+                .with_context(|| {
+                    format!("Failed to read {}", page.source.display())
+                })?;
+            diagnostics.extend(run_rules(&rules, page, &source));
+        }
+        Ok(diagnostics)
+    }
+
+    /// Checks `content`, logging warnings and failing on errors, or on
+    /// warnings with `deny_warnings` on. Returns the number of warnings.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failing [`Diagnostic`], with the number of
+    /// problems as context, or an error from [`check`](Self::check).
+    pub fn run(&self, content: &ContentSet) -> Result<usize> {
+        let mut diagnostics = self.check(content)?;
+        let fails = |diagnostic: &Diagnostic| {
+            diagnostic.severity == Severity::Error
+                || self.options.deny_warnings
+        };
+        let failures = diagnostics.iter().filter(|d| fails(d)).count();
+        match diagnostics.iter().position(fails) {
+            Some(first) => {
+                let first = diagnostics.remove(first);
+                for diagnostic in &diagnostics {
+                    warn!("{}", diagnostic);
+                }
+                Err(anyhow::Error::new(Diagnostic {
+                    severity: Severity::Error,
+                    ..first
+                })
+                .context(format!(
+                    "Content lint found {} problem(s)",
+                    failures
+                )))
+            }
+            None => {
+                for diagnostic in &diagnostics {
+                    warn!("{}", diagnostic);
+                }
+                Ok(diagnostics.len())
+            }
+        }
+    }
+
+    fn builtin_rules(
+        &self,
+        root: &Path,
+    ) -> Result<Vec<Box<dyn LintRule>>> {
+        let options = &self.options;
+        let mut rules: Vec<Box<dyn LintRule>> = Vec::new();
+        if !options.enabled {
+            return Ok(rules);
+        }
+        if let Some(max_depth) = options.max_heading_depth {
+            rules.push(Box::new(HeadingDepthRule { max_depth }));
+        }
+        if !options.required_frontmatter.is_empty() {
+            rules.push(Box::new(RequiredFrontmatterRule {
+                sections: options.required_frontmatter.clone(),
+            }));
+        }
+        if let Some(max_bytes) = options.max_image_bytes {
+            rules.push(Box::new(ImageSizeRule {
+                root: root.to_path_buf(),
+                max_bytes,
+            }));
+        }
+        if !options.forbidden_words.is_empty() {
+            rules.push(Box::new(ForbiddenWordsRule::new(
+                &options.forbidden_words,
+            )?));
+        }
+        Ok(rules)
+    }
+}
+
+/// Runs `rules` on a page, naming the rule in each message.
+fn run_rules(
+    rules: &[&dyn LintRule],
+    page: &Page,
+    source: &str,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for rule in rules {
+        for mut diagnostic in rule.check(page, source) {
+            diagnostic.message =
+                format!("{} [{}]", diagnostic.message, rule.name());
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics.sort_by_key(|d| (d.line, d.column));
+    diagnostics
+}
+
+/// Returns the one-based numbers and text of the body lines of a
+/// Markdown source, skipping its frontmatter and fenced code blocks.
+fn body_lines(source: &str) -> Vec<(usize, &str)> {
+    let mut lines = source.lines().enumerate().peekable();
+    if matches!(lines.peek(), Some((_, first)) if first.trim_end() == "---")
+    {
+        let _ = lines.next();
+        for (_, line) in lines.by_ref() {
+            if line.trim_end() == "---" {
+                break;
+            }
+        }
+    }
+
+    let mut body = Vec::new();
+    let mut fence: Option<&str> = None;
+    for (index, line) in lines {
+        let trimmed = line.trim_start();
+        match fence {
+            Some(marker) => {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+            }
+            None if trimmed.starts_with("```") => fence = Some("```"),
+            None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+            None => body.push((index + 1, line)),
+        }
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// A custom rule flagging pages without a summary paragraph.
+    #[derive(Debug)]
+    struct TodoRule;
+
+    impl LintRule for TodoRule {
+        fn name(&self) -> &str {
+            "todo"
+        }
+
+        fn check(&self, page: &Page, source: &str) -> Vec<Diagnostic> {
+            body_lines(source)
+                .into_iter()
+                .filter(|(_, text)| text.contains("TODO"))
+                .map(|(line, _)| {
+                    Diagnostic::error("unfinished page").with_location(
+                        &page.source,
+                        source,
+                        line,
+                        1,
+                        4,
+                    )
+                })
+                .collect()
+        }
+    }
+
+    fn write_site(dir: &Path) -> Result<ContentSet> {
+        fs::create_dir_all(dir.join("blog"))?;
+        fs::write(
+            dir.join("blog/post.md"),
+            "---\ntitle: Post\n---\n# Post\n\nIt is simply done.\n\n```md\n#### Not a heading, simply code\n```\n\n#### Deep\n\n![Big](/big.png) ![Small](small.png)\n",
+        )?;
+        fs::write(dir.join("big.png"), vec![0_u8; 64])?;
+        fs::write(dir.join("blog/small.png"), vec![0_u8; 8])?;
+        fs::write(dir.join("index.md"), "# Home\n\nTODO\n")?;
+        ContentSet::load(dir)
+    }
+
+    #[test]
+    fn test_builtin_rules() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let content = write_site(temp_dir.path())?;
+        let mut required = BTreeMap::new();
+        let _ = required
+            .insert("blog".to_string(), vec!["date".to_string()]);
+        let _ =
+            required.insert("*".to_string(), vec!["title".to_string()]);
+        let linter = Linter::new(LintOptions {
+            enabled: true,
+            max_heading_depth: Some(3),
+            required_frontmatter: required,
+            max_image_bytes: Some(32),
+            forbidden_words: vec!["Simply".to_string()],
+            deny_warnings: false,
+        });
+
+        let messages: Vec<(String, usize)> = linter
+            .check(&content)?
+            .into_iter()
+            .map(|d| {
+                (
+                    format!(
+                        "{}: {}",
+                        d.file
+                            .unwrap()
+                            .strip_prefix(temp_dir.path())
+                            .unwrap()
+                            .display(),
+                        d.message
+                    ),
+                    d.line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                ("blog/post.md: missing frontmatter `date` required in section `blog` [required-frontmatter]".to_string(), 1),
+                ("blog/post.md: forbidden word `simply` [forbidden-words]".to_string(), 6),
+                ("blog/post.md: heading level 4 is deeper than the maximum of 3 [heading-depth]".to_string(), 12),
+                ("blog/post.md: image `/big.png` is 64 bytes, over the limit of 32 bytes [image-size]".to_string(), 14),
+                ("index.md: missing frontmatter `title` required on every page [required-frontmatter]".to_string(), 0),
+            ]
+        );
+        assert_eq!(linter.run(&content)?, 5);
+
+        let mut strict = LintOptions {
+            enabled: true,
+            max_heading_depth: Some(3),
+            deny_warnings: true,
+            ..LintOptions::default()
+        };
+        let error =
+            Linter::new(strict.clone()).run(&content).unwrap_err();
+        let diagnostic =
+            error.downcast_ref::<Diagnostic>().expect("a diagnostic");
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!((diagnostic.line, diagnostic.column), (12, 1));
+
+        strict.max_heading_depth = Some(7);
+        assert!(strict.validate().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_rules() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let content = write_site(temp_dir.path())?;
+        let linter = Linter::default().with_rule(TodoRule);
+        assert!(linter.is_enabled());
+
+        let error = linter.run(&content).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Content lint found 1 problem(s)"
+        );
+        let diagnostic =
+            error.downcast_ref::<Diagnostic>().expect("a diagnostic");
+        assert_eq!(diagnostic.message, "unfinished page [todo]");
+        assert_eq!(diagnostic.line, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,926 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Open Graph Locales
+//!
+//! Adds `og:locale` and `og:locale:alternate` meta tags to generated
+//! pages. A page's locale comes from its `locale` frontmatter, then its
+//! `language`, then the site language. Alternates are the locales of the
+//! page's translations.
+//!
+//! Translations are linked by a shared `translation_key` in their
+//! frontmatter. Without one, pages at the same path below their language
+//! directory are linked, so `en/about.md` and `fr/about.md` are
+//! translations of each other.
+//!
+//! Pages with translations are also listed with
+//! `<xhtml:link rel="alternate" hreflang>` entries in `sitemap.xml`, so
+//! search engines index each language version. Every page in a group
+//! lists all of them, its own included.
+//!
+//! Open Graph expects locales like `en_GB`. Any valid BCP 47
+//! [`LanguageTag`], such as `en-gb` or `zh-Hant-TW`, is normalised to
+//! that form; anything else is an error pointing at the page.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    content::{ContentSet, Page},
+    diagnostics::Diagnostic,
+    page_meta,
+};
+
+/// Existing `og:locale` and `og:locale:alternate` meta tags.
+static OG_LOCALE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)[ \t]*<meta\s+property\s*=\s*["']og:locale(?::alternate)?["'][^>]*>[ \t]*\r?\n?"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid og:locale regex: {}", e))
+});
+
+/// A `<url>` entry of a sitemap.
+static SITEMAP_URL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<url>(.*?)</url>")
+        .unwrap_or_else(|e| panic!("Invalid sitemap url regex: {}", e))
+});
+
+/// The `<loc>` of a sitemap entry.
+pub(crate) static SITEMAP_LOC_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<loc>\s*([^<]*?)\s*</loc>")
+        .unwrap_or_else(|e| panic!("Invalid sitemap loc regex: {}", e))
+});
+
+/// Existing `xhtml:link` entries of a sitemap.
+static SITEMAP_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[ \t]*<xhtml:link\b[^>]*/>[ \t]*\r?\n?")
+        .unwrap_or_else(|e| panic!("Invalid sitemap link regex: {}", e))
+});
+
+/// The namespace of `xhtml:link` sitemap entries.
+const XHTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
+
+/// A BCP 47 language tag such as `en-GB`, `pt-BR` or `zh-Hant-TW`.
+///
+/// Tags are parsed following [RFC 5646]: a two or three letter language,
+/// then optional extended language, script, region, variant, extension
+/// and private use subtags. Subtags are case-insensitive and stored in
+/// their canonical case, so `EN-gb` becomes `en-GB`. Reserved four to
+/// eight letter languages and irregular grandfathered tags such as
+/// `i-klingon` are rejected.
+///
+/// [RFC 5646]: https://www.rfc-editor.org/rfc/rfc5646
+///
+/// # Examples
+///
+/// ```
+/// use ssg::locales::LanguageTag;
+///
+/// let tag = LanguageTag::parse("zh-hant-tw").unwrap();
+/// assert_eq!(tag.to_string(), "zh-Hant-TW");
+/// assert_eq!(tag.script(), Some("Hant"));
+/// assert_eq!(tag.fallbacks(), vec!["zh-Hant-TW", "zh-Hant", "zh"]);
+/// assert!(LanguageTag::parse("english").is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LanguageTag {
+    language: String,
+    extlangs: Vec<String>,
+    script: Option<String>,
+    region: Option<String>,
+    variants: Vec<String>,
+    extensions: Vec<String>,
+    private_use: Option<String>,
+}
+
+impl LanguageTag {
+    /// Parses a `-` separated BCP 47 tag.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first invalid subtag.
+    pub fn parse(tag: &str) -> Result<Self> {
+        let tag = tag.trim();
+        let invalid = |reason: &str| {
+            anyhow::anyhow!(
+                "Invalid language tag `{}`: {}; expected a BCP 47 tag like `en-GB` or `zh-Hant`",
+                tag,
+                reason
+            )
+        };
+        let subtags: Vec<&str> = tag.split('-').collect();
+        if subtags.iter().any(|subtag| {
+            subtag.is_empty()
+                || subtag.len() > 8
+                || !subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        }) {
+            return Err(invalid(
+                "subtags must be 1 to 8 letters or digits",
+            ));
+        }
+
+        let mut parsed = Self::default();
+        let mut rest = subtags.as_slice();
+        match rest.first() {
+            Some(language)
+                if matches!(language.len(), 2 | 3)
+                    && is_alpha(language) =>
+            {
+                parsed.language = language.to_ascii_lowercase();
+                rest = &rest[1..];
+            }
+            Some(x) if x.eq_ignore_ascii_case("x") => {}
+            _ => {
+                return Err(invalid(
+                    "the language must be 2 or 3 letters",
+                ))
+            }
+        }
+
+        if !parsed.language.is_empty() {
+            while parsed.extlangs.len() < 3
+                && matches!(rest.first(), Some(s) if s.len() == 3 && is_alpha(s))
+            {
+                parsed.extlangs.push(rest[0].to_ascii_lowercase());
+                rest = &rest[1..];
+            }
+            if let Some(script) =
+                rest.first().filter(|s| s.len() == 4 && is_alpha(s))
+            {
+                let lower = script.to_ascii_lowercase();
+                parsed.script =
+                    Some(lower[..1].to_ascii_uppercase() + &lower[1..]);
+                rest = &rest[1..];
+            }
+            if let Some(region) = rest.first().filter(|s| {
+                (s.len() == 2 && is_alpha(s))
+                    || (s.len() == 3
+                        && s.chars().all(|c| c.is_ascii_digit()))
+            }) {
+                parsed.region = Some(region.to_ascii_uppercase());
+                rest = &rest[1..];
+            }
+            while let Some(variant) = rest.first().filter(|s| {
+                (5..=8).contains(&s.len())
+                    || (s.len() == 4
+                        && s.starts_with(|c: char| c.is_ascii_digit()))
+            }) {
+                let variant = variant.to_ascii_lowercase();
+                if parsed.variants.contains(&variant) {
+                    return Err(invalid("variants must not repeat"));
+                }
+                parsed.variants.push(variant);
+                rest = &rest[1..];
+            }
+            while matches!(
+                rest.first(),
+                Some(s) if s.len() == 1 && !s.eq_ignore_ascii_case("x")
+            ) {
+                let values = rest[1..]
+                    .iter()
+                    .take_while(|s| s.len() > 1)
+                    .count();
+                if values == 0 {
+                    return Err(invalid("an extension needs a value"));
+                }
+                parsed.extensions.push(
+                    rest[..=values].join("-").to_ascii_lowercase(),
+                );
+                rest = &rest[values + 1..];
+            }
+        }
+
+        if let Some(x) = rest.first() {
+            if !x.eq_ignore_ascii_case("x") || rest.len() < 2 {
+                return Err(invalid(&format!(
+                    "unexpected subtag `{}`",
+                    x
+                )));
+            }
+            parsed.private_use =
+                Some(rest.join("-").to_ascii_lowercase());
+        }
+        Ok(parsed)
+    }
+
+    /// Parses a tag that may use `_` as a separator, as in POSIX locales
+    /// and Open Graph (`en_GB`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tag is not valid BCP 47.
+    pub fn parse_lenient(tag: &str) -> Result<Self> {
+        Self::parse(&tag.replace('_', "-"))
+    }
+
+    /// The primary language subtag, e.g. `en`; empty for private use
+    /// tags such as `x-klingon`.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// The script subtag, e.g. `Hant`.
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    /// The region subtag, e.g. `GB` or `419`.
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    /// Tags to try when looking up resources for this tag, most specific
+    /// first: `zh-Hant-TW`, `zh-Hant`, `zh`.
+    ///
+    /// Follows the lookup fallback of RFC 4647, so a page in `pt-BR` can
+    /// use `pt` translations.
+    pub fn fallbacks(&self) -> Vec<String> {
+        let canonical = self.to_string();
+        let mut subtags: Vec<&str> = canonical.split('-').collect();
+        let mut fallbacks = Vec::new();
+        while !subtags.is_empty() {
+            fallbacks.push(subtags.join("-"));
+            let _ = subtags.pop();
+            // A dangling singleton such as `-u` is not a tag.
+            while matches!(subtags.last(), Some(s) if s.len() == 1) {
+                let _ = subtags.pop();
+            }
+        }
+        fallbacks
+    }
+
+    /// Returns the first of `available` matching this tag or one of its
+    /// [`fallbacks`](Self::fallbacks), ignoring case.
+    pub fn lookup<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        self.fallbacks().iter().find_map(|candidate| {
+            available.iter().copied().find(|tag| {
+                tag.replace('_', "-").eq_ignore_ascii_case(candidate)
+            })
+        })
+    }
+}
+
+impl fmt::Display for LanguageTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let subtags = std::iter::once(self.language.as_str())
+            .filter(|language| !language.is_empty())
+            .chain(self.extlangs.iter().map(String::as_str))
+            .chain(self.script.as_deref())
+            .chain(self.region.as_deref())
+            .chain(self.variants.iter().map(String::as_str))
+            .chain(self.extensions.iter().map(String::as_str))
+            .chain(self.private_use.as_deref());
+        for (i, subtag) in subtags.enumerate() {
+            if i > 0 {
+                f.write_str("-")?;
+            }
+            f.write_str(subtag)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for LanguageTag {
+    type Err = anyhow::Error;
+
+    fn from_str(tag: &str) -> Result<Self> {
+        Self::parse(tag)
+    }
+}
+
+fn is_alpha(subtag: &str) -> bool {
+    subtag.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Converts a language tag to the Open Graph `ll_TT` form.
+///
+/// Accepts any valid BCP 47 tag, with `-` or `_` separators, and keeps
+/// only its language and region.
+///
+/// # Errors
+///
+/// Returns an error if `tag` is not a valid language tag.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::locales::og_locale;
+///
+/// assert_eq!(og_locale("en-gb").unwrap(), "en_GB");
+/// assert_eq!(og_locale("zh-Hant-TW").unwrap(), "zh_TW");
+/// assert_eq!(og_locale("fr").unwrap(), "fr");
+/// assert!(og_locale("english").is_err());
+/// ```
+pub fn og_locale(tag: &str) -> Result<String> {
+    let parsed = LanguageTag::parse_lenient(tag)?;
+    if parsed.language().is_empty() {
+        bail!("Invalid locale `{}`; expected a form like `en_GB`", tag);
+    }
+    Ok(match parsed.region() {
+        Some(region) => format!("{}_{}", parsed.language(), region),
+        None => parsed.language().to_string(),
+    })
+}
+
+/// The Open Graph locales of one page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageLocales {
+    /// The page's own locale.
+    pub locale: String,
+    /// Locales of its translations, sorted, without the page's own.
+    pub alternates: Vec<String>,
+}
+
+impl PageLocales {
+    /// Renders the meta tags, one per line.
+    pub fn to_meta_tags(&self) -> String {
+        let mut tags = format!(
+            "<meta property=\"og:locale\" content=\"{}\" />\n",
+            self.locale
+        );
+        for alternate in &self.alternates {
+            tags.push_str(&format!(
+                "<meta property=\"og:locale:alternate\" content=\"{}\" />\n",
+                alternate
+            ));
+        }
+        tags
+    }
+}
+
+/// Returns the locales of every page, keyed by its relative path.
+///
+/// # Errors
+///
+/// Returns a [`Diagnostic`] error for the first page with an invalid
+/// `locale` or `language`, or if `default_language` is invalid.
+pub fn page_locales(
+    content: &ContentSet,
+    default_language: &str,
+) -> Result<BTreeMap<PathBuf, PageLocales>> {
+    let default_locale =
+        og_locale(default_language).context("Invalid site language")?;
+
+    let mut locales = BTreeMap::new();
+    let mut groups: BTreeMap<String, BTreeSet<String>> =
+        BTreeMap::new();
+    for page in content.iter() {
+        let locale = match page_locale(page)? {
+            Some(locale) => locale,
+            None => default_locale.clone(),
+        };
+        let _ = groups
+            .entry(translation_key(page, &locale))
+            .or_default()
+            .insert(locale.clone());
+        let _ = locales.insert(page.relative.clone(), locale);
+    }
+
+    Ok(content
+        .iter()
+        .filter_map(|page| {
+            let locale = locales.get(&page.relative)?.clone();
+            let alternates = groups
+                .get(&translation_key(page, &locale))
+                .map(|group| {
+                    group
+                        .iter()
+                        .filter(|other| **other != locale)
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some((
+                page.relative.clone(),
+                PageLocales { locale, alternates },
+            ))
+        })
+        .collect())
+}
+
+/// Replaces the `og:locale` tags of every generated page in `site_dir`.
+///
+/// Returns the number of pages rewritten. Pages without an output file
+/// or a `</head>` are skipped.
+///
+/// # Errors
+///
+/// Returns an error if a locale is invalid or a page cannot be read or
+/// written.
+pub fn write_locale_tags(
+    content: &ContentSet,
+    site_dir: &Path,
+    default_language: &str,
+) -> Result<usize> {
+    let mut rewritten = 0;
+    for (relative, locales) in page_locales(content, default_language)?
+    {
+        let page = content
+            .iter()
+            .find(|page| page.relative == relative)
+            .map(Page::output_path)
+            .unwrap_or_default();
+        let path = site_dir.join(page);
+        if !path.is_file() {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        if let Some(updated) = insert_locale_tags(&html, &locales) {
+            fs::write(&path, updated).with_context(|| {
+                format!("Failed to write {}", path.display())
+            })?;
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Replaces any `og:locale` tags in `html` with `locales`, inserted
+/// before `</head>`. Returns `None` if the document has no head.
+pub fn insert_locale_tags(
+    html: &str,
+    locales: &PageLocales,
+) -> Option<String> {
+    let html = OG_LOCALE_RE.replace_all(html, "");
+    let head = html.to_ascii_lowercase().find("</head>")?;
+    let mut updated = String::with_capacity(html.len() + 128);
+    updated.push_str(&html[..head]);
+    updated.push_str(&locales.to_meta_tags());
+    updated.push_str(&html[head..]);
+    Some(updated)
+}
+
+/// One `<xhtml:link rel="alternate">` entry of a sitemap.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SitemapAlternate {
+    /// BCP 47 language of the translation, e.g. `en-GB`.
+    pub hreflang: String,
+    /// Absolute URL of the translation.
+    pub href: String,
+}
+
+/// Returns the sitemap alternates of every page with translations,
+/// keyed by its absolute URL below `base_url`.
+///
+/// Each page lists its whole translation group, itself included,
+/// sorted by language. Pages without translations are left out, and
+/// so are pages search engines may not index.
+///
+/// # Errors
+///
+/// Returns a [`Diagnostic`] error for the first page with an invalid
+/// `locale` or `language`, or if `default_language` is invalid.
+pub fn sitemap_alternates(
+    content: &ContentSet,
+    base_url: &str,
+    default_language: &str,
+) -> Result<BTreeMap<String, Vec<SitemapAlternate>>> {
+    let default_locale =
+        og_locale(default_language).context("Invalid site language")?;
+    let base = base_url.trim_end_matches('/');
+
+    let mut groups: BTreeMap<String, Vec<SitemapAlternate>> =
+        BTreeMap::new();
+    for page in
+        content.iter().filter(|page| !page_meta::is_noindex(page))
+    {
+        let locale = match page_locale(page)? {
+            Some(locale) => locale,
+            None => default_locale.clone(),
+        };
+        groups
+            .entry(translation_key(page, &locale))
+            .or_default()
+            .push(SitemapAlternate {
+                hreflang: locale.replace('_', "-"),
+                href: format!("{}{}", base, page.url_path()),
+            });
+    }
+
+    let mut alternates = BTreeMap::new();
+    for mut group in
+        groups.into_values().filter(|group| group.len() > 1)
+    {
+        group.sort();
+        for alternate in &group {
+            let _ = alternates
+                .insert(alternate.href.clone(), group.clone());
+        }
+    }
+    Ok(alternates)
+}
+
+/// Adds `alternates` to the matching `<url>` entries of a sitemap,
+/// replacing any `xhtml:link` entries it already has, and declares the
+/// `xhtml` namespace when needed.
+///
+/// Entries are matched by `<loc>`, ignoring a trailing `index.html`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::locales::{insert_sitemap_alternates, SitemapAlternate};
+/// use std::collections::BTreeMap;
+///
+/// let group = vec![
+///     SitemapAlternate {
+///         hreflang: "en-GB".to_string(),
+///         href: "https://example.com/en/".to_string(),
+///     },
+///     SitemapAlternate {
+///         hreflang: "fr-FR".to_string(),
+///         href: "https://example.com/fr/".to_string(),
+///     },
+/// ];
+/// let alternates: BTreeMap<_, _> = group
+///     .iter()
+///     .map(|alternate| (alternate.href.clone(), group.clone()))
+///     .collect();
+/// let sitemap = "<urlset><url><loc>https://example.com/en/index.html</loc></url></urlset>";
+///
+/// let updated = insert_sitemap_alternates(sitemap, &alternates);
+/// assert!(updated.contains("xmlns:xhtml=\"http://www.w3.org/1999/xhtml\""));
+/// assert!(updated.contains(
+///     "<xhtml:link rel=\"alternate\" hreflang=\"fr-FR\" href=\"https://example.com/fr/\" />"
+/// ));
+/// ```
+pub fn insert_sitemap_alternates(
+    xml: &str,
+    alternates: &BTreeMap<String, Vec<SitemapAlternate>>,
+) -> String {
+    let by_key: BTreeMap<&str, &Vec<SitemapAlternate>> = alternates
+        .iter()
+        .map(|(url, group)| (sitemap_key(url), group))
+        .collect();
+    let mut linked = false;
+    let updated = SITEMAP_URL_RE.replace_all(
+        xml,
+        |caps: &regex::Captures<'_>| {
+            let entry = SITEMAP_LINK_RE.replace_all(&caps[1], "");
+            let group =
+                SITEMAP_LOC_RE.captures(&entry).and_then(|loc| {
+                    by_key
+                        .get(sitemap_key(&loc[1].replace("&amp;", "&")))
+                });
+            let group = match group {
+                Some(group) => group,
+                None => return format!("<url>{}</url>", entry),
+            };
+            linked = true;
+            format!("<url>{}</url>", with_alternates(&entry, group))
+        },
+    );
+    if !linked || updated.contains("xmlns:xhtml") {
+        return updated.into_owned();
+    }
+    updated.replacen(
+        "<urlset",
+        &format!("<urlset xmlns:xhtml=\"{}\"", XHTML_NAMESPACE),
+        1,
+    )
+}
+
+/// Adds translation alternates to `sitemap.xml` in `site_dir`.
+///
+/// Returns the number of pages with alternates, or zero if the site has
+/// no sitemap.
+///
+/// # Errors
+///
+/// Returns an error if a locale is invalid or the sitemap cannot be
+/// read or written.
+pub fn write_sitemap_alternates(
+    content: &ContentSet,
+    site_dir: &Path,
+    base_url: &str,
+    default_language: &str,
+) -> Result<usize> {
+    let path = site_dir.join("sitemap.xml");
+    if !path.is_file() {
+        return Ok(0);
+    }
+    let alternates =
+        sitemap_alternates(content, base_url, default_language)?;
+    let xml = fs::read_to_string(&path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    let updated = insert_sitemap_alternates(&xml, &alternates);
+    if updated != xml {
+        fs::write(&path, updated).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+    }
+    Ok(alternates.len())
+}
+
+/// Appends `group` as `xhtml:link` entries to the body of a `<url>`,
+/// following the indentation of its last line.
+fn with_alternates(entry: &str, group: &[SitemapAlternate]) -> String {
+    let trimmed = entry.trim_end();
+    let (separator, indent) = if entry.contains('\n') {
+        let line = trimmed.rsplit('\n').next().unwrap_or_default();
+        ("\n", &line[..line.len() - line.trim_start().len()])
+    } else {
+        ("", "")
+    };
+    let mut updated = trimmed.to_string();
+    for alternate in group {
+        updated.push_str(&format!(
+            "{}{}<xhtml:link rel=\"alternate\" hreflang=\"{}\" href=\"{}\" />",
+            separator,
+            indent,
+            alternate.hreflang,
+            alternate.href.replace('&', "&amp;"),
+        ));
+    }
+    updated.push_str(&entry[trimmed.len()..]);
+    updated
+}
+
+/// Returns `url` without a trailing `index.html` or `/`, for matching
+/// sitemap entries to pages.
+pub(crate) fn sitemap_key(url: &str) -> &str {
+    url.trim_end_matches("index.html").trim_end_matches('/')
+}
+
+/// Reads the page locale from its frontmatter, if it sets one.
+fn page_locale(page: &Page) -> Result<Option<String>> {
+    let (key, value) =
+        match ["locale", "language"].iter().find_map(|key| {
+            page.frontmatter.get_str(key).map(|v| (*key, v))
+        }) {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+    og_locale(&value).map(Some).map_err(|e| {
+        let source = fs::read_to_string(&page.source).unwrap_or_default();
+        let diagnostic = Diagnostic::error(e.to_string())
+            .with_help("use a language code with an optional region, e.g. `en_GB` or `fr`");
+        let diagnostic = match source
+            .lines()
+            .position(|line| line.trim_start().starts_with(&format!("{}:", key)))
+        {
+            Some(index) => {
+                let column = source
+                    .lines()
+                    .nth(index)
+                    .and_then(|line| line.find(&value))
+                    .map_or(1, |column| column + 1);
+                diagnostic.with_location(
+                    &page.source,
+                    &source,
+                    index + 1,
+                    column,
+                    value.chars().count(),
+                )
+            }
+            None => Diagnostic {
+                file: Some(page.source.clone()),
+                ..diagnostic
+            },
+        };
+        anyhow::Error::new(diagnostic)
+    })
+}
+
+/// Returns the key linking a page to its translations.
+fn translation_key(page: &Page, locale: &str) -> String {
+    if let Some(key) = page.frontmatter.get_str("translation_key") {
+        return format!("key:{}", key);
+    }
+    let language = locale.split('_').next().unwrap_or_default();
+    let mut components = page.relative.components();
+    let relative = match components.next() {
+        Some(Component::Normal(first))
+            if first
+                .to_str()
+                .and_then(|dir| og_locale(dir).ok())
+                .map(|dir| {
+                    dir.split('_').next().unwrap_or_default()
+                        == language
+                })
+                == Some(true) =>
+        {
+            components.as_path().to_path_buf()
+        }
+        _ => page.relative.clone(),
+    };
+    format!("path:{}", relative.to_string_lossy().replace('\\', "/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn page(path: &str, raw: &str) -> Page {
+        Page::from_source(PathBuf::from(path), PathBuf::from(path), raw)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_og_locale_formats() {
+        assert_eq!(og_locale("en_GB").unwrap(), "en_GB");
+        assert_eq!(og_locale("EN-gb").unwrap(), "en_GB");
+        assert_eq!(og_locale("es-419").unwrap(), "es_419");
+        assert_eq!(og_locale("fil").unwrap(), "fil");
+        assert_eq!(og_locale("pt_br").unwrap(), "pt_BR");
+        for invalid in ["", "e", "en-GB-Latn", "en-GB-x", "en_1", "12"]
+        {
+            assert!(og_locale(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_language_tag_parsing() {
+        for (tag, canonical) in [
+            ("en-GB", "en-GB"),
+            ("pt-br", "pt-BR"),
+            ("zh-hant", "zh-Hant"),
+            ("ZH-HANS-CN", "zh-Hans-CN"),
+            ("es-419", "es-419"),
+            ("sl-rozaj-biske", "sl-rozaj-biske"),
+            ("de-CH-1901", "de-CH-1901"),
+            ("zh-yue-HK", "zh-yue-HK"),
+            ("en-US-u-ca-gregory", "en-US-u-ca-gregory"),
+            ("en-x-Twain", "en-x-twain"),
+            ("x-whatever", "x-whatever"),
+        ] {
+            let parsed = LanguageTag::parse(tag)
+                .unwrap_or_else(|e| panic!("{}: {}", tag, e));
+            assert_eq!(parsed.to_string(), canonical);
+        }
+        for invalid in [
+            "",
+            "e",
+            "english",
+            "en_GB",
+            "en--GB",
+            "en-GB-x",
+            "en-u",
+            "de-1901-1901",
+            "i-klingon",
+            "en-Latn-Latn",
+        ] {
+            assert!(
+                LanguageTag::parse(invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+        assert_eq!(
+            LanguageTag::parse_lenient("en_GB").unwrap().region(),
+            Some("GB")
+        );
+    }
+
+    #[test]
+    fn test_language_tag_fallbacks_and_lookup() -> Result<()> {
+        let tag: LanguageTag = "pt-BR-u-nu-latn".parse()?;
+        assert_eq!(
+            tag.fallbacks(),
+            vec!["pt-BR-u-nu-latn", "pt-BR-u-nu", "pt-BR", "pt"]
+        );
+        assert_eq!(tag.lookup(&["en", "pt", "pt-PT"]), Some("pt"));
+        assert_eq!(tag.lookup(&["pt_br", "pt"]), Some("pt_br"));
+        assert_eq!(tag.lookup(&["fr"]), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_translations_share_alternates() -> Result<()> {
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("en/about.md", "---\nlanguage: en-GB\n---\n"),
+                page("fr/about.md", "---\nlocale: fr_FR\n---\n"),
+                page(
+                    "fr/caracteristiques.md",
+                    "---\nlanguage: fr-FR\ntranslation_key: features\n---\n",
+                ),
+                page(
+                    "en/features.md",
+                    "---\nlanguage: en-GB\ntranslation_key: features\n---\n",
+                ),
+                page("contact.md", "Contact"),
+            ],
+        };
+        let locales = page_locales(&content, "en-us")?;
+
+        let about = &locales[Path::new("en/about.md")];
+        assert_eq!(about.locale, "en_GB");
+        assert_eq!(about.alternates, vec!["fr_FR"]);
+        assert_eq!(
+            locales[Path::new("fr/caracteristiques.md")].alternates,
+            vec!["en_GB"]
+        );
+        assert_eq!(
+            locales[Path::new("contact.md")],
+            PageLocales {
+                locale: "en_US".to_string(),
+                alternates: Vec::new(),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_locale_points_at_frontmatter() -> Result<()> {
+        let temp_dir = tempdir()?;
+        fs::write(
+            temp_dir.path().join("bad.md"),
+            "---\ntitle: Bad\nlocale: english\n---\n",
+        )?;
+        let content = ContentSet::load(temp_dir.path())?;
+
+        let error = page_locales(&content, "en").unwrap_err();
+        let diagnostic =
+            error.downcast_ref::<Diagnostic>().expect("a diagnostic");
+        assert_eq!(diagnostic.line, 3);
+        assert_eq!(diagnostic.column, 9);
+        assert!(diagnostic.message.contains("`english`"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_locale_tags_replaces_existing() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let html = "<html><head>\n    <meta property=\"og:locale\" content=\"en-gb\" />\n<title>A</title>\n</head><body></body></html>";
+        fs::create_dir_all(temp_dir.path().join("about"))?;
+        fs::write(temp_dir.path().join("about/index.html"), html)?;
+
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page(
+                    "about.md",
+                    "---\nlocale: en_GB\ntranslation_key: about\n---\n",
+                ),
+                page(
+                    "a-propos.md",
+                    "---\nlocale: fr_FR\ntranslation_key: about\n---\n",
+                ),
+            ],
+        };
+        assert_eq!(
+            write_locale_tags(&content, temp_dir.path(), "en")?,
+            1
+        );
+
+        let updated = fs::read_to_string(
+            temp_dir.path().join("about/index.html"),
+        )?;
+        assert!(!updated.contains("en-gb"));
+        assert_eq!(updated.matches("og:locale\"").count(), 1);
+        assert!(updated.contains(
+            "<meta property=\"og:locale\" content=\"en_GB\" />\n<meta property=\"og:locale:alternate\" content=\"fr_FR\" />\n</head>"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_sitemap_alternates() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let sitemap = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n  <url>\n    <loc>https://example.com/en/about/index.html</loc>\n    <xhtml:link rel=\"alternate\" hreflang=\"de\" href=\"https://example.com/de/\" />\n  </url>\n  <url>\n    <loc>https://example.com/fr/about/</loc>\n  </url>\n  <url>\n    <loc>https://example.com/contact/</loc>\n  </url>\n</urlset>\n";
+        fs::write(temp_dir.path().join("sitemap.xml"), sitemap)?;
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("en/about.md", "---\nlanguage: en-GB\n---\n"),
+                page("fr/about.md", "---\nlocale: fr_FR\n---\n"),
+                page("contact.md", "Contact"),
+            ],
+        };
+
+        assert_eq!(
+            write_sitemap_alternates(
+                &content,
+                temp_dir.path(),
+                "https://example.com/",
+                "en",
+            )?,
+            2
+        );
+        let updated =
+            fs::read_to_string(temp_dir.path().join("sitemap.xml"))?;
+        assert!(updated.contains(
+            "<urlset xmlns:xhtml=\"http://www.w3.org/1999/xhtml\" xmlns="
+        ));
+        assert!(!updated.contains("hreflang=\"de\""));
+        let links = "    <xhtml:link rel=\"alternate\" hreflang=\"en-GB\" href=\"https://example.com/en/about/\" />\n    <xhtml:link rel=\"alternate\" hreflang=\"fr-FR\" href=\"https://example.com/fr/about/\" />\n  </url>";
+        assert_eq!(updated.matches(links).count(), 2);
+        assert!(updated.contains(
+            "<loc>https://example.com/contact/</loc>\n  </url>"
+        ));
+        Ok(())
+    }
+}
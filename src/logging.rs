@@ -0,0 +1,568 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Logging
+//!
+//! Every log record, from the `log` macros of the build or from
+//! [`log_initialization`](crate::log_initialization) and the other
+//! functions writing to a log file, goes through [`write_record`], so
+//! it has one format and carries the correlation ID of its build,
+//! [`build_id`]. The records go to standard error unless the `[log]`
+//! table of the configuration file, or `--log-file`, names a file:
+//!
+//! ```toml
+//! [log]
+//! file = "ssg.log"
+//! format = "json"       # or "text", the default
+//! max_bytes = 10485760  # rotate from 10 MB, 0 for no limit
+//! rotate_daily = true   # and when the day changes
+//! max_files = 5         # rotated files kept, ssg.log.1 the newest
+//! ```
+//!
+//! A text record reads
+//! `[2025-01-01T12:00:00.000Z INFO  ssg::compiler 5b2f…] Compiled`;
+//! a JSON record is one object per line with the `timestamp`, `level`,
+//! `target`, `build_id` and `message` fields.
+
+use anyhow::{ensure, Result};
+use clap::ArgMatches;
+use log::{LevelFilter, Record};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Mutex, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Size from which the log file is rotated by default: 10 MB.
+pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Rotated log files kept by default.
+pub const DEFAULT_MAX_FILES: usize = 5;
+
+/// Seconds in a day, for daily rotation.
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Format of the log records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// One line of text per record.
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        Self::Text
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => anyhow::bail!(
+                "Unknown log format `{}`, use `text` or `json`",
+                name
+            ),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        })
+    }
+}
+
+/// Options of the `[log]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogOptions {
+    /// File the records are written to, instead of standard error.
+    pub file: Option<PathBuf>,
+    /// Format of the records.
+    pub format: LogFormat,
+    /// Size from which the file is rotated, `0` for no limit.
+    pub max_bytes: u64,
+    /// Rotates the file when the day, in UTC, changes.
+    pub rotate_daily: bool,
+    /// Rotated files kept next to the file.
+    pub max_files: usize,
+}
+
+impl Default for LogOptions {
+    fn default() -> Self {
+        Self {
+            file: None,
+            format: LogFormat::default(),
+            max_bytes: DEFAULT_MAX_BYTES,
+            rotate_daily: false,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+}
+
+impl LogOptions {
+    /// Checks that the log file is not a directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(file) = &self.file {
+            ensure!(
+                !file.as_os_str().is_empty() && !file.is_dir(),
+                "file must name a file"
+            );
+        }
+        Ok(())
+    }
+
+    /// Applies `--log-file` and `--log-format`, when given.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the format is not `text` or `json`.
+    pub fn override_with_cli(
+        &mut self,
+        matches: &ArgMatches,
+    ) -> Result<()> {
+        if let Ok(Some(file)) =
+            matches.try_get_one::<PathBuf>("log-file")
+        {
+            self.file = Some(file.clone());
+        }
+        if let Ok(Some(format)) =
+            matches.try_get_one::<String>("log-format")
+        {
+            self.format = format.parse()?;
+        }
+        Ok(())
+    }
+}
+
+/// Format and correlation ID of the records, see [`configure`].
+#[derive(Debug, Default)]
+struct Settings {
+    format: LogFormat,
+    build_id: String,
+}
+
+static SETTINGS: Lazy<RwLock<Settings>> = Lazy::new(|| {
+    RwLock::new(Settings {
+        format: LogFormat::default(),
+        build_id: new_build_id(),
+    })
+});
+
+/// Log file the records go to, if any, see [`configure`].
+static FILE: Lazy<Mutex<Option<RotatingFile>>> =
+    Lazy::new(Mutex::default);
+
+/// Serializes the tests that change the logger, its settings or the
+/// `SHOKUNIN_LOG_LEVEL` variable, which the whole process shares.
+#[cfg(test)]
+pub(crate) fn test_lock() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: Lazy<Mutex<()>> = Lazy::new(Mutex::default);
+    LOCK.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Returns a new correlation ID.
+fn new_build_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Sends the records written from now on to the file and in the format
+/// of `options`.
+///
+/// # Errors
+///
+/// Returns an error if the log file cannot be opened.
+pub fn configure(options: &LogOptions) -> io::Result<()> {
+    let file = match &options.file {
+        Some(path) => Some(RotatingFile::open(path, options)?),
+        None => None,
+    };
+    if let Ok(mut settings) = SETTINGS.write() {
+        settings.format = options.format;
+    }
+    if let Ok(mut current) = FILE.lock() {
+        *current = file;
+    }
+    Ok(())
+}
+
+/// Gives the records written from now on a new correlation ID, and
+/// returns it.
+pub fn start_build() -> String {
+    let id = new_build_id();
+    if let Ok(mut settings) = SETTINGS.write() {
+        settings.build_id = id.clone();
+    }
+    id
+}
+
+/// Returns the correlation ID of the current build.
+pub fn build_id() -> String {
+    SETTINGS
+        .read()
+        .map(|settings| settings.build_id.clone())
+        .unwrap_or_default()
+}
+
+/// Returns the format of the records.
+pub fn format() -> LogFormat {
+    SETTINGS
+        .read()
+        .map(|settings| settings.format)
+        .unwrap_or_default()
+}
+
+/// Installs the logger of the `log` macros at `level`, writing through
+/// [`write_record`] to the [configured](configure) file or standard
+/// error. A logger already set, as by an embedding application, is
+/// kept.
+pub fn init(level: LevelFilter) {
+    let _ = env_logger::Builder::new()
+        .filter_level(level)
+        .format(|buf, record| {
+            let timestamp = buf.timestamp_millis().to_string();
+            write_log_record(buf, &timestamp, record)
+        })
+        .target(env_logger::Target::Pipe(Box::new(Sink)))
+        .try_init();
+}
+
+/// Writes `record` of the `log` macros, logged at `timestamp`.
+fn write_log_record(
+    out: &mut dyn Write,
+    timestamp: &str,
+    record: &Record<'_>,
+) -> io::Result<()> {
+    write_record(
+        out,
+        timestamp,
+        record.level().as_str(),
+        record.target(),
+        &record.args().to_string(),
+    )
+}
+
+/// Writes a record of `message` from `target` at `level`, logged at
+/// `timestamp`, in the configured format and with the correlation ID
+/// of the build.
+///
+/// # Errors
+///
+/// Returns an error if `out` cannot be written.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::logging::{build_id, write_record};
+///
+/// let mut out = Vec::new();
+/// write_record(&mut out, "2025-01-01T12:00:00Z", "INFO", "ssg", "Hi")
+///     .unwrap();
+/// let line = String::from_utf8(out).unwrap();
+/// assert!(line.ends_with("] Hi\n"));
+/// assert!(line.contains(&build_id()));
+/// ```
+pub fn write_record(
+    out: &mut dyn Write,
+    timestamp: &str,
+    level: &str,
+    target: &str,
+    message: &str,
+) -> io::Result<()> {
+    format_record(
+        out,
+        format(),
+        &build_id(),
+        timestamp,
+        level,
+        target,
+        message,
+    )
+}
+
+/// Writes a record like [`write_record`], in `format` and with
+/// `build_id`.
+fn format_record(
+    out: &mut dyn Write,
+    format: LogFormat,
+    build_id: &str,
+    timestamp: &str,
+    level: &str,
+    target: &str,
+    message: &str,
+) -> io::Result<()> {
+    match format {
+        LogFormat::Text => writeln!(
+            out,
+            "[{} {:<5} {} {}] {}",
+            timestamp, level, target, build_id, message
+        ),
+        LogFormat::Json => {
+            let record = serde_json::json!({
+                "timestamp": timestamp,
+                "level": level,
+                "target": target,
+                "build_id": build_id,
+                "message": message,
+            });
+            writeln!(out, "{}", record)
+        }
+    }
+}
+
+/// Writer of the logger, to the configured file or standard error.
+struct Sink;
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Ok(mut file) = FILE.lock() {
+            if let Some(file) = file.as_mut() {
+                return file.write(buf);
+            }
+        }
+        io::stderr().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Ok(mut file) = FILE.lock() {
+            if let Some(file) = file.as_mut() {
+                return file.flush();
+            }
+        }
+        io::stderr().flush()
+    }
+}
+
+/// Log file rotated by size and by day: the full file becomes
+/// `<file>.1`, the previous `<file>.1` becomes `<file>.2`, and so on up
+/// to the number of files kept.
+#[derive(Debug)]
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    day: u64,
+    max_bytes: u64,
+    rotate_daily: bool,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    /// Opens the file at `path` to append to it, with the rotation of
+    /// `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file or its directory cannot be created.
+    pub fn open(path: &Path, options: &LogOptions) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file =
+            OpenOptions::new().create(true).append(true).open(path)?;
+        let metadata = file.metadata()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size: metadata.len(),
+            day: metadata.modified().map_or_else(|_| today(), day_of),
+            max_bytes: options.max_bytes,
+            rotate_daily: options.rotate_daily,
+            max_files: options.max_files,
+        })
+    }
+
+    /// Returns the path of the rotated file `n`, `1` being the newest.
+    pub fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    /// Rotates the file first if writing `len` bytes on `day` would
+    /// take it over its size or into a new day.
+    fn rotate_for(&mut self, len: u64, day: u64) -> io::Result<()> {
+        let full = self.max_bytes > 0
+            && self.size > 0
+            && self.size + len > self.max_bytes;
+        let new_day =
+            self.rotate_daily && self.size > 0 && day != self.day;
+        if full || new_day {
+            self.rotate()?;
+        }
+        self.day = day;
+        Ok(())
+    }
+
+    /// Moves the file to `<file>.1` and the older rotated files one
+    /// place up, dropping the oldest, then starts an empty file.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let oldest = self.rotated_path(self.max_files);
+            if oldest.exists() {
+                fs::remove_file(oldest)?;
+            }
+            for n in (1..self.max_files).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.rotate_for(buf.len() as u64, today())?;
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Returns the day of `time`, in days since the Unix epoch, in UTC.
+fn day_of(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / SECONDS_PER_DAY)
+}
+
+/// Returns the current day, see [`day_of`].
+fn today() -> u64 {
+    day_of(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rotation_by_size_and_day() -> Result<()> {
+        let temp = tempdir()?;
+        let path = temp.path().join("logs/ssg.log");
+        let options = LogOptions {
+            max_bytes: 10,
+            rotate_daily: true,
+            max_files: 2,
+            ..LogOptions::default()
+        };
+        let mut file = RotatingFile::open(&path, &options)?;
+        let day = file.day;
+        for line in ["first\n", "second\n", "third\n"] {
+            file.rotate_for(line.len() as u64, day)?;
+            file.file.write_all(line.as_bytes())?;
+            file.size += line.len() as u64;
+        }
+        assert_eq!(fs::read_to_string(&path)?, "third\n");
+        assert_eq!(
+            fs::read_to_string(file.rotated_path(1))?,
+            "second\n"
+        );
+        assert_eq!(
+            fs::read_to_string(file.rotated_path(2))?,
+            "first\n"
+        );
+
+        file.rotate_for(1, day + 1)?;
+        assert_eq!(fs::read_to_string(&path)?, "");
+        assert_eq!(
+            fs::read_to_string(file.rotated_path(2))?,
+            "second\n"
+        );
+        assert!(!file.rotated_path(3).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_records_carry_the_build_id() -> Result<()> {
+        let _lock = test_lock();
+        let id = start_build();
+        assert_eq!(build_id(), id);
+        let mut out = Vec::new();
+        write_record(&mut out, "now", "WARN", "ssg::links", "Broken")?;
+        let text = String::from_utf8(out)?;
+        assert!(text.contains(&id), "{}", text);
+
+        let mut out = Vec::new();
+        format_record(
+            &mut out,
+            LogFormat::Json,
+            "b1",
+            "now",
+            "INFO",
+            "ssg",
+            "Built \"docs\"",
+        )?;
+        let record: serde_json::Value = serde_json::from_slice(&out)?;
+        assert_eq!(record["build_id"], "b1");
+        assert_eq!(record["message"], "Built \"docs\"");
+        assert_eq!(out.last(), Some(&b'\n'));
+
+        let options: LogOptions =
+            toml::from_str("format = \"json\"\nmax_files = 1")?;
+        assert_eq!(options.format, LogFormat::Json);
+        assert_eq!(options.max_bytes, DEFAULT_MAX_BYTES);
+        assert_eq!("json".parse::<LogFormat>()?, LogFormat::Json);
+        assert!("xml".parse::<LogFormat>().is_err());
+        assert!(LogOptions {
+            file: Some(PathBuf::new()),
+            ..LogOptions::default()
+        }
+        .validate()
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_override_with_cli_rejects_unknown_formats() {
+        let command = clap::Command::new("ssg")
+            .arg(clap::Arg::new("log-format").long("log-format"));
+        let mut options = LogOptions::default();
+        let matches = command.clone().get_matches_from([
+            "ssg",
+            "--log-format",
+            "json",
+        ]);
+        assert!(options.override_with_cli(&matches).is_ok());
+        assert_eq!(options.format, LogFormat::Json);
+        let matches =
+            command.get_matches_from(["ssg", "--log-format", "xml"]);
+        assert!(options.override_with_cli(&matches).is_err());
+    }
+}
@@ -22,7 +22,7 @@
 //! }
 //! ```
 
-use ssg::run;
+use ssg::{diagnostics::report_error, run};
 
 /// Executes the main logic of the Shokunin Static Site Generator.
 ///
@@ -35,7 +35,10 @@
 async fn execute_main_logic() -> Result<String, String> {
     match run().await {
         Ok(_) => Ok("Site generated successfully.".to_string()),
-        Err(e) => Err(format!("Program encountered an error: {}", e)),
+        Err(e) => Err(format!(
+            "Program encountered an error:\n{}",
+            report_error(&e, true).trim_end()
+        )),
     }
 }
 
@@ -0,0 +1,1223 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Markdown Options
+//!
+//! Controls how Markdown is rendered and post-processed: syntax
+//! highlighting of fenced code blocks (the theme, line numbers,
+//! highlighted lines and language aliases), the markup of tables, and
+//! the Markdown extensions of the renderer.
+//!
+//! Options live in the `[markdown]` table of the configuration file:
+//!
+//! ```toml
+//! [markdown]
+//! syntax_theme = "InspiredGitHub"
+//! line_numbers = true
+//!
+//! [markdown.language_aliases]
+//! shell = "bash"
+//!
+//! [markdown.tables]
+//! responsive = true
+//! wrapper_class = "table-wrapper"
+//! ```
+//!
+//! Individual code blocks can highlight lines and toggle line numbers
+//! from the fence info string:
+//!
+//! ````markdown
+//! ```rust{2,4-5}:line-numbers
+//! fn main() {
+//!     let x = 1;
+//! }
+//! ```
+//! ````
+//!
+//! A `Table: ...` paragraph right before or after a table becomes its
+//! `<caption>`:
+//!
+//! ```markdown
+//! Table: Supported platforms
+//!
+//! | Platform | Tier |
+//! |:---------|-----:|
+//! | Linux    |    1 |
+//! ```
+//!
+//! Column alignments become `align-left`, `align-center` and
+//! `align-right` classes instead of inline styles, header cells get a
+//! `scope`, and with `responsive` on each table is wrapped in a `<div>`
+//! that can scroll on narrow screens.
+//!
+//! ## Extensions
+//!
+//! Footnotes, task lists, definition lists and strikethrough are
+//! rendered by [`render_markdown_with`](crate::template::render_markdown_with)
+//! with the same markup as comrak and GitHub, and can be turned off one
+//! by one; the syntax of an extension that is off stays as text:
+//!
+//! ```toml
+//! [markdown.extensions]
+//! footnotes = true
+//! task_lists = true
+//! definition_lists = true
+//! strikethrough = true
+//! ```
+//!
+//! ```markdown
+//! Shokunin is fast[^speed] and ~~slow~~ simple.
+//!
+//! - [x] Write the post
+//! - [ ] Publish it
+//!
+//! Static site
+//! : A site served as plain files.
+//!
+//! [^speed]: See the benchmarks.
+//! ```
+//!
+//! Footnotes are numbered in order of first reference and listed in a
+//! `<section class="footnotes">` at the end of the page. Their IDs come
+//! from the label, slugged like heading IDs, so `[^speed]` links to
+//! `#fn-speed` and back to `#fnref-speed`; they stay the same when other
+//! footnotes are added. Code blocks and inline code are left alone.
+//!
+//! `$...$` and `$$...$$` math is rendered when `[markdown.math]` is
+//! enabled; see [`crate::math`]. Mermaid, Graphviz and other diagram
+//! code blocks are rendered when `[markdown.diagrams]` is enabled; see
+//! [`crate::diagrams`]. `:rocket:`-style shortcodes become emoji when
+//! `[markdown.emoji]` is enabled; see [`crate::emoji`]. Headings get
+//! anchor links when `[markdown.anchors]` is enabled; see
+//! [`crate::anchors`].
+
+use anyhow::{anyhow, ensure, Context, Result};
+use once_cell::sync::Lazy;
+use pulldown_cmark::{CowStr, Event, Parser, Tag, TagEnd};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Color, Theme, ThemeSet},
+    html::{styled_line_to_highlighted_html, IncludeBackground},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+use crate::{
+    anchors::AnchorOptions, collect_files_recursive,
+    diagrams::DiagramOptions, emoji::EmojiOptions, math::MathOptions,
+    template::slugify,
+};
+
+/// Theme used when none is configured.
+pub const DEFAULT_SYNTAX_THEME: &str = "base16-ocean.dark";
+
+static SYNTAX_SET: Lazy<SyntaxSet> =
+    Lazy::new(SyntaxSet::load_defaults_newlines);
+
+static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+/// Matches a rendered fenced code block with a language.
+///
+/// The body may already contain highlighted markup from the Markdown
+/// renderer, which is stripped before highlighting again.
+pub(crate) static CODE_BLOCK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?s)<pre><code class="language-([^"]*)">(.*?)</code></pre>"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid code block regex: {}", e))
+});
+
+/// Matches a rendered table with an optional `Table:` caption paragraph
+/// before or after it.
+static TABLE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?s)(?:<p>Table:[ \t]*([^\n]*?)</p>\s*)?<table>(.*?)</table>(?:\s*<p>Table:[ \t]*([^\n]*?)</p>)?",
+    )
+    .unwrap_or_else(|e| panic!("Invalid table regex: {}", e))
+});
+
+/// Matches a table cell with its alignment, as `style` or `align`.
+static CELL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"<(th|td)(?:\s+style="text-align:\s*(left|center|right);?"|\s+align="(left|center|right)")?>"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid table cell regex: {}", e))
+});
+
+/// Matches the opening line of a fenced code block in Markdown.
+static FENCE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^ {0,3}(```+|~~~+)")
+        .unwrap_or_else(|e| panic!("Invalid fence regex: {}", e))
+});
+
+/// Matches any HTML tag.
+static TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<[^>]*>")
+        .unwrap_or_else(|e| panic!("Invalid tag regex: {}", e))
+});
+
+/// Options applied to rendered Markdown.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownOptions {
+    /// Enables syntax highlighting of fenced code blocks.
+    pub syntax_highlighting: bool,
+    /// Name of the highlighting theme, e.g. `InspiredGitHub`.
+    pub syntax_theme: String,
+    /// Shows line numbers on every code block.
+    pub line_numbers: bool,
+    /// Maps fence languages to the syntax used for highlighting, e.g.
+    /// `shell = "bash"`.
+    pub language_aliases: BTreeMap<String, String>,
+    /// Markup of rendered tables.
+    pub tables: TableOptions,
+    /// Markdown extensions of the renderer.
+    pub extensions: ExtensionOptions,
+    /// Rendering of `$...$` and `$$...$$` math.
+    pub math: MathOptions,
+    /// Rendering of Mermaid, Graphviz and other diagram code blocks.
+    pub diagrams: DiagramOptions,
+    /// Replacement of `:rocket:`-style shortcodes with emoji.
+    pub emoji: EmojiOptions,
+    /// Anchor links next to headings.
+    pub anchors: AnchorOptions,
+}
+
+/// `[markdown.extensions]` options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExtensionOptions {
+    /// Renders `[^label]` references and `[^label]: text` definitions.
+    pub footnotes: bool,
+    /// Renders `[ ]` and `[x]` list items as checkboxes.
+    pub task_lists: bool,
+    /// Renders a term followed by `: definition` lines as a `<dl>`.
+    pub definition_lists: bool,
+    /// Renders `~~text~~` as `<del>`.
+    pub strikethrough: bool,
+}
+
+impl Default for ExtensionOptions {
+    fn default() -> Self {
+        Self {
+            footnotes: true,
+            task_lists: true,
+            definition_lists: true,
+            strikethrough: true,
+        }
+    }
+}
+
+impl ExtensionOptions {
+    /// Returns `true` if any extension is rendered.
+    pub fn is_enabled(&self) -> bool {
+        self.footnotes
+            || self.task_lists
+            || self.definition_lists
+            || self.strikethrough
+    }
+}
+
+/// `[markdown.tables]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableOptions {
+    /// Turns a `Table:` paragraph next to a table into its caption.
+    pub captions: bool,
+    /// Replaces inline alignment styles of cells with `align-*`
+    /// classes.
+    pub alignment_classes: bool,
+    /// Adds `scope="col"` to column headers and `scope="row"` to row
+    /// headers.
+    pub header_scope: bool,
+    /// Wraps each table in a `<div>` with `wrapper_class`.
+    pub responsive: bool,
+    /// Class of the responsive wrapper.
+    pub wrapper_class: String,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        Self {
+            captions: true,
+            alignment_classes: true,
+            header_scope: true,
+            responsive: false,
+            wrapper_class: "table-wrapper".to_string(),
+        }
+    }
+}
+
+impl TableOptions {
+    /// Returns `true` if any table markup is changed.
+    pub fn is_enabled(&self) -> bool {
+        self.captions
+            || self.alignment_classes
+            || self.header_scope
+            || self.responsive
+    }
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            syntax_highlighting: true,
+            syntax_theme: DEFAULT_SYNTAX_THEME.to_string(),
+            line_numbers: false,
+            language_aliases: BTreeMap::new(),
+            tables: TableOptions::default(),
+            extensions: ExtensionOptions::default(),
+            math: MathOptions::default(),
+            diagrams: DiagramOptions::default(),
+            emoji: EmojiOptions::default(),
+            anchors: AnchorOptions::default(),
+        }
+    }
+}
+
+impl MarkdownOptions {
+    /// Returns the names of the available highlighting themes.
+    pub fn available_themes() -> Vec<&'static str> {
+        THEME_SET.themes.keys().map(String::as_str).collect()
+    }
+
+    /// Checks that the configured theme exists, the table wrapper
+    /// class is usable and the math, diagram, emoji and anchor options
+    /// are valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the available themes if the theme does
+    /// not exist, or an error naming an invalid wrapper class, math,
+    /// diagram, emoji or anchor option.
+    pub fn validate(&self) -> Result<()> {
+        let _ = self.theme()?;
+        let class = &self.tables.wrapper_class;
+        ensure!(
+            !self.tables.responsive
+                || (!class.is_empty()
+                    && class.chars().all(|c| {
+                        c.is_ascii_alphanumeric()
+                            || c == '-'
+                            || c == '_'
+                    })),
+            "tables.wrapper_class `{}` must be a single CSS class name",
+            class
+        );
+        self.math.validate().map_err(|e| anyhow!("math.{}", e))?;
+        self.diagrams
+            .validate()
+            .map_err(|e| anyhow!("diagrams.{}", e))?;
+        self.emoji.validate().map_err(|e| anyhow!("emoji.{}", e))?;
+        self.anchors
+            .validate()
+            .map_err(|e| anyhow!("anchors.{}", e))?;
+        Ok(())
+    }
+
+    fn theme(&self) -> Result<&'static Theme> {
+        THEME_SET.themes.get(&self.syntax_theme).with_context(|| {
+            format!(
+                "Unknown syntax theme '{}' (available: {})",
+                self.syntax_theme,
+                Self::available_themes().join(", ")
+            )
+        })
+    }
+
+    /// Resolves a fence language through the configured aliases.
+    pub fn resolve_language<'a>(&'a self, lang: &'a str) -> &'a str {
+        self.language_aliases
+            .get(lang)
+            .or_else(|| {
+                self.language_aliases.get(&lang.to_ascii_lowercase())
+            })
+            .map(String::as_str)
+            .unwrap_or(lang)
+    }
+}
+
+/// Per-block settings parsed from a fence info string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeBlockInfo {
+    /// The fence language, before alias resolution.
+    pub language: String,
+    /// One-based line numbers to highlight.
+    pub hl_lines: Vec<usize>,
+    /// Overrides the global line-number setting when present.
+    pub line_numbers: Option<bool>,
+}
+
+impl CodeBlockInfo {
+    /// Parses an info string such as `rust{2,4-5}:line-numbers`.
+    ///
+    /// Unknown flags and malformed line ranges are ignored.
+    pub fn parse(info: &str) -> Self {
+        let mut block = Self::default();
+        let (head, rest) = match info.find(['{', ':']) {
+            Some(index) => info.split_at(index),
+            None => (info, ""),
+        };
+        block.language = head.trim().to_string();
+
+        let mut flags = rest;
+        if let Some(ranges) = rest.strip_prefix('{') {
+            let end = ranges.find('}').unwrap_or(ranges.len());
+            block.hl_lines = parse_line_ranges(&ranges[..end]);
+            flags = ranges.get(end + 1..).unwrap_or("");
+        }
+
+        for flag in flags.split(':').map(str::trim) {
+            match flag {
+                "line-numbers" => block.line_numbers = Some(true),
+                "no-line-numbers" => block.line_numbers = Some(false),
+                _ => {}
+            }
+        }
+        block
+    }
+}
+
+/// Parses line ranges such as `1,3-5` into sorted line numbers.
+pub fn parse_line_ranges(ranges: &str) -> Vec<usize> {
+    let mut lines = Vec::new();
+    for part in ranges.split(',').map(str::trim) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) =
+                    (start.trim().parse::<usize>(), end.trim().parse())
+                {
+                    lines.extend(start..=end);
+                }
+            }
+            None => {
+                if let Ok(line) = part.parse() {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+    lines.sort_unstable();
+    lines.dedup();
+    lines
+}
+
+/// Highlights a single code block.
+///
+/// Each line is wrapped in `<span class="line">`, with an extra
+/// `highlighted` class for lines listed in `hl_lines`. When line numbers
+/// are enabled each line starts with a `<span class="line-number">`.
+///
+/// # Arguments
+///
+/// * `code` - The raw (unescaped) source code.
+/// * `lang` - The fence language; aliases are resolved first.
+/// * `options` - The Markdown options.
+/// * `hl_lines` - One-based line numbers to highlight.
+/// * `line_numbers` - Whether to render line numbers.
+///
+/// # Errors
+///
+/// Returns an error if the theme is unknown or highlighting fails.
+pub fn apply_syntax_highlighting(
+    code: &str,
+    lang: &str,
+    options: &MarkdownOptions,
+    hl_lines: &[usize],
+    line_numbers: bool,
+) -> Result<String> {
+    let theme = options.theme()?;
+    let lang = options.resolve_language(lang);
+    let syntax = find_syntax(lang);
+    let background = theme.settings.background.unwrap_or(Color::WHITE);
+
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut html = format!(
+        "<pre class=\"highlight\" style=\"background-color:#{:02x}{:02x}{:02x};\">\
+         <code class=\"language-{}\" data-lang=\"{}\">",
+        background.r,
+        background.g,
+        background.b,
+        escape_attribute(lang),
+        escape_attribute(lang)
+    );
+
+    for (index, line) in LinesWithEndings::from(code).enumerate() {
+        let number = index + 1;
+        let regions = highlighter
+            .highlight_line(line, &SYNTAX_SET)
+            .with_context(|| {
+                format!("Failed to highlight {} code", lang)
+            })?;
+        let rendered = styled_line_to_highlighted_html(
+            &regions,
+            IncludeBackground::IfDifferent(background),
+        )
+        .with_context(|| format!("Failed to render {} code", lang))?;
+
+        if hl_lines.contains(&number) {
+            html.push_str("<span class=\"line highlighted\">");
+        } else {
+            html.push_str("<span class=\"line\">");
+        }
+        if line_numbers {
+            html.push_str(&format!(
+                "<span class=\"line-number\">{}</span>",
+                number
+            ));
+        }
+        html.push_str(&rendered);
+        html.push_str("</span>");
+    }
+
+    html.push_str("</code></pre>");
+    Ok(html)
+}
+
+/// Re-highlights every fenced code block in rendered HTML.
+///
+/// # Errors
+///
+/// Returns an error if the theme is unknown or a block fails to
+/// highlight.
+pub fn highlight_code_blocks(
+    html: &str,
+    options: &MarkdownOptions,
+) -> Result<String> {
+    if !options.syntax_highlighting {
+        return Ok(html.to_string());
+    }
+
+    let mut output = String::with_capacity(html.len());
+    let mut last_end = 0;
+    for caps in CODE_BLOCK_RE.captures_iter(html) {
+        let whole = match caps.get(0) {
+            Some(whole) => whole,
+            None => continue,
+        };
+        output.push_str(&html[last_end..whole.start()]);
+        output.push_str(&highlight_block(&caps, options)?);
+        last_end = whole.end();
+    }
+    output.push_str(&html[last_end..]);
+    Ok(output)
+}
+
+/// Applies [`highlight_code_blocks`] to every HTML file below `site_dir`.
+///
+/// Returns the number of files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if the options are invalid or a file cannot be read
+/// or written.
+pub fn highlight_site(
+    site_dir: &Path,
+    options: &MarkdownOptions,
+) -> Result<usize> {
+    if !options.syntax_highlighting || !site_dir.exists() {
+        return Ok(0);
+    }
+    options.validate()?;
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+
+    let mut rewritten = 0;
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        if !CODE_BLOCK_RE.is_match(&html) {
+            continue;
+        }
+        let highlighted = highlight_code_blocks(&html, options)
+            .with_context(|| {
+                format!("Failed to highlight {}", path.display())
+            })?;
+        fs::write(&path, highlighted).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+/// Applies the table options to every rendered table in `html`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::markdown::{enhance_tables, TableOptions};
+///
+/// let html = "<p>Table: Tiers</p>\n<table><thead><tr><th style=\"text-align: right\">Tier</th></tr></thead><tbody>\n<tr><td style=\"text-align: right\">1</td></tr>\n</tbody></table>";
+/// let enhanced = enhance_tables(html, &TableOptions::default());
+/// assert_eq!(
+///     enhanced,
+///     "<table><caption>Tiers</caption><thead><tr><th class=\"align-right\" scope=\"col\">Tier</th></tr></thead><tbody>\n<tr><td class=\"align-right\">1</td></tr>\n</tbody></table>"
+/// );
+/// ```
+pub fn enhance_tables(html: &str, options: &TableOptions) -> String {
+    if !options.is_enabled() {
+        return html.to_string();
+    }
+    let wrapper = format!("<div class=\"{}\">", options.wrapper_class);
+    let mut output = String::with_capacity(html.len());
+    let mut last_end = 0;
+    for caps in TABLE_RE.captures_iter(html) {
+        let (whole, body) = match (caps.get(0), caps.get(2)) {
+            (Some(whole), Some(body)) => (whole, body),
+            _ => continue,
+        };
+        // Without captions, `Table:` paragraphs are left as they are.
+        let (start, end) = if options.captions {
+            (whole.start(), whole.end())
+        } else {
+            (
+                body.start() - "<table>".len(),
+                body.end() + "</table>".len(),
+            )
+        };
+        output.push_str(&html[last_end..start]);
+        last_end = end;
+
+        let mut table = String::from("<table>");
+        if let Some(caption) = caps.get(1).or_else(|| caps.get(3)) {
+            if options.captions {
+                table.push_str(&format!(
+                    "<caption>{}</caption>",
+                    caption.as_str().trim()
+                ));
+            }
+        }
+        table.push_str(&enhance_cells(body.as_str(), options));
+        table.push_str("</table>");
+
+        if options.responsive && !output.trim_end().ends_with(&wrapper)
+        {
+            output.push_str(&wrapper);
+            output.push_str(&table);
+            output.push_str("</div>");
+        } else {
+            output.push_str(&table);
+        }
+    }
+    output.push_str(&html[last_end..]);
+    output
+}
+
+/// Applies [`enhance_tables`] to every HTML file below `site_dir`.
+///
+/// Returns the number of files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written.
+pub fn enhance_tables_site(
+    site_dir: &Path,
+    options: &TableOptions,
+) -> Result<usize> {
+    if !options.is_enabled() || !site_dir.exists() {
+        return Ok(0);
+    }
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+
+    let mut rewritten = 0;
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let enhanced = enhance_tables(&html, options);
+        if enhanced == html {
+            continue;
+        }
+        fs::write(&path, enhanced).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+/// Rewrites the footnotes and task list items of a Markdown `parser`
+/// into the markup of comrak and GitHub.
+///
+/// References are numbered in order of first use. The definitions they
+/// use are listed at the end of the page, each with a link back to its
+/// first reference; definitions nobody refers to are left out, and
+/// references without a definition stay as text.
+pub(crate) fn render_extensions<'a>(
+    parser: Parser<'a>,
+) -> Vec<Event<'a>> {
+    let mut body = Vec::new();
+    let mut definitions: Vec<(CowStr<'a>, Vec<Event<'a>>)> = Vec::new();
+    let mut in_definition = false;
+    for event in parser {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                definitions.push((label, Vec::new()));
+                in_definition = true;
+            }
+            Event::End(TagEnd::FootnoteDefinition) => {
+                in_definition = false;
+            }
+            event => match definitions.last_mut() {
+                Some((_, events)) if in_definition => {
+                    events.push(event)
+                }
+                _ => body.push(event),
+            },
+        }
+    }
+    mark_task_items(&mut body);
+
+    let mut footnotes = Footnotes::default();
+    let mut events = footnotes.link(body, &definitions);
+    if footnotes.ids.is_empty() {
+        return events;
+    }
+    events.push(Event::Html(
+        "<section class=\"footnotes\" data-footnotes>\n<ol>\n".into(),
+    ));
+    // Definitions can refer to further footnotes, listed after them.
+    let mut index = 0;
+    while index < footnotes.ids.len() {
+        let (label, id) = footnotes.ids[index].clone();
+        index += 1;
+        let mut definition = definitions
+            .iter()
+            .find(|(defined, _)| defined.as_ref() == label)
+            .map_or_else(Vec::new, |(_, events)| events.clone());
+        mark_task_items(&mut definition);
+        let mut definition = footnotes.link(definition, &definitions);
+        let backref = format!(
+            "<a href=\"#fnref-{}\" class=\"footnote-backref\" data-footnote-backref aria-label=\"Back to reference {}\">↩</a>",
+            id, index
+        );
+        match definition.last() {
+            Some(Event::End(TagEnd::Paragraph)) => {
+                let end = definition.len() - 1;
+                definition.insert(
+                    end,
+                    Event::InlineHtml(format!(" {}", backref).into()),
+                );
+            }
+            _ => definition.push(Event::Html(
+                format!("<p>{}</p>\n", backref).into(),
+            )),
+        }
+        events.push(Event::Html(
+            format!("<li id=\"fn-{}\">\n", id).into(),
+        ));
+        events.extend(definition);
+        events.push(Event::Html("</li>\n".into()));
+    }
+    events.push(Event::Html("</ol>\n</section>\n".into()));
+    events
+}
+
+/// Footnotes of a page, in order of first reference.
+#[derive(Debug, Default)]
+struct Footnotes {
+    /// Label and ID of each footnote referred to.
+    ids: Vec<(String, String)>,
+    /// Number of references to each ID so far.
+    references: BTreeMap<String, usize>,
+}
+
+impl Footnotes {
+    /// Replaces the footnote references of `events` with numbered links
+    /// to their `definitions`.
+    fn link<'a>(
+        &mut self,
+        events: Vec<Event<'a>>,
+        definitions: &[(CowStr<'a>, Vec<Event<'a>>)],
+    ) -> Vec<Event<'a>> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                Event::FootnoteReference(label)
+                    if definitions
+                        .iter()
+                        .any(|(defined, _)| *defined == label) =>
+                {
+                    Event::InlineHtml(self.reference(&label).into())
+                }
+                Event::FootnoteReference(label) => {
+                    Event::Text(format!("[^{}]", label).into())
+                }
+                event => event,
+            })
+            .collect()
+    }
+
+    /// Returns the markup of a reference to the footnote `label`, which
+    /// is numbered on its first reference. IDs come from the label,
+    /// slugged like heading IDs, so they stay the same when other
+    /// footnotes are added.
+    fn reference(&mut self, label: &str) -> String {
+        let index = match self.ids.iter().position(|(l, _)| l == label)
+        {
+            Some(index) => index,
+            None => {
+                let slug = slugify(label);
+                let number = self.ids.len() + 1;
+                let id = if slug.is_empty() {
+                    number.to_string()
+                } else if self.ids.iter().any(|(_, id)| *id == slug) {
+                    format!("{}-{}", slug, number)
+                } else {
+                    slug
+                };
+                self.ids.push((label.to_string(), id));
+                number - 1
+            }
+        };
+        let id = &self.ids[index].1;
+        let count = self.references.entry(id.clone()).or_insert(0);
+        *count += 1;
+        let reference = if *count == 1 {
+            format!("fnref-{}", id)
+        } else {
+            format!("fnref-{}-{}", id, count)
+        };
+        format!(
+            "<sup class=\"footnote-ref\"><a href=\"#fn-{}\" id=\"{}\" data-footnote-ref>{}</a></sup>",
+            id,
+            reference,
+            index + 1
+        )
+    }
+}
+
+/// Gives the list items of `events` that start with a task list marker
+/// the `task-list-item` class, and the marker the checkbox markup of
+/// GitHub.
+fn mark_task_items(events: &mut [Event<'_>]) {
+    let mut item = None;
+    for index in 0..events.len() {
+        if let Event::Start(Tag::Item) = events[index] {
+            item = Some(index);
+        } else if let Event::TaskListMarker(checked) = events[index] {
+            if let Some(start) = item.take() {
+                events[start] =
+                    Event::Html("<li class=\"task-list-item\">".into());
+            }
+            events[index] = Event::InlineHtml(
+                format!(
+                    "<input type=\"checkbox\" class=\"task-list-item-checkbox\" disabled=\"\"{} /> ",
+                    if checked { " checked=\"\"" } else { "" }
+                )
+                .into(),
+            );
+        }
+    }
+}
+
+/// Rewrites the cells of a table body: alignment classes and header
+/// scopes.
+fn enhance_cells(table: &str, options: &TableOptions) -> String {
+    let head_end = table.find("</thead>").unwrap_or(0);
+    CELL_RE
+        .replace_all(table, |caps: &Captures<'_>| {
+            let tag = &caps[1];
+            let align = caps.get(2).or_else(|| caps.get(3));
+            let mut cell = format!("<{}", tag);
+            match align {
+                Some(align) if options.alignment_classes => {
+                    cell.push_str(&format!(
+                        " class=\"align-{}\"",
+                        align.as_str()
+                    ));
+                }
+                Some(_) => cell.push_str(
+                    &caps[0][tag.len() + 1..caps[0].len() - 1],
+                ),
+                None => {}
+            }
+            if tag == "th" && options.header_scope {
+                let in_head = caps
+                    .get(0)
+                    .map_or(false, |cell| cell.start() < head_end);
+                cell.push_str(if in_head {
+                    " scope=\"col\""
+                } else {
+                    " scope=\"row\""
+                });
+            }
+            cell.push('>');
+            cell
+        })
+        .into_owned()
+}
+
+fn highlight_block(
+    caps: &Captures<'_>,
+    options: &MarkdownOptions,
+) -> Result<String> {
+    let info = CodeBlockInfo::parse(&decode_entities(&caps[1]));
+    let code = extract_code(&caps[2]);
+    apply_syntax_highlighting(
+        &code,
+        &info.language,
+        options,
+        &info.hl_lines,
+        info.line_numbers.unwrap_or(options.line_numbers),
+    )
+}
+
+/// Recovers the source code from a rendered block body.
+///
+/// The Markdown renderer may have wrapped the code in its own highlighted
+/// `<pre>`, which starts with a newline that is not part of the code.
+pub(crate) fn extract_code(body: &str) -> String {
+    let body = match body.strip_prefix("<pre") {
+        Some(rest) => match rest.find('>') {
+            Some(end) => rest[end + 1..].trim_start_matches('\n'),
+            None => body,
+        },
+        None => body,
+    };
+    decode_entities(&TAG_RE.replace_all(body, ""))
+}
+
+fn find_syntax(lang: &str) -> &'static SyntaxReference {
+    SYNTAX_SET
+        .find_syntax_by_token(lang)
+        .or_else(|| {
+            SYNTAX_SET.find_syntax_by_token(&lang.to_ascii_lowercase())
+        })
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text())
+}
+
+/// Applies `f` to the runs of Markdown text outside fenced code blocks,
+/// leaving the blocks as they are.
+pub(crate) fn map_prose(
+    markdown: &str,
+    f: impl Fn(&str) -> String,
+) -> String {
+    let mut mapped = String::with_capacity(markdown.len());
+    let mut text = String::new();
+    let mut fence: Option<String> = None;
+    for line in markdown.split_inclusive('\n') {
+        match &fence {
+            Some(marker) => {
+                mapped.push_str(line);
+                if closes_fence(line, marker) {
+                    fence = None;
+                }
+            }
+            None => match FENCE_RE.captures(line) {
+                Some(caps) => {
+                    mapped.push_str(&f(&text));
+                    text.clear();
+                    mapped.push_str(line);
+                    fence = Some(caps[1].to_string());
+                }
+                None => text.push_str(line),
+            },
+        }
+    }
+    mapped.push_str(&f(&text));
+    mapped
+}
+
+/// Returns `true` if `line` closes the fenced code block opened with
+/// `marker`: a run of the same character at least as long.
+fn closes_fence(line: &str, marker: &str) -> bool {
+    let line = line.trim();
+    let fence = marker.chars().next();
+    line.len() >= marker.len() && line.chars().all(|c| Some(c) == fence)
+}
+
+pub(crate) fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
+
+fn escape_attribute(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::{render_markdown, render_markdown_with};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_code_block_info_parse() {
+        let info = CodeBlockInfo::parse("rust{2,4-5}:line-numbers");
+        assert_eq!(info.language, "rust");
+        assert_eq!(info.hl_lines, vec![2, 4, 5]);
+        assert_eq!(info.line_numbers, Some(true));
+
+        let plain = CodeBlockInfo::parse("python");
+        assert_eq!(plain.language, "python");
+        assert!(plain.hl_lines.is_empty());
+        assert_eq!(plain.line_numbers, None);
+
+        let off = CodeBlockInfo::parse("js:no-line-numbers");
+        assert_eq!(off.line_numbers, Some(false));
+    }
+
+    #[test]
+    fn test_parse_line_ranges_ignores_garbage() {
+        assert_eq!(parse_line_ranges("3, 1-2, x, 2"), vec![1, 2, 3]);
+        assert!(parse_line_ranges("").is_empty());
+    }
+
+    #[test]
+    fn test_highlight_with_line_numbers_and_hl_lines() -> Result<()> {
+        let options = MarkdownOptions {
+            line_numbers: true,
+            ..MarkdownOptions::default()
+        };
+        let html = apply_syntax_highlighting(
+            "fn main() {\n    let x = 1;\n}\n",
+            "rust",
+            &options,
+            &[2],
+            true,
+        )?;
+
+        assert!(html.starts_with("<pre class=\"highlight\""));
+        assert!(html.contains("data-lang=\"rust\""));
+        assert_eq!(html.matches("<span class=\"line\">").count(), 2);
+        assert_eq!(html.matches("line highlighted").count(), 1);
+        assert!(html.contains("<span class=\"line-number\">3</span>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_language_aliases_and_unknown_theme() {
+        let mut options = MarkdownOptions::default();
+        let _ = options
+            .language_aliases
+            .insert("shell".to_string(), "bash".to_string());
+        assert_eq!(options.resolve_language("shell"), "bash");
+        assert_eq!(options.resolve_language("rust"), "rust");
+
+        options.syntax_theme = "no-such-theme".to_string();
+        let err = options.validate().unwrap_err().to_string();
+        assert!(err.contains("InspiredGitHub"));
+    }
+
+    #[test]
+    fn test_highlight_code_blocks_rewrites_rendered_html() -> Result<()>
+    {
+        let rendered = "<p>Intro</p>\
+            <pre><code class=\"language-rust{1}\">let a = &quot;&lt;b&gt;&quot;;\n</code></pre>\
+            <pre><code>plain</code></pre>";
+        let html = highlight_code_blocks(
+            rendered,
+            &MarkdownOptions::default(),
+        )?;
+
+        assert!(
+            html.starts_with("<p>Intro</p><pre class=\"highlight\"")
+        );
+        assert!(html.contains("line highlighted"));
+        assert!(html.contains("&lt;b&gt;"));
+        assert!(html.ends_with("<pre><code>plain</code></pre>"));
+
+        let again =
+            highlight_code_blocks(&html, &MarkdownOptions::default())?;
+        assert_eq!(again, html);
+        Ok(())
+    }
+
+    #[test]
+    fn test_highlight_replaces_renderer_markup() -> Result<()> {
+        let rendered = "<pre><code class=\"language-rust\">\
+            <pre style=\"background-color:#2b303b;\">\n\
+            <span style=\"color:#b48ead;\">fn</span> main() {}\n\
+            </pre></code></pre>";
+        let options = MarkdownOptions {
+            syntax_theme: "InspiredGitHub".to_string(),
+            ..MarkdownOptions::default()
+        };
+        let html = highlight_code_blocks(rendered, &options)?;
+
+        assert!(!html.contains("#2b303b"));
+        assert_eq!(html.matches("<span class=\"line\">").count(), 1);
+        assert!(html.contains("main"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_highlight_site() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let page = temp_dir.path().join("index.html");
+        fs::write(
+            &page,
+            "<pre><code class=\"language-toml\">a = 1\n</code></pre>",
+        )?;
+        fs::write(temp_dir.path().join("style.css"), "pre {}")?;
+
+        let count = highlight_site(
+            temp_dir.path(),
+            &MarkdownOptions::default(),
+        )?;
+        assert_eq!(count, 1);
+        assert!(fs::read_to_string(&page)?.contains("class=\"line\""));
+
+        let disabled = MarkdownOptions {
+            syntax_highlighting: false,
+            ..MarkdownOptions::default()
+        };
+        assert_eq!(highlight_site(temp_dir.path(), &disabled)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_enhance_tables_with_options() {
+        let rendered = "<table><thead><tr><th>Name</th><th align=\"center\">Tier</th></tr></thead><tbody>\n<tr><th>Linux</th><td align=\"center\">1</td></tr>\n</tbody></table>\n<p>Table: Platforms</p>\n";
+        let options = TableOptions {
+            captions: false,
+            alignment_classes: false,
+            responsive: true,
+            ..TableOptions::default()
+        };
+
+        let html = enhance_tables(rendered, &options);
+        assert_eq!(
+            html,
+            "<div class=\"table-wrapper\"><table><thead><tr><th scope=\"col\">Name</th><th align=\"center\" scope=\"col\">Tier</th></tr></thead><tbody>\n<tr><th scope=\"row\">Linux</th><td align=\"center\">1</td></tr>\n</tbody></table></div>\n<p>Table: Platforms</p>\n"
+        );
+        assert_eq!(enhance_tables(&html, &options), html);
+
+        let captioned =
+            enhance_tables(rendered, &TableOptions::default());
+        assert!(captioned
+            .starts_with("<table><caption>Platforms</caption><thead>"));
+        assert!(captioned.contains("<td class=\"align-center\">1</td>"));
+        assert!(captioned.ends_with("</table>\n"));
+
+        let mut invalid = MarkdownOptions::default();
+        invalid.tables.responsive = true;
+        invalid.tables.wrapper_class = "table wrapper".to_string();
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_enhance_tables_site() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let page = temp_dir.path().join("index.html");
+        fs::write(&page, render_markdown("| A |\n|--:|\n| 1 |\n"))?;
+        fs::write(
+            temp_dir.path().join("plain.html"),
+            "<p>No tables</p>",
+        )?;
+
+        let options = TableOptions::default();
+        assert_eq!(enhance_tables_site(temp_dir.path(), &options)?, 1);
+        let html = fs::read_to_string(&page)?;
+        assert!(html.contains(
+            "<th class=\"align-right\" scope=\"col\">A</th>"
+        ));
+        assert_eq!(enhance_tables_site(temp_dir.path(), &options)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_footnotes() {
+        let markdown =
+            "Fast[^speed], simple[^1] and fast[^speed] again.\n\n\
+                        `[^speed]` and [^missing] stay.\n\n\
+                        [^speed]: See [benchmarks](/bench/).\n\
+                        [^1]: No config.\n\
+                        [^unused]: Never referenced.\n\n\
+                        After the notes.\n";
+        let html = render_markdown_with(
+            markdown,
+            &ExtensionOptions::default(),
+        );
+        assert!(html.starts_with(
+            "<p>Fast<sup class=\"footnote-ref\"><a href=\"#fn-speed\" \
+             id=\"fnref-speed\" data-footnote-ref>1</a></sup>, simple\
+             <sup class=\"footnote-ref\"><a href=\"#fn-1\" id=\"fnref-1\" \
+             data-footnote-ref>2</a></sup>"
+        ));
+        assert!(
+            html.contains("id=\"fnref-speed-2\" data-footnote-ref>1<")
+        );
+        assert!(
+            html.contains("<code>[^speed]</code> and [^missing] stay.")
+        );
+        assert!(html.contains(
+            "<p>After the notes.</p>\n\
+             <section class=\"footnotes\" data-footnotes>\n<ol>\n\
+             <li id=\"fn-speed\">\n<p>See <a href=\"/bench/\">benchmarks</a>. \
+             <a href=\"#fnref-speed\" class=\"footnote-backref\" \
+             data-footnote-backref aria-label=\"Back to reference 1\">↩</a></p>\n</li>\n"
+        ));
+        assert!(html.contains(
+            "<li id=\"fn-1\">\n<p>No config. <a href=\"#fnref-1\""
+        ));
+        assert!(!html.contains("Never referenced"));
+        assert!(html.ends_with("</ol>\n</section>\n"));
+
+        let options = ExtensionOptions {
+            footnotes: false,
+            ..ExtensionOptions::default()
+        };
+        let html = render_markdown_with(markdown, &options);
+        assert!(html.contains("Fast[^speed], simple[^1]"));
+        assert!(!html.contains("footnote"));
+    }
+
+    #[test]
+    fn test_render_lists_and_strikethrough() {
+        let markdown = "- [ ] Open\n- [X] Done\n\n\
+                        Apple\n: A fruit\n: A company\n\n\
+                        ~~old~~ `~~kept~~`\n";
+        assert_eq!(
+            render_markdown_with(markdown, &ExtensionOptions::default()),
+            "<ul>\n<li class=\"task-list-item\"><input type=\"checkbox\" \
+             class=\"task-list-item-checkbox\" disabled=\"\" /> Open</li>\n\
+             <li class=\"task-list-item\"><input type=\"checkbox\" \
+             class=\"task-list-item-checkbox\" disabled=\"\" checked=\"\" /> \
+             Done</li>\n</ul>\n\
+             <dl>\n<dt>Apple</dt>\n<dd>A fruit</dd>\n<dd>A company</dd>\n</dl>\n\
+             <p><del>old</del> <code>~~kept~~</code></p>\n"
+        );
+
+        let off = ExtensionOptions {
+            footnotes: false,
+            task_lists: false,
+            definition_lists: false,
+            strikethrough: false,
+        };
+        assert!(!off.is_enabled());
+        let html = render_markdown_with(markdown, &off);
+        assert!(html.contains("<li>[ ] Open</li>"));
+        assert!(html.contains("<p>Apple\n: A fruit\n: A company</p>"));
+        assert!(html.contains("~~old~~"));
+        assert!(!html.contains("<del>"));
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Math
+//!
+//! Renders TeX math written in Markdown: `$...$` inline and `$$...$$`
+//! as a display block.
+//!
+//! ```markdown
+//! Euler's identity, $e^{i\pi} + 1 = 0$, follows from
+//!
+//! $$
+//! e^{ix} = \cos x + i \sin x
+//! $$
+//! ```
+//!
+//! Math is off by default, since a `$` in prose is common. The
+//! `[markdown.math]` table of the configuration file turns it on:
+//!
+//! ```toml
+//! [markdown.math]
+//! enabled = true
+//! renderer = "katex"   # or "mathml"
+//! katex_url = "https://cdn.jsdelivr.net/npm/katex@0.16.11/dist"
+//! ```
+//!
+//! Before the core compile, each formula in the Markdown is swapped for
+//! a token the Markdown renderer leaves alone, so `a_1 * b_2` is not
+//! turned into emphasis. After it, the tokens are rendered:
+//!
+//! | Renderer | Output |
+//! |----------|--------|
+//! | `katex` | `<span class="math math-inline">` and `<div class="math math-display">` holding the TeX, rendered in the browser by KaTeX, whose stylesheet and script are added to the pages with math |
+//! | `mathml` | MathML rendered at build time, with no script; needs the `mathml` feature |
+//!
+//! A `$` must be followed by a non-space and the closing `$` preceded
+//! by one and not followed by a digit, so `$5 and $10` stays text.
+//! `\$` is a literal dollar sign. Code blocks and inline code are left
+//! alone. A formula MathML cannot render is kept as text with a
+//! warning.
+
+use anyhow::{ensure, Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+use crate::{
+    collect_files_recursive,
+    content_files::rewrite_bodies,
+    diagnostics,
+    markdown::map_prose,
+    template::{escape_html, hex},
+};
+
+/// Default location of the KaTeX distribution.
+pub const DEFAULT_KATEX_URL: &str =
+    "https://cdn.jsdelivr.net/npm/katex@0.16.11/dist";
+
+/// Id of the injected KaTeX script.
+pub const KATEX_SCRIPT_ID: &str = "ssg-katex";
+
+/// Renders every `.math` element with KaTeX once the page is parsed.
+const KATEX_RENDER_JS: &str = r#"document.addEventListener("DOMContentLoaded",function(){document.querySelectorAll(".math").forEach(function(e){katex.render(e.textContent,e,{displayMode:e.classList.contains("math-display"),throwOnError:false});});});"#;
+
+/// Matches, in Markdown, an escaped dollar sign, inline code, display
+/// math or inline math followed by an optional digit.
+static MATH_SOURCE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?s)\\\$|``.+?``|`[^`]*`|\$\$(.+?)\$\$|\$([^\s$\\](?:[^$\n]*?[^\s$\\])?)\$([0-9])?",
+    )
+    .unwrap_or_else(|e| panic!("Invalid math source regex: {}", e))
+});
+
+/// Matches a display math token alone in a paragraph.
+static DISPLAY_TOKEN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<p>\s*ssgmathd([0-9a-f]*)z\s*</p>")
+        .unwrap_or_else(|e| panic!("Invalid math token regex: {}", e))
+});
+
+/// Matches a math token.
+static TOKEN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"ssgmath([di])([0-9a-f]*)z")
+        .unwrap_or_else(|e| panic!("Invalid math token regex: {}", e))
+});
+
+/// Matches the closing `</head>` tag.
+static HEAD_END_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)</head\s*>")
+        .unwrap_or_else(|e| panic!("Invalid head regex: {}", e))
+});
+
+/// How formulas are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MathRenderer {
+    /// In the browser, by KaTeX.
+    Katex,
+    /// At build time, to MathML.
+    Mathml,
+}
+
+impl Default for MathRenderer {
+    fn default() -> Self {
+        Self::Katex
+    }
+}
+
+/// `[markdown.math]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MathOptions {
+    /// Renders `$...$` and `$$...$$` as math.
+    pub enabled: bool,
+    /// How formulas are rendered.
+    pub renderer: MathRenderer,
+    /// Location of the KaTeX `katex.min.css` and `katex.min.js` files.
+    pub katex_url: String,
+}
+
+impl Default for MathOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            renderer: MathRenderer::default(),
+            katex_url: DEFAULT_KATEX_URL.to_string(),
+        }
+    }
+}
+
+impl MathOptions {
+    /// Checks that the KaTeX location is an HTTPS URL or a site path,
+    /// and that MathML rendering is available.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.katex_url.starts_with("https://")
+                || self.katex_url.starts_with('/'),
+            "katex_url `{}` must be an https:// URL or start with /",
+            self.katex_url
+        );
+        ensure!(
+            self.renderer != MathRenderer::Mathml
+                || cfg!(feature = "mathml"),
+            "renderer `mathml` needs ssg built with the `mathml` feature"
+        );
+        Ok(())
+    }
+}
+
+/// Replaces the formulas of a Markdown body with tokens the Markdown
+/// renderer passes through as text, for [`render_math`] to render.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::math::protect_math;
+///
+/// assert_eq!(protect_math("$a_1$ costs $5"), "ssgmathi615f31z costs $5");
+/// assert_eq!(protect_math("`$x$`"), "`$x$`");
+/// ```
+pub fn protect_math(markdown: &str) -> String {
+    map_prose(markdown, protect_text)
+}
+
+/// Protects the formulas of every Markdown file below `dir` in place,
+/// leaving their frontmatter alone.
+///
+/// Returns the number of files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written.
+pub fn protect_math_dir(dir: &Path) -> Result<usize> {
+    rewrite_bodies(dir, protect_math)
+}
+
+/// Renders the math tokens left in `html` by [`protect_math`] and, with
+/// KaTeX, adds its stylesheet and script before `</head>`. Returns
+/// `None` if the page has no math.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::math::{protect_math, render_math, MathOptions};
+///
+/// let html = format!("<p>{}</p>", protect_math("$x < 1$"));
+/// let rendered = render_math(&html, &MathOptions::default()).unwrap();
+/// assert_eq!(
+///     rendered,
+///     "<p><span class=\"math math-inline\">x &lt; 1</span></p>"
+/// );
+/// ```
+pub fn render_math(
+    html: &str,
+    options: &MathOptions,
+) -> Option<String> {
+    if !TOKEN_RE.is_match(html) {
+        return None;
+    }
+    let html =
+        DISPLAY_TOKEN_RE.replace_all(html, |caps: &Captures<'_>| {
+            render_formula(&decode(&caps[1]), true, "div", options)
+        });
+    let html = TOKEN_RE.replace_all(&html, |caps: &Captures<'_>| {
+        render_formula(
+            &decode(&caps[2]),
+            &caps[1] == "d",
+            "span",
+            options,
+        )
+    });
+    if options.renderer == MathRenderer::Mathml
+        || html.contains(KATEX_SCRIPT_ID)
+    {
+        return Some(html.into_owned());
+    }
+    let assets = katex_assets(&options.katex_url);
+    match HEAD_END_RE.find(&html) {
+        Some(head) => Some(format!(
+            "{}{}{}",
+            &html[..head.start()],
+            assets,
+            &html[head.start()..]
+        )),
+        None => Some(html.into_owned()),
+    }
+}
+
+/// Applies [`render_math`] to every HTML file below `site_dir`.
+///
+/// Returns the number of files that were rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written.
+pub fn render_math_site(
+    site_dir: &Path,
+    options: &MathOptions,
+) -> Result<usize> {
+    if !options.enabled || !site_dir.exists() {
+        return Ok(0);
+    }
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+
+    let mut rewritten = 0;
+    for path in files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let rendered = match render_math(&html, options) {
+            Some(rendered) => rendered,
+            None => continue,
+        };
+        fs::write(&path, rendered).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        rewritten += 1;
+    }
+    Ok(rewritten)
+}
+
+/// Replaces the formulas of Markdown text outside code blocks with
+/// tokens.
+fn protect_text(text: &str) -> String {
+    MATH_SOURCE_RE
+        .replace_all(text, |caps: &Captures<'_>| {
+            match (caps.get(1), caps.get(2), caps.get(3)) {
+                (Some(tex), _, _) => token('d', tex.as_str().trim()),
+                (None, Some(tex), None) => token('i', tex.as_str()),
+                _ => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// Encodes `tex` as a token of `kind`, `i` for inline and `d` for
+/// display math.
+fn token(kind: char, tex: &str) -> String {
+    format!("ssgmath{}{}z", kind, hex(tex.as_bytes()))
+}
+
+/// Decodes the TeX of a token.
+fn decode(encoded: &str) -> String {
+    let bytes: Vec<u8> = (0..encoded.len() / 2)
+        .filter_map(|i| {
+            u8::from_str_radix(&encoded[i * 2..i * 2 + 2], 16).ok()
+        })
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Renders one formula in an element named `tag`.
+fn render_formula(
+    tex: &str,
+    display: bool,
+    tag: &str,
+    options: &MathOptions,
+) -> String {
+    let class = if display {
+        "math-display"
+    } else {
+        "math-inline"
+    };
+    if options.renderer == MathRenderer::Mathml {
+        match to_mathml(tex, display) {
+            Ok(mathml) => return mathml,
+            Err(e) => diagnostics::warn(format!(
+                "Math `{}` not rendered: {}",
+                tex, e
+            )),
+        }
+        return format!(
+            "<{tag} class=\"math {class} math-error\">{}</{tag}>",
+            escape_html(tex),
+            tag = tag,
+            class = class
+        );
+    }
+    format!(
+        "<{tag} class=\"math {class}\">{}</{tag}>",
+        escape_html(tex),
+        tag = tag,
+        class = class
+    )
+}
+
+/// Returns the KaTeX stylesheet and scripts for `url`.
+fn katex_assets(url: &str) -> String {
+    let url = escape_html(url.trim_end_matches('/'));
+    format!(
+        "<link rel=\"stylesheet\" href=\"{url}/katex.min.css\">\n\
+         <script defer id=\"{id}\" src=\"{url}/katex.min.js\"></script>\n\
+         <script>{js}</script>\n",
+        url = url,
+        id = KATEX_SCRIPT_ID,
+        js = KATEX_RENDER_JS
+    )
+}
+
+/// Renders `tex` to MathML.
+#[cfg(feature = "mathml")]
+fn to_mathml(tex: &str, display: bool) -> Result<String> {
+    use latex2mathml::{latex_to_mathml, DisplayStyle};
+
+    let style = if display {
+        DisplayStyle::Block
+    } else {
+        DisplayStyle::Inline
+    };
+    latex_to_mathml(tex, style).map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+/// Fails: MathML rendering needs the `mathml` feature.
+#[cfg(not(feature = "mathml"))]
+fn to_mathml(_tex: &str, _display: bool) -> Result<String> {
+    anyhow::bail!("build with the `mathml` feature to render MathML")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protect_math() {
+        let markdown = "Let $x_1 * y_2$ be \\$3 or $5 and $10.\n\n\
+                        $$\na_b_c\n$$\n\n\
+                        ```tex\n$kept$\n```\n\n\
+                        Use `$HOME` and ``$``.\n";
+        let protected = protect_math(markdown);
+        assert_eq!(
+            protected,
+            format!(
+                "Let {} be \\$3 or $5 and $10.\n\n{}\n\n\
+                 ```tex\n$kept$\n```\n\n\
+                 Use `$HOME` and ``$``.\n",
+                token('i', "x_1 * y_2"),
+                token('d', "a_b_c")
+            )
+        );
+        assert_eq!(protect_math("$x$2"), "$x$2");
+        assert_eq!(protect_math("$ x $"), "$ x $");
+    }
+
+    #[test]
+    fn test_render_math_with_katex() {
+        let html = format!(
+            "<html><head><title>A</title></head><body><p>{}</p>\n<p>Where {} holds.</p></body></html>",
+            token('d', "\\sum_i x_i"),
+            token('i', "x > 0")
+        );
+        let rendered =
+            render_math(&html, &MathOptions::default()).unwrap();
+        assert!(rendered.contains(
+            "<div class=\"math math-display\">\\sum_i x_i</div>"
+        ));
+        assert!(rendered.contains(
+            "<span class=\"math math-inline\">x &gt; 0</span>"
+        ));
+        assert!(rendered.contains(&format!(
+            "{}/katex.min.css\">",
+            DEFAULT_KATEX_URL
+        )));
+        assert!(
+            rendered.find(KATEX_SCRIPT_ID) < rendered.find("</head>")
+        );
+        assert_eq!(
+            render_math(&rendered, &MathOptions::default()),
+            None
+        );
+        assert_eq!(
+            render_math("<p>$5</p>", &MathOptions::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut options = MathOptions::default();
+        assert!(options.validate().is_ok());
+        options.katex_url = "http://cdn.example.com/katex".into();
+        assert!(options.validate().is_err());
+        options.katex_url = "/assets/katex".into();
+        options.renderer = MathRenderer::Mathml;
+        assert_eq!(
+            options.validate().is_ok(),
+            cfg!(feature = "mathml")
+        );
+    }
+}
@@ -0,0 +1,493 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Content-Addressable Media
+//!
+//! Moves every media file of the site to `/media/<hash>.<ext>`, named by
+//! a hash of its contents, and rewrites references to it in HTML
+//! (`src`, `href`, `poster` and `srcset`) and CSS (`url(...)`). Media
+//! URLs then never change while the file stays the same, so they can be
+//! cached forever, and identical files anywhere in the site are stored
+//! once.
+//!
+//! Enable it in the `[media]` table of the configuration file:
+//!
+//! ```toml
+//! [media]
+//! content_addressed = true
+//! # Also keep files at their old paths, for references from scripts.
+//! keep_originals = false
+//! ```
+//!
+//! The mapping from old to new URLs is written to `media.json`:
+//!
+//! ```json
+//! {
+//!   "/blog/cat.jpg": "/media/1f3a9c0e52b7d4a8e6c1b2d3f4a5b6c7.jpg"
+//! }
+//! ```
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    collect_files_recursive,
+    images::{escape_attribute, resolve_local},
+    template::hex,
+};
+
+/// Directory of content-addressed media, relative to the site root.
+pub const MEDIA_DIR: &str = "media";
+
+/// File name of the URL map written to the site root.
+pub const MEDIA_MANIFEST: &str = "media.json";
+
+/// Extensions of files treated as media.
+pub const MEDIA_EXTENSIONS: &[&str] = &[
+    "avif", "gif", "ico", "jpeg", "jpg", "png", "svg", "webp", "mp3",
+    "mp4", "ogg", "wav", "webm", "pdf",
+];
+
+/// Number of hash bytes in a media file name.
+const HASH_BYTES: usize = 16;
+
+/// Matches a URL attribute of an HTML tag.
+static ATTRIBUTE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)(\s(?:src|href|poster|srcset)\s*=\s*)(?:"([^"]*)"|'([^']*)')"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid attribute regex: {}", e))
+});
+
+/// Matches a CSS `url(...)`.
+static CSS_URL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"url\(\s*(?:"([^"]*)"|'([^']*)'|([^)'"\s]*))\s*\)"#)
+        .unwrap_or_else(|e| panic!("Invalid CSS url regex: {}", e))
+});
+
+/// Options of the `[media]` config table.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct MediaOptions {
+    /// Moves media to `/media/<hash>.<ext>`.
+    pub content_addressed: bool,
+    /// Keeps media at their original paths as well.
+    pub keep_originals: bool,
+}
+
+/// Result of [`store_media`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MediaManifest {
+    /// Old URL path of each media file mapped to its new one.
+    pub files: BTreeMap<String, String>,
+    /// Number of files whose contents were already stored.
+    pub deduplicated: usize,
+    /// Number of HTML and CSS files that were rewritten.
+    pub rewritten: usize,
+}
+
+/// Returns the content-addressed path of a file with `contents` and
+/// `extension`, relative to the site root.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::media::media_path;
+///
+/// let path = media_path(b"GIF89a", "GIF");
+/// assert!(path.starts_with("media/") && path.ends_with(".gif"));
+/// assert_eq!(path, media_path(b"GIF89a", "gif"));
+/// ```
+pub fn media_path(contents: &[u8], extension: &str) -> String {
+    let digest = Sha256::digest(contents);
+    format!(
+        "{}/{}.{}",
+        MEDIA_DIR,
+        hex(&digest[..HASH_BYTES]),
+        extension.to_ascii_lowercase()
+    )
+}
+
+/// Moves the media below `site_dir` to content-addressed paths and
+/// rewrites the HTML and CSS references to them, when enabled.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read, written or moved.
+pub fn store_media(
+    site_dir: &Path,
+    options: &MediaOptions,
+) -> Result<MediaManifest> {
+    let mut manifest = MediaManifest::default();
+    if !options.content_addressed || !site_dir.exists() {
+        return Ok(manifest);
+    }
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    let media_dir = site_dir.join(MEDIA_DIR);
+    let media: Vec<PathBuf> = files
+        .iter()
+        .filter(|path| is_media(path) && !path.starts_with(&media_dir))
+        .cloned()
+        .collect();
+
+    let hashed = media
+        .par_iter()
+        .map(|path| {
+            let contents = fs::read(path).with_context(|| {
+                format!("Failed to read {}", path.display())
+            })?;
+            let extension = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or_default();
+            Ok((
+                url_path(site_dir, path),
+                media_path(&contents, extension),
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let paths: BTreeMap<String, String> = hashed.into_iter().collect();
+
+    for path in &files {
+        let rewritten = match path.extension().and_then(|e| e.to_str())
+        {
+            Some("html") => {
+                rewrite_file(site_dir, path, &paths, rewrite_html)?
+            }
+            Some("css") => {
+                rewrite_file(site_dir, path, &paths, rewrite_css)?
+            }
+            _ => false,
+        };
+        if rewritten {
+            manifest.rewritten += 1;
+        }
+    }
+
+    for path in &media {
+        let target = site_dir.join(&paths[&url_path(site_dir, path)]);
+        if target.exists() {
+            manifest.deduplicated += 1;
+            if !options.keep_originals {
+                fs::remove_file(path).with_context(|| {
+                    format!("Failed to remove {}", path.display())
+                })?;
+            }
+            continue;
+        }
+        fs::create_dir_all(&media_dir).with_context(|| {
+            format!("Failed to create {}", media_dir.display())
+        })?;
+        if options.keep_originals {
+            fs::copy(path, &target).map(|_| ())
+        } else {
+            fs::rename(path, &target)
+        }
+        .with_context(|| {
+            format!(
+                "Failed to move {} to {}",
+                path.display(),
+                target.display()
+            )
+        })?;
+    }
+
+    manifest.files = paths
+        .into_iter()
+        .map(|(old, new)| (format!("/{}", old), format!("/{}", new)))
+        .collect();
+    let json = serde_json::to_string_pretty(&manifest.files)
+        .context("Failed to serialize the media map")?;
+    let path = site_dir.join(MEDIA_MANIFEST);
+    fs::write(&path, json).with_context(|| {
+        format!("Failed to write {}", path.display())
+    })?;
+    Ok(manifest)
+}
+
+/// Rewrites the media references of the HTML page at site-relative
+/// `page_path`, given old site-relative media paths mapped to new ones.
+/// Returns `None` if nothing changed.
+pub fn rewrite_html(
+    html: &str,
+    page_path: &Path,
+    paths: &BTreeMap<String, String>,
+) -> Option<String> {
+    let mut changed = false;
+    let updated =
+        ATTRIBUTE_RE.replace_all(html, |caps: &Captures<'_>| {
+            let value = match caps.get(2).or_else(|| caps.get(3)) {
+                Some(value) => value.as_str(),
+                None => return caps[0].to_string(),
+            };
+            let rewritten =
+                if caps[1].to_ascii_lowercase().contains("srcset") {
+                    rewrite_srcset(value, page_path, paths)
+                } else {
+                    lookup(value, page_path, paths)
+                };
+            match rewritten {
+                Some(rewritten) => {
+                    changed = true;
+                    format!(
+                        "{}\"{}\"",
+                        &caps[1],
+                        escape_attribute(&rewritten)
+                    )
+                }
+                None => caps[0].to_string(),
+            }
+        });
+    if changed {
+        Some(updated.into_owned())
+    } else {
+        None
+    }
+}
+
+/// Rewrites the `url(...)` media references of the stylesheet at
+/// site-relative `css_path`. Returns `None` if nothing changed.
+pub fn rewrite_css(
+    css: &str,
+    css_path: &Path,
+    paths: &BTreeMap<String, String>,
+) -> Option<String> {
+    let mut changed = false;
+    let updated = CSS_URL_RE.replace_all(css, |caps: &Captures<'_>| {
+        let value = caps
+            .get(1)
+            .or_else(|| caps.get(2))
+            .or_else(|| caps.get(3))
+            .map_or("", |value| value.as_str());
+        match lookup(value, css_path, paths) {
+            Some(rewritten) => {
+                changed = true;
+                format!("url(\"{}\")", rewritten)
+            }
+            None => caps[0].to_string(),
+        }
+    });
+    if changed {
+        Some(updated.into_owned())
+    } else {
+        None
+    }
+}
+
+fn rewrite_srcset(
+    srcset: &str,
+    page_path: &Path,
+    paths: &BTreeMap<String, String>,
+) -> Option<String> {
+    let mut changed = false;
+    let candidates: Vec<String> = srcset
+        .split(',')
+        .map(|candidate| {
+            let candidate = candidate.trim();
+            let (url, descriptor) = candidate
+                .split_once(char::is_whitespace)
+                .unwrap_or((candidate, ""));
+            match lookup(url, page_path, paths) {
+                Some(url) => {
+                    changed = true;
+                    format!("{} {}", url, descriptor.trim())
+                        .trim_end()
+                        .to_string()
+                }
+                None => candidate.to_string(),
+            }
+        })
+        .collect();
+    if changed {
+        Some(candidates.join(", "))
+    } else {
+        None
+    }
+}
+
+fn lookup(
+    reference: &str,
+    page_path: &Path,
+    paths: &BTreeMap<String, String>,
+) -> Option<String> {
+    let resolved = resolve_local(page_path, reference)?;
+    paths.get(&resolved).map(|path| format!("/{}", path))
+}
+
+fn rewrite_file(
+    site_dir: &Path,
+    path: &Path,
+    paths: &BTreeMap<String, String>,
+    rewrite: fn(
+        &str,
+        &Path,
+        &BTreeMap<String, String>,
+    ) -> Option<String>,
+) -> Result<bool> {
+    let source = fs::read_to_string(path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    let relative = path.strip_prefix(site_dir).unwrap_or(path);
+    match rewrite(&source, relative, paths) {
+        Some(updated) => {
+            fs::write(path, updated).with_context(|| {
+                format!("Failed to write {}", path.display())
+            })?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+fn is_media(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map_or(false, |ext| {
+            MEDIA_EXTENSIONS
+                .contains(&ext.to_ascii_lowercase().as_str())
+        })
+}
+
+/// The site-relative path of `path` with `/` separators.
+fn url_path(site_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(site_dir)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn paths() -> BTreeMap<String, String> {
+        let mut paths = BTreeMap::new();
+        let _ =
+            paths.insert("img/cat.jpg".into(), "media/aa.jpg".into());
+        let _ = paths
+            .insert("img/cat@2x.jpg".into(), "media/bb.jpg".into());
+        paths
+    }
+
+    #[test]
+    fn test_rewrite_html_references() {
+        let html = "<img src=\"../img/cat.jpg\" \
+                    srcset='/img/cat.jpg 1x, /img/cat@2x.jpg 2x'>\
+                    <a href=\"/about/\">About</a>";
+        assert_eq!(
+            rewrite_html(html, Path::new("blog/index.html"), &paths())
+                .unwrap(),
+            "<img src=\"/media/aa.jpg\" \
+             srcset=\"/media/aa.jpg 1x, /media/bb.jpg 2x\">\
+             <a href=\"/about/\">About</a>"
+        );
+        assert!(rewrite_html(
+            "<img src=\"https://example.com/img/cat.jpg\">",
+            Path::new("index.html"),
+            &paths()
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_rewrite_css_urls() {
+        let css = "body{background:url(../img/cat.jpg)}\
+                   .x{background:url('data:image/png;base64,AA')}";
+        assert_eq!(
+            rewrite_css(css, Path::new("css/site.css"), &paths())
+                .unwrap(),
+            "body{background:url(\"/media/aa.jpg\")}\
+             .x{background:url('data:image/png;base64,AA')}"
+        );
+    }
+
+    #[test]
+    fn test_store_media_moves_and_deduplicates() -> Result<()> {
+        let site = tempdir()?;
+        let root = site.path();
+        fs::create_dir_all(root.join("blog"))?;
+        fs::create_dir_all(root.join("img"))?;
+        fs::write(root.join("img/cat.jpg"), "cat")?;
+        fs::write(root.join("blog/copy.jpg"), "cat")?;
+        fs::write(root.join("img/dog.png"), "dog")?;
+        fs::write(
+            root.join("blog/index.html"),
+            "<img src=\"copy.jpg\"><img src=\"/img/dog.png\">",
+        )?;
+        fs::write(
+            root.join("style.css"),
+            "a{background:url(img/cat.jpg)}",
+        )?;
+
+        assert_eq!(
+            store_media(root, &MediaOptions::default())?,
+            MediaManifest::default()
+        );
+        let options = MediaOptions {
+            content_addressed: true,
+            keep_originals: false,
+        };
+        let manifest = store_media(root, &options)?;
+        assert_eq!(manifest.files.len(), 3);
+        assert_eq!(manifest.deduplicated, 1);
+        assert_eq!(manifest.rewritten, 2);
+
+        let cat = media_path(b"cat", "jpg");
+        assert_eq!(manifest.files["/img/cat.jpg"], format!("/{}", cat));
+        assert_eq!(
+            manifest.files["/blog/copy.jpg"],
+            format!("/{}", cat)
+        );
+        assert!(root.join(&cat).is_file());
+        assert!(!root.join("img/cat.jpg").exists());
+        assert!(!root.join("blog/copy.jpg").exists());
+        assert_eq!(
+            fs::read_to_string(root.join("blog/index.html"))?,
+            format!(
+                "<img src=\"/{}\"><img src=\"/{}\">",
+                cat,
+                media_path(b"dog", "png")
+            )
+        );
+        let json: BTreeMap<String, String> = serde_json::from_str(
+            &fs::read_to_string(root.join(MEDIA_MANIFEST))?,
+        )?;
+        assert_eq!(json, manifest.files);
+
+        // Running again finds no media outside `media/`.
+        assert!(store_media(root, &options)?.files.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_media_keeps_originals() -> Result<()> {
+        let site = tempdir()?;
+        fs::write(site.path().join("logo.png"), "logo")?;
+        let options = MediaOptions {
+            content_addressed: true,
+            keep_originals: true,
+        };
+        let _ = store_media(site.path(), &options)?;
+        assert!(site.path().join("logo.png").is_file());
+        assert!(site.path().join(media_path(b"logo", "png")).is_file());
+        Ok(())
+    }
+}
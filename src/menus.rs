@@ -0,0 +1,577 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Menus
+//!
+//! Named menus built from the configuration and from page frontmatter,
+//! for sites whose navigation should not simply mirror the content
+//! directory. Entries are declared per menu in the configuration file:
+//!
+//! ```toml
+//! [[menus.main]]
+//! name = "Docs"
+//! url = "/docs/"
+//! weight = 2
+//!
+//! [[menus.main]]
+//! name = "Install"
+//! url = "/docs/install/"
+//! parent = "Docs"
+//! ```
+//!
+//! or by the pages themselves:
+//!
+//! ```yaml
+//! menu: main
+//! weight: 3
+//! menu_title: About us   # defaults to the page title
+//! menu_parent: Company   # nests the page under that entry
+//! ```
+//!
+//! An entry is known by its `identifier`, or by its name without one,
+//! and `parent` names the identifier of the entry it is nested under.
+//! Entries are ordered by weight, then by name. Draft pages are left
+//! out.
+//!
+//! Templates rendered by the compiler see the menus three ways:
+//!
+//! | Template use | Value |
+//! |--------------|-------|
+//! | `menus.main` | The entries of `main`, each with `name`, `url`, `weight`, `identifier` and `children` |
+//! | `menu("main", url)` | The same entries, with `active` set on the entry for `url` and `active_trail` on its ancestors |
+//! | `menu_html("main", url)` | A nested `<ul>` of links, the active one marked with `aria-current="page"` |
+//!
+//! Menus are used by pages rendered with the native
+//! [pipeline](crate::pipeline) and by taxonomy pages. Templates also see
+//! the generated `navigation` of the starter templates, a link to every
+//! top-level page, see [`navigation_html`].
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use staticdatagen::{
+    models::data::FileData, modules::navigation::NavigationGenerator,
+};
+use std::{collections::BTreeMap, fmt::Write as _, path::Path};
+
+use crate::{
+    content::ContentSet,
+    template::{escape_html, Engine},
+};
+
+/// Name of the template global holding every menu.
+pub const MENUS_VARIABLE: &str = "menus";
+
+/// Name of the template global holding the generated navigation.
+pub const NAVIGATION_VARIABLE: &str = "navigation";
+
+/// Name of the template function returning a menu with active flags.
+pub const MENU_FUNCTION: &str = "menu";
+
+/// Name of the template function rendering a menu as HTML.
+pub const MENU_HTML_FUNCTION: &str = "menu_html";
+
+/// A `[[menus.<name>]]` entry.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct MenuEntry {
+    /// Link text.
+    pub name: String,
+    /// Link target: a site-relative URL or an `http(s)` URL.
+    pub url: String,
+    /// Sort key among siblings; lower weights come first.
+    pub weight: i64,
+    /// Name other entries use as their `parent`; defaults to `name`.
+    pub identifier: Option<String>,
+    /// Identifier of the entry this one is nested under.
+    pub parent: Option<String>,
+}
+
+impl MenuEntry {
+    /// Checks the name and URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first invalid setting.
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("name must not be empty");
+        }
+        if !(self.url.starts_with('/')
+            || self.url.starts_with("https://")
+            || self.url.starts_with("http://"))
+        {
+            bail!(
+                "url '{}' of '{}' must start with '/' or http(s)://",
+                self.url,
+                self.name
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the identifier of the entry.
+    pub fn identifier(&self) -> &str {
+        self.identifier.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// An entry of a built menu, with its nested entries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MenuItem {
+    /// Identifier of the entry.
+    pub identifier: String,
+    /// Link text.
+    pub name: String,
+    /// Link target.
+    pub url: String,
+    /// Sort key among siblings.
+    pub weight: i64,
+    /// Nested entries, in order.
+    pub children: Vec<MenuItem>,
+}
+
+impl MenuItem {
+    /// Returns `true` if this entry or a nested one links to `url`.
+    pub fn contains(&self, url: &str) -> bool {
+        same_url(&self.url, url)
+            || self.children.iter().any(|child| child.contains(url))
+    }
+
+    /// Returns the entry as JSON, with `active` and `active_trail`
+    /// flags for the page at `url`.
+    fn to_json(&self, url: Option<&str>) -> Value {
+        let mut value = json!({
+            "identifier": self.identifier,
+            "name": self.name,
+            "url": self.url,
+            "weight": self.weight,
+            "children": self
+                .children
+                .iter()
+                .map(|child| child.to_json(url))
+                .collect::<Vec<_>>(),
+        });
+        if let (Some(url), Value::Object(map)) = (url, &mut value) {
+            let active = same_url(&self.url, url);
+            let trail = !active && self.contains(url);
+            let _ = map.insert("active".into(), Value::Bool(active));
+            let _ =
+                map.insert("active_trail".into(), Value::Bool(trail));
+        }
+        value
+    }
+}
+
+/// The menus of a site, by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Menus {
+    menus: BTreeMap<String, Vec<MenuItem>>,
+}
+
+impl Menus {
+    /// Builds the menus from the configured entries and the `menu`
+    /// frontmatter of the pages of `content`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an entry or page is invalid, two entries of a
+    /// menu share an identifier, or a `parent` names no entry of the
+    /// menu or makes a cycle.
+    pub fn build(
+        config: &BTreeMap<String, Vec<MenuEntry>>,
+        content: &ContentSet,
+    ) -> Result<Self> {
+        let mut entries = config.clone();
+        for page in content.iter() {
+            let names = page.frontmatter.get_list("menu");
+            if names.is_empty()
+                || page.frontmatter.get_bool("draft") == Some(true)
+            {
+                continue;
+            }
+            let weight = match page.frontmatter.get_str("weight") {
+                Some(weight) => weight.parse().map_err(|_| {
+                    anyhow!(
+                        "{}: weight '{}' is not a whole number",
+                        page.relative.display(),
+                        weight
+                    )
+                })?,
+                None => 0,
+            };
+            for menu in names {
+                entries.entry(menu).or_default().push(MenuEntry {
+                    name: page
+                        .frontmatter
+                        .get_str("menu_title")
+                        .unwrap_or_else(|| page.title()),
+                    url: page.url_path(),
+                    weight,
+                    identifier: None,
+                    parent: page.frontmatter.get_str("menu_parent"),
+                });
+            }
+        }
+
+        let mut menus = BTreeMap::new();
+        for (name, entries) in entries {
+            let items = build_tree(&entries)
+                .map_err(|e| anyhow!("menus.{}: {}", name, e))?;
+            let _ = menus.insert(name, items);
+        }
+        Ok(Self { menus })
+    }
+
+    /// Returns `true` if no menu has an entry.
+    pub fn is_empty(&self) -> bool {
+        self.menus.values().all(Vec::is_empty)
+    }
+
+    /// Returns the top-level entries of menu `name`.
+    pub fn get(&self, name: &str) -> Option<&[MenuItem]> {
+        self.menus.get(name).map(Vec::as_slice)
+    }
+
+    /// Returns the entries of menu `name` as JSON, with active flags
+    /// for the page at `url`.
+    pub fn menu_json(&self, name: &str, url: &str) -> Option<Value> {
+        self.get(name).map(|items| items_json(items, Some(url)))
+    }
+
+    /// Returns every menu as a JSON object, without active flags.
+    pub fn to_json(&self) -> Value {
+        Value::Object(
+            self.menus
+                .iter()
+                .map(|(name, items)| {
+                    (name.clone(), items_json(items, None))
+                })
+                .collect::<Map<_, _>>(),
+        )
+    }
+
+    /// Renders menu `name` as a nested list of links, marking the entry
+    /// for `url` and its ancestors.
+    pub fn render_html(&self, name: &str, url: &str) -> Option<String> {
+        let items = self.get(name)?;
+        let mut html = String::new();
+        write_list(
+            &mut html,
+            items,
+            url,
+            &format!(" class=\"menu menu-{}\"", escape_html(name)),
+        );
+        Some(html)
+    }
+
+    /// Makes the menus available to templates rendered by `engine` as
+    /// the `menus` global and the `menu` and `menu_html` functions.
+    pub fn register(&self, engine: &mut Engine) {
+        engine.set_global(MENUS_VARIABLE, self.to_json());
+        let menus = self.clone();
+        engine.register_function(MENU_FUNCTION, move |args| {
+            let (name, url) = menu_args(MENU_FUNCTION, args)?;
+            menus
+                .menu_json(name, url)
+                .ok_or_else(|| anyhow!("no menu named `{}`", name))
+        });
+        let menus = self.clone();
+        engine.register_function(MENU_HTML_FUNCTION, move |args| {
+            let (name, url) = menu_args(MENU_HTML_FUNCTION, args)?;
+            menus
+                .render_html(name, url)
+                .map(Value::String)
+                .ok_or_else(|| anyhow!("no menu named `{}`", name))
+        });
+    }
+}
+
+/// Returns the generated navigation of `content`: a `<ul>` linking
+/// every top-level page but the home, error, legal and offline pages,
+/// sorted by title-cased file name, as the core compile generates it.
+pub fn navigation_html(content: &ContentSet) -> String {
+    let files: Vec<FileData> = content
+        .iter()
+        .filter(|page| page.relative.parent() == Some(Path::new("")))
+        .map(|page| FileData {
+            name: page.relative.to_string_lossy().into_owned(),
+            ..FileData::default()
+        })
+        .collect();
+    NavigationGenerator::generate_navigation(&files)
+}
+
+/// Nests `entries` under their parents and sorts each level.
+fn build_tree(entries: &[MenuEntry]) -> Result<Vec<MenuItem>> {
+    let mut children: BTreeMap<&str, Vec<&MenuEntry>> = BTreeMap::new();
+    let mut roots = Vec::new();
+    for entry in entries {
+        entry.validate()?;
+        if entries
+            .iter()
+            .filter(|other| other.identifier() == entry.identifier())
+            .count()
+            > 1
+        {
+            bail!(
+                "two entries are identified as '{}'",
+                entry.identifier()
+            );
+        }
+        match &entry.parent {
+            Some(parent) => {
+                if !entries.iter().any(|e| e.identifier() == parent) {
+                    bail!(
+                        "parent '{}' of '{}' is not an entry of the menu",
+                        parent,
+                        entry.name
+                    );
+                }
+                children.entry(parent).or_default().push(entry);
+            }
+            None => roots.push(entry),
+        }
+    }
+
+    let items = nest(&roots, &children);
+    if count(&items) < entries.len() {
+        bail!("the parents of some entries form a cycle");
+    }
+    Ok(items)
+}
+
+fn nest(
+    entries: &[&MenuEntry],
+    children: &BTreeMap<&str, Vec<&MenuEntry>>,
+) -> Vec<MenuItem> {
+    let mut items: Vec<MenuItem> = entries
+        .iter()
+        .map(|entry| MenuItem {
+            identifier: entry.identifier().to_string(),
+            name: entry.name.clone(),
+            url: entry.url.clone(),
+            weight: entry.weight,
+            children: children
+                .get(entry.identifier())
+                .map(|nested| nest(nested, children))
+                .unwrap_or_default(),
+        })
+        .collect();
+    items.sort_by(|a, b| {
+        a.weight.cmp(&b.weight).then_with(|| a.name.cmp(&b.name))
+    });
+    items
+}
+
+fn count(items: &[MenuItem]) -> usize {
+    items.iter().map(|item| 1 + count(&item.children)).sum()
+}
+
+fn items_json(items: &[MenuItem], url: Option<&str>) -> Value {
+    Value::Array(items.iter().map(|item| item.to_json(url)).collect())
+}
+
+fn write_list(
+    html: &mut String,
+    items: &[MenuItem],
+    url: &str,
+    attrs: &str,
+) {
+    html.push_str(&format!("<ul{}>", attrs));
+    for item in items {
+        let class = if same_url(&item.url, url) {
+            " class=\"active\""
+        } else if item.contains(url) {
+            " class=\"active-trail\""
+        } else {
+            ""
+        };
+        let current = if same_url(&item.url, url) {
+            " aria-current=\"page\""
+        } else {
+            ""
+        };
+        let _ = write!(
+            html,
+            "<li{}><a href=\"{}\"{}>{}</a>",
+            class,
+            escape_html(&item.url),
+            current,
+            escape_html(&item.name)
+        );
+        if !item.children.is_empty() {
+            write_list(html, &item.children, url, "");
+        }
+        html.push_str("</li>");
+    }
+    html.push_str("</ul>");
+}
+
+/// Returns the menu name and page URL arguments of `function`.
+fn menu_args<'a>(
+    function: &str,
+    args: &'a [Value],
+) -> Result<(&'a str, &'a str)> {
+    let name = args
+        .first()
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("`{}` expects a menu name", function))?;
+    Ok((
+        name,
+        args.get(1).and_then(Value::as_str).unwrap_or_default(),
+    ))
+}
+
+/// Compares URLs ignoring a trailing `/` or `index.html`.
+fn same_url(a: &str, b: &str) -> bool {
+    fn normalize(url: &str) -> &str {
+        url.trim_end_matches("index.html").trim_end_matches('/')
+    }
+    !b.is_empty() && normalize(a) == normalize(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::Page;
+    use std::path::PathBuf;
+
+    fn page(relative: &str, raw: &str) -> Page {
+        Page::from_source(
+            PathBuf::from("content").join(relative),
+            PathBuf::from(relative),
+            raw,
+        )
+        .unwrap()
+    }
+
+    fn site() -> Result<Menus> {
+        let mut config = BTreeMap::new();
+        let _ = config.insert(
+            "main".to_string(),
+            vec![
+                MenuEntry {
+                    name: "Home".into(),
+                    url: "/".into(),
+                    ..MenuEntry::default()
+                },
+                MenuEntry {
+                    name: "Docs".into(),
+                    url: "/docs/".into(),
+                    weight: 2,
+                    ..MenuEntry::default()
+                },
+            ],
+        );
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page(
+                    "about.md",
+                    "---\ntitle: About\nmenu: [main, footer]\nweight: 1\n---\n",
+                ),
+                page(
+                    "docs/install.md",
+                    "---\ntitle: Installing\nmenu_title: Install\nmenu: main\nmenu_parent: Docs\n---\n",
+                ),
+                page(
+                    "draft.md",
+                    "---\ntitle: Draft\nmenu: main\ndraft: true\n---\n",
+                ),
+            ],
+        };
+        Menus::build(&config, &content)
+    }
+
+    #[test]
+    fn test_build_nests_and_orders_entries() -> Result<()> {
+        let menus = site()?;
+        let main = menus.get("main").unwrap();
+        let names: Vec<&str> =
+            main.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["Home", "About", "Docs"]);
+        assert_eq!(main[2].children[0].url, "/docs/install/");
+        assert_eq!(menus.get("footer").unwrap()[0].name, "About");
+
+        let json = menus.menu_json("main", "/docs/install/").unwrap();
+        assert_eq!(json[2]["active"], json!(false));
+        assert_eq!(json[2]["active_trail"], json!(true));
+        assert_eq!(json[2]["children"][0]["active"], json!(true));
+        assert_eq!(menus.to_json()["main"][1]["name"], json!("About"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_entries() {
+        let entry = |name: &str, parent: Option<&str>| MenuEntry {
+            name: name.into(),
+            url: format!("/{}/", name),
+            parent: parent.map(String::from),
+            ..MenuEntry::default()
+        };
+        for entries in [
+            vec![entry("a", Some("missing"))],
+            vec![entry("a", Some("b")), entry("b", Some("a"))],
+            vec![entry("a", None), entry("a", None)],
+            vec![MenuEntry {
+                name: "a".into(),
+                url: "docs".into(),
+                ..MenuEntry::default()
+            }],
+        ] {
+            let mut config = BTreeMap::new();
+            let _ = config.insert("main".to_string(), entries);
+            let error = Menus::build(&config, &ContentSet::default())
+                .unwrap_err();
+            assert!(error.to_string().starts_with("menus.main: "));
+        }
+    }
+
+    #[test]
+    fn test_register_renders_menu_html() -> Result<()> {
+        let mut engine = Engine::new();
+        site()?.register(&mut engine);
+        let html = engine.render(
+            "{{ menu_html(\"main\", url) }}",
+            &json!({ "url": "/docs/install/" }),
+        )?;
+        assert_eq!(
+            html,
+            "<ul class=\"menu menu-main\"><li><a href=\"/\">Home</a></li>\
+             <li><a href=\"/about/\">About</a></li>\
+             <li class=\"active-trail\"><a href=\"/docs/\">Docs</a>\
+             <ul><li class=\"active\"><a href=\"/docs/install/\" \
+             aria-current=\"page\">Install</a></li></ul></li></ul>"
+        );
+        assert_eq!(
+            engine.render("{{ menus.main.0.name }}", &json!({}))?,
+            "Home"
+        );
+        assert!(engine
+            .render(
+                "{{ menu(\"sidebar\", url) }}",
+                &json!({ "url": "/" })
+            )
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_navigation_links_top_level_pages() {
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("index.md", "---\ntitle: Home\n---\n"),
+                page("contact.md", "---\ntitle: Contact\n---\n"),
+                page("blog/post.md", "---\ntitle: Post\n---\n"),
+            ],
+        };
+        let html = navigation_html(&content);
+        assert!(html.contains("href=\"/contact/index.html\""));
+        assert!(!html.contains("post"));
+        assert!(!html.contains("href=\"/index/"));
+    }
+}
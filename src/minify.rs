@@ -0,0 +1,711 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Output Minification
+//!
+//! Minifies the files in the site output by format once every other
+//! stage has written them, so pages rewritten by the post-processing
+//! stages and the generated sitemaps and feeds are minified too. Each
+//! format is switched on separately:
+//!
+//! ```toml
+//! [minify]
+//! html = true
+//! css = true
+//! js = true
+//! json = true
+//! xml = true
+//! ```
+//!
+//! The minifiers are conservative: they only drop comments and
+//! whitespace that cannot change how the file is read, and leave
+//! `<pre>` and `<textarea>` contents, strings, CDATA sections and
+//! `/*! ... */` licence comments alone. Files already named `*.min.css`
+//! or `*.min.js` are skipped. With `css` set, stylesheets compiled from
+//! Sass are also emitted in compressed form.
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+use crate::{
+    collect_files_recursive,
+    diagnostics::{self, WarningLog},
+};
+
+/// Blocks of HTML that are minified on their own terms or kept verbatim.
+static HTML_BLOCK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?is)<pre\b[^>]*>.*?</pre\s*>|<textarea\b[^>]*>.*?</textarea\s*>|<script\b([^>]*)>(.*?)</script\s*>|<style\b([^>]*)>(.*?)</style\s*>|<!--.*?-->",
+    )
+    .unwrap_or_else(|e| panic!("Invalid HTML block regex: {}", e))
+});
+
+/// HTML tags, whose attributes are kept as written.
+static HTML_TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<(?:[^>"']|"[^"]*"|'[^']*')*>"#)
+        .unwrap_or_else(|e| panic!("Invalid HTML tag regex: {}", e))
+});
+
+/// `type` attribute of a `<script>` tag.
+static SCRIPT_TYPE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\btype\s*=\s*["']?([^"'\s>]+)"#)
+        .unwrap_or_else(|e| panic!("Invalid script type regex: {}", e))
+});
+
+/// CDATA sections and comments in XML.
+static XML_BLOCK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<!\[CDATA\[.*?\]\]>|<!--.*?-->")
+        .unwrap_or_else(|e| panic!("Invalid XML block regex: {}", e))
+});
+
+/// Whitespace between two XML tags.
+static XML_GAP_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r">\s+<")
+        .unwrap_or_else(|e| panic!("Invalid XML gap regex: {}", e))
+});
+
+/// Runs of whitespace.
+static WHITESPACE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\s+")
+        .unwrap_or_else(|e| panic!("Invalid whitespace regex: {}", e))
+});
+
+/// Keywords after which a `/` starts a regular expression in JavaScript.
+const JS_REGEX_KEYWORDS: &[&str] = &[
+    "case",
+    "delete",
+    "do",
+    "else",
+    "in",
+    "instanceof",
+    "new",
+    "of",
+    "return",
+    "throw",
+    "typeof",
+    "void",
+    "yield",
+];
+
+/// `[minify]` options, one switch per format.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct MinifyOptions {
+    /// Minifies `.html` pages, including inline scripts and styles.
+    pub html: bool,
+    /// Minifies `.css` stylesheets.
+    pub css: bool,
+    /// Minifies `.js` and `.mjs` scripts.
+    pub js: bool,
+    /// Minifies `.json` and `.webmanifest` files.
+    pub json: bool,
+    /// Minifies `.xml`, `.rss` and `.atom` files such as sitemaps and
+    /// feeds.
+    pub xml: bool,
+}
+
+impl MinifyOptions {
+    /// Returns `true` if any format is minified.
+    pub fn is_enabled(&self) -> bool {
+        self.html || self.css || self.js || self.json || self.xml
+    }
+
+    /// Returns `true` if `format` is minified.
+    pub fn minifies(&self, format: Format) -> bool {
+        match format {
+            Format::Html => self.html,
+            Format::Css => self.css,
+            Format::Js => self.js,
+            Format::Json => self.json,
+            Format::Xml => self.xml,
+        }
+    }
+}
+
+/// A file format the minifier understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// HTML pages.
+    Html,
+    /// CSS stylesheets.
+    Css,
+    /// JavaScript.
+    Js,
+    /// JSON documents.
+    Json,
+    /// XML documents, sitemaps and feeds.
+    Xml,
+}
+
+impl Format {
+    /// Returns the format of `path` from its extension, or `None` for
+    /// other files and for files that are already minified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::minify::Format;
+    /// use std::path::Path;
+    ///
+    /// assert_eq!(Format::from_path(Path::new("sitemap.xml")), Some(Format::Xml));
+    /// assert_eq!(Format::from_path(Path::new("app.min.js")), None);
+    /// ```
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".min.css") || name.ends_with(".min.js") {
+            return None;
+        }
+        let extension = name.rsplit_once('.')?.1;
+        match extension {
+            "html" | "htm" => Some(Self::Html),
+            "css" => Some(Self::Css),
+            "js" | "mjs" => Some(Self::Js),
+            "json" | "webmanifest" => Some(Self::Json),
+            "xml" | "rss" | "atom" => Some(Self::Xml),
+            _ => None,
+        }
+    }
+}
+
+/// Minifies `text` as `format`.
+///
+/// # Errors
+///
+/// Returns an error if `text` is not valid JSON when `format` is
+/// [`Format::Json`].
+pub fn minify(format: Format, text: &str) -> Result<String> {
+    match format {
+        Format::Html => Ok(minify_html(text)),
+        Format::Css => Ok(minify_css(text)),
+        Format::Js => Ok(minify_js(text)),
+        Format::Json => minify_json(text),
+        Format::Xml => Ok(minify_xml(text)),
+    }
+}
+
+/// Minifies an HTML page.
+///
+/// Collapses whitespace between and inside text runs to a single space
+/// and drops comments other than conditional comments. Inline styles,
+/// JavaScript and JSON scripts are minified with the matching
+/// minifier, while `<pre>`, `<textarea>` and other scripts are kept as
+/// they are.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::minify::minify_html;
+///
+/// let html = "<ul>\n  <li>One</li>\n  <!-- two -->\n</ul>\n<pre>a\n  b</pre>";
+/// assert_eq!(minify_html(html), "<ul> <li>One</li> </ul> <pre>a\n  b</pre>");
+/// ```
+pub fn minify_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut last = 0;
+    for block in HTML_BLOCK_RE.captures_iter(html) {
+        let whole = block.get(0).map_or(0..0, |m| m.range());
+        collapse_html_text(&html[last..whole.start], &mut out);
+        last = whole.end;
+        out.push_str(&minify_html_block(&block));
+    }
+    collapse_html_text(&html[last..], &mut out);
+    out.trim().to_string()
+}
+
+/// Minifies a CSS stylesheet.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::minify::minify_css;
+///
+/// let css = "/* theme */\na > b,\nc {\n  color: red;\n  content: \"a  b\";\n}\n";
+/// assert_eq!(minify_css(css), "a>b,c{color:red;content:\"a  b\"}");
+/// ```
+pub fn minify_css(css: &str) -> String {
+    let chars: Vec<char> = css.chars().collect();
+    let mut out = String::with_capacity(css.len());
+    let mut space = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '/' && chars.get(i + 1) == Some(&'*') {
+            let end = find_comment_end(&chars, i + 2);
+            if chars.get(i + 2) == Some(&'!') {
+                out.extend(&chars[i..end]);
+            } else {
+                space = true;
+            }
+            i = end;
+            continue;
+        }
+        if c.is_whitespace() {
+            space = true;
+            i += 1;
+            continue;
+        }
+        if space && !out.is_empty() && css_needs_space(&out, c) {
+            out.push(' ');
+        }
+        space = false;
+        if c == '"' || c == '\'' {
+            let end = find_string_end(&chars, i);
+            out.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+        if c == '}' && out.ends_with(';') {
+            let _ = out.pop();
+        }
+        out.push(c);
+        i += 1;
+    }
+    out
+}
+
+/// Minifies JavaScript.
+///
+/// Drops comments and indentation and keeps one line break wherever
+/// the source had one, so automatic semicolon insertion reads the
+/// result the same way.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::minify::minify_js;
+///
+/// let js = "// greet\nfunction greet(name) {\n  return 'Hi ' + name; /* done */\n}\n";
+/// assert_eq!(minify_js(js), "function greet(name){\nreturn'Hi '+name;\n}");
+/// ```
+pub fn minify_js(js: &str) -> String {
+    let chars: Vec<char> = js.chars().collect();
+    let mut out = String::with_capacity(js.len());
+    let mut space = false;
+    let mut newline = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if c == '/' && next == Some('*') {
+            let end = find_comment_end(&chars, i + 2);
+            if chars.get(i + 2) == Some(&'!') {
+                out.extend(&chars[i..end]);
+            } else if chars[i..end].contains(&'\n') {
+                newline = true;
+            } else {
+                space = true;
+            }
+            i = end;
+            continue;
+        }
+        if c.is_whitespace() {
+            if c == '\n' {
+                newline = true;
+            } else {
+                space = true;
+            }
+            i += 1;
+            continue;
+        }
+        if newline && !out.is_empty() {
+            out.push('\n');
+        } else if space && js_needs_space(&out, c) {
+            out.push(' ');
+        }
+        space = false;
+        newline = false;
+        let end = match c {
+            '"' | '\'' | '`' => find_string_end(&chars, i),
+            '/' if js_starts_regex(&out) => find_regex_end(&chars, i),
+            _ => i + 1,
+        };
+        out.extend(&chars[i..end]);
+        i = end;
+    }
+    out
+}
+
+/// Minifies a JSON document, keeping its keys in their original order.
+///
+/// # Errors
+///
+/// Returns an error if `json` is not valid JSON.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::minify::minify_json;
+///
+/// let json = "{\n  \"b\": [1, 2],\n  \"a\": \"x y\"\n}";
+/// assert_eq!(minify_json(json).unwrap(), r#"{"b":[1,2],"a":"x y"}"#);
+/// assert!(minify_json("{").is_err());
+/// ```
+pub fn minify_json(json: &str) -> Result<String> {
+    let _: serde::de::IgnoredAny =
+        serde_json::from_str(json).context("Invalid JSON")?;
+    let chars: Vec<char> = json.chars().collect();
+    let mut out = String::with_capacity(json.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' {
+            let end = find_string_end(&chars, i);
+            out.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+        if !c.is_whitespace() {
+            out.push(c);
+        }
+        i += 1;
+    }
+    Ok(out)
+}
+
+/// Minifies an XML document such as a sitemap or feed.
+///
+/// Drops comments and whitespace between tags. Text and CDATA sections
+/// are kept as they are.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::minify::minify_xml;
+///
+/// let xml = "<?xml version=\"1.0\"?>\n<urlset>\n  <!-- pages -->\n  <url>\n    <loc>https://example.com/</loc>\n  </url>\n</urlset>\n";
+/// assert_eq!(
+///     minify_xml(xml),
+///     "<?xml version=\"1.0\"?><urlset><url><loc>https://example.com/</loc></url></urlset>"
+/// );
+/// ```
+pub fn minify_xml(xml: &str) -> String {
+    let mut out = String::with_capacity(xml.len());
+    let mut last = 0;
+    for block in XML_BLOCK_RE.find_iter(xml) {
+        out.push_str(
+            &XML_GAP_RE.replace_all(&xml[last..block.start()], "><"),
+        );
+        if block.as_str().starts_with("<![CDATA[") {
+            out.push_str(block.as_str());
+        }
+        last = block.end();
+    }
+    out.push_str(&XML_GAP_RE.replace_all(&xml[last..], "><"));
+    // Removing a comment can leave whitespace between the tags around it.
+    XML_GAP_RE.replace_all(out.trim(), "><").into_owned()
+}
+
+/// Minifies every file below `site_dir` whose format is switched on in
+/// `options`, in parallel, and returns the number of files rewritten.
+///
+/// Files that fail to minify, such as malformed JSON, are kept as they
+/// are with a warning.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or written.
+pub fn minify_site(
+    site_dir: &Path,
+    options: &MinifyOptions,
+) -> Result<usize> {
+    if !options.is_enabled() {
+        return Ok(0);
+    }
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    let warnings = WarningLog::new();
+    let rewritten = files
+        .par_iter()
+        .filter_map(|path| {
+            Format::from_path(path)
+                .filter(|format| options.minifies(*format))
+                .map(|format| (path, format))
+        })
+        .map(|(path, format)| {
+            warnings.scope(path, || minify_file(path, format))
+        })
+        .collect::<Result<Vec<bool>>>()?;
+    let _ = warnings.flush();
+    Ok(rewritten.into_iter().filter(|rewritten| *rewritten).count())
+}
+
+/// Minifies one file in place, returning `true` if it got smaller.
+fn minify_file(path: &Path, format: Format) -> Result<bool> {
+    let text = fs::read_to_string(path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    let minified = match minify(format, &text) {
+        Ok(minified) => minified,
+        Err(e) => {
+            diagnostics::warn(format!("Not minified: {:#}", e));
+            return Ok(false);
+        }
+    };
+    if minified.len() >= text.len() {
+        return Ok(false);
+    }
+    fs::write(path, minified).with_context(|| {
+        format!("Failed to write {}", path.display())
+    })?;
+    Ok(true)
+}
+
+/// Returns a `<pre>`, `<textarea>`, `<script>`, `<style>` or comment
+/// block of an HTML page, minified.
+fn minify_html_block(block: &Captures<'_>) -> String {
+    let whole = block.get(0).map_or("", |m| m.as_str());
+    if let (Some(attributes), Some(body)) = (block.get(1), block.get(2))
+    {
+        let kind = SCRIPT_TYPE_RE
+            .captures(attributes.as_str())
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str().to_ascii_lowercase());
+        let minified = match kind.as_deref() {
+            None | Some("module") | Some("text/javascript") => {
+                Some(minify_js(body.as_str()))
+            }
+            Some(kind) if kind.ends_with("json") => {
+                minify_json(body.as_str()).ok()
+            }
+            _ => None,
+        };
+        return match minified {
+            Some(minified) => format!(
+                "<script{}>{}</script>",
+                attributes.as_str(),
+                minified
+            ),
+            None => whole.to_string(),
+        };
+    }
+    if let (Some(attributes), Some(body)) = (block.get(3), block.get(4))
+    {
+        return format!(
+            "<style{}>{}</style>",
+            attributes.as_str(),
+            minify_css(body.as_str())
+        );
+    }
+    if whole.starts_with("<!--") && !whole.starts_with("<!--[if") {
+        return String::new();
+    }
+    whole.to_string()
+}
+
+/// Appends `html` to `out` with the whitespace in its text collapsed.
+fn collapse_html_text(html: &str, out: &mut String) {
+    let mut last = 0;
+    for tag in HTML_TAG_RE.find_iter(html) {
+        push_collapsed(&html[last..tag.start()], out);
+        out.push_str(tag.as_str());
+        last = tag.end();
+    }
+    push_collapsed(&html[last..], out);
+}
+
+/// Appends `text` to `out` with whitespace runs collapsed to one space,
+/// without doubling a space already at the end of `out`.
+fn push_collapsed(text: &str, out: &mut String) {
+    let collapsed = WHITESPACE_RE.replace_all(text, " ");
+    if out.ends_with(' ') {
+        out.push_str(collapsed.trim_start());
+    } else {
+        out.push_str(&collapsed);
+    }
+}
+
+/// Returns the index just past the `*/` closing the comment whose body
+/// starts at `start`, or the end of the input if it is unterminated.
+fn find_comment_end(chars: &[char], start: usize) -> usize {
+    let mut i = start;
+    while i + 1 < chars.len() {
+        if chars[i] == '*' && chars[i + 1] == '/' {
+            return i + 2;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+/// Returns the index just past the closing quote of the string starting
+/// at `start`, skipping escaped characters.
+fn find_string_end(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            c if c == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+/// Returns the index just past the JavaScript regular expression literal
+/// starting at `start`, including its flags.
+fn find_regex_end(chars: &[char], start: usize) -> usize {
+    let mut i = start + 1;
+    let mut class = false;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => class = true,
+            ']' => class = false,
+            '/' if !class => {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_alphabetic()
+                {
+                    i += 1;
+                }
+                return i;
+            }
+            '\n' => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+/// Returns `true` if whitespace between the end of `out` and `next` is
+/// significant in CSS.
+fn css_needs_space(out: &str, next: char) -> bool {
+    let separators = ['{', '}', ';', ',', '>', ':'];
+    let prev = out.chars().last().unwrap_or(' ');
+    !(out.ends_with("*/")
+        || separators.contains(&prev)
+        || (separators.contains(&next) && next != ':')
+        || next == '!')
+}
+
+/// Returns `true` if whitespace between the end of `out` and `next` is
+/// significant in JavaScript.
+fn js_needs_space(out: &str, next: char) -> bool {
+    let prev = match out.chars().last() {
+        Some(prev) => prev,
+        None => return false,
+    };
+    (is_js_word(prev) && is_js_word(next))
+        || (prev == next && matches!(next, '+' | '-'))
+        || (prev == '/' && next == '/')
+}
+
+/// Returns `true` if a `/` after `out` starts a regular expression
+/// rather than a division.
+fn js_starts_regex(out: &str) -> bool {
+    let prev = match out.chars().last() {
+        Some(prev) => prev,
+        None => return true,
+    };
+    if is_js_word(prev) {
+        let word_start = out
+            .rfind(|c: char| !is_js_word(c))
+            .map_or(0, |index| index + 1);
+        return JS_REGEX_KEYWORDS.contains(&&out[word_start..]);
+    }
+    !matches!(prev, ')' | ']' | '}' | '"' | '\'' | '`')
+}
+
+/// Returns `true` if `c` can be part of a JavaScript identifier or
+/// number.
+fn is_js_word(c: char) -> bool {
+    c.is_alphanumeric()
+        || matches!(c, '_' | '$' | '\\')
+        || !c.is_ascii()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_minify_html_keeps_preformatted_blocks() {
+        let html = "<html>\n  <head>\n    <style>\n      body { margin: 0; }\n    </style>\n    <script type=\"application/ld+json\">\n      { \"@type\": \"Article\" }\n    </script>\n    <script type=\"text/template\">\n      <p>  {{ x }}  </p>\n    </script>\n  </head>\n  <body>\n    <!--[if IE]><p>Old</p><![endif]-->\n    <p title=\"a  b\">Hello,\n      world</p>\n    <textarea>\n  keep </textarea>\n  </body>\n</html>\n";
+        let minified = minify_html(html);
+
+        assert!(minified.contains("<style>body{margin:0}</style>"));
+        assert!(minified.contains(
+            "<script type=\"application/ld+json\">{\"@type\":\"Article\"}</script>"
+        ));
+        assert!(minified.contains("\n      <p>  {{ x }}  </p>\n"));
+        assert!(minified.contains("<!--[if IE]>"));
+        assert!(minified.contains("<p title=\"a  b\">Hello, world</p>"));
+        assert!(minified.contains("<textarea>\n  keep </textarea>"));
+        assert!(!minified.contains("  <body>"));
+    }
+
+    #[test]
+    fn test_minify_css_keeps_significant_spaces() {
+        let css = "/*! MIT */\n@media (min-width: 40em) and (hover: hover) {\n  a :hover, b::after { margin: 0 auto !important; }\n}\n";
+        assert_eq!(
+            minify_css(css),
+            "/*! MIT */@media (min-width:40em) and (hover:hover){a :hover,b::after{margin:0 auto!important}}"
+        );
+    }
+
+    #[test]
+    fn test_minify_js_keeps_strings_and_regexes() {
+        let js = "const url = \"http://example.com\"; // home\nconst re = /\\/\\/[a-z/]+/g;\nconst t = `line\n    two`;\nlet a = b + +c;\nif (x) return /y/.test(s);\n";
+        assert_eq!(
+            minify_js(js),
+            "const url=\"http://example.com\";\nconst re=/\\/\\/[a-z/]+/g;\nconst t=`line\n    two`;\nlet a=b+ +c;\nif(x)return/y/.test(s);"
+        );
+    }
+
+    #[test]
+    fn test_minify_site_by_format() {
+        let dir = tempdir().unwrap();
+        let site = dir.path();
+        fs::write(site.join("index.html"), "<p>\n  Hi\n</p>\n")
+            .unwrap();
+        fs::write(site.join("style.css"), "a {\n  color: red;\n}\n")
+            .unwrap();
+        fs::write(site.join("app.min.js"), "a ;\n\n b ;\n").unwrap();
+        fs::write(site.join("bad.json"), "{ \"a\": ").unwrap();
+        fs::write(site.join("manifest.json"), "{ \"a\": 1 }").unwrap();
+        fs::write(
+            site.join("sitemap.xml"),
+            "<urlset>\n  <url/>\n</urlset>\n",
+        )
+        .unwrap();
+
+        let options = MinifyOptions {
+            css: true,
+            js: true,
+            json: true,
+            xml: true,
+            ..MinifyOptions::default()
+        };
+        assert_eq!(minify_site(site, &options).unwrap(), 3);
+
+        let read =
+            |name: &str| fs::read_to_string(site.join(name)).unwrap();
+        assert_eq!(read("index.html"), "<p>\n  Hi\n</p>\n");
+        assert_eq!(read("style.css"), "a{color:red}");
+        assert_eq!(read("app.min.js"), "a ;\n\n b ;\n");
+        assert_eq!(read("bad.json"), "{ \"a\": ");
+        assert_eq!(read("manifest.json"), "{\"a\":1}");
+        assert_eq!(read("sitemap.xml"), "<urlset><url/></urlset>");
+
+        assert_eq!(
+            minify_site(site, &MinifyOptions::default()).unwrap(),
+            0
+        );
+    }
+}
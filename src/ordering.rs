@@ -0,0 +1,417 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Feed and Sitemap Order
+//!
+//! Applies the [section order](crate::sections) to the feeds and the
+//! sitemap written by the core compiler, which list pages in whatever
+//! order the filesystem returned them.
+//!
+//! - Each `rss.xml` lists the pages under its directory in the order of
+//!   that section, keeping at most its `limit` items.
+//! - `sitemap.xml` lists the home page, then every other page in the
+//!   order of the root section, without a limit.
+//!
+//! Both leave out pages with `hidden: true` unless their section sets
+//! `include_hidden`. Entries that match no page keep their relative
+//! order after the others.
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    content::{ContentSet, Page},
+    locales::{sitemap_key, SITEMAP_LOC_RE},
+    page_meta::SITEMAP_ENTRY_RE,
+    sections::{is_index, page_entry, ListingOrder, SectionOptions},
+};
+
+/// Name of the RSS feed of a directory.
+pub const FEED_FILE: &str = "rss.xml";
+
+/// Name of the sitemap at the root of the site.
+pub const SITEMAP_FILE: &str = "sitemap.xml";
+
+/// An `<item>` of an RSS feed with the whitespace around it.
+static FEED_ITEM_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)[ \t]*<item\b[^>]*>(.*?)</item>[ \t]*\r?\n?")
+        .unwrap_or_else(|e| panic!("Invalid feed item regex: {}", e))
+});
+
+/// The `<link>` of an RSS item.
+static FEED_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<link>\s*([^<]*?)\s*</link>")
+        .unwrap_or_else(|e| panic!("Invalid feed link regex: {}", e))
+});
+
+/// The pages of a listing: their rank by URL key and the URL keys of
+/// those left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ranking {
+    /// Position of each listed page.
+    pub ranks: BTreeMap<String, usize>,
+    /// Pages left out of the listing.
+    pub hidden: BTreeSet<String>,
+    /// Most entries kept, `0` for all.
+    pub limit: usize,
+}
+
+impl Ranking {
+    /// Ranks the pages under the content directory `dir` by its order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the order of a section is invalid.
+    pub fn under(
+        content: &ContentSet,
+        options: &SectionOptions,
+        dir: &Path,
+    ) -> Result<Self> {
+        let order = dir_order(content, options, dir)?;
+        let mut orders = BTreeMap::new();
+        let mut entries = Vec::new();
+        let mut hidden = BTreeSet::new();
+        for page in content.iter().filter(|page| {
+            page.relative.starts_with(dir) && !is_own_index(page, dir)
+        }) {
+            let parent = page
+                .relative
+                .parent()
+                .map_or_else(PathBuf::new, Path::to_path_buf);
+            if !orders.contains_key(&parent) {
+                let _ = orders.insert(
+                    parent.clone(),
+                    dir_order(content, options, &parent)?,
+                );
+            }
+            if orders[&parent].includes(page) {
+                entries.push(page_entry(page));
+            } else {
+                let _ = hidden
+                    .insert(sitemap_key(&page.url_path()).to_string());
+            }
+        }
+        order.sort(&mut entries);
+        Ok(Self {
+            ranks: entries
+                .iter()
+                .enumerate()
+                .map(|(rank, entry)| {
+                    (sitemap_key(&entry.url).to_string(), rank)
+                })
+                .collect(),
+            hidden,
+            limit: order.limit,
+        })
+    }
+}
+
+/// Reorders the feeds and the sitemap in `site_dir` by the section
+/// order of `content`.
+///
+/// Returns the number of files rewritten.
+///
+/// # Errors
+///
+/// Returns an error if the order of a section is invalid or a file
+/// cannot be read or written.
+pub fn apply_listing_order(
+    content: &ContentSet,
+    options: &SectionOptions,
+    site_dir: &Path,
+    base_url: &str,
+) -> Result<usize> {
+    let dirs: BTreeSet<PathBuf> = content
+        .iter()
+        .filter_map(|page| page.relative.parent())
+        .flat_map(Path::ancestors)
+        .map(Path::to_path_buf)
+        .collect();
+    let mut rewritten = 0;
+    for dir in &dirs {
+        let feed = site_dir.join(dir).join(FEED_FILE);
+        if feed.is_file() {
+            let ranking = Ranking::under(content, options, dir)?;
+            if rewrite(&feed, |xml| {
+                reorder_entries(
+                    xml,
+                    &FEED_ITEM_RE,
+                    &FEED_LINK_RE,
+                    base_url,
+                    &ranking,
+                )
+            })? {
+                rewritten += 1;
+            }
+        }
+    }
+
+    let sitemap = site_dir.join(SITEMAP_FILE);
+    if sitemap.is_file() {
+        let mut ranking =
+            Ranking::under(content, options, Path::new(""))?;
+        ranking.limit = 0;
+        // The home page leads the sitemap.
+        for rank in ranking.ranks.values_mut() {
+            *rank += 1;
+        }
+        let _ = ranking.ranks.insert(sitemap_key("/").to_string(), 0);
+        if rewrite(&sitemap, |xml| {
+            reorder_entries(
+                xml,
+                &SITEMAP_ENTRY_RE,
+                &SITEMAP_LOC_RE,
+                base_url,
+                &ranking,
+            )
+        })? {
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Reorders the entries of `xml` matched by `entry_re` by the rank of
+/// the link `link_re` finds in them, drops the hidden ones and keeps at
+/// most `limit`. The XML is returned as it is if anything but
+/// whitespace separates the entries.
+pub fn reorder_entries(
+    xml: &str,
+    entry_re: &Regex,
+    link_re: &Regex,
+    base_url: &str,
+    ranking: &Ranking,
+) -> String {
+    let matches: Vec<(regex::Match<'_>, &str)> = entry_re
+        .captures_iter(xml)
+        .filter_map(|caps| Some((caps.get(0)?, caps.get(1)?.as_str())))
+        .collect();
+    let (start, end) = match (matches.first(), matches.last()) {
+        (Some((first, _)), Some((last, _))) => {
+            (first.start(), last.end())
+        }
+        _ => return xml.to_string(),
+    };
+    if matches.windows(2).any(|pair| {
+        !xml[pair[0].0.end()..pair[1].0.start()].trim().is_empty()
+    }) {
+        return xml.to_string();
+    }
+
+    let base = base_url.trim_end_matches('/');
+    let mut entries = Vec::with_capacity(matches.len());
+    for (position, (entry, inner)) in matches.iter().enumerate() {
+        let link = link_re
+            .captures(inner)
+            .map(|link| link[1].replace("&amp;", "&"))
+            .unwrap_or_default();
+        let link =
+            sitemap_key(link.strip_prefix(base).unwrap_or(&link));
+        if ranking.hidden.contains(link) {
+            continue;
+        }
+        let rank =
+            ranking.ranks.get(link).copied().unwrap_or(usize::MAX);
+        entries.push((rank, position, entry.as_str()));
+    }
+    entries.sort_unstable();
+    if ranking.limit > 0 {
+        entries.truncate(ranking.limit);
+    }
+
+    let mut updated = String::with_capacity(xml.len());
+    updated.push_str(&xml[..start]);
+    for (_, _, entry) in entries {
+        updated.push_str(entry);
+    }
+    updated.push_str(&xml[end..]);
+    updated
+}
+
+/// Returns the order of the content directory `dir`.
+fn dir_order(
+    content: &ContentSet,
+    options: &SectionOptions,
+    dir: &Path,
+) -> Result<ListingOrder> {
+    let index = content.iter().find(|page| is_own_index(page, dir));
+    options.order(dir, index)
+}
+
+/// Returns `true` if `page` is the `index.md` of `dir`.
+fn is_own_index(page: &Page, dir: &Path) -> bool {
+    is_index(page) && page.relative.parent() == Some(dir)
+}
+
+/// Applies `update` to the file at `path`, writing it back if it
+/// changed.
+fn rewrite(
+    path: &Path,
+    update: impl FnOnce(&str) -> String,
+) -> Result<bool> {
+    let xml = fs::read_to_string(path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    let updated = update(&xml);
+    if updated == xml {
+        return Ok(false);
+    }
+    fs::write(path, updated).with_context(|| {
+        format!("Failed to write {}", path.display())
+    })?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sections::{SectionOrder, SectionSort};
+    use tempfile::tempdir;
+
+    fn page(relative: &str, frontmatter: &str) -> Page {
+        Page::from_source(
+            PathBuf::from("content").join(relative),
+            PathBuf::from(relative),
+            &format!("---\n{}\n---\n", frontmatter),
+        )
+        .unwrap()
+    }
+
+    fn content() -> ContentSet {
+        ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("index.md", "title: Home"),
+                page("blog/index.md", "title: Blog\nlimit: 2"),
+                page("blog/a.md", "title: A\ndate: 2025-01-01"),
+                page("blog/b.md", "title: B\ndate: 2025-02-01"),
+                page("blog/c.md", "title: C\ndate: 2025-03-01"),
+                page("blog/secret.md", "title: Secret\nhidden: true"),
+            ],
+        }
+    }
+
+    fn feed(slugs: &[&str]) -> String {
+        let items: String = slugs
+            .iter()
+            .map(|slug| {
+                format!(
+                    "    <item>\n      <link>https://example.com/blog/{}/</link>\n    </item>\n",
+                    slug
+                )
+            })
+            .collect();
+        format!("<rss><channel>\n{}</channel></rss>\n", items)
+    }
+
+    #[test]
+    fn test_feeds_follow_section_order() -> Result<()> {
+        let site = tempdir()?;
+        fs::create_dir_all(site.path().join("blog"))?;
+        let path = site.path().join("blog").join(FEED_FILE);
+        fs::write(&path, feed(&["a", "secret", "c", "b"]))?;
+
+        let rewritten = apply_listing_order(
+            &content(),
+            &SectionOptions::default(),
+            site.path(),
+            "https://example.com",
+        )?;
+        assert_eq!(rewritten, 1);
+        assert_eq!(fs::read_to_string(&path)?, feed(&["c", "b"]));
+
+        let mut options = SectionOptions::default();
+        let _ = options.dirs.insert(
+            "blog".to_string(),
+            SectionOrder {
+                sort: Some(SectionSort::Title),
+                include_hidden: Some(true),
+                ..SectionOrder::default()
+            },
+        );
+        fs::write(&path, feed(&["secret", "missing", "b"]))?;
+        let _ = apply_listing_order(
+            &content(),
+            &options,
+            site.path(),
+            "https://example.com/",
+        )?;
+        assert_eq!(fs::read_to_string(&path)?, feed(&["b", "secret"]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sitemap_lists_every_visible_page() -> Result<()> {
+        let site = tempdir()?;
+        let path = site.path().join(SITEMAP_FILE);
+        let entry = |url: &str| {
+            format!(
+                "  <url><loc>https://example.com{}</loc></url>\n",
+                url
+            )
+        };
+        let sitemap = |urls: &[&str]| {
+            format!(
+                "<urlset>\n{}</urlset>\n",
+                urls.iter().map(|url| entry(url)).collect::<String>()
+            )
+        };
+        fs::write(
+            &path,
+            sitemap(&[
+                "/",
+                "/blog/a/",
+                "/blog/secret/",
+                "/blog/c/",
+                "/blog/b/",
+            ]),
+        )?;
+        let _ = apply_listing_order(
+            &content(),
+            &SectionOptions::default(),
+            site.path(),
+            "https://example.com",
+        )?;
+        assert_eq!(
+            fs::read_to_string(&path)?,
+            sitemap(&["/", "/blog/c/", "/blog/b/", "/blog/a/"])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reorder_keeps_interleaved_xml() {
+        let xml = "<item><link>/b/</link></item><!-- note --><item><link>/a/</link></item>";
+        let ranking = Ranking {
+            ranks: [("/a".to_string(), 0), ("/b".to_string(), 1)]
+                .into_iter()
+                .collect(),
+            ..Ranking::default()
+        };
+        assert_eq!(
+            reorder_entries(
+                xml,
+                &FEED_ITEM_RE,
+                &FEED_LINK_RE,
+                "",
+                &ranking
+            ),
+            xml
+        );
+        let xml = xml.replace("<!-- note -->", "\n");
+        assert!(reorder_entries(
+            &xml,
+            &FEED_ITEM_RE,
+            &FEED_LINK_RE,
+            "",
+            &ranking
+        )
+        .starts_with("<item><link>/a/</link></item>"));
+    }
+}
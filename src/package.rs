@@ -0,0 +1,360 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Output Archives
+//!
+//! Backs `ssg package --format zip|tar.gz`, which packs the built site
+//! into a single archive for handing a build over or attaching it to a
+//! release.
+//!
+//! Archives are reproducible: entries are written in path order with
+//! `/` separators, fixed permissions and no owner, and every entry gets
+//! the same timestamp. The timestamp is taken from `SOURCE_DATE_EPOCH`
+//! when it is set, as in other reproducible-build tools, and is
+//! 1980-01-01 otherwise, the earliest date a zip archive can hold.
+//! Packing the same site twice gives byte-identical archives.
+
+use anyhow::{anyhow, bail, Context, Result};
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    env, fmt,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{collect_files_recursive, dates::Date};
+
+/// Environment variable holding the timestamp of reproducible builds,
+/// in seconds since the Unix epoch.
+pub const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+/// Timestamp used when `SOURCE_DATE_EPOCH` is not set: 1980-01-01.
+pub const DEFAULT_EPOCH: u64 = 315_532_800;
+
+/// Permissions recorded for every file.
+const FILE_MODE: u32 = 0o644;
+
+/// Seconds in a day.
+const DAY_SECS: u64 = 86_400;
+
+/// Archive format of `ssg package`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// A zip archive with deflated entries.
+    Zip,
+    /// A gzip-compressed tar archive.
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Returns the file extension of the format, without a leading dot.
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::TarGz => "tar.gz",
+        }
+    }
+}
+
+impl fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "zip" => Ok(Self::Zip),
+            "tar.gz" | "tgz" => Ok(Self::TarGz),
+            other => bail!(
+                "Unknown archive format '{}', expected zip or tar.gz",
+                other
+            ),
+        }
+    }
+}
+
+/// What [`package`] wrote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageSummary {
+    /// Path of the archive.
+    pub archive: PathBuf,
+    /// Number of files packed.
+    pub files: usize,
+    /// Size of the archive in bytes.
+    pub bytes: u64,
+}
+
+/// Returns the timestamp given to archive entries, from
+/// `SOURCE_DATE_EPOCH` or [`DEFAULT_EPOCH`].
+///
+/// # Errors
+///
+/// Returns an error if `SOURCE_DATE_EPOCH` is set but is not a number of
+/// seconds.
+pub fn source_date_epoch() -> Result<u64> {
+    match env::var(SOURCE_DATE_EPOCH) {
+        Ok(value) => value.trim().parse().with_context(|| {
+            format!("{} must be a number of seconds", SOURCE_DATE_EPOCH)
+        }),
+        Err(_) => Ok(DEFAULT_EPOCH),
+    }
+}
+
+/// Returns the default archive path for `site_dir`: its directory name
+/// with the format's extension, in the current directory.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::package::{default_archive_path, ArchiveFormat};
+/// use std::path::{Path, PathBuf};
+///
+/// assert_eq!(
+///     default_archive_path(Path::new("./public"), ArchiveFormat::TarGz),
+///     PathBuf::from("public.tar.gz")
+/// );
+/// ```
+pub fn default_archive_path(
+    site_dir: &Path,
+    format: ArchiveFormat,
+) -> PathBuf {
+    let name = site_dir
+        .file_name()
+        .map_or_else(|| "site".into(), |name| name.to_string_lossy());
+    PathBuf::from(format!("{}.{}", name, format.extension()))
+}
+
+/// Packs every file below `site_dir` into `archive`, with entries
+/// timestamped `epoch` seconds after the Unix epoch.
+///
+/// # Errors
+///
+/// Returns an error if the site cannot be read, the archive cannot be
+/// written, or the archive would be written inside the site.
+pub fn package(
+    site_dir: &Path,
+    archive: &Path,
+    format: ArchiveFormat,
+    epoch: u64,
+) -> Result<PackageSummary> {
+    if !site_dir.is_dir() {
+        bail!(
+            "{} does not exist; build the site before packaging it",
+            site_dir.display()
+        );
+    }
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    let mut entries = files
+        .iter()
+        .map(|path| Ok((entry_name(site_dir, path)?, path.as_path())))
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort();
+
+    let parent = archive
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let inside_site = parent.starts_with(site_dir)
+        || matches!(
+            (fs::canonicalize(parent), fs::canonicalize(site_dir)),
+            (Ok(parent), Ok(site)) if parent.starts_with(&site)
+        );
+    if inside_site {
+        bail!(
+            "The archive {} cannot be written inside the site",
+            archive.display()
+        );
+    }
+    fs::create_dir_all(parent).with_context(|| {
+        format!("Failed to create {}", parent.display())
+    })?;
+    let file = File::create(archive).with_context(|| {
+        format!("Failed to create {}", archive.display())
+    })?;
+    match format {
+        ArchiveFormat::Zip => write_zip(file, &entries, epoch),
+        ArchiveFormat::TarGz => write_tar_gz(file, &entries, epoch),
+    }
+    .with_context(|| {
+        format!("Failed to write {}", archive.display())
+    })?;
+
+    let bytes = fs::metadata(archive)
+        .with_context(|| {
+            format!("Failed to read {}", archive.display())
+        })?
+        .len();
+    Ok(PackageSummary {
+        archive: archive.to_path_buf(),
+        files: entries.len(),
+        bytes,
+    })
+}
+
+/// Returns the archive entry name of `path`, relative to `site_dir`
+/// with `/` separators.
+fn entry_name(site_dir: &Path, path: &Path) -> Result<String> {
+    let relative = path.strip_prefix(site_dir).with_context(|| {
+        format!("{} is outside {}", path.display(), site_dir.display())
+    })?;
+    let segments: Vec<_> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    Ok(segments.join("/"))
+}
+
+fn write_zip(
+    file: File,
+    entries: &[(String, &Path)],
+    epoch: u64,
+) -> Result<()> {
+    let Date { year, month, day } =
+        Date::from_days((epoch / DAY_SECS) as i64);
+    let seconds = epoch % DAY_SECS;
+    let modified = zip::DateTime::from_date_and_time(
+        u16::try_from(year).context("Timestamp out of range")?,
+        month as u8,
+        day as u8,
+        (seconds / 3600) as u8,
+        (seconds / 60 % 60) as u8,
+        (seconds % 60) as u8,
+    )
+    .map_err(|_| {
+        anyhow!("Zip archives cannot hold dates before 1980")
+    })?;
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(modified)
+        .unix_permissions(FILE_MODE);
+
+    let mut zip = zip::ZipWriter::new(BufWriter::new(file));
+    for (name, path) in entries {
+        let data = fs::read(path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(&data)?;
+    }
+    zip.finish()?.flush()?;
+    Ok(())
+}
+
+fn write_tar_gz(
+    file: File,
+    entries: &[(String, &Path)],
+    epoch: u64,
+) -> Result<()> {
+    let encoder =
+        GzEncoder::new(BufWriter::new(file), Compression::best());
+    let mut tar = tar::Builder::new(encoder);
+    for (name, path) in entries {
+        let data = fs::read(path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let mut header = tar::Header::new_ustar();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(data.len() as u64);
+        header.set_mode(FILE_MODE);
+        header.set_mtime(epoch);
+        header.set_uid(0);
+        header.set_gid(0);
+        tar.append_data(&mut header, name, data.as_slice())?;
+    }
+    tar.into_inner()?.finish()?.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use tempfile::tempdir;
+
+    fn site(root: &Path) -> Result<PathBuf> {
+        let site = root.join("public");
+        fs::create_dir_all(site.join("blog/post"))?;
+        fs::write(site.join("index.html"), "<p>Home</p>")?;
+        fs::write(site.join("blog/post/index.html"), "<p>Post</p>")?;
+        fs::write(site.join("style.css"), "body{}")?;
+        Ok(site)
+    }
+
+    #[test]
+    fn test_package_is_reproducible() -> Result<()> {
+        let dir = tempdir()?;
+        let site = site(dir.path())?;
+        for format in [ArchiveFormat::Zip, ArchiveFormat::TarGz] {
+            let first = dir.path().join(format!("a.{}", format));
+            let second = dir.path().join(format!("b.{}", format));
+            let summary =
+                package(&site, &first, format, DEFAULT_EPOCH)?;
+            assert_eq!(summary.files, 3);
+            fs::write(site.join("style.css"), "body{}")?;
+            let _ = package(&site, &second, format, DEFAULT_EPOCH)?;
+            assert_eq!(
+                fs::read(&first)?,
+                fs::read(&second)?,
+                "{}",
+                format
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_tar_gz_entries_are_sorted() -> Result<()> {
+        let dir = tempdir()?;
+        let site = site(dir.path())?;
+        let archive = dir.path().join("site.tar.gz");
+        let _ = package(
+            &site,
+            &archive,
+            ArchiveFormat::TarGz,
+            1_700_000_000,
+        )?;
+
+        let mut tar =
+            tar::Archive::new(GzDecoder::new(File::open(&archive)?));
+        let mut names = Vec::new();
+        for entry in tar.entries()? {
+            let entry = entry?;
+            assert_eq!(entry.header().mtime()?, 1_700_000_000);
+            names.push(entry.path()?.to_string_lossy().into_owned());
+        }
+        assert_eq!(
+            names,
+            ["blog/post/index.html", "index.html", "style.css"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_package_rejects_archive_inside_site() -> Result<()> {
+        let dir = tempdir()?;
+        let site = site(dir.path())?;
+        let archive = site.join("site.zip");
+        assert!(package(
+            &site,
+            &archive,
+            ArchiveFormat::Zip,
+            DEFAULT_EPOCH
+        )
+        .is_err());
+        assert!("rar".parse::<ArchiveFormat>().is_err());
+        assert_eq!(
+            "tgz".parse::<ArchiveFormat>()?,
+            ArchiveFormat::TarGz
+        );
+        Ok(())
+    }
+}
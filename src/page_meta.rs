@@ -0,0 +1,408 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Canonical URLs and Meta Robots
+//!
+//! Adds a `<link rel="canonical">` and a `<meta name="robots">` tag to
+//! pages that set them in their frontmatter:
+//!
+//! ```yaml
+//! canonical: https://partner.example.com/original-post/
+//! robots: noindex, nofollow
+//! ```
+//!
+//! `canonical` is an absolute URL, or a site-relative path such as
+//! `/blog/original/` resolved against the base URL. It points search
+//! engines at the original of syndicated content. `robots` takes the
+//! directives in [`ROBOTS_DIRECTIVES`], as a list or comma-separated.
+//! Tags the template already wrote for the same purpose are replaced.
+//!
+//! Pages with `noindex` or `none` are left out of `sitemap.xml`, so
+//! staging-only pages are not submitted to search engines. Pages
+//! protected with a password are always `noindex`.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{collections::BTreeSet, fs, path::Path};
+use url::Url;
+
+use crate::{
+    content::{ContentSet, Page},
+    locales::{sitemap_key, SITEMAP_LOC_RE},
+    protect,
+};
+
+/// Directives accepted in `robots` frontmatter.
+pub const ROBOTS_DIRECTIVES: &[&str] = &[
+    "all",
+    "index",
+    "noindex",
+    "follow",
+    "nofollow",
+    "none",
+    "noarchive",
+    "nosnippet",
+    "noimageindex",
+    "notranslate",
+];
+
+/// Existing canonical links.
+static CANONICAL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)[ \t]*<link\s+[^>]*rel\s*=\s*["']canonical["'][^>]*>[ \t]*\r?\n?"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid canonical regex: {}", e))
+});
+
+/// Existing robots meta tags.
+static ROBOTS_META_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)[ \t]*<meta\s+name\s*=\s*["']robots["'][^>]*>[ \t]*\r?\n?"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid robots meta regex: {}", e))
+});
+
+/// A `<url>` entry of a sitemap with the whitespace around it.
+pub(crate) static SITEMAP_ENTRY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)[ \t]*<url>(.*?)</url>[ \t]*\r?\n?")
+        .unwrap_or_else(|e| {
+            panic!("Invalid sitemap entry regex: {}", e)
+        })
+});
+
+/// The canonical URL and robots directives of one page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageMeta {
+    /// Absolute canonical URL.
+    pub canonical: Option<String>,
+    /// Robots directives, lowercase, in frontmatter order.
+    pub robots: Vec<String>,
+}
+
+impl PageMeta {
+    /// Reads the `canonical` and `robots` frontmatter of `page`,
+    /// resolving a site-relative canonical path against `base_url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the page if `canonical` is neither an
+    /// absolute `http` or `https` URL nor a path starting with `/`, or
+    /// `robots` has an unknown directive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::{content::Page, page_meta::PageMeta};
+    /// use std::path::PathBuf;
+    ///
+    /// let page = Page::from_source(
+    ///     PathBuf::from("draft.md"),
+    ///     PathBuf::from("draft.md"),
+    ///     "---\ncanonical: /blog/original/\nrobots: NoIndex, follow\n---\n",
+    /// )
+    /// .unwrap();
+    /// let meta = PageMeta::from_page(&page, "https://example.com/").unwrap();
+    /// assert_eq!(
+    ///     meta.canonical.as_deref(),
+    ///     Some("https://example.com/blog/original/")
+    /// );
+    /// assert!(meta.is_noindex());
+    /// ```
+    pub fn from_page(page: &Page, base_url: &str) -> Result<Self> {
+        let canonical = match page.frontmatter.get_str("canonical") {
+            Some(url) => {
+                Some(canonical_url(url.trim(), base_url).with_context(
+                    || {
+                        format!(
+                            "Invalid canonical in {}",
+                            page.source.display()
+                        )
+                    },
+                )?)
+            }
+            None => None,
+        };
+        let robots = robots_directives(page);
+        if let Some(unknown) = robots.iter().find(|directive| {
+            !ROBOTS_DIRECTIVES.contains(&directive.as_str())
+        }) {
+            bail!(
+                "Unknown robots directive `{}` in {}; expected {}",
+                unknown,
+                page.source.display(),
+                ROBOTS_DIRECTIVES.join(", ")
+            );
+        }
+        Ok(Self { canonical, robots })
+    }
+
+    /// Returns `true` if search engines may not index the page.
+    pub fn is_noindex(&self) -> bool {
+        self.robots.iter().any(|directive| {
+            matches!(directive.as_str(), "noindex" | "none")
+        })
+    }
+
+    /// Renders the tags, one per line.
+    pub fn to_tags(&self) -> String {
+        let mut tags = String::new();
+        if let Some(canonical) = &self.canonical {
+            tags.push_str(&format!(
+                "<link rel=\"canonical\" href=\"{}\" />\n",
+                canonical.replace('&', "&amp;").replace('"', "&quot;")
+            ));
+        }
+        if !self.robots.is_empty() {
+            tags.push_str(&format!(
+                "<meta name=\"robots\" content=\"{}\" />\n",
+                self.robots.join(", ")
+            ));
+        }
+        tags
+    }
+}
+
+/// Returns `true` if `page` has a `noindex` or `none` robots
+/// directive.
+pub fn is_noindex(page: &Page) -> bool {
+    PageMeta {
+        canonical: None,
+        robots: robots_directives(page),
+    }
+    .is_noindex()
+}
+
+/// Returns `true` if `html` already has a canonical link.
+pub fn has_canonical(html: &str) -> bool {
+    CANONICAL_RE.is_match(html)
+}
+
+/// Replaces the tags of `html` that `meta` sets with its own, inserted
+/// before `</head>`. Returns `None` if the document has no head.
+pub fn insert_page_meta(html: &str, meta: &PageMeta) -> Option<String> {
+    let mut html = html.to_string();
+    if meta.canonical.is_some() {
+        html = CANONICAL_RE.replace_all(&html, "").into_owned();
+    }
+    if !meta.robots.is_empty() {
+        html = ROBOTS_META_RE.replace_all(&html, "").into_owned();
+    }
+    let head = html.to_ascii_lowercase().find("</head>")?;
+    html.insert_str(head, &meta.to_tags());
+    Some(html)
+}
+
+/// Adds the canonical and robots tags of every page of `content` to its
+/// generated file in `site_dir`, and removes noindexed pages from
+/// `sitemap.xml`. Returns the number of pages rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a page's frontmatter is invalid or a file cannot
+/// be read or written.
+pub fn write_page_meta(
+    content: &ContentSet,
+    site_dir: &Path,
+    base_url: &str,
+) -> Result<usize> {
+    let mut rewritten = 0;
+    let mut noindex = BTreeSet::new();
+    let base = base_url.trim_end_matches('/');
+    for page in content.iter() {
+        let meta = PageMeta::from_page(page, base_url)?;
+        if meta.is_noindex() {
+            let _ =
+                noindex.insert(format!("{}{}", base, page.url_path()));
+        }
+        let path = site_dir.join(page.output_path());
+        if meta == PageMeta::default() || !path.is_file() {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        if let Some(updated) = insert_page_meta(&html, &meta) {
+            fs::write(&path, updated).with_context(|| {
+                format!("Failed to write {}", path.display())
+            })?;
+            rewritten += 1;
+        }
+    }
+
+    let sitemap = site_dir.join("sitemap.xml");
+    if !noindex.is_empty() && sitemap.is_file() {
+        let xml = fs::read_to_string(&sitemap).with_context(|| {
+            format!("Failed to read {}", sitemap.display())
+        })?;
+        let updated = remove_sitemap_urls(&xml, &noindex);
+        if updated != xml {
+            fs::write(&sitemap, updated).with_context(|| {
+                format!("Failed to write {}", sitemap.display())
+            })?;
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Removes the `<url>` entries of a sitemap whose `<loc>` is in `urls`.
+pub fn remove_sitemap_urls(
+    xml: &str,
+    urls: &BTreeSet<String>,
+) -> String {
+    let keys: BTreeSet<&str> =
+        urls.iter().map(|url| sitemap_key(url)).collect();
+    SITEMAP_ENTRY_RE
+        .replace_all(xml, |caps: &regex::Captures<'_>| {
+            let excluded = SITEMAP_LOC_RE.captures(&caps[1]).map_or(
+                false,
+                |loc| {
+                    keys.contains(sitemap_key(
+                        &loc[1].replace("&amp;", "&"),
+                    ))
+                },
+            );
+            if excluded {
+                String::new()
+            } else {
+                caps[0].to_string()
+            }
+        })
+        .into_owned()
+}
+
+/// Returns the lowercase `robots` directives of `page`.
+fn robots_directives(page: &Page) -> Vec<String> {
+    let mut directives: Vec<String> = page
+        .frontmatter
+        .get_list("robots")
+        .into_iter()
+        .map(|directive| directive.to_ascii_lowercase())
+        .collect();
+    if protect::is_protected(page)
+        && !directives.iter().any(|directive| directive == "noindex")
+    {
+        directives.push("noindex".to_string());
+    }
+    directives
+}
+
+/// Resolves a `canonical` value against `base_url`.
+fn canonical_url(url: &str, base_url: &str) -> Result<String> {
+    if url.starts_with('/') && !url.starts_with("//") {
+        return Ok(format!(
+            "{}{}",
+            base_url.trim_end_matches('/'),
+            url
+        ));
+    }
+    match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {
+            Ok(url.to_string())
+        }
+        _ => bail!(
+            "`{}` must be an absolute http or https URL or a path starting with `/`",
+            url
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn page(path: &str, raw: &str) -> Page {
+        Page::from_source(PathBuf::from(path), PathBuf::from(path), raw)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_page_meta_from_frontmatter() {
+        let syndicated = page(
+            "post.md",
+            "---\ncanonical: https://partner.example.com/post/\nrobots:\n  - NOFOLLOW\n---\n",
+        );
+        let meta =
+            PageMeta::from_page(&syndicated, "https://example.com")
+                .unwrap();
+        assert_eq!(
+            meta.canonical.as_deref(),
+            Some("https://partner.example.com/post/")
+        );
+        assert_eq!(meta.robots, ["nofollow"]);
+        assert!(!meta.is_noindex());
+        assert_eq!(
+            meta.to_tags(),
+            "<link rel=\"canonical\" href=\"https://partner.example.com/post/\" />\n<meta name=\"robots\" content=\"nofollow\" />\n"
+        );
+
+        for raw in [
+            "---\ncanonical: partner.example.com/post/\n---\n",
+            "---\ncanonical: //partner.example.com/post/\n---\n",
+            "---\nrobots: noindex, hide\n---\n",
+        ] {
+            let error = PageMeta::from_page(&page("bad.md", raw), "/")
+                .unwrap_err();
+            assert!(
+                format!("{:#}", error).contains("bad.md"),
+                "{:#}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_page_meta() -> Result<()> {
+        let temp_dir = tempdir()?;
+        fs::create_dir_all(temp_dir.path().join("staging"))?;
+        fs::write(
+            temp_dir.path().join("staging/index.html"),
+            "<html><head>\n<link rel=\"canonical\" href=\"https://example.com/staging/\">\n<meta name=\"robots\" content=\"index\">\n</head></html>",
+        )?;
+        fs::write(
+            temp_dir.path().join("index.html"),
+            "<html><head><link rel=\"canonical\" href=\"https://example.com/\"></head></html>",
+        )?;
+        fs::write(
+            temp_dir.path().join("sitemap.xml"),
+            "<urlset>\n  <url>\n    <loc>https://example.com/</loc>\n  </url>\n  <url>\n    <loc>https://example.com/staging/index.html</loc>\n  </url>\n</urlset>\n",
+        )?;
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("index.md", "Home"),
+                page(
+                    "staging.md",
+                    "---\ncanonical: /live/\nrobots: noindex\n---\n",
+                ),
+            ],
+        };
+
+        assert_eq!(
+            write_page_meta(
+                &content,
+                temp_dir.path(),
+                "https://example.com/"
+            )?,
+            1
+        );
+        let staging = fs::read_to_string(
+            temp_dir.path().join("staging/index.html"),
+        )?;
+        assert_eq!(
+            staging,
+            "<html><head>\n<link rel=\"canonical\" href=\"https://example.com/live/\" />\n<meta name=\"robots\" content=\"noindex\" />\n</head></html>"
+        );
+        assert!(fs::read_to_string(
+            temp_dir.path().join("index.html")
+        )?
+        .contains("href=\"https://example.com/\""));
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("sitemap.xml"))?,
+            "<urlset>\n  <url>\n    <loc>https://example.com/</loc>\n  </url>\n</urlset>\n"
+        );
+        Ok(())
+    }
+}
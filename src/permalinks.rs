@@ -0,0 +1,638 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Permalinks
+//!
+//! Decouples page URLs from the layout of the content directory. A
+//! permalink template such as `/:year/:month/:slug/` is expanded for
+//! every page into its `url_path` (see [`crate::content`]), and the
+//! compiled pages are moved to match.
+//!
+//! Templates are set for the whole site and per section, the first
+//! directory below the content directory:
+//!
+//! ```toml
+//! [permalinks]
+//! default = "/:slug/"
+//!
+//! [permalinks.sections]
+//! blog = "/:year/:month/:slug/"
+//! ```
+//!
+//! | Token       | Value |
+//! |-------------|-------|
+//! | `:year`     | Four-digit year of the `date` frontmatter |
+//! | `:month`    | Two-digit month of the `date` frontmatter |
+//! | `:day`      | Two-digit day of the `date` frontmatter |
+//! | `:slug`     | The `slug` frontmatter, or the title made into a slug |
+//! | `:section`  | The page's section, empty for top-level pages |
+//! | `:filename` | The file name without its extension |
+//!
+//! Slugs are transliterated to ASCII where there is a common spelling
+//! (`Crème brûlée` becomes `creme-brulee`, `Straße` becomes `strasse`);
+//! letters of other scripts are kept as they are.
+//!
+//! Section `index.md` pages and pages with their own `url_path` or
+//! `route` keep their URL, and pages with a `route` are moved to it. Two
+//! pages expanding or routed to the same URL fail the build.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    content::{ContentSet, Page, ROUTE_KEY, URL_PATH_KEY},
+    dates::Date,
+    template::slugify,
+};
+
+/// Frontmatter key overriding the slug made from the title.
+pub const SLUG_KEY: &str = "slug";
+
+/// Tokens a permalink template may contain.
+const TOKENS: &[&str] =
+    &[":year", ":month", ":day", ":slug", ":section", ":filename"];
+
+/// Matches a template token.
+static TOKEN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r":[a-z]+")
+        .unwrap_or_else(|e| panic!("Invalid token regex: {}", e))
+});
+
+/// `[permalinks]` options.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct PermalinkOptions {
+    /// Template for pages whose section has none; `None` keeps the
+    /// content directory layout.
+    pub default: Option<String>,
+    /// Templates by section name.
+    pub sections: BTreeMap<String, String>,
+}
+
+impl PermalinkOptions {
+    /// Checks that every template is a site-relative path using only
+    /// known tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first invalid template.
+    pub fn validate(&self) -> Result<()> {
+        let templates = self
+            .default
+            .iter()
+            .map(|template| ("default", template))
+            .chain(
+                self.sections
+                    .iter()
+                    .map(|(name, template)| (name.as_str(), template)),
+            );
+        for (name, template) in templates {
+            if !template.starts_with('/') {
+                bail!(
+                    "Permalink template '{}' for {} must start with '/'",
+                    template,
+                    name
+                );
+            }
+            if template.split('/').any(|s| s == "." || s == "..") {
+                bail!(
+                    "Permalink template '{}' for {} must not contain '.' or '..' segments",
+                    template,
+                    name
+                );
+            }
+            if let Some(token) = TOKEN_RE
+                .find_iter(template)
+                .map(|m| m.as_str())
+                .find(|token| !TOKENS.contains(token))
+            {
+                bail!(
+                    "Unknown token '{}' in permalink template '{}' for {}; expected one of {}",
+                    token,
+                    template,
+                    name,
+                    TOKENS.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the template for `page`, if any.
+    pub fn template_for(&self, page: &Page) -> Option<&str> {
+        page.section()
+            .and_then(|section| self.sections.get(&section))
+            .or(self.default.as_ref())
+            .map(String::as_str)
+    }
+
+    /// Returns the URL `page` gets from its template, or `None` if it
+    /// keeps its URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template uses a date token and the page
+    /// has no readable `date`.
+    pub fn expand(&self, page: &Page) -> Result<Option<String>> {
+        let template = match self.template_for(page) {
+            Some(template) => template,
+            None => return Ok(None),
+        };
+        if page.route().is_some()
+            || page.custom_url().is_some()
+            || is_index(page)
+        {
+            return Ok(None);
+        }
+
+        let date = page.frontmatter.get_str("date");
+        let ymd = date.as_deref().and_then(Date::parse);
+        let needs_date = [":year", ":month", ":day"]
+            .iter()
+            .any(|token| template.contains(token));
+        let Date { year, month, day } = match (needs_date, ymd) {
+            (_, Some(ymd)) => ymd,
+            (false, None) => Date::from_days(0),
+            (true, None) => bail!(
+                "{} needs a date for permalink '{}' but has {}",
+                page.relative.display(),
+                template,
+                date.map_or_else(
+                    || "none".to_string(),
+                    |date| format!("'{}'", date)
+                )
+            ),
+        };
+
+        let filename = page
+            .relative
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let slug = page
+            .frontmatter
+            .get_str(SLUG_KEY)
+            .map(|slug| slug_from(&slug))
+            .filter(|slug| !slug.is_empty())
+            .unwrap_or_else(|| {
+                let title = slug_from(&page.title());
+                if title.is_empty() {
+                    slug_from(&filename)
+                } else {
+                    title
+                }
+            });
+
+        let url = TOKEN_RE.replace_all(
+            template,
+            |caps: &regex::Captures<'_>| match &caps[0] {
+                ":year" => format!("{:04}", year),
+                ":month" => format!("{:02}", month),
+                ":day" => format!("{:02}", day),
+                ":slug" => slug.clone(),
+                ":section" => page.section().unwrap_or_default(),
+                ":filename" => filename.clone(),
+                other => other.to_string(),
+            },
+        );
+
+        // Collapse the empty segments left by an empty `:section`.
+        let mut normalised = String::from("/");
+        for segment in url.split('/').filter(|s| !s.is_empty()) {
+            normalised.push_str(segment);
+            normalised.push('/');
+        }
+        if !url.ends_with('/') && normalised.len() > 1 {
+            let _ = normalised.pop();
+        }
+        Ok(Some(normalised))
+    }
+}
+
+/// A page moved by its permalink.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermalinkMove {
+    /// Output path of the page before, relative to the site directory.
+    pub from: PathBuf,
+    /// Output path from the permalink.
+    pub to: PathBuf,
+}
+
+/// Sets the `url_path` of every page in `content` with a permalink
+/// template, returning how their output paths and those of pages with a
+/// `route` change.
+///
+/// # Errors
+///
+/// Returns an error if a page needs a date it does not have, has an
+/// invalid `route`, or two pages end up with the same URL or output
+/// file.
+pub fn apply(
+    content: &mut ContentSet,
+    options: &PermalinkOptions,
+) -> Result<Vec<PermalinkMove>> {
+    let mut moves = Vec::new();
+    let mut expanded = vec![false; content.len()];
+    for (i, page) in content.pages.iter_mut().enumerate() {
+        if page.route().is_some() {
+            expanded[i] = true;
+            let from = page.computed_output_path();
+            let to = page.output_path();
+            if from != to {
+                moves.push(PermalinkMove { from, to });
+            }
+            continue;
+        }
+        if let Some(route) = page.frontmatter.get_str(ROUTE_KEY) {
+            bail!(
+                "{} has the route '{}', which is not a path such as \
+                 /.well-known/security.txt",
+                page.relative.display(),
+                route
+            );
+        }
+        if let Some(url) = options.expand(page)? {
+            expanded[i] = true;
+            let from = page.output_path();
+            page.frontmatter.insert(URL_PATH_KEY, Value::String(url));
+            let to = page.output_path();
+            if from != to {
+                moves.push(PermalinkMove { from, to });
+            }
+        }
+    }
+
+    // Pages keeping their URL may already share one; only report
+    // clashes a template or route caused.
+    let mut owners: HashMap<PathBuf, usize> = HashMap::new();
+    for (i, page) in content.iter().enumerate() {
+        if let Some(other) = owners.insert(page.output_path(), i) {
+            if !expanded[i] && !expanded[other] {
+                continue;
+            }
+            bail!(
+                "{} and {} both have the URL {}",
+                content.pages[other].relative.display(),
+                page.relative.display(),
+                page.url_path()
+            );
+        }
+    }
+    Ok(moves)
+}
+
+/// Moves the compiled pages in `site_dir` to their permalinks, removing
+/// directories left empty. Pages that were not compiled are skipped.
+///
+/// Returns the number of pages moved.
+///
+/// # Errors
+///
+/// Returns an error if a page cannot be moved.
+pub fn move_pages(
+    site_dir: &Path,
+    moves: &[PermalinkMove],
+) -> Result<usize> {
+    let mut moved = 0;
+    for PermalinkMove { from, to } in moves {
+        let source = site_dir.join(from);
+        if !source.is_file() {
+            continue;
+        }
+        let target = site_dir.join(to);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        fs::rename(&source, &target).with_context(|| {
+            format!(
+                "Failed to move {} to {}",
+                source.display(),
+                target.display()
+            )
+        })?;
+        moved += 1;
+
+        let mut dir = source.parent();
+        while let Some(current) = dir {
+            if current == site_dir || fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+    Ok(moved)
+}
+
+/// Turns text into a URL slug, transliterating letters with a common
+/// ASCII spelling first.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::permalinks::slug_from;
+///
+/// assert_eq!(slug_from("Crème Brûlée à Paris"), "creme-brulee-a-paris");
+/// assert_eq!(slug_from("Die Straße"), "die-strasse");
+/// assert_eq!(slug_from("Привет, мир"), "privet-mir");
+/// assert_eq!(slug_from("東京 2025"), "東京-2025");
+/// ```
+pub fn slug_from(text: &str) -> String {
+    let mut ascii = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        match transliterate(c) {
+            Some(replacement) => ascii.push_str(replacement),
+            None => ascii.push(c),
+        }
+    }
+    slugify(&ascii)
+}
+
+/// Returns the ASCII spelling of a lowercase letter, if it has one.
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ā' | 'ă' | 'ą' | 'å' => "a",
+        'ä' | 'æ' => "ae",
+        'ç' | 'ć' | 'č' | 'ĉ' | 'ċ' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => {
+            "e"
+        }
+        'ğ' | 'ĝ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => {
+            "i"
+        }
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ō' | 'ŏ' | 'ő' | 'ø' => "o",
+        'ö' | 'œ' => "oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => "s",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => {
+            "u"
+        }
+        'ü' => "ue",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'д' => "d",
+        'е' | 'ё' | 'э' => "e",
+        'ж' => "zh",
+        'з' => "z",
+        'и' | 'і' => "i",
+        'й' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ы' => "y",
+        'ъ' | 'ь' => "",
+        'ю' => "yu",
+        'я' => "ya",
+        'ї' => "yi",
+        'є' => "ye",
+        'α' => "a",
+        'β' => "v",
+        'γ' => "g",
+        'δ' => "d",
+        'ε' | 'έ' => "e",
+        'ζ' => "z",
+        'η' | 'ή' => "i",
+        'θ' => "th",
+        'ι' | 'ί' | 'ϊ' => "i",
+        'κ' => "k",
+        'λ' => "l",
+        'μ' => "m",
+        'ν' => "n",
+        'ξ' => "x",
+        'ο' | 'ό' => "o",
+        'π' => "p",
+        'ρ' => "r",
+        'σ' | 'ς' => "s",
+        'τ' => "t",
+        'υ' | 'ύ' | 'ϋ' => "y",
+        'φ' => "f",
+        'χ' => "ch",
+        'ψ' => "ps",
+        'ω' | 'ώ' => "o",
+        _ => return None,
+    })
+}
+
+/// Returns `true` for `index.md` pages, which list their section.
+fn is_index(page: &Page) -> bool {
+    page.relative.file_stem().and_then(|stem| stem.to_str())
+        == Some("index")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn page(path: &str, frontmatter: &str) -> Page {
+        Page::from_source(
+            PathBuf::from(path),
+            PathBuf::from(path),
+            &format!("---\n{}\n---\n", frontmatter),
+        )
+        .unwrap()
+    }
+
+    fn options() -> PermalinkOptions {
+        PermalinkOptions {
+            default: Some("/:slug/".into()),
+            sections: [(
+                "blog".to_string(),
+                "/:year/:month/:slug/".into(),
+            )]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    #[test]
+    fn test_expand_templates() -> Result<()> {
+        let options = options();
+        let expand = |path: &str, frontmatter: &str| {
+            options.expand(&page(path, frontmatter))
+        };
+
+        assert_eq!(
+            expand(
+                "blog/post.md",
+                "title: Hello, Wörld\ndate: 2025-03-09"
+            )?
+            .as_deref(),
+            Some("/2025/03/hello-woerld/")
+        );
+        assert_eq!(
+            expand(
+                "blog/named.md",
+                "title: x\nslug: Custom Slug\ndate: January 01, 2025"
+            )?
+            .as_deref(),
+            Some("/2025/01/custom-slug/")
+        );
+        assert_eq!(
+            expand(
+                "blog/rfc.md",
+                "date: Fri, 31 Jan 2025 09:00:00 +0000"
+            )?
+            .as_deref(),
+            Some("/2025/01/rfc/")
+        );
+        assert_eq!(
+            expand("docs/guide/intro.md", "title: Intro")?.as_deref(),
+            Some("/intro/")
+        );
+        assert_eq!(expand("blog/index.md", "title: Blog")?, None);
+        assert_eq!(
+            expand("blog/moved.md", "url_path: /elsewhere/")?,
+            None
+        );
+        assert!(expand("blog/undated.md", "title: Undated").is_err());
+
+        let sections = PermalinkOptions {
+            default: Some("/:section/:filename.html".into()),
+            ..PermalinkOptions::default()
+        };
+        assert_eq!(
+            sections.expand(&page("about.md", ""))?.as_deref(),
+            Some("/about.html")
+        );
+        assert_eq!(
+            PermalinkOptions::default().expand(&page("a/b.md", ""))?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_and_move_pages() -> Result<()> {
+        let mut content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("blog/post.md", "title: Post\ndate: 2025-03-09"),
+                page("about.md", "title: About"),
+            ],
+        };
+        let moves = apply(&mut content, &options())?;
+        assert_eq!(
+            moves,
+            vec![PermalinkMove {
+                from: PathBuf::from("blog/post/index.html"),
+                to: PathBuf::from("2025/03/post/index.html"),
+            }]
+        );
+        assert_eq!(content.pages[0].url_path(), "/2025/03/post/");
+
+        let site = tempdir()?;
+        fs::create_dir_all(site.path().join("blog/post"))?;
+        fs::write(site.path().join("blog/post/index.html"), "post")?;
+        assert_eq!(move_pages(site.path(), &moves)?, 1);
+        assert_eq!(
+            fs::read_to_string(
+                site.path().join("2025/03/post/index.html")
+            )?,
+            "post"
+        );
+        assert!(!site.path().join("blog").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_rejects_duplicate_urls() {
+        let mut content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("a.md", "title: Same"),
+                page("b.md", "title: Same"),
+            ],
+        };
+        let error = apply(&mut content, &options()).unwrap_err();
+        assert!(error.to_string().contains("both have the URL /same/"));
+    }
+
+    #[test]
+    fn test_apply_routes() -> Result<()> {
+        let mut content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("blog/old.md", "title: Old\nroute: /old-post.php"),
+                page("security.md", "route: /.well-known/security.txt"),
+            ],
+        };
+        let moves = apply(&mut content, &options())?;
+        assert_eq!(
+            moves,
+            vec![
+                PermalinkMove {
+                    from: PathBuf::from("blog/old/index.html"),
+                    to: PathBuf::from("old-post.php"),
+                },
+                PermalinkMove {
+                    from: PathBuf::from("security/index.html"),
+                    to: PathBuf::from(".well-known/security.txt"),
+                },
+            ]
+        );
+
+        content.pages.push(page("about.md", "title: About"));
+        content
+            .pages
+            .push(page("team.md", "route: /about/index.html"));
+        let error = apply(&mut content, &options()).unwrap_err();
+        assert!(error.to_string().contains("about.md and team.md"));
+
+        content.pages.truncate(2);
+        content.pages.push(page("bad.md", "route: no-slash.txt"));
+        assert!(apply(&mut content, &options()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(options().validate().is_ok());
+        for template in ["no-slash/", "/:slug/../", "/:author/:slug/"] {
+            let options = PermalinkOptions {
+                default: Some(template.into()),
+                ..PermalinkOptions::default()
+            };
+            assert!(options.validate().is_err(), "{}", template);
+        }
+    }
+}
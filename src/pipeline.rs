@@ -0,0 +1,999 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Parallel Page Pipeline
+//!
+//! Renders the pages of a [`ContentSet`] in parallel. Pages are arranged
+//! in a [`PageGraph`] whose edges say which pages must be rendered before
+//! which:
+//!
+//! - a section's `index.md` depends on the other pages in its directory,
+//!   so listings can show their rendered content;
+//! - a `depends_on` frontmatter list names further pages by their path
+//!   relative to the content directory.
+//!
+//! The graph is split into waves of pages whose dependencies are all in
+//! earlier waves. Each wave is rendered with rayon against one read-only
+//! [`TemplateBackend`], so no render state is shared between workers.
+//!
+//! [`Compiler::compile`](crate::compiler::Compiler::compile) renders the
+//! pages of every build this way.
+//!
+//! Results are ordered by source path no matter which worker finished
+//! first, so sitemaps and feeds built from a [`RenderedSite`] are the same
+//! on every build. Warnings raised while rendering a page, such as a
+//! `fetch_json` call falling back to its cache, are logged in path order
+//! once all pages are rendered.
+//!
+//! For sites too large to hold in memory, [`stream_pages`] writes pages
+//! in batches bounded by a memory budget instead of returning them.
+//!
+//! With the `async` feature, [`render_pages_async`] renders each page of
+//! a wave in its own task on tokio's blocking pool, and
+//! [`RenderedSite::write_async`] writes the pages through the
+//! [output write queue](crate::writer), so servers embedding the
+//! generator can build a site without blocking their runtime.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use ssg::{
+//!     content::{ContentSet, Page},
+//!     pipeline::render_pages,
+//!     template::{NativeBackend, TemplateBackend},
+//! };
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let mut backend = NativeBackend::default();
+//!     backend.add_template("page.html", "<h1>{{ title }}</h1>{{ content }}")?;
+//!     let page = Page::from_source(
+//!         "content/about.md".into(),
+//!         "about.md".into(),
+//!         "---\ntitle: About\n---\nHello",
+//!     )?;
+//!     let content = ContentSet { pages: vec![page], ..ContentSet::default() };
+//!
+//!     let site = render_pages(&content, &backend)?;
+//!     assert_eq!(site.pages[0].url, "/about/");
+//!     assert!(site.pages[0].html.starts_with("<h1>About</h1>"));
+//!     Ok(())
+//! }
+//! ```
+
+use anyhow::{bail, Context, Result};
+use metadata_gen::generate_metatags;
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "async")]
+use crate::writer::{WriteQueue, WriteStats};
+use crate::{
+    content::{ContentSet, Page},
+    dates::{DateContext, DEFAULT_DATE_LANGUAGE},
+    diagnostics::WarningLog,
+    page_meta,
+    template::TemplateBackend,
+    vfs::{RealFs, Vfs},
+};
+#[cfg(feature = "async")]
+use std::sync::Arc;
+
+/// Template used for pages without a `layout`.
+pub const DEFAULT_LAYOUT: &str = "page";
+
+/// Default memory budget of [`stream_pages`]: 256 MiB.
+pub const DEFAULT_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+
+/// Frontmatter key listing extra dependencies of a page.
+pub const DEPENDS_ON_KEY: &str = "depends_on";
+
+/// Render order of the pages of a [`ContentSet`].
+///
+/// Nodes are indices into [`ContentSet::pages`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageGraph {
+    /// Dependencies of each page, sorted.
+    dependencies: Vec<BTreeSet<usize>>,
+}
+
+impl PageGraph {
+    /// Builds the graph for `content`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a `depends_on` entry names a page that does not
+    /// exist.
+    pub fn build(content: &ContentSet) -> Result<Self> {
+        let index: BTreeMap<&Path, usize> = content
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(i, page)| (page.relative.as_path(), i))
+            .collect();
+
+        let mut dependencies =
+            vec![BTreeSet::new(); content.pages.len()];
+        for (i, page) in content.pages.iter().enumerate() {
+            if is_section_index(page) {
+                let dir = page.relative.parent();
+                for (j, other) in content.pages.iter().enumerate() {
+                    if i != j && other.relative.parent() == dir {
+                        let _ = dependencies[i].insert(j);
+                    }
+                }
+            }
+            for name in page.frontmatter.get_list(DEPENDS_ON_KEY) {
+                let j = *index.get(Path::new(&name)).with_context(
+                    || {
+                        format!(
+                            "{} depends on `{}`, which does not exist",
+                            page.relative.display(),
+                            name
+                        )
+                    },
+                )?;
+                if i != j {
+                    let _ = dependencies[i].insert(j);
+                }
+            }
+        }
+        Ok(Self { dependencies })
+    }
+
+    /// Returns the number of pages in the graph.
+    pub fn len(&self) -> usize {
+        self.dependencies.len()
+    }
+
+    /// Returns `true` if the graph has no pages.
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty()
+    }
+
+    /// Returns the pages `page` depends on, in path order.
+    pub fn dependencies(
+        &self,
+        page: usize,
+    ) -> impl Iterator<Item = usize> + '_ {
+        self.dependencies[page].iter().copied()
+    }
+
+    /// Splits the pages into waves: each page's dependencies are all in
+    /// earlier waves, and pages in one wave can be rendered in parallel.
+    /// Pages in a wave are in path order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the pages of a dependency cycle.
+    pub fn waves(
+        &self,
+        content: &ContentSet,
+    ) -> Result<Vec<Vec<usize>>> {
+        let mut remaining: Vec<usize> =
+            self.dependencies.iter().map(BTreeSet::len).collect();
+        let mut dependents = vec![Vec::new(); self.len()];
+        for (page, dependencies) in self.dependencies.iter().enumerate()
+        {
+            for &dependency in dependencies {
+                dependents[dependency].push(page);
+            }
+        }
+
+        let mut waves = Vec::new();
+        let mut wave: Vec<usize> =
+            (0..self.len()).filter(|&i| remaining[i] == 0).collect();
+        let mut done = 0;
+        while !wave.is_empty() {
+            done += wave.len();
+            let mut next = Vec::new();
+            for &page in &wave {
+                for &dependent in &dependents[page] {
+                    remaining[dependent] -= 1;
+                    if remaining[dependent] == 0 {
+                        next.push(dependent);
+                    }
+                }
+            }
+            next.sort_unstable();
+            waves.push(wave);
+            wave = next;
+        }
+
+        if done < self.len() {
+            let cycle: Vec<String> = (0..self.len())
+                .filter(|&i| remaining[i] > 0)
+                .map(|i| {
+                    content.pages[i].relative.display().to_string()
+                })
+                .collect();
+            bail!("Pages depend on each other: {}", cycle.join(", "));
+        }
+        Ok(waves)
+    }
+}
+
+/// A rendered page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedPage {
+    /// Path of the source file relative to the content directory.
+    pub relative: PathBuf,
+    /// Output path relative to the site directory.
+    pub path: PathBuf,
+    /// Site-relative URL, e.g. `/blog/post/`.
+    pub url: String,
+    /// The page's `date` frontmatter, if any.
+    pub date: Option<String>,
+    /// Whether the page's `robots` frontmatter forbids indexing.
+    pub noindex: bool,
+    /// The rendered HTML.
+    pub html: String,
+}
+
+/// The rendered pages of a site, in source path order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderedSite {
+    /// Rendered pages, sorted by source path.
+    pub pages: Vec<RenderedPage>,
+}
+
+impl RenderedSite {
+    /// Returns the pages in feed order: newest `date` first, undated
+    /// pages last, and ties broken by source path.
+    pub fn feed_order(&self) -> Vec<&RenderedPage> {
+        let mut pages: Vec<&RenderedPage> = self.pages.iter().collect();
+        pages.sort_by(|a, b| {
+            b.date
+                .is_some()
+                .cmp(&a.date.is_some())
+                .then_with(|| b.date.cmp(&a.date))
+                .then_with(|| a.relative.cmp(&b.relative))
+        });
+        pages
+    }
+
+    /// Returns the absolute URLs of all pages in path order, for a
+    /// sitemap. Pages that may not be indexed are left out.
+    pub fn sitemap_urls(&self, base_url: &str) -> Vec<String> {
+        let base = base_url.trim_end_matches('/');
+        self.pages
+            .iter()
+            .filter(|page| !page.noindex)
+            .map(|page| format!("{}{}", base, page.url))
+            .collect()
+    }
+
+    /// Writes every page below `site_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a directory or file cannot be written.
+    pub fn write(&self, site_dir: &Path) -> Result<()> {
+        self.write_to(&RealFs, site_dir)
+    }
+
+    /// Writes every page below `site_dir` in the file system `vfs`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page cannot be written.
+    pub fn write_to(
+        &self,
+        vfs: &dyn Vfs,
+        site_dir: &Path,
+    ) -> Result<()> {
+        for page in &self.pages {
+            vfs.write(
+                &site_dir.join(&page.path),
+                page.html.as_bytes(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes every page below `site_dir` like [`write`](Self::write),
+    /// through a [`WriteQueue`] on the current tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page cannot be written.
+    #[cfg(feature = "async")]
+    pub async fn write_async(
+        &self,
+        site_dir: &Path,
+    ) -> Result<WriteStats> {
+        let queue = WriteQueue::spawn();
+        for page in &self.pages {
+            queue
+                .write(site_dir.join(&page.path), page.html.clone())?;
+        }
+        queue.finish().await
+    }
+}
+
+/// Renders every page of `content` with `backend`, one [`PageGraph`]
+/// wave at a time and the pages of each wave in parallel.
+///
+/// A page is rendered with its `layout` template (`page.html` without
+/// one). The context holds its frontmatter plus:
+///
+/// | Variable  | Value |
+/// |-----------|-------|
+/// | `content` | The page body rendered from Markdown |
+/// | `url`     | The page URL, e.g. `/blog/post/` |
+/// | `pages`   | The pages it depends on, with their `title`, `url`, `description`, `date` and rendered `content` |
+/// | `page`    | The `published` and `updated` dates; see [`crate::dates`] |
+/// | `primary`, `opengraph`, `twitter`, `apple`, `microsoft` | The meta tags generated from the frontmatter |
+///
+/// Dates are formatted in English unless a page sets its language; use
+/// [`render_pages_with_dates`] to choose the site language.
+///
+/// # Errors
+///
+/// Returns an error if the pages depend on each other in a cycle, a
+/// layout template is missing, or a page fails to render.
+pub fn render_pages(
+    content: &ContentSet,
+    backend: &dyn TemplateBackend,
+) -> Result<RenderedSite> {
+    render_pages_with_dates(
+        content,
+        backend,
+        &DateContext::load(content, DEFAULT_DATE_LANGUAGE),
+    )
+}
+
+/// Renders like [`render_pages`], with page dates from `dates`.
+///
+/// # Errors
+///
+/// Returns an error if the pages depend on each other in a cycle, a
+/// layout template is missing, or a page fails to render.
+pub fn render_pages_with_dates(
+    content: &ContentSet,
+    backend: &dyn TemplateBackend,
+    dates: &DateContext,
+) -> Result<RenderedSite> {
+    let graph = PageGraph::build(content)?;
+    let mut bodies: Vec<Option<String>> = vec![None; content.len()];
+    let mut rendered: Vec<Option<RenderedPage>> =
+        vec![None; content.len()];
+    let warnings = WarningLog::new();
+
+    for wave in graph.waves(content)? {
+        let results = render_batch(
+            &wave, content, &graph, &bodies, backend, dates, &warnings,
+        )?;
+        for (i, body, html) in results {
+            let page = &content.pages[i];
+            bodies[i] = Some(body);
+            rendered[i] = Some(RenderedPage {
+                relative: page.relative.clone(),
+                path: page.output_path(),
+                url: page.url_path(),
+                date: page.frontmatter.get_str("date"),
+                noindex: page_meta::is_noindex(page),
+                html,
+            });
+        }
+    }
+
+    let _ = warnings.flush();
+    Ok(RenderedSite {
+        pages: rendered.into_iter().flatten().collect(),
+    })
+}
+
+/// Renders like [`render_pages_with_dates`] without blocking an async
+/// runtime: the pages of each wave are rendered concurrently, each in
+/// its own task on tokio's blocking pool, and the next wave starts once
+/// they have all finished.
+///
+/// # Errors
+///
+/// Returns an error if the pages depend on each other in a cycle, a
+/// layout template is missing, a page fails to render, or a render task
+/// panics.
+#[cfg(feature = "async")]
+pub async fn render_pages_async(
+    content: Arc<ContentSet>,
+    backend: Arc<dyn TemplateBackend>,
+    dates: Arc<DateContext>,
+) -> Result<RenderedSite> {
+    let graph = Arc::new(PageGraph::build(&content)?);
+    let mut bodies: Arc<Vec<Option<String>>> =
+        Arc::new(vec![None; content.len()]);
+    let mut rendered: Vec<Option<RenderedPage>> =
+        vec![None; content.len()];
+    let warnings = Arc::new(WarningLog::new());
+
+    for wave in graph.waves(&content)? {
+        let mut tasks = tokio::task::JoinSet::new();
+        for i in wave {
+            let content = Arc::clone(&content);
+            let graph = Arc::clone(&graph);
+            let bodies = Arc::clone(&bodies);
+            let backend = Arc::clone(&backend);
+            let dates = Arc::clone(&dates);
+            let warnings = Arc::clone(&warnings);
+            let _ = tasks.spawn_blocking(move || {
+                warnings.scope(&content.pages[i].relative, || {
+                    render_one(
+                        i,
+                        &content,
+                        &graph,
+                        &bodies,
+                        backend.as_ref(),
+                        &dates,
+                    )
+                    .map(|(body, html)| (i, body, html))
+                })
+            });
+        }
+        let mut results = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            results
+                .push(result.context("A page render task panicked")??);
+        }
+        // Every task has dropped its handle, so this does not clone.
+        let known = Arc::make_mut(&mut bodies);
+        for (i, body, html) in results {
+            let page = &content.pages[i];
+            known[i] = Some(body);
+            rendered[i] = Some(RenderedPage {
+                relative: page.relative.clone(),
+                path: page.output_path(),
+                url: page.url_path(),
+                date: page.frontmatter.get_str("date"),
+                noindex: page_meta::is_noindex(page),
+                html,
+            });
+        }
+    }
+
+    let _ = warnings.flush();
+    Ok(RenderedSite {
+        pages: rendered.into_iter().flatten().collect(),
+    })
+}
+
+/// Counts of a [`stream_pages`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamSummary {
+    /// Number of pages written.
+    pub pages: usize,
+    /// Number of batches the pages were rendered in.
+    pub batches: usize,
+    /// Total size of the written pages in bytes.
+    pub bytes_written: usize,
+    /// Size of the largest batch held in memory, in bytes.
+    pub largest_batch: usize,
+}
+
+/// Renders like [`render_pages`], but writes each batch of pages to
+/// `site_dir` as soon as it is rendered instead of keeping the whole
+/// site in memory.
+///
+/// Batches are sized so the rendered output held at once stays near
+/// `memory_budget` bytes: the first batch has one page per thread, and
+/// later batches are sized from the average page size so far. Rendered
+/// bodies are only kept while a page that depends on them is still to
+/// be rendered.
+///
+/// # Errors
+///
+/// Returns an error if the pages depend on each other in a cycle, a
+/// page fails to render, or a page cannot be written.
+pub fn stream_pages(
+    content: &ContentSet,
+    backend: &dyn TemplateBackend,
+    site_dir: &Path,
+    memory_budget: usize,
+) -> Result<StreamSummary> {
+    stream_pages_with_dates(
+        content,
+        backend,
+        site_dir,
+        memory_budget,
+        &DateContext::load(content, DEFAULT_DATE_LANGUAGE),
+    )
+}
+
+/// Streams like [`stream_pages`], with page dates from `dates`.
+///
+/// # Errors
+///
+/// Returns an error if the pages depend on each other in a cycle, a
+/// page fails to render, or a page cannot be written.
+pub fn stream_pages_with_dates(
+    content: &ContentSet,
+    backend: &dyn TemplateBackend,
+    site_dir: &Path,
+    memory_budget: usize,
+    dates: &DateContext,
+) -> Result<StreamSummary> {
+    stream_pages_to(
+        &RealFs,
+        content,
+        backend,
+        site_dir,
+        memory_budget,
+        dates,
+    )
+}
+
+/// Streams like [`stream_pages_with_dates`], writing the pages to
+/// `site_dir` in the file system `vfs`.
+///
+/// # Errors
+///
+/// Returns an error if the pages depend on each other in a cycle, a
+/// page fails to render, or a page cannot be written.
+pub fn stream_pages_to(
+    vfs: &dyn Vfs,
+    content: &ContentSet,
+    backend: &dyn TemplateBackend,
+    site_dir: &Path,
+    memory_budget: usize,
+    dates: &DateContext,
+) -> Result<StreamSummary> {
+    let graph = PageGraph::build(content)?;
+    let mut dependents = vec![0_usize; content.len()];
+    for page in 0..graph.len() {
+        for dependency in graph.dependencies(page) {
+            dependents[dependency] += 1;
+        }
+    }
+    let mut bodies: Vec<Option<String>> = vec![None; content.len()];
+    let mut summary = StreamSummary::default();
+    let mut batch_size = rayon::current_num_threads().max(1);
+    let warnings = WarningLog::new();
+
+    for wave in graph.waves(content)? {
+        let mut start = 0;
+        while start < wave.len() {
+            let end = (start + batch_size).min(wave.len());
+            let results = render_batch(
+                &wave[start..end],
+                content,
+                &graph,
+                &bodies,
+                backend,
+                dates,
+                &warnings,
+            )?;
+
+            let bytes: usize = results
+                .iter()
+                .map(|(_, body, html)| body.len() + html.len())
+                .sum();
+            for (i, body, html) in results {
+                let page = &content.pages[i];
+                vfs.write(
+                    &site_dir.join(page.output_path()),
+                    html.as_bytes(),
+                )?;
+                summary.bytes_written += html.len();
+                if dependents[i] > 0 {
+                    bodies[i] = Some(body);
+                }
+                for dependency in graph.dependencies(i) {
+                    dependents[dependency] -= 1;
+                    if dependents[dependency] == 0 {
+                        bodies[dependency] = None;
+                    }
+                }
+            }
+
+            summary.pages += end - start;
+            summary.batches += 1;
+            summary.largest_batch = summary.largest_batch.max(bytes);
+            let average = (bytes / (end - start)).max(1);
+            batch_size = (memory_budget / average).max(1);
+            start = end;
+        }
+    }
+    let _ = warnings.flush();
+    Ok(summary)
+}
+
+/// Renders `batch` in parallel, returning each page's index, rendered
+/// body and HTML in batch order. Warnings are kept in `warnings`.
+fn render_batch(
+    batch: &[usize],
+    content: &ContentSet,
+    graph: &PageGraph,
+    bodies: &[Option<String>],
+    backend: &dyn TemplateBackend,
+    dates: &DateContext,
+    warnings: &WarningLog,
+) -> Result<Vec<(usize, String, String)>> {
+    batch
+        .par_iter()
+        .map(|&i| {
+            warnings.scope(&content.pages[i].relative, || {
+                render_one(i, content, graph, bodies, backend, dates)
+                    .map(|(body, html)| (i, body, html))
+            })
+        })
+        .collect()
+}
+
+/// Renders page `i` of `content`, returning its Markdown body rendered
+/// to HTML and the page rendered with its layout.
+fn render_one(
+    i: usize,
+    content: &ContentSet,
+    graph: &PageGraph,
+    bodies: &[Option<String>],
+    backend: &dyn TemplateBackend,
+    dates: &DateContext,
+) -> Result<(String, String)> {
+    let page = &content.pages[i];
+    let body = backend.render_markdown(&page.body);
+    let pages: Vec<Value> = graph
+        .dependencies(i)
+        .map(|j| {
+            summary(
+                &content.pages[j],
+                bodies[j].as_deref().unwrap_or_default(),
+            )
+        })
+        .collect();
+    let html = render_page(page, &body, pages, backend, dates)
+        .with_context(|| {
+            format!("Failed to render {}", page.relative.display())
+        })?;
+    Ok((body, html))
+}
+
+fn render_page(
+    page: &Page,
+    body: &str,
+    pages: Vec<Value>,
+    backend: &dyn TemplateBackend,
+    dates: &DateContext,
+) -> Result<String> {
+    let layout = page
+        .frontmatter
+        .get_str("layout")
+        .unwrap_or_else(|| DEFAULT_LAYOUT.to_string());
+    let template = format!("{}.html", layout);
+    if !backend.has_template(&template) {
+        bail!("Layout template `{}` not found", template);
+    }
+
+    let mut context = page.frontmatter.to_json();
+    if let Value::Object(map) = &mut context {
+        let _ =
+            map.insert("content".into(), Value::String(body.into()));
+        let _ =
+            map.insert("url".into(), Value::String(page.url_path()));
+        let _ = map.insert("pages".into(), Value::Array(pages));
+        let _ = map.insert("page".into(), dates.page_context(page));
+        for (name, tags) in meta_tags(page) {
+            let _ = map.insert(name.into(), Value::String(tags));
+        }
+    }
+    backend.render(&template, &context)
+}
+
+/// Returns the meta tags of `page` generated from its frontmatter, by
+/// the name templates see them under, as the core compile sets them.
+fn meta_tags(page: &Page) -> [(&'static str, String); 5] {
+    let metadata: HashMap<String, String> = page
+        .frontmatter
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            (key.clone(), value)
+        })
+        .collect();
+    let tags = generate_metatags(&metadata);
+    [
+        ("apple", tags.apple),
+        ("primary", tags.primary),
+        ("opengraph", tags.og),
+        ("microsoft", tags.ms),
+        ("twitter", tags.twitter),
+    ]
+}
+
+fn summary(page: &Page, body: &str) -> Value {
+    json!({
+        "title": page.title(),
+        "url": page.url_path(),
+        "description": page.description(),
+        "date": page.frontmatter.get_str("date"),
+        "content": body,
+    })
+}
+
+fn is_section_index(page: &Page) -> bool {
+    page.relative.file_stem().and_then(|stem| stem.to_str())
+        == Some("index")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{template::NativeBackend, vfs::MemoryFs};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn page(relative: &str, raw: &str) -> Page {
+        Page::from_source(
+            PathBuf::from("content").join(relative),
+            PathBuf::from(relative),
+            raw,
+        )
+        .unwrap()
+    }
+
+    fn blog() -> ContentSet {
+        ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("about.md", "---\ntitle: About\n---\nAbout us"),
+                page(
+                    "blog/first.md",
+                    "---\ntitle: First\ndate: 2025-01-01\n---\n*one*",
+                ),
+                page(
+                    "blog/index.md",
+                    "---\ntitle: Blog\nlayout: list\n---\n",
+                ),
+                page(
+                    "blog/second.md",
+                    "---\ntitle: Second\ndate: 2025-02-01\n---\ntwo",
+                ),
+                page(
+                    "index.md",
+                    "---\ntitle: Home\ndepends_on: [blog/index.md]\n---\n",
+                ),
+            ],
+        }
+    }
+
+    fn backend() -> NativeBackend {
+        let mut backend = NativeBackend::default();
+        backend
+            .add_template(
+                "page.html",
+                "<h1>{{ title }}</h1>{{ content }}",
+            )
+            .unwrap();
+        backend
+            .add_template(
+                "list.html",
+                "<a href=\"{{ pages.0.url }}\">{{ pages.0.title }}</a>\
+                 {{ pages.0.content }}{{ pages.1.title }}",
+            )
+            .unwrap();
+        backend
+    }
+
+    #[test]
+    fn test_waves_follow_dependencies() -> Result<()> {
+        let content = blog();
+        let graph = PageGraph::build(&content)?;
+        assert_eq!(graph.dependencies(2).collect::<Vec<_>>(), [1, 3]);
+        // The root index depends on its siblings and on the blog index.
+        assert_eq!(graph.dependencies(4).collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(
+            graph.waves(&content)?,
+            vec![vec![0, 1, 3], vec![2], vec![4]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cycles_and_unknown_dependencies_are_errors() {
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("a.md", "---\ndepends_on: [b.md]\n---\n"),
+                page("b.md", "---\ndepends_on: [a.md]\n---\n"),
+                page("c.md", "---\ntitle: C\n---\n"),
+            ],
+        };
+        let graph = PageGraph::build(&content).unwrap();
+        let error = graph.waves(&content).unwrap_err().to_string();
+        assert!(error.contains("a.md, b.md"), "{}", error);
+        assert!(!error.contains("c.md"), "{}", error);
+
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![page("a.md", "---\ndepends_on: [x.md]\n---\n")],
+        };
+        assert!(PageGraph::build(&content).is_err());
+    }
+
+    #[test]
+    fn test_render_pages_sees_rendered_dependencies() -> Result<()> {
+        let site = render_pages(&blog(), &backend())?;
+        let paths: Vec<_> =
+            site.pages.iter().map(|p| p.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [
+                "about/index.html",
+                "blog/first/index.html",
+                "blog/index.html",
+                "blog/second/index.html",
+                "index.html",
+            ]
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            site.pages[2].html,
+            "<a href=\"/blog/first/\">First</a><p><em>one</em></p>\nSecond"
+        );
+
+        let feed: Vec<_> =
+            site.feed_order().iter().map(|p| p.url.as_str()).collect();
+        assert_eq!(
+            feed,
+            ["/blog/second/", "/blog/first/", "/about/", "/blog/", "/"]
+        );
+        assert_eq!(
+            site.sitemap_urls("https://example.com/")[4],
+            "https://example.com/"
+        );
+
+        let temp_dir = tempdir()?;
+        site.write(temp_dir.path())?;
+        assert!(temp_dir
+            .path()
+            .join("blog/second/index.html")
+            .is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_pages_is_deterministic() -> Result<()> {
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: (0..64)
+                .map(|i| {
+                    page(
+                        &format!("posts/{:02}.md", i),
+                        &format!("---\ntitle: Post {}\n---\n", i),
+                    )
+                })
+                .collect(),
+        };
+        let backend = backend();
+        let first = render_pages(&content, &backend)?;
+        for _ in 0..4 {
+            assert_eq!(render_pages(&content, &backend)?, first);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_pages_matches_render_pages() -> Result<()> {
+        let content = blog();
+        let backend = backend();
+        let site = render_pages(&content, &backend)?;
+
+        let temp_dir = tempdir()?;
+        // A tiny budget renders one page per batch.
+        let summary =
+            stream_pages(&content, &backend, temp_dir.path(), 1)?;
+        assert_eq!(summary.pages, 5);
+        assert!(summary.batches >= 4, "{:?}", summary);
+        assert_eq!(
+            summary.bytes_written,
+            site.pages.iter().map(|p| p.html.len()).sum::<usize>()
+        );
+        for page in &site.pages {
+            assert_eq!(
+                fs::read_to_string(temp_dir.path().join(&page.path))?,
+                page.html
+            );
+        }
+
+        let summary = stream_pages(
+            &content,
+            &backend,
+            tempdir()?.path(),
+            usize::MAX,
+        )?;
+        // The first wave of three pages fits the first batch only with
+        // three threads; after it, the budget fits any wave.
+        let expected = if rayon::current_num_threads() >= 3 {
+            3
+        } else {
+            4
+        };
+        assert_eq!((summary.pages, summary.batches), (5, expected));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pages_written_to_a_virtual_file_system() -> Result<()> {
+        let content = blog();
+        let backend = backend();
+        let site = render_pages(&content, &backend)?;
+
+        let written = MemoryFs::new();
+        site.write_to(&written, Path::new("public"))?;
+        let streamed = MemoryFs::new();
+        let _ = stream_pages_to(
+            &streamed,
+            &content,
+            &backend,
+            Path::new("public"),
+            1,
+            &DateContext::load(&content, DEFAULT_DATE_LANGUAGE),
+        )?;
+        assert_eq!(written.paths().len(), 5);
+        for page in &site.pages {
+            let path = Path::new("public").join(&page.path);
+            assert_eq!(written.read_to_string(&path)?, page.html);
+            assert_eq!(streamed.read_to_string(&path)?, page.html);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_page_dates_in_context() -> Result<()> {
+        let mut backend = NativeBackend::default();
+        backend.add_template(
+            "page.html",
+            "{{ page.published.formatted }}, {{ page.updated.relative }}",
+        )?;
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![page(
+                "a.md",
+                "---\ndate: 2025-03-01\nupdated: 2025-03-10\n---\n",
+            )],
+        };
+        let dates = DateContext::new(
+            "de",
+            crate::dates::Date::from_days(20_159),
+        );
+        let site = render_pages_with_dates(&content, &backend, &dates)?;
+        assert_eq!(site.pages[0].html, "1. März 2025, vor 2 Tagen");
+        Ok(())
+    }
+
+    #[test]
+    fn test_meta_tags_in_context() -> Result<()> {
+        let mut backend = NativeBackend::default();
+        backend.add_template("page.html", "{{ primary }}")?;
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![page(
+                "a.md",
+                "---\ndescription: About us\n---\n",
+            )],
+        };
+        let site = render_pages(&content, &backend)?;
+        assert!(site.pages[0].html.contains(
+            r#"<meta name="description" content="About us">"#
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_layout_is_an_error() {
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![page("a.md", "---\nlayout: post\n---\n")],
+        };
+        let error = render_pages(&content, &backend()).unwrap_err();
+        assert!(
+            format!("{:#}", error).contains("`post.html` not found")
+        );
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Build Plugins
+//!
+//! A [`Plugin`] hooks into the build lifecycle, so downstream crates can
+//! extend the generator without patching it. Every hook has a no-op
+//! default, so a plugin implements only the ones it needs:
+//!
+//! | Hook | Called |
+//! |------|--------|
+//! | [`before_build`](Plugin::before_build) | before the core compile |
+//! | [`on_page`](Plugin::on_page) | for every page of the content model, before permalinks, taxonomies, generators and the later stages read it |
+//! | [`after_render`](Plugin::after_render) | with the HTML of every page, once it is at its final path |
+//! | [`after_build`](Plugin::after_build) | once every output is written |
+//!
+//! Plugins are registered on a [`PluginRegistry`] and run in
+//! registration order. The compiler runs the first three hooks; the
+//! last one is run by [`run_with_plugins`](crate::run_with_plugins):
+//!
+//! ```no_run
+//! use anyhow::Result;
+//! use ssg::plugins::{PageData, Plugin, PluginRegistry};
+//!
+//! #[derive(Debug)]
+//! struct Signature;
+//!
+//! impl Plugin for Signature {
+//!     fn name(&self) -> &str {
+//!         "signature"
+//!     }
+//!
+//!     fn after_render(&self, _page: &PageData, html: &mut String) -> Result<()> {
+//!         html.push_str("<!-- built with ssg -->\n");
+//!         Ok(())
+//!     }
+//! }
+//!
+//! # async fn build() -> Result<()> {
+//! ssg::run_with_plugins(PluginRegistry::new().with_plugin(Signature)).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `on_page` runs once per build, on the content model the pages are
+//! rendered from, so changes it makes to a page reach its HTML, the
+//! search index and every later stage.
+//!
+//! With the `wasm` feature, plugins compiled to WebAssembly can be
+//! loaded at run time from the `wasm_plugins` configuration key; see
+//! the `plugins::wasm` module for their host API.
+
+use anyhow::{Context, Result};
+use std::{fmt::Debug, fs, path::PathBuf};
+
+use crate::{
+    build_info::BuildInfo,
+    content::{ContentSet, Page},
+    Paths,
+};
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// The page a plugin sees: its source path, frontmatter and body.
+pub type PageData = Page;
+
+/// Hooks into the build lifecycle.
+///
+/// Hooks are called from the build thread, but plugins are shared with
+/// the parallel stages, so implementations must be `Send + Sync`.
+pub trait Plugin: Debug + Send + Sync {
+    /// A short, unique name used in error messages.
+    fn name(&self) -> &str;
+
+    /// Called before the core compile.
+    ///
+    /// # Errors
+    ///
+    /// Any error aborts the build and is reported with the plugin name.
+    fn before_build(&self, _paths: &Paths) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for every page of the content model, in path order.
+    ///
+    /// # Errors
+    ///
+    /// Any error aborts the build and is reported with the plugin name
+    /// and page path.
+    fn on_page(&self, _page: &mut PageData) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called with the generated HTML of every page.
+    ///
+    /// # Errors
+    ///
+    /// Any error aborts the build and is reported with the plugin name
+    /// and page path.
+    fn after_render(
+        &self,
+        _page: &PageData,
+        _html: &mut String,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once every output of the build is written.
+    ///
+    /// # Errors
+    ///
+    /// Any error aborts the build and is reported with the plugin name.
+    fn after_build(&self, _paths: &Paths) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The registered plugins, in the order their hooks run.
+#[derive(Debug, Default)]
+pub struct PluginRegistry {
+    plugins: Vec<Box<dyn Plugin>>,
+}
+
+impl PluginRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a plugin, returning the registry for chaining.
+    pub fn with_plugin<P: Plugin + 'static>(
+        mut self,
+        plugin: P,
+    ) -> Self {
+        self.register(Box::new(plugin));
+        self
+    }
+
+    /// Registers a boxed plugin.
+    pub fn register(&mut self, plugin: Box<dyn Plugin>) {
+        self.plugins.push(plugin);
+    }
+
+    /// Loads and registers a [WebAssembly plugin](wasm) from each of
+    /// `files`, in order, passing each the variables of the build.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read or is not a valid
+    /// module.
+    #[cfg(feature = "wasm")]
+    pub fn load_wasm(
+        &mut self,
+        files: &[PathBuf],
+        build_info: &BuildInfo,
+    ) -> Result<()> {
+        for file in files {
+            self.register(Box::new(
+                wasm::WasmPlugin::load(file)?
+                    .with_build_info(build_info.clone()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fails if `files` is not empty: WebAssembly plugins need the
+    /// `wasm` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first file.
+    #[cfg(not(feature = "wasm"))]
+    pub fn load_wasm(
+        &mut self,
+        files: &[PathBuf],
+        _build_info: &BuildInfo,
+    ) -> Result<()> {
+        match files.first() {
+            Some(file) => anyhow::bail!(
+                "`{}` cannot be loaded; build with the `wasm` feature to run WebAssembly plugins",
+                file.display()
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns `true` if no plugin is registered.
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Returns the names of the registered plugins.
+    pub fn names(&self) -> Vec<&str> {
+        self.plugins.iter().map(|plugin| plugin.name()).collect()
+    }
+
+    /// Runs every plugin's [`before_build`](Plugin::before_build).
+    ///
+    /// # Errors
+    ///
+    /// Returns the first plugin error.
+    pub fn before_build(&self, paths: &Paths) -> Result<()> {
+        for plugin in &self.plugins {
+            plugin.before_build(paths).with_context(|| {
+                format!(
+                    "Plugin '{}' failed before the build",
+                    plugin.name()
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Runs every plugin's [`on_page`](Plugin::on_page) over the pages
+    /// of `content`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first plugin error.
+    pub fn on_pages(&self, content: &mut ContentSet) -> Result<()> {
+        if self.plugins.is_empty() {
+            return Ok(());
+        }
+        for page in &mut content.pages {
+            for plugin in &self.plugins {
+                plugin.on_page(page).with_context(|| {
+                    format!(
+                        "Plugin '{}' failed for {}",
+                        plugin.name(),
+                        page.relative.display()
+                    )
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs every plugin's [`after_render`](Plugin::after_render) over
+    /// the generated page of each page of `content`, rewriting the
+    /// files that change. Returns the number of files rewritten.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first plugin error, or an error if a page cannot be
+    /// read or written.
+    pub fn after_render(
+        &self,
+        content: &ContentSet,
+        paths: &Paths,
+    ) -> Result<usize> {
+        let mut rewritten = 0;
+        if self.plugins.is_empty() {
+            return Ok(rewritten);
+        }
+        for page in content.iter() {
+            let path = paths.site.join(page.output_path());
+            if !path.is_file() {
+                continue;
+            }
+            let original =
+                fs::read_to_string(&path).with_context(|| {
+                    format!("Failed to read {}", path.display())
+                })?;
+            let mut html = original.clone();
+            self.after_render_page(page, &mut html)?;
+            if html != original {
+                fs::write(&path, html).with_context(|| {
+                    format!("Failed to write {}", path.display())
+                })?;
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
+    }
+
+    /// Runs every plugin's [`after_render`](Plugin::after_render) over
+    /// the HTML of a single page.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first plugin error.
+    pub fn after_render_page(
+        &self,
+        page: &PageData,
+        html: &mut String,
+    ) -> Result<()> {
+        for plugin in &self.plugins {
+            plugin.after_render(page, html).with_context(|| {
+                format!(
+                    "Plugin '{}' failed for {}",
+                    plugin.name(),
+                    page.relative.display()
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Runs every plugin's [`after_build`](Plugin::after_build).
+    ///
+    /// # Errors
+    ///
+    /// Returns the first plugin error.
+    pub fn after_build(&self, paths: &Paths) -> Result<()> {
+        for plugin in &self.plugins {
+            plugin.after_build(paths).with_context(|| {
+                format!(
+                    "Plugin '{}' failed after the build",
+                    plugin.name()
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use tempfile::tempdir;
+
+    #[derive(Debug)]
+    struct Recorder;
+
+    impl Plugin for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn before_build(&self, paths: &Paths) -> Result<()> {
+            fs::create_dir_all(&paths.build)?;
+            Ok(())
+        }
+
+        fn on_page(&self, page: &mut PageData) -> Result<()> {
+            page.body.push_str("\nEdited.");
+            Ok(())
+        }
+
+        fn after_render(
+            &self,
+            page: &PageData,
+            html: &mut String,
+        ) -> Result<()> {
+            *html = html.replace(
+                "</body>",
+                &format!("<p>{}</p></body>", page.relative.display()),
+            );
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct Failing;
+
+    impl Plugin for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn after_build(&self, _paths: &Paths) -> Result<()> {
+            bail!("out of coffee")
+        }
+    }
+
+    #[test]
+    fn test_registry_runs_hooks() -> Result<()> {
+        let dir = tempdir()?;
+        let paths = Paths {
+            site: dir.path().join("public"),
+            content: dir.path().join("content"),
+            build: dir.path().join("build"),
+            template: dir.path().join("templates"),
+        };
+        fs::create_dir_all(paths.site.join("about"))?;
+        fs::create_dir_all(&paths.content)?;
+        fs::write(
+            paths.content.join("about.md"),
+            "---\ntitle: About\n---\nHi",
+        )?;
+        fs::write(
+            paths.site.join("about/index.html"),
+            "<html><body>Hi</body></html>",
+        )?;
+
+        let registry = PluginRegistry::new().with_plugin(Recorder);
+        assert_eq!(registry.names(), ["recorder"]);
+        registry.before_build(&paths)?;
+        assert!(paths.build.is_dir());
+
+        let mut content = ContentSet::load(&paths.content)?;
+        registry.on_pages(&mut content)?;
+        assert!(content.pages[0].body.ends_with("\nEdited."));
+
+        assert_eq!(registry.after_render(&content, &paths)?, 1);
+        assert_eq!(
+            fs::read_to_string(paths.site.join("about/index.html"))?,
+            "<html><body>Hi<p>about.md</p></body></html>"
+        );
+        registry.after_build(&paths)?;
+
+        let failing = PluginRegistry::new().with_plugin(Failing);
+        let error = failing.after_build(&paths).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Plugin 'failing' failed after the build"
+        );
+        assert_eq!(error.root_cause().to_string(), "out of coffee");
+        Ok(())
+    }
+}
@@ -0,0 +1,499 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # WebAssembly Plugins
+//!
+//! Runs [plugins](super) compiled to WebAssembly with
+//! [wasmtime](https://wasmtime.dev/), so site authors can ship portable
+//! content transforms without recompiling the generator. Enable with
+//! the `wasm` feature:
+//!
+//! ```toml
+//! ssg = { version = "0.0.32", features = ["wasm"] }
+//! ```
+//!
+//! A module exports its `memory` and any of these hooks, each taking no
+//! arguments and returning `0` on success:
+//!
+//! | Export | Input | Output |
+//! |--------|-------|--------|
+//! | `on_page` | the Markdown body | replaces the body |
+//! | `after_render` | the page HTML | replaces the HTML |
+//!
+//! Modules get no WASI, so no file system, network or clock access; the
+//! only host API is the `ssg` import module:
+//!
+//! | Import | Signature | Effect |
+//! |--------|-----------|--------|
+//! | `input_len` | `() -> i32` | length of the input |
+//! | `read_input` | `(ptr)` | copies the input to `ptr` |
+//! | `page_len` | `() -> i32` | length of the page JSON |
+//! | `read_page` | `(ptr)` | copies `{"path", "url", "frontmatter", "build", "env"}` to `ptr` |
+//! | `set_output` | `(ptr, len)` | sets the output of the hook |
+//! | `emit_file` | `(path_ptr, path_len, ptr, len) -> i32` | writes a file below the site directory after the build; `-1` if the path escapes it |
+//! | `warn` | `(ptr, len)` | reports a build warning |
+//!
+//! `build` and `env` hold the [build variables](crate::build_info) of
+//! plugins loaded with [`WasmPlugin::with_build_info`], and are `null`
+//! otherwise.
+//!
+//! Every call runs in a fresh instance bounded by [`WasmLimits`], so a
+//! runaway transform fails the build instead of hanging it.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::json;
+use std::{
+    fmt, fs,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
+use wasmtime::{
+    Caller, Config, Engine, Linker, Memory, Module, Store, StoreLimits,
+    StoreLimitsBuilder,
+};
+
+use super::{PageData, Plugin};
+use crate::{
+    build_info::BuildInfo, compiler::GeneratedOutput, diagnostics,
+    Paths,
+};
+
+/// Import module of the host API.
+pub const HOST_MODULE: &str = "ssg";
+
+/// Resource limits of one hook call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasmLimits {
+    /// Fuel, roughly the number of instructions a call may execute.
+    pub fuel: u64,
+    /// Largest linear memory a module may grow to, in bytes.
+    pub memory: usize,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 1_000_000_000,
+            memory: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// State of the store of one hook call.
+struct HostState {
+    name: String,
+    input: Vec<u8>,
+    page: Vec<u8>,
+    output: Option<Vec<u8>>,
+    files: Vec<GeneratedOutput>,
+    limits: StoreLimits,
+}
+
+/// A [`Plugin`] backed by a WebAssembly module.
+pub struct WasmPlugin {
+    name: String,
+    engine: Engine,
+    module: Module,
+    limits: WasmLimits,
+    build_info: Option<BuildInfo>,
+    files: Mutex<Vec<GeneratedOutput>>,
+}
+
+impl fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmPlugin")
+            .field("name", &self.name)
+            .field("limits", &self.limits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WasmPlugin {
+    /// Loads the module at `path`, named after its file stem.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid
+    /// module.
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| {
+            format!(
+                "Failed to read WebAssembly plugin {}",
+                path.display()
+            )
+        })?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        Self::from_bytes(&name, &bytes).with_context(|| {
+            format!("Invalid WebAssembly plugin {}", path.display())
+        })
+    }
+
+    /// Compiles a plugin from a binary module or its text format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` is not a valid module.
+    pub fn from_bytes(name: &str, bytes: &[u8]) -> Result<Self> {
+        let mut config = Config::new();
+        let _ = config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::new(&engine, bytes)?;
+        Ok(Self {
+            name: name.to_string(),
+            engine,
+            module,
+            limits: WasmLimits::default(),
+            build_info: None,
+            files: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Sets the limits of each hook call, returning the plugin for
+    /// chaining.
+    pub fn with_limits(mut self, limits: WasmLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Passes `build_info` to every hook call in the page JSON,
+    /// returning the plugin for chaining.
+    pub fn with_build_info(mut self, build_info: BuildInfo) -> Self {
+        self.build_info = Some(build_info);
+        self
+    }
+
+    /// Returns the limits of each hook call.
+    pub fn limits(&self) -> WasmLimits {
+        self.limits
+    }
+
+    /// Calls the `hook` export with `input`, returning its output, if
+    /// it set one. Returns `None` without a call if the module does not
+    /// export `hook`.
+    fn call(
+        &self,
+        hook: &str,
+        page: &PageData,
+        input: &str,
+    ) -> Result<Option<String>> {
+        if self.module.get_export(hook).is_none() {
+            return Ok(None);
+        }
+        let state = HostState {
+            name: self.name.clone(),
+            input: input.as_bytes().to_vec(),
+            page: json!({
+                "path": page.relative.to_string_lossy(),
+                "url": page.url_path(),
+                "frontmatter": page.frontmatter.to_json(),
+                "build": self.build_info.as_ref().map(BuildInfo::build_json),
+                "env": self.build_info.as_ref().map(BuildInfo::env_json),
+            })
+            .to_string()
+            .into_bytes(),
+            output: None,
+            files: Vec::new(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.limits.memory)
+                .build(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.limits.fuel)?;
+
+        let instance = host_linker(&self.engine)?
+            .instantiate(&mut store, &self.module)?;
+        let status = instance
+            .get_typed_func::<(), i32>(&mut store, hook)?
+            .call(&mut store, ())?;
+        if status != 0 {
+            bail!("`{}` returned status {}", hook, status);
+        }
+
+        let state = store.into_data();
+        self.files
+            .lock()
+            .map_err(|_| anyhow!("Emitted file list is poisoned"))?
+            .extend(state.files);
+        state
+            .output
+            .map(|output| {
+                String::from_utf8(output).map_err(|_| {
+                    anyhow!(
+                        "`{}` set an output that is not UTF-8",
+                        hook
+                    )
+                })
+            })
+            .transpose()
+    }
+}
+
+impl Plugin for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn on_page(&self, page: &mut PageData) -> Result<()> {
+        if let Some(body) = self.call("on_page", page, &page.body)? {
+            page.body = body;
+        }
+        Ok(())
+    }
+
+    fn after_render(
+        &self,
+        page: &PageData,
+        html: &mut String,
+    ) -> Result<()> {
+        if let Some(output) = self.call("after_render", page, html)? {
+            *html = output;
+        }
+        Ok(())
+    }
+
+    fn after_build(&self, paths: &Paths) -> Result<()> {
+        let files =
+            std::mem::take(&mut *self.files.lock().map_err(|_| {
+                anyhow!("Emitted file list is poisoned")
+            })?);
+        for file in files {
+            let target = paths.site.join(&file.path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).with_context(|| {
+                    format!("Failed to create {}", parent.display())
+                })?;
+            }
+            fs::write(&target, &file.contents).with_context(|| {
+                format!("Failed to write {}", target.display())
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns a linker with the `ssg` host API.
+fn host_linker(engine: &Engine) -> Result<Linker<HostState>> {
+    let mut linker = Linker::new(engine);
+    let _ = linker.func_wrap(
+        HOST_MODULE,
+        "input_len",
+        |caller: Caller<'_, HostState>| length(&caller.data().input),
+    )?;
+    let _ = linker.func_wrap(
+        HOST_MODULE,
+        "read_input",
+        |mut caller: Caller<'_, HostState>, ptr: i32| {
+            let input = caller.data().input.clone();
+            write_guest(&mut caller, ptr, &input)
+        },
+    )?;
+    let _ = linker.func_wrap(
+        HOST_MODULE,
+        "page_len",
+        |caller: Caller<'_, HostState>| length(&caller.data().page),
+    )?;
+    let _ = linker.func_wrap(
+        HOST_MODULE,
+        "read_page",
+        |mut caller: Caller<'_, HostState>, ptr: i32| {
+            let page = caller.data().page.clone();
+            write_guest(&mut caller, ptr, &page)
+        },
+    )?;
+    let _ = linker.func_wrap(
+        HOST_MODULE,
+        "set_output",
+        |mut caller: Caller<'_, HostState>,
+         ptr: i32,
+         len: i32|
+         -> Result<()> {
+            let output = read_guest(&mut caller, ptr, len)?;
+            caller.data_mut().output = Some(output);
+            Ok(())
+        },
+    )?;
+    let _ = linker.func_wrap(
+        HOST_MODULE,
+        "emit_file",
+        |mut caller: Caller<'_, HostState>,
+         path_ptr: i32,
+         path_len: i32,
+         ptr: i32,
+         len: i32|
+         -> Result<i32> {
+            let path = read_guest(&mut caller, path_ptr, path_len)?;
+            let path = PathBuf::from(String::from_utf8(path)?);
+            if escapes(&path) {
+                return Ok(-1);
+            }
+            let contents = read_guest(&mut caller, ptr, len)?;
+            caller
+                .data_mut()
+                .files
+                .push(GeneratedOutput::new(path, contents));
+            Ok(0)
+        },
+    )?;
+    let _ = linker.func_wrap(
+        HOST_MODULE,
+        "warn",
+        |mut caller: Caller<'_, HostState>,
+         ptr: i32,
+         len: i32|
+         -> Result<()> {
+            let message = read_guest(&mut caller, ptr, len)?;
+            diagnostics::warn(format!(
+                "Plugin '{}': {}",
+                caller.data().name,
+                String::from_utf8_lossy(&message)
+            ));
+            Ok(())
+        },
+    )?;
+    Ok(linker)
+}
+
+/// Returns the length of a host buffer as a guest `i32`.
+fn length(bytes: &[u8]) -> Result<i32> {
+    i32::try_from(bytes.len())
+        .map_err(|_| anyhow!("Buffer too large for the module"))
+}
+
+/// Returns the exported `memory` of the calling module.
+fn memory(caller: &mut Caller<'_, HostState>) -> Result<Memory> {
+    caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| anyhow!("Module does not export `memory`"))
+}
+
+/// Copies `len` bytes at `ptr` out of the guest memory.
+fn read_guest(
+    caller: &mut Caller<'_, HostState>,
+    ptr: i32,
+    len: i32,
+) -> Result<Vec<u8>> {
+    let mut bytes = vec![0; usize::try_from(len)?];
+    memory(caller)?.read(
+        &*caller,
+        usize::try_from(ptr)?,
+        &mut bytes,
+    )?;
+    Ok(bytes)
+}
+
+/// Copies `bytes` into the guest memory at `ptr`.
+fn write_guest(
+    caller: &mut Caller<'_, HostState>,
+    ptr: i32,
+    bytes: &[u8],
+) -> Result<()> {
+    memory(caller)?.write(
+        &mut *caller,
+        usize::try_from(ptr)?,
+        bytes,
+    )?;
+    Ok(())
+}
+
+/// Returns `true` if `path` is absolute or leaves its base directory.
+fn escapes(path: &Path) -> bool {
+    path.as_os_str().is_empty()
+        || path.is_absolute()
+        || path.components().any(|c| {
+            matches!(c, Component::ParentDir | Component::Prefix(_))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::ContentSet;
+    use tempfile::tempdir;
+
+    const REWRITER: &str = r#"
+        (module
+          (import "ssg" "input_len" (func $input_len (result i32)))
+          (import "ssg" "read_input" (func $read_input (param i32)))
+          (import "ssg" "set_output" (func $set_output (param i32 i32)))
+          (import "ssg" "emit_file"
+            (func $emit_file (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "Rewritten")
+          (data (i32.const 16) "notes/body.md")
+          (data (i32.const 32) "../escape.md")
+          (func (export "on_page") (result i32)
+            (call $read_input (i32.const 64))
+            (call $set_output (i32.const 0) (i32.const 9))
+            (drop (call $emit_file (i32.const 16) (i32.const 13)
+              (i32.const 64) (call $input_len)))
+            ;; The escaping path is rejected with -1, so this is 0.
+            (i32.add (i32.const 1)
+              (call $emit_file (i32.const 32) (i32.const 12)
+                (i32.const 64) (call $input_len)))))
+    "#;
+
+    const SPINNER: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "after_render") (result i32)
+            (loop $spin (br $spin))
+            (i32.const 0)))
+    "#;
+
+    #[test]
+    fn test_wasm_plugin_rewrites_and_emits() -> Result<()> {
+        let dir = tempdir()?;
+        let paths = Paths {
+            site: dir.path().join("public"),
+            content: dir.path().join("content"),
+            build: dir.path().join("build"),
+            template: dir.path().join("templates"),
+        };
+        fs::create_dir_all(&paths.content)?;
+        fs::write(paths.content.join("about.md"), "Original")?;
+        let mut content = ContentSet::load(&paths.content)?;
+
+        let plugin =
+            WasmPlugin::from_bytes("rewriter", REWRITER.as_bytes())?;
+        plugin.on_page(&mut content.pages[0])?;
+        assert_eq!(content.pages[0].body, "Rewritten");
+        let mut html = String::from("<p>Kept</p>");
+        plugin.after_render(&content.pages[0], &mut html)?;
+        assert_eq!(html, "<p>Kept</p>");
+
+        plugin.after_build(&paths)?;
+        assert_eq!(
+            fs::read_to_string(paths.site.join("notes/body.md"))?,
+            "Original"
+        );
+        assert!(!dir.path().join("escape.md").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_wasm_plugin_runs_out_of_fuel() -> Result<()> {
+        let mut content = ContentSet::default();
+        content.pages.push(PageData::from_source(
+            PathBuf::from("about.md"),
+            PathBuf::from("about.md"),
+            "About",
+        )?);
+        let plugin =
+            WasmPlugin::from_bytes("spinner", SPINNER.as_bytes())?
+                .with_limits(WasmLimits {
+                    fuel: 10_000,
+                    ..WasmLimits::default()
+                });
+        let mut html = String::new();
+        assert!(plugin
+            .after_render(&content.pages[0], &mut html)
+            .is_err());
+        assert!(WasmPlugin::from_bytes("broken", b"(module").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,545 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Deploy Previews
+//!
+//! Builds a site for a temporary preview URL, such as a pull request
+//! deploy preview, while it still describes itself as the production
+//! site. Pass `--preview-url` or set it in the configuration file:
+//!
+//! ```toml
+//! base_url = "https://example.com"
+//!
+//! [preview]
+//! base_url = "https://deploy-preview-42--example.netlify.app"
+//! fail_on_leak = true
+//! ```
+//!
+//! The site is built against the production `base_url` as usual, then:
+//!
+//! - every page without a canonical link gets one pointing to its
+//!   production URL, so search engines never treat the preview as the
+//!   original;
+//! - production URLs in the `href`, `src`, `srcset`, `action`, `poster`
+//!   and `data` attributes of pages and in stylesheets are rewritten to
+//!   the preview URL, so following links and loading assets stays on
+//!   the preview;
+//! - every other production URL left in pages, stylesheets, scripts and
+//!   JSON files is reported as a leak, since the preview would load or
+//!   link to production there. Canonical and `hreflang` links, `<meta>`
+//!   tags and JSON-LD describe the production site and are not leaks.
+//!
+//! Leaks are warnings unless `fail_on_leak` is set. Files matching an
+//! `ignore` prefix, such as a feed meant for production readers, are
+//! not checked.
+
+use anyhow::{bail, ensure, Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+use url::Url;
+
+use crate::{
+    collect_files_recursive,
+    content::ContentSet,
+    diagnostics,
+    page_meta::{self, PageMeta},
+};
+
+/// Extensions of the files checked for production URLs.
+const CHECKED_EXTENSIONS: &[&str] =
+    &["html", "css", "js", "mjs", "json", "webmanifest"];
+
+/// Longest URL quoted in a leak warning.
+const MAX_QUOTED_URL: usize = 80;
+
+/// Matches an opening tag.
+static TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<[a-z][^>]*>")
+        .unwrap_or_else(|e| panic!("Invalid tag regex: {}", e))
+});
+
+/// Matches tags that describe the production site: `<meta>` tags and
+/// canonical and alternate links.
+static PRODUCTION_TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?is)^<(?:meta\b|link\b[^>]*\brel\s*=\s*["']?(?:canonical|alternate)\b)"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid production tag regex: {}", e))
+});
+
+/// Matches a JSON-LD script block.
+static JSON_LD_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?is)<script\b[^>]*type\s*=\s*["']application/ld\+json["'][^>]*>.*?</script\s*>"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid JSON-LD regex: {}", e))
+});
+
+/// Matches a URL attribute of a tag and its quoted value.
+static URL_ATTR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)(\s(?:href|src|srcset|action|formaction|poster|data)\s*=\s*)("[^"]*"|'[^']*')"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid URL attribute regex: {}", e))
+});
+
+/// `[preview]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreviewOptions {
+    /// URL the preview is served from; previews are off when unset.
+    pub base_url: Option<String>,
+    /// Adds a canonical link to the production URL of every page that
+    /// has none.
+    pub canonical: bool,
+    /// Fails the build when a production URL leaks into the preview.
+    pub fail_on_leak: bool,
+    /// Site-relative path prefixes that are not checked for leaks.
+    pub ignore: Vec<String>,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            canonical: true,
+            fail_on_leak: false,
+            ignore: Vec::new(),
+        }
+    }
+}
+
+impl PreviewOptions {
+    /// Returns `true` if the site is built for a preview URL.
+    pub fn is_enabled(&self) -> bool {
+        self.base_url.is_some()
+    }
+
+    /// Checks that the preview URL is an absolute `http` or `https`
+    /// URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the preview URL is invalid.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(base_url) = &self.base_url {
+            let parsed = Url::parse(base_url).with_context(|| {
+                format!("Invalid preview base_url '{}'", base_url)
+            })?;
+            ensure!(
+                matches!(parsed.scheme(), "http" | "https")
+                    && parsed.host_str().is_some(),
+                "Preview base_url must be an http or https URL, got '{}'",
+                base_url
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A production URL found in a preview build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leak {
+    /// Site-relative path of the file.
+    pub path: PathBuf,
+    /// One-based line of the URL.
+    pub line: usize,
+    /// The URL, shortened to 80 characters.
+    pub url: String,
+}
+
+/// What [`apply_preview`] changed and found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreviewSummary {
+    /// Pages given a canonical link to production.
+    pub canonical: usize,
+    /// Files whose production URLs were rewritten.
+    pub rewritten: usize,
+    /// Production URLs left in the preview.
+    pub leaks: Vec<Leak>,
+}
+
+/// Matches the URLs of one site, over `http` or `https`.
+#[derive(Debug, Clone)]
+pub struct SiteUrl {
+    base: String,
+    pattern: Regex,
+}
+
+impl SiteUrl {
+    /// Creates the matcher for the site at `base_url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `base_url` is not an absolute URL with a
+    /// host.
+    pub fn new(base_url: &str) -> Result<Self> {
+        let parsed = Url::parse(base_url).with_context(|| {
+            format!("Invalid base URL '{}'", base_url)
+        })?;
+        let host = match parsed.host_str() {
+            Some(host) => host,
+            None => bail!("Base URL '{}' has no host", base_url),
+        };
+        let mut authority = regex::escape(host);
+        if let Some(port) = parsed.port() {
+            authority.push_str(&format!(":{}", port));
+        }
+        let path = parsed.path().trim_end_matches('/');
+        let pattern = Regex::new(&format!(
+            r#"(?i)https?://{}{}(?:[/"'?#\s),;]|$)"#,
+            authority,
+            regex::escape(path)
+        ))
+        .with_context(|| format!("Invalid base URL '{}'", base_url))?;
+        Ok(Self {
+            base: base_url.trim_end_matches('/').to_string(),
+            pattern,
+        })
+    }
+
+    /// Replaces the URLs of this site in `text` with the same paths
+    /// below `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::preview::SiteUrl;
+    ///
+    /// let site = SiteUrl::new("https://example.com").unwrap();
+    /// assert_eq!(
+    ///     site.rebase(
+    ///         "http://example.com/about/ https://example.com.au/",
+    ///         "https://preview.example.dev",
+    ///     ),
+    ///     "https://preview.example.dev/about/ https://example.com.au/"
+    /// );
+    /// ```
+    pub fn rebase(&self, text: &str, other: &str) -> String {
+        let other = other.trim_end_matches('/');
+        self.pattern
+            .replace_all(text, |caps: &Captures<'_>| {
+                let matched = &caps[0];
+                let boundary = matched
+                    .chars()
+                    .last()
+                    .filter(|c| !c.is_ascii_alphanumeric())
+                    .map_or(String::new(), String::from);
+                format!("{}{}", other, boundary)
+            })
+            .into_owned()
+    }
+
+    /// Returns the byte ranges of the URLs of this site in `text`.
+    pub fn find(&self, text: &str) -> Vec<Range<usize>> {
+        self.pattern
+            .find_iter(text)
+            .map(|found| {
+                let end = text[found.start()..]
+                    .find(|c: char| {
+                        c.is_whitespace()
+                            || matches!(c, '"' | '\'' | ')' | '<' | ',')
+                    })
+                    .map_or(text.len(), |offset| {
+                        found.start() + offset
+                    });
+                found.start()..end
+            })
+            .collect()
+    }
+
+    /// The base URL, without a trailing slash.
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+}
+
+/// Rewrites the production URLs in the URL attributes of `html` to
+/// `preview`, leaving the tags that describe the production site alone.
+pub fn rewrite_html(
+    html: &str,
+    production: &SiteUrl,
+    preview: &str,
+) -> String {
+    TAG_RE
+        .replace_all(html, |tag: &Captures<'_>| {
+            let tag = &tag[0];
+            if PRODUCTION_TAG_RE.is_match(tag) {
+                return tag.to_string();
+            }
+            URL_ATTR_RE
+                .replace_all(tag, |attr: &Captures<'_>| {
+                    format!(
+                        "{}{}",
+                        &attr[1],
+                        production.rebase(&attr[2], preview)
+                    )
+                })
+                .into_owned()
+        })
+        .into_owned()
+}
+
+/// Returns the production URLs left in `text` as one-based lines and
+/// URLs. In HTML, production tags and JSON-LD are skipped.
+pub fn find_leaks(
+    text: &str,
+    production: &SiteUrl,
+    is_html: bool,
+) -> Vec<(usize, String)> {
+    let exempt: Vec<Range<usize>> = if is_html {
+        TAG_RE
+            .find_iter(text)
+            .filter(|tag| PRODUCTION_TAG_RE.is_match(tag.as_str()))
+            .chain(JSON_LD_RE.find_iter(text))
+            .map(|found| found.range())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    production
+        .find(text)
+        .into_iter()
+        .filter(|url| {
+            !exempt.iter().any(|range| range.contains(&url.start))
+        })
+        .map(|url| {
+            let line = text[..url.start].matches('\n').count() + 1;
+            let quoted: String =
+                text[url].chars().take(MAX_QUOTED_URL).collect();
+            (line, quoted)
+        })
+        .collect()
+}
+
+/// Turns the build in `site_dir` into a preview of the production site
+/// at `production_url`: adds canonical links to the pages of `content`,
+/// rewrites production URLs to the preview URL and reports the ones
+/// left over.
+///
+/// # Errors
+///
+/// Returns an error if a URL is invalid, a file cannot be read or
+/// written, or production URLs leak and `fail_on_leak` is set.
+pub fn apply_preview(
+    site_dir: &Path,
+    content: &ContentSet,
+    production_url: &str,
+    options: &PreviewOptions,
+) -> Result<PreviewSummary> {
+    let mut summary = PreviewSummary::default();
+    let preview = match &options.base_url {
+        Some(preview) => preview.trim_end_matches('/'),
+        None => return Ok(summary),
+    };
+    options.validate()?;
+    let production = SiteUrl::new(production_url)?;
+
+    if options.canonical {
+        for page in content.iter() {
+            let path = site_dir.join(page.output_path());
+            if !path.is_file() {
+                continue;
+            }
+            let html =
+                fs::read_to_string(&path).with_context(|| {
+                    format!("Failed to read {}", path.display())
+                })?;
+            if page_meta::has_canonical(&html) {
+                continue;
+            }
+            let meta = PageMeta {
+                canonical: Some(format!(
+                    "{}{}",
+                    production.base(),
+                    page.url_path()
+                )),
+                robots: Vec::new(),
+            };
+            if let Some(updated) =
+                page_meta::insert_page_meta(&html, &meta)
+            {
+                fs::write(&path, updated).with_context(|| {
+                    format!("Failed to write {}", path.display())
+                })?;
+                summary.canonical += 1;
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    files.sort();
+    for path in files {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        let relative = path.strip_prefix(site_dir).unwrap_or(&path);
+        let relative_str =
+            relative.to_string_lossy().replace('\\', "/");
+        if !CHECKED_EXTENSIONS.contains(&extension.as_str())
+            || options
+                .ignore
+                .iter()
+                .any(|prefix| relative_str.starts_with(prefix.as_str()))
+        {
+            continue;
+        }
+        let text = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let rewritten = match extension.as_str() {
+            "html" => rewrite_html(&text, &production, preview),
+            "css" => production.rebase(&text, preview),
+            _ => text.clone(),
+        };
+        if rewritten != text {
+            fs::write(&path, &rewritten).with_context(|| {
+                format!("Failed to write {}", path.display())
+            })?;
+            summary.rewritten += 1;
+        }
+        for (line, url) in
+            find_leaks(&rewritten, &production, extension == "html")
+        {
+            summary.leaks.push(Leak {
+                path: relative.to_path_buf(),
+                line,
+                url,
+            });
+        }
+    }
+
+    for leak in &summary.leaks {
+        diagnostics::warn(format!(
+            "{}:{}: production URL {} in a preview build",
+            leak.path.display(),
+            leak.line,
+            leak.url
+        ));
+    }
+    if options.fail_on_leak && !summary.leaks.is_empty() {
+        bail!(
+            "{} production URL{} leaked into the preview of {}",
+            summary.leaks.len(),
+            if summary.leaks.len() == 1 { "" } else { "s" },
+            production.base()
+        );
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const PRODUCTION: &str = "https://example.com/";
+    const PREVIEW: &str = "https://pr-7.example.dev";
+
+    #[test]
+    fn test_rewrite_html_keeps_production_tags() -> Result<()> {
+        let production = SiteUrl::new(PRODUCTION)?;
+        let html = r#"<head>
+<link rel="canonical" href="https://example.com/about/">
+<meta property="og:url" content="https://example.com/about/">
+<link rel="stylesheet" href="https://example.com/style.css">
+<script type="application/ld+json">{"url":"https://example.com/about/"}</script>
+</head><body>
+<a href="https://example.com/blog/">Blog</a>
+<img srcset="https://example.com/a.png 1x, https://example.com/b.png 2x">
+<a href="https://example.community/">Elsewhere</a>
+<script>fetch("https://example.com/api.json")</script>
+</body>"#;
+        let rewritten = rewrite_html(html, &production, PREVIEW);
+        assert!(rewritten
+            .contains(r#"<link rel="canonical" href="https://example.com/about/">"#));
+        assert!(rewritten
+            .contains(r#"content="https://example.com/about/""#));
+        assert!(rewritten
+            .contains(r#"href="https://pr-7.example.dev/style.css""#));
+        assert!(rewritten
+            .contains(r#"href="https://pr-7.example.dev/blog/""#));
+        assert!(rewritten.contains(
+            "https://pr-7.example.dev/a.png 1x, https://pr-7.example.dev/b.png 2x"
+        ));
+        assert!(rewritten.contains("https://example.community/"));
+
+        let leaks = find_leaks(&rewritten, &production, true);
+        assert_eq!(
+            leaks,
+            vec![(10, "https://example.com/api.json".to_string())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_preview() -> Result<()> {
+        let dir = tempdir()?;
+        let site = dir.path().join("public");
+        let content_dir = dir.path().join("content");
+        fs::create_dir_all(site.join("about"))?;
+        fs::create_dir_all(&content_dir)?;
+        fs::write(
+            content_dir.join("about.md"),
+            "---\ntitle: About\n---\n",
+        )?;
+        fs::write(
+            site.join("about/index.html"),
+            "<html><head></head><body><a href=\"https://example.com/\">Home</a></body></html>",
+        )?;
+        fs::write(
+            site.join("style.css"),
+            "body{background:url(https://example.com/bg.png)}",
+        )?;
+        fs::write(
+            site.join("app.js"),
+            "const api = 'https://example.com/api';",
+        )?;
+        fs::write(
+            site.join("feed.json"),
+            "{\"home\":\"https://example.com/\"}",
+        )?;
+
+        let content = ContentSet::load(&content_dir)?;
+        let options = PreviewOptions {
+            base_url: Some(PREVIEW.to_string()),
+            ignore: vec!["feed.json".to_string()],
+            ..PreviewOptions::default()
+        };
+        let summary =
+            apply_preview(&site, &content, PRODUCTION, &options)?;
+        assert_eq!(summary.canonical, 1);
+        assert_eq!(summary.rewritten, 2);
+        assert_eq!(summary.leaks.len(), 1);
+        assert_eq!(summary.leaks[0].path, PathBuf::from("app.js"));
+
+        let page = fs::read_to_string(site.join("about/index.html"))?;
+        assert!(page.contains(
+            "<link rel=\"canonical\" href=\"https://example.com/about/\" />"
+        ));
+        assert!(page.contains("href=\"https://pr-7.example.dev/\""));
+        assert_eq!(
+            fs::read_to_string(site.join("style.css"))?,
+            "body{background:url(https://pr-7.example.dev/bg.png)}"
+        );
+
+        let strict = PreviewOptions {
+            fail_on_leak: true,
+            ..options
+        };
+        assert!(apply_preview(&site, &content, PRODUCTION, &strict)
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Build Profiles
+//!
+//! Switches the settings that differ between working on a site and
+//! publishing it in one go, with `--profile dev` or `--profile prod`:
+//!
+//! | Setting | `dev` | `prod` |
+//! |---------|-------|--------|
+//! | `base_url` | The development server | As configured |
+//! | Minification (`[minify]`) | Off | Every format |
+//! | Pages marked `draft: true` (`drafts`) | Built | Left out |
+//! | Precompression (`[compress]`) | Off | On |
+//! | Stylesheet source maps (`source_maps`) | Written | Left out |
+//!
+//! The `[profiles]` tables of the configuration file change what a
+//! profile sets:
+//!
+//! ```toml
+//! [profiles.dev]
+//! base_url = "http://localhost:3000"
+//!
+//! [profiles.prod]
+//! base_url = "https://example.com"
+//! compress = false
+//! ```
+//!
+//! A profile replaces the `[minify]` switches, `source_maps` and
+//! `[compress].enabled`, and keeps the other options of those tables.
+//! Flags such as `--preview-url` still apply on top of it. Without
+//! `--profile`, the configuration is used as written.
+
+use anyhow::{bail, ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use url::Url;
+
+/// A build profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// Working on the site with the development server.
+    Dev,
+    /// Publishing the site.
+    Prod,
+}
+
+impl FromStr for Profile {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "dev" | "development" => Ok(Self::Dev),
+            "prod" | "production" => Ok(Self::Prod),
+            _ => bail!("unknown profile `{}`, use dev or prod", name),
+        }
+    }
+}
+
+/// Settings of one profile in the `[profiles]` config table; unset
+/// ones take the default of the profile.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct ProfileOptions {
+    /// Base URL of the site.
+    pub base_url: Option<String>,
+    /// Minifies every format, or none.
+    pub minify: Option<bool>,
+    /// Builds pages marked `draft: true`.
+    pub drafts: Option<bool>,
+    /// Writes the precompressed copies.
+    pub compress: Option<bool>,
+    /// Writes source maps of the compiled stylesheets.
+    pub source_maps: Option<bool>,
+}
+
+/// Options of the `[profiles]` config table.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct ProfilesOptions {
+    /// Settings of the `dev` profile.
+    pub dev: ProfileOptions,
+    /// Settings of the `prod` profile.
+    pub prod: ProfileOptions,
+}
+
+impl ProfilesOptions {
+    /// Checks that the base URLs are absolute URLs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        for (name, options) in
+            [("dev", &self.dev), ("prod", &self.prod)]
+        {
+            if let Some(url) = &options.base_url {
+                ensure!(
+                    Url::parse(url).is_ok(),
+                    "{}.base_url `{}` must be an absolute URL",
+                    name,
+                    url
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the settings of `profile`.
+    pub fn get(&self, profile: Profile) -> &ProfileOptions {
+        match profile {
+            Profile::Dev => &self.dev,
+            Profile::Prod => &self.prod,
+        }
+    }
+}
+
+/// The settings a profile applies to the configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSettings {
+    /// Base URL of the site, or `None` to keep the configured one.
+    pub base_url: Option<String>,
+    /// Minifies every format, or none.
+    pub minify: bool,
+    /// Builds pages marked `draft: true`.
+    pub drafts: bool,
+    /// Writes the precompressed copies.
+    pub compress: bool,
+    /// Writes source maps of the compiled stylesheets.
+    pub source_maps: bool,
+}
+
+impl Profile {
+    /// Returns the settings of the profile, taking those that `options`
+    /// leaves unset from its defaults. `server_url` is the default base
+    /// URL of `dev`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::profiles::{Profile, ProfilesOptions};
+    ///
+    /// let settings = Profile::Prod.settings(
+    ///     &ProfilesOptions::default(),
+    ///     "http://127.0.0.1:8000",
+    /// );
+    /// assert!(settings.minify && settings.compress);
+    /// assert!(!settings.drafts);
+    /// assert_eq!(settings.base_url, None);
+    /// ```
+    pub fn settings(
+        self,
+        options: &ProfilesOptions,
+        server_url: &str,
+    ) -> ProfileSettings {
+        let options = options.get(self);
+        let prod = self == Self::Prod;
+        ProfileSettings {
+            base_url: options
+                .base_url
+                .clone()
+                .or_else(|| (!prod).then(|| server_url.to_string())),
+            minify: options.minify.unwrap_or(prod),
+            drafts: options.drafts.unwrap_or(!prod),
+            compress: options.compress.unwrap_or(prod),
+            source_maps: options.source_maps.unwrap_or(!prod),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_with_overrides() {
+        let options: ProfilesOptions = toml::from_str(
+            r#"
+            [dev]
+            minify = true
+
+            [prod]
+            base_url = "https://example.com"
+            compress = false
+            "#,
+        )
+        .unwrap();
+        assert!(options.validate().is_ok());
+
+        let server = "http://127.0.0.1:8000";
+        assert_eq!(
+            Profile::Dev.settings(&options, server),
+            ProfileSettings {
+                base_url: Some(server.into()),
+                minify: true,
+                drafts: true,
+                compress: false,
+                source_maps: true,
+            }
+        );
+        assert_eq!(
+            Profile::Prod.settings(&options, server),
+            ProfileSettings {
+                base_url: Some("https://example.com".into()),
+                minify: true,
+                drafts: false,
+                compress: false,
+                source_maps: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_and_validate() {
+        assert_eq!(
+            "production".parse::<Profile>().unwrap(),
+            Profile::Prod
+        );
+        assert_eq!("dev".parse::<Profile>().unwrap(), Profile::Dev);
+        assert!("staging".parse::<Profile>().is_err());
+
+        let mut options = ProfilesOptions::default();
+        options.dev.base_url = Some("localhost".into());
+        assert!(options.validate().is_err());
+    }
+}
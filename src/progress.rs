@@ -0,0 +1,237 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Progress Reporting
+//!
+//! Stages that work through many files, or through one large file,
+//! report how much of the work they found is done, sized up front from
+//! the files they discovered:
+//!
+//! - on a terminal, as a progress bar on standard error;
+//! - when standard error is not a terminal, as in CI or when piped, as
+//!   plain log lines, one for every tenth of the work, so logs are not
+//!   filled with escape codes;
+//! - with `--quiet`, not at all.
+//!
+//! ```
+//! use ssg::progress::Progress;
+//!
+//! let progress = Progress::new("Copying", 3);
+//! for _ in 0..3 {
+//!     progress.inc(1);
+//! }
+//! progress.finish();
+//! assert_eq!(progress.position(), 3);
+//! ```
+
+use indicatif::{
+    HumanBytes, ProgressBar, ProgressDrawTarget, ProgressStyle,
+};
+use log::info;
+
+use crate::cmd::Verbosity;
+
+/// Number of log lines of a plain report, at most.
+const PLAIN_STEPS: u64 = 10;
+
+/// Where a report goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// Nowhere, as with `--quiet`.
+    Hidden,
+    /// To a progress bar on the terminal.
+    Bar,
+    /// To plain log lines.
+    Plain,
+}
+
+impl ProgressMode {
+    /// Returns the mode for the current verbosity and standard error.
+    pub fn detect() -> Self {
+        if !Verbosity::current().shows_progress() {
+            Self::Hidden
+        } else if ProgressDrawTarget::stderr().is_hidden() {
+            Self::Plain
+        } else {
+            Self::Bar
+        }
+    }
+}
+
+/// What a report counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Files,
+    Bytes,
+}
+
+/// Progress of a stage over a known amount of work.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    /// Position and length, drawn only in [`ProgressMode::Bar`].
+    bar: ProgressBar,
+    /// Name of the stage.
+    label: String,
+    /// Where the report goes.
+    mode: ProgressMode,
+    /// What is counted.
+    unit: Unit,
+}
+
+impl Progress {
+    /// Reports on `len` files, in the [detected](ProgressMode::detect)
+    /// mode.
+    pub fn new(label: &str, len: u64) -> Self {
+        Self::with_mode(label, len, ProgressMode::detect())
+    }
+
+    /// Reports on `len` bytes, in the detected mode.
+    pub fn bytes(label: &str, len: u64) -> Self {
+        let mut progress = Self::new(label, len);
+        progress.unit = Unit::Bytes;
+        if let Ok(style) = ProgressStyle::default_bar().template(
+            "{msg} [{bar:40.cyan/blue}] {bytes}/{total_bytes}",
+        ) {
+            progress.bar.set_style(style.progress_chars("#>-"));
+        }
+        progress
+    }
+
+    /// Reports on nothing, for work nobody watches.
+    pub fn hidden() -> Self {
+        Self::with_mode("", 0, ProgressMode::Hidden)
+    }
+
+    /// Reports on `len` files in `mode`.
+    pub fn with_mode(
+        label: &str,
+        len: u64,
+        mode: ProgressMode,
+    ) -> Self {
+        let bar = ProgressBar::with_draw_target(
+            Some(len),
+            if mode == ProgressMode::Bar {
+                ProgressDrawTarget::stderr()
+            } else {
+                ProgressDrawTarget::hidden()
+            },
+        );
+        if let Ok(style) = ProgressStyle::default_bar().template(
+            "{msg} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len}",
+        ) {
+            bar.set_style(style.progress_chars("#>-"));
+        }
+        bar.set_message(label.to_string());
+        Self {
+            bar,
+            label: label.to_string(),
+            mode,
+            unit: Unit::Files,
+        }
+    }
+
+    /// Returns where the report goes.
+    pub fn mode(&self) -> ProgressMode {
+        self.mode
+    }
+
+    /// Returns the work done so far.
+    pub fn position(&self) -> u64 {
+        self.bar.position()
+    }
+
+    /// Returns the work to do.
+    pub fn length(&self) -> u64 {
+        self.bar.length().unwrap_or(0)
+    }
+
+    /// Records `delta` more work done, logging a line when a plain
+    /// report passes another tenth of it.
+    pub fn inc(&self, delta: u64) {
+        self.bar.inc(delta);
+        let position = self.position();
+        if self.mode == ProgressMode::Plain
+            && crosses_step(
+                position.saturating_sub(delta),
+                position,
+                self.length(),
+            )
+        {
+            self.log(position);
+        }
+    }
+
+    /// Ends the report, logging where it stopped if that is short of
+    /// its length.
+    pub fn finish(&self) {
+        let position = self.position();
+        match self.mode {
+            ProgressMode::Bar => self.bar.finish_and_clear(),
+            ProgressMode::Plain if position < self.length() => {
+                self.log(position)
+            }
+            _ => {}
+        }
+    }
+
+    /// Logs the line of a plain report at `position`.
+    fn log(&self, position: u64) {
+        match self.unit {
+            Unit::Files => info!(
+                "{}: {}/{} files",
+                self.label,
+                position,
+                self.length()
+            ),
+            Unit::Bytes => info!(
+                "{}: {}/{}",
+                self.label,
+                HumanBytes(position),
+                HumanBytes(self.length())
+            ),
+        }
+    }
+}
+
+/// Returns `true` if going from `before` to `after` out of `len` passes
+/// one of the [`PLAIN_STEPS`] steps of the work.
+fn crosses_step(before: u64, after: u64, len: u64) -> bool {
+    len > 0
+        && after > before
+        && before * PLAIN_STEPS / len
+            != after.min(len) * PLAIN_STEPS / len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_reports_log_every_tenth() {
+        let logged = (0..100u64)
+            .filter(|done| crosses_step(*done, done + 1, 100))
+            .count();
+        assert_eq!(logged, 10);
+        assert!(crosses_step(0, 3, 3));
+        assert!(crosses_step(1, 2, 3));
+        assert!(!crosses_step(0, 0, 3));
+        assert!(!crosses_step(0, 1, 0));
+        assert!(!crosses_step(5, 6, 1000));
+    }
+
+    #[test]
+    fn test_progress_counts_the_work_done() {
+        let progress =
+            Progress::with_mode("Copying", 4, ProgressMode::Plain);
+        progress.inc(1);
+        progress.inc(2);
+        assert_eq!((progress.position(), progress.length()), (3, 4));
+        progress.finish();
+        assert_eq!(progress.mode(), ProgressMode::Plain);
+
+        let hidden = Progress::hidden();
+        hidden.inc(1);
+        assert_eq!(hidden.length(), 0);
+        assert_eq!(hidden.mode(), ProgressMode::Hidden);
+    }
+}
@@ -0,0 +1,385 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Password-Protected Pages
+//!
+//! Encrypts the body of a page at build time, so semi-private content
+//! can be published on static hosting. A page opts in with a password
+//! in its frontmatter:
+//!
+//! ```yaml
+//! ---
+//! title: Team notes
+//! protected: correct horse battery staple
+//! ---
+//! ```
+//!
+//! The rendered `<body>` is encrypted with AES-256-GCM under a key
+//! derived from the password with PBKDF2-HMAC-SHA256 and a random salt,
+//! and replaced with a password form. A small inline script derives the
+//! same key with the browser's Web Crypto API and swaps the decrypted
+//! body in; the plain text never reaches the server. The `<head>`,
+//! including the title, stays readable.
+//!
+//! Protected pages get a `noindex` robots tag and are left out of the
+//! sitemap, the search index and `llms.txt`. The `[protect]` table of
+//! the configuration file tunes the form and the key derivation:
+//!
+//! ```toml
+//! [protect]
+//! iterations = 310000
+//! prompt = "Enter the password to read this page."
+//! ```
+//!
+//! This is as strong as the password: anyone can download the page and
+//! try passwords offline, so use a long one. Scripts in the protected
+//! body do not run once it is decrypted, and the password must not be
+//! printed by a template, since templates see the frontmatter.
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::{fs, path::Path};
+
+use crate::{
+    content::{ContentSet, Page},
+    template::{escape_html, hex},
+};
+
+/// Frontmatter key holding the password of a page.
+pub const PROTECTED_KEY: &str = "protected";
+
+/// PBKDF2 iterations used when none are configured.
+pub const DEFAULT_ITERATIONS: u32 = 310_000;
+
+/// Length of the PBKDF2 salt in bytes.
+const SALT_LEN: usize = 16;
+
+/// Length of an AES-GCM nonce in bytes.
+const NONCE_LEN: usize = 12;
+
+/// Decrypts the body when the form is submitted, reading the sealed
+/// body from the `ssg-protected-data` script.
+const UNLOCK_JS: &str = r#"(function(){var d=JSON.parse(document.getElementById("ssg-protected-data").textContent);function h(s){var b=new Uint8Array(s.length/2);for(var i=0;i<b.length;i++)b[i]=parseInt(s.substr(i*2,2),16);return b}document.getElementById("ssg-protected").addEventListener("submit",function(e){e.preventDefault();var p=new TextEncoder().encode(document.getElementById("ssg-password").value);crypto.subtle.importKey("raw",p,"PBKDF2",false,["deriveKey"]).then(function(k){return crypto.subtle.deriveKey({name:"PBKDF2",salt:h(d.salt),iterations:d.iterations,hash:"SHA-256"},k,{name:"AES-GCM",length:256},false,["decrypt"])}).then(function(k){return crypto.subtle.decrypt({name:"AES-GCM",iv:h(d.nonce)},k,h(d.ciphertext))}).then(function(b){document.body.innerHTML=new TextDecoder().decode(b)},function(){document.getElementById("ssg-protected-error").hidden=false})})})();"#;
+
+/// Matches the body of a document, with its opening and closing tags.
+static BODY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)(<body\b[^>]*>)(.*)(</body\s*>)")
+        .unwrap_or_else(|e| panic!("Invalid body regex: {}", e))
+});
+
+/// Options of the `[protect]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProtectOptions {
+    /// PBKDF2 iterations deriving the key from the password.
+    pub iterations: u32,
+    /// Label of the password field.
+    pub prompt: String,
+}
+
+impl Default for ProtectOptions {
+    fn default() -> Self {
+        Self {
+            iterations: DEFAULT_ITERATIONS,
+            prompt:
+                "This page is protected. Enter the password to read it."
+                    .to_string(),
+        }
+    }
+}
+
+impl ProtectOptions {
+    /// Checks that the iteration count is between 10,000 and
+    /// 10,000,000 and that the prompt is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            (10_000..=10_000_000).contains(&self.iterations),
+            "iterations must be between 10000 and 10000000, got {}",
+            self.iterations
+        );
+        ensure!(!self.prompt.trim().is_empty(), "prompt must be set");
+        Ok(())
+    }
+}
+
+/// A body encrypted by [`seal`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedBody {
+    /// PBKDF2 salt, in hex.
+    pub salt: String,
+    /// PBKDF2 iterations.
+    pub iterations: u32,
+    /// AES-GCM nonce, in hex.
+    pub nonce: String,
+    /// Ciphertext followed by the authentication tag, in hex.
+    pub ciphertext: String,
+}
+
+/// Returns `true` if `page` has a `protected` password.
+pub fn is_protected(page: &Page) -> bool {
+    !matches!(
+        page.frontmatter.get(PROTECTED_KEY),
+        None | Some(Value::Null) | Some(Value::Bool(false))
+    )
+}
+
+/// Returns the password of `page`, or `None` if it is not protected.
+///
+/// # Errors
+///
+/// Returns an error if `protected` is set but is not a password.
+pub fn password(page: &Page) -> Result<Option<String>> {
+    if !is_protected(page) {
+        return Ok(None);
+    }
+    match page.frontmatter.get(PROTECTED_KEY) {
+        Some(Value::String(password))
+            if !password.trim().is_empty() =>
+        {
+            Ok(Some(password.clone()))
+        }
+        Some(Value::Number(password)) => Ok(Some(password.to_string())),
+        _ => bail!(
+            "`{}` in {} must be the password of the page",
+            PROTECTED_KEY,
+            page.source.display()
+        ),
+    }
+}
+
+/// Encrypts `plaintext` with a key derived from `password`, under a
+/// fresh salt and nonce.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::protect::{open, seal};
+///
+/// let sealed = seal("<p>Secret</p>", "hunter2", 10_000)?;
+/// assert_eq!(open(&sealed, "hunter2")?, "<p>Secret</p>");
+/// assert!(open(&sealed, "hunter3").is_err());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if encryption fails.
+pub fn seal(
+    plaintext: &str,
+    password: &str,
+    iterations: u32,
+) -> Result<SealedBody> {
+    let mut salt = [0; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher(password, &salt, iterations)
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| anyhow!("Failed to encrypt page"))?;
+    Ok(SealedBody {
+        salt: hex(&salt),
+        iterations,
+        nonce: hex(&nonce),
+        ciphertext: hex(&ciphertext),
+    })
+}
+
+/// Decrypts a body sealed by [`seal`], as the page script does.
+///
+/// # Errors
+///
+/// Returns an error if the password is wrong or the data is corrupted.
+pub fn open(sealed: &SealedBody, password: &str) -> Result<String> {
+    let salt = unhex(&sealed.salt)?;
+    let nonce = unhex(&sealed.nonce)?;
+    ensure!(nonce.len() == NONCE_LEN, "invalid nonce");
+    let plaintext = cipher(password, &salt, sealed.iterations)
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            &*unhex(&sealed.ciphertext)?,
+        )
+        .map_err(|_| anyhow!("wrong password or corrupted page"))?;
+    String::from_utf8(plaintext).context("decrypted page is not UTF-8")
+}
+
+/// Replaces the body of `html` with the password form and the sealed
+/// body. Returns `None` if the document has no body.
+///
+/// # Errors
+///
+/// Returns an error if encryption fails.
+pub fn protect_page(
+    html: &str,
+    password: &str,
+    options: &ProtectOptions,
+) -> Result<Option<String>> {
+    let caps = match BODY_RE.captures(html) {
+        Some(caps) => caps,
+        None => return Ok(None),
+    };
+    let (whole, body) = match (caps.get(0), caps.get(2)) {
+        (Some(whole), Some(body)) => (whole, body),
+        _ => return Ok(None),
+    };
+    let sealed = seal(body.as_str(), password, options.iterations)?;
+    // `<` is escaped so the data cannot close its script element.
+    let data = serde_json::to_string(&sealed)?.replace('<', "\\u003c");
+    Ok(Some(format!(
+        "{}{}\n\
+         <form id=\"ssg-protected\" class=\"protected\">\n\
+         <label for=\"ssg-password\">{}</label>\n\
+         <input type=\"password\" id=\"ssg-password\" autocomplete=\"current-password\" required>\n\
+         <button type=\"submit\">Unlock</button>\n\
+         <p id=\"ssg-protected-error\" role=\"alert\" hidden>Wrong password.</p>\n\
+         </form>\n\
+         <script type=\"application/json\" id=\"ssg-protected-data\">{}</script>\n\
+         <script>{}</script>\n\
+         {}{}",
+        &html[..whole.start()],
+        &caps[1],
+        escape_html(&options.prompt),
+        data,
+        UNLOCK_JS,
+        &caps[3],
+        &html[whole.end()..]
+    )))
+}
+
+/// Encrypts the generated page of every protected page of `content`.
+///
+/// Returns the number of pages encrypted.
+///
+/// # Errors
+///
+/// Returns an error if a password is invalid or a page cannot be read,
+/// encrypted or written.
+pub fn protect_site(
+    content: &ContentSet,
+    site_dir: &Path,
+    options: &ProtectOptions,
+) -> Result<usize> {
+    let mut protected = 0;
+    for page in content.iter() {
+        let password = match password(page)? {
+            Some(password) => password,
+            None => continue,
+        };
+        let path = site_dir.join(page.output_path());
+        if !path.is_file() {
+            continue;
+        }
+        let html = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let sealed = protect_page(&html, &password, options)?
+            .with_context(|| {
+                format!("{} has no <body> to protect", path.display())
+            })?;
+        fs::write(&path, sealed).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        protected += 1;
+    }
+    Ok(protected)
+}
+
+/// Derives the AES key from `password`.
+fn cipher(password: &str, salt: &[u8], iterations: u32) -> Aes256Gcm {
+    let mut key = [0; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(
+        password.as_bytes(),
+        salt,
+        iterations,
+        &mut key,
+    );
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+}
+
+/// Decodes lowercase or uppercase hex.
+fn unhex(text: &str) -> Result<Vec<u8>> {
+    ensure!(text.len() % 2 == 0, "invalid hex");
+    (0..text.len() / 2)
+        .map(|i| {
+            u8::from_str_radix(&text[i * 2..i * 2 + 2], 16)
+                .map_err(|_| anyhow!("invalid hex"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn page(frontmatter: &str) -> Page {
+        Page::from_source(
+            PathBuf::from("notes.md"),
+            PathBuf::from("notes.md"),
+            &format!("---\ntitle: Notes\n{}---\nBody", frontmatter),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_protect_page() -> Result<()> {
+        let options = ProtectOptions {
+            iterations: 10_000,
+            ..ProtectOptions::default()
+        };
+        let html = "<html><head><title>Notes</title></head>\
+                    <body class=\"page\"><p>Launch code: 0000</p></body></html>";
+        let protected =
+            protect_page(html, "hunter2", &options)?.unwrap();
+        assert!(!protected.contains("Launch code"));
+        assert!(protected.starts_with(
+            "<html><head><title>Notes</title></head><body class=\"page\">"
+        ));
+        assert!(protected.ends_with("</body></html>"));
+
+        let start = protected.find("{\"").unwrap();
+        let end = protected[start..].find("</script>").unwrap() + start;
+        let sealed: SealedBody =
+            serde_json::from_str(&protected[start..end])?;
+        assert_eq!(
+            open(&sealed, "hunter2")?,
+            "<p>Launch code: 0000</p>"
+        );
+        assert_eq!(
+            protect_page("<p>No body</p>", "x", &options)?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_password() -> Result<()> {
+        assert_eq!(password(&page(""))?, None);
+        assert_eq!(password(&page("protected: false\n"))?, None);
+        assert_eq!(
+            password(&page("protected: hunter2\n"))?.as_deref(),
+            Some("hunter2")
+        );
+        assert!(is_protected(&page("protected: true\n")));
+        assert!(password(&page("protected: true\n")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut options = ProtectOptions::default();
+        assert!(options.validate().is_ok());
+        options.iterations = 1_000;
+        assert!(options.validate().is_err());
+    }
+}
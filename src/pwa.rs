@@ -0,0 +1,692 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Progressive Web App
+//!
+//! Generates a service worker for the built site, so it installs as a
+//! PWA and keeps working offline. Enable it in the `[pwa]` table of the
+//! configuration file:
+//!
+//! ```toml
+//! [pwa]
+//! enabled = true
+//! offline_page = "/offline/"
+//! precache = ["/", "/about/"]
+//!
+//! [[pwa.runtime_caching]]
+//! pattern = "^/blog/"
+//! strategy = "network-first"
+//! cache = "pages"
+//! max_entries = 50
+//! ```
+//!
+//! The service worker (`sw.js` by default, replacing any copied from the
+//! templates) precaches:
+//!
+//! - the content-addressed files below `/media/` (see [`crate::media`]),
+//!   whose URLs change with their contents;
+//! - files with a `precache_extensions` extension, stylesheets and
+//!   scripts by default, with a revision hash so changed files are
+//!   fetched again;
+//! - the offline page and the `precache` URLs.
+//!
+//! The cache name is versioned by the precache manifest, so a build
+//! that changes any precached file replaces the old cache when the new
+//! worker activates. Requests matching a `runtime_caching` pattern use
+//! its strategy (`cache-first`, `network-first`,
+//! `stale-while-revalidate`, `network-only` or `cache-only`) and cache;
+//! other requests are answered from the precache, then the network.
+//! Navigations that fail offline get the offline page, which is
+//! generated when the site has none.
+//!
+//! Pages that do not register a service worker themselves get a short
+//! registration script before `</body>`.
+
+use anyhow::{bail, ensure, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    collect_files_recursive, etags::etag_for, media::MEDIA_DIR,
+    template::escape_html,
+};
+
+/// Matches the closing `</body>` tag.
+static BODY_END_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)</body\s*>")
+        .unwrap_or_else(|e| panic!("Invalid body regex: {}", e))
+});
+
+/// Id of the injected registration script.
+pub const REGISTER_SCRIPT_ID: &str = "ssg-sw-register";
+
+/// The service worker, with `__VERSION__`, `__PRECACHE__`,
+/// `__OFFLINE_PAGE__` and `__ROUTES__` replaced by JSON values.
+pub const SERVICE_WORKER_JS: &str = r#""use strict";
+// Generated by ssg from the [pwa] configuration; edits are overwritten.
+var VERSION = __VERSION__;
+var PRECACHE = __PRECACHE__;
+var OFFLINE_PAGE = __OFFLINE_PAGE__;
+var ROUTES = __ROUTES__.map(function (route) {
+  route.pattern = new RegExp(route.pattern);
+  return route;
+});
+var PRECACHE_CACHE = "precache-" + VERSION;
+
+function offline() {
+  return Promise.reject(new Error("offline"));
+}
+
+function fromCache(cacheName, request) {
+  return caches.open(cacheName).then(function (cache) {
+    return cache.match(request);
+  });
+}
+
+function trim(route) {
+  if (!route.max_entries) {
+    return Promise.resolve();
+  }
+  return caches.open(route.cache).then(function (cache) {
+    return cache.keys().then(function (keys) {
+      var excess = keys.slice(0, Math.max(0, keys.length - route.max_entries));
+      return Promise.all(excess.map(function (key) {
+        return cache.delete(key);
+      }));
+    });
+  });
+}
+
+function fromNetwork(route, request) {
+  return fetch(request).then(function (response) {
+    if (response.ok) {
+      var copy = response.clone();
+      caches.open(route.cache).then(function (cache) {
+        return cache.put(request, copy);
+      }).then(function () {
+        return trim(route);
+      });
+    }
+    return response;
+  });
+}
+
+function handle(route, request) {
+  switch (route.strategy) {
+    case "cache-first":
+      return fromCache(route.cache, request).then(function (cached) {
+        return cached || fromNetwork(route, request);
+      });
+    case "network-first":
+      return fromNetwork(route, request).catch(function () {
+        return fromCache(route.cache, request).then(function (cached) {
+          return cached || offline();
+        });
+      });
+    case "stale-while-revalidate":
+      var network = fromNetwork(route, request);
+      network.catch(function () {});
+      return fromCache(route.cache, request).then(function (cached) {
+        return cached || network;
+      });
+    case "cache-only":
+      return fromCache(route.cache, request).then(function (cached) {
+        return cached || offline();
+      });
+    default:
+      return fetch(request);
+  }
+}
+
+function fallback(request) {
+  if (request.mode === "navigate" && OFFLINE_PAGE) {
+    return caches.match(OFFLINE_PAGE).then(function (page) {
+      return page || Response.error();
+    });
+  }
+  return Response.error();
+}
+
+self.addEventListener("install", function (event) {
+  event.waitUntil(
+    caches.open(PRECACHE_CACHE).then(function (cache) {
+      return cache.addAll(PRECACHE.map(function (entry) {
+        return entry.url;
+      }));
+    }).then(function () {
+      return self.skipWaiting();
+    })
+  );
+});
+
+self.addEventListener("activate", function (event) {
+  var keep = [PRECACHE_CACHE].concat(ROUTES.map(function (route) {
+    return route.cache;
+  }));
+  event.waitUntil(
+    caches.keys().then(function (keys) {
+      return Promise.all(keys.filter(function (key) {
+        return keep.indexOf(key) < 0;
+      }).map(function (key) {
+        return caches.delete(key);
+      }));
+    }).then(function () {
+      return self.clients.claim();
+    })
+  );
+});
+
+self.addEventListener("message", function (event) {
+  if (event.data && event.data.action === "skipWaiting") {
+    self.skipWaiting();
+  }
+});
+
+self.addEventListener("fetch", function (event) {
+  var request = event.request;
+  var url = new URL(request.url);
+  if (request.method !== "GET" || url.origin !== self.location.origin) {
+    return;
+  }
+  var route = ROUTES.find(function (route) {
+    return route.pattern.test(url.pathname);
+  });
+  var response = route
+    ? handle(route, request)
+    : fromCache(PRECACHE_CACHE, request).then(function (cached) {
+        return cached || fetch(request);
+      });
+  event.respondWith(response.catch(function () {
+    return fallback(request);
+  }));
+});
+"#;
+
+/// How a runtime route answers requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheStrategy {
+    /// Answers from the cache, fetching and caching on a miss.
+    CacheFirst,
+    /// Fetches and caches, answering from the cache when offline.
+    NetworkFirst,
+    /// Answers from the cache and refreshes it in the background.
+    StaleWhileRevalidate,
+    /// Always fetches, never caches.
+    NetworkOnly,
+    /// Only answers from the cache.
+    CacheOnly,
+}
+
+/// A `[[pwa.runtime_caching]]` route.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeCaching {
+    /// Regular expression matched against the request path.
+    pub pattern: String,
+    /// How matching requests are answered.
+    pub strategy: CacheStrategy,
+    /// Name of the cache holding the responses.
+    pub cache: String,
+    /// Most responses kept in the cache, oldest dropped first.
+    #[serde(default)]
+    pub max_entries: Option<usize>,
+}
+
+/// `[pwa]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PwaOptions {
+    /// Generates the service worker.
+    pub enabled: bool,
+    /// Path of the service worker, relative to the site root.
+    pub service_worker: String,
+    /// URL of the page shown for navigations that fail offline.
+    pub offline_page: String,
+    /// Extra URLs to precache, such as the home page.
+    pub precache: Vec<String>,
+    /// Extensions of the files precached with a revision hash.
+    pub precache_extensions: Vec<String>,
+    /// Precaches the content-addressed files below `/media/`.
+    pub precache_media: bool,
+    /// Adds a registration script to pages that do not register a
+    /// service worker.
+    pub register: bool,
+    /// Runtime caching routes, tried in order.
+    pub runtime_caching: Vec<RuntimeCaching>,
+}
+
+impl Default for PwaOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            service_worker: "sw.js".to_string(),
+            offline_page: "/offline/".to_string(),
+            precache: vec!["/".to_string()],
+            precache_extensions: vec![
+                "css".to_string(),
+                "js".to_string(),
+            ],
+            precache_media: true,
+            register: true,
+            runtime_caching: vec![RuntimeCaching {
+                pattern: r"\.(?:avif|gif|jpe?g|png|svg|webp)$"
+                    .to_string(),
+                strategy: CacheStrategy::CacheFirst,
+                cache: "images".to_string(),
+                max_entries: Some(100),
+            }],
+        }
+    }
+}
+
+impl PwaOptions {
+    /// Checks the service worker path, the URLs and the runtime routes.
+    ///
+    /// Patterns are checked with Rust's regex syntax, which covers the
+    /// JavaScript regular expressions used for URL paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.service_worker.ends_with(".js")
+                && !self.service_worker.contains(['/', '\\']),
+            "service_worker must be a .js file name in the site root, got '{}'",
+            self.service_worker
+        );
+        for url in
+            std::iter::once(&self.offline_page).chain(&self.precache)
+        {
+            ensure!(
+                url.starts_with('/') && !url.contains(".."),
+                "'{}' must be a site-relative URL starting with '/'",
+                url
+            );
+        }
+        for route in &self.runtime_caching {
+            let _ = Regex::new(&route.pattern).with_context(|| {
+                format!(
+                    "Invalid runtime_caching pattern '{}'",
+                    route.pattern
+                )
+            })?;
+            ensure!(
+                !route.cache.trim().is_empty(),
+                "runtime_caching cache names must not be empty"
+            );
+            ensure!(
+                !route.cache.starts_with("precache-"),
+                "runtime_caching cache '{}' clashes with the precache",
+                route.cache
+            );
+            ensure!(
+                route.max_entries != Some(0),
+                "runtime_caching max_entries must be at least 1"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A precached URL.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct PrecacheEntry {
+    /// Site-relative URL.
+    pub url: String,
+    /// Content hash, or `None` for URLs that change with their
+    /// contents.
+    pub revision: Option<String>,
+}
+
+/// What [`write_pwa`] wrote.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PwaSummary {
+    /// Number of precached URLs.
+    pub precached: usize,
+    /// Number of pages given a registration script.
+    pub registered: usize,
+    /// Whether the offline page was generated.
+    pub offline_page_generated: bool,
+}
+
+/// Returns the file serving `url` below `site_dir`: `index.html` for
+/// URLs ending in `/`.
+fn file_for_url(site_dir: &Path, url: &str) -> PathBuf {
+    let path = site_dir.join(url.trim_start_matches('/'));
+    if url.ends_with('/') {
+        path.join("index.html")
+    } else {
+        path
+    }
+}
+
+/// Returns the revision of a file: the hex digest of its ETag.
+fn revision(path: &Path) -> Result<String> {
+    let contents = fs::read(path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    Ok(etag_for(&contents).trim_matches('"').to_string())
+}
+
+/// Builds the precache manifest of the site in `site_dir`, sorted by
+/// URL.
+///
+/// # Errors
+///
+/// Returns an error if the site cannot be read or a `precache` URL or
+/// the offline page has no file.
+pub fn precache_entries(
+    site_dir: &Path,
+    options: &PwaOptions,
+) -> Result<Vec<PrecacheEntry>> {
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    let media_dir = site_dir.join(MEDIA_DIR);
+    let service_worker = site_dir.join(&options.service_worker);
+
+    let mut entries = Vec::new();
+    for path in files {
+        if path == service_worker {
+            continue;
+        }
+        let relative = match path.strip_prefix(site_dir) {
+            Ok(relative) => relative,
+            Err(_) => continue,
+        };
+        let url = format!(
+            "/{}",
+            relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+        );
+        if options.precache_media && path.starts_with(&media_dir) {
+            entries.push(PrecacheEntry {
+                url,
+                revision: None,
+            });
+        } else if path.extension().and_then(|ext| ext.to_str()).map_or(
+            false,
+            |ext| {
+                options
+                    .precache_extensions
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(ext))
+            },
+        ) {
+            entries.push(PrecacheEntry {
+                url,
+                revision: Some(revision(&path)?),
+            });
+        }
+    }
+
+    for url in
+        std::iter::once(&options.offline_page).chain(&options.precache)
+    {
+        let path = file_for_url(site_dir, url);
+        if !path.is_file() {
+            bail!(
+                "Cannot precache {}: {} does not exist",
+                url,
+                path.display()
+            );
+        }
+        entries.push(PrecacheEntry {
+            url: url.clone(),
+            revision: Some(revision(&path)?),
+        });
+    }
+    entries.sort();
+    entries.dedup_by(|a, b| a.url == b.url);
+    Ok(entries)
+}
+
+/// Renders the service worker for `entries`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::pwa::{render_service_worker, PrecacheEntry, PwaOptions};
+///
+/// let entries = vec![PrecacheEntry {
+///     url: "/style.css".into(),
+///     revision: Some("3a7bd3e2".into()),
+/// }];
+/// let sw = render_service_worker(&entries, &PwaOptions::default());
+/// assert!(sw.contains(r#"var OFFLINE_PAGE = "/offline/";"#));
+/// assert!(sw.contains(r#""url":"/style.css""#));
+/// ```
+pub fn render_service_worker(
+    entries: &[PrecacheEntry],
+    options: &PwaOptions,
+) -> String {
+    let precache = json!(entries).to_string();
+    let version = etag_for(precache.as_bytes());
+    let routes = json!(options.runtime_caching).to_string();
+    SERVICE_WORKER_JS
+        .replace("__VERSION__", &json!(&version[1..9]).to_string())
+        .replace("__PRECACHE__", &precache)
+        .replace(
+            "__OFFLINE_PAGE__",
+            &json!(options.offline_page).to_string(),
+        )
+        .replace("__ROUTES__", &routes)
+}
+
+/// Returns the offline page written when the site has none.
+pub fn offline_page_html(site_title: &str, language: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"{lang}\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>Offline - {title}</title>\n</head>\n<body>\n<main>\n\
+         <h1>You are offline</h1>\n\
+         <p>This page of {title} is not available offline. Check your \
+         connection and try again.</p>\n\
+         <p><a href=\"/\">Back to the home page</a></p>\n\
+         </main>\n</body>\n</html>\n",
+        lang = escape_html(language),
+        title = escape_html(site_title),
+    )
+}
+
+/// Adds the registration script for `service_worker` before `</body>`.
+///
+/// Returns `None` for pages without `</body>` or that already register a
+/// service worker.
+pub fn insert_registration(
+    html: &str,
+    service_worker: &str,
+) -> Option<String> {
+    if html.contains("serviceWorker.register") {
+        return None;
+    }
+    let body_end = BODY_END_RE.find(html)?.start();
+    let script = format!(
+        "<script id=\"{}\">if (\"serviceWorker\" in navigator) {{ \
+         navigator.serviceWorker.register(\"/{}\"); }}</script>\n",
+        REGISTER_SCRIPT_ID, service_worker
+    );
+    let mut page = String::with_capacity(html.len() + script.len());
+    page.push_str(&html[..body_end]);
+    page.push_str(&script);
+    page.push_str(&html[body_end..]);
+    Some(page)
+}
+
+/// Writes the offline page if the site has none, the service worker
+/// and, if configured, registration scripts in the pages of `site_dir`.
+///
+/// # Errors
+///
+/// Returns an error if the options are invalid, a precached URL has no
+/// file, or a file cannot be read or written.
+pub fn write_pwa(
+    site_dir: &Path,
+    options: &PwaOptions,
+    site_title: &str,
+    language: &str,
+) -> Result<PwaSummary> {
+    let mut summary = PwaSummary::default();
+    if !options.enabled || !site_dir.exists() {
+        return Ok(summary);
+    }
+    options.validate()?;
+
+    let offline = file_for_url(site_dir, &options.offline_page);
+    if !offline.exists() {
+        if let Some(parent) = offline.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        fs::write(&offline, offline_page_html(site_title, language))
+            .with_context(|| {
+                format!("Failed to write {}", offline.display())
+            })?;
+        summary.offline_page_generated = true;
+    }
+
+    if options.register {
+        let mut files = Vec::new();
+        collect_files_recursive(site_dir, &mut files).with_context(
+            || format!("Failed to read {}", site_dir.display()),
+        )?;
+        for path in files {
+            if path.extension().and_then(|ext| ext.to_str())
+                != Some("html")
+            {
+                continue;
+            }
+            let html =
+                fs::read_to_string(&path).with_context(|| {
+                    format!("Failed to read {}", path.display())
+                })?;
+            if let Some(page) =
+                insert_registration(&html, &options.service_worker)
+            {
+                fs::write(&path, page).with_context(|| {
+                    format!("Failed to write {}", path.display())
+                })?;
+                summary.registered += 1;
+            }
+        }
+    }
+
+    let entries = precache_entries(site_dir, options)?;
+    summary.precached = entries.len();
+    let worker = site_dir.join(&options.service_worker);
+    fs::write(&worker, render_service_worker(&entries, options))
+        .with_context(|| {
+            format!("Failed to write {}", worker.display())
+        })?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn options() -> PwaOptions {
+        PwaOptions {
+            enabled: true,
+            ..PwaOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_write_pwa() -> Result<()> {
+        let dir = tempdir()?;
+        let site = dir.path();
+        fs::create_dir_all(site.join("media"))?;
+        fs::write(site.join("index.html"), "<body><p>Home</p></body>")?;
+        fs::write(site.join("style.css"), "body{}")?;
+        fs::write(site.join("media/1f3a.png"), "png")?;
+        fs::write(site.join("photo.jpg"), "jpg")?;
+        fs::write(site.join("sw.js"), "// from the templates")?;
+
+        let summary = write_pwa(site, &options(), "Example", "en-GB")?;
+        assert!(summary.offline_page_generated);
+        assert_eq!(summary.registered, 2);
+        assert_eq!(summary.precached, 4);
+
+        let worker = fs::read_to_string(site.join("sw.js"))?;
+        let precache: Vec<serde_json::Value> = worker
+            .lines()
+            .find_map(|line| line.strip_prefix("var PRECACHE = "))
+            .map(|json| {
+                serde_json::from_str(json.trim_end_matches(';'))
+            })
+            .unwrap()?;
+        assert!(precache.contains(
+            &json!({"url": "/media/1f3a.png", "revision": null})
+        ));
+        assert!(worker.contains(r#""url":"/style.css""#));
+        assert!(worker.contains(r#""url":"/offline/""#));
+        assert!(!worker.contains("photo.jpg"));
+        assert!(!worker.contains("__ROUTES__"));
+        assert!(worker.contains(r#""strategy":"cache-first""#));
+
+        let offline =
+            fs::read_to_string(site.join("offline/index.html"))?;
+        assert!(offline.contains("<title>Offline - Example</title>"));
+        let home = fs::read_to_string(site.join("index.html"))?;
+        assert!(home.contains(REGISTER_SCRIPT_ID));
+
+        let again = write_pwa(site, &options(), "Example", "en-GB")?;
+        assert_eq!(again.registered, 0);
+        assert!(!again.offline_page_generated);
+        Ok(())
+    }
+
+    #[test]
+    fn test_version_follows_precache() {
+        let entry = |revision: &str| PrecacheEntry {
+            url: "/style.css".into(),
+            revision: Some(revision.into()),
+        };
+        let a = render_service_worker(&[entry("a")], &options());
+        let b = render_service_worker(&[entry("b")], &options());
+        let version = |sw: &str| {
+            sw.lines()
+                .find(|line| line.starts_with("var VERSION"))
+                .map(str::to_string)
+        };
+        assert_ne!(version(&a), version(&b));
+    }
+
+    #[test]
+    fn test_validate_and_missing_precache() -> Result<()> {
+        assert!(options().validate().is_ok());
+        let mut invalid = options();
+        invalid.runtime_caching[0].pattern = "(".into();
+        assert!(invalid.validate().is_err());
+        let invalid = PwaOptions {
+            service_worker: "js/sw.js".into(),
+            ..options()
+        };
+        assert!(invalid.validate().is_err());
+
+        let dir = tempdir()?;
+        fs::write(dir.path().join("index.html"), "<body></body>")?;
+        let missing = PwaOptions {
+            precache: vec!["/missing/".into()],
+            ..options()
+        };
+        assert!(
+            write_pwa(dir.path(), &missing, "Example", "en").is_err()
+        );
+        Ok(())
+    }
+}
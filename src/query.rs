@@ -0,0 +1,527 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Content Queries
+//!
+//! Selects pages of a [`ContentSet`] by frontmatter predicates, so plugins
+//! and custom generators do not have to walk the content directory again.
+//!
+//! Predicates are typed by the value they compare against: a
+//! [`DateTime`] compares the field as a date, a number as a number and a
+//! string as text. Pages whose field is missing or cannot be read as that
+//! type never match.
+//!
+//! The `section` field falls back to the first directory of the page's
+//! path, so `blog/hello.md` is in section `blog` unless its frontmatter
+//! says otherwise.
+//!
+//! ```rust
+//! use dtt::datetime::DateTime;
+//! use ssg::content::{ContentSet, Page};
+//! use std::path::PathBuf;
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let page = |path: &str, raw: &str| {
+//!         Page::from_source(PathBuf::from(path), PathBuf::from(path), raw)
+//!     };
+//!     let content = ContentSet {
+//!         root: PathBuf::from("content"),
+//!         pages: vec![
+//!             page("about.md", "---\ndate: 2025-01-01\n---\n")?,
+//!             page("blog/old.md", "---\ndate: 2024-06-01\n---\n")?,
+//!             page("blog/new.md", "---\ndate: 2025-03-01\n---\n")?,
+//!         ],
+//!     };
+//!
+//!     let since = DateTime::parse("2025-01-01")?;
+//!     let recent = content
+//!         .query()
+//!         .where_eq("section", "blog")
+//!         .where_gt("date", since)
+//!         .pages();
+//!     assert_eq!(recent.len(), 1);
+//!     assert_eq!(recent[0].title(), "new");
+//!     Ok(())
+//! }
+//! ```
+
+use dtt::datetime::DateTime;
+use serde_json::Value;
+use std::{cmp::Ordering, fmt};
+
+use crate::content::{ContentSet, Page};
+
+/// A typed value that a frontmatter field is compared against.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// Compared as text.
+    Text(String),
+    /// Compared numerically; numeric strings are accepted.
+    Number(f64),
+    /// Compared as a boolean; `"yes"`/`"no"` strings are accepted.
+    Bool(bool),
+    /// Compared as a date; the field must be an RFC 3339 or
+    /// `YYYY-MM-DD` date.
+    Date(DateTime),
+}
+
+impl FieldValue {
+    /// Reads `value` as the same type as `self`.
+    fn coerce(&self, value: &Value) -> Option<Self> {
+        match (self, value) {
+            (Self::Text(_), Value::String(text)) => {
+                Some(Self::Text(text.clone()))
+            }
+            (Self::Text(_), Value::Number(n)) => {
+                Some(Self::Text(n.to_string()))
+            }
+            (Self::Number(_), Value::Number(n)) => {
+                n.as_f64().map(Self::Number)
+            }
+            (Self::Number(_), Value::String(text)) => {
+                text.trim().parse().ok().map(Self::Number)
+            }
+            (Self::Bool(_), Value::Bool(b)) => Some(Self::Bool(*b)),
+            (Self::Bool(_), Value::String(text)) => {
+                match text.to_ascii_lowercase().as_str() {
+                    "true" | "yes" => Some(Self::Bool(true)),
+                    "false" | "no" => Some(Self::Bool(false)),
+                    _ => None,
+                }
+            }
+            (Self::Date(_), Value::String(text)) => {
+                DateTime::parse(text.trim()).ok().map(Self::Date)
+            }
+            _ => None,
+        }
+    }
+
+    /// Orders two values of the same type.
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Text(a), Self::Text(b)) => Some(a.cmp(b)),
+            (Self::Number(a), Self::Number(b)) => a.partial_cmp(b),
+            (Self::Bool(a), Self::Bool(b)) => Some(a.cmp(b)),
+            (Self::Date(a), Self::Date(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<i64> for FieldValue {
+    fn from(value: i64) -> Self {
+        Self::Number(value as f64)
+    }
+}
+
+impl From<f64> for FieldValue {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<DateTime> for FieldValue {
+    fn from(value: DateTime) -> Self {
+        Self::Date(value)
+    }
+}
+
+/// How a predicate compares a field with its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Comparison {
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering == Ordering::Equal,
+            Self::Ne => ordering != Ordering::Equal,
+            Self::Gt => ordering == Ordering::Greater,
+            Self::Ge => ordering != Ordering::Less,
+            Self::Lt => ordering == Ordering::Less,
+            Self::Le => ordering != Ordering::Greater,
+        }
+    }
+}
+
+/// A single condition on a page.
+enum Predicate<'a> {
+    Compare {
+        field: String,
+        comparison: Comparison,
+        value: FieldValue,
+    },
+    Contains {
+        field: String,
+        item: String,
+    },
+    Exists(String),
+    Custom(Box<dyn Fn(&Page) -> bool + 'a>),
+}
+
+impl Predicate<'_> {
+    fn matches(&self, page: &Page) -> bool {
+        match self {
+            Self::Compare {
+                field,
+                comparison,
+                value,
+            } => field_value(page, field)
+                .and_then(|raw| value.coerce(&raw))
+                .and_then(|found| found.compare(value))
+                .map_or(false, |ordering| comparison.accepts(ordering)),
+            Self::Contains { field, item } => {
+                page.frontmatter.get_list(field).iter().any(
+                    |candidate| candidate.eq_ignore_ascii_case(item),
+                )
+            }
+            Self::Exists(field) => field_value(page, field).is_some(),
+            Self::Custom(predicate) => predicate(page),
+        }
+    }
+}
+
+/// Returns the frontmatter value of `field`, or the derived section.
+fn field_value(page: &Page, field: &str) -> Option<Value> {
+    match page.frontmatter.get(field) {
+        Some(value) => Some(value.clone()),
+        None if field == "section" => page.section().map(Value::String),
+        None => None,
+    }
+}
+
+/// A query over the pages of a [`ContentSet`], built with
+/// [`ContentSet::query`].
+///
+/// All predicates must match. Results keep the content set's path order
+/// unless [`order_by`](Query::order_by) is used.
+pub struct Query<'a> {
+    content: &'a ContentSet,
+    predicates: Vec<Predicate<'a>>,
+    order: Option<(String, bool)>,
+    limit: Option<usize>,
+}
+
+impl fmt::Debug for Query<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Query")
+            .field("predicates", &self.predicates.len())
+            .field("order", &self.order)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl<'a> Query<'a> {
+    /// Creates a query matching every page of `content`.
+    pub fn new(content: &'a ContentSet) -> Self {
+        Self {
+            content,
+            predicates: Vec::new(),
+            order: None,
+            limit: None,
+        }
+    }
+
+    fn compare(
+        mut self,
+        field: &str,
+        comparison: Comparison,
+        value: impl Into<FieldValue>,
+    ) -> Self {
+        self.predicates.push(Predicate::Compare {
+            field: field.to_string(),
+            comparison,
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Keeps pages whose `field` equals `value`.
+    pub fn where_eq(
+        self,
+        field: &str,
+        value: impl Into<FieldValue>,
+    ) -> Self {
+        self.compare(field, Comparison::Eq, value)
+    }
+
+    /// Keeps pages whose `field` is set and differs from `value`.
+    pub fn where_ne(
+        self,
+        field: &str,
+        value: impl Into<FieldValue>,
+    ) -> Self {
+        self.compare(field, Comparison::Ne, value)
+    }
+
+    /// Keeps pages whose `field` is greater than `value`.
+    pub fn where_gt(
+        self,
+        field: &str,
+        value: impl Into<FieldValue>,
+    ) -> Self {
+        self.compare(field, Comparison::Gt, value)
+    }
+
+    /// Keeps pages whose `field` is greater than or equal to `value`.
+    pub fn where_ge(
+        self,
+        field: &str,
+        value: impl Into<FieldValue>,
+    ) -> Self {
+        self.compare(field, Comparison::Ge, value)
+    }
+
+    /// Keeps pages whose `field` is less than `value`.
+    pub fn where_lt(
+        self,
+        field: &str,
+        value: impl Into<FieldValue>,
+    ) -> Self {
+        self.compare(field, Comparison::Lt, value)
+    }
+
+    /// Keeps pages whose `field` is less than or equal to `value`.
+    pub fn where_le(
+        self,
+        field: &str,
+        value: impl Into<FieldValue>,
+    ) -> Self {
+        self.compare(field, Comparison::Le, value)
+    }
+
+    /// Keeps pages whose list `field` (a YAML sequence or a
+    /// comma-separated string) contains `item`, ignoring ASCII case.
+    pub fn where_contains(mut self, field: &str, item: &str) -> Self {
+        self.predicates.push(Predicate::Contains {
+            field: field.to_string(),
+            item: item.to_string(),
+        });
+        self
+    }
+
+    /// Keeps pages where `field` is set.
+    pub fn where_exists(mut self, field: &str) -> Self {
+        self.predicates.push(Predicate::Exists(field.to_string()));
+        self
+    }
+
+    /// Keeps pages for which `predicate` returns `true`.
+    pub fn filter<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Page) -> bool + 'a,
+    {
+        self.predicates.push(Predicate::Custom(Box::new(predicate)));
+        self
+    }
+
+    /// Sorts results by `field` in ascending order.
+    ///
+    /// Dates, numbers and text are compared as such; pages without the
+    /// field come last.
+    pub fn order_by(mut self, field: &str) -> Self {
+        self.order = Some((field.to_string(), false));
+        self
+    }
+
+    /// Sorts results by `field` in descending order, e.g. newest first.
+    pub fn order_by_desc(mut self, field: &str) -> Self {
+        self.order = Some((field.to_string(), true));
+        self
+    }
+
+    /// Returns at most `count` pages.
+    pub fn limit(mut self, count: usize) -> Self {
+        self.limit = Some(count);
+        self
+    }
+
+    /// Returns the matching pages.
+    pub fn pages(&self) -> Vec<&'a Page> {
+        let mut pages: Vec<&'a Page> = self
+            .content
+            .iter()
+            .filter(|page| {
+                self.predicates.iter().all(|p| p.matches(page))
+            })
+            .collect();
+
+        if let Some((field, descending)) = &self.order {
+            pages.sort_by(|a, b| {
+                match (sort_key(a, field), sort_key(b, field)) {
+                    (Some(a), Some(b)) => {
+                        let ordering =
+                            a.compare(&b).unwrap_or(Ordering::Equal);
+                        if *descending {
+                            ordering.reverse()
+                        } else {
+                            ordering
+                        }
+                    }
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            });
+        }
+
+        if let Some(limit) = self.limit {
+            pages.truncate(limit);
+        }
+        pages
+    }
+
+    /// Returns the first matching page.
+    pub fn first(&self) -> Option<&'a Page> {
+        self.pages().into_iter().next()
+    }
+
+    /// Returns the number of matching pages.
+    pub fn count(&self) -> usize {
+        self.pages().len()
+    }
+}
+
+/// Reads `field` as a date, a number or text, in that order.
+fn sort_key(page: &Page, field: &str) -> Option<FieldValue> {
+    let raw = field_value(page, field)?;
+    [
+        FieldValue::Date(DateTime::new()),
+        FieldValue::Number(0.0),
+        FieldValue::Text(String::new()),
+    ]
+    .iter()
+    .find_map(|kind| kind.coerce(&raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn content() -> ContentSet {
+        let page = |path: &str, raw: &str| {
+            Page::from_source(
+                PathBuf::from(path),
+                PathBuf::from(path),
+                raw,
+            )
+            .unwrap()
+        };
+        ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page(
+                    "about.md",
+                    "---\ntitle: About\nweight: 3\ndraft: false\n---\n",
+                ),
+                page(
+                    "blog/a.md",
+                    "---\ntitle: A\ndate: 2024-05-01\nweight: \"1\"\ntags: [rust, web]\n---\n",
+                ),
+                page(
+                    "blog/b.md",
+                    "---\ntitle: B\ndate: 2025-02-01T10:00:00Z\nweight: 2\ntags: Rust, seo\ndraft: yes\n---\n",
+                ),
+                page(
+                    "news/c.md",
+                    "---\ntitle: C\nsection: blog\ndate: 2025-01-01\n---\n",
+                ),
+            ],
+        }
+    }
+
+    fn titles(pages: Vec<&Page>) -> Vec<String> {
+        pages.into_iter().map(Page::title).collect()
+    }
+
+    #[test]
+    fn test_section_falls_back_to_directory() {
+        let content = content();
+        assert_eq!(
+            titles(content.query().where_eq("section", "blog").pages()),
+            vec!["A", "B", "C"]
+        );
+        assert_eq!(content.query().where_exists("section").count(), 3);
+    }
+
+    #[test]
+    fn test_typed_comparisons() -> anyhow::Result<()> {
+        let content = content();
+        let since = DateTime::parse("2025-01-01")?;
+        assert_eq!(
+            titles(content.query().where_ge("date", since).pages()),
+            vec!["B", "C"]
+        );
+        assert_eq!(
+            titles(content.query().where_lt("weight", 3).pages()),
+            vec!["A", "B"]
+        );
+        assert_eq!(
+            titles(content.query().where_eq("draft", true).pages()),
+            vec!["B"]
+        );
+        assert_eq!(
+            titles(content.query().where_ne("draft", true).pages()),
+            vec!["About"]
+        );
+        assert_eq!(
+            titles(
+                content.query().where_contains("tags", "rust").pages()
+            ),
+            vec!["A", "B"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_limit_and_filter() {
+        let content = content();
+        let newest = content
+            .query()
+            .where_exists("date")
+            .order_by_desc("date")
+            .limit(2)
+            .pages();
+        assert_eq!(titles(newest), vec!["B", "C"]);
+
+        assert_eq!(
+            titles(content.query().order_by("weight").pages()),
+            vec!["A", "B", "About", "C"]
+        );
+        assert_eq!(
+            content
+                .query()
+                .filter(|page| page.body.is_empty())
+                .where_eq("title", "C")
+                .first()
+                .map(Page::title),
+            Some("C".to_string())
+        );
+    }
+}
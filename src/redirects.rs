@@ -0,0 +1,660 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Redirects
+//!
+//! Loads the redirect rules of a generated site so the development server
+//! can answer with real `301`/`302` responses instead of serving the stub
+//! pages. Redirect behaviour can then be checked locally before deploy,
+//! in the preview `ssg` serves once the site is built.
+//!
+//! Rules come from two places:
+//!
+//! - a Netlify-style `_redirects` file in the site root, one rule per
+//!   line (`from to [status]`, status defaults to `301`). A trailing `*`
+//!   in `from` matches any suffix, which `:splat` inserts into `to`;
+//! - meta-refresh stub pages (`<meta http-equiv="refresh"
+//!   content="0; url=/new/">`), served as `301` from the stub's URL.
+//!
+//! ```text
+//! # _redirects
+//! /old-post/      /blog/new-post/   301
+//! /docs/*         /guide/:splat     302
+//! ```
+//!
+//! Rules in `_redirects` take precedence over stub pages.
+//!
+//! Pages list their old URLs in an `aliases` frontmatter key so links
+//! keep working after a restructure:
+//!
+//! ```yaml
+//! aliases: [/old-url/, /2019/old-post/]
+//! ```
+//!
+//! [`collect_aliases`] turns them into `301` rules, which
+//! [`write_redirects`] writes in each format of the `[redirects]` config
+//! table: meta-refresh stubs (`html`, the default), a `_redirects` file
+//! (`netlify`) or `Redirect` lines in `.htaccess` (`apache`).
+//!
+//! ```toml
+//! [redirects]
+//! formats = ["html", "netlify"]
+//! ```
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+use warp::{
+    http::{header::LOCATION, StatusCode},
+    path::FullPath,
+    Filter, Rejection, Reply,
+};
+
+use crate::{
+    collect_files_recursive, content::ContentSet, template::escape_html,
+};
+
+/// File name of the redirect rules in the site root.
+pub const REDIRECTS_FILE: &str = "_redirects";
+
+/// File name of the Apache configuration in the site root.
+pub const HTACCESS_FILE: &str = ".htaccess";
+
+/// Frontmatter key listing the old URLs of a page.
+pub const ALIASES_KEY: &str = "aliases";
+
+/// Marks the start of the alias rules in `.htaccess`.
+const HTACCESS_BEGIN: &str = "# BEGIN ssg aliases";
+
+/// Marks the end of the alias rules in `.htaccess`.
+const HTACCESS_END: &str = "# END ssg aliases";
+
+/// Status used when a rule does not specify one.
+pub const DEFAULT_REDIRECT_STATUS: u16 = 301;
+
+/// Matches the target of a meta refresh tag.
+static META_REFRESH_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)<meta\s+http-equiv=["']?refresh["']?\s+content=["']\s*\d+\s*;\s*url=([^"'>]+)["']"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid meta refresh regex: {}", e))
+});
+
+/// A single redirect from one URL path to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectRule {
+    /// Path the rule applies to; a trailing `*` matches any suffix.
+    pub from: String,
+    /// Redirect target; `:splat` is replaced by the matched suffix.
+    pub to: String,
+    /// HTTP status code: 301, 302, 303, 307 or 308.
+    pub status: u16,
+}
+
+impl RedirectRule {
+    /// Returns the target for `path`, if this rule matches it.
+    pub fn target(&self, path: &str) -> Option<String> {
+        match self.from.strip_suffix('*') {
+            Some(prefix) => path
+                .strip_prefix(prefix)
+                .map(|splat| self.to.replace(":splat", splat)),
+            None => (normalise(&self.from) == normalise(path))
+                .then(|| self.to.clone()),
+        }
+    }
+}
+
+/// An output format for alias redirects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedirectFormat {
+    /// A meta-refresh stub page at each old URL.
+    Html,
+    /// Rules in the Netlify-style `_redirects` file.
+    Netlify,
+    /// `Redirect` directives in `.htaccess`.
+    Apache,
+}
+
+/// `[redirects]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedirectOptions {
+    /// Formats alias redirects are written in.
+    pub formats: Vec<RedirectFormat>,
+}
+
+impl Default for RedirectOptions {
+    fn default() -> Self {
+        Self {
+            formats: vec![RedirectFormat::Html],
+        }
+    }
+}
+
+/// The redirect rules of a site, in precedence order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedirectTable {
+    /// Rules, first match wins.
+    pub rules: Vec<RedirectRule>,
+}
+
+impl RedirectTable {
+    /// Loads `_redirects` and meta-refresh stubs from `site_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the site directory or a file cannot be read.
+    pub fn load(site_dir: &Path) -> Result<Self> {
+        let mut table = Self::default();
+
+        let redirects = site_dir.join(REDIRECTS_FILE);
+        if redirects.is_file() {
+            let contents = fs::read_to_string(&redirects)
+                .with_context(|| {
+                    format!("Failed to read {}", redirects.display())
+                })?;
+            table.rules.extend(parse_redirects(&contents));
+        }
+
+        let mut files = Vec::new();
+        collect_files_recursive(site_dir, &mut files).with_context(
+            || format!("Failed to read {}", site_dir.display()),
+        )?;
+        files.sort();
+        for file in files {
+            if file.extension().and_then(|ext| ext.to_str())
+                != Some("html")
+            {
+                continue;
+            }
+            let html =
+                fs::read_to_string(&file).with_context(|| {
+                    format!("Failed to read {}", file.display())
+                })?;
+            if let Some(to) = meta_refresh_target(&html) {
+                table.rules.push(RedirectRule {
+                    from: url_path(site_dir, &file),
+                    to,
+                    status: DEFAULT_REDIRECT_STATUS,
+                });
+            }
+        }
+        Ok(table)
+    }
+
+    /// Returns the status and location for a request to `path`.
+    pub fn resolve(&self, path: &str) -> Option<(u16, String)> {
+        self.rules.iter().find_map(|rule| {
+            rule.target(path).map(|to| (rule.status, to))
+        })
+    }
+
+    /// Returns `true` if there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// Parses the contents of a `_redirects` file.
+///
+/// Blank lines, comments and rules with a non-redirect status (such as
+/// `200` rewrites) are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::redirects::parse_redirects;
+///
+/// let rules = parse_redirects("# moved\n/old /new\n/a /b 302\n/app/* /index.html 200");
+/// assert_eq!(rules.len(), 2);
+/// assert_eq!(rules[0].status, 301);
+/// assert_eq!(rules[1].status, 302);
+/// ```
+pub fn parse_redirects(contents: &str) -> Vec<RedirectRule> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let from = fields.next()?;
+            let to = fields.next()?;
+            let status = match fields.next() {
+                Some(status) => {
+                    status.trim_end_matches('!').parse().ok()?
+                }
+                None => DEFAULT_REDIRECT_STATUS,
+            };
+            matches!(status, 301 | 302 | 303 | 307 | 308).then(|| {
+                RedirectRule {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    status,
+                }
+            })
+        })
+        .collect()
+}
+
+/// Returns the target URL of a meta-refresh stub page.
+pub fn meta_refresh_target(html: &str) -> Option<String> {
+    META_REFRESH_RE
+        .captures(html)
+        .map(|captures| captures[1].trim().to_string())
+}
+
+/// Collects a `301` rule from every alias in the `aliases` frontmatter
+/// of `content` to its page, sorted by alias.
+///
+/// # Errors
+///
+/// Returns an error if an alias is not an absolute path within the site,
+/// is the URL of a page, or is claimed by two pages.
+pub fn collect_aliases(
+    content: &ContentSet,
+) -> Result<Vec<RedirectRule>> {
+    let urls: BTreeMap<String, &Path> = content
+        .iter()
+        .map(|page| (page.url_path(), page.relative.as_path()))
+        .collect();
+
+    let mut rules: BTreeMap<String, (RedirectRule, &Path)> =
+        BTreeMap::new();
+    for page in content.iter() {
+        for alias in page.frontmatter.get_list(ALIASES_KEY) {
+            let source = page.relative.as_path();
+            if !alias.starts_with('/')
+                || alias.contains(char::is_whitespace)
+                || alias.contains(&['*', '?', '#'][..])
+                || alias.split('/').any(|part| part == "..")
+            {
+                bail!(
+                    "Alias `{}` of {} must be an absolute URL path such                      as `/old-url/`",
+                    alias,
+                    source.display()
+                );
+            }
+            let key = normalise(&alias).to_string();
+            if let Some(other) = urls
+                .iter()
+                .find(|(url, _)| normalise(url) == key)
+                .map(|(_, page)| page)
+            {
+                bail!(
+                    "Alias `{}` of {} is the URL of {}",
+                    alias,
+                    source.display(),
+                    other.display()
+                );
+            }
+            if let Some((_, other)) = rules.get(&key) {
+                bail!(
+                    "Alias `{}` is claimed by both {} and {}",
+                    alias,
+                    other.display(),
+                    source.display()
+                );
+            }
+            let rule = RedirectRule {
+                from: alias,
+                to: page.url_path(),
+                status: DEFAULT_REDIRECT_STATUS,
+            };
+            let _ = rules.insert(key, (rule, source));
+        }
+    }
+    Ok(rules.into_values().map(|(rule, _)| rule).collect())
+}
+
+/// Renders a meta-refresh stub page redirecting to `to`.
+pub fn stub_html(to: &str) -> String {
+    let to = escape_html(to);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Redirecting to {0}</title>\n\
+         <link rel=\"canonical\" href=\"{0}\">\n\
+         <meta name=\"robots\" content=\"noindex\">\n\
+         <meta http-equiv=\"refresh\" content=\"0; url={0}\">\n\
+         </head>\n<body>\n<p>This page has moved to \
+         <a href=\"{0}\">{0}</a>.</p>\n</body>\n</html>\n",
+        to
+    )
+}
+
+/// Writes `rules` to `site_dir` in every format of `options`, returning
+/// the site-relative paths written.
+///
+/// Existing `_redirects` rules are kept, and earlier alias rules in
+/// `.htaccess` are replaced.
+///
+/// # Errors
+///
+/// Returns an error if a stub would replace a page that is not a stub,
+/// or a file cannot be read or written.
+pub fn write_redirects(
+    site_dir: &Path,
+    rules: &[RedirectRule],
+    options: &RedirectOptions,
+) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    if rules.is_empty() {
+        return Ok(written);
+    }
+    for format in &options.formats {
+        match format {
+            RedirectFormat::Html => {
+                for rule in rules {
+                    let relative = stub_path(&rule.from);
+                    let target = site_dir.join(&relative);
+                    if let Ok(existing) = fs::read_to_string(&target) {
+                        if meta_refresh_target(&existing).is_none() {
+                            bail!(
+                                "Alias `{}` would replace the page {}",
+                                rule.from,
+                                target.display()
+                            );
+                        }
+                    }
+                    if let Some(parent) = target.parent() {
+                        fs::create_dir_all(parent).with_context(
+                            || {
+                                format!(
+                                    "Failed to create {}",
+                                    parent.display()
+                                )
+                            },
+                        )?;
+                    }
+                    fs::write(&target, stub_html(&rule.to))
+                        .with_context(|| {
+                            format!(
+                                "Failed to write {}",
+                                target.display()
+                            )
+                        })?;
+                    written.push(relative);
+                }
+            }
+            RedirectFormat::Netlify => {
+                let path = site_dir.join(REDIRECTS_FILE);
+                let mut contents =
+                    fs::read_to_string(&path).unwrap_or_default();
+                let existing = parse_redirects(&contents);
+                if !contents.is_empty() && !contents.ends_with('\n') {
+                    contents.push('\n');
+                }
+                for rule in rules {
+                    if !existing.contains(rule) {
+                        contents.push_str(&format!(
+                            "{} {} {}\n",
+                            rule.from, rule.to, rule.status
+                        ));
+                    }
+                }
+                fs::write(&path, contents).with_context(|| {
+                    format!("Failed to write {}", path.display())
+                })?;
+                written.push(PathBuf::from(REDIRECTS_FILE));
+            }
+            RedirectFormat::Apache => {
+                let path = site_dir.join(HTACCESS_FILE);
+                let existing =
+                    fs::read_to_string(&path).unwrap_or_default();
+                let mut contents = match (
+                    existing.find(HTACCESS_BEGIN),
+                    existing.find(HTACCESS_END),
+                ) {
+                    (Some(begin), Some(end)) if begin < end => format!(
+                        "{}{}",
+                        &existing[..begin],
+                        existing[end + HTACCESS_END.len()..]
+                            .trim_start_matches('\n')
+                    ),
+                    _ => existing,
+                };
+                if !contents.is_empty() && !contents.ends_with('\n') {
+                    contents.push('\n');
+                }
+                contents.push_str(HTACCESS_BEGIN);
+                contents.push('\n');
+                for rule in rules {
+                    contents.push_str(&format!(
+                        "Redirect {} \"{}\" \"{}\"\n",
+                        rule.status, rule.from, rule.to
+                    ));
+                }
+                contents.push_str(HTACCESS_END);
+                contents.push('\n');
+                fs::write(&path, contents).with_context(|| {
+                    format!("Failed to write {}", path.display())
+                })?;
+                written.push(PathBuf::from(HTACCESS_FILE));
+            }
+        }
+    }
+    Ok(written)
+}
+
+/// Returns the site-relative path of the stub page for `alias`:
+/// `old.html` for `/old.html`, `old/index.html` otherwise.
+fn stub_path(alias: &str) -> PathBuf {
+    let mut path: PathBuf = Path::new(alias.trim_start_matches('/'))
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect();
+    if path.extension().and_then(|ext| ext.to_str()) != Some("html") {
+        path.push("index.html");
+    }
+    path
+}
+
+/// A warp filter answering requests that match a rule with a redirect.
+///
+/// Other requests are rejected so the filter can be combined with a file
+/// server using `.or()`.
+pub fn filter(
+    table: Arc<RedirectTable>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path::full().and_then(move |path: FullPath| {
+        let table = Arc::clone(&table);
+        async move {
+            let (status, location) = table
+                .resolve(path.as_str())
+                .ok_or_else(warp::reject::not_found)?;
+            let status = StatusCode::from_u16(status)
+                .unwrap_or(StatusCode::MOVED_PERMANENTLY);
+            Ok::<_, Rejection>(warp::reply::with_header(
+                warp::reply::with_status(warp::reply(), status),
+                LOCATION,
+                location,
+            ))
+        }
+    })
+}
+
+/// Strips `index.html` and trailing slashes so `/a`, `/a/` and
+/// `/a/index.html` compare equal.
+fn normalise(path: &str) -> &str {
+    let path = path.strip_suffix("index.html").unwrap_or(path);
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        "/"
+    } else {
+        trimmed
+    }
+}
+
+/// Returns the URL path of `file` relative to `site_dir`.
+fn url_path(site_dir: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(site_dir).unwrap_or(file);
+    let mut url = String::new();
+    for component in relative.components() {
+        url.push('/');
+        url.push_str(&component.as_os_str().to_string_lossy());
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rule_matching() {
+        let rules = parse_redirects(
+            "/old/ /new/\n/docs/* /guide/:splat 302\n/bad\n/x /y 404",
+        );
+        assert_eq!(rules.len(), 2);
+        let table = RedirectTable { rules };
+
+        assert_eq!(table.resolve("/old"), Some((301, "/new/".into())));
+        assert_eq!(
+            table.resolve("/old/index.html"),
+            Some((301, "/new/".into()))
+        );
+        assert_eq!(
+            table.resolve("/docs/intro/"),
+            Some((302, "/guide/intro/".into()))
+        );
+        assert_eq!(table.resolve("/other/"), None);
+    }
+
+    #[test]
+    fn test_load_redirects_and_stubs() -> Result<()> {
+        let temp_dir = tempdir()?;
+        fs::create_dir_all(temp_dir.path().join("moved"))?;
+        fs::write(temp_dir.path().join(REDIRECTS_FILE), "/a /b 302\n")?;
+        fs::write(
+            temp_dir.path().join("moved/index.html"),
+            r#"<html><head><meta http-equiv="refresh" content="0; url=/here/"></head></html>"#,
+        )?;
+        fs::write(temp_dir.path().join("index.html"), "<p>Home</p>")?;
+
+        let table = RedirectTable::load(temp_dir.path())?;
+        assert_eq!(table.rules.len(), 2);
+        assert_eq!(table.resolve("/a"), Some((302, "/b".into())));
+        assert_eq!(
+            table.resolve("/moved/"),
+            Some((301, "/here/".into()))
+        );
+        assert_eq!(table.resolve("/"), None);
+        Ok(())
+    }
+
+    fn content(pages: &[(&str, &str)]) -> Result<ContentSet> {
+        let dir = tempdir()?;
+        for (relative, raw) in pages {
+            let path = dir.path().join(relative);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, raw)?;
+        }
+        ContentSet::load(dir.path())
+    }
+
+    #[test]
+    fn test_collect_aliases() -> Result<()> {
+        let rules = collect_aliases(&content(&[
+            (
+                "blog/new.md",
+                "---\naliases: [/old-url/, /2019/old-post.html]\n---\n",
+            ),
+            ("about.md", "---\ntitle: About\n---\n"),
+        ])?)?;
+        assert_eq!(
+            rules,
+            vec![
+                RedirectRule {
+                    from: "/2019/old-post.html".into(),
+                    to: "/blog/new/".into(),
+                    status: 301,
+                },
+                RedirectRule {
+                    from: "/old-url/".into(),
+                    to: "/blog/new/".into(),
+                    status: 301,
+                },
+            ]
+        );
+
+        for pages in [
+            &[("a.md", "---\naliases: [old]\n---\n")][..],
+            &[
+                ("a.md", "---\naliases: [/about]\n---\n"),
+                ("about.md", ""),
+            ][..],
+            &[
+                ("a.md", "---\naliases: [/old/]\n---\n"),
+                ("b.md", "---\naliases: [/old]\n---\n"),
+            ][..],
+        ] {
+            assert!(collect_aliases(&content(pages)?).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_redirects() -> Result<()> {
+        let site = tempdir()?;
+        fs::write(site.path().join(REDIRECTS_FILE), "/a /b 302")?;
+        fs::write(
+            site.path().join(HTACCESS_FILE),
+            "ErrorDocument 404 /404.html\n",
+        )?;
+        let rules = parse_redirects("/old/ /new/\n/x.html /y/");
+        let options = RedirectOptions {
+            formats: vec![
+                RedirectFormat::Html,
+                RedirectFormat::Netlify,
+                RedirectFormat::Apache,
+            ],
+        };
+
+        for _ in 0..2 {
+            let _ = write_redirects(site.path(), &rules, &options)?;
+        }
+        let table = RedirectTable::load(site.path())?;
+        assert_eq!(table.resolve("/old/"), Some((301, "/new/".into())));
+        assert_eq!(table.resolve("/x.html"), Some((301, "/y/".into())));
+        assert_eq!(
+            fs::read_to_string(site.path().join(REDIRECTS_FILE))?,
+            "/a /b 302\n/old/ /new/ 301\n/x.html /y/ 301\n"
+        );
+        assert_eq!(
+            fs::read_to_string(site.path().join(HTACCESS_FILE))?,
+            "ErrorDocument 404 /404.html\n# BEGIN ssg aliases\n\
+             Redirect 301 \"/old/\" \"/new/\"\n\
+             Redirect 301 \"/x.html\" \"/y/\"\n# END ssg aliases\n"
+        );
+
+        fs::write(site.path().join("old/index.html"), "<p>Page</p>")?;
+        assert!(write_redirects(site.path(), &rules, &options).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_issues_redirects() {
+        let table = Arc::new(RedirectTable {
+            rules: parse_redirects("/old /new 301"),
+        });
+        let routes = filter(table).or(warp::any().map(|| "page"));
+
+        let response =
+            warp::test::request().path("/old").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(response.headers()[LOCATION], "/new");
+
+        let response =
+            warp::test::request().path("/other").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "page");
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Related Pages
+//!
+//! Computes the most closely related pages for every page in a
+//! [`ContentSet`], so templates and scripts can link to them without manual
+//! cross-linking.
+//!
+//! Pages are scored by shared `tags` (weighted double) and shared keywords.
+//! Keywords are taken from the `keywords` frontmatter field and the
+//! significant words of the title. Ties are broken by URL so the output is
+//! deterministic.
+//!
+//! The result is written to `related.json` in the site root, keyed by page
+//! URL:
+//!
+//! ```json
+//! {
+//!   "/blog/rust-async/": [
+//!     { "title": "Tokio in practice", "url": "/blog/tokio/", "score": 5 }
+//!   ]
+//! }
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::content::{ContentSet, Page};
+
+/// File name of the related pages index written to the site root.
+pub const RELATED_FILE: &str = "related.json";
+
+/// Default number of related pages kept per page.
+pub const DEFAULT_RELATED_LIMIT: usize = 5;
+
+/// Weight of a shared tag relative to a shared keyword.
+const TAG_WEIGHT: u32 = 2;
+
+/// Title words shorter than this are not used as keywords.
+const MIN_KEYWORD_LEN: usize = 4;
+
+/// A page related to another page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelatedPage {
+    /// Title of the related page.
+    pub title: String,
+    /// Site-relative URL of the related page.
+    pub url: String,
+    /// Similarity score; higher is more closely related.
+    pub score: u32,
+}
+
+/// Related pages for every page of a site.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct RelatedIndex {
+    /// Related pages keyed by page URL, best match first.
+    pub pages: BTreeMap<String, Vec<RelatedPage>>,
+}
+
+/// Tags and keywords of a page, normalised for comparison.
+#[derive(Debug)]
+struct Terms {
+    url: String,
+    title: String,
+    tags: BTreeSet<String>,
+    keywords: BTreeSet<String>,
+}
+
+impl Terms {
+    fn from_page(page: &Page) -> Self {
+        let title = page.title();
+        let normalise = |items: Vec<String>| {
+            items
+                .into_iter()
+                .map(|item| item.to_lowercase())
+                .collect::<BTreeSet<_>>()
+        };
+
+        let mut keywords =
+            normalise(page.frontmatter.get_list("keywords"));
+        keywords.extend(
+            title
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| word.chars().count() >= MIN_KEYWORD_LEN)
+                .map(str::to_lowercase),
+        );
+
+        Self {
+            url: page.url_path(),
+            title,
+            tags: normalise(page.frontmatter.get_list("tags")),
+            keywords,
+        }
+    }
+
+    fn score(&self, other: &Self) -> u32 {
+        let tags = self.tags.intersection(&other.tags).count() as u32;
+        let keywords =
+            self.keywords.intersection(&other.keywords).count() as u32;
+        tags * TAG_WEIGHT + keywords
+    }
+}
+
+impl RelatedIndex {
+    /// Computes up to `limit` related pages for every page.
+    ///
+    /// Pages with nothing in common are never listed as related.
+    pub fn build(content: &ContentSet, limit: usize) -> Self {
+        let terms: Vec<_> =
+            content.iter().map(Terms::from_page).collect();
+
+        let pages = terms
+            .iter()
+            .map(|page| {
+                let mut related: Vec<_> = terms
+                    .iter()
+                    .filter(|other| other.url != page.url)
+                    .filter_map(|other| {
+                        let score = page.score(other);
+                        (score > 0).then(|| RelatedPage {
+                            title: other.title.clone(),
+                            url: other.url.clone(),
+                            score,
+                        })
+                    })
+                    .collect();
+                related.sort_by(|a, b| {
+                    b.score
+                        .cmp(&a.score)
+                        .then_with(|| a.url.cmp(&b.url))
+                });
+                related.truncate(limit);
+                (page.url.clone(), related)
+            })
+            .collect();
+
+        Self { pages }
+    }
+
+    /// Returns the related pages for the page at `url`.
+    pub fn get(&self, url: &str) -> &[RelatedPage] {
+        self.pages.get(url).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Writes the index to `related.json` in `site_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn write(&self, site_dir: &Path) -> Result<PathBuf> {
+        let path = site_dir.join(RELATED_FILE);
+        let json = serde_json::to_string_pretty(self)
+            .context("Failed to serialize related pages")?;
+        fs::write(&path, json).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(path: &str, frontmatter: &str) -> Page {
+        Page::from_source(
+            PathBuf::from(path),
+            PathBuf::from(path),
+            &format!("---\n{}\n---\nBody", frontmatter),
+        )
+        .unwrap()
+    }
+
+    fn content() -> ContentSet {
+        ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page(
+                    "async.md",
+                    "title: Async Rust\ntags: [rust, async]",
+                ),
+                page("tokio.md", "title: Tokio\ntags: [rust, async]"),
+                page("serde.md", "title: Serde\ntags: [rust]"),
+                page(
+                    "cooking.md",
+                    "title: Cooking\ntags: [food]\nkeywords: rust",
+                ),
+                page("garden.md", "title: Garden\ntags: [outdoors]"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_related_pages_ranked_by_score() {
+        let index = RelatedIndex::build(&content(), 5);
+        let related: Vec<_> = index
+            .get("/async/")
+            .iter()
+            .map(|page| (page.url.as_str(), page.score))
+            .collect();
+
+        assert_eq!(
+            related,
+            vec![("/tokio/", 4), ("/serde/", 2), ("/cooking/", 1)]
+        );
+        assert!(index.get("/garden/").is_empty());
+    }
+
+    #[test]
+    fn test_related_pages_keywords_and_limit() {
+        let mut set = content();
+        set.pages.push(page(
+            "rust-cooking.md",
+            "title: Rust Cooking\nkeywords: [Cooking]",
+        ));
+        let index = RelatedIndex::build(&set, 1);
+
+        assert_eq!(index.get("/cooking/").len(), 1);
+        assert_eq!(index.get("/cooking/")[0].url, "/rust-cooking/");
+        assert!(index.get("/missing/").is_empty());
+    }
+
+    #[test]
+    fn test_write_related_index() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let index = RelatedIndex::build(&content(), 2);
+        let path = index.write(temp_dir.path())?;
+
+        let written: RelatedIndex =
+            serde_json::from_str(&fs::read_to_string(path)?)?;
+        assert_eq!(written, index);
+        Ok(())
+    }
+}
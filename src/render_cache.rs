@@ -0,0 +1,411 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Render Cache
+//!
+//! An on-disk cache of rendered pages, keyed by a hash of the template's
+//! parsed form and a hash of the render context. A page is only rendered
+//! again when its own context or its template changes, even if other
+//! pages sharing the template did change. With the native backend, the
+//! template half covers the globals the template reads, such as `data`
+//! and `build`, and the state of the functions it calls; templates
+//! calling `fetch_json` are rendered on every build.
+//!
+//! Entries live in `.ssg-cache/render/<key>.html` and survive between
+//! builds. [`RenderCache::prune`] removes entries that were not used in
+//! the current build. Incremental builds (`--incremental`) render pages
+//! through the cache and prune it once the site is written.
+//!
+//! ```rust
+//! use serde_json::json;
+//! use ssg::{
+//!     render_cache::{CachedBackend, RenderCache},
+//!     template::{NativeBackend, TemplateBackend},
+//! };
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let dir = tempfile::tempdir()?;
+//!     let cache = RenderCache::open(dir.path())?;
+//!     let mut backend = CachedBackend::new(NativeBackend::default(), cache);
+//!     backend.add_template("page.html", "<h1>{{ title }}</h1>")?;
+//!
+//!     let context = json!({ "title": "Hello" });
+//!     backend.render("page.html", &context)?;
+//!     backend.render("page.html", &context)?;
+//!     assert_eq!(backend.cache().stats().hits, 1);
+//!     Ok(())
+//! }
+//! ```
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::{
+    template::{hex, read_templates, TemplateBackend},
+    vfs::Vfs,
+};
+
+/// Default cache directory, relative to the project root.
+pub const RENDER_CACHE_DIR: &str = ".ssg-cache/render";
+
+/// Extension of cache entry files.
+const ENTRY_EXTENSION: &str = "html";
+
+/// Cache hits and misses since the cache was opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Renders served from the cache.
+    pub hits: usize,
+    /// Renders that had to run.
+    pub misses: usize,
+}
+
+/// A persistent cache of rendered output.
+///
+/// Safe to share between threads rendering pages in parallel.
+#[derive(Debug)]
+pub struct RenderCache {
+    dir: PathBuf,
+    used: Mutex<HashSet<String>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl RenderCache {
+    /// Opens the cache in `dir`, creating the directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created.
+    pub fn open(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| {
+            format!(
+                "Failed to create cache directory {}",
+                dir.display()
+            )
+        })?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            used: Mutex::new(HashSet::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        })
+    }
+
+    /// The cache directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the cache key for a template hash and render context.
+    pub fn key(template_hash: &str, context: &Value) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(template_hash.as_bytes());
+        hasher.update([0]);
+        hasher.update(context.to_string().as_bytes());
+        hex(&hasher.finalize())
+    }
+
+    /// Returns the cached output for `key`, if any.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let output = fs::read_to_string(self.entry_path(key)).ok()?;
+        self.mark_used(key);
+        Some(output)
+    }
+
+    /// Stores `output` under `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry cannot be written.
+    pub fn insert(&self, key: &str, output: &str) -> Result<()> {
+        let path = self.entry_path(key);
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, output)
+            .and_then(|_| fs::rename(&temp, &path))
+            .with_context(|| {
+                format!(
+                    "Failed to write cache entry {}",
+                    path.display()
+                )
+            })?;
+        self.mark_used(key);
+        Ok(())
+    }
+
+    /// Returns the cached output for the template and context, calling
+    /// `render` and caching its result on a miss.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `render` fails or the entry cannot be written.
+    pub fn get_or_render<F>(
+        &self,
+        template_hash: &str,
+        context: &Value,
+        render: F,
+    ) -> Result<String>
+    where
+        F: FnOnce() -> Result<String>,
+    {
+        let key = Self::key(template_hash, context);
+        if let Some(output) = self.get(&key) {
+            let _ = self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(output);
+        }
+        let _ = self.misses.fetch_add(1, Ordering::Relaxed);
+        let output = render()?;
+        self.insert(&key, &output)?;
+        Ok(output)
+    }
+
+    /// Hits and misses since the cache was opened.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Removes entries not read or written since the cache was opened.
+    ///
+    /// Returns the number of removed entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read or an entry
+    /// cannot be removed.
+    pub fn prune(&self) -> Result<usize> {
+        let used = self
+            .used
+            .lock()
+            .map(|used| used.clone())
+            .unwrap_or_default();
+        let mut removed = 0;
+        for entry in fs::read_dir(&self.dir).with_context(|| {
+            format!("Failed to read {}", self.dir.display())
+        })? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str())
+                != Some(ENTRY_EXTENSION)
+            {
+                continue;
+            }
+            let key = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if !used.contains(&key) {
+                fs::remove_file(&path).with_context(|| {
+                    format!("Failed to remove {}", path.display())
+                })?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", key, ENTRY_EXTENSION))
+    }
+
+    fn mark_used(&self, key: &str) {
+        if let Ok(mut used) = self.used.lock() {
+            let _ = used.insert(key.to_string());
+        }
+    }
+}
+
+/// A [`TemplateBackend`] that serves renders from a [`RenderCache`].
+///
+/// Templates are identified by the backend's
+/// [`fingerprint`](TemplateBackend::fingerprint). For backends without
+/// one, a hash of every template source is used instead, so any template
+/// change invalidates all entries; this keeps inheritance and includes
+/// correct.
+#[derive(Debug)]
+pub struct CachedBackend<B> {
+    backend: B,
+    cache: Arc<RenderCache>,
+    sources: BTreeMap<String, String>,
+}
+
+impl<B: TemplateBackend> CachedBackend<B> {
+    /// Wraps `backend` with `cache`, which may be shared with other
+    /// backends of the same build.
+    pub fn new(backend: B, cache: impl Into<Arc<RenderCache>>) -> Self {
+        Self {
+            backend,
+            cache: cache.into(),
+            sources: BTreeMap::new(),
+        }
+    }
+
+    /// The wrapped backend.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// The wrapped backend, for registering filters and functions.
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// The render cache.
+    pub fn cache(&self) -> &RenderCache {
+        &self.cache
+    }
+
+    fn template_hash(&self, name: &str) -> String {
+        self.backend.fingerprint(name).unwrap_or_else(|| {
+            let mut hasher = Sha256::new();
+            for (name, source) in &self.sources {
+                hasher.update(name.as_bytes());
+                hasher.update([0]);
+                hasher.update(source.as_bytes());
+                hasher.update([0]);
+            }
+            hex(&hasher.finalize())
+        })
+    }
+}
+
+impl<B: TemplateBackend> TemplateBackend for CachedBackend<B> {
+    fn name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    fn add_template(&mut self, name: &str, source: &str) -> Result<()> {
+        self.backend.add_template(name, source)?;
+        let _ =
+            self.sources.insert(name.to_string(), source.to_string());
+        Ok(())
+    }
+
+    fn has_template(&self, name: &str) -> bool {
+        self.backend.has_template(name)
+    }
+
+    fn render(&self, name: &str, context: &Value) -> Result<String> {
+        self.cache.get_or_render(
+            &self.template_hash(name),
+            context,
+            || self.backend.render(name, context),
+        )
+    }
+
+    fn fingerprint(&self, name: &str) -> Option<String> {
+        self.backend.fingerprint(name)
+    }
+
+    fn render_markdown(&self, markdown: &str) -> String {
+        self.backend.render_markdown(markdown)
+    }
+
+    fn add_dir_from(
+        &mut self,
+        vfs: &dyn Vfs,
+        dir: &Path,
+    ) -> Result<usize> {
+        let count = self.backend.add_dir_from(vfs, dir)?;
+        self.sources.extend(read_templates(vfs, dir)?);
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::NativeBackend;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_cache_survives_reopen_and_prunes() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let context = json!({ "title": "A" });
+        {
+            let cache = RenderCache::open(temp_dir.path())?;
+            let output = cache
+                .get_or_render("t1", &context, || Ok("A".into()))?;
+            assert_eq!(output, "A");
+            let _ = cache
+                .get_or_render("t2", &context, || Ok("B".into()))?;
+            assert_eq!(
+                cache.stats(),
+                CacheStats { hits: 0, misses: 2 }
+            );
+        }
+
+        let cache = RenderCache::open(temp_dir.path())?;
+        let output = cache.get_or_render("t1", &context, || {
+            panic!("cached render should not run")
+        })?;
+        assert_eq!(output, "A");
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.prune()?, 1);
+        assert!(cache.get(&RenderCache::key("t2", &context)).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_depends_on_template_and_context() {
+        let a = json!({ "title": "A" });
+        let b = json!({ "title": "B" });
+        assert_eq!(
+            RenderCache::key("t", &a),
+            RenderCache::key("t", &a)
+        );
+        assert_ne!(
+            RenderCache::key("t", &a),
+            RenderCache::key("t", &b)
+        );
+        assert_ne!(
+            RenderCache::key("t", &a),
+            RenderCache::key("u", &a)
+        );
+    }
+
+    #[test]
+    fn test_cached_backend_rerenders_only_changed_inputs() -> Result<()>
+    {
+        let temp_dir = tempdir()?;
+        let mut backend = CachedBackend::new(
+            NativeBackend::default(),
+            RenderCache::open(temp_dir.path())?,
+        );
+        backend.add_template("page.html", "<h1>{{ title }}</h1>")?;
+        backend.add_template("list.html", "<ul>{{ title }}</ul>")?;
+
+        let one = json!({ "title": "One" });
+        let two = json!({ "title": "Two" });
+        assert_eq!(backend.render("page.html", &one)?, "<h1>One</h1>");
+        let _ = backend.render("page.html", &two)?;
+        let _ = backend.render("page.html", &one)?;
+        assert_eq!(
+            backend.cache().stats(),
+            CacheStats { hits: 1, misses: 2 }
+        );
+
+        // Changing another template leaves this page cached.
+        backend.add_template("list.html", "<ol>{{ title }}</ol>")?;
+        let _ = backend.render("page.html", &one)?;
+        assert_eq!(backend.cache().stats().hits, 2);
+
+        // Changing the page template renders it again.
+        backend.add_template("page.html", "<h2>{{ title }}</h2>")?;
+        assert_eq!(backend.render("page.html", &one)?, "<h2>One</h2>");
+        assert_eq!(backend.cache().stats().misses, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,341 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Build Report
+//!
+//! Records how long each build stage took and what the build produced, to
+//! find out where a slow build spends its time. The report is printed as
+//! a summary at the end of a build and can be written as JSON with
+//! `--report build-report.json`:
+//!
+//! ```json
+//! {
+//!   "pages": 120,
+//!   "html_files": 126,
+//!   "assets": 48,
+//!   "output_bytes": 5242880,
+//!   "total_ms": 812.4,
+//!   "stages": [
+//!     { "name": "compile", "duration_ms": 640.2 },
+//!     { "name": "styles", "duration_ms": 12.9 }
+//!   ]
+//! }
+//! ```
+//!
+//! The `compile` stage covers the core compile, which renders Markdown,
+//! applies templates, minifies and writes the sitemaps and feeds.
+//!
+//! When precompression is on, the report also records how many bytes the
+//! `.gz` and `.br` copies save. The copies themselves are not counted in
+//! `assets` and `output_bytes`.
+
+use anyhow::{Context, Result};
+use serde::{Serialize, Serializer};
+use std::{
+    fmt::Write as _,
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    collect_files_recursive,
+    compress::{self, CompressionStats},
+};
+
+/// Timing of one build stage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StageTiming {
+    /// Stage name, e.g. `compile`.
+    pub name: String,
+    /// Time the stage took.
+    #[serde(rename = "duration_ms", serialize_with = "as_millis")]
+    pub duration: Duration,
+}
+
+/// Timings and output statistics of a build.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BuildReport {
+    /// Number of content pages.
+    pub pages: usize,
+    /// Number of HTML files in the site.
+    pub html_files: usize,
+    /// Number of other files in the site, such as images and styles.
+    pub assets: usize,
+    /// Total size of the site in bytes.
+    pub output_bytes: u64,
+    /// Time from the start of the first stage to the end of the last.
+    #[serde(rename = "total_ms", serialize_with = "as_millis")]
+    pub total: Duration,
+    /// Stage timings in the order the stages ran.
+    pub stages: Vec<StageTiming>,
+    /// Sizes of the precompressed copies, if any were written.
+    #[serde(skip_serializing_if = "CompressionStats::is_empty")]
+    pub compression: CompressionStats,
+    #[serde(skip)]
+    started: Option<Instant>,
+}
+
+impl BuildReport {
+    /// Creates an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `stage`, recording how long it took under `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the stage's error; its timing is still recorded.
+    pub fn time<T, F>(&mut self, name: &str, stage: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        let start = Instant::now();
+        let started = *self.started.get_or_insert(start);
+        let result = stage();
+        self.record(name, start.elapsed());
+        self.total = started.elapsed();
+        result
+    }
+
+    /// Records a stage that was timed elsewhere.
+    pub fn record(&mut self, name: &str, duration: Duration) {
+        self.stages.push(StageTiming {
+            name: name.to_string(),
+            duration,
+        });
+    }
+
+    /// Returns the timing of the stage `name`, if it ran.
+    pub fn stage(&self, name: &str) -> Option<Duration> {
+        self.stages
+            .iter()
+            .find(|stage| stage.name == name)
+            .map(|stage| stage.duration)
+    }
+
+    /// Counts the files in `site_dir` and adds up their size, leaving
+    /// out precompressed copies.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or a file's metadata cannot be
+    /// read.
+    pub fn scan_output(&mut self, site_dir: &Path) -> Result<()> {
+        let mut files = Vec::new();
+        if site_dir.exists() {
+            collect_files_recursive(site_dir, &mut files)
+                .with_context(|| {
+                    format!("Failed to read {}", site_dir.display())
+                })?;
+        }
+        self.html_files = 0;
+        self.assets = 0;
+        self.output_bytes = 0;
+        for file in files {
+            if compress::is_precompressed(&file) {
+                continue;
+            }
+            let metadata = fs::metadata(&file).with_context(|| {
+                format!("Failed to read {}", file.display())
+            })?;
+            self.output_bytes += metadata.len();
+            if file.extension().and_then(|ext| ext.to_str())
+                == Some("html")
+            {
+                self.html_files += 1;
+            } else {
+                self.assets += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the report as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .context("Failed to serialize the build report")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        fs::write(path, json).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })
+    }
+
+    /// Renders a console summary with one line per stage and its share
+    /// of the total time.
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "Built {} pages ({} HTML files, {} assets, {}) in {}",
+            self.pages,
+            self.html_files,
+            self.assets,
+            format_bytes(self.output_bytes),
+            format_duration(self.total)
+        );
+        if !self.compression.is_empty() {
+            let stats = &self.compression;
+            let _ = writeln!(
+                out,
+                "Precompressed {} files ({}): gzip {} (-{:.1}%), brotli {} (-{:.1}%)",
+                stats.files,
+                format_bytes(stats.original_bytes),
+                format_bytes(stats.gzip_bytes),
+                stats.savings(stats.gzip_bytes),
+                format_bytes(stats.brotli_bytes),
+                stats.savings(stats.brotli_bytes)
+            );
+        }
+        let width = self
+            .stages
+            .iter()
+            .map(|stage| stage.name.len())
+            .max()
+            .unwrap_or(0);
+        let total = self.total.as_secs_f64();
+        for stage in &self.stages {
+            let share = if total > 0.0 {
+                stage.duration.as_secs_f64() / total * 100.0
+            } else {
+                0.0
+            };
+            let _ = writeln!(
+                out,
+                "  {:<width$}  {:>9}  {:>5.1}%",
+                stage.name,
+                format_duration(stage.duration),
+                share,
+                width = width
+            );
+        }
+        out
+    }
+}
+
+fn as_millis<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+fn format_duration(duration: Duration) -> String {
+    if duration.as_secs() > 0 {
+        format!("{:.2}s", duration.as_secs_f64())
+    } else {
+        format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::thread;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_time_records_stages_and_errors() {
+        let mut report = BuildReport::new();
+        let value = report
+            .time("compile", || {
+                thread::sleep(Duration::from_millis(5));
+                Ok(42)
+            })
+            .unwrap();
+        assert_eq!(value, 42);
+        let failed: Result<()> =
+            report.time("styles", || Err(anyhow!("bad scss")));
+        assert!(failed.is_err());
+
+        assert_eq!(report.stages.len(), 2);
+        assert!(
+            report.stage("compile").unwrap()
+                >= Duration::from_millis(5)
+        );
+        assert!(report.total >= report.stage("compile").unwrap());
+        assert!(report.stage("minify").is_none());
+    }
+
+    #[test]
+    fn test_scan_output_and_json() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let site = temp_dir.path().join("public");
+        fs::create_dir_all(site.join("about"))?;
+        fs::write(site.join("index.html"), "<p>Hi</p>")?;
+        fs::write(site.join("about/index.html"), "<p>About</p>")?;
+        fs::write(site.join("style.css"), "body{}")?;
+        fs::write(site.join("style.css.gz"), "gzip")?;
+
+        let mut report = BuildReport::new();
+        report.pages = 2;
+        report.record("compile", Duration::from_millis(1500));
+        report.scan_output(&site)?;
+        assert_eq!((report.html_files, report.assets), (2, 1));
+        assert_eq!(report.output_bytes, 9 + 12 + 6);
+
+        let path = temp_dir.path().join("reports/build.json");
+        report.write(&path)?;
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path)?)?;
+        assert_eq!(json["pages"], 2);
+        assert_eq!(json["stages"][0]["name"], "compile");
+        assert_eq!(json["stages"][0]["duration_ms"], 1500.0);
+        assert!(json.get("started").is_none());
+        assert!(json.get("compression").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_formatting() {
+        let mut report = BuildReport::new();
+        report.pages = 3;
+        report.output_bytes = 2048;
+        report.total = Duration::from_millis(200);
+        report.record("compile", Duration::from_millis(150));
+        report.record("etags", Duration::from_millis(50));
+
+        let summary = report.summary();
+        assert!(summary.starts_with(
+            "Built 3 pages (0 HTML files, 0 assets, 2.0 KB) in 200.0ms"
+        ));
+        assert!(summary.contains("  compile    150.0ms   75.0%"));
+        assert!(summary.contains("  etags       50.0ms   25.0%"));
+        assert!(!summary.contains("Precompressed"));
+
+        report.compression = CompressionStats {
+            files: 2,
+            original_bytes: 4096,
+            gzip_bytes: 1024,
+            brotli_bytes: 512,
+        };
+        assert!(report.summary().contains(
+            "Precompressed 2 files (4.0 KB): gzip 1.0 KB (-75.0%), brotli 512 B (-87.5%)"
+        ));
+    }
+}
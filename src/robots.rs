@@ -0,0 +1,450 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Robots Directives
+//!
+//! Writes `robots.txt` with a rule group per user agent, and optionally
+//! `ai.txt` and `llms.txt`, from the `[robots]` table of the configuration
+//! file:
+//!
+//! ```toml
+//! [robots]
+//! block_ai_crawlers = true
+//! ai_txt = true
+//! llms_txt = true
+//!
+//! [[robots.groups]]
+//! user_agents = ["Googlebot"]
+//! allow = ["/"]
+//!
+//! [[robots.groups]]
+//! user_agents = ["*"]
+//! disallow = ["/drafts/"]
+//! ```
+//!
+//! `block_ai_crawlers` adds a group disallowing the whole site to the
+//! crawlers in [`AI_CRAWLERS`]. `ai.txt` tells AI crawlers whether the
+//! site may be used for training, and `llms.txt` lists the pages with
+//! their descriptions for language models.
+//!
+//! Without groups or `block_ai_crawlers`, the `robots.txt` written by the
+//! core compile is kept.
+
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    auxiliary::AuxiliaryGenerator, content::ContentSet, protect,
+};
+
+/// User agents of crawlers collecting training data for AI models.
+pub const AI_CRAWLERS: &[&str] = &[
+    "GPTBot",
+    "ChatGPT-User",
+    "OAI-SearchBot",
+    "ClaudeBot",
+    "anthropic-ai",
+    "Google-Extended",
+    "Applebot-Extended",
+    "CCBot",
+    "PerplexityBot",
+    "Bytespider",
+    "Meta-ExternalAgent",
+    "Amazonbot",
+    "cohere-ai",
+];
+
+/// Rules for a set of user agents.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct RobotsGroup {
+    /// User agents the rules apply to, e.g. `Googlebot` or `*`.
+    pub user_agents: Vec<String>,
+    /// Paths the agents may crawl.
+    pub allow: Vec<String>,
+    /// Paths the agents may not crawl.
+    pub disallow: Vec<String>,
+    /// Seconds to wait between requests, for agents that honour it.
+    pub crawl_delay: Option<u32>,
+}
+
+impl RobotsGroup {
+    /// Creates a group disallowing the whole site to `user_agents`.
+    pub fn block(user_agents: &[&str]) -> Self {
+        Self {
+            user_agents: user_agents
+                .iter()
+                .map(|agent| (*agent).to_string())
+                .collect(),
+            disallow: vec!["/".to_string()],
+            ..Self::default()
+        }
+    }
+
+    fn write_to(&self, out: &mut String) {
+        for agent in &self.user_agents {
+            let _ = writeln!(out, "User-agent: {}", agent);
+        }
+        for path in &self.allow {
+            let _ = writeln!(out, "Allow: {}", path);
+        }
+        for path in &self.disallow {
+            let _ = writeln!(out, "Disallow: {}", path);
+        }
+        if self.allow.is_empty() && self.disallow.is_empty() {
+            // A group needs at least one rule; an empty one allows all.
+            out.push_str("Disallow:\n");
+        }
+        if let Some(delay) = self.crawl_delay {
+            let _ = writeln!(out, "Crawl-delay: {}", delay);
+        }
+    }
+}
+
+/// Options of the `[robots]` config table.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct RobotsOptions {
+    /// Disallows the whole site to the crawlers in [`AI_CRAWLERS`].
+    pub block_ai_crawlers: bool,
+    /// Writes `ai.txt`.
+    pub ai_txt: bool,
+    /// Writes `llms.txt`.
+    pub llms_txt: bool,
+    /// Rule groups, in the order they are written.
+    pub groups: Vec<RobotsGroup>,
+}
+
+impl RobotsOptions {
+    /// Returns `true` if the options replace the core `robots.txt`.
+    pub fn writes_robots_txt(&self) -> bool {
+        self.block_ai_crawlers || !self.groups.is_empty()
+    }
+
+    /// Checks that every group names a user agent and that every path
+    /// starts with `/` or `*`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first invalid group.
+    pub fn validate(&self) -> Result<()> {
+        for (i, group) in self.groups.iter().enumerate() {
+            ensure!(
+                !group.user_agents.is_empty()
+                    && group
+                        .user_agents
+                        .iter()
+                        .all(|agent| is_token(agent)),
+                "groups[{}] needs user agents without spaces or newlines",
+                i
+            );
+            for path in group.allow.iter().chain(&group.disallow) {
+                ensure!(
+                    path.starts_with('/') || path.starts_with('*'),
+                    "groups[{}]: path `{}` must start with `/` or `*`",
+                    i,
+                    path
+                );
+                ensure!(
+                    is_token(path),
+                    "groups[{}]: path `{}` contains whitespace",
+                    i,
+                    path
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders `robots.txt`, ending with the sitemap of `base_url`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::robots::{RobotsGroup, RobotsOptions};
+    ///
+    /// let options = RobotsOptions {
+    ///     groups: vec![RobotsGroup::block(&["GPTBot"])],
+    ///     ..RobotsOptions::default()
+    /// };
+    /// assert_eq!(
+    ///     options.robots_txt("https://example.com/"),
+    ///     "User-agent: GPTBot\nDisallow: /\n\n\
+    ///      Sitemap: https://example.com/sitemap.xml\n"
+    /// );
+    /// ```
+    pub fn robots_txt(&self, base_url: &str) -> String {
+        let mut groups = self.groups.clone();
+        if self.block_ai_crawlers {
+            groups.push(RobotsGroup::block(AI_CRAWLERS));
+        }
+        if groups.is_empty() {
+            groups.push(RobotsGroup {
+                user_agents: vec!["*".to_string()],
+                ..RobotsGroup::default()
+            });
+        }
+
+        let mut out = String::new();
+        for group in &groups {
+            group.write_to(&mut out);
+            out.push('\n');
+        }
+        let _ = writeln!(
+            out,
+            "Sitemap: {}/sitemap.xml",
+            base_url.trim_end_matches('/')
+        );
+        out
+    }
+
+    /// Renders `ai.txt`, allowing or disallowing AI training on the whole
+    /// site depending on `block_ai_crawlers`.
+    pub fn ai_txt(&self) -> String {
+        let rule = if self.block_ai_crawlers {
+            "Disallow"
+        } else {
+            "Allow"
+        };
+        format!(
+            "# Permissions for AI training on this site.\nUser-Agent: *\n{}: /\n",
+            rule
+        )
+    }
+}
+
+/// Renders `llms.txt`: the site title and description, then a link to
+/// every page that is not a draft.
+pub fn llms_txt(
+    content: &ContentSet,
+    base_url: &str,
+    title: &str,
+    description: &str,
+) -> String {
+    let base = base_url.trim_end_matches('/');
+    let mut out = format!("# {}\n", title);
+    if !description.is_empty() {
+        let _ = write!(out, "\n> {}\n", description);
+    }
+    out.push_str("\n## Pages\n\n");
+    for page in content.iter() {
+        if page.frontmatter.get_bool("draft") == Some(true)
+            || protect::is_protected(page)
+        {
+            continue;
+        }
+        let _ = write!(
+            out,
+            "- [{}]({}{})",
+            page.title(),
+            base,
+            page.url_path()
+        );
+        match page.description() {
+            Some(text) if !text.is_empty() => {
+                let _ = writeln!(out, ": {}", text);
+            }
+            _ => out.push('\n'),
+        }
+    }
+    out
+}
+
+/// Writes `robots.txt`, `ai.txt` and `llms.txt` as configured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RobotsGenerator {
+    /// The `[robots]` options.
+    pub options: RobotsOptions,
+    /// Base URL of the site, for the sitemap and `llms.txt` links.
+    pub base_url: String,
+    /// Title of the site.
+    pub site_title: String,
+    /// Description of the site.
+    pub site_description: String,
+}
+
+impl AuxiliaryGenerator for RobotsGenerator {
+    fn name(&self) -> &str {
+        "robots"
+    }
+
+    fn generate(
+        &self,
+        content: &ContentSet,
+        site_dir: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        if self.options.writes_robots_txt() {
+            files.push((
+                "robots.txt",
+                self.options.robots_txt(&self.base_url),
+            ));
+        }
+        if self.options.ai_txt {
+            files.push(("ai.txt", self.options.ai_txt()));
+        }
+        if self.options.llms_txt {
+            files.push((
+                "llms.txt",
+                llms_txt(
+                    content,
+                    &self.base_url,
+                    &self.site_title,
+                    &self.site_description,
+                ),
+            ));
+        }
+
+        let mut written = Vec::new();
+        for (name, contents) in files {
+            let path = site_dir.join(name);
+            fs::write(&path, contents).with_context(|| {
+                format!("Failed to write {}", path.display())
+            })?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+fn is_token(value: &str) -> bool {
+    !value.is_empty() && !value.chars().any(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::Page;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_robots_txt_groups() {
+        let options: RobotsOptions = toml::from_str(
+            r#"
+            block_ai_crawlers = true
+
+            [[groups]]
+            user_agents = ["Googlebot", "Bingbot"]
+            allow = ["/"]
+            crawl_delay = 2
+
+            [[groups]]
+            user_agents = ["*"]
+            disallow = ["/drafts/", "*.pdf"]
+            "#,
+        )
+        .unwrap();
+        assert!(options.validate().is_ok());
+
+        let robots = options.robots_txt("https://example.com");
+        assert!(robots.starts_with(
+            "User-agent: Googlebot\nUser-agent: Bingbot\nAllow: /\nCrawl-delay: 2\n\n\
+             User-agent: *\nDisallow: /drafts/\nDisallow: *.pdf\n\n\
+             User-agent: GPTBot\n"
+        ));
+        assert!(
+            robots.contains("User-agent: cohere-ai\nDisallow: /\n\n")
+        );
+        assert!(robots
+            .ends_with("Sitemap: https://example.com/sitemap.xml\n"));
+        assert!(options.ai_txt().contains("Disallow: /"));
+    }
+
+    #[test]
+    fn test_default_options_keep_core_robots_txt() {
+        let options = RobotsOptions::default();
+        assert!(!options.writes_robots_txt());
+        assert_eq!(
+            options.robots_txt("https://example.com"),
+            "User-agent: *\nDisallow:\n\nSitemap: https://example.com/sitemap.xml\n"
+        );
+        assert!(options.ai_txt().contains("Allow: /"));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_groups() {
+        let invalid = [
+            RobotsGroup::default(),
+            RobotsGroup {
+                user_agents: vec!["Google bot".into()],
+                ..RobotsGroup::default()
+            },
+            RobotsGroup {
+                user_agents: vec!["*".into()],
+                disallow: vec!["drafts/".into()],
+                ..RobotsGroup::default()
+            },
+        ];
+        for group in invalid {
+            let options = RobotsOptions {
+                groups: vec![group],
+                ..RobotsOptions::default()
+            };
+            assert!(options.validate().is_err(), "{:?}", options);
+        }
+    }
+
+    #[test]
+    fn test_generator_writes_configured_files() -> Result<()> {
+        let site = tempdir()?;
+        let content = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                Page::from_source(
+                    "content/about.md".into(),
+                    "about.md".into(),
+                    "---\ntitle: About\ndescription: Who we are\n---\n",
+                )?,
+                Page::from_source(
+                    "content/wip.md".into(),
+                    "wip.md".into(),
+                    "---\ntitle: WIP\ndraft: true\n---\n",
+                )?,
+                Page::from_source(
+                    "content/index.md".into(),
+                    "index.md".into(),
+                    "---\ntitle: Home\n---\n",
+                )?,
+            ],
+        };
+        let generator = RobotsGenerator {
+            options: RobotsOptions {
+                block_ai_crawlers: true,
+                ai_txt: true,
+                llms_txt: true,
+                groups: Vec::new(),
+            },
+            base_url: "https://example.com/".into(),
+            site_title: "Example".into(),
+            site_description: "An example site".into(),
+        };
+
+        let files = generator.generate(&content, site.path())?;
+        assert_eq!(files.len(), 3);
+        assert!(fs::read_to_string(site.path().join("robots.txt"))?
+            .starts_with("User-agent: GPTBot\n"));
+        assert_eq!(
+            fs::read_to_string(site.path().join("llms.txt"))?,
+            "# Example\n\n> An example site\n\n## Pages\n\n\
+             - [About](https://example.com/about/): Who we are\n\
+             - [Home](https://example.com/)\n"
+        );
+
+        let generator = RobotsGenerator {
+            options: RobotsOptions::default(),
+            ..generator
+        };
+        let files = generator.generate(&content, tempdir()?.path())?;
+        assert!(files.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Project Scaffolding
+//!
+//! Backs `ssg new <name>`, which creates a project that builds straight
+//! away:
+//!
+//! ```text
+//! my-site/
+//! ├── shokunin.toml     # project config
+//! ├── .gitignore        # ignores build output and caches
+//! ├── archetypes/       # frontmatter for `ssg new post`
+//! ├── content/          # example pages
+//! └── templates/        # default templates
+//! ```
+//!
+//! With `--template <git-url>`, the starter is cloned from a repository
+//! instead, and its git history is removed so the project starts fresh.
+
+use anyhow::{bail, ensure, Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    archetypes::DEFAULT_ARCHETYPE,
+    cmd::{ShokuninConfig, PROJECT_FILE},
+};
+
+/// Default templates written into new projects.
+const STARTER_TEMPLATES: &[(&str, &str)] = &[
+    (
+        "index.html",
+        include_str!("../examples/templates/index.html"),
+    ),
+    ("page.html", include_str!("../examples/templates/page.html")),
+    ("post.html", include_str!("../examples/templates/post.html")),
+    ("main.js", include_str!("../examples/templates/main.js")),
+    ("sw.js", include_str!("../examples/templates/sw.js")),
+];
+
+/// `.gitignore` written into new projects.
+const STARTER_GITIGNORE: &str = "\
+# Build output
+/public/
+
+# Render cache
+/.ssg-cache/
+";
+
+/// Creates a starter project named `name` in `target`.
+///
+/// Returns the files written, relative to `target`.
+///
+/// # Errors
+///
+/// Returns an error if `target` exists and is not an empty directory,
+/// if `name` is empty, or if a file cannot be written.
+///
+/// # Examples
+///
+/// ```rust
+/// use ssg::{cmd::PROJECT_FILE, scaffold::scaffold_project};
+///
+/// fn main() -> anyhow::Result<()> {
+///     let temp = tempfile::tempdir()?;
+///     let target = temp.path().join("my-site");
+///     scaffold_project(&target, "my-site")?;
+///     assert!(target.join(PROJECT_FILE).is_file());
+///     assert!(target.join("content/index.md").is_file());
+///     Ok(())
+/// }
+/// ```
+pub fn scaffold_project(
+    target: &Path,
+    name: &str,
+) -> Result<Vec<PathBuf>> {
+    ensure!(!name.trim().is_empty(), "Project name cannot be empty");
+    ensure_empty_target(target)?;
+
+    let config = starter_config(name);
+    let mut files = vec![
+        (PathBuf::from(PROJECT_FILE), config_toml(&config)?),
+        (PathBuf::from(".gitignore"), STARTER_GITIGNORE.to_string()),
+        (config.content_dir.join("index.md"), index_markdown(&config)),
+        (config.content_dir.join("about.md"), about_markdown(&config)),
+    ];
+    files.push((
+        config.archetypes_dir.join("post.md"),
+        DEFAULT_ARCHETYPE.to_string(),
+    ));
+    files.extend(STARTER_TEMPLATES.iter().map(|(file, source)| {
+        (config.template_dir.join(file), (*source).to_string())
+    }));
+
+    for (relative, contents) in &files {
+        write_file(&target.join(relative), contents)?;
+    }
+    Ok(files.into_iter().map(|(relative, _)| relative).collect())
+}
+
+/// Clones the starter at `url` into `target` with `git`, then removes
+/// its `.git` directory.
+///
+/// # Errors
+///
+/// Returns an error if `target` exists and is not an empty directory,
+/// if `git` cannot be run or the clone fails, or if the cloned starter
+/// has no `shokunin.toml`.
+pub fn clone_starter(url: &str, target: &Path) -> Result<()> {
+    ensure!(!url.trim().is_empty(), "Template URL cannot be empty");
+    ensure!(!url.starts_with('-'), "Invalid template URL `{}`", url);
+    ensure_empty_target(target)?;
+
+    let status = Command::new("git")
+        .args(["clone", "--depth", "1", "--quiet", "--", url])
+        .arg(target)
+        .status()
+        .context("Failed to run `git`; is it installed?")?;
+    if !status.success() {
+        bail!("Failed to clone template `{}` ({})", url, status);
+    }
+
+    let git_dir = target.join(".git");
+    if git_dir.is_dir() {
+        fs::remove_dir_all(&git_dir).with_context(|| {
+            format!("Failed to remove {}", git_dir.display())
+        })?;
+    }
+    ensure!(
+        target.join(PROJECT_FILE).is_file(),
+        "Template `{}` has no {}; is it a Shokunin starter?",
+        url,
+        PROJECT_FILE
+    );
+    Ok(())
+}
+
+/// The config written into a new project: the defaults, named after
+/// the project.
+fn starter_config(name: &str) -> ShokuninConfig {
+    ShokuninConfig {
+        site_name: name.to_string(),
+        site_title: name.to_string(),
+        ..ShokuninConfig::default()
+    }
+}
+
+fn config_toml(config: &ShokuninConfig) -> Result<String> {
+    let toml = toml::to_string_pretty(config)
+        .context("Failed to serialize the starter config")?;
+    Ok(format!(
+        "# Shokunin project configuration.\n# Directories are relative to this file.\n\n{}",
+        toml
+    ))
+}
+
+fn index_markdown(config: &ShokuninConfig) -> String {
+    format!(
+        "---\n\
+         title: \"{title}\"\n\
+         description: \"{description}\"\n\
+         author: \"{title}\"\n\
+         charset: \"utf-8\"\n\
+         language: \"{language}\"\n\
+         locale: \"{locale}\"\n\
+         layout: \"index\"\n\
+         permalink: \"{url}\"\n\
+         url: \"{url}\"\n\
+         name: \"{title}\"\n\
+         short_name: \"{short_name}\"\n\
+         ---\n\n\
+         # Welcome to {title}\n\n\
+         This page lives in `content/index.md`. Edit it, then run `ssg` \
+         to rebuild the site.\n\n\
+         Read more [about this site](./about.html).\n",
+        title = config.site_title,
+        description = config.site_description,
+        language = config.language,
+        locale = config.language.replace('-', "_"),
+        url = config.base_url,
+        short_name = short_name(&config.site_name),
+    )
+}
+
+fn about_markdown(config: &ShokuninConfig) -> String {
+    format!(
+        "---\n\
+         title: \"About\"\n\
+         description: \"About {title}\"\n\
+         author: \"{title}\"\n\
+         charset: \"utf-8\"\n\
+         language: \"{language}\"\n\
+         locale: \"{locale}\"\n\
+         layout: \"page\"\n\
+         permalink: \"{url}/about.html\"\n\
+         url: \"{url}/about.html\"\n\
+         name: \"{title}\"\n\
+         short_name: \"{short_name}\"\n\
+         ---\n\n\
+         # About\n\n\
+         Pages are Markdown files with YAML frontmatter. The `layout` \
+         key picks the template from `templates/`.\n",
+        title = config.site_title,
+        language = config.language,
+        locale = config.language.replace('-', "_"),
+        url = config.base_url,
+        short_name = short_name(&config.site_name),
+    )
+}
+
+/// A lowercase, dash-separated name of at most 12 characters.
+fn short_name(name: &str) -> String {
+    let slug = crate::template::slugify(name);
+    slug.chars()
+        .take(12)
+        .collect::<String>()
+        .trim_end_matches('-')
+        .to_string()
+}
+
+fn ensure_empty_target(target: &Path) -> Result<()> {
+    if !target.exists() {
+        return Ok(());
+    }
+    ensure!(
+        target.is_dir(),
+        "{} already exists and is not a directory",
+        target.display()
+    );
+    let mut entries = fs::read_dir(target).with_context(|| {
+        format!("Failed to read {}", target.display())
+    })?;
+    ensure!(
+        entries.next().is_none(),
+        "{} already exists and is not empty",
+        target.display()
+    );
+    Ok(())
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            format!("Failed to create {}", parent.display())
+        })?;
+    }
+    fs::write(path, contents)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_scaffold_project_writes_a_loadable_project() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let target = temp_dir.path().join("My Blog");
+        let files = scaffold_project(&target, "My Blog")?;
+
+        assert!(files.contains(&PathBuf::from(".gitignore")));
+        assert!(files.contains(&PathBuf::from("templates/page.html")));
+        for file in &files {
+            assert!(target.join(file).is_file(), "{}", file.display());
+        }
+
+        let config =
+            ShokuninConfig::from_file(&target.join(PROJECT_FILE))?;
+        assert_eq!(config.site_name, "My Blog");
+        assert_eq!(config.content_dir, PathBuf::from("content"));
+
+        let index =
+            fs::read_to_string(target.join("content/index.md"))?;
+        assert!(index.contains("short_name: \"my-blog\""));
+        assert!(index.contains("layout: \"index\""));
+        Ok(())
+    }
+
+    #[test]
+    fn test_scaffold_refuses_non_empty_target() -> Result<()> {
+        let temp_dir = tempdir()?;
+        fs::write(temp_dir.path().join("notes.txt"), "keep me")?;
+
+        let error =
+            scaffold_project(temp_dir.path(), "site").unwrap_err();
+        assert!(error.to_string().contains("is not empty"));
+        assert!(
+            scaffold_project(&temp_dir.path().join("x"), " ").is_err()
+        );
+        assert!(clone_starter("--upload-pack=evil", temp_dir.path())
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_short_name() {
+        assert_eq!(short_name("My Blog"), "my-blog");
+        assert_eq!(short_name("A very long site name"), "a-very-long");
+    }
+}
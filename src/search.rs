@@ -0,0 +1,821 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Search Index
+//!
+//! Builds a client-side full-text search index from page titles,
+//! descriptions and body text, written to `search-index.json` in the site
+//! root.
+//!
+//! Each page is analyzed in its own language, taken from the `language`
+//! (or `lang`) frontmatter field and falling back to the site language.
+//! English, French, German and Spanish pages are indexed with stop words
+//! removed and words reduced to their stems; see [`crate::analysis`].
+//! Queries are analyzed the same way for each language in the index.
+//!
+//! The index contains the documents to display and a simple inverted
+//! index mapping each term to the documents containing it, with a weighted
+//! term frequency. Terms in titles count three times, terms in
+//! descriptions twice. The document list can also be fed directly to
+//! lunr.js or elasticlunr.
+//!
+//! ```json
+//! {
+//!   "documents": [
+//!     { "id": 0, "url": "/blog/hello/", "title": "Hello", "description": "", "excerpt": "...", "lang": "en" }
+//!   ],
+//!   "index": { "hello": [[0, 4]] }
+//! }
+//! ```
+//!
+//! Incremental builds keep the index and a hash of each indexed page in
+//! `.ssg-cache/search.json`. The next build analyzes only new and changed
+//! pages and reuses the terms of the others, so rebuild times stay flat
+//! as sites grow; the result is the same index a full build writes.
+//! The cache holds page text, so it can be encrypted at rest; see
+//! [`crate::cache_crypto`].
+//!
+//! `ssg search "query"` runs [`search_site`] over the index of a built
+//! site and prints the matching pages with their scores and a snippet
+//! of the generated page around the first match, to check what the
+//! index finds.
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    analysis::{self, Analyzer, Language},
+    cache_crypto::{self, CacheCipher},
+    content::{ContentSet, Page},
+    diagnostics, protect,
+    template::hex,
+};
+
+/// File name of the search index written to the site root.
+pub const SEARCH_INDEX_FILE: &str = "search-index.json";
+
+/// Default cache of the index kept between incremental builds, relative
+/// to the project root.
+pub const SEARCH_CACHE_FILE: &str = ".ssg-cache/search.json";
+
+/// Maximum number of characters kept in a document excerpt.
+const EXCERPT_CHARS: usize = 160;
+
+/// Number of results `ssg search` prints by default.
+pub const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// Words shown before the first match in a snippet.
+const SNIPPET_WORDS_BEFORE: usize = 8;
+
+/// Words shown from the first match on in a snippet.
+const SNIPPET_WORDS_AFTER: usize = 16;
+
+/// Weight of a term found in the title.
+const TITLE_WEIGHT: u32 = 3;
+
+/// Weight of a term found in the description.
+const DESCRIPTION_WEIGHT: u32 = 2;
+
+static FENCE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^(```|~~~).*$")
+        .unwrap_or_else(|e| panic!("Invalid fence regex: {}", e))
+});
+
+static LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"!?\[([^\]]*)\]\([^)]*\)")
+        .unwrap_or_else(|e| panic!("Invalid link regex: {}", e))
+});
+
+static TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<[^>]*>")
+        .unwrap_or_else(|e| panic!("Invalid tag regex: {}", e))
+});
+
+/// Matches blocks whose text is not page content.
+static NON_CONTENT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<(script|style|nav|header|footer)\b.*?</(script|style|nav|header|footer)\s*>")
+        .unwrap_or_else(|e| panic!("Invalid non-content regex: {}", e))
+});
+
+/// Matches the main content of a page.
+static MAIN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<main\b[^>]*>(.*)</main\s*>")
+        .unwrap_or_else(|e| panic!("Invalid main regex: {}", e))
+});
+
+/// A page entry in the search index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchDocument {
+    /// Position of the document in the index.
+    pub id: usize,
+    /// Site-relative URL of the page.
+    pub url: String,
+    /// Page title.
+    pub title: String,
+    /// Page description, or an empty string.
+    pub description: String,
+    /// The start of the page text.
+    pub excerpt: String,
+    /// Language code the page was analyzed with, such as `en` or `fr`.
+    #[serde(default)]
+    pub lang: String,
+}
+
+/// A document matching a query, with its score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchHit<'a> {
+    /// The matching document.
+    pub document: &'a SearchDocument,
+    /// Sum of the weighted frequencies of the query terms.
+    pub score: u32,
+}
+
+/// A search result as printed by `ssg search`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchResult {
+    /// Site-relative URL of the page.
+    pub url: String,
+    /// Page title.
+    pub title: String,
+    /// Score of the match; higher is better.
+    pub score: u32,
+    /// Text around the first match, or the page excerpt.
+    pub snippet: String,
+}
+
+/// A client-side search index.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct SearchIndex {
+    /// Indexed documents, in content order.
+    pub documents: Vec<SearchDocument>,
+    /// Term to `(document id, weighted frequency)` pairs.
+    pub index: BTreeMap<String, Vec<(usize, u32)>>,
+}
+
+impl SearchIndex {
+    /// Builds the index from every page of `content`.
+    ///
+    /// Pages without a language are analyzed as `default_language`.
+    /// Pages marked `draft: true` are left out.
+    pub fn build(content: &ContentSet, default_language: &str) -> Self {
+        let mut index = Self::default();
+        for page in indexed_pages(content) {
+            index.add_page(page, default_language);
+        }
+        index
+    }
+
+    /// Adds a single page to the index.
+    pub fn add_page(&mut self, page: &Page, default_language: &str) {
+        let id = self.documents.len();
+        let lang = page_language(page, default_language);
+        let analyzer = Analyzer::for_language(&lang);
+        let title = page.title();
+        let description = page.description().unwrap_or_default();
+        let text = plain_text(&page.body);
+
+        let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+        for (source, weight) in [
+            (title.as_str(), TITLE_WEIGHT),
+            (description.as_str(), DESCRIPTION_WEIGHT),
+            (text.as_str(), 1),
+        ] {
+            for term in analyzer.analyze(source) {
+                *counts.entry(term).or_insert(0) += weight;
+            }
+        }
+        for (term, count) in counts {
+            self.index.entry(term).or_default().push((id, count));
+        }
+
+        self.documents.push(SearchDocument {
+            id,
+            url: page.url_path(),
+            title,
+            description,
+            excerpt: excerpt(&text),
+            lang,
+        });
+    }
+
+    /// Returns the documents matching every term of `query`, best first.
+    ///
+    /// The query is analyzed once per language in the index and matched
+    /// against the documents of that language.
+    pub fn search(&self, query: &str) -> Vec<&SearchDocument> {
+        self.search_scored(query)
+            .into_iter()
+            .map(|hit| hit.document)
+            .collect()
+    }
+
+    /// Like [`search`](Self::search), with the score of each document.
+    pub fn search_scored(&self, query: &str) -> Vec<SearchHit<'_>> {
+        let languages: BTreeSet<&str> = self
+            .documents
+            .iter()
+            .map(|doc| doc.lang.as_str())
+            .collect();
+
+        let mut matches = Vec::new();
+        for lang in languages {
+            let terms = Analyzer::for_language(lang).analyze(query);
+            let mut scores: BTreeMap<usize, (usize, u32)> =
+                BTreeMap::new();
+            for term in &terms {
+                for (id, count) in
+                    self.index.get(term).into_iter().flatten()
+                {
+                    if self
+                        .documents
+                        .get(*id)
+                        .map(|doc| doc.lang.as_str())
+                        != Some(lang)
+                    {
+                        continue;
+                    }
+                    let entry = scores.entry(*id).or_insert((0, 0));
+                    entry.0 += 1;
+                    entry.1 += count;
+                }
+            }
+            matches.extend(
+                scores
+                    .into_iter()
+                    .filter(|(_, (hits, _))| *hits == terms.len()),
+            );
+        }
+        matches.sort_by(|a, b| b.1 .1.cmp(&a.1 .1).then(a.0.cmp(&b.0)));
+        matches
+            .into_iter()
+            .filter_map(|(id, (_, score))| {
+                self.documents
+                    .get(id)
+                    .map(|document| SearchHit { document, score })
+            })
+            .collect()
+    }
+
+    /// Reads `search-index.json` from `site_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file is missing or not a search index.
+    pub fn load(site_dir: &Path) -> Result<Self> {
+        let path = site_dir.join(SEARCH_INDEX_FILE);
+        let json = fs::read_to_string(&path).with_context(|| {
+            format!(
+                "Failed to read {}; build the site first",
+                path.display()
+            )
+        })?;
+        serde_json::from_str(&json).with_context(|| {
+            format!("Invalid search index {}", path.display())
+        })
+    }
+
+    /// Writes the index to `search-index.json` in `site_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn write(&self, site_dir: &Path) -> Result<PathBuf> {
+        let path = site_dir.join(SEARCH_INDEX_FILE);
+        let json = serde_json::to_string(self)
+            .context("Failed to serialize search index")?;
+        fs::write(&path, json).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        Ok(path)
+    }
+}
+
+/// A search index with the hash of each indexed page, kept between
+/// incremental builds.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct IncrementalIndex {
+    /// The index.
+    pub index: SearchIndex,
+    /// Hash of the indexed fields of each page, by document id.
+    pub hashes: Vec<String>,
+}
+
+impl IncrementalIndex {
+    /// Builds the index of `content` from scratch.
+    pub fn build(content: &ContentSet, default_language: &str) -> Self {
+        Self {
+            index: SearchIndex::build(content, default_language),
+            hashes: indexed_pages(content)
+                .map(|page| page_hash(page, default_language))
+                .collect(),
+        }
+    }
+
+    /// Indexes `content`, analyzing only the pages that are new or
+    /// changed since this index was built and reusing the terms of the
+    /// others.
+    ///
+    /// Returns the updated index and the number of pages analyzed.
+    pub fn update(
+        &self,
+        content: &ContentSet,
+        default_language: &str,
+    ) -> (Self, usize) {
+        let mut previous: HashMap<&str, usize> = self
+            .hashes
+            .iter()
+            .enumerate()
+            .map(|(id, hash)| (hash.as_str(), id))
+            .collect();
+
+        let mut updated = Self::default();
+        let mut moved = HashMap::new();
+        for page in indexed_pages(content) {
+            let hash = page_hash(page, default_language);
+            let id = updated.index.documents.len();
+            match previous.remove(hash.as_str()) {
+                Some(old) => {
+                    let mut document =
+                        self.index.documents[old].clone();
+                    document.id = id;
+                    updated.index.documents.push(document);
+                    let _ = moved.insert(old, id);
+                }
+                None => updated.index.add_page(page, default_language),
+            }
+            updated.hashes.push(hash);
+        }
+        let analyzed = updated.hashes.len() - moved.len();
+
+        for (term, postings) in &self.index.index {
+            for (old, count) in postings {
+                if let Some(&id) = moved.get(old) {
+                    updated
+                        .index
+                        .index
+                        .entry(term.clone())
+                        .or_default()
+                        .push((id, *count));
+                }
+            }
+        }
+        for postings in updated.index.index.values_mut() {
+            postings.sort_unstable();
+        }
+        (updated, analyzed)
+    }
+
+    /// Reads an index written by [`save`](Self::save), or returns `None`
+    /// if there is none or it cannot be used. A cache sealed by
+    /// [`cache_crypto`] is opened with `cipher`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read.
+    pub fn load(
+        path: &Path,
+        cipher: Option<&CacheCipher>,
+    ) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read(path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        let json = match cache_crypto::decode(data, cipher) {
+            Ok(json) => json,
+            Err(e) => {
+                diagnostics::warn(format!(
+                    "Ignoring search index cache {}: {}",
+                    path.display(),
+                    e
+                ));
+                return Ok(None);
+            }
+        };
+        match serde_json::from_slice::<Self>(&json) {
+            Ok(cached)
+                if cached.hashes.len()
+                    == cached.index.documents.len() =>
+            {
+                Ok(Some(cached))
+            }
+            _ => {
+                diagnostics::warn(format!(
+                    "Ignoring invalid search index cache {}",
+                    path.display()
+                ));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Writes the index to `path` for the next build, sealed with
+    /// `cipher` if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(
+        &self,
+        path: &Path,
+        cipher: Option<&CacheCipher>,
+    ) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        let json = serde_json::to_vec(self)
+            .context("Failed to serialize search index cache")?;
+        let data = cache_crypto::encode(json, cipher)?;
+        fs::write(path, data).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })
+    }
+}
+
+/// Searches the index of the site built in `site_dir`, returning at most
+/// `limit` results. Snippets are taken from the generated pages.
+///
+/// # Errors
+///
+/// Returns an error if the search index cannot be read.
+pub fn search_site(
+    site_dir: &Path,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchResult>> {
+    let index = SearchIndex::load(site_dir)?;
+    Ok(index
+        .search_scored(query)
+        .into_iter()
+        .take(limit)
+        .map(|hit| {
+            let document = hit.document;
+            let snippet = page_text(site_dir, &document.url)
+                .and_then(|text| snippet(&text, query, &document.lang))
+                .unwrap_or_else(|| document.excerpt.clone());
+            SearchResult {
+                url: document.url.clone(),
+                title: document.title.clone(),
+                score: hit.score,
+                snippet,
+            }
+        })
+        .collect())
+}
+
+/// Returns the words of `text` around the first word matching a term of
+/// `query`, analyzed as `lang`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::search::snippet;
+///
+/// let text = "Shokunin renders pages quickly and builds a search index.";
+/// assert_eq!(
+///     snippet(text, "indexing", "en").as_deref(),
+///     Some("Shokunin renders pages quickly and builds a search index.")
+/// );
+/// assert_eq!(snippet(text, "golang", "en"), None);
+/// ```
+pub fn snippet(text: &str, query: &str, lang: &str) -> Option<String> {
+    let analyzer = Analyzer::for_language(lang);
+    let terms: BTreeSet<String> =
+        analyzer.analyze(query).into_iter().collect();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let first = words.iter().position(|word| {
+        analyzer
+            .analyze(word)
+            .iter()
+            .any(|term| terms.contains(term))
+    })?;
+
+    let start = first.saturating_sub(SNIPPET_WORDS_BEFORE);
+    let end = (first + SNIPPET_WORDS_AFTER).min(words.len());
+    Some(format!(
+        "{}{}{}",
+        if start > 0 { "…" } else { "" },
+        words[start..end].join(" "),
+        if end < words.len() { "…" } else { "" }
+    ))
+}
+
+/// Returns the text of the generated page at `url`, preferring its
+/// `<main>` element and leaving out scripts, styles and navigation.
+fn page_text(site_dir: &Path, url: &str) -> Option<String> {
+    let relative = url.trim_start_matches('/');
+    let path = if relative.is_empty() || relative.ends_with('/') {
+        site_dir.join(relative).join("index.html")
+    } else {
+        site_dir.join(relative)
+    };
+    let html = fs::read_to_string(path).ok()?;
+    let html = MAIN_RE
+        .captures(&html)
+        .map_or(html.as_str(), |caps| {
+            caps.get(1).map_or("", |m| m.as_str())
+        })
+        .to_string();
+    let html = NON_CONTENT_RE.replace_all(&html, " ");
+    let text = TAG_RE.replace_all(&html, " ");
+    Some(
+        text.replace("&amp;", "&")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&#39;", "'")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Splits text into lowercase search terms, dropping English stop
+/// words. No stemming is applied.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::search::tokenize;
+///
+/// assert_eq!(tokenize("The Rust Book, 2nd ed."), vec!["rust", "book", "2nd", "ed"]);
+/// ```
+pub fn tokenize(text: &str) -> Vec<String> {
+    analysis::words(text)
+        .filter(|word| !Language::English.is_stop_word(word))
+        .collect()
+}
+
+/// Returns the language code of `page`, or `default_language`.
+///
+/// Supported languages are reduced to their two-letter code.
+/// Returns the pages of `content` that are indexed, leaving out drafts.
+fn indexed_pages(content: &ContentSet) -> impl Iterator<Item = &Page> {
+    content
+        .iter()
+        .filter(|page| page.frontmatter.get_bool("draft") != Some(true))
+        .filter(|page| !protect::is_protected(page))
+}
+
+/// Hashes the fields of `page` that make up its search document.
+fn page_hash(page: &Page, default_language: &str) -> String {
+    let mut hasher = Sha256::new();
+    for field in [
+        page.url_path(),
+        page_language(page, default_language),
+        page.title(),
+        page.description().unwrap_or_default(),
+    ] {
+        hasher.update(field.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(page.body.as_bytes());
+    hex(&hasher.finalize())
+}
+
+fn page_language(page: &Page, default_language: &str) -> String {
+    let code = page
+        .frontmatter
+        .get_str("language")
+        .or_else(|| page.frontmatter.get_str("lang"))
+        .unwrap_or_else(|| default_language.to_string());
+    match Language::from_code(&code) {
+        Some(language) => language.code().to_string(),
+        None => code.to_lowercase(),
+    }
+}
+
+/// Reduces Markdown to plain text for indexing.
+pub fn plain_text(markdown: &str) -> String {
+    let text = FENCE_RE.replace_all(markdown, "");
+    let text = LINK_RE.replace_all(&text, "$1");
+    let text = TAG_RE.replace_all(&text, " ");
+    text.chars()
+        .map(|c| match c {
+            '#' | '*' | '_' | '`' | '>' | '|' | '~' => ' ',
+            c => c,
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn excerpt(text: &str) -> String {
+    if text.chars().count() <= EXCERPT_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(EXCERPT_CHARS).collect();
+    let cut = match cut.rfind(' ') {
+        Some(space) => &cut[..space],
+        None => cut.as_str(),
+    };
+    format!("{}…", cut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(path: &str, raw: &str) -> Page {
+        Page::from_source(PathBuf::from(path), PathBuf::from(path), raw)
+            .unwrap()
+    }
+
+    fn content() -> ContentSet {
+        ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page(
+                    "rust.md",
+                    "---\ntitle: Learning Rust\ndescription: Ownership\n---\n\
+                     # Intro\nRust has *ownership* and [borrowing](/b/).",
+                ),
+                page(
+                    "python.md",
+                    "---\ntitle: Python\n---\nPython is not Rust.",
+                ),
+                page("draft.md", "---\ntitle: Secret Rust\ndraft: true\n---\n"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_plain_text_strips_markdown() {
+        let text = plain_text(
+            "# Title\n```rust\nlet x = 1;\n```\nSee [docs](https://x) <b>now</b>",
+        );
+        assert_eq!(text, "Title let x = 1; See docs now");
+    }
+
+    #[test]
+    fn test_build_weights_and_skips_drafts() {
+        let index = SearchIndex::build(&content(), "en-GB");
+
+        assert_eq!(index.documents.len(), 2);
+        assert_eq!(index.index["rust"], vec![(0, 4), (1, 1)]);
+        assert_eq!(index.index["ownership"], vec![(0, 3)]);
+        assert!(!index.index.contains_key("secret"));
+        assert!(!index.index.contains_key("the"));
+        assert_eq!(index.documents[0].url, "/rust/");
+        assert_eq!(index.documents[0].lang, "en");
+    }
+
+    #[test]
+    fn test_pages_analyzed_in_their_language() {
+        let set = ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page(
+                    "chevaux.md",
+                    "---\ntitle: Les chevaux\nlanguage: fr-FR\n---\nDes chevaux rapides.",
+                ),
+                page(
+                    "haus.md",
+                    "---\ntitle: Die Häuser\nlang: de\n---\n",
+                ),
+                page("learn.md", "---\ntitle: Learning Rust\n---\n"),
+            ],
+        };
+        let index = SearchIndex::build(&set, "en");
+
+        assert!(index.index.contains_key("cheval"));
+        assert!(index.index.contains_key("haus"));
+        assert!(!index.index.contains_key("les"));
+        assert_eq!(index.documents[1].lang, "de");
+
+        let urls = |query: &str| -> Vec<String> {
+            index
+                .search(query)
+                .iter()
+                .map(|doc| doc.url.clone())
+                .collect()
+        };
+        assert_eq!(urls("cheval"), vec!["/chevaux/"]);
+        assert_eq!(urls("haus"), vec!["/haus/"]);
+        assert_eq!(urls("learned"), vec!["/learn/"]);
+        assert!(urls("the").is_empty());
+    }
+
+    #[test]
+    fn test_search_ranks_matches() {
+        let index = SearchIndex::build(&content(), "en-GB");
+        let titles: Vec<_> = index
+            .search("RUST")
+            .iter()
+            .map(|doc| doc.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Learning Rust", "Python"]);
+        assert_eq!(index.search("python rust").len(), 1);
+        assert!(index.search("golang").is_empty());
+    }
+
+    #[test]
+    fn test_search_site() -> Result<()> {
+        let site = tempfile::tempdir()?;
+        let _ = SearchIndex::build(&content(), "en-GB")
+            .write(site.path())?;
+        fs::create_dir_all(site.path().join("rust"))?;
+        fs::write(
+            site.path().join("rust/index.html"),
+            "<nav>Rust Python</nav><main><h1>Learning Rust</h1>\
+             <p>Rust has ownership &amp; borrowing.</p></main>",
+        )?;
+
+        let results = search_site(site.path(), "ownership", 10)?;
+        assert_eq!(
+            results,
+            vec![SearchResult {
+                url: "/rust/".into(),
+                title: "Learning Rust".into(),
+                score: 3,
+                snippet:
+                    "Learning Rust Rust has ownership & borrowing."
+                        .into(),
+            }]
+        );
+
+        // Pages that were not generated fall back to the excerpt.
+        let results = search_site(site.path(), "python", 10)?;
+        assert_eq!(results[0].snippet, "Python is not Rust.");
+        assert_eq!(search_site(site.path(), "rust", 1)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental_update_matches_full_build() -> Result<()> {
+        let cached = IncrementalIndex::build(&content(), "en-GB");
+        let (unchanged, analyzed) = cached.update(&content(), "en-GB");
+        assert_eq!(analyzed, 0);
+        assert_eq!(unchanged, cached);
+
+        let mut edited = content();
+        edited.pages[0] = page(
+            "rust.md",
+            "---\ntitle: Learning Rust\n---\nRust has lifetimes.",
+        );
+        edited.pages.insert(
+            0,
+            page("go.md", "---\ntitle: Go\n---\nGo is not Rust."),
+        );
+        edited
+            .pages
+            .retain(|page| page.relative != Path::new("python.md"));
+        let (updated, analyzed) = cached.update(&edited, "en-GB");
+        assert_eq!(analyzed, 2);
+        assert_eq!(updated, IncrementalIndex::build(&edited, "en-GB"));
+        assert_eq!(updated.index.index["rust"], vec![(0, 1), (1, 4)]);
+
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join(".ssg-cache/search.json");
+        assert_eq!(IncrementalIndex::load(&path, None)?, None);
+        updated.save(&path, None)?;
+        assert_eq!(
+            IncrementalIndex::load(&path, None)?,
+            Some(updated.clone())
+        );
+
+        let cipher = CacheCipher::with_iterations("secret", 1_000);
+        updated.save(&path, Some(&cipher))?;
+        assert!(cache_crypto::is_sealed(&fs::read(&path)?));
+        assert_eq!(
+            IncrementalIndex::load(&path, Some(&cipher))?,
+            Some(updated)
+        );
+        assert_eq!(IncrementalIndex::load(&path, None)?, None);
+        fs::write(&path, "{}")?;
+        assert_eq!(IncrementalIndex::load(&path, Some(&cipher))?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_and_excerpt() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = SearchIndex::build(&content(), "en-GB")
+            .write(temp_dir.path())?;
+        let written: SearchIndex =
+            serde_json::from_str(&fs::read_to_string(path)?)?;
+        assert_eq!(written.documents.len(), 2);
+
+        let long = "word ".repeat(100);
+        let cut = excerpt(long.trim());
+        assert!(cut.ends_with('…'));
+        assert!(cut.chars().count() <= EXCERPT_CHARS + 1);
+        Ok(())
+    }
+}
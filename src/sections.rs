@@ -0,0 +1,828 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Sections
+//!
+//! Every directory of the content tree is a section, listing the pages
+//! in it and its own subsections. A section without an `index.md` gets a
+//! listing page written at its URL, so `/docs/guides/` does not 404 when
+//! only `docs/guides/*.md` exist. The `[sections]` table of the
+//! configuration file picks the order:
+//!
+//! ```toml
+//! [sections]
+//! listings = true   # write pages for sections without an index.md
+//! sort = "date"     # or "title", "weight" or "path"
+//! reverse = false
+//! limit = 0         # entries per section, 0 for all
+//!
+//! [sections.dirs.docs]
+//! sort = "weight"
+//! ```
+//!
+//! `date` lists the newest pages first and undated pages last, `title`
+//! and `path` sort alphabetically, and `weight` orders by the `weight`
+//! frontmatter, lowest first. Draft pages are left out, and so are
+//! pages with `hidden: true` unless `include_hidden` is set.
+//!
+//! A table under `dirs` overrides the order of one content directory,
+//! and the `index.md` of a section overrides both with its own
+//! frontmatter:
+//!
+//! ```yaml
+//! sort_by: title
+//! sort_reverse: true
+//! limit: 10
+//! ```
+//!
+//! The same order applies to the section's feed and to the sitemap;
+//! see [`crate::ordering`].
+//!
+//! Listing pages are rendered with the `section.html` template when the
+//! site or theme has one, with this context:
+//!
+//! | Variable  | Value |
+//! |-----------|-------|
+//! | `section` | The directory of the section, e.g. `docs/guides` |
+//! | `title`   | The section title, from its URL segment |
+//! | `url`     | URL of the listing page |
+//! | `pages`   | The entries of the section, each with `title`, `url`, `description`, `date`, `weight` and `section`, `true` for subsections |
+//!
+//! Without one, a plain list page is written. A section that has an
+//! `index.md` keeps it; its template, like any template rendered by the
+//! compiler, gets two functions:
+//!
+//! | Function | Value |
+//! |----------|-------|
+//! | `section_pages(url)` | The entries of the section at `url` |
+//! | `section_pages_html(url)` | The entries as a `<ul>` of links |
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::Write as _,
+    fs,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    breadcrumbs::segment_title,
+    content::{ContentSet, Page},
+    template::{escape_html, Engine, TemplateBackend},
+};
+
+/// Template used for listing pages when the site or theme provides it.
+pub const SECTION_TEMPLATE: &str = "section.html";
+
+/// Name of the template function returning the entries of a section.
+pub const SECTION_PAGES_FUNCTION: &str = "section_pages";
+
+/// Name of the template function rendering the entries as HTML.
+pub const SECTION_PAGES_HTML_FUNCTION: &str = "section_pages_html";
+
+/// Frontmatter key of a section's `index.md` choosing its order.
+pub const SORT_BY_KEY: &str = "sort_by";
+
+/// Frontmatter key of a section's `index.md` reversing its order.
+pub const SORT_REVERSE_KEY: &str = "sort_reverse";
+
+/// Frontmatter key of a section's `index.md` limiting its entries.
+pub const LIMIT_KEY: &str = "limit";
+
+/// Frontmatter key leaving a page out of listings, feeds and the
+/// sitemap.
+pub const HIDDEN_KEY: &str = "hidden";
+
+/// Order of the entries of a section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionSort {
+    /// Newest first, undated entries last.
+    Date,
+    /// By title.
+    Title,
+    /// By `weight` frontmatter, lowest first, then by title.
+    Weight,
+    /// By URL.
+    Path,
+}
+
+impl Default for SectionSort {
+    fn default() -> Self {
+        Self::Date
+    }
+}
+
+impl FromStr for SectionSort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "date" => Ok(Self::Date),
+            "title" => Ok(Self::Title),
+            "weight" => Ok(Self::Weight),
+            "path" => Ok(Self::Path),
+            _ => bail!(
+                "sort `{}` must be date, title, weight or path",
+                s
+            ),
+        }
+    }
+}
+
+/// Overrides of the order of one content directory.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct SectionOrder {
+    /// Order of the entries.
+    pub sort: Option<SectionSort>,
+    /// Reverses the order.
+    pub reverse: Option<bool>,
+    /// Most entries listed, `0` for all.
+    pub limit: Option<usize>,
+    /// Lists pages with `hidden: true`.
+    pub include_hidden: Option<bool>,
+}
+
+/// The order and filters of one listing, with every override applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListingOrder {
+    /// Order of the entries.
+    pub sort: SectionSort,
+    /// Reverses the order.
+    pub reverse: bool,
+    /// Most entries listed, `0` for all.
+    pub limit: usize,
+    /// Lists pages with `hidden: true`.
+    pub include_hidden: bool,
+}
+
+impl ListingOrder {
+    /// Returns `true` if `page` is listed.
+    pub fn includes(&self, page: &Page) -> bool {
+        page.frontmatter.get_bool("draft") != Some(true)
+            && (self.include_hidden
+                || page.frontmatter.get_bool(HIDDEN_KEY) != Some(true))
+    }
+
+    /// Sorts `entries`, then keeps the first `limit` of them.
+    pub fn apply(&self, entries: &mut Vec<SectionEntry>) {
+        self.sort(entries);
+        if self.limit > 0 {
+            entries.truncate(self.limit);
+        }
+    }
+
+    /// Sorts `entries` without limiting them.
+    pub fn sort(&self, entries: &mut [SectionEntry]) {
+        let by_title = |a: &SectionEntry, b: &SectionEntry| {
+            a.title.to_lowercase().cmp(&b.title.to_lowercase())
+        };
+        entries.sort_by(|a, b| {
+            let order = match self.sort {
+                SectionSort::Date => b
+                    .date
+                    .is_some()
+                    .cmp(&a.date.is_some())
+                    .then_with(|| b.date.cmp(&a.date)),
+                SectionSort::Title => by_title(a, b),
+                SectionSort::Weight => {
+                    a.weight.cmp(&b.weight).then_with(|| by_title(a, b))
+                }
+                SectionSort::Path => Ordering::Equal,
+            };
+            order.then_with(|| a.url.cmp(&b.url))
+        });
+        if self.reverse {
+            entries.reverse();
+        }
+    }
+}
+
+/// Options of the `[sections]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SectionOptions {
+    /// Writes a listing page for each section without an `index.md`.
+    pub listings: bool,
+    /// Order of the entries.
+    pub sort: SectionSort,
+    /// Reverses the order.
+    pub reverse: bool,
+    /// Most entries listed per section, `0` for all.
+    pub limit: usize,
+    /// Lists pages with `hidden: true`.
+    pub include_hidden: bool,
+    /// Order overrides by content directory, e.g. `docs/guides`.
+    pub dirs: BTreeMap<String, SectionOrder>,
+}
+
+impl Default for SectionOptions {
+    fn default() -> Self {
+        Self {
+            listings: true,
+            sort: SectionSort::default(),
+            reverse: false,
+            limit: 0,
+            include_hidden: false,
+            dirs: BTreeMap::new(),
+        }
+    }
+}
+
+impl SectionOptions {
+    /// Checks that the keys of `dirs` are relative directories.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first invalid key.
+    pub fn validate(&self) -> Result<()> {
+        for dir in self.dirs.keys() {
+            ensure!(
+                !dir.is_empty()
+                    && Path::new(dir)
+                        .components()
+                        .all(|c| matches!(c, Component::Normal(_))),
+                "dirs key `{}` must be a directory of the content tree",
+                dir
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the order of the content directory `dir`, whose
+    /// `index.md` is `index` if it has one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `index.md` frontmatter has an invalid
+    /// `sort_by` or `limit`.
+    pub fn order(
+        &self,
+        dir: &Path,
+        index: Option<&Page>,
+    ) -> Result<ListingOrder> {
+        let mut order = ListingOrder {
+            sort: self.sort,
+            reverse: self.reverse,
+            limit: self.limit,
+            include_hidden: self.include_hidden,
+        };
+        let key = dir.to_string_lossy().replace('\\', "/");
+        if let Some(overrides) = self.dirs.get(&key) {
+            order.sort = overrides.sort.unwrap_or(order.sort);
+            order.reverse = overrides.reverse.unwrap_or(order.reverse);
+            order.limit = overrides.limit.unwrap_or(order.limit);
+            order.include_hidden = overrides
+                .include_hidden
+                .unwrap_or(order.include_hidden);
+        }
+
+        if let Some(page) = index {
+            let frontmatter = &page.frontmatter;
+            if let Some(sort) = frontmatter.get_str(SORT_BY_KEY) {
+                order.sort = sort.parse().with_context(|| {
+                    format!(
+                        "Invalid {} in {}",
+                        SORT_BY_KEY,
+                        page.relative.display()
+                    )
+                })?;
+            }
+            if let Some(reverse) =
+                frontmatter.get_bool(SORT_REVERSE_KEY)
+            {
+                order.reverse = reverse;
+            }
+            if let Some(limit) = frontmatter.get_str(LIMIT_KEY) {
+                order.limit = limit.parse().map_err(|_| {
+                    anyhow!(
+                        "{}: limit `{}` is not a whole number",
+                        page.relative.display(),
+                        limit
+                    )
+                })?;
+            }
+        }
+        Ok(order)
+    }
+}
+
+/// A page or subsection listed in a section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SectionEntry {
+    /// Page or section title.
+    pub title: String,
+    /// Site-relative URL.
+    pub url: String,
+    /// The `description` frontmatter, if any.
+    pub description: Option<String>,
+    /// The `date` frontmatter, if any.
+    pub date: Option<String>,
+    /// The `weight` frontmatter, `0` without one.
+    pub weight: i64,
+    /// Whether the entry is a subsection.
+    pub section: bool,
+}
+
+/// A directory of the content tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Directory relative to the content directory.
+    pub dir: PathBuf,
+    /// Title of the section.
+    pub title: String,
+    /// Site-relative URL of the section page.
+    pub url: String,
+    /// Whether the section has an `index.md`.
+    pub has_index: bool,
+    /// Pages and subsections, in the configured order.
+    pub entries: Vec<SectionEntry>,
+}
+
+/// The sections of a site by URL.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sections {
+    sections: BTreeMap<String, Section>,
+}
+
+impl Sections {
+    /// Collects the sections of the non-draft pages of `content`,
+    /// ordering and filtering their entries by `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the order of a section is invalid.
+    pub fn build(
+        content: &ContentSet,
+        options: &SectionOptions,
+    ) -> Result<Self> {
+        let pages: Vec<&Page> = content
+            .iter()
+            .filter(|page| {
+                page.frontmatter.get_bool("draft") != Some(true)
+            })
+            .collect();
+        let dirs: BTreeSet<PathBuf> = pages
+            .iter()
+            .flat_map(|page| page.relative.ancestors().skip(1))
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .collect();
+        let index = |dir: &Path| {
+            pages.iter().copied().find(|page| {
+                page.relative.parent() == Some(dir) && is_index(page)
+            })
+        };
+
+        // Subsections are titled and linked before their parents list
+        // them, so directories are visited deepest first.
+        let mut by_dir: BTreeMap<PathBuf, Section> = BTreeMap::new();
+        let mut ordered: Vec<&PathBuf> = dirs.iter().collect();
+        ordered.sort_by_key(|dir| {
+            std::cmp::Reverse(dir.components().count())
+        });
+        for dir in ordered {
+            let index_page = index(dir);
+            let order = options.order(dir, index_page)?;
+            let mut entries: Vec<SectionEntry> = pages
+                .iter()
+                .filter(|page| {
+                    page.relative.parent() == Some(dir.as_path())
+                        && !is_index(page)
+                        && order.includes(page)
+                })
+                .map(|page| page_entry(page))
+                .collect();
+            for sub in by_dir.values() {
+                if sub.dir.parent() != Some(dir.as_path()) {
+                    continue;
+                }
+                let mut entry = match index(&sub.dir) {
+                    Some(page) if !order.includes(page) => continue,
+                    Some(page) => page_entry(page),
+                    None => SectionEntry {
+                        title: sub.title.clone(),
+                        url: sub.url.clone(),
+                        description: None,
+                        date: None,
+                        weight: 0,
+                        section: true,
+                    },
+                };
+                entry.section = true;
+                entries.push(entry);
+            }
+            order.apply(&mut entries);
+
+            let title = index_page.map_or_else(
+                || {
+                    segment_title(
+                        &dir.file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy(),
+                    )
+                },
+                Page::title,
+            );
+            let url =
+                index_page.map_or_else(|| dir_url(dir), Page::url_path);
+            let _ = by_dir.insert(
+                dir.clone(),
+                Section {
+                    dir: dir.clone(),
+                    title,
+                    url,
+                    has_index: index_page.is_some(),
+                    entries,
+                },
+            );
+        }
+
+        Ok(Self {
+            sections: by_dir
+                .into_values()
+                .map(|section| (section.url.clone(), section))
+                .collect(),
+        })
+    }
+
+    /// Returns the section whose page is at `url`.
+    pub fn get(&self, url: &str) -> Option<&Section> {
+        self.sections.get(url)
+    }
+
+    /// Iterates over the sections in URL order.
+    pub fn iter(&self) -> impl Iterator<Item = &Section> {
+        self.sections.values()
+    }
+
+    /// Renders the entries of the section at `url` as a `<ul>` of
+    /// links, or nothing if there is no such section.
+    pub fn render_html(&self, url: &str) -> String {
+        match self.get(url) {
+            Some(section) => entries_html(&section.entries),
+            None => String::new(),
+        }
+    }
+
+    /// Makes the `section_pages` and `section_pages_html` functions
+    /// available to templates rendered by `engine`.
+    pub fn register(&self, engine: &mut Engine) {
+        let sections = self.clone();
+        engine.register_function(SECTION_PAGES_FUNCTION, move |args| {
+            let url = url_arg(SECTION_PAGES_FUNCTION, args)?;
+            let entries =
+                sections.get(url).map(|section| &section.entries);
+            Ok(serde_json::to_value(entries.unwrap_or(&Vec::new()))?)
+        });
+        let sections = self.clone();
+        engine.register_function(
+            SECTION_PAGES_HTML_FUNCTION,
+            move |args| {
+                let url = url_arg(SECTION_PAGES_HTML_FUNCTION, args)?;
+                Ok(Value::String(sections.render_html(url)))
+            },
+        );
+    }
+}
+
+/// Writes a listing page for every section of `content` without an
+/// `index.md` to `site_dir`, rendered with `backend` when it has the
+/// [`SECTION_TEMPLATE`]. Sections whose URL another page already has
+/// are skipped.
+///
+/// Returns the written paths relative to `site_dir`.
+///
+/// # Errors
+///
+/// Returns an error if the order of a section is invalid, or a page
+/// cannot be rendered or written.
+pub fn write_sections(
+    content: &ContentSet,
+    options: &SectionOptions,
+    backend: &dyn TemplateBackend,
+    site_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    if !options.listings {
+        return Ok(Vec::new());
+    }
+    let taken: HashSet<String> =
+        content.iter().map(Page::url_path).collect();
+    let mut written = Vec::new();
+
+    for section in Sections::build(content, options)?.iter() {
+        if section.has_index || taken.contains(&section.url) {
+            continue;
+        }
+        let context = json!({
+            "section": section.dir.to_string_lossy().replace('\\', "/"),
+            "title": section.title,
+            "url": section.url,
+            "pages": section.entries,
+        });
+        let html = if backend.has_template(SECTION_TEMPLATE) {
+            backend.render(SECTION_TEMPLATE, &context)?
+        } else {
+            section_html(section)
+        };
+
+        let relative =
+            PathBuf::from(section.url.trim_start_matches('/'))
+                .join("index.html");
+        let target = site_dir.join(&relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        fs::write(&target, html).with_context(|| {
+            format!("Failed to write {}", target.display())
+        })?;
+        written.push(relative);
+    }
+    Ok(written)
+}
+
+/// Renders the plain listing page used without a `section.html`
+/// template.
+fn section_html(section: &Section) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n</head>\n<body>\n<main>\n<h1>{title}</h1>\n{list}\n</main>\n</body>\n</html>\n",
+        title = escape_html(&section.title),
+        list = entries_html(&section.entries),
+    )
+}
+
+/// Renders entries as a list of links with their description and
+/// date.
+fn entries_html(entries: &[SectionEntry]) -> String {
+    let mut html = String::from("<ul class=\"section-pages\">");
+    for entry in entries {
+        let _ = write!(
+            html,
+            "<li><a href=\"{}\">{}</a>",
+            escape_html(&entry.url),
+            escape_html(&entry.title)
+        );
+        if let Some(date) = &entry.date {
+            let _ = write!(
+                html,
+                " <time datetime=\"{0}\">{0}</time>",
+                escape_html(date)
+            );
+        }
+        if let Some(description) = &entry.description {
+            let _ = write!(html, "<p>{}</p>", escape_html(description));
+        }
+        html.push_str("</li>");
+    }
+    html.push_str("</ul>");
+    html
+}
+
+/// Returns the entry listing `page`.
+pub(crate) fn page_entry(page: &Page) -> SectionEntry {
+    SectionEntry {
+        title: page.title(),
+        url: page.url_path(),
+        description: page.description(),
+        date: page.frontmatter.get_str("date"),
+        weight: page
+            .frontmatter
+            .get_str("weight")
+            .and_then(|weight| weight.parse().ok())
+            .unwrap_or(0),
+        section: false,
+    }
+}
+
+/// Returns `true` if `page` is the `index.md` of its directory.
+pub(crate) fn is_index(page: &Page) -> bool {
+    page.relative.file_stem().and_then(|stem| stem.to_str())
+        == Some("index")
+}
+
+/// Returns the URL of a content directory, e.g. `/docs/guides/`.
+fn dir_url(dir: &Path) -> String {
+    let mut url = String::from("/");
+    for component in dir.components() {
+        url.push_str(&component.as_os_str().to_string_lossy());
+        url.push('/');
+    }
+    url
+}
+
+/// Returns the section URL argument of `function`.
+fn url_arg<'a>(function: &str, args: &'a [Value]) -> Result<&'a str> {
+    args.first()
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("`{}` expects a section URL", function))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::NativeBackend;
+    use tempfile::tempdir;
+
+    fn page(relative: &str, frontmatter: &str) -> Page {
+        Page::from_source(
+            PathBuf::from("content").join(relative),
+            PathBuf::from(relative),
+            &format!("---\n{}\n---\n", frontmatter),
+        )
+        .unwrap()
+    }
+
+    fn content() -> ContentSet {
+        ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page("index.md", "title: Home"),
+                page("blog/a.md", "title: A\ndate: 2025-01-01"),
+                page("blog/b.md", "title: B\ndate: 2025-02-01"),
+                page("blog/draft.md", "title: Draft\ndraft: true"),
+                page(
+                    "docs/index.md",
+                    "title: Documentation\ndescription: All docs",
+                ),
+                page("docs/install.md", "title: Install\nweight: 2"),
+                page("docs/getting-started/first.md", "title: First"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_build_sections() -> Result<()> {
+        let sections =
+            Sections::build(&content(), &SectionOptions::default())?;
+        let urls: Vec<&str> =
+            sections.iter().map(|s| s.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            ["/blog/", "/docs/", "/docs/getting-started/"]
+        );
+
+        let blog = sections.get("/blog/").unwrap();
+        assert!(!blog.has_index);
+        assert_eq!(blog.title, "Blog");
+        let titles: Vec<&str> =
+            blog.entries.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, ["B", "A"]);
+
+        let docs = sections.get("/docs/").unwrap();
+        assert!(docs.has_index);
+        assert_eq!(docs.entries.len(), 2);
+        let subsection =
+            docs.entries.iter().find(|entry| entry.section).unwrap();
+        assert_eq!(subsection.title, "Getting started");
+        assert_eq!(subsection.url, "/docs/getting-started/");
+
+        let options = SectionOptions {
+            sort: SectionSort::Weight,
+            reverse: true,
+            ..SectionOptions::default()
+        };
+        let docs = Sections::build(&content(), &options)?;
+        assert_eq!(
+            docs.get("/docs/").unwrap().entries[0].title,
+            "Install"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_overrides() -> Result<()> {
+        let mut set = content();
+        set.pages.push(page(
+            "blog/c.md",
+            "title: C
+hidden: true",
+        ));
+        set.pages.push(page(
+            "notes/index.md",
+            "title: Notes
+sort_by: title
+sort_reverse: true
+limit: 2",
+        ));
+        for name in ["x", "y", "z"] {
+            set.pages.push(page(
+                &format!("notes/{}.md", name),
+                &format!("title: {}", name.to_uppercase()),
+            ));
+        }
+
+        let mut options = SectionOptions::default();
+        let _ = options.dirs.insert(
+            "blog".to_string(),
+            SectionOrder {
+                sort: Some(SectionSort::Title),
+                limit: Some(1),
+                ..SectionOrder::default()
+            },
+        );
+        options.validate()?;
+        let sections = Sections::build(&set, &options)?;
+        let titles = |url: &str| -> Vec<String> {
+            sections
+                .get(url)
+                .unwrap()
+                .entries
+                .iter()
+                .map(|e| e.title.clone())
+                .collect()
+        };
+        assert_eq!(titles("/blog/"), ["A"]);
+        assert_eq!(titles("/notes/"), ["Z", "Y"]);
+
+        options.include_hidden = true;
+        options.dirs.clear();
+        let sections = Sections::build(&set, &options)?;
+        assert_eq!(sections.get("/blog/").unwrap().entries.len(), 3);
+
+        set.pages.push(page(
+            "bad/index.md",
+            "title: Bad
+sort_by: size",
+        ));
+        let error = Sections::build(&set, &options).unwrap_err();
+        assert!(format!("{:#}", error).contains("bad/index.md"));
+
+        let _ = options
+            .dirs
+            .insert("../docs".to_string(), SectionOrder::default());
+        assert!(options.validate().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_sections() -> Result<()> {
+        let site = tempdir()?;
+        let mut backend = NativeBackend::default();
+        let written = write_sections(
+            &content(),
+            &SectionOptions::default(),
+            &backend,
+            site.path(),
+        )?;
+        assert_eq!(
+            written,
+            [
+                PathBuf::from("blog/index.html"),
+                PathBuf::from("docs/getting-started/index.html"),
+            ]
+        );
+        let blog =
+            fs::read_to_string(site.path().join("blog/index.html"))?;
+        assert!(blog.contains("<h1>Blog</h1>"));
+        assert!(blog.contains(
+            "<li><a href=\"/blog/b/\">B</a> <time datetime=\"2025-02-01\">"
+        ));
+        assert!(!blog.contains("Draft"));
+
+        backend
+            .add_template(SECTION_TEMPLATE, "{{ title }}: {{ url }}")?;
+        let _ = write_sections(
+            &content(),
+            &SectionOptions::default(),
+            &backend,
+            site.path(),
+        )?;
+        assert_eq!(
+            fs::read_to_string(site.path().join("blog/index.html"))?,
+            "Blog: /blog/"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_register_lists_children() -> Result<()> {
+        let mut engine = Engine::new();
+        Sections::build(&content(), &SectionOptions::default())?
+            .register(&mut engine);
+        let html = engine.render(
+            "{{ section_pages_html(url) }}",
+            &json!({ "url": "/docs/" }),
+        )?;
+        assert!(html.starts_with("<ul class=\"section-pages\">"));
+        assert!(html.contains("<a href=\"/docs/install/\">Install</a>"));
+        assert_eq!(
+            engine.render(
+                "{{ section_pages_html(\"/missing/\") }}",
+                &json!({})
+            )?,
+            ""
+        );
+        Ok(())
+    }
+}
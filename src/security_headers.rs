@@ -0,0 +1,933 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Security Headers
+//!
+//! Writes the security headers of the site in the format of its host:
+//! a Netlify-style `_headers` file, the `headers` of `vercel.json`, or an
+//! nginx snippet of `add_header` directives to `include` in a server
+//! block. Enable it in the `[security_headers]` table of the
+//! configuration file:
+//!
+//! ```toml
+//! [security_headers]
+//! enabled = true
+//! formats = ["netlify", "nginx"]
+//! frame_options = "SAMEORIGIN"
+//!
+//! [security_headers.csp_extra]
+//! connect-src = ["https://api.example.com"]
+//! ```
+//!
+//! The Content Security Policy is built from the generated site: the
+//! origins of the scripts, stylesheets, images, media, frames and fonts
+//! the pages and stylesheets reference are allowed for their directive,
+//! and inline scripts and styles are allowed by their SHA-256 hash.
+//! Scripts held back by the consent banner count with the source they
+//! load once accepted. `style` attributes add `'unsafe-inline'` to
+//! `style-src`; inline event handlers such as `onclick` cannot be
+//! allowed by hash and are reported as warnings instead. Sources the
+//! pages cannot show, such as `fetch` endpoints, go in `csp_extra`.
+//!
+//! The other headers are HSTS, `X-Frame-Options` (mirrored by the
+//! `frame-ancestors` directive), `X-Content-Type-Options`,
+//! `Referrer-Policy`, `Permissions-Policy` and any `custom` headers.
+
+use anyhow::{bail, ensure, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+use url::Url;
+
+use crate::{collect_files_recursive, diagnostics};
+
+/// File name of the Netlify-style headers file in the site root.
+pub const HEADERS_FILE: &str = "_headers";
+
+/// File name of the Vercel configuration in the site root.
+pub const VERCEL_FILE: &str = "vercel.json";
+
+/// File name of the nginx snippet in the site root.
+pub const NGINX_FILE: &str = "nginx-security-headers.conf";
+
+/// Marks the start of the generated block in `_headers`.
+const HEADERS_BEGIN: &str = "# BEGIN ssg security headers";
+
+/// Marks the end of the generated block in `_headers`.
+const HEADERS_END: &str = "# END ssg security headers";
+
+/// Vercel source pattern matching every path.
+const VERCEL_SOURCE: &str = "/(.*)";
+
+/// Directives that fall back to `default-src` and can be extended.
+const FETCH_DIRECTIVES: &[&str] = &[
+    "child-src",
+    "connect-src",
+    "default-src",
+    "font-src",
+    "frame-src",
+    "img-src",
+    "manifest-src",
+    "media-src",
+    "object-src",
+    "script-src",
+    "style-src",
+    "worker-src",
+];
+
+/// Document and navigation directives `csp_extra` may also set.
+const OTHER_DIRECTIVES: &[&str] = &["base-uri", "form-action"];
+
+/// Matches a `<script>` element with its attributes and body.
+static SCRIPT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<script\b([^>]*)>(.*?)</script\s*>")
+        .unwrap_or_else(|e| panic!("Invalid script regex: {}", e))
+});
+
+/// Matches a `<style>` element's body.
+static STYLE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<style\b[^>]*>(.*?)</style\s*>")
+        .unwrap_or_else(|e| panic!("Invalid style regex: {}", e))
+});
+
+/// Matches the opening tags that load a resource.
+static RESOURCE_TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?is)<(img|iframe|link|source|video|audio|track|embed|object)\b([^>]*)>",
+    )
+    .unwrap_or_else(|e| panic!("Invalid resource tag regex: {}", e))
+});
+
+/// Matches an attribute and its value.
+static ATTR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?is)(?:^|\s)([a-z-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>"']+))"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid attribute regex: {}", e))
+});
+
+/// Matches a tag with a `style` attribute.
+static STYLE_ATTR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<[a-z][^>]*\sstyle\s*=\s*["']"#).unwrap_or_else(
+        |e| panic!("Invalid style attribute regex: {}", e),
+    )
+});
+
+/// Matches a tag with an inline event handler such as `onclick`.
+static EVENT_HANDLER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<[a-z][^>]*\son[a-z]+\s*=\s*["']"#)
+        .unwrap_or_else(|e| {
+            panic!("Invalid event handler regex: {}", e)
+        })
+});
+
+/// Matches a `url(...)` in CSS.
+static CSS_URL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)url\(\s*["']?([^"')\s]+)["']?\s*\)"#)
+        .unwrap_or_else(|e| panic!("Invalid CSS url regex: {}", e))
+});
+
+/// A host-specific file the headers are written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeaderFormat {
+    /// A `/*` block in the Netlify-style `_headers` file.
+    Netlify,
+    /// A `/(.*)` entry in the `headers` of `vercel.json`.
+    Vercel,
+    /// `add_header` directives in `nginx-security-headers.conf`.
+    Nginx,
+}
+
+/// `[security_headers]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecurityHeaderOptions {
+    /// Writes the header files.
+    pub enabled: bool,
+    /// Host formats the headers are written in.
+    pub formats: Vec<HeaderFormat>,
+    /// Sends a Content Security Policy built from the site.
+    pub csp: bool,
+    /// Sends the policy as `Content-Security-Policy-Report-Only`.
+    pub csp_report_only: bool,
+    /// Extra sources per CSP directive.
+    pub csp_extra: BTreeMap<String, Vec<String>>,
+    /// URL violation reports are sent to.
+    pub report_uri: Option<String>,
+    /// HSTS max age in seconds; `0` leaves HSTS out.
+    pub hsts_max_age: u64,
+    /// Applies HSTS to subdomains.
+    pub hsts_include_subdomains: bool,
+    /// Asks to be included in browsers' HSTS preload lists.
+    pub hsts_preload: bool,
+    /// `X-Frame-Options`: `DENY`, `SAMEORIGIN` or empty to leave out.
+    pub frame_options: String,
+    /// Sends `X-Content-Type-Options: nosniff`.
+    pub content_type_options: bool,
+    /// `Referrer-Policy`, or empty to leave out.
+    pub referrer_policy: String,
+    /// `Permissions-Policy`, or empty to leave out.
+    pub permissions_policy: String,
+    /// Other headers, by name.
+    pub custom: BTreeMap<String, String>,
+}
+
+impl Default for SecurityHeaderOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            formats: vec![HeaderFormat::Netlify],
+            csp: true,
+            csp_report_only: false,
+            csp_extra: BTreeMap::new(),
+            report_uri: None,
+            hsts_max_age: 31_536_000,
+            hsts_include_subdomains: true,
+            hsts_preload: false,
+            frame_options: "DENY".to_string(),
+            content_type_options: true,
+            referrer_policy: "strict-origin-when-cross-origin"
+                .to_string(),
+            permissions_policy:
+                "camera=(), microphone=(), geolocation=()".to_string(),
+            custom: BTreeMap::new(),
+        }
+    }
+}
+
+impl SecurityHeaderOptions {
+    /// Checks the frame options, the CSP directives and sources and the
+    /// custom header names and values.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first invalid option.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            matches!(
+                self.frame_options.as_str(),
+                "" | "DENY" | "SAMEORIGIN"
+            ),
+            "frame_options must be DENY, SAMEORIGIN or empty, got '{}'",
+            self.frame_options
+        );
+        for (directive, sources) in &self.csp_extra {
+            ensure!(
+                FETCH_DIRECTIVES.contains(&directive.as_str())
+                    || OTHER_DIRECTIVES.contains(&directive.as_str()),
+                "Unknown CSP directive '{}' in csp_extra",
+                directive
+            );
+            for source in sources {
+                ensure!(
+                    !source.is_empty()
+                        && !source.contains(|c: char| {
+                            c.is_whitespace() || c == ';' || c == ','
+                        }),
+                    "Invalid CSP source '{}' for {}",
+                    source,
+                    directive
+                );
+            }
+        }
+        for (name, value) in &self.custom {
+            ensure!(
+                !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-'),
+                "Invalid header name '{}'",
+                name
+            );
+            ensure!(
+                !value.contains(['\n', '\r']),
+                "The value of header '{}' must be a single line",
+                name
+            );
+        }
+        if let Some(uri) = &self.report_uri {
+            ensure!(
+                !uri.contains(|c: char| c.is_whitespace() || c == ';'),
+                "Invalid report_uri '{}'",
+                uri
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the headers for a site using `sources`, in the order they
+    /// are written.
+    pub fn headers(
+        &self,
+        sources: &CspSources,
+    ) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if self.csp {
+            let name = if self.csp_report_only {
+                "Content-Security-Policy-Report-Only"
+            } else {
+                "Content-Security-Policy"
+            };
+            headers.push((name.to_string(), sources.policy(self)));
+        }
+        if self.hsts_max_age > 0 {
+            let mut hsts = format!("max-age={}", self.hsts_max_age);
+            if self.hsts_include_subdomains {
+                hsts.push_str("; includeSubDomains");
+            }
+            if self.hsts_preload {
+                hsts.push_str("; preload");
+            }
+            headers
+                .push(("Strict-Transport-Security".to_string(), hsts));
+        }
+        if !self.frame_options.is_empty() {
+            headers.push((
+                "X-Frame-Options".to_string(),
+                self.frame_options.clone(),
+            ));
+        }
+        if self.content_type_options {
+            headers.push((
+                "X-Content-Type-Options".to_string(),
+                "nosniff".to_string(),
+            ));
+        }
+        if !self.referrer_policy.is_empty() {
+            headers.push((
+                "Referrer-Policy".to_string(),
+                self.referrer_policy.clone(),
+            ));
+        }
+        if !self.permissions_policy.is_empty() {
+            headers.push((
+                "Permissions-Policy".to_string(),
+                self.permissions_policy.clone(),
+            ));
+        }
+        headers.extend(
+            self.custom
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        headers
+    }
+}
+
+/// CSP sources found in a site, by directive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CspSources {
+    /// Sources other than `'self'`, by directive.
+    pub directives: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl CspSources {
+    /// Creates an empty source list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add(&mut self, directive: &str, source: String) {
+        let _ = self
+            .directives
+            .entry(directive.to_string())
+            .or_default()
+            .insert(source);
+    }
+
+    fn add_url(&mut self, directive: &str, url: &str) {
+        if let Some(source) = source_for(url) {
+            self.add(directive, source);
+        }
+    }
+
+    /// Adds the sources referenced by an HTML page, returning the
+    /// number of inline event handlers, which no source can allow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::security_headers::CspSources;
+    ///
+    /// let mut sources = CspSources::new();
+    /// let handlers = sources.scan_html(
+    ///     r#"<script src="https://cdn.example.com/a.js"></script>
+    ///        <img src="/logo.png" onclick="zoom()">"#,
+    /// );
+    /// assert_eq!(handlers, 1);
+    /// assert!(sources.directives["script-src"].contains("https://cdn.example.com"));
+    /// assert!(!sources.directives.contains_key("img-src"));
+    /// ```
+    pub fn scan_html(&mut self, html: &str) -> usize {
+        for script in SCRIPT_RE.captures_iter(html) {
+            let attributes = attributes(&script[1]);
+            let src = attributes
+                .get("src")
+                .or_else(|| attributes.get("data-consent-src"));
+            if let Some(src) = src {
+                self.add_url("script-src", src);
+                continue;
+            }
+            let kind = attributes
+                .get("type")
+                .map(|kind| kind.to_ascii_lowercase())
+                .unwrap_or_default();
+            let executed = matches!(
+                kind.as_str(),
+                "" | "module"
+                    | "text/javascript"
+                    | "application/javascript"
+            ) || (kind == "text/plain"
+                && attributes.contains_key("data-consent"));
+            if executed && !script[2].trim().is_empty() {
+                self.add("script-src", hash_source(&script[2]));
+            }
+        }
+        for style in STYLE_RE.captures_iter(html) {
+            self.add("style-src", hash_source(&style[1]));
+        }
+        if STYLE_ATTR_RE.is_match(html) {
+            self.add("style-src", "'unsafe-inline'".to_string());
+        }
+        for tag in RESOURCE_TAG_RE.captures_iter(html) {
+            let name = tag[1].to_ascii_lowercase();
+            let attributes = attributes(&tag[2]);
+            let directive = match name.as_str() {
+                "img" => "img-src",
+                "iframe" => "frame-src",
+                "embed" | "object" => "object-src",
+                "video" | "audio" | "source" | "track" => "media-src",
+                _ => {
+                    let rel = attributes
+                        .get("rel")
+                        .map(|rel| rel.to_ascii_lowercase())
+                        .unwrap_or_default();
+                    let as_kind =
+                        attributes.get("as").map(String::as_str);
+                    if rel.contains("stylesheet")
+                        || as_kind == Some("style")
+                    {
+                        "style-src"
+                    } else if as_kind == Some("script")
+                        || rel.contains("modulepreload")
+                    {
+                        "script-src"
+                    } else if as_kind == Some("font") {
+                        "font-src"
+                    } else if rel.contains("icon")
+                        || as_kind == Some("image")
+                    {
+                        "img-src"
+                    } else if rel.contains("manifest") {
+                        "manifest-src"
+                    } else {
+                        continue;
+                    }
+                }
+            };
+            for attribute in
+                ["src", "href", "data", "poster", "data-consent-src"]
+            {
+                if let Some(url) = attributes.get(attribute) {
+                    let directive = if attribute == "poster" {
+                        "img-src"
+                    } else {
+                        directive
+                    };
+                    self.add_url(directive, url);
+                }
+            }
+            for attribute in ["srcset", "imagesrcset"] {
+                if let Some(srcset) = attributes.get(attribute) {
+                    for candidate in srcset.split(',') {
+                        if let Some(url) =
+                            candidate.split_whitespace().next()
+                        {
+                            self.add_url("img-src", url);
+                        }
+                    }
+                }
+            }
+        }
+        EVENT_HANDLER_RE.find_iter(html).count()
+    }
+
+    /// Adds the sources of the fonts and images a stylesheet loads.
+    pub fn scan_css(&mut self, css: &str) {
+        for url in CSS_URL_RE.captures_iter(css) {
+            let path =
+                url[1].split(['?', '#']).next().unwrap_or_default();
+            let is_font = [".woff2", ".woff", ".ttf", ".otf", ".eot"]
+                .iter()
+                .any(|ext| path.to_ascii_lowercase().ends_with(ext))
+                || url[1].starts_with("data:font")
+                || url[1].starts_with("data:application/font");
+            let directive =
+                if is_font { "font-src" } else { "img-src" };
+            self.add_url(directive, &url[1]);
+        }
+    }
+
+    /// Returns the policy for these sources with the extra sources,
+    /// frame ancestors and report URI of `options`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::security_headers::{CspSources, SecurityHeaderOptions};
+    ///
+    /// let mut sources = CspSources::new();
+    /// let _ = sources.scan_html(r#"<img src="https://images.example.com/a.png">"#);
+    /// let policy = sources.policy(&SecurityHeaderOptions::default());
+    /// assert!(policy.starts_with("default-src 'self'; base-uri 'self'"));
+    /// assert!(policy.contains("img-src 'self' https://images.example.com"));
+    /// assert!(policy.contains("frame-ancestors 'none'"));
+    /// ```
+    pub fn policy(&self, options: &SecurityHeaderOptions) -> String {
+        let mut directives: BTreeMap<&str, Vec<String>> =
+            BTreeMap::new();
+        for directive in [
+            "default-src",
+            "base-uri",
+            "form-action",
+            "img-src",
+            "script-src",
+            "style-src",
+        ] {
+            directives
+                .entry(directive)
+                .or_default()
+                .push("'self'".to_string());
+        }
+        let _ =
+            directives.insert("object-src", vec!["'none'".to_string()]);
+        for (directive, sources) in &self.directives {
+            let list = directives
+                .entry(directive.as_str())
+                .or_insert_with(|| vec!["'self'".to_string()]);
+            if *list == ["'none'"] {
+                list.clear();
+            }
+            list.extend(sources.iter().cloned());
+        }
+        for (directive, sources) in &options.csp_extra {
+            let list = directives
+                .entry(directive.as_str())
+                .or_insert_with(|| vec!["'self'".to_string()]);
+            if *list == ["'none'"] {
+                list.clear();
+            }
+            for source in sources {
+                if !list.contains(source) {
+                    list.push(source.clone());
+                }
+            }
+        }
+
+        let mut policy = String::new();
+        let default =
+            directives.remove("default-src").unwrap_or_default();
+        let _ = write!(policy, "default-src {}", default.join(" "));
+        for (directive, sources) in directives {
+            let _ =
+                write!(policy, "; {} {}", directive, sources.join(" "));
+        }
+        match options.frame_options.as_str() {
+            "DENY" => policy.push_str("; frame-ancestors 'none'"),
+            "SAMEORIGIN" => policy.push_str("; frame-ancestors 'self'"),
+            _ => {}
+        }
+        if let Some(uri) = &options.report_uri {
+            let _ = write!(policy, "; report-uri {}", uri);
+        }
+        policy
+    }
+}
+
+/// Returns the attributes of a tag, with lowercase names.
+fn attributes(tag: &str) -> BTreeMap<String, String> {
+    ATTR_RE
+        .captures_iter(tag)
+        .map(|caps| {
+            let value = caps
+                .get(2)
+                .or_else(|| caps.get(3))
+                .or_else(|| caps.get(4))
+                .map_or("", |m| m.as_str());
+            (caps[1].to_ascii_lowercase(), value.trim().to_string())
+        })
+        .collect()
+}
+
+/// Returns the CSP source allowing `url`: its origin for absolute URLs,
+/// its scheme for `data:` and `blob:` URLs, and `None` for same-origin
+/// URLs, which `'self'` covers.
+fn source_for(url: &str) -> Option<String> {
+    let url = url.trim();
+    let lower = url.to_ascii_lowercase();
+    for scheme in ["data:", "blob:"] {
+        if lower.starts_with(scheme) {
+            return Some(scheme.to_string());
+        }
+    }
+    let absolute = if url.starts_with("//") {
+        Url::parse(&format!("https:{}", url)).ok()?
+    } else {
+        Url::parse(url).ok()?
+    };
+    if !matches!(absolute.scheme(), "http" | "https") {
+        return None;
+    }
+    Some(absolute.origin().ascii_serialization())
+}
+
+/// Returns the `'sha256-...'` source allowing an inline script or
+/// style.
+fn hash_source(body: &str) -> String {
+    format!("'sha256-{}'", base64(&Sha256::digest(body.as_bytes())))
+}
+
+/// Encodes `bytes` as standard, padded base64.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16)
+            | (u32::from(b[1]) << 8)
+            | u32::from(b[2]);
+        for (i, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if i <= chunk.len() {
+                let index = ((n >> shift) & 63) as usize;
+                out.push(char::from(ALPHABET[index]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Scans the HTML pages and stylesheets of `site_dir` for CSP sources,
+/// warning about pages with inline event handlers.
+///
+/// # Errors
+///
+/// Returns an error if the site or a file cannot be read.
+pub fn collect_sources(site_dir: &Path) -> Result<CspSources> {
+    let mut files = Vec::new();
+    collect_files_recursive(site_dir, &mut files).with_context(
+        || format!("Failed to read {}", site_dir.display()),
+    )?;
+    files.sort();
+    let mut sources = CspSources::new();
+    for path in files {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        if !matches!(extension.as_deref(), Some("html" | "css")) {
+            continue;
+        }
+        let text = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })?;
+        if extension.as_deref() == Some("css") {
+            sources.scan_css(&text);
+            continue;
+        }
+        let handlers = sources.scan_html(&text);
+        if handlers > 0 {
+            diagnostics::warn(format!(
+                "{}: {} inline event handler{} will be blocked by the \
+                 Content Security Policy",
+                path.display(),
+                handlers,
+                if handlers == 1 { "" } else { "s" }
+            ));
+        }
+    }
+    Ok(sources)
+}
+
+/// Writes the security headers of the site in `site_dir` in every
+/// format of `options`, returning the site-relative paths written.
+///
+/// An earlier generated block in `_headers` and the `/(.*)` entry of
+/// `vercel.json` are replaced; other rules in those files are kept.
+///
+/// # Errors
+///
+/// Returns an error if the options are invalid, `vercel.json` is not a
+/// JSON object, or a file cannot be read or written.
+pub fn write_security_headers(
+    site_dir: &Path,
+    options: &SecurityHeaderOptions,
+) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    if !options.enabled || !site_dir.exists() {
+        return Ok(written);
+    }
+    options.validate()?;
+    let sources = if options.csp {
+        collect_sources(site_dir)?
+    } else {
+        CspSources::new()
+    };
+    let headers = options.headers(&sources);
+
+    for format in &options.formats {
+        let (file, contents) = match format {
+            HeaderFormat::Netlify => {
+                (HEADERS_FILE, netlify_headers(site_dir, &headers))
+            }
+            HeaderFormat::Vercel => {
+                (VERCEL_FILE, vercel_config(site_dir, &headers)?)
+            }
+            HeaderFormat::Nginx => {
+                (NGINX_FILE, nginx_snippet(&headers))
+            }
+        };
+        let path = site_dir.join(file);
+        fs::write(&path, contents).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        written.push(PathBuf::from(file));
+    }
+    Ok(written)
+}
+
+/// Returns `_headers` with the generated `/*` block replaced.
+fn netlify_headers(
+    site_dir: &Path,
+    headers: &[(String, String)],
+) -> String {
+    let existing = fs::read_to_string(site_dir.join(HEADERS_FILE))
+        .unwrap_or_default();
+    let mut contents = match (
+        existing.find(HEADERS_BEGIN),
+        existing.find(HEADERS_END),
+    ) {
+        (Some(begin), Some(end)) if begin < end => format!(
+            "{}{}",
+            &existing[..begin],
+            existing[end + HEADERS_END.len()..]
+                .trim_start_matches('\n')
+        ),
+        _ => existing,
+    };
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(HEADERS_BEGIN);
+    contents.push_str("\n/*\n");
+    for (name, value) in headers {
+        let _ = writeln!(contents, "  {}: {}", name, value);
+    }
+    contents.push_str(HEADERS_END);
+    contents.push('\n');
+    contents
+}
+
+/// Returns `vercel.json` with the `/(.*)` header entry replaced.
+fn vercel_config(
+    site_dir: &Path,
+    headers: &[(String, String)],
+) -> Result<String> {
+    let path = site_dir.join(VERCEL_FILE);
+    let mut config = match fs::read_to_string(&path) {
+        Ok(existing) => {
+            serde_json::from_str(&existing).with_context(|| {
+                format!("Failed to parse {}", path.display())
+            })?
+        }
+        Err(_) => json!({}),
+    };
+    let object = match config.as_object_mut() {
+        Some(object) => object,
+        None => bail!("{} must contain a JSON object", path.display()),
+    };
+    let entries = object
+        .entry("headers")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    let entries = match entries.as_array_mut() {
+        Some(entries) => entries,
+        None => {
+            bail!("`headers` in {} must be an array", path.display())
+        }
+    };
+    entries.retain(|entry| entry["source"] != VERCEL_SOURCE);
+    entries.push(json!({
+        "source": VERCEL_SOURCE,
+        "headers": headers
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": value }))
+            .collect::<Vec<_>>(),
+    }));
+    let mut contents = serde_json::to_string_pretty(&config)
+        .context("Failed to serialize vercel.json")?;
+    contents.push('\n');
+    Ok(contents)
+}
+
+/// Returns the nginx snippet of `add_header` directives.
+fn nginx_snippet(headers: &[(String, String)]) -> String {
+    let mut contents = String::from(
+        "# Generated by ssg. Include it in the server block of the site.\n",
+    );
+    for (name, value) in headers {
+        let _ = writeln!(
+            contents,
+            "add_header {} \"{}\" always;",
+            name,
+            value.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+    }
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const PAGE: &str = r#"<html><head>
+<link rel="stylesheet" href="https://fonts.googleapis.com/css2?family=Inter">
+<link rel="stylesheet" href="/style.css">
+<style>body{margin:0}</style>
+<script type="application/ld+json">{"@type":"Article"}</script>
+</head><body>
+<img src="https://images.example.com/a.png" srcset="/a-2x.png 2x, data:image/png;base64,AA 1x">
+<iframe src="https://www.youtube-nocookie.com/embed/x"></iframe>
+<script type="text/plain" data-consent="analytics" data-consent-src="https://stats.example.com/s.js"></script>
+<script>console.log("hi")</script>
+<p style="color: red">Hi</p>
+</body></html>"#;
+
+    #[test]
+    fn test_scan_html_sources() {
+        let mut sources = CspSources::new();
+        assert_eq!(sources.scan_html(PAGE), 0);
+        sources.scan_css(
+            "@font-face{src:url(https://fonts.gstatic.com/s/inter.woff2)}",
+        );
+        let policy = sources.policy(&SecurityHeaderOptions::default());
+
+        assert!(policy.contains("script-src 'self' 'sha256-"));
+        assert!(policy.contains("https://stats.example.com"));
+        assert!(!policy.contains("Article"));
+        assert!(policy.contains("style-src 'self' 'sha256-"));
+        assert!(policy
+            .contains("'unsafe-inline' https://fonts.googleapis.com"));
+        assert!(policy.contains(
+            "img-src 'self' data: https://images.example.com"
+        ));
+        assert!(policy.contains(
+            "frame-src 'self' https://www.youtube-nocookie.com"
+        ));
+        assert!(policy
+            .contains("font-src 'self' https://fonts.gstatic.com"));
+        assert!(policy.contains("object-src 'none'"));
+    }
+
+    #[test]
+    fn test_hash_source_matches_known_digest() {
+        // printf 'alert(1)' | openssl dgst -sha256 -binary | base64
+        assert_eq!(
+            hash_source("alert(1)"),
+            "'sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI='"
+        );
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"abcd"), "YWJjZA==");
+    }
+
+    #[test]
+    fn test_write_security_headers() -> Result<()> {
+        let dir = tempdir()?;
+        let site = dir.path();
+        fs::write(site.join("index.html"), PAGE)?;
+        fs::write(
+            site.join(HEADERS_FILE),
+            "/fonts/*\n  Cache-Control: max-age=31536000\n",
+        )?;
+        fs::write(
+            site.join(VERCEL_FILE),
+            r#"{"cleanUrls": true, "headers": [{"source": "/(.*)", "headers": []}]}"#,
+        )?;
+
+        let options = SecurityHeaderOptions {
+            enabled: true,
+            formats: vec![
+                HeaderFormat::Netlify,
+                HeaderFormat::Vercel,
+                HeaderFormat::Nginx,
+            ],
+            custom: [(
+                "Cross-Origin-Opener-Policy".to_string(),
+                "same-origin".to_string(),
+            )]
+            .into_iter()
+            .collect(),
+            ..SecurityHeaderOptions::default()
+        };
+        let written = write_security_headers(site, &options)?;
+        assert_eq!(written.len(), 3);
+        let _ = write_security_headers(site, &options)?;
+
+        let netlify = fs::read_to_string(site.join(HEADERS_FILE))?;
+        assert!(netlify.starts_with("/fonts/*\n"));
+        assert_eq!(netlify.matches(HEADERS_BEGIN).count(), 1);
+        assert!(netlify.contains("  Strict-Transport-Security: max-age=31536000; includeSubDomains\n"));
+        assert!(netlify
+            .contains("  Cross-Origin-Opener-Policy: same-origin\n"));
+
+        let vercel: Value = serde_json::from_str(&fs::read_to_string(
+            site.join(VERCEL_FILE),
+        )?)?;
+        assert_eq!(vercel["cleanUrls"], true);
+        assert_eq!(vercel["headers"].as_array().map(Vec::len), Some(1));
+        assert_eq!(
+            vercel["headers"][0]["headers"][1]["key"],
+            "Strict-Transport-Security"
+        );
+
+        let nginx = fs::read_to_string(site.join(NGINX_FILE))?;
+        assert!(nginx
+            .contains("add_header X-Frame-Options \"DENY\" always;"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(SecurityHeaderOptions::default().validate().is_ok());
+        let invalid = SecurityHeaderOptions {
+            csp_extra: [(
+                "script".to_string(),
+                vec!["https://a.com".to_string()],
+            )]
+            .into_iter()
+            .collect(),
+            ..SecurityHeaderOptions::default()
+        };
+        assert!(invalid.validate().is_err());
+        let invalid = SecurityHeaderOptions {
+            frame_options: "ALLOW-FROM https://a.com".into(),
+            ..SecurityHeaderOptions::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,544 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # security.txt
+//!
+//! Writes `/.well-known/security.txt` ([RFC 9116]) from the
+//! `[security_txt]` table of the configuration file, so security
+//! researchers know how to report a vulnerability:
+//!
+//! ```toml
+//! [security_txt]
+//! enabled = true
+//! contact = ["mailto:security@example.com"]
+//! expires = "2026-12-31T23:59:59Z"   # the default is a year after the build
+//! encryption = "https://example.com/pgp-key.txt"
+//! acknowledgments = "https://example.com/hall-of-fame/"
+//! preferred_languages = ["en", "fr"]
+//! policy = "https://example.com/security-policy/"
+//! hiring = "https://example.com/jobs/"
+//! sign_command = ["gpg", "--clearsign", "--local-user", "security@example.com"]
+//! ```
+//!
+//! Fields the table leaves unset are taken from the `security_*`
+//! frontmatter of the home page, such as `security_contact` and
+//! `security_expires`. `Canonical` defaults to the file's URL under
+//! `base_url`.
+//!
+//! A fixed `expires` date is checked on every build. A warning is
+//! logged once it is less than `expiry_warning_days` away, when it has
+//! passed, and when it is more than a year ahead, which RFC 9116 advises
+//! against. Without a fixed date, `Expires` rolls over to a year after
+//! each build.
+//!
+//! With `sign_command`, a program and its arguments, the file is piped
+//! through the command, run without a shell, and its output is written
+//! instead, so the file can be OpenPGP clearsigned with `gpg`.
+//!
+//! [RFC 9116]: https://www.rfc-editor.org/rfc/rfc9116
+
+use anyhow::{ensure, Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    auxiliary::AuxiliaryGenerator,
+    content::{ContentSet, Page},
+    dates::Date,
+    diagrams::pipe_command,
+};
+
+/// Path of the file below the site directory.
+pub const SECURITY_TXT_PATH: &str = ".well-known/security.txt";
+
+/// Days before `expires` from which a warning is logged by default.
+pub const DEFAULT_EXPIRY_WARNING_DAYS: u32 = 30;
+
+/// Options of the `[security_txt]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecurityTxtOptions {
+    /// Writes `/.well-known/security.txt`.
+    pub enabled: bool,
+    /// `mailto:`, `tel:` or `https://` addresses to report issues to.
+    pub contact: Vec<String>,
+    /// Date after which the file is stale.
+    pub expires: Option<String>,
+    /// Key to encrypt reports with.
+    pub encryption: Option<String>,
+    /// Page thanking reporters.
+    pub acknowledgments: Option<String>,
+    /// Languages reports may be written in.
+    pub preferred_languages: Vec<String>,
+    /// URL of the file, overriding the one under `base_url`.
+    pub canonical: Option<String>,
+    /// Vulnerability disclosure policy.
+    pub policy: Option<String>,
+    /// Security job openings.
+    pub hiring: Option<String>,
+    /// Command signing the file, as a program and its arguments, e.g.
+    /// `["gpg", "--clearsign"]`.
+    pub sign_command: Option<Vec<String>>,
+    /// Days before `expires` from which a warning is logged.
+    pub expiry_warning_days: u32,
+}
+
+impl Default for SecurityTxtOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            contact: Vec::new(),
+            expires: None,
+            encryption: None,
+            acknowledgments: None,
+            preferred_languages: Vec::new(),
+            canonical: None,
+            policy: None,
+            hiring: None,
+            sign_command: None,
+            expiry_warning_days: DEFAULT_EXPIRY_WARNING_DAYS,
+        }
+    }
+}
+
+impl SecurityTxtOptions {
+    /// Checks that the contacts and URLs use the schemes RFC 9116
+    /// allows, that `expires` is a date and that the sign command
+    /// names a program when given.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid option.
+    pub fn validate(&self) -> Result<()> {
+        for contact in &self.contact {
+            ensure!(
+                is_contact(contact),
+                "contact `{}` must start with mailto:, tel: or https://",
+                contact
+            );
+        }
+        if let Some(expires) = &self.expires {
+            ensure!(
+                Date::parse(expires).is_some(),
+                "expires `{}` must be a date such as 2026-12-31T23:59:59Z",
+                expires
+            );
+        }
+        for (name, url) in [
+            ("encryption", &self.encryption),
+            ("acknowledgments", &self.acknowledgments),
+            ("canonical", &self.canonical),
+            ("policy", &self.policy),
+            ("hiring", &self.hiring),
+        ] {
+            if let Some(url) = url {
+                ensure!(
+                    url.starts_with("https://")
+                        || (name == "encryption"
+                            && (url.starts_with("openpgp4fpr:")
+                                || url.starts_with("dns:"))),
+                    "{} `{}` must be an https:// URL",
+                    name,
+                    url
+                );
+            }
+        }
+        for language in &self.preferred_languages {
+            ensure!(
+                !language.is_empty()
+                    && language
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-'),
+                "preferred_languages: `{}` must be a language tag",
+                language
+            );
+        }
+        if let Some(command) = &self.sign_command {
+            let program = command.first().map_or("", String::as_str);
+            ensure!(
+                !program.trim().is_empty(),
+                "sign_command must name a program"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The fields of `security.txt`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SecurityData {
+    /// Addresses to report issues to.
+    pub contact: Vec<String>,
+    /// Expiry date in RFC 3339.
+    pub expires: String,
+    /// Key to encrypt reports with.
+    pub encryption: Option<String>,
+    /// Page thanking reporters.
+    pub acknowledgments: Option<String>,
+    /// Languages reports may be written in.
+    pub preferred_languages: Vec<String>,
+    /// URL of the file.
+    pub canonical: String,
+    /// Vulnerability disclosure policy.
+    pub policy: Option<String>,
+    /// Security job openings.
+    pub hiring: Option<String>,
+}
+
+impl SecurityData {
+    /// Creates the data from the `[security_txt]` options, with the
+    /// canonical URL under `base_url` and, without a fixed date, an
+    /// expiry a year after `today`.
+    pub fn from_config(
+        options: &SecurityTxtOptions,
+        base_url: &str,
+        today: Date,
+    ) -> Self {
+        Self {
+            contact: options.contact.clone(),
+            expires: match &options.expires {
+                Some(expires) => rfc3339(expires),
+                None => format!(
+                    "{}T00:00:00Z",
+                    Date::from_days(today.days() + 365)
+                ),
+            },
+            encryption: options.encryption.clone(),
+            acknowledgments: options.acknowledgments.clone(),
+            preferred_languages: options.preferred_languages.clone(),
+            canonical: options.canonical.clone().unwrap_or_else(|| {
+                format!(
+                    "{}/{}",
+                    base_url.trim_end_matches('/'),
+                    SECURITY_TXT_PATH
+                )
+            }),
+            policy: options.policy.clone(),
+            hiring: options.hiring.clone(),
+        }
+    }
+
+    /// Fills the fields `options` leaves unset from the `security_*`
+    /// frontmatter of `page`.
+    pub fn with_frontmatter(
+        mut self,
+        options: &SecurityTxtOptions,
+        page: &Page,
+    ) -> Self {
+        let frontmatter = &page.frontmatter;
+        if options.contact.is_empty() {
+            self.contact = frontmatter.get_list("security_contact");
+        }
+        if options.expires.is_none() {
+            if let Some(expires) = frontmatter
+                .get_str("security_expires")
+                .filter(|expires| Date::parse(expires).is_some())
+            {
+                self.expires = rfc3339(&expires);
+            }
+        }
+        if options.preferred_languages.is_empty() {
+            self.preferred_languages =
+                frontmatter.get_list("security_languages");
+        }
+        if options.canonical.is_none() {
+            if let Some(canonical) =
+                frontmatter.get_str("security_canonical")
+            {
+                self.canonical = canonical;
+            }
+        }
+        for (field, key) in [
+            (&mut self.encryption, "security_encryption"),
+            (&mut self.acknowledgments, "security_acknowledgments"),
+            (&mut self.policy, "security_policy"),
+            (&mut self.hiring, "security_hiring"),
+        ] {
+            if field.is_none() {
+                *field = frontmatter.get_str(key);
+            }
+        }
+        self
+    }
+
+    /// Renders `security.txt`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::security_txt::SecurityData;
+    ///
+    /// let data = SecurityData {
+    ///     contact: vec!["mailto:security@example.com".into()],
+    ///     expires: "2026-12-31T23:59:59Z".into(),
+    ///     canonical: "https://example.com/.well-known/security.txt"
+    ///         .into(),
+    ///     ..SecurityData::default()
+    /// };
+    /// assert_eq!(
+    ///     data.to_text(),
+    ///     "Contact: mailto:security@example.com\n\
+    ///      Expires: 2026-12-31T23:59:59Z\n\
+    ///      Canonical: https://example.com/.well-known/security.txt\n"
+    /// );
+    /// ```
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for contact in &self.contact {
+            let _ = writeln!(out, "Contact: {}", contact);
+        }
+        let _ = writeln!(out, "Expires: {}", self.expires);
+        if let Some(encryption) = &self.encryption {
+            let _ = writeln!(out, "Encryption: {}", encryption);
+        }
+        if let Some(acknowledgments) = &self.acknowledgments {
+            let _ =
+                writeln!(out, "Acknowledgments: {}", acknowledgments);
+        }
+        if !self.preferred_languages.is_empty() {
+            let _ = writeln!(
+                out,
+                "Preferred-Languages: {}",
+                self.preferred_languages.join(", ")
+            );
+        }
+        let _ = writeln!(out, "Canonical: {}", self.canonical);
+        if let Some(policy) = &self.policy {
+            let _ = writeln!(out, "Policy: {}", policy);
+        }
+        if let Some(hiring) = &self.hiring {
+            let _ = writeln!(out, "Hiring: {}", hiring);
+        }
+        out
+    }
+}
+
+/// Returns the warning due for a file expiring on `expires`, if any.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::dates::Date;
+/// use ssg::security_txt::expiry_warning;
+///
+/// let today = Date::parse("2026-12-10").unwrap();
+/// let expires = Date::parse("2026-12-31").unwrap();
+/// assert_eq!(
+///     expiry_warning(expires, today, 30).as_deref(),
+///     Some("security.txt expires on 2026-12-31, in 21 days")
+/// );
+/// assert_eq!(expiry_warning(expires, today, 7), None);
+/// ```
+pub fn expiry_warning(
+    expires: Date,
+    today: Date,
+    warning_days: u32,
+) -> Option<String> {
+    let days = expires.days() - today.days();
+    if days < 0 {
+        Some(format!("security.txt expired on {}", expires))
+    } else if days <= i64::from(warning_days) {
+        Some(format!(
+            "security.txt expires on {}, in {} days",
+            expires, days
+        ))
+    } else if days > 366 {
+        Some(format!(
+            "security.txt expires on {}, more than a year ahead",
+            expires
+        ))
+    } else {
+        None
+    }
+}
+
+/// Writes `/.well-known/security.txt` from the `[security_txt]` options
+/// and the frontmatter of the home page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityTxtGenerator {
+    /// The `[security_txt]` options.
+    pub options: SecurityTxtOptions,
+    /// Base URL of the site, for the canonical URL.
+    pub base_url: String,
+}
+
+impl AuxiliaryGenerator for SecurityTxtGenerator {
+    fn name(&self) -> &str {
+        "security_txt"
+    }
+
+    fn generate(
+        &self,
+        content: &ContentSet,
+        site_dir: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        if !self.options.enabled {
+            return Ok(Vec::new());
+        }
+        let today = Date::today();
+        let mut data = SecurityData::from_config(
+            &self.options,
+            &self.base_url,
+            today,
+        );
+        if let Some(home) =
+            content.iter().find(|page| page.url_path() == "/")
+        {
+            data = data.with_frontmatter(&self.options, home);
+        }
+        ensure!(
+            !data.contact.is_empty(),
+            "security.txt needs a contact in [security_txt] or the \
+             security_contact frontmatter of the home page"
+        );
+        if let Some(warning) =
+            Date::parse(&data.expires).and_then(|expires| {
+                expiry_warning(
+                    expires,
+                    today,
+                    self.options.expiry_warning_days,
+                )
+            })
+        {
+            warn!("{}", warning);
+        }
+
+        let mut text = data.to_text();
+        if let Some(command) = &self.options.sign_command {
+            text = pipe_command(command, &text)
+                .context("Failed to sign security.txt")?;
+        }
+        let path = site_dir.join(SECURITY_TXT_PATH);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        fs::write(&path, text).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })?;
+        Ok(vec![path])
+    }
+}
+
+/// Returns `expires` in RFC 3339, at midnight UTC when it is a date
+/// in another format.
+fn rfc3339(expires: &str) -> String {
+    let expires = expires.trim();
+    let is_rfc3339 = expires.len() > 10
+        && expires.as_bytes()[10] == b'T'
+        && Date::parse(&expires[..10]).is_some();
+    match Date::parse(expires) {
+        Some(_) if is_rfc3339 => expires.to_string(),
+        Some(date) => format!("{}T00:00:00Z", date),
+        None => expires.to_string(),
+    }
+}
+
+/// Returns `true` if `contact` is a URI RFC 9116 allows as a contact.
+fn is_contact(contact: &str) -> bool {
+    ["mailto:", "tel:", "https://"]
+        .iter()
+        .any(|scheme| contact.starts_with(scheme))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_config_and_frontmatter() -> Result<()> {
+        let options: SecurityTxtOptions = toml::from_str(
+            r#"
+            enabled = true
+            preferred_languages = ["en", "fr"]
+            policy = "https://example.com/policy/"
+            "#,
+        )?;
+        assert!(options.validate().is_ok());
+        let home = Page::from_source(
+            "content/index.md".into(),
+            "index.md".into(),
+            "---\ntitle: Home\n\
+             security_contact: mailto:security@example.com\n\
+             security_expires: Wed, 31 Dec 2026 01:01:01 GMT\n\
+             security_languages: de\n\
+             security_hiring: https://example.com/jobs/\n---\n",
+        )?;
+        let today = Date::parse("2026-01-01").unwrap();
+        let data = SecurityData::from_config(
+            &options,
+            "https://example.com/",
+            today,
+        )
+        .with_frontmatter(&options, &home);
+        assert_eq!(
+            data.to_text(),
+            "Contact: mailto:security@example.com\n\
+             Expires: 2026-12-31T00:00:00Z\n\
+             Preferred-Languages: en, fr\n\
+             Canonical: https://example.com/.well-known/security.txt\n\
+             Policy: https://example.com/policy/\n\
+             Hiring: https://example.com/jobs/\n"
+        );
+
+        let rolled = SecurityData::from_config(&options, "", today);
+        assert_eq!(rolled.expires, "2027-01-01T00:00:00Z");
+        Ok(())
+    }
+
+    #[test]
+    fn test_generator_signs_the_file() -> Result<()> {
+        let site = tempdir()?;
+        let generator = SecurityTxtGenerator {
+            options: SecurityTxtOptions {
+                enabled: true,
+                contact: vec!["https://example.com/report/".into()],
+                sign_command: Some(vec!["sed".into(), "s/^/>/".into()]),
+                ..SecurityTxtOptions::default()
+            },
+            base_url: "https://example.com".into(),
+        };
+        let files =
+            generator.generate(&ContentSet::default(), site.path())?;
+        assert_eq!(files, [site.path().join(SECURITY_TXT_PATH)]);
+        assert!(fs::read_to_string(&files[0])?
+            .starts_with(">Contact: https://example.com/report/\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_expiry_warning() {
+        let today = Date::parse("2026-01-01").unwrap();
+        let expired = Date::parse("2025-12-31").unwrap();
+        let far = Date::parse("2027-06-01").unwrap();
+        assert_eq!(
+            expiry_warning(expired, today, 30).as_deref(),
+            Some("security.txt expired on 2025-12-31")
+        );
+        assert!(expiry_warning(far, today, 30)
+            .unwrap_or_default()
+            .ends_with("more than a year ahead"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut options = SecurityTxtOptions {
+            contact: vec!["security@example.com".into()],
+            ..SecurityTxtOptions::default()
+        };
+        assert!(options.validate().is_err());
+        options.contact = vec!["mailto:security@example.com".into()];
+        options.policy = Some("http://example.com/policy".into());
+        assert!(options.validate().is_err());
+        options.policy = None;
+        options.expires = Some("next year".into());
+        assert!(options.validate().is_err());
+    }
+}
@@ -0,0 +1,662 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Development Server URLs
+//!
+//! Resolves request paths the way static hosts do, so the local preview
+//! that `ssg` serves on `127.0.0.1:8000` after a build behaves like
+//! production. Pages are written as `about/index.html`, and
+//! both `/about/` and `/about` are answered with that file; `/about`
+//! also finds `about.html`. The `[serve]` table of the configuration
+//! file sets what happens to the trailing slash, to match the host the
+//! site is deployed to:
+//!
+//! ```toml
+//! [serve]
+//! trailing_slash = "always"   # or "never", or "ignore" (the default)
+//! ```
+//!
+//! | `trailing_slash` | `/about` | `/about/` |
+//! |------------------|----------|-----------|
+//! | `ignore` | Served | Served |
+//! | `always` | Redirected to `/about/` | Served |
+//! | `never` | Served | Redirected to `/about` |
+//!
+//! Files are sent with the content type of their extension, including
+//! `application/manifest+json` for `.webmanifest`, `application/xml` for
+//! `.xml` and `application/wasm` for `.wasm`. A missing page is answered
+//! with `404.html` when the site has one.
+//!
+//! Every request is counted, and failed ones are logged with their
+//! method, path, status, size and duration. `log_requests = true` in
+//! `[serve]`, or the `--verbose-serve` flag, logs every request:
+//!
+//! ```text
+//! method=GET path=/about/ status=200 bytes=5120 duration_ms=0.4
+//! method=GET path=/img/logo.png status=404 bytes=0 duration_ms=0.1
+//! ```
+//!
+//! The counts, the paths that were not found and the mean and longest
+//! durations are served as JSON at [`STATS_PATH`].
+//!
+//! ## HTTPS
+//!
+//! Service workers and other APIs need a secure context. Built with the
+//! `tls` feature, `tls = true` in `[serve]`, or the `--tls` flag, serves
+//! the site over HTTPS with the PEM certificate and key of `cert` and
+//! `key`:
+//!
+//! ```toml
+//! [serve]
+//! tls = true
+//! cert = "localhost.pem"
+//! key = "localhost-key.pem"
+//! ```
+//!
+//! Without them, a self-signed certificate for `localhost` is generated
+//! once and kept in `ssg/tls/` of the user cache directory (see
+//! [`tls_cache_dir`]). Browsers only treat the site as secure once the
+//! certificate is trusted, so add it to the trust store, or pass a
+//! certificate from a local authority such as `mkcert`.
+
+use anyhow::{ensure, Context, Result};
+use log::{info, warn};
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+use warp::{
+    http::{
+        header::{CONTENT_TYPE, LOCATION},
+        Method, Response, StatusCode,
+    },
+    hyper::body::HttpBody,
+    path::FullPath,
+    reply, Filter, Rejection, Reply,
+};
+
+/// Directory of the self-signed certificate in the user cache directory.
+pub const TLS_CACHE_DIR: &str = "ssg/tls";
+
+/// URL path of the request statistics.
+pub const STATS_PATH: &str = "/_ssg/stats";
+
+/// What the server does with the trailing slash of a page URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrailingSlash {
+    /// Serves pages with and without the slash.
+    Ignore,
+    /// Redirects page URLs without the slash to the URL with it.
+    Always,
+    /// Redirects page URLs with the slash to the URL without it.
+    Never,
+}
+
+impl Default for TrailingSlash {
+    fn default() -> Self {
+        Self::Ignore
+    }
+}
+
+/// Options of the `[serve]` config table.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct ServeOptions {
+    /// What the server does with the trailing slash of a page URL.
+    pub trailing_slash: TrailingSlash,
+    /// Logs every request, not only failed ones.
+    pub log_requests: bool,
+    /// Serves over HTTPS.
+    pub tls: bool,
+    /// PEM certificate to serve HTTPS with, instead of a self-signed
+    /// one.
+    pub cert: Option<PathBuf>,
+    /// PEM private key of `cert`.
+    pub key: Option<PathBuf>,
+}
+
+impl ServeOptions {
+    /// Checks that `cert` and `key` are set together.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the option that is missing.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.key.is_some() || self.cert.is_none(),
+            "cert needs a key"
+        );
+        ensure!(
+            self.cert.is_some() || self.key.is_none(),
+            "key needs a cert"
+        );
+        Ok(())
+    }
+}
+
+/// What a request path resolves to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// The file to send.
+    File(PathBuf),
+    /// The URL path to redirect to.
+    Redirect(String),
+    /// Nothing is found at the path.
+    NotFound,
+}
+
+/// Resolves the URL path `url` against the site in `root`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::serve::{resolve, Resolution, TrailingSlash};
+/// use std::path::Path;
+///
+/// assert_eq!(
+///     resolve(Path::new("public"), "/../secret", TrailingSlash::Ignore),
+///     Resolution::NotFound
+/// );
+/// ```
+pub fn resolve(
+    root: &Path,
+    url: &str,
+    trailing_slash: TrailingSlash,
+) -> Resolution {
+    let decoded = match percent_decode_str(url).decode_utf8() {
+        Ok(decoded) => decoded,
+        Err(_) => return Resolution::NotFound,
+    };
+    let mut path = root.to_path_buf();
+    for segment in decoded.split('/').filter(|s| !s.is_empty()) {
+        if segment == "." || segment == ".." || segment.contains('\\') {
+            return Resolution::NotFound;
+        }
+        path.push(segment);
+    }
+    let index = path.join("index.html");
+
+    if decoded.ends_with('/') {
+        if !index.is_file() {
+            return Resolution::NotFound;
+        }
+        return match trailing_slash {
+            TrailingSlash::Never if decoded != "/" => {
+                Resolution::Redirect(
+                    url.trim_end_matches('/').to_string(),
+                )
+            }
+            _ => Resolution::File(index),
+        };
+    }
+    if path.is_file() {
+        return Resolution::File(path);
+    }
+    if index.is_file() {
+        return match trailing_slash {
+            TrailingSlash::Always => {
+                Resolution::Redirect(format!("{}/", url))
+            }
+            _ => Resolution::File(index),
+        };
+    }
+    let page = path.with_extension("html");
+    if path.extension().is_none() && page.is_file() {
+        return Resolution::File(page);
+    }
+    Resolution::NotFound
+}
+
+/// Returns the content type of `path` from its extension.
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "xml" => "application/xml",
+        "rss" => "application/rss+xml",
+        "atom" => "application/atom+xml",
+        "txt" => "text/plain; charset=utf-8",
+        "wasm" => "application/wasm",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "pdf" => "application/pdf",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        _ => "application/octet-stream",
+    }
+}
+
+/// A warp filter serving the site in `root` with clean URLs.
+///
+/// Paths with nothing to serve and no `404.html` are rejected, so the
+/// filter can be combined with other filters using `.or()`.
+pub fn filter(
+    root: PathBuf,
+    options: ServeOptions,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path::full().and_then(move |path: FullPath| {
+        let root = root.clone();
+        async move {
+            let (status, file) = match resolve(
+                &root,
+                path.as_str(),
+                options.trailing_slash,
+            ) {
+                Resolution::File(file) => (StatusCode::OK, file),
+                Resolution::Redirect(location) => {
+                    return Response::builder()
+                        .status(StatusCode::MOVED_PERMANENTLY)
+                        .header(LOCATION, location)
+                        .body(Vec::new())
+                        .map_err(|_| warp::reject::not_found());
+                }
+                Resolution::NotFound => {
+                    let page = root.join("404.html");
+                    if !page.is_file() {
+                        return Err(warp::reject::not_found());
+                    }
+                    (StatusCode::NOT_FOUND, page)
+                }
+            };
+            let body = tokio::fs::read(&file)
+                .await
+                .map_err(|_| warp::reject::not_found())?;
+            Response::builder()
+                .status(status)
+                .header(CONTENT_TYPE, content_type(&file))
+                .body(body)
+                .map_err(|_| warp::reject::not_found())
+        }
+    })
+}
+
+/// Request statistics of the development server.
+#[derive(Debug, Default)]
+pub struct ServeStats {
+    inner: Mutex<Counts>,
+}
+
+/// Counters behind [`ServeStats`].
+#[derive(Debug, Default)]
+struct Counts {
+    requests: u64,
+    bytes: u64,
+    statuses: BTreeMap<u16, u64>,
+    not_found: BTreeMap<String, u64>,
+    total: Duration,
+    longest: Duration,
+}
+
+/// The statistics served at [`STATS_PATH`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StatsSnapshot {
+    /// Number of requests answered.
+    pub requests: u64,
+    /// Bytes of the response bodies.
+    pub bytes: u64,
+    /// Number of responses by status code.
+    pub statuses: BTreeMap<u16, u64>,
+    /// Number of `404` responses by path.
+    pub not_found: BTreeMap<String, u64>,
+    /// Mean time to answer a request, in milliseconds.
+    pub mean_ms: f64,
+    /// Longest time to answer a request, in milliseconds.
+    pub max_ms: f64,
+}
+
+impl ServeStats {
+    /// Counts a request.
+    pub fn record(
+        &self,
+        path: &str,
+        status: StatusCode,
+        bytes: u64,
+        duration: Duration,
+    ) {
+        let mut counts =
+            self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        counts.requests += 1;
+        counts.bytes += bytes;
+        *counts.statuses.entry(status.as_u16()).or_default() += 1;
+        if status == StatusCode::NOT_FOUND {
+            *counts.not_found.entry(path.to_string()).or_default() += 1;
+        }
+        counts.total += duration;
+        counts.longest = counts.longest.max(duration);
+    }
+
+    /// Returns the statistics so far.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let counts =
+            self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let mean = match counts.requests {
+            0 => Duration::ZERO,
+            requests => {
+                counts.total
+                    / u32::try_from(requests).unwrap_or(u32::MAX)
+            }
+        };
+        StatsSnapshot {
+            requests: counts.requests,
+            bytes: counts.bytes,
+            statuses: counts.statuses.clone(),
+            not_found: counts.not_found.clone(),
+            mean_ms: milliseconds(mean),
+            max_ms: milliseconds(counts.longest),
+        }
+    }
+}
+
+/// A warp filter answering [`STATS_PATH`] with the statistics as JSON.
+pub fn stats_filter(
+    stats: Arc<ServeStats>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get().and(warp::path::full()).and_then(
+        move |path: FullPath| {
+            let stats = Arc::clone(&stats);
+            async move {
+                if path.as_str() != STATS_PATH {
+                    return Err(warp::reject::not_found());
+                }
+                Ok::<_, Rejection>(reply::json(&stats.snapshot()))
+            }
+        },
+    )
+}
+
+/// Wraps `routes` so every request is counted in `stats` and logged,
+/// answering requests `routes` rejects with an empty `404`.
+///
+/// Failed requests are logged as warnings, and the others too when
+/// `log_requests` is set.
+pub fn logged<F, R>(
+    routes: F,
+    stats: Arc<ServeStats>,
+    log_requests: bool,
+) -> impl Filter<Extract = (reply::Response,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync,
+    R: Reply,
+{
+    let routes = routes
+        .map(|reply: R| reply.into_response())
+        .or(warp::any().map(|| StatusCode::NOT_FOUND.into_response()))
+        .unify();
+    warp::any()
+        .map(Instant::now)
+        .and(warp::method())
+        .and(warp::path::full())
+        .and(routes)
+        .map(
+            move |start: Instant,
+                  method: Method,
+                  path: FullPath,
+                  response: reply::Response| {
+                let duration = start.elapsed();
+                let status = response.status();
+                let bytes = response.body().size_hint().exact().unwrap_or(0);
+                stats.record(path.as_str(), status, bytes, duration);
+                let line = format!(
+                    "method={} path={} status={} bytes={} duration_ms={:.1}",
+                    method,
+                    path.as_str(),
+                    status.as_u16(),
+                    bytes,
+                    milliseconds(duration)
+                );
+                if status.is_client_error() || status.is_server_error() {
+                    warn!("{}", line);
+                } else if log_requests {
+                    info!("{}", line);
+                }
+                response
+            },
+        )
+}
+
+/// Returns the directory of the self-signed certificate:
+/// [`TLS_CACHE_DIR`] in the user cache directory, or in `.ssg-cache`
+/// when there is none.
+pub fn tls_cache_dir() -> PathBuf {
+    user_cache_dir()
+        .unwrap_or_else(|| PathBuf::from(".ssg-cache"))
+        .join(TLS_CACHE_DIR)
+}
+
+/// Returns the certificate and key files to serve HTTPS with: `cert`
+/// and `key` when they are set, or else the self-signed certificate in
+/// [`tls_cache_dir`], generated on first use.
+///
+/// # Errors
+///
+/// Returns an error if a configured file is missing, or if the
+/// certificate cannot be generated.
+pub fn tls_files(options: &ServeOptions) -> Result<(PathBuf, PathBuf)> {
+    if let (Some(cert), Some(key)) = (&options.cert, &options.key) {
+        for path in [cert, key] {
+            ensure!(path.is_file(), "{} not found", path.display());
+        }
+        return Ok((cert.clone(), key.clone()));
+    }
+    let dir = tls_cache_dir();
+    let cert = dir.join("localhost.pem");
+    let key = dir.join("localhost-key.pem");
+    if !cert.is_file() || !key.is_file() {
+        fs::create_dir_all(&dir).with_context(|| {
+            format!("Failed to create {}", dir.display())
+        })?;
+        generate_certificate(&cert, &key)?;
+    }
+    Ok((cert, key))
+}
+
+/// Writes a self-signed certificate for `localhost` and its key.
+#[cfg(feature = "tls")]
+fn generate_certificate(cert: &Path, key: &Path) -> Result<()> {
+    let names = ["localhost", "127.0.0.1", "::1"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    let certified = rcgen::generate_simple_self_signed(names)
+        .context("Failed to generate a self-signed certificate")?;
+    fs::write(cert, certified.cert.pem()).with_context(|| {
+        format!("Failed to write {}", cert.display())
+    })?;
+    fs::write(key, certified.key_pair.serialize_pem()).with_context(
+        || format!("Failed to write {}", key.display()),
+    )?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(key, fs::Permissions::from_mode(0o600))
+            .with_context(|| {
+                format!("Failed to restrict {}", key.display())
+            })?;
+    }
+    Ok(())
+}
+
+/// Fails, as certificates are generated with the `tls` feature only.
+#[cfg(not(feature = "tls"))]
+fn generate_certificate(_cert: &Path, _key: &Path) -> Result<()> {
+    anyhow::bail!("Generating a certificate requires the `tls` feature")
+}
+
+/// Returns the cache directory of the user, following the conventions
+/// of each platform.
+fn user_cache_dir() -> Option<PathBuf> {
+    let var = |name: &str| {
+        env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    if cfg!(windows) {
+        var("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Caches"))
+    } else {
+        var("XDG_CACHE_HOME")
+            .or_else(|| var("HOME").map(|home| home.join(".cache")))
+    }
+}
+
+/// Returns `duration` in milliseconds.
+fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_clean_urls() -> Result<()> {
+        let site = tempdir()?;
+        let root = site.path();
+        fs::create_dir_all(root.join("about"))?;
+        fs::write(root.join("index.html"), "home")?;
+        fs::write(root.join("about/index.html"), "about")?;
+        fs::write(root.join("contact.html"), "contact")?;
+
+        let about = Resolution::File(root.join("about/index.html"));
+        let ignore = TrailingSlash::Ignore;
+        assert_eq!(resolve(root, "/about/", ignore), about);
+        assert_eq!(resolve(root, "/about", ignore), about);
+        assert_eq!(resolve(root, "/%61bout", ignore), about);
+        assert_eq!(
+            resolve(root, "/contact", ignore),
+            Resolution::File(root.join("contact.html"))
+        );
+        assert_eq!(
+            resolve(root, "/about", TrailingSlash::Always),
+            Resolution::Redirect("/about/".into())
+        );
+        assert_eq!(
+            resolve(root, "/about/", TrailingSlash::Never),
+            Resolution::Redirect("/about".into())
+        );
+        assert_eq!(
+            resolve(root, "/", TrailingSlash::Never),
+            Resolution::File(root.join("index.html"))
+        );
+        assert_eq!(
+            resolve(root, "/missing", ignore),
+            Resolution::NotFound
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_serves_content_types() -> Result<()> {
+        let site = tempdir()?;
+        let root = site.path();
+        fs::write(root.join("site.webmanifest"), "{}")?;
+        fs::write(root.join("404.html"), "gone")?;
+        let routes =
+            filter(root.to_path_buf(), ServeOptions::default());
+
+        let response = warp::test::request()
+            .path("/site.webmanifest")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[CONTENT_TYPE],
+            "application/manifest+json"
+        );
+
+        let response =
+            warp::test::request().path("/nope/").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.body(), "gone");
+        assert_eq!(
+            content_type(Path::new("feed.XML")),
+            "application/xml"
+        );
+        assert_eq!(
+            content_type(Path::new("app.wasm")),
+            "application/wasm"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_counted() {
+        let stats = Arc::new(ServeStats::default());
+        let routes = logged(
+            stats_filter(Arc::clone(&stats))
+                .or(warp::path("page").map(|| "hello")),
+            Arc::clone(&stats),
+            true,
+        );
+
+        let response =
+            warp::test::request().path("/page").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response =
+            warp::test::request().path("/missing").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.requests, 2);
+        assert_eq!(snapshot.bytes, 5);
+        assert_eq!(snapshot.statuses[&200], 1);
+        assert_eq!(snapshot.not_found["/missing"], 1);
+
+        let response =
+            warp::test::request().path(STATS_PATH).reply(&routes).await;
+        let body: serde_json::Value =
+            serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["requests"], 2);
+    }
+
+    #[test]
+    fn test_tls_files_from_options() -> Result<()> {
+        let dir = tempdir()?;
+        let cert = dir.path().join("cert.pem");
+        let key = dir.path().join("key.pem");
+        let mut options = ServeOptions {
+            tls: true,
+            cert: Some(cert.clone()),
+            ..ServeOptions::default()
+        };
+        assert!(options.validate().is_err());
+        options.key = Some(key.clone());
+        assert!(options.validate().is_ok());
+        assert!(tls_files(&options).is_err());
+
+        fs::write(&cert, "cert")?;
+        fs::write(&key, "key")?;
+        assert_eq!(tls_files(&options)?, (cert, key));
+        Ok(())
+    }
+}
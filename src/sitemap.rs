@@ -0,0 +1,415 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Sitemap Priorities
+//!
+//! Gives every `<url>` of `sitemap.xml` a `<priority>` from its place in
+//! the breadcrumb trail, so search engines get a hint of what matters
+//! without any configuration:
+//!
+//! | Page | Priority |
+//! |------|----------|
+//! | The home page | 1.0 |
+//! | A section, i.e. a page with pages below it | 0.8 |
+//! | Any other page | 0.5 |
+//!
+//! Rules in the `[sitemap]` table override the defaults for the URL
+//! paths their glob pattern matches, and can set a `<changefreq>`. The
+//! first matching rule wins:
+//!
+//! ```toml
+//! [sitemap]
+//! default_priorities = true   # set to false to only apply the rules
+//!
+//! [[sitemap.rules]]
+//! pattern = "/blog/**"        # every page below /blog/
+//! priority = 0.6
+//! changefreq = "weekly"
+//!
+//! [[sitemap.rules]]
+//! pattern = "/legal/*"
+//! priority = 0.1
+//! ```
+//!
+//! Patterns are matched against the site-relative path without a
+//! trailing slash, such as `/blog/first-post`; the home page is `/`.
+//! `*` stays within one path segment and `**` matches any number of
+//! them. A priority the sitemap already has is only replaced by a rule.
+
+use anyhow::{ensure, Context, Result};
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fs, path::Path};
+
+use crate::{
+    locales::{sitemap_key, SITEMAP_LOC_RE},
+    page_meta::SITEMAP_ENTRY_RE,
+};
+
+/// Priority of the home page.
+pub const HOME_PRIORITY: f64 = 1.0;
+
+/// Priority of a page with pages below it.
+pub const SECTION_PRIORITY: f64 = 0.8;
+
+/// Priority of any other page.
+pub const PAGE_PRIORITY: f64 = 0.5;
+
+/// Values accepted for `changefreq`.
+pub const CHANGE_FREQUENCIES: &[&str] = &[
+    "always", "hourly", "daily", "weekly", "monthly", "yearly", "never",
+];
+
+/// Keeps `*` from matching across `/`.
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A `[[sitemap.rules]]` entry.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SitemapRule {
+    /// Glob pattern of the URL paths the rule applies to.
+    pub pattern: String,
+    /// Priority between 0.0 and 1.0.
+    pub priority: Option<f64>,
+    /// How often the pages change, one of [`CHANGE_FREQUENCIES`].
+    pub changefreq: Option<String>,
+}
+
+/// Options of the `[sitemap]` config table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SitemapOptions {
+    /// Gives pages no rule sets a priority for the default one.
+    pub default_priorities: bool,
+    /// Overrides by URL path pattern, first match first.
+    pub rules: Vec<SitemapRule>,
+}
+
+impl Default for SitemapOptions {
+    fn default() -> Self {
+        Self {
+            default_priorities: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl SitemapOptions {
+    /// Checks the patterns and values of the rules.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first invalid rule.
+    pub fn validate(&self) -> Result<()> {
+        for rule in &self.rules {
+            let _ = Pattern::new(&rule.pattern).with_context(|| {
+                format!("Invalid rule pattern `{}`", rule.pattern)
+            })?;
+            ensure!(
+                rule.priority.is_some() || rule.changefreq.is_some(),
+                "rule `{}` sets neither a priority nor a changefreq",
+                rule.pattern
+            );
+            if let Some(priority) = rule.priority {
+                ensure!(
+                    (0.0..=1.0).contains(&priority),
+                    "rule `{}` has priority {}, outside 0.0 to 1.0",
+                    rule.pattern,
+                    priority
+                );
+            }
+            if let Some(changefreq) = &rule.changefreq {
+                ensure!(
+                    CHANGE_FREQUENCIES.contains(&changefreq.as_str()),
+                    "rule `{}` has changefreq `{}`; use one of {}",
+                    rule.pattern,
+                    changefreq,
+                    CHANGE_FREQUENCIES.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the first rule matching the site-relative `path`.
+    fn rule(&self, path: &str) -> Option<&SitemapRule> {
+        self.rules.iter().find(|rule| {
+            Pattern::new(&rule.pattern).map_or(false, |pattern| {
+                pattern.matches_with(path, MATCH_OPTIONS)
+            })
+        })
+    }
+}
+
+/// Adds priorities and change frequencies to the `<url>` entries of
+/// `xml`.
+///
+/// ```
+/// use ssg::sitemap::{insert_sitemap_priorities, SitemapOptions};
+///
+/// let sitemap = "<urlset>\
+///     <url><loc>https://example.com/</loc></url>\
+///     <url><loc>https://example.com/docs/</loc></url>\
+///     <url><loc>https://example.com/docs/install/</loc></url>\
+///     </urlset>";
+/// let updated = insert_sitemap_priorities(
+///     sitemap,
+///     &SitemapOptions::default(),
+///     "https://example.com",
+/// );
+/// assert!(updated.contains("/</loc><priority>1.0</priority>"));
+/// assert!(updated.contains("/docs/</loc><priority>0.8</priority>"));
+/// assert!(updated.contains("/install/</loc><priority>0.5</priority>"));
+/// ```
+pub fn insert_sitemap_priorities(
+    xml: &str,
+    options: &SitemapOptions,
+    base_url: &str,
+) -> String {
+    let base = base_url.trim_end_matches('/');
+    let path = |entry: &str| {
+        SITEMAP_LOC_RE.captures(entry).map(|loc| {
+            let url = loc[1].replace("&amp;", "&");
+            let key =
+                sitemap_key(url.strip_prefix(base).unwrap_or(&url));
+            format!("/{}", key.trim_start_matches('/'))
+        })
+    };
+    let paths: BTreeSet<String> = SITEMAP_ENTRY_RE
+        .captures_iter(xml)
+        .filter_map(|caps| path(&caps[1]))
+        .collect();
+
+    SITEMAP_ENTRY_RE
+        .replace_all(xml, |caps: &regex::Captures<'_>| {
+            let path = match path(&caps[1]) {
+                Some(path) => path,
+                None => return caps[0].to_string(),
+            };
+            let rule = options.rule(&path);
+            let mut tags = Vec::new();
+            match rule.and_then(|rule| rule.priority) {
+                Some(priority) => {
+                    tags.push(("priority", priority_value(priority)))
+                }
+                None if options.default_priorities
+                    && !caps[1].contains("<priority>") =>
+                {
+                    tags.push((
+                        "priority",
+                        priority_value(default_priority(&path, &paths)),
+                    ));
+                }
+                None => {}
+            }
+            if let Some(changefreq) =
+                rule.and_then(|rule| rule.changefreq.clone())
+            {
+                tags.push(("changefreq", changefreq));
+            }
+            caps[0].replacen(&caps[1], &with_tags(&caps[1], &tags), 1)
+        })
+        .into_owned()
+}
+
+/// Adds priorities and change frequencies to `sitemap.xml` in
+/// `site_dir`.
+///
+/// Returns `true` if the sitemap changed.
+///
+/// # Errors
+///
+/// Returns an error if the sitemap cannot be read or written.
+pub fn write_sitemap_priorities(
+    site_dir: &Path,
+    options: &SitemapOptions,
+    base_url: &str,
+) -> Result<bool> {
+    let path = site_dir.join("sitemap.xml");
+    if !path.is_file() {
+        return Ok(false);
+    }
+    let xml = fs::read_to_string(&path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    let updated = insert_sitemap_priorities(&xml, options, base_url);
+    if updated == xml {
+        return Ok(false);
+    }
+    fs::write(&path, updated).with_context(|| {
+        format!("Failed to write {}", path.display())
+    })?;
+    Ok(true)
+}
+
+/// Returns the priority of the page at `path` among all the `paths` of
+/// the sitemap.
+fn default_priority(path: &str, paths: &BTreeSet<String>) -> f64 {
+    if path == "/" {
+        return HOME_PRIORITY;
+    }
+    let prefix = format!("{}/", path);
+    if paths.iter().any(|other| other.starts_with(&prefix)) {
+        SECTION_PRIORITY
+    } else {
+        PAGE_PRIORITY
+    }
+}
+
+/// Formats a priority with one or two decimals, e.g. `0.8` or `0.65`.
+fn priority_value(priority: f64) -> String {
+    let value = format!("{:.2}", priority);
+    match value.strip_suffix('0') {
+        Some(value) => value.to_string(),
+        None => value,
+    }
+}
+
+/// Sets `tags` in the body of a `<url>`, replacing the ones it has and
+/// appending the others after its last line with the same indentation.
+fn with_tags(entry: &str, tags: &[(&str, String)]) -> String {
+    let mut updated = entry.to_string();
+    let mut appended = Vec::new();
+    for (name, value) in tags {
+        let open = format!("<{}>", name);
+        let close = format!("</{}>", name);
+        let existing = updated.find(&open).and_then(|start| {
+            updated[start..]
+                .find(&close)
+                .map(|end| start..start + end + close.len())
+        });
+        let tag = format!("{}{}{}", open, value, close);
+        match existing {
+            Some(range) => updated.replace_range(range, &tag),
+            None => appended.push(tag),
+        }
+    }
+    if appended.is_empty() {
+        return updated;
+    }
+
+    let trimmed_len = updated.trim_end().len();
+    let (separator, indent) = if updated.contains('\n') {
+        let line = updated[..trimmed_len]
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default();
+        (
+            "\n",
+            line[..line.len() - line.trim_start().len()].to_string(),
+        )
+    } else {
+        ("", String::new())
+    };
+    let tail = updated.split_off(trimmed_len);
+    for tag in appended {
+        updated.push_str(separator);
+        updated.push_str(&indent);
+        updated.push_str(&tag);
+    }
+    updated.push_str(&tail);
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITEMAP: &str = "<urlset>
+  <url>
+    <loc>https://example.com/</loc>
+  </url>
+  <url>
+    <loc>https://example.com/blog/index.html</loc>
+    <priority>0.3</priority>
+  </url>
+  <url>
+    <loc>https://example.com/blog/first/</loc>
+  </url>
+  <url>
+    <loc>https://example.com/legal/terms/</loc>
+  </url>
+</urlset>
+";
+
+    #[test]
+    fn test_default_priorities() {
+        let updated = insert_sitemap_priorities(
+            SITEMAP,
+            &SitemapOptions::default(),
+            "https://example.com/",
+        );
+        assert!(updated.contains(
+            "<loc>https://example.com/</loc>\n    <priority>1.0</priority>\n  </url>"
+        ));
+        assert!(updated.contains("<priority>0.3</priority>"));
+        assert_eq!(
+            updated.matches("<priority>0.5</priority>").count(),
+            2
+        );
+        assert_eq!(
+            insert_sitemap_priorities(
+                SITEMAP,
+                &SitemapOptions {
+                    default_priorities: false,
+                    ..SitemapOptions::default()
+                },
+                "https://example.com",
+            ),
+            SITEMAP
+        );
+    }
+
+    #[test]
+    fn test_rules_override_defaults() -> Result<()> {
+        let options: SitemapOptions = toml::from_str(
+            r#"
+            [[rules]]
+            pattern = "/blog/**"
+            priority = 0.65
+            changefreq = "weekly"
+
+            [[rules]]
+            pattern = "/blog"
+            priority = 0.9
+
+            [[rules]]
+            pattern = "/*"
+            changefreq = "monthly"
+            "#,
+        )?;
+        options.validate()?;
+        let updated = insert_sitemap_priorities(
+            SITEMAP,
+            &options,
+            "https://example.com",
+        );
+        assert!(updated.contains(
+            "/blog/index.html</loc>\n    <priority>0.9</priority>\n  </url>"
+        ));
+        assert!(updated.contains(
+            "/blog/first/</loc>\n    <priority>0.65</priority>\n    <changefreq>weekly</changefreq>"
+        ));
+        assert!(updated.contains(
+            "https://example.com/</loc>\n    <priority>1.0</priority>\n    <changefreq>monthly</changefreq>"
+        ));
+        assert!(updated.contains(
+            "/legal/terms/</loc>\n    <priority>0.5</priority>\n  </url>"
+        ));
+
+        let invalid = SitemapOptions {
+            rules: vec![SitemapRule {
+                pattern: "/blog/**".into(),
+                priority: Some(1.5),
+                changefreq: None,
+            }],
+            ..SitemapOptions::default()
+        };
+        assert!(invalid.validate().is_err());
+        Ok(())
+    }
+}
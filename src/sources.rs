@@ -0,0 +1,718 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Remote Content Sources
+//!
+//! Populates parts of the content directory from remote sources before
+//! the build, so documentation kept in another repository does not have
+//! to be synced by hand:
+//!
+//! ```toml
+//! [[content_sources]]
+//! type = "git"
+//! repository = "https://github.com/example/docs.git"
+//! rev = "3f786850e387550fdab836ed7e6dc881de23001b"
+//! path = "docs"
+//! target = "docs"
+//!
+//! [[content_sources]]
+//! type = "archive"
+//! url = "https://example.com/reference-1.2.tar.gz"
+//! sha256 = "0f343b0931126a20f133d67c2b018a3b1e1a5e2a3f8d4b0c5d6e7f8091a2b3c4"
+//! path = "reference-1.2"
+//! target = "reference"
+//! ```
+//!
+//! Each source replaces `<content_dir>/<target>`. Git sources are fetched
+//! with `git` into a cached checkout; a `rev` that is a full commit hash
+//! is verified against the checkout and, once present, not fetched again.
+//! Archives are plain or gzip-compressed tarballs and must match their
+//! `sha256`; verified archives are cached, so downloading (which needs
+//! the `fetch` feature) only happens once per hash. Before anything is
+//! extracted, every entry is checked: an archive holding links, devices,
+//! absolute paths or `..` components is rejected whole.
+//!
+//! A `cms` source pulls items from a headless CMS instead; see
+//! [`cms`].
+//!
+//! Everything is cached in `.ssg-cache/sources/`.
+
+use anyhow::{bail, ensure, Context, Result};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use crate::{fetch::Request, template::hex};
+
+/// Content items from a REST or GraphQL headless CMS.
+pub mod cms;
+
+use self::cms::CmsSource;
+
+/// Default cache directory, relative to the project root.
+pub const SOURCES_CACHE_DIR: &str = ".ssg-cache/sources";
+
+/// Largest archive that is downloaded.
+const MAX_ARCHIVE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Timeout of an archive download.
+const ARCHIVE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Redirects followed for archives; the hash makes them safe.
+const ARCHIVE_REDIRECTS: u32 = 5;
+
+/// A remote source of content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ContentSource {
+    /// A directory of a git repository.
+    Git {
+        /// Repository URL.
+        repository: String,
+        /// Branch, tag or full commit hash; the default branch if unset.
+        #[serde(default)]
+        rev: Option<String>,
+        /// Directory in the repository; the root if unset.
+        #[serde(default)]
+        path: Option<PathBuf>,
+        /// Directory below the content directory to populate.
+        target: PathBuf,
+    },
+    /// A directory of a tarball.
+    Archive {
+        /// Archive URL.
+        url: String,
+        /// Expected SHA-256 of the archive, in hex.
+        sha256: String,
+        /// Directory in the archive; the root if unset.
+        #[serde(default)]
+        path: Option<PathBuf>,
+        /// Directory below the content directory to populate.
+        target: PathBuf,
+    },
+    /// Items of a headless CMS, written as Markdown documents.
+    Cms(CmsSource),
+}
+
+impl ContentSource {
+    /// Returns the directory below the content directory to populate.
+    pub fn target(&self) -> &Path {
+        match self {
+            Self::Git { target, .. } | Self::Archive { target, .. } => {
+                target
+            }
+            Self::Cms(cms) => &cms.target,
+        }
+    }
+
+    /// Returns the directory to use inside the source, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Git { path, .. } | Self::Archive { path, .. } => {
+                path.as_deref()
+            }
+            Self::Cms(_) => None,
+        }
+    }
+
+    /// Checks that paths stay inside their directories, the archive
+    /// hash is well-formed and nothing can be mistaken for an option.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid setting.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            is_relative(self.target())
+                && self.target().components().next().is_some(),
+            "target `{}` must be a relative directory",
+            self.target().display()
+        );
+        if let Some(path) = self.path() {
+            ensure!(
+                is_relative(path),
+                "path `{}` must be relative",
+                path.display()
+            );
+        }
+        match self {
+            Self::Git {
+                repository, rev, ..
+            } => {
+                ensure!(
+                    !repository.is_empty()
+                        && !repository.starts_with('-'),
+                    "Invalid repository `{}`",
+                    repository
+                );
+                if let Some(rev) = rev {
+                    ensure!(
+                        !rev.is_empty() && !rev.starts_with('-'),
+                        "Invalid rev `{}`",
+                        rev
+                    );
+                }
+            }
+            Self::Archive { url, sha256, .. } => {
+                ensure!(
+                    url.starts_with("https://"),
+                    "archive `{}` must be an https URL",
+                    url
+                );
+                ensure!(
+                    sha256.len() == 64
+                        && sha256
+                            .chars()
+                            .all(|c| c.is_ascii_hexdigit()),
+                    "sha256 of `{}` must be 64 hex digits",
+                    url
+                );
+            }
+            Self::Cms(cms) => cms.validate()?,
+        }
+        Ok(())
+    }
+}
+
+/// Result of syncing one source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncedSource {
+    /// Populated directory.
+    pub target: PathBuf,
+    /// Commit hash or archive hash that was used.
+    pub revision: String,
+    /// Number of files copied or written.
+    pub files: usize,
+}
+
+/// Replaces the target directory of each git and archive source below
+/// `content_dir` and updates the documents of each CMS source, caching
+/// downloads in `cache_dir`.
+///
+/// # Errors
+///
+/// Returns an error if a source is invalid, cannot be fetched, fails
+/// its integrity check, or does not contain its `path`.
+pub fn sync_sources(
+    sources: &[ContentSource],
+    content_dir: &Path,
+    cache_dir: &Path,
+) -> Result<Vec<SyncedSource>> {
+    fs::create_dir_all(cache_dir).with_context(|| {
+        format!("Failed to create {}", cache_dir.display())
+    })?;
+    sources
+        .iter()
+        .map(|source| {
+            source.validate()?;
+            let extracted;
+            let (root, revision) = match source {
+                ContentSource::Git {
+                    repository, rev, ..
+                } => {
+                    let checkout = cache_dir
+                        .join(format!("git-{}", key(repository)));
+                    let revision = checkout_git(
+                        repository,
+                        rev.as_deref(),
+                        &checkout,
+                    )?;
+                    (checkout, revision)
+                }
+                ContentSource::Archive { url, sha256, .. } => {
+                    let archive =
+                        fetch_archive(url, sha256, cache_dir)?;
+                    extracted = tempfile::tempdir().context(
+                        "Failed to create an extraction directory",
+                    )?;
+                    extract_archive(&archive, extracted.path())
+                        .with_context(|| {
+                            format!("Failed to extract `{}`", url)
+                        })?;
+                    (
+                        extracted.path().to_path_buf(),
+                        sha256.to_lowercase(),
+                    )
+                }
+                ContentSource::Cms(cms) => {
+                    let target = content_dir.join(&cms.target);
+                    let state = cache_dir.join(format!(
+                        "cms-{}.json",
+                        key(&format!(
+                            "{}\n{}",
+                            cms.endpoint,
+                            cms.target.display()
+                        ))
+                    ));
+                    let sync = cms.sync(&target, &state)?;
+                    return Ok(SyncedSource {
+                        target,
+                        revision: sync.revision,
+                        files: sync.written,
+                    });
+                }
+            };
+
+            let from = match source.path() {
+                Some(path) => root.join(path),
+                None => root,
+            };
+            ensure!(
+                from.is_dir(),
+                "{} has no directory `{}`",
+                describe(source),
+                source
+                    .path()
+                    .unwrap_or_else(|| Path::new(""))
+                    .display()
+            );
+            let target = content_dir.join(source.target());
+            let files = replace_dir(&from, &target)?;
+            Ok(SyncedSource {
+                target,
+                revision,
+                files,
+            })
+        })
+        .collect()
+}
+
+/// Fetches `rev` of `repository` into `checkout` and returns the commit
+/// hash. A full commit hash that is already checked out is not fetched.
+fn checkout_git(
+    repository: &str,
+    rev: Option<&str>,
+    checkout: &Path,
+) -> Result<String> {
+    let pinned = rev.filter(|rev| is_commit_hash(rev));
+    if checkout.join(".git").is_dir() {
+        if let (Some(pinned), Ok(head)) = (pinned, head(checkout)) {
+            if head.eq_ignore_ascii_case(pinned) {
+                return Ok(head);
+            }
+        }
+    } else {
+        run(Command::new("git")
+            .arg("init")
+            .arg("--quiet")
+            .arg(checkout))?;
+    }
+
+    run(git(checkout)
+        .args(["fetch", "--quiet", "--depth", "1", "--", repository])
+        .arg(rev.unwrap_or("HEAD")))
+    .with_context(|| format!("Failed to fetch `{}`", repository))?;
+    run(git(checkout).args([
+        "checkout",
+        "--quiet",
+        "--force",
+        "--detach",
+        "FETCH_HEAD",
+    ]))?;
+
+    let head = head(checkout)?;
+    if let Some(pinned) = pinned {
+        ensure!(
+            head.eq_ignore_ascii_case(pinned),
+            "`{}` resolved to {} instead of the pinned {}",
+            repository,
+            head,
+            pinned
+        );
+    }
+    Ok(head)
+}
+
+/// Returns the cached archive with `sha256`, downloading it if needed.
+fn fetch_archive(
+    url: &str,
+    sha256: &str,
+    cache_dir: &Path,
+) -> Result<PathBuf> {
+    let sha256 = sha256.to_lowercase();
+    let path = cache_dir.join(format!("{}.archive", sha256));
+    if let Ok(bytes) = fs::read(&path) {
+        if hex(&Sha256::digest(&bytes)) == sha256 {
+            return Ok(path);
+        }
+    }
+
+    let bytes = Request::get(url, ARCHIVE_TIMEOUT, MAX_ARCHIVE_BYTES)
+        .redirects(ARCHIVE_REDIRECTS)
+        .send()?;
+    let actual = hex(&Sha256::digest(&bytes));
+    if actual != sha256 {
+        bail!(
+            "`{}` has sha256 {} but the config expects {}",
+            url,
+            actual,
+            sha256
+        );
+    }
+    fs::write(&path, bytes).with_context(|| {
+        format!("Failed to write {}", path.display())
+    })?;
+    Ok(path)
+}
+
+/// Extracts the tarball `archive` into `dir` once every entry has
+/// passed [`check_entry`], so a rejected archive writes nothing.
+fn extract_archive(archive: &Path, dir: &Path) -> Result<()> {
+    let bytes = fs::read(archive).with_context(|| {
+        format!("Failed to read {}", archive.display())
+    })?;
+    for entry in open_tarball(&bytes).entries()? {
+        let entry = entry?;
+        check_entry(&entry.path()?, entry.header().entry_type())?;
+    }
+
+    for entry in open_tarball(&bytes).entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_pax_global_extensions() {
+            let _ = entry.unpack_in(dir)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads `bytes` as a tarball, gzip-compressed if it starts with the
+/// gzip magic number.
+fn open_tarball(bytes: &[u8]) -> tar::Archive<Box<dyn Read + '_>> {
+    let reader: Box<dyn Read + '_> = if bytes.starts_with(&[0x1f, 0x8b])
+    {
+        Box::new(GzDecoder::new(bytes))
+    } else {
+        Box::new(bytes)
+    };
+    tar::Archive::new(reader)
+}
+
+/// Checks that an archive entry is a file or directory, or a global
+/// header, whose path stays inside the extraction directory.
+fn check_entry(path: &Path, kind: tar::EntryType) -> Result<()> {
+    ensure!(
+        !kind.is_symlink() && !kind.is_hard_link(),
+        "`{}` is a link",
+        path.display()
+    );
+    ensure!(
+        kind.is_file()
+            || kind.is_contiguous()
+            || kind.is_dir()
+            || kind.is_pax_global_extensions(),
+        "`{}` is not a file or directory",
+        path.display()
+    );
+    ensure!(
+        path.components().all(|c| matches!(
+            c,
+            Component::Normal(_) | Component::CurDir
+        )),
+        "`{}` is not a relative path inside the archive",
+        path.display()
+    );
+    Ok(())
+}
+
+/// Replaces `to` with a copy of `from`, leaving out `.git` directories,
+/// and returns the number of files copied.
+fn replace_dir(from: &Path, to: &Path) -> Result<usize> {
+    if to.exists() {
+        fs::remove_dir_all(to).with_context(|| {
+            format!("Failed to remove {}", to.display())
+        })?;
+    }
+    fs::create_dir_all(to).with_context(|| {
+        format!("Failed to create {}", to.display())
+    })?;
+
+    let mut files = 0;
+    for entry in fs::read_dir(from)
+        .with_context(|| format!("Failed to read {}", from.display()))?
+    {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if entry.file_name() != ".git" {
+                files +=
+                    replace_dir(&path, &to.join(entry.file_name()))?;
+            }
+        } else if file_type.is_file() {
+            let _ = fs::copy(&path, to.join(entry.file_name()))
+                .with_context(|| {
+                    format!("Failed to copy {}", path.display())
+                })?;
+            files += 1;
+        }
+    }
+    Ok(files)
+}
+
+fn git(checkout: &Path) -> Command {
+    let mut command = Command::new("git");
+    let _ = command.arg("-C").arg(checkout);
+    command
+}
+
+fn head(checkout: &Path) -> Result<String> {
+    let output = git(checkout)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .context("Failed to run `git`; is it installed?")?;
+    ensure!(
+        output.status.success(),
+        "{} has no commit",
+        checkout.display()
+    );
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn run(command: &mut Command) -> Result<()> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let status = command.status().with_context(|| {
+        format!("Failed to run `{}`; is it installed?", program)
+    })?;
+    ensure!(status.success(), "`{}` failed ({})", program, status);
+    Ok(())
+}
+
+fn describe(source: &ContentSource) -> String {
+    match source {
+        ContentSource::Git { repository, .. } => repository.clone(),
+        ContentSource::Archive { url, .. } => url.clone(),
+        ContentSource::Cms(cms) => cms.endpoint.clone(),
+    }
+}
+
+fn key(text: &str) -> String {
+    hex(&Sha256::digest(text.as_bytes())[..8])
+}
+
+fn is_commit_hash(rev: &str) -> bool {
+    rev.len() == 40 && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_relative(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn git_in(dir: &Path, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args([
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@localhost",
+            ])
+            .args(args)
+            .output()?;
+        ensure!(output.status.success(), "git {:?} failed", args);
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn repository(dir: &Path) -> Result<String> {
+        fs::create_dir_all(dir.join("docs/guide"))?;
+        fs::write(dir.join("README.md"), "# Repo")?;
+        fs::write(dir.join("docs/index.md"), "# Docs")?;
+        fs::write(dir.join("docs/guide/setup.md"), "# Setup")?;
+        let _ = git_in(dir, &["init", "--quiet"])?;
+        let _ = git_in(dir, &["add", "--all"])?;
+        let _ = git_in(dir, &["commit", "--quiet", "-m", "Docs"])?;
+        git_in(dir, &["rev-parse", "HEAD"])
+    }
+
+    #[test]
+    fn test_validate() {
+        let archive = |url: &str, sha256: &str, target: &str| {
+            ContentSource::Archive {
+                url: url.into(),
+                sha256: sha256.into(),
+                path: None,
+                target: target.into(),
+            }
+        };
+        let sha = "a".repeat(64);
+        assert!(archive("https://example.com/a.tgz", &sha, "ref")
+            .validate()
+            .is_ok());
+        assert!(archive("http://example.com/a.tgz", &sha, "ref")
+            .validate()
+            .is_err());
+        assert!(archive("https://example.com/a.tgz", "abc", "ref")
+            .validate()
+            .is_err());
+        assert!(archive("https://example.com/a.tgz", &sha, "../ref")
+            .validate()
+            .is_err());
+        assert!(ContentSource::Git {
+            repository: "--upload-pack=evil".into(),
+            rev: None,
+            path: None,
+            target: "docs".into(),
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_sync_git_source() -> Result<()> {
+        let remote = tempdir()?;
+        let commit = repository(remote.path())?;
+        let project = tempdir()?;
+        let content = project.path().join("content");
+        let cache = project.path().join("cache");
+        fs::create_dir_all(content.join("docs"))?;
+        fs::write(content.join("docs/stale.md"), "old")?;
+
+        let sources = [ContentSource::Git {
+            repository: remote.path().display().to_string(),
+            rev: Some(commit.clone()),
+            path: Some("docs".into()),
+            target: "docs".into(),
+        }];
+        let synced = sync_sources(&sources, &content, &cache)?;
+        assert_eq!(synced[0].revision, commit);
+        assert_eq!(synced[0].files, 2);
+        assert!(content.join("docs/guide/setup.md").is_file());
+        assert!(!content.join("docs/stale.md").exists());
+        assert!(!content.join("docs/.git").exists());
+
+        // The pinned commit is served from the cache.
+        fs::remove_dir_all(remote.path())?;
+        let synced = sync_sources(&sources, &content, &cache)?;
+        assert_eq!(synced[0].revision, commit);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pinned_rev_must_match() -> Result<()> {
+        let remote = tempdir()?;
+        let _ = repository(remote.path())?;
+        let project = tempdir()?;
+        let source = ContentSource::Git {
+            repository: remote.path().display().to_string(),
+            rev: Some("0".repeat(40)),
+            path: None,
+            target: "docs".into(),
+        };
+        assert!(sync_sources(
+            &[source],
+            &project.path().join("content"),
+            &project.path().join("cache"),
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sync_cached_archive() -> Result<()> {
+        let work = tempdir()?;
+        let tree = work.path().join("tree");
+        fs::create_dir_all(tree.join("reference-1.2"))?;
+        fs::write(tree.join("reference-1.2/api.md"), "# API")?;
+        let archive = work.path().join("reference.tar.gz");
+        run(Command::new("tar")
+            .arg("-czf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(&tree)
+            .arg("reference-1.2"))?;
+        let sha256 = hex(&Sha256::digest(&fs::read(&archive)?));
+
+        let cache = work.path().join("cache");
+        fs::create_dir_all(&cache)?;
+        let _ = fs::copy(
+            &archive,
+            cache.join(format!("{}.archive", sha256)),
+        )?;
+        let content = work.path().join("content");
+        let source = ContentSource::Archive {
+            url: "https://example.com/reference.tar.gz".into(),
+            sha256: sha256.clone(),
+            path: Some("reference-1.2".into()),
+            target: "reference".into(),
+        };
+        let synced = sync_sources(&[source], &content, &cache)?;
+        assert_eq!(synced[0].revision, sha256);
+        assert_eq!(
+            fs::read_to_string(content.join("reference/api.md"))?,
+            "# API"
+        );
+
+        // A corrupted cache entry is not trusted.
+        fs::write(
+            cache.join(format!("{}.archive", sha256)),
+            "tampered",
+        )?;
+        let source = ContentSource::Archive {
+            url: "https://example.com/reference.tar.gz".into(),
+            sha256,
+            path: None,
+            target: "reference".into(),
+        };
+        assert!(sync_sources(&[source], &content, &cache).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsafe_archive_entries_are_rejected() -> Result<()> {
+        let archive =
+            |name: &[u8], kind: tar::EntryType| -> Result<_> {
+                let mut header = tar::Header::new_gnu();
+                header.as_old_mut().name[..name.len()]
+                    .copy_from_slice(name);
+                header.set_entry_type(kind);
+                header.set_mode(0o644);
+                header.set_size(0);
+                if kind.is_symlink() {
+                    header.set_link_name("/etc/passwd")?;
+                }
+                header.set_cksum();
+                let mut tar = tar::Builder::new(Vec::new());
+                tar.append(&header, std::io::empty())?;
+                Ok(tar.into_inner()?)
+            };
+
+        let work = tempdir()?;
+        let cache = work.path().join("cache");
+        fs::create_dir_all(&cache)?;
+        let content = work.path().join("content");
+        for bytes in [
+            archive(b"../escape.md", tar::EntryType::Regular)?,
+            archive(b"/tmp/escape.md", tar::EntryType::Regular)?,
+            archive(b"docs/link.md", tar::EntryType::Symlink)?,
+            archive(b"docs/pipe", tar::EntryType::Fifo)?,
+        ] {
+            let sha256 = hex(&Sha256::digest(&bytes));
+            fs::write(
+                cache.join(format!("{}.archive", sha256)),
+                bytes,
+            )?;
+            let source = ContentSource::Archive {
+                url: "https://example.com/reference.tar".into(),
+                sha256,
+                path: None,
+                target: "reference".into(),
+            };
+            assert!(sync_sources(&[source], &content, &cache).is_err());
+        }
+        assert!(!work.path().join("escape.md").exists());
+        assert!(!content.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,467 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Headless CMS Source
+//!
+//! Pulls content items from a REST or GraphQL endpoint and writes each
+//! one as a Markdown document with frontmatter, so CMS content goes
+//! through the normal build:
+//!
+//! ```toml
+//! [[content_sources]]
+//! type = "cms"
+//! endpoint = "https://cms.example.com/graphql"
+//! query = "{ posts { slug title date body } }"
+//! items = "data.posts"
+//! token_env = "CMS_TOKEN"
+//! target = "blog"
+//! ```
+//!
+//! Without `query` the endpoint is fetched with `GET`; with it, the
+//! query is posted as GraphQL. `items` is the dotted path of the item
+//! list in the response. Every field except `body_field` becomes
+//! frontmatter; `slug_field` names the file. The token is read from the
+//! `token_env` environment variable and sent as a bearer token, so it
+//! never appears in the config file.
+//!
+//! Syncing is incremental: only new and changed documents are written,
+//! documents of deleted items are removed, and files not created by
+//! the source are left alone. If the endpoint cannot be reached, the
+//! documents of the last sync are kept.
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    fetch::Request,
+    template::{hex, lookup, slugify},
+};
+
+/// Timeout of a CMS request.
+const CMS_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest accepted CMS response.
+const MAX_RESPONSE_BYTES: u64 = 16 * 1024 * 1024;
+
+fn default_slug_field() -> String {
+    "slug".to_string()
+}
+
+fn default_body_field() -> String {
+    "body".to_string()
+}
+
+/// A REST or GraphQL endpoint listing content items.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CmsSource {
+    /// Endpoint URL.
+    pub endpoint: String,
+    /// GraphQL query; the endpoint is fetched with `GET` if unset.
+    #[serde(default)]
+    pub query: Option<String>,
+    /// Dotted path of the item list in the response; the response
+    /// itself if empty.
+    #[serde(default)]
+    pub items: String,
+    /// Item field holding the file name.
+    #[serde(default = "default_slug_field")]
+    pub slug_field: String,
+    /// Item field holding the Markdown or HTML body.
+    #[serde(default = "default_body_field")]
+    pub body_field: String,
+    /// Environment variable holding the API token.
+    #[serde(default)]
+    pub token_env: Option<String>,
+    /// Directory below the content directory to populate.
+    pub target: PathBuf,
+}
+
+/// Outcome of a CMS sync.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CmsSync {
+    /// Documents written because they are new or changed.
+    pub written: usize,
+    /// Documents removed because their item was deleted.
+    pub deleted: usize,
+    /// Documents left as they were.
+    pub unchanged: usize,
+    /// Hash of all documents, identifying this version of the content.
+    pub revision: String,
+}
+
+impl CmsSource {
+    /// Checks the endpoint and field names.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid setting.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.endpoint.starts_with("https://"),
+            "endpoint `{}` must be an https URL",
+            self.endpoint
+        );
+        ensure!(
+            !self.slug_field.is_empty() && !self.body_field.is_empty(),
+            "slug_field and body_field must not be empty"
+        );
+        if let Some(name) = &self.token_env {
+            ensure!(
+                !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                "Invalid token_env `{}`",
+                name
+            );
+        }
+        Ok(())
+    }
+
+    /// Fetches the items from the endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token variable is unset, the request
+    /// fails, the response has GraphQL errors, or `items` is not a list.
+    pub fn fetch_items(&self) -> Result<Vec<Value>> {
+        let mut request = Request::get(
+            &self.endpoint,
+            CMS_TIMEOUT,
+            MAX_RESPONSE_BYTES,
+        )
+        .header("Accept", "application/json".to_string());
+        if let Some(name) = &self.token_env {
+            let token = env::var(name).with_context(|| {
+                format!("Set {} to the CMS API token", name)
+            })?;
+            request = request
+                .header("Authorization", format!("Bearer {}", token));
+        }
+        if let Some(query) = &self.query {
+            request =
+                request.json(json!({ "query": query }).to_string());
+        }
+        let bytes = request.send()?;
+        let response: Value = serde_json::from_slice(&bytes)
+            .with_context(|| {
+                format!("`{}` did not return valid JSON", self.endpoint)
+            })?;
+        self.items(&response)
+    }
+
+    /// Returns the item list of `response`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the response has GraphQL errors or `items`
+    /// is not a list.
+    pub fn items(&self, response: &Value) -> Result<Vec<Value>> {
+        if let Some(errors) =
+            response.get("errors").and_then(Value::as_array)
+        {
+            if !errors.is_empty() {
+                let messages: Vec<&str> = errors
+                    .iter()
+                    .filter_map(|e| {
+                        e.get("message").and_then(Value::as_str)
+                    })
+                    .collect();
+                bail!(
+                    "`{}` returned errors: {}",
+                    self.endpoint,
+                    messages.join("; ")
+                );
+            }
+        }
+        let items = if self.items.is_empty() {
+            Some(response)
+        } else {
+            lookup(response, &self.items)
+        };
+        items.and_then(Value::as_array).cloned().ok_or_else(|| {
+            anyhow!(
+                "`{}` has no item list at `{}`",
+                self.endpoint,
+                self.items
+            )
+        })
+    }
+
+    /// Turns items into Markdown documents, keyed by file name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an item is not an object, has no usable slug,
+    /// or two items share a slug.
+    pub fn documents(
+        &self,
+        items: &[Value],
+    ) -> Result<BTreeMap<String, String>> {
+        let mut documents = BTreeMap::new();
+        for item in items {
+            let fields = item
+                .as_object()
+                .ok_or_else(|| anyhow!("CMS item is not an object"))?;
+            let slug = [&self.slug_field, "title", "id"]
+                .iter()
+                .filter_map(|field| fields.get(*field))
+                .map(|value| match value {
+                    Value::String(text) => slugify(text),
+                    other => slugify(&other.to_string()),
+                })
+                .find(|slug| !slug.is_empty())
+                .ok_or_else(|| {
+                    anyhow!("CMS item has no `{}`", self.slug_field)
+                })?;
+
+            let body = match fields.get(&self.body_field) {
+                Some(Value::String(body)) => body.clone(),
+                Some(Value::Null) | None => String::new(),
+                Some(other) => other.to_string(),
+            };
+            let frontmatter: Map<String, Value> = fields
+                .iter()
+                .filter(|(key, _)| **key != self.body_field)
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            let yaml = serde_yaml::to_string(&frontmatter)
+                .context("Failed to serialize CMS frontmatter")?;
+            let document =
+                format!("---\n{}---\n\n{}\n", yaml, body.trim());
+
+            let file = format!("{}.md", slug);
+            if documents.insert(file.clone(), document).is_some() {
+                bail!("Two CMS items have the slug `{}`", slug);
+            }
+        }
+        Ok(documents)
+    }
+
+    /// Fetches the items and updates `target_dir`, remembering the
+    /// written documents in `state_file`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the items cannot be fetched and there is no
+    /// previous sync, or the documents cannot be written.
+    pub fn sync(
+        &self,
+        target_dir: &Path,
+        state_file: &Path,
+    ) -> Result<CmsSync> {
+        let previous = load_state(state_file)?;
+        let items = match self.fetch_items() {
+            Ok(items) => items,
+            Err(error) if !previous.is_empty() => {
+                warn!(
+                    "Keeping the last sync of `{}`: {:#}",
+                    self.endpoint, error
+                );
+                return Ok(CmsSync {
+                    unchanged: previous.len(),
+                    revision: revision(&previous),
+                    ..CmsSync::default()
+                });
+            }
+            Err(error) => return Err(error),
+        };
+        apply(&self.documents(&items)?, target_dir, state_file)
+    }
+}
+
+/// Writes new and changed `documents` to `target_dir` and removes the
+/// ones from the last sync that are gone.
+///
+/// # Errors
+///
+/// Returns an error if a file or the state cannot be written.
+pub fn apply(
+    documents: &BTreeMap<String, String>,
+    target_dir: &Path,
+    state_file: &Path,
+) -> Result<CmsSync> {
+    let previous = load_state(state_file)?;
+    fs::create_dir_all(target_dir).with_context(|| {
+        format!("Failed to create {}", target_dir.display())
+    })?;
+
+    let mut sync = CmsSync::default();
+    let mut state = BTreeMap::new();
+    for (file, document) in documents {
+        let hash = hex(&Sha256::digest(document.as_bytes()));
+        let path = target_dir.join(file);
+        if previous.get(file) == Some(&hash) && path.is_file() {
+            sync.unchanged += 1;
+        } else {
+            fs::write(&path, document).with_context(|| {
+                format!("Failed to write {}", path.display())
+            })?;
+            sync.written += 1;
+        }
+        let _ = state.insert(file.clone(), hash);
+    }
+    for file in previous.keys().filter(|f| !documents.contains_key(*f))
+    {
+        let path = target_dir.join(file);
+        if path.is_file() {
+            fs::remove_file(&path).with_context(|| {
+                format!("Failed to remove {}", path.display())
+            })?;
+        }
+        sync.deleted += 1;
+    }
+
+    if let Some(parent) = state_file.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            format!("Failed to create {}", parent.display())
+        })?;
+    }
+    let json = serde_json::to_string_pretty(&state)
+        .context("Failed to serialize the CMS state")?;
+    fs::write(state_file, json).with_context(|| {
+        format!("Failed to write {}", state_file.display())
+    })?;
+    sync.revision = revision(&state);
+    Ok(sync)
+}
+
+fn load_state(path: &Path) -> Result<BTreeMap<String, String>> {
+    if !path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    let json = fs::read_to_string(path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    serde_json::from_str(&json).with_context(|| {
+        format!("Invalid CMS state {}", path.display())
+    })
+}
+
+fn revision(state: &BTreeMap<String, String>) -> String {
+    let mut hasher = Sha256::new();
+    for (file, hash) in state {
+        hasher.update(file.as_bytes());
+        hasher.update(hash.as_bytes());
+    }
+    hex(&hasher.finalize()[..8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::ContentSource;
+    use tempfile::tempdir;
+
+    fn source() -> CmsSource {
+        CmsSource {
+            endpoint: "https://cms.example.com/graphql".into(),
+            query: Some("{ posts { slug title body } }".into()),
+            items: "data.posts".into(),
+            slug_field: default_slug_field(),
+            body_field: default_body_field(),
+            token_env: Some("SSG_TEST_CMS_TOKEN".into()),
+            target: "blog".into(),
+        }
+    }
+
+    #[test]
+    fn test_items_and_documents() -> Result<()> {
+        let source = source();
+        source.validate()?;
+        let parsed: ContentSource = toml::from_str(
+            r#"
+            type = "cms"
+            endpoint = "https://cms.example.com/graphql"
+            query = "{ posts { slug title body } }"
+            items = "data.posts"
+            token_env = "SSG_TEST_CMS_TOKEN"
+            target = "blog"
+            "#,
+        )?;
+        assert_eq!(parsed, ContentSource::Cms(source.clone()));
+        let response = json!({ "data": { "posts": [
+            { "slug": "hello", "title": "Hello", "tags": ["a"], "body": "# Hi" },
+            { "title": "Second Post", "body": null },
+        ] } });
+        let items = source.items(&response)?;
+        let documents = source.documents(&items)?;
+        assert_eq!(
+            documents.keys().collect::<Vec<_>>(),
+            ["hello.md", "second-post.md"]
+        );
+        let hello = &documents["hello.md"];
+        assert!(hello.starts_with("---\n"));
+        assert!(hello.contains("title: Hello\n"));
+        assert!(hello.ends_with("---\n\n# Hi\n"));
+        assert!(!hello.contains("body:"));
+
+        let errors = json!({ "errors": [{ "message": "Bad query" }] });
+        assert!(source.items(&errors).is_err());
+        assert!(source
+            .documents(&[
+                json!({ "slug": "a" }),
+                json!({ "slug": "a" })
+            ])
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_is_incremental() -> Result<()> {
+        let dir = tempdir()?;
+        let target = dir.path().join("blog");
+        let state = dir.path().join("state.json");
+        fs::create_dir_all(&target)?;
+        fs::write(target.join("manual.md"), "kept")?;
+
+        let mut documents = BTreeMap::new();
+        let _ = documents.insert("a.md".to_string(), "A".to_string());
+        let _ = documents.insert("b.md".to_string(), "B".to_string());
+        let first = apply(&documents, &target, &state)?;
+        assert_eq!((first.written, first.unchanged), (2, 0));
+
+        let _ = documents.insert("a.md".to_string(), "A2".to_string());
+        let _ = documents.remove("b.md");
+        let second = apply(&documents, &target, &state)?;
+        assert_eq!(
+            (second.written, second.deleted, second.unchanged),
+            (1, 1, 0)
+        );
+        assert_ne!(first.revision, second.revision);
+        assert!(!target.join("b.md").exists());
+        assert!(target.join("manual.md").exists());
+
+        let third = apply(&documents, &target, &state)?;
+        assert_eq!((third.written, third.unchanged), (0, 1));
+        Ok(())
+    }
+
+    #[cfg(not(feature = "fetch"))]
+    #[test]
+    fn test_sync_keeps_last_documents_when_offline() -> Result<()> {
+        let dir = tempdir()?;
+        let target = dir.path().join("blog");
+        let state = dir.path().join("state.json");
+        let mut source = source();
+        source.token_env = None;
+        assert!(source.sync(&target, &state).is_err());
+
+        let mut documents = BTreeMap::new();
+        let _ = documents.insert("a.md".to_string(), "A".to_string());
+        let _ = apply(&documents, &target, &state)?;
+        let sync = source.sync(&target, &state)?;
+        assert_eq!((sync.written, sync.unchanged), (0, 1));
+        assert!(target.join("a.md").is_file());
+        Ok(())
+    }
+}
@@ -0,0 +1,684 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Structured Data
+//!
+//! Adds schema.org JSON-LD to generated pages so search engines can show
+//! them as rich results. It is opt-in:
+//!
+//! ```toml
+//! [structured_data]
+//! enabled = true
+//! organization = "Example Ltd"
+//! logo = "https://example.com/logo.png"
+//! same_as = ["https://github.com/example"]
+//! blog_sections = ["blog", "posts"]
+//! ```
+//!
+//! Each page gets one `<script type="application/ld+json">` before
+//! `</head>` holding a `@graph` of:
+//!
+//! - an `Article` for pages with a `date` or `published` date, or a
+//!   `BlogPosting` in `blog_sections`. A `schema_type` frontmatter value
+//!   picks another article type such as `NewsArticle`;
+//! - a `BreadcrumbList` of the page's [breadcrumb trail](crate::breadcrumbs),
+//!   for pages below the home page, unless `breadcrumbs = false`;
+//! - a `FAQPage` from a `faq` frontmatter list of `question` and
+//!   `answer` pairs;
+//! - the `Organization` publishing the site, on the home page. Its name
+//!   defaults to the site title.
+//!
+//! Items are checked for the properties schema.org consumers require,
+//! such as an article's `headline` and `datePublished`. A missing one is
+//! an error naming the page.
+
+use anyhow::{bail, ensure, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+use url::Url;
+
+use crate::{
+    breadcrumbs::Breadcrumbs,
+    content::{ContentSet, Page},
+    dates::{DateContext, DEFAULT_DATE_LANGUAGE},
+};
+
+/// `id` of the script element holding the generated JSON-LD.
+const SCRIPT_ID: &str = "ssg-structured-data";
+
+/// Article types accepted as `schema_type` frontmatter.
+const ARTICLE_TYPES: &[&str] = &[
+    "Article",
+    "BlogPosting",
+    "NewsArticle",
+    "TechArticle",
+    "ScholarlyArticle",
+];
+
+/// Properties required by each type. Every article type uses those of
+/// `Article`.
+const REQUIRED_PROPERTIES: &[(&str, &[&str])] = &[
+    ("Article", &["headline", "datePublished"]),
+    ("BreadcrumbList", &["itemListElement"]),
+    ("ListItem", &["position", "name", "item"]),
+    ("FAQPage", &["mainEntity"]),
+    ("Question", &["name", "acceptedAnswer"]),
+    ("Answer", &["text"]),
+    ("Organization", &["name", "url"]),
+    ("Person", &["name"]),
+];
+
+/// A structured data script written by an earlier run.
+static SCRIPT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r#"(?is)[ \t]*<script\s+type="application/ld\+json"\s+id="{}"\s*>.*?</script>[ \t]*\r?\n?"#,
+        SCRIPT_ID
+    ))
+    .unwrap_or_else(|e| panic!("Invalid structured data regex: {}", e))
+});
+
+/// `[structured_data]` options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StructuredDataOptions {
+    /// Adds JSON-LD to generated pages.
+    pub enabled: bool,
+    /// Name of the organization publishing the site; the site title
+    /// when empty.
+    pub organization: String,
+    /// Absolute URL of the organization's logo.
+    pub logo: String,
+    /// Absolute URLs of the organization's profiles on other sites.
+    pub same_as: Vec<String>,
+    /// Sections whose dated pages are `BlogPosting`s.
+    pub blog_sections: Vec<String>,
+    /// Adds a `BreadcrumbList` to pages below the home page.
+    pub breadcrumbs: bool,
+}
+
+impl Default for StructuredDataOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            organization: String::new(),
+            logo: String::new(),
+            same_as: Vec::new(),
+            blog_sections: vec![
+                "blog".to_string(),
+                "posts".to_string(),
+            ],
+            breadcrumbs: true,
+        }
+    }
+}
+
+impl StructuredDataOptions {
+    /// Checks that the logo and profiles are absolute URLs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the invalid URL.
+    pub fn validate(&self) -> Result<()> {
+        let urls = std::iter::once(&self.logo)
+            .filter(|logo| !logo.is_empty())
+            .chain(&self.same_as);
+        for url in urls {
+            ensure!(
+                Url::parse(url).map_or(false, |url| {
+                    matches!(url.scheme(), "http" | "https")
+                }),
+                "`{}` must be an absolute http or https URL",
+                url
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Builds the structured data of the pages of a site.
+#[derive(Debug)]
+pub struct StructuredData<'a> {
+    options: &'a StructuredDataOptions,
+    dates: DateContext,
+    base_url: String,
+    organization: String,
+    breadcrumbs: Breadcrumbs,
+}
+
+impl<'a> StructuredData<'a> {
+    /// Creates the builder for `content`, published at `base_url`.
+    pub fn new(
+        content: &ContentSet,
+        options: &'a StructuredDataOptions,
+        base_url: &str,
+        site_title: &str,
+    ) -> Self {
+        let organization = if options.organization.is_empty() {
+            site_title.to_string()
+        } else {
+            options.organization.clone()
+        };
+        Self {
+            options,
+            dates: DateContext::load(content, DEFAULT_DATE_LANGUAGE),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            organization,
+            breadcrumbs: Breadcrumbs::new(content),
+        }
+    }
+
+    /// Returns the JSON-LD items of `page`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frontmatter sets an unknown `schema_type`
+    /// or a malformed `faq`, or an item lacks a required property.
+    pub fn page_items(&self, page: &Page) -> Result<Vec<Value>> {
+        self.items(page).with_context(|| {
+            format!(
+                "Invalid structured data for {}",
+                page.source.display()
+            )
+        })
+    }
+
+    /// Inserts the structured data of every page of `content` into its
+    /// generated file in `site_dir`, returning the number of pages
+    /// rewritten. Pages without items, an output file or a `</head>`
+    /// are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a page's structured data is invalid or a
+    /// file cannot be read or written.
+    pub fn write(
+        &self,
+        content: &ContentSet,
+        site_dir: &Path,
+    ) -> Result<usize> {
+        let mut rewritten = 0;
+        for page in content.iter() {
+            let path = site_dir.join(page.output_path());
+            if !path.is_file() {
+                continue;
+            }
+            let items = self.page_items(page)?;
+            if items.is_empty() {
+                continue;
+            }
+            let html =
+                fs::read_to_string(&path).with_context(|| {
+                    format!("Failed to read {}", path.display())
+                })?;
+            if let Some(updated) = insert_structured_data(&html, &items)
+            {
+                fs::write(&path, updated).with_context(|| {
+                    format!("Failed to write {}", path.display())
+                })?;
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
+    }
+
+    fn items(&self, page: &Page) -> Result<Vec<Value>> {
+        let mut items = Vec::new();
+        items.extend(self.article(page)?);
+        items.extend(self.breadcrumbs(page));
+        items.extend(faq(page)?);
+        items.extend(self.organization_item(page));
+        let items: Vec<Value> =
+            items.into_iter().map(compact).collect();
+        items.iter().try_for_each(validate_item)?;
+        Ok(items)
+    }
+
+    fn url(&self, path: &str) -> String {
+        if path.starts_with("http://") || path.starts_with("https://") {
+            return path.to_string();
+        }
+        format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+    }
+
+    fn article(&self, page: &Page) -> Result<Option<Value>> {
+        let (published, updated) = self.dates.page_dates(page);
+        let kind = match (
+            page.frontmatter.get_str("schema_type"),
+            published,
+        ) {
+            (Some(kind), _) => {
+                if !ARTICLE_TYPES.contains(&kind.as_str()) {
+                    bail!(
+                        "Unknown schema_type `{}`; expected one of {}",
+                        kind,
+                        ARTICLE_TYPES.join(", ")
+                    );
+                }
+                kind
+            }
+            (None, Some(_)) => {
+                let blog = page.section().map_or(false, |section| {
+                    self.options.blog_sections.contains(&section)
+                });
+                let kind = if blog { "BlogPosting" } else { "Article" };
+                kind.to_string()
+            }
+            (None, None) => return Ok(None),
+        };
+
+        let authors: Vec<Value> =
+            match page.frontmatter.get("author") {
+                Some(Value::Array(_)) => {
+                    page.frontmatter.get_list("author")
+                }
+                _ => page
+                    .frontmatter
+                    .get_str("author")
+                    .into_iter()
+                    .collect(),
+            }
+            .into_iter()
+            .map(|name| json!({ "@type": "Person", "name": name }))
+            .collect();
+        let image = page
+            .frontmatter
+            .get_str("image")
+            .map(|image| self.url(&image));
+        let url = self.url(&page.url_path());
+        Ok(Some(json!({
+            "@type": kind,
+            "headline": page.title(),
+            "description": page.description(),
+            "url": url,
+            "mainEntityOfPage": url,
+            "datePublished": published.map(|date| date.to_string()),
+            "dateModified": updated.map(|date| date.to_string()),
+            "author": authors,
+            "image": image,
+            "publisher": {
+                "@type": "Organization",
+                "name": self.organization,
+                "url": self.url("/"),
+            },
+        })))
+    }
+
+    fn breadcrumbs(&self, page: &Page) -> Option<Value> {
+        let trail = self.breadcrumbs.page_trail(page);
+        if !self.options.breadcrumbs || trail.is_empty() {
+            return None;
+        }
+        Some(json!({
+            "@type": "BreadcrumbList",
+            "itemListElement": trail
+                .into_iter()
+                .enumerate()
+                .map(|(i, crumb)| json!({
+                    "@type": "ListItem",
+                    "position": i + 1,
+                    "name": crumb.name,
+                    "item": self.url(&crumb.url),
+                }))
+                .collect::<Vec<_>>(),
+        }))
+    }
+
+    fn organization_item(&self, page: &Page) -> Option<Value> {
+        if page.url_path() != "/" {
+            return None;
+        }
+        Some(json!({
+            "@type": "Organization",
+            "name": self.organization,
+            "url": self.url("/"),
+            "logo": if self.options.logo.is_empty() {
+                None
+            } else {
+                Some(&self.options.logo)
+            },
+            "sameAs": if self.options.same_as.is_empty() {
+                None
+            } else {
+                Some(&self.options.same_as)
+            },
+        }))
+    }
+}
+
+/// Adds the structured data of every page of `content` in `site_dir`,
+/// if enabled in `options`. Returns the number of pages rewritten.
+///
+/// # Errors
+///
+/// Returns an error if a page's structured data is invalid or a file
+/// cannot be read or written.
+pub fn write_structured_data(
+    content: &ContentSet,
+    site_dir: &Path,
+    options: &StructuredDataOptions,
+    base_url: &str,
+    site_title: &str,
+) -> Result<usize> {
+    if !options.enabled {
+        return Ok(0);
+    }
+    StructuredData::new(content, options, base_url, site_title)
+        .write(content, site_dir)
+}
+
+/// Replaces any structured data written by an earlier run with `items`,
+/// inserted before `</head>`. Returns `None` if the document has no
+/// head.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use ssg::structured_data::insert_structured_data;
+///
+/// let items = vec![json!({ "@type": "Organization", "name": "</script>" })];
+/// let html = insert_structured_data("<head></head>", &items).unwrap();
+/// assert!(html.starts_with("<head><script type=\"application/ld+json\""));
+/// assert!(html.contains(r#""name":"<\/script>""#));
+/// assert_eq!(insert_structured_data(&html, &items), Some(html));
+/// ```
+pub fn insert_structured_data(
+    html: &str,
+    items: &[Value],
+) -> Option<String> {
+    let html = SCRIPT_RE.replace_all(html, "");
+    let head = html.to_ascii_lowercase().find("</head>")?;
+    let graph = json!({
+        "@context": "https://schema.org",
+        "@graph": items,
+    });
+    let script = format!(
+        "<script type=\"application/ld+json\" id=\"{}\">{}</script>\n",
+        SCRIPT_ID,
+        graph.to_string().replace("</", "<\\/")
+    );
+    let mut updated = String::with_capacity(html.len() + script.len());
+    updated.push_str(&html[..head]);
+    updated.push_str(&script);
+    updated.push_str(&html[head..]);
+    Some(updated)
+}
+
+/// Returns the `FAQPage` of a page with `faq` frontmatter.
+fn faq(page: &Page) -> Result<Option<Value>> {
+    let entries = match page.frontmatter.get("faq") {
+        Some(Value::Array(entries)) => entries,
+        Some(_) => {
+            bail!(
+                "`faq` must be a list of `question` and `answer` pairs"
+            )
+        }
+        None => return Ok(None),
+    };
+    let questions: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            json!({
+                "@type": "Question",
+                "name": entry.get("question"),
+                "acceptedAnswer": {
+                    "@type": "Answer",
+                    "text": entry.get("answer"),
+                },
+            })
+        })
+        .collect();
+    Ok(Some(json!({
+        "@type": "FAQPage",
+        "mainEntity": questions,
+    })))
+}
+
+/// Checks that `item` and the items nested in it have the properties
+/// their type requires.
+fn validate_item(item: &Value) -> Result<()> {
+    match item {
+        Value::Array(items) => items.iter().try_for_each(validate_item),
+        Value::Object(map) => {
+            if let Some(kind) = map.get("@type").and_then(Value::as_str)
+            {
+                let base = if ARTICLE_TYPES.contains(&kind) {
+                    "Article"
+                } else {
+                    kind
+                };
+                let required = REQUIRED_PROPERTIES
+                    .iter()
+                    .find(|(name, _)| *name == base)
+                    .map_or(&[][..], |(_, required)| *required);
+                for property in required {
+                    ensure!(
+                        map.get(*property).map_or(false, is_present),
+                        "{} is missing the required `{}` property",
+                        kind,
+                        property
+                    );
+                }
+            }
+            map.values().try_for_each(validate_item)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns `false` for null, blank and empty values.
+fn is_present(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::String(s) => !s.trim().is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        _ => true,
+    }
+}
+
+/// Removes null and empty list properties, so optional values left
+/// unset are omitted.
+fn compact(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, value)| match value {
+                    Value::Null => false,
+                    Value::Array(items) => !items.is_empty(),
+                    _ => true,
+                })
+                .map(|(key, value)| (key, compact(value)))
+                .collect(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(compact).collect())
+        }
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn page(path: &str, raw: &str) -> Page {
+        Page::from_source(PathBuf::from(path), PathBuf::from(path), raw)
+            .unwrap()
+    }
+
+    fn content(pages: Vec<Page>) -> ContentSet {
+        ContentSet {
+            root: PathBuf::from("content"),
+            pages,
+        }
+    }
+
+    #[test]
+    fn test_blog_post_items() -> Result<()> {
+        let site = content(vec![
+            page("blog/index.md", "---\ntitle: Journal\n---\n"),
+            page(
+                "blog/getting-started/hello.md",
+                "---\ntitle: Hello\ndate: 2025-01-31\nupdated: 2025-02-01\nauthor: Jane Doe\nimage: /images/hello.png\n---\n",
+            ),
+        ]);
+        let options = StructuredDataOptions::default();
+        let data = StructuredData::new(
+            &site,
+            &options,
+            "https://example.com/",
+            "Example",
+        );
+
+        let items = data.page_items(&site.pages[1])?;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0]["@type"], "BlogPosting");
+        assert_eq!(items[0]["headline"], "Hello");
+        assert_eq!(items[0]["datePublished"], "2025-01-31");
+        assert_eq!(items[0]["dateModified"], "2025-02-01");
+        assert_eq!(items[0]["author"][0]["name"], "Jane Doe");
+        assert_eq!(
+            items[0]["image"],
+            "https://example.com/images/hello.png"
+        );
+        assert!(items[0].get("description").is_none());
+
+        let crumbs = &items[1]["itemListElement"];
+        let names: Vec<&str> = crumbs
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|crumb| crumb["name"].as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["Home", "Journal", "Getting started", "Hello"]
+        );
+        assert_eq!(crumbs[3]["position"], 4);
+        assert_eq!(
+            crumbs[3]["item"],
+            "https://example.com/blog/getting-started/hello/"
+        );
+
+        let options = StructuredDataOptions {
+            breadcrumbs: false,
+            ..StructuredDataOptions::default()
+        };
+        let data = StructuredData::new(
+            &site,
+            &options,
+            "https://example.com/",
+            "Example",
+        );
+        assert_eq!(data.page_items(&site.pages[1])?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_faq_and_validation() -> Result<()> {
+        let site = content(vec![
+            page(
+                "faq.md",
+                "---\nfaq:\n  - question: Is it free?\n    answer: Yes.\n---\n",
+            ),
+            page("broken.md", "---\nfaq:\n  - question: Why?\n---\n"),
+            page("news.md", "---\nschema_type: NewsArticle\n---\n"),
+            page("typo.md", "---\nschema_type: Articel\n---\n"),
+        ]);
+        let options = StructuredDataOptions::default();
+        let data = StructuredData::new(
+            &site,
+            &options,
+            "https://example.com",
+            "Example",
+        );
+
+        let items = data.page_items(&site.pages[0])?;
+        assert_eq!(items[1]["@type"], "FAQPage");
+        assert_eq!(items[1]["mainEntity"][0]["name"], "Is it free?");
+        assert_eq!(
+            items[1]["mainEntity"][0]["acceptedAnswer"]["text"],
+            "Yes."
+        );
+
+        for (index, message) in [
+            (1, "Answer is missing the required `text` property"),
+            (2, "NewsArticle is missing the required `datePublished`"),
+            (3, "Unknown schema_type `Articel`"),
+        ] {
+            let error =
+                data.page_items(&site.pages[index]).unwrap_err();
+            assert!(
+                format!("{:#}", error).contains(message),
+                "{:#}",
+                error
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_structured_data() -> Result<()> {
+        let temp_dir = tempdir()?;
+        fs::write(
+            temp_dir.path().join("index.html"),
+            "<html><head><title>Home</title></head><body></body></html>",
+        )?;
+        let site = content(vec![page("index.md", "Home")]);
+        let mut options = StructuredDataOptions::default();
+        assert_eq!(
+            write_structured_data(
+                &site,
+                temp_dir.path(),
+                &options,
+                "https://example.com",
+                "Example",
+            )?,
+            0
+        );
+
+        options.enabled = true;
+        options.same_as =
+            vec!["https://github.com/example".to_string()];
+        for _ in 0..2 {
+            assert_eq!(
+                write_structured_data(
+                    &site,
+                    temp_dir.path(),
+                    &options,
+                    "https://example.com",
+                    "Example",
+                )?,
+                1
+            );
+        }
+        let html =
+            fs::read_to_string(temp_dir.path().join("index.html"))?;
+        assert_eq!(html.matches("application/ld+json").count(), 1);
+        assert!(html.contains(r#""@type":"Organization""#));
+        assert!(
+            html.contains(r#""sameAs":["https://github.com/example"]"#)
+        );
+        assert!(!html.contains("logo"));
+        assert!(
+            html.ends_with("</script>\n</head><body></body></html>")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_options_validate() {
+        let mut options = StructuredDataOptions::default();
+        assert!(options.validate().is_ok());
+        options.logo = "/logo.png".to_string();
+        assert!(options.validate().is_err());
+        options.logo = "https://example.com/logo.png".to_string();
+        options.same_as = vec!["ftp://example.com".to_string()];
+        assert!(options.validate().is_err());
+    }
+}
@@ -0,0 +1,917 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # SCSS/Sass Compilation
+//!
+//! Compiles `.scss` and `.sass` stylesheets found in the template and
+//! assets directories into CSS in the site output directory, so no
+//! external Sass toolchain is needed before running the generator.
+//!
+//! Partials (files whose name starts with `_`) are never emitted on their own;
+//! they are only pulled in through `@use`/`@import` from other stylesheets.
+//!
+//! Stylesheets are minified when `[minify].css` is set, which the `prod`
+//! build profile does. With `source_maps`, which the `dev` profile
+//! sets, each expanded stylesheet gets a `.css.map` source map next to
+//! it, pointing its declarations and style rules at the Sass lines they
+//! came from, partials included. Sources are named relative to the
+//! stylesheet and embedded, so no local path ends up in the site.
+//!
+//! `grass` does not track output positions, so the files it reads are
+//! served with a marker comment before each declaration; the markers
+//! come out in the CSS next to the declarations and are turned into the
+//! mappings, then removed.
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::{
+    cell::RefCell,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::collect_files_recursive;
+
+/// Default directory of the site's own assets, such as stylesheets.
+pub const ASSETS_DIR: &str = "assets";
+
+/// Options controlling how stylesheets are compiled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StyleOptions {
+    /// Emit compressed, single-line CSS.
+    pub minify: bool,
+    /// Write a `.css.map` source map next to each stylesheet; ignored
+    /// when minifying.
+    pub source_maps: bool,
+}
+
+impl StyleOptions {
+    /// Options for development builds: expanded output with source
+    /// maps.
+    pub const fn development() -> Self {
+        Self {
+            minify: false,
+            source_maps: true,
+        }
+    }
+
+    /// Options for release builds: minified output without source
+    /// maps.
+    pub const fn production() -> Self {
+        Self {
+            minify: true,
+            source_maps: false,
+        }
+    }
+}
+
+/// Returns `true` if the path is a Sass stylesheet that should be compiled.
+///
+/// Partials (`_name.scss`) are excluded as they are only meant to be
+/// imported by other stylesheets.
+pub fn is_stylesheet(path: &Path) -> bool {
+    let is_sass = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("scss") | Some("sass")
+    );
+    let is_partial = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map_or(false, |name| name.starts_with('_'));
+
+    is_sass && !is_partial
+}
+
+/// Compiles every stylesheet under `src_dir` into `out_dir`.
+///
+/// The relative directory structure is preserved, and each `.scss`/`.sass`
+/// file is written with a `.css` extension.
+///
+/// # Arguments
+///
+/// * `src_dir` - Directory to search for stylesheets (usually the template or assets directory)
+/// * `out_dir` - Directory where the compiled CSS is written
+/// * `options` - Minification and source map settings
+///
+/// # Returns
+///
+/// * `Ok(Vec<PathBuf>)` - The paths of the CSS files written
+/// * `Err` - If a stylesheet fails to compile or cannot be written
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::path::Path;
+/// use ssg::styles::{compile_styles, StyleOptions};
+///
+/// fn main() -> anyhow::Result<()> {
+///     let written = compile_styles(
+///         Path::new("templates"),
+///         Path::new("public"),
+///         &StyleOptions::production(),
+///     )?;
+///     println!("Compiled {} stylesheet(s)", written.len());
+///     Ok(())
+/// }
+/// ```
+pub fn compile_styles(
+    src_dir: &Path,
+    out_dir: &Path,
+    options: &StyleOptions,
+) -> Result<Vec<PathBuf>> {
+    if !src_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    collect_files_recursive(src_dir, &mut files)?;
+    files.retain(|path| is_stylesheet(path));
+    files.sort();
+
+    let mut written = Vec::with_capacity(files.len());
+    for file in files {
+        let relative = file.strip_prefix(src_dir).unwrap_or(&file);
+        let css_path = out_dir.join(relative).with_extension("css");
+        compile_stylesheet(&file, src_dir, &css_path, options)?;
+        written.push(css_path);
+    }
+
+    Ok(written)
+}
+
+/// Compiles a single stylesheet and writes the CSS, with its source
+/// map if `options` asks for one.
+///
+/// A stylesheet whose marked sources fail to compile is compiled again
+/// as it is, so errors point at the real code.
+fn compile_stylesheet(
+    source: &Path,
+    load_root: &Path,
+    css_path: &Path,
+    options: &StyleOptions,
+) -> Result<()> {
+    let marking = MarkingFs::new(load_root);
+    let marked = if options.source_maps && !options.minify {
+        grass::from_path(
+            source,
+            &grass_options(source, load_root, options).fs(&marking),
+        )
+        .ok()
+    } else {
+        None
+    };
+
+    let css = match marked {
+        Some(marked) => {
+            let (mut css, positions) = marking.strip(&marked);
+            let map_path =
+                PathBuf::from(format!("{}.map", css_path.display()));
+            let map = marking.source_map(
+                source.parent().unwrap_or(load_root),
+                css_path,
+                &positions,
+            )?;
+            write_file(&map_path, &map)?;
+            if let Some(map_name) = map_path.file_name() {
+                css.push_str(&format!(
+                    "/*# sourceMappingURL={} */\n",
+                    map_name.to_string_lossy()
+                ));
+            }
+            css
+        }
+        None => grass::from_path(
+            source,
+            &grass_options(source, load_root, options),
+        )
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to compile stylesheet {}: {}",
+                source.display(),
+                e
+            )
+        })?,
+    };
+    write_file(css_path, &css)
+}
+
+/// Returns the `grass` options of `source`, which loads modules from
+/// `load_root` and its own directory.
+fn grass_options(
+    source: &Path,
+    load_root: &Path,
+    options: &StyleOptions,
+) -> grass::Options<'static> {
+    let style = if options.minify {
+        grass::OutputStyle::Compressed
+    } else {
+        grass::OutputStyle::Expanded
+    };
+
+    let mut grass_options =
+        grass::Options::default().style(style).load_path(load_root);
+    if let Some(parent) = source.parent() {
+        grass_options = grass_options.load_path(parent);
+    }
+    grass_options
+}
+
+/// Writes `contents` to `path`, creating its directory.
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            format!(
+                "Failed to create stylesheet directory: {}",
+                parent.display()
+            )
+        })?;
+    }
+
+    fs::write(path, contents).with_context(|| {
+        format!("Failed to write stylesheet: {}", path.display())
+    })
+}
+
+/// Opening of the marker comment placed before each declaration.
+const MARKER_START: &str = "/*ssg-map:";
+
+/// Closing of the marker comment.
+const MARKER_END: &str = "*/";
+
+/// Zero-based line and column of a position in a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    source: usize,
+    line: usize,
+    column: usize,
+}
+
+/// Where a marked declaration, and the style rule around it, start.
+#[derive(Debug, Clone, Copy)]
+struct Origin {
+    declaration: Position,
+    rule: Option<Position>,
+}
+
+/// A [`grass::Fs`] serving Sass files with a marker comment before
+/// each declaration, which records the files it read and where each
+/// marked declaration starts.
+#[derive(Debug)]
+struct MarkingFs {
+    root: PathBuf,
+    sources: RefCell<Vec<(PathBuf, String)>>,
+    origins: RefCell<Vec<Origin>>,
+}
+
+impl MarkingFs {
+    fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            sources: RefCell::new(Vec::new()),
+            origins: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Removes the markers from `css`, and the style rules left empty
+    /// by declarations without output, returning the CSS and, for each
+    /// of its lines, the column its code starts at and the source
+    /// position it came from.
+    fn strip(
+        &self,
+        css: &str,
+    ) -> (String, Vec<(usize, Option<Position>)>) {
+        let origins = self.origins.borrow();
+        let mut lines: Vec<(String, Option<Position>)> = Vec::new();
+        let mut pending: Option<Origin> = None;
+        let mut dropped = false;
+        for raw in css.lines() {
+            let (line, marker) = take_markers(raw);
+            if let Some(id) = marker {
+                pending = origins.get(id).copied();
+                if line.trim().is_empty() {
+                    continue;
+                }
+            }
+            if dropped && line.is_empty() {
+                dropped = false;
+                continue;
+            }
+            dropped = false;
+            if line.trim() == "}" {
+                pending = None;
+                if is_block_start(lines.last()) {
+                    let _ = lines.pop();
+                    while lines
+                        .last()
+                        .map_or(false, |(line, _)| line.ends_with(','))
+                    {
+                        let _ = lines.pop();
+                    }
+                    dropped = true;
+                    continue;
+                }
+            }
+            let origin = pending.take();
+            if let Some(rule) = origin.and_then(|origin| origin.rule) {
+                if is_block_start(lines.last()) {
+                    for (index, (line, position)) in
+                        lines.iter_mut().rev().enumerate()
+                    {
+                        if index > 0 && !line.ends_with(',')
+                            || position.is_some()
+                            || line.trim_start().starts_with('@')
+                        {
+                            break;
+                        }
+                        *position = Some(rule);
+                    }
+                }
+            }
+            lines.push((line, origin.map(|origin| origin.declaration)));
+        }
+        while lines.first().map_or(false, |(line, _)| line.is_empty()) {
+            let _ = lines.remove(0);
+        }
+
+        let mut stripped = String::new();
+        let mut positions = Vec::with_capacity(lines.len());
+        for (line, position) in lines {
+            stripped.push_str(&line);
+            stripped.push('\n');
+            positions.push((indentation(&line), position));
+        }
+        (stripped, positions)
+    }
+
+    /// Returns the version 3 source map of `css_path`, whose lines come
+    /// from `positions`, naming sources relative to `dir`.
+    fn source_map(
+        &self,
+        dir: &Path,
+        css_path: &Path,
+        positions: &[(usize, Option<Position>)],
+    ) -> Result<String> {
+        let sources = self.sources.borrow();
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.into());
+        let root = fs::canonicalize(&self.root)
+            .unwrap_or_else(|_| self.root.clone());
+        let names: Vec<String> = sources
+            .iter()
+            .map(|(path, _)| source_name(&root, &dir, path))
+            .collect();
+        let contents: Vec<&str> =
+            sources.iter().map(|(_, text)| text.as_str()).collect();
+
+        let mut mappings = String::new();
+        let mut previous = Position {
+            source: 0,
+            line: 0,
+            column: 0,
+        };
+        for (line, (column, position)) in positions.iter().enumerate() {
+            if line > 0 {
+                mappings.push(';');
+            }
+            if let Some(position) = position {
+                push_vlq(&mut mappings, *column as i64);
+                push_vlq(
+                    &mut mappings,
+                    delta(position.source, previous.source),
+                );
+                push_vlq(
+                    &mut mappings,
+                    delta(position.line, previous.line),
+                );
+                push_vlq(
+                    &mut mappings,
+                    delta(position.column, previous.column),
+                );
+                previous = *position;
+            }
+        }
+
+        let map = json!({
+            "version": 3,
+            "file": css_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            "sources": names,
+            "sourcesContent": contents,
+            "names": [],
+            "mappings": mappings,
+        });
+        Ok(serde_json::to_string(&map)?)
+    }
+
+    /// Records `text`, read from `path`, and returns the index of the
+    /// source.
+    fn add_source(&self, path: &Path, text: &str) -> usize {
+        let mut sources = self.sources.borrow_mut();
+        sources.push((path.to_path_buf(), text.to_string()));
+        sources.len() - 1
+    }
+
+    /// Records an origin and returns its marker comment.
+    fn marker(&self, origin: Origin) -> String {
+        let mut origins = self.origins.borrow_mut();
+        origins.push(origin);
+        format!("{}{}{}", MARKER_START, origins.len() - 1, MARKER_END)
+    }
+}
+
+impl grass::Fs for MarkingFs {
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let bytes = fs::read(path)?;
+        let indented =
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some("scss") => false,
+                Some("sass") => true,
+                _ => return Ok(bytes),
+            };
+        let text = match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(error) => return Ok(error.into_bytes()),
+        };
+        let source = self.add_source(path, &text);
+        let marked = if indented {
+            self.mark_sass(source, &text)
+        } else {
+            self.mark_scss(source, &text)
+        };
+        Ok(marked.into_bytes())
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+}
+
+/// What a brace of a SCSS file opened.
+enum Open {
+    /// A block, with the start of the style rule it is in.
+    Block(Option<usize>),
+    /// An interpolation, `#{...}`.
+    Interpolation,
+}
+
+impl MarkingFs {
+    /// Returns the SCSS `text` with a marker before each declaration.
+    /// Markers are put on the line of their declaration, so compile
+    /// errors keep their line numbers.
+    fn mark_scss(&self, source: usize, text: &str) -> String {
+        let bytes = text.as_bytes();
+        let lines = LineIndex::new(source, text);
+        let mut stack: Vec<Open> = Vec::new();
+        let mut markers = Vec::new();
+        let mut start: Option<usize> = None;
+        let mut parens = 0_usize;
+        let mut i = 0;
+        while i < bytes.len() {
+            let byte = bytes[i];
+            let next = bytes.get(i + 1).copied();
+            let comment = byte == b'/'
+                && (next == Some(b'*')
+                    || next == Some(b'/') && parens == 0);
+            if start.is_none()
+                && !comment
+                && !byte.is_ascii_whitespace()
+                && !matches!(byte, b';' | b'{' | b'}')
+            {
+                start = Some(i);
+            }
+            let rule = stack.iter().rev().find_map(|open| match open {
+                Open::Block(rule) => Some(*rule),
+                Open::Interpolation => None,
+            });
+            let in_interpolation =
+                matches!(stack.last(), Some(Open::Interpolation));
+            match byte {
+                b'"' | b'\'' => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != byte {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                }
+                b'/' if comment && next == Some(b'*') => {
+                    i = text[i + 2..]
+                        .find("*/")
+                        .map_or(bytes.len(), |end| i + end + 3);
+                }
+                b'/' if comment => {
+                    i = text[i..]
+                        .find('\n')
+                        .map_or(bytes.len(), |end| i + end);
+                }
+                b'#' if next == Some(b'{') => {
+                    stack.push(Open::Interpolation);
+                    i += 1;
+                }
+                b'(' | b'[' => parens += 1,
+                b')' | b']' => parens = parens.saturating_sub(1),
+                b'}' if in_interpolation => {
+                    let _ = stack.pop();
+                }
+                b'{' if parens == 0 && !in_interpolation => {
+                    let header = start
+                        .take()
+                        .map_or("", |start| &text[start..i]);
+                    let nested = header.starts_with('@')
+                        || header.trim_end().ends_with(':');
+                    stack.push(Open::Block(if nested {
+                        rule.flatten()
+                    } else {
+                        Some(i - header.len())
+                    }));
+                }
+                b';' | b'}' if parens == 0 && !in_interpolation => {
+                    if let (Some(start), Some(rule)) =
+                        (start.take(), rule)
+                    {
+                        if is_declaration(&text[start..i]) {
+                            let origin = Origin {
+                                declaration: lines.position(start),
+                                rule: rule
+                                    .map(|rule| lines.position(rule)),
+                            };
+                            markers.push((start, self.marker(origin)));
+                        }
+                    }
+                    if byte == b'}' {
+                        let _ = stack.pop();
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        let mut marked = String::with_capacity(text.len());
+        let mut copied = 0;
+        for (offset, marker) in markers {
+            marked.push_str(&text[copied..offset]);
+            marked.push_str(&marker);
+            copied = offset;
+        }
+        marked.push_str(&text[copied..]);
+        marked
+    }
+
+    /// Returns the indented Sass `text` with a marker line before each
+    /// declaration.
+    fn mark_sass(&self, source: usize, text: &str) -> String {
+        let lines: Vec<&str> = text.split('\n').collect();
+        let index = LineIndex::new(source, text);
+        let mut rules: Vec<(usize, Option<usize>)> = Vec::new();
+        let mut comment: Option<usize> = None;
+        let mut selector: Option<usize> = None;
+        let mut marked = String::with_capacity(text.len());
+        let mut offset = 0;
+        for (number, line) in lines.iter().enumerate() {
+            let trimmed = line.trim();
+            let depth = indentation(line);
+            if number > 0 {
+                marked.push('\n');
+            }
+            if comment.map_or(false, |comment| depth <= comment)
+                && !trimmed.is_empty()
+            {
+                comment = None;
+            }
+            if !trimmed.is_empty() && comment.is_none() {
+                while rules.last().map_or(false, |&(d, _)| d >= depth) {
+                    let _ = rules.pop();
+                }
+                let nested = lines[number + 1..]
+                    .iter()
+                    .find(|line| !line.trim().is_empty())
+                    .map_or(false, |line| indentation(line) > depth);
+                let rule = rules.last().and_then(|&(_, rule)| rule);
+                let start = selector.take().unwrap_or(offset + depth);
+                if trimmed.starts_with("//")
+                    || trimmed.starts_with("/*")
+                {
+                    comment = Some(depth);
+                } else if nested {
+                    let header = trimmed
+                        .starts_with(['@', '+', '='].as_ref())
+                        || trimmed.ends_with(':');
+                    rules.push((
+                        depth,
+                        if header { rule } else { Some(start) },
+                    ));
+                } else if trimmed.ends_with(',') {
+                    selector = Some(start);
+                } else if !rules.is_empty()
+                    && !trimmed.starts_with(['+', '=', '&'].as_ref())
+                    && is_declaration(trimmed)
+                {
+                    let origin = Origin {
+                        declaration: index.position(offset + depth),
+                        rule: rule.map(|rule| index.position(rule)),
+                    };
+                    marked.push_str(&line[..depth]);
+                    marked.push_str(&self.marker(origin));
+                    marked.push('\n');
+                }
+            }
+            marked.push_str(line);
+            offset += line.len() + 1;
+        }
+        marked
+    }
+}
+
+/// Byte offsets of the line starts of a source file.
+struct LineIndex {
+    source: usize,
+    starts: Vec<usize>,
+    text: String,
+}
+
+impl LineIndex {
+    fn new(source: usize, text: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            starts,
+            text: text.to_string(),
+        }
+    }
+
+    /// Returns the position of the byte at `offset`, with the column
+    /// in characters.
+    fn position(&self, offset: usize) -> Position {
+        let line =
+            self.starts.partition_point(|&start| start <= offset) - 1;
+        Position {
+            source: self.source,
+            line,
+            column: self.text[self.starts[line]..offset]
+                .chars()
+                .count(),
+        }
+    }
+}
+
+/// Returns `true` if the statement `text` is a property declaration
+/// rather than a variable, an at-rule or a placeholder.
+fn is_declaration(text: &str) -> bool {
+    !text.starts_with(['@', '$', '%'].as_ref()) && text.contains(':')
+}
+
+/// Returns the width of the indentation of `line`.
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Returns `line` without marker comments, and the id of the last one.
+fn take_markers(line: &str) -> (String, Option<usize>) {
+    let mut rest = line.to_string();
+    let mut id = None;
+    while let Some(start) = rest.find(MARKER_START) {
+        let body = start + MARKER_START.len();
+        let end = match rest[body..].find(MARKER_END) {
+            Some(end) => body + end,
+            None => break,
+        };
+        id = rest[body..end].parse().ok().or(id);
+        rest.replace_range(start..end + MARKER_END.len(), "");
+    }
+    (rest, id)
+}
+
+/// Returns `true` if `line` opens a block.
+fn is_block_start(line: Option<&(String, Option<Position>)>) -> bool {
+    line.map_or(false, |(line, _)| line.ends_with('{'))
+}
+
+/// Names `path` relative to `dir` if it is under `root`, or by its file
+/// name otherwise, so the map holds no local paths.
+fn source_name(root: &Path, dir: &Path, path: &Path) -> String {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.into());
+    if !path.starts_with(root) {
+        return path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+    }
+    let common = dir
+        .components()
+        .zip(path.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let ups =
+        dir.components().skip(common).map(|_| Component::ParentDir);
+    let relative: PathBuf =
+        ups.chain(path.components().skip(common)).collect();
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+/// Returns `to - from` as a source map field.
+fn delta(to: usize, from: usize) -> i64 {
+    to as i64 - from as i64
+}
+
+/// Appends `value` to `out` as a base64 VLQ.
+fn push_vlq(out: &mut String, value: i64) {
+    const DIGITS: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut rest = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = rest & 31;
+        rest >>= 5;
+        if rest > 0 {
+            digit |= 32;
+        }
+        out.push(char::from(DIGITS[digit as usize]));
+        if rest == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_is_stylesheet() {
+        assert!(is_stylesheet(Path::new("css/main.scss")));
+        assert!(is_stylesheet(Path::new("css/main.sass")));
+        assert!(!is_stylesheet(Path::new("css/_partial.scss")));
+        assert!(!is_stylesheet(Path::new("css/main.css")));
+    }
+
+    #[test]
+    fn test_compile_styles_with_partials() -> Result<()> {
+        let src = tempdir()?;
+        let out = tempdir()?;
+        fs::create_dir_all(src.path().join("css"))?;
+        fs::write(src.path().join("css/_vars.scss"), "$c: red;")?;
+        fs::write(
+            src.path().join("css/main.scss"),
+            "@use 'vars';\nbody { color: vars.$c; }",
+        )?;
+
+        let written = compile_styles(
+            src.path(),
+            out.path(),
+            &StyleOptions::production(),
+        )?;
+
+        assert_eq!(written, vec![out.path().join("css/main.css")]);
+        let css = fs::read_to_string(&written[0])?;
+        assert_eq!(css, "body{color:red}");
+        assert!(!out.path().join("css/_vars.css").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_styles_development_source_map() -> Result<()> {
+        let src = tempdir()?;
+        fs::create_dir_all(src.path().join("css"))?;
+        fs::write(
+            src.path().join("_vars.scss"),
+            "$c: red;\n@mixin pad {\n  padding: 0;\n}\n",
+        )?;
+        let main = "@use '../vars';\na {\n  color: vars.$c;\n  margin: null;\n  @include vars.pad;\n  b { top: 0 }\n  i { left: null; }\n}\n";
+        fs::write(src.path().join("css/main.scss"), main)?;
+
+        let plain = tempdir()?;
+        let _ = compile_styles(
+            src.path(),
+            plain.path(),
+            &StyleOptions {
+                source_maps: false,
+                ..StyleOptions::development()
+            },
+        )?;
+        let out = tempdir()?;
+        let _ = compile_styles(
+            src.path(),
+            out.path(),
+            &StyleOptions::development(),
+        )?;
+
+        let css = fs::read_to_string(out.path().join("css/main.css"))?;
+        assert_eq!(
+            css,
+            fs::read_to_string(plain.path().join("css/main.css"))?
+                + "/*# sourceMappingURL=main.css.map */\n"
+        );
+        assert!(!plain.path().join("css/main.css.map").exists());
+
+        let map =
+            fs::read_to_string(out.path().join("css/main.css.map"))?;
+        assert!(!map.contains(&*src.path().to_string_lossy()));
+        let map: serde_json::Value = serde_json::from_str(&map)?;
+        assert_eq!(map["version"], 3);
+        assert_eq!(map["file"], "main.css");
+        assert_eq!(
+            map["sources"],
+            json!(["main.scss", "../_vars.scss"])
+        );
+        assert_eq!(map["sourcesContent"][0], main);
+        // `a {` and `color` in main.scss, `padding` in the mixin, then
+        // `a b {` and `top`.
+        assert_eq!(map["mappings"], "AACA;EACE;ECAA;;ADGA;EAAI;");
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_styles_indented_source_map() -> Result<()> {
+        let src = tempdir()?;
+        let out = tempdir()?;
+        fs::write(
+            src.path().join("site.sass"),
+            "// a: b\na,\nb\n  color: blue\n",
+        )?;
+
+        let _ = compile_styles(
+            src.path(),
+            out.path(),
+            &StyleOptions::development(),
+        )?;
+
+        let css = fs::read_to_string(out.path().join("site.css"))?;
+        assert!(css.starts_with("a,\nb {\n  color: blue;\n}\n"));
+        let map: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(out.path().join("site.css.map"))?,
+        )?;
+        assert_eq!(map["sources"], json!(["site.sass"]));
+        assert_eq!(map["mappings"], "AACA;AAAA;EAEE;");
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_styles_source_map_errors_keep_lines() -> Result<()>
+    {
+        let src = tempdir()?;
+        let out = tempdir()?;
+        fs::write(
+            src.path().join("broken.sass"),
+            "a\n  color: red\n  top: $nope\n",
+        )?;
+
+        let error = compile_styles(
+            src.path(),
+            out.path(),
+            &StyleOptions::development(),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("broken.sass:3:"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_vlq() {
+        let mut out = String::new();
+        for value in [0, 1, -1, 16, 123] {
+            push_vlq(&mut out, value);
+        }
+        assert_eq!(out, "ACDgB2H");
+    }
+
+    #[test]
+    fn test_compile_styles_invalid_source() -> Result<()> {
+        let src = tempdir()?;
+        let out = tempdir()?;
+        fs::write(src.path().join("broken.scss"), "a { color: ")?;
+
+        let result = compile_styles(
+            src.path(),
+            out.path(),
+            &StyleOptions::default(),
+        );
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_styles_missing_directory() -> Result<()> {
+        let out = tempdir()?;
+        let written = compile_styles(
+            Path::new("does/not/exist"),
+            out.path(),
+            &StyleOptions::default(),
+        )?;
+        assert!(written.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Symlink Policy
+//!
+//! Configured directories and copied files that are symlinks are
+//! refused by default, so a link cannot pull files from elsewhere on the
+//! machine into the published site. Sites that keep their content or
+//! assets behind symlinks, in a monorepo or on a mounted volume, choose
+//! another policy with the `symlinks` setting of the configuration file:
+//!
+//! ```toml
+//! symlinks = "follow-within-root"
+//! ```
+//!
+//! | Policy | Symlinks |
+//! |--------|----------|
+//! | `deny` (default) | Refused |
+//! | `follow-within-root` | Followed when their target is inside the root |
+//! | `follow-all` | Followed wherever their target is |
+//!
+//! The root of the configured directories is the project root, the
+//! directory of the `shokunin.toml` file in use, or the current
+//! directory without one; the root of a copied file is the directory
+//! being copied.
+//! Links are resolved completely, so a chain of links is judged by
+//! where it ends, and a link whose target does not exist is refused by
+//! every policy.
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// What is done with a path that is a symlink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymlinkPolicy {
+    /// Refuses symlinks.
+    Deny,
+    /// Follows symlinks whose target is inside the root.
+    FollowWithinRoot,
+    /// Follows every symlink.
+    FollowAll,
+}
+
+impl Default for SymlinkPolicy {
+    fn default() -> Self {
+        Self::Deny
+    }
+}
+
+impl SymlinkPolicy {
+    /// Checks that `path` may be used under the policy when it is a
+    /// symlink. Paths that are not symlinks, or do not exist, pass.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the policy refuses the symlink, if its target
+    /// does not exist, or if the target is outside `root` with
+    /// [`SymlinkPolicy::FollowWithinRoot`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::symlinks::SymlinkPolicy;
+    /// use std::path::Path;
+    ///
+    /// let readme = Path::new("README.md");
+    /// let policy = SymlinkPolicy::Deny;
+    /// assert!(policy.check(readme, Path::new(".")).is_ok());
+    /// ```
+    pub fn check(self, path: &Path, root: &Path) -> Result<()> {
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(_) => return Ok(()),
+        };
+        if !metadata.file_type().is_symlink() {
+            return Ok(());
+        }
+        if self == Self::Deny {
+            bail!(
+                "Symlinks are not allowed, see the `symlinks` setting \
+                 to follow them"
+            );
+        }
+        let target = path.canonicalize().with_context(|| {
+            format!("Symlink {} has no target", path.display())
+        })?;
+        if self == Self::FollowWithinRoot {
+            let root = root.canonicalize().with_context(|| {
+                format!("Failed to resolve {}", root.display())
+            })?;
+            ensure!(
+                target.starts_with(&root),
+                "Symlink points to {}, outside {}",
+                target.display(),
+                root.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_policies() -> Result<()> {
+        let temp = tempdir()?;
+        let root = temp.path().join("site");
+        fs::create_dir_all(root.join("shared"))?;
+        fs::write(temp.path().join("secret.txt"), "outside")?;
+        let inside = root.join("content");
+        let outside = root.join("secret.txt");
+        let broken = root.join("broken");
+        symlink(root.join("shared"), &inside)?;
+        symlink(temp.path().join("secret.txt"), &outside)?;
+        symlink(root.join("missing"), &broken)?;
+
+        let all = SymlinkPolicy::FollowAll;
+        for link in [&inside, &outside] {
+            assert!(SymlinkPolicy::Deny.check(link, &root).is_err());
+            assert!(all.check(link, &root).is_ok());
+        }
+        let policy = SymlinkPolicy::FollowWithinRoot;
+        assert!(policy.check(&inside, &root).is_ok());
+        let error = policy.check(&outside, &root).unwrap_err();
+        assert!(error.to_string().contains("outside"), "{}", error);
+        assert!(all.check(&broken, &root).is_err());
+        assert!(policy.check(&root.join("shared"), &root).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_links_escaping_through_a_chain() -> Result<()> {
+        let temp = tempdir()?;
+        let root = temp.path().join("site");
+        fs::create_dir_all(&root)?;
+        fs::create_dir_all(temp.path().join("elsewhere"))?;
+        // A link inside the root to a link that leaves it.
+        symlink(temp.path().join("elsewhere"), root.join("hop"))?;
+        symlink(root.join("hop"), root.join("assets"))?;
+        symlink("../site/../elsewhere", root.join("relative"))?;
+
+        let policy = SymlinkPolicy::FollowWithinRoot;
+        assert!(policy.check(&root.join("assets"), &root).is_err());
+        assert!(policy.check(&root.join("relative"), &root).is_err());
+
+        let policy: SymlinkPolicy =
+            serde_json::from_str("\"follow-within-root\"")?;
+        assert_eq!(policy, SymlinkPolicy::FollowWithinRoot);
+        assert_eq!(SymlinkPolicy::default(), SymlinkPolicy::Deny);
+        Ok(())
+    }
+}
@@ -0,0 +1,648 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Taxonomies
+//!
+//! Writes a listing page for every term of a taxonomy such as `tags` or
+//! `categories`, at a URL pattern chosen per taxonomy so migrated sites
+//! keep their existing term URLs:
+//!
+//! ```toml
+//! [taxonomies.tags]
+//! url = "/topics/:term/"
+//! paginate = 10
+//! page_url = "/topics/:term/page/:page/"
+//! slugs_file = "data/tag-slugs.yml"
+//!
+//! [taxonomies.categories]
+//! key = "category"
+//! ```
+//!
+//! Terms are read from the frontmatter key named like the taxonomy (or
+//! `key`), as a list or a comma-separated string. A term's slug is made
+//! with [`slug_from`] unless `slugs` or the `slugs_file` data file (YAML,
+//! JSON or TOML, mapping term to slug) overrides it; terms with the same
+//! slug are listed together.
+//!
+//! With `paginate` set, each term lists that many pages per listing page.
+//! The first page is at `url`, the others at `page_url`, which defaults
+//! to `url` followed by `page/:page/`.
+//!
+//! Term pages are rendered with the `taxonomy.html` template when the
+//! site or theme has one, with this context:
+//!
+//! | Variable     | Value |
+//! |--------------|-------|
+//! | `taxonomy`   | The taxonomy name, e.g. `tags` |
+//! | `term`       | The term as written in the first page using it |
+//! | `slug`       | The term slug |
+//! | `url`        | URL of this listing page |
+//! | `pages`      | The pages on this listing page, newest first, with their `title`, `url`, `description` and `date` |
+//! | `pagination` | `page`, `total_pages`, `total_items`, `prev_url` and `next_url` |
+//!
+//! Without one, a plain list page is written.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    content::{ContentSet, Page},
+    permalinks::slug_from,
+    template::{escape_html, TemplateBackend},
+};
+
+/// Template used for term pages when the site or theme provides it.
+pub const TAXONOMY_TEMPLATE: &str = "taxonomy.html";
+
+/// `[taxonomies.<name>]` options.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct TaxonomyOptions {
+    /// Frontmatter key holding the terms; defaults to the taxonomy name.
+    pub key: Option<String>,
+    /// URL pattern of a term page with a `:term` token; defaults to
+    /// `/<name>/:term/`.
+    pub url: Option<String>,
+    /// Pages listed per term page; `0` lists them all on one page.
+    pub paginate: usize,
+    /// URL pattern of the second and later term pages, with `:term` and
+    /// `:page` tokens.
+    pub page_url: Option<String>,
+    /// Slugs by term, overriding the generated ones.
+    pub slugs: BTreeMap<String, String>,
+    /// YAML, JSON or TOML data file of further slugs by term.
+    pub slugs_file: Option<PathBuf>,
+}
+
+impl TaxonomyOptions {
+    /// Checks the URL patterns and slug overrides.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first invalid setting.
+    pub fn validate(&self) -> Result<()> {
+        for (name, pattern, tokens) in [
+            ("url", &self.url, &[":term"][..]),
+            ("page_url", &self.page_url, &[":term", ":page"][..]),
+        ] {
+            if let Some(pattern) = pattern {
+                if !pattern.starts_with('/') {
+                    bail!("{} '{}' must start with '/'", name, pattern);
+                }
+                if pattern.split('/').any(|s| s == "." || s == "..") {
+                    bail!(
+                        "{} '{}' must not contain '.' or '..' segments",
+                        name,
+                        pattern
+                    );
+                }
+                if let Some(token) = tokens
+                    .iter()
+                    .find(|token| !pattern.contains(*token))
+                {
+                    bail!(
+                        "{} '{}' must contain {}",
+                        name,
+                        pattern,
+                        token
+                    );
+                }
+            }
+        }
+        for (term, slug) in &self.slugs {
+            validate_slug(term, slug)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the frontmatter key of the terms of taxonomy `name`.
+    pub fn key<'a>(&'a self, name: &'a str) -> &'a str {
+        self.key.as_deref().unwrap_or(name)
+    }
+
+    /// Returns the URL of page `page` (from 1) of a term.
+    pub fn term_url(
+        &self,
+        name: &str,
+        slug: &str,
+        page: usize,
+    ) -> String {
+        let url = self
+            .url
+            .clone()
+            .unwrap_or_else(|| format!("/{}/:term/", name));
+        if page <= 1 {
+            return url.replace(":term", slug);
+        }
+        let pattern = self.page_url.clone().unwrap_or_else(|| {
+            format!("{}/page/:page/", url.trim_end_matches('/'))
+        });
+        pattern
+            .replace(":term", slug)
+            .replace(":page", &page.to_string())
+    }
+
+    /// Returns the slug overrides from `slugs_file` and `slugs`, the
+    /// inline ones taking precedence.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data file cannot be read or parsed, or a
+    /// slug is not a single URL segment.
+    pub fn load_slugs(&self) -> Result<BTreeMap<String, String>> {
+        let mut slugs = match &self.slugs_file {
+            Some(path) => load_slugs_file(path)?,
+            None => BTreeMap::new(),
+        };
+        slugs.extend(self.slugs.clone());
+        for (term, slug) in &slugs {
+            validate_slug(term, slug)?;
+        }
+        Ok(slugs)
+    }
+}
+
+/// A page listed under a term.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TermPage {
+    /// Page title.
+    pub title: String,
+    /// Site-relative URL of the page.
+    pub url: String,
+    /// Page description, if any.
+    pub description: Option<String>,
+    /// The page's `date` frontmatter, if any.
+    pub date: Option<String>,
+}
+
+/// A term with the pages using it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    /// The term as written in the first page using it.
+    pub name: String,
+    /// URL slug of the term.
+    pub slug: String,
+    /// Pages using the term, newest first.
+    pub pages: Vec<TermPage>,
+}
+
+/// The terms of one taxonomy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Taxonomy {
+    /// Taxonomy name, e.g. `tags`.
+    pub name: String,
+    /// Terms sorted by slug.
+    pub terms: Vec<Term>,
+}
+
+impl Taxonomy {
+    /// Collects the terms of taxonomy `name` from the non-draft pages of
+    /// `content`, slugged with `slugs` where it has an entry.
+    pub fn build(
+        name: &str,
+        options: &TaxonomyOptions,
+        content: &ContentSet,
+        slugs: &BTreeMap<String, String>,
+    ) -> Self {
+        let mut terms: BTreeMap<String, Term> = BTreeMap::new();
+        for page in content.iter() {
+            if page.frontmatter.get_bool("draft") == Some(true) {
+                continue;
+            }
+            let mut seen = HashSet::new();
+            for term in page.frontmatter.get_list(options.key(name)) {
+                let slug = slugs
+                    .get(&term)
+                    .cloned()
+                    .unwrap_or_else(|| slug_from(&term));
+                if slug.is_empty() || !seen.insert(slug.clone()) {
+                    continue;
+                }
+                terms
+                    .entry(slug.clone())
+                    .or_insert_with(|| Term {
+                        name: term,
+                        slug,
+                        pages: Vec::new(),
+                    })
+                    .pages
+                    .push(term_page(page));
+            }
+        }
+
+        let mut terms: Vec<Term> = terms.into_values().collect();
+        for term in &mut terms {
+            term.pages.sort_by(|a, b| {
+                b.date
+                    .is_some()
+                    .cmp(&a.date.is_some())
+                    .then_with(|| b.date.cmp(&a.date))
+                    .then_with(|| a.url.cmp(&b.url))
+            });
+        }
+        Self {
+            name: name.to_string(),
+            terms,
+        }
+    }
+}
+
+/// Builds every taxonomy in `taxonomies` and writes its term pages to
+/// `site_dir`, rendered with `backend` when it has the
+/// [`TAXONOMY_TEMPLATE`].
+///
+/// Returns the written paths relative to `site_dir`.
+///
+/// # Errors
+///
+/// Returns an error if slugs cannot be loaded, a term page would replace
+/// a content page or another term page, or a page cannot be rendered or
+/// written.
+pub fn write_taxonomies(
+    content: &ContentSet,
+    taxonomies: &BTreeMap<String, TaxonomyOptions>,
+    backend: &dyn TemplateBackend,
+    site_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let mut taken: HashSet<String> =
+        content.iter().map(Page::url_path).collect();
+    let mut written = Vec::new();
+
+    for (name, options) in taxonomies {
+        let slugs = options.load_slugs().with_context(|| {
+            format!("Invalid slugs for taxonomy {}", name)
+        })?;
+        let taxonomy = Taxonomy::build(name, options, content, &slugs);
+        for term in &taxonomy.terms {
+            let per_page = match options.paginate {
+                0 => term.pages.len().max(1),
+                n => n,
+            };
+            let chunks: Vec<&[TermPage]> = if term.pages.is_empty() {
+                vec![&[]]
+            } else {
+                term.pages.chunks(per_page).collect()
+            };
+            let total_pages = chunks.len();
+
+            for (i, pages) in chunks.into_iter().enumerate() {
+                let number = i + 1;
+                let url = options.term_url(name, &term.slug, number);
+                if !taken.insert(url.clone()) {
+                    bail!(
+                        "Term page {} of {} '{}' clashes with another page",
+                        url,
+                        name,
+                        term.name
+                    );
+                }
+                let context = json!({
+                    "taxonomy": name,
+                    "term": term.name,
+                    "slug": term.slug,
+                    "url": url,
+                    "pages": pages,
+                    "pagination": {
+                        "page": number,
+                        "total_pages": total_pages,
+                        "total_items": term.pages.len(),
+                        "prev_url": (number > 1).then(|| {
+                            options.term_url(name, &term.slug, number - 1)
+                        }),
+                        "next_url": (number < total_pages).then(|| {
+                            options.term_url(name, &term.slug, number + 1)
+                        }),
+                    },
+                });
+                let html = if backend.has_template(TAXONOMY_TEMPLATE) {
+                    backend.render(TAXONOMY_TEMPLATE, &context)?
+                } else {
+                    term_html(&context)
+                };
+
+                let relative = output_path(&url);
+                let target = site_dir.join(&relative);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create {}", parent.display())
+                    })?;
+                }
+                fs::write(&target, html).with_context(|| {
+                    format!("Failed to write {}", target.display())
+                })?;
+                written.push(relative);
+            }
+        }
+    }
+    Ok(written)
+}
+
+/// Renders the plain term page used without a `taxonomy.html` template.
+fn term_html(context: &Value) -> String {
+    let text =
+        |value: &Value| escape_html(value.as_str().unwrap_or(""));
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{term}</title>\n</head>\n<body>\n<main>\n<h1>{term}</h1>\n<ul>\n",
+        term = text(&context["term"]),
+    );
+    for page in context["pages"].as_array().into_iter().flatten() {
+        let _ = writeln!(
+            html,
+            "<li><a href=\"{}\">{}</a></li>",
+            text(&page["url"]),
+            text(&page["title"])
+        );
+    }
+    html.push_str("</ul>\n");
+
+    let pagination = &context["pagination"];
+    if pagination["total_pages"].as_u64().unwrap_or(1) > 1 {
+        html.push_str("<nav aria-label=\"Pagination\">\n");
+        if let Some(prev) = pagination["prev_url"].as_str() {
+            let _ = writeln!(
+                html,
+                "<a rel=\"prev\" href=\"{}\">Previous</a>",
+                escape_html(prev)
+            );
+        }
+        if let Some(next) = pagination["next_url"].as_str() {
+            let _ = writeln!(
+                html,
+                "<a rel=\"next\" href=\"{}\">Next</a>",
+                escape_html(next)
+            );
+        }
+        html.push_str("</nav>\n");
+    }
+    html.push_str("</main>\n</body>\n</html>\n");
+    html
+}
+
+fn term_page(page: &Page) -> TermPage {
+    TermPage {
+        title: page.title(),
+        url: page.url_path(),
+        description: page.description(),
+        date: page.frontmatter.get_str("date"),
+    }
+}
+
+/// Returns the file a term page URL is written to.
+fn output_path(url: &str) -> PathBuf {
+    let path = PathBuf::from(url.trim_start_matches('/'));
+    if url.ends_with('/') {
+        path.join("index.html")
+    } else {
+        path
+    }
+}
+
+fn load_slugs_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    let source = fs::read_to_string(path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    let slugs = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => {
+            serde_json::from_str(&source).map_err(anyhow::Error::from)
+        }
+        Some("toml") => {
+            toml::from_str(&source).map_err(anyhow::Error::from)
+        }
+        _ => serde_yaml::from_str(&source).map_err(anyhow::Error::from),
+    };
+    slugs.with_context(|| {
+        format!("Invalid slug data file {}", path.display())
+    })
+}
+
+fn validate_slug(term: &str, slug: &str) -> Result<()> {
+    if slug.is_empty()
+        || slug == "."
+        || slug == ".."
+        || slug.contains(['/', '\\'])
+    {
+        bail!("Invalid slug '{}' for term '{}'", slug, term);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::NativeBackend;
+    use tempfile::tempdir;
+
+    fn content() -> ContentSet {
+        let page = |path: &str, frontmatter: &str| {
+            Page::from_source(
+                PathBuf::from(path),
+                PathBuf::from(path),
+                &format!("---\n{}\n---\n", frontmatter),
+            )
+            .unwrap()
+        };
+        ContentSet {
+            root: PathBuf::from("content"),
+            pages: vec![
+                page(
+                    "a.md",
+                    "title: A\ndate: 2025-01-01\ntags: [Rust, Café]",
+                ),
+                page(
+                    "b.md",
+                    "title: B\ndate: 2025-02-01\ntags: rust, web",
+                ),
+                page(
+                    "c.md",
+                    "title: C\ndate: 2025-03-01\ntags: [Rust]",
+                ),
+                page("d.md", "title: D\ntags: [Rust]\ndraft: true"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_build_terms() {
+        let options = TaxonomyOptions::default();
+        let slugs = [("web".to_string(), "web-dev".to_string())]
+            .into_iter()
+            .collect();
+        let taxonomy =
+            Taxonomy::build("tags", &options, &content(), &slugs);
+
+        let terms: Vec<_> = taxonomy
+            .terms
+            .iter()
+            .map(|term| {
+                (
+                    term.name.as_str(),
+                    term.slug.as_str(),
+                    term.pages.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            terms,
+            vec![
+                ("Café", "cafe", 1),
+                ("Rust", "rust", 3),
+                ("web", "web-dev", 1)
+            ]
+        );
+        let titles: Vec<_> = taxonomy.terms[1]
+            .pages
+            .iter()
+            .map(|page| page.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["C", "B", "A"]);
+    }
+
+    #[test]
+    fn test_term_urls() {
+        let options = TaxonomyOptions::default();
+        assert_eq!(options.term_url("tags", "rust", 1), "/tags/rust/");
+        assert_eq!(
+            options.term_url("tags", "rust", 2),
+            "/tags/rust/page/2/"
+        );
+
+        let options = TaxonomyOptions {
+            url: Some("/topics/:term.html".into()),
+            page_url: Some("/topics/:term-:page.html".into()),
+            ..TaxonomyOptions::default()
+        };
+        assert_eq!(
+            options.term_url("tags", "rust", 1),
+            "/topics/rust.html"
+        );
+        assert_eq!(
+            options.term_url("tags", "rust", 3),
+            "/topics/rust-3.html"
+        );
+    }
+
+    #[test]
+    fn test_write_paginated_terms() -> Result<()> {
+        let site = tempdir()?;
+        let slugs = site.path().join("slugs.yml");
+        fs::write(&slugs, "Café: coffee\n")?;
+        let taxonomies = [(
+            "tags".to_string(),
+            TaxonomyOptions {
+                url: Some("/topics/:term/".into()),
+                paginate: 2,
+                slugs_file: Some(slugs),
+                ..TaxonomyOptions::default()
+            },
+        )]
+        .into_iter()
+        .collect();
+
+        let mut backend = NativeBackend::default();
+        let written = write_taxonomies(
+            &content(),
+            &taxonomies,
+            &backend,
+            site.path(),
+        )?;
+        assert_eq!(
+            written,
+            vec![
+                PathBuf::from("topics/coffee/index.html"),
+                PathBuf::from("topics/rust/index.html"),
+                PathBuf::from("topics/rust/page/2/index.html"),
+                PathBuf::from("topics/web/index.html"),
+            ]
+        );
+        let first = fs::read_to_string(
+            site.path().join("topics/rust/index.html"),
+        )?;
+        assert!(first.contains("<a href=\"/c/\">C</a>"));
+        assert!(
+            first.contains("href=\"/topics/rust/page/2/\">Next</a>")
+        );
+        assert!(!first.contains("/a/"));
+
+        backend.add_template(
+            TAXONOMY_TEMPLATE,
+            "{{ term }} {{ pagination.page }}/{{ pagination.total_pages }}",
+        )?;
+        let _ = write_taxonomies(
+            &content(),
+            &taxonomies,
+            &backend,
+            site.path(),
+        )?;
+        assert_eq!(
+            fs::read_to_string(
+                site.path().join("topics/rust/page/2/index.html")
+            )?,
+            "Rust 2/2"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_term_page_clashing_with_content() {
+        let taxonomies = [(
+            "tags".to_string(),
+            TaxonomyOptions {
+                url: Some("/:term/".into()),
+                ..TaxonomyOptions::default()
+            },
+        )]
+        .into_iter()
+        .collect();
+        let mut content = content();
+        content.pages[0].frontmatter.insert("tags", json!(["b"]));
+        let site = tempdir().unwrap();
+        let error = write_taxonomies(
+            &content,
+            &taxonomies,
+            &NativeBackend::default(),
+            site.path(),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("/b/"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(TaxonomyOptions::default().validate().is_ok());
+        for options in [
+            TaxonomyOptions {
+                url: Some("topics/:term/".into()),
+                ..TaxonomyOptions::default()
+            },
+            TaxonomyOptions {
+                url: Some("/topics/".into()),
+                ..TaxonomyOptions::default()
+            },
+            TaxonomyOptions {
+                page_url: Some("/topics/:term/".into()),
+                ..TaxonomyOptions::default()
+            },
+            TaxonomyOptions {
+                slugs: [("a".to_string(), "../x".to_string())]
+                    .into_iter()
+                    .collect(),
+                ..TaxonomyOptions::default()
+            },
+        ] {
+            assert!(options.validate().is_err(), "{:?}", options);
+        }
+    }
+}
@@ -0,0 +1,1686 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Template Engine
+//!
+//! Renders `{{ ... }}` tags against a JSON context. A tag holds a
+//! variable, a literal or a function call, followed by any number of
+//! filters:
+//!
+//! ```text
+//! <h1>{{ title | upper }}</h1>
+//! <a href="/tags/{{ tag | slugify }}/">{{ tag }}</a>
+//! <time>{{ date | date("[month repr:long] [day], [year]") }}</time>
+//! <p>{{ description | default("No description") | truncate(80) }}</p>
+//! ```
+//!
+//! Variables use dotted paths (`page.author.name`, `tags.0`), whose
+//! names may hold hyphens (`theme-color`). A variable with no value is
+//! an error unless it is followed by `default`.
+//!
+//! Filters and functions are plain closures over [`serde_json::Value`],
+//! so downstream users can extend rendering with
+//! [`Engine::register_filter`] and [`Engine::register_function`]. The
+//! built-in filters are:
+//!
+//! | Filter | Description |
+//! |--------|-------------|
+//! | `date(format)` | Formats an RFC 3339 or `YYYY-MM-DD` date with a `time` format description. |
+//! | `default(value)` | Replaces a missing or empty value. |
+//! | `escape` | Escapes HTML special characters. |
+//! | `join(separator)` | Joins a list. |
+//! | `length` | Length of a string, list or object. |
+//! | `lower`, `upper`, `trim` | Case and whitespace helpers. |
+//! | `markdown` | Renders Markdown to HTML. |
+//! | `slugify` | Turns text into a URL slug. |
+//! | `truncate(length, suffix)` | Shortens text at a word boundary. |
+//! | `urlencode` | Percent-encodes text for use in a URL. |
+//!
+//! Built-in functions are `now(format)` and `concat(values...)`.
+//! Sites with a `[fetch]` allow list also get `fetch_json(url, path)`;
+//! see [`crate::fetch`]. Sites with translation catalogs get
+//! `t(key, language)`; see [`crate::i18n`].
+//!
+//! Globals set with [`Engine::set_global`] are visible to every
+//! template and macro, unless the context has a value at the same path.
+//! The site's data files are the `data` global; see [`crate::data`].
+//! Facts about the build are the `build` and `env` globals; see
+//! [`crate::build_info`].
+//!
+//! Macros are reusable fragments with parameters. A macro only sees its
+//! arguments, and arguments left out are `null`:
+//!
+//! ```text
+//! {% macro button(text, url) %}<a class="button" href="{{ url }}">{{ text }}</a>{% endmacro %}
+//! {{ button("Read more", page.url) }}
+//! ```
+//!
+//! Templates rendered by [`NativeBackend`] can import the macros of
+//! another template under a namespace, called as `ui.button(...)` or,
+//! as in Tera, `ui::button(...)`:
+//!
+//! ```text
+//! {% import "macros/ui.html" as ui %}
+//! {{ ui.button("Subscribe", "/newsletter/") }}
+//! ```
+//!
+//! The line break after a `{% ... %}` tag is dropped, so files of macro
+//! definitions do not leave blank lines behind.
+//!
+//! Named templates are rendered through the [`TemplateBackend`] trait.
+//! [`NativeBackend`] uses this engine; enabling the `tera` feature adds
+//! [`tera::TeraBackend`] so themes written for Tera can be reused.
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use dtt::datetime::DateTime;
+use once_cell::sync::Lazy;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use pulldown_cmark::{html, Options, Parser};
+use regex::Regex;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{self, Write as _},
+    path::Path,
+};
+
+use crate::{
+    diagnostics::{self, Severity},
+    markdown::{self, ExtensionOptions},
+    vfs::{RealFs, Vfs},
+};
+
+/// Adapter rendering Tera templates through [`TemplateBackend`].
+#[cfg(feature = "tera")]
+pub mod tera;
+
+/// Date format used by `date` and `now` when none is given.
+pub const DEFAULT_DATE_FORMAT: &str = "[year]-[month]-[day]";
+
+/// Suffix appended by `truncate` when none is given.
+const DEFAULT_TRUNCATE_SUFFIX: &str = "…";
+
+/// Maximum depth of nested macro calls, which stops runaway recursion.
+const MAX_MACRO_DEPTH: usize = 32;
+
+/// Matches a `{% macro name(params) %}` tag.
+static MACRO_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^macro\s+([A-Za-z_][A-Za-z0-9_]*)\s*\(([^)]*)\)$")
+        .unwrap_or_else(|e| panic!("Invalid macro regex: {}", e))
+});
+
+/// Matches a `{% import "file" as name %}` tag.
+static IMPORT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"^import\s+["']([^"']+)["']\s+as\s+([A-Za-z_][A-Za-z0-9_]*)$"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid import regex: {}", e))
+});
+
+/// A filter: receives the piped value and the filter arguments.
+pub type Filter =
+    Box<dyn Fn(&Value, &[Value]) -> Result<Value> + Send + Sync>;
+
+/// A function: receives the call arguments.
+pub type Function =
+    Box<dyn Fn(&[Value]) -> Result<Value> + Send + Sync>;
+
+/// A template engine with registered filters, functions and globals.
+pub struct Engine {
+    filters: HashMap<String, Filter>,
+    functions: HashMap<String, Function>,
+    globals: Map<String, Value>,
+    states: BTreeMap<String, String>,
+}
+
+impl fmt::Debug for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut filters: Vec<_> = self.filters.keys().collect();
+        let mut functions: Vec<_> = self.functions.keys().collect();
+        filters.sort();
+        functions.sort();
+        let globals: Vec<_> = self.globals.keys().collect();
+        f.debug_struct("Engine")
+            .field("filters", &filters)
+            .field("functions", &functions)
+            .field("globals", &globals)
+            .finish()
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    /// Creates an engine with the built-in filters and functions.
+    pub fn new() -> Self {
+        let mut engine = Self::empty();
+        engine.register_filter("date", |value, args| {
+            let format =
+                str_arg(args, 0).unwrap_or(DEFAULT_DATE_FORMAT);
+            format_date(&to_text(value), format).map(Value::String)
+        });
+        engine.register_filter("default", |value, args| {
+            Ok(if is_empty(value) {
+                args.first().cloned().unwrap_or(Value::Null)
+            } else {
+                value.clone()
+            })
+        });
+        engine.register_filter("escape", |value, _| {
+            Ok(Value::String(escape_html(&to_text(value))))
+        });
+        engine.register_filter("join", |value, args| {
+            let separator = str_arg(args, 0).unwrap_or(", ");
+            let items = value
+                .as_array()
+                .ok_or_else(|| anyhow!("`join` expects a list"))?;
+            Ok(Value::String(
+                items
+                    .iter()
+                    .map(to_text)
+                    .collect::<Vec<_>>()
+                    .join(separator),
+            ))
+        });
+        engine.register_filter("length", |value, _| {
+            let length = match value {
+                Value::String(text) => text.chars().count(),
+                Value::Array(items) => items.len(),
+                Value::Object(map) => map.len(),
+                Value::Null => 0,
+                _ => bail!("`length` expects a string, list or object"),
+            };
+            Ok(Value::from(length))
+        });
+        engine.register_filter("lower", |value, _| {
+            Ok(Value::String(to_text(value).to_lowercase()))
+        });
+        engine.register_filter("markdown", |value, _| {
+            Ok(Value::String(render_markdown(&to_text(value))))
+        });
+        engine.register_filter("slugify", |value, _| {
+            Ok(Value::String(slugify(&to_text(value))))
+        });
+        engine.register_filter("trim", |value, _| {
+            Ok(Value::String(to_text(value).trim().to_string()))
+        });
+        engine.register_filter("truncate", |value, args| {
+            let length =
+                args.first().and_then(Value::as_u64).ok_or_else(
+                    || anyhow!("`truncate` expects a length"),
+                )?;
+            let suffix =
+                str_arg(args, 1).unwrap_or(DEFAULT_TRUNCATE_SUFFIX);
+            Ok(Value::String(truncate(
+                &to_text(value),
+                length as usize,
+                suffix,
+            )))
+        });
+        engine.register_filter("upper", |value, _| {
+            Ok(Value::String(to_text(value).to_uppercase()))
+        });
+        engine.register_filter("urlencode", |value, _| {
+            Ok(Value::String(
+                utf8_percent_encode(&to_text(value), NON_ALPHANUMERIC)
+                    .to_string(),
+            ))
+        });
+
+        engine.register_function("concat", |args| {
+            Ok(Value::String(args.iter().map(to_text).collect()))
+        });
+        engine.register_function("now", |args| {
+            let format =
+                str_arg(args, 0).unwrap_or(DEFAULT_DATE_FORMAT);
+            DateTime::new()
+                .format(format)
+                .map(Value::String)
+                .map_err(|e| anyhow!("Invalid date format: {}", e))
+        });
+        engine
+    }
+
+    /// Creates an engine without any filters or functions.
+    pub fn empty() -> Self {
+        Self {
+            filters: HashMap::new(),
+            functions: HashMap::new(),
+            globals: Map::new(),
+            states: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a filter, replacing any filter with the same name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serde_json::{json, Value};
+    /// use ssg::template::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.register_filter("reverse", |value, _| {
+    ///     let text = value.as_str().unwrap_or_default();
+    ///     Ok(Value::String(text.chars().rev().collect()))
+    /// });
+    ///
+    /// let html = engine.render("{{ name | reverse }}", &json!({ "name": "abc" }));
+    /// assert_eq!(html.unwrap(), "cba");
+    /// ```
+    pub fn register_filter<F>(&mut self, name: &str, filter: F)
+    where
+        F: Fn(&Value, &[Value]) -> Result<Value>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let _ = self.filters.insert(name.to_string(), Box::new(filter));
+    }
+
+    /// Registers a function, replacing any function with the same name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serde_json::{json, Value};
+    /// use ssg::template::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.register_function("year", |_| Ok(Value::from(2025)));
+    ///
+    /// let html = engine.render("© {{ year() }}", &json!({}));
+    /// assert_eq!(html.unwrap(), "© 2025");
+    /// ```
+    pub fn register_function<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value> + Send + Sync + 'static,
+    {
+        let _ =
+            self.functions.insert(name.to_string(), Box::new(function));
+        let _ = self.states.remove(name);
+    }
+
+    /// Sets a fingerprint of the state the function `name` reads, such
+    /// as the catalogs behind `t`, so templates calling it
+    /// [fingerprint](Self::fingerprint) differently when it changes.
+    pub fn set_function_state(&mut self, name: &str, state: String) {
+        let _ = self.states.insert(name.to_string(), state);
+    }
+
+    /// Sets the global variable `name`, replacing any global with the
+    /// same name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serde_json::json;
+    /// use ssg::template::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_global("site", json!({ "name": "Shokunin" }));
+    ///
+    /// let html = engine.render("{{ site.name }}", &json!({}));
+    /// assert_eq!(html.unwrap(), "Shokunin");
+    /// ```
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        let _ = self.globals.insert(name.to_string(), value);
+    }
+
+    /// Returns the value of the global at a dotted path.
+    fn global(&self, path: &str) -> Option<&Value> {
+        match path.split_once('.') {
+            Some((name, rest)) => lookup(self.globals.get(name)?, rest),
+            None => self.globals.get(path),
+        }
+    }
+
+    /// Returns `true` if a filter named `name` is registered.
+    pub fn has_filter(&self, name: &str) -> bool {
+        self.filters.contains_key(name)
+    }
+
+    /// Returns `true` if a function named `name` is registered.
+    pub fn has_function(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    /// Renders `source` with `context`.
+    ///
+    /// # Errors
+    ///
+    /// See [`Engine::render_file`].
+    pub fn render(
+        &self,
+        source: &str,
+        context: &Value,
+    ) -> Result<String> {
+        self.render_file(Path::new("<template>"), source, context)
+    }
+
+    /// Renders the template `source` read from `file` with `context`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a tag is malformed, refers to an unknown
+    /// filter, function or macro, or a filter, function or macro fails.
+    /// Unknown variables are reported as a [`diagnostics::Diagnostic`]
+    /// pointing at the tag. Imports fail, as there are no other
+    /// templates to import from.
+    pub fn render_file(
+        &self,
+        file: &Path,
+        source: &str,
+        context: &Value,
+    ) -> Result<String> {
+        self.render_with_templates(
+            file,
+            source,
+            context,
+            &HashMap::new(),
+        )
+    }
+
+    /// Renders `source` with the macros it defines and those it imports
+    /// from `templates`.
+    fn render_with_templates(
+        &self,
+        file: &Path,
+        source: &str,
+        context: &Value,
+        templates: &HashMap<String, String>,
+    ) -> Result<String> {
+        let mut scopes = Scopes::default();
+        let (text, scope) = scopes
+            .load(source, templates, &mut Vec::new())
+            .with_context(|| {
+                format!("Invalid template {}", file.display())
+            })?;
+        self.render_text(
+            file,
+            &text,
+            context,
+            Frame {
+                scopes: &scopes,
+                scope,
+                depth: 0,
+            },
+        )
+    }
+
+    /// Renders the `{{ ... }}` tags of `source`.
+    fn render_text(
+        &self,
+        file: &Path,
+        source: &str,
+        context: &Value,
+        frame: Frame<'_>,
+    ) -> Result<String> {
+        let mut output = String::with_capacity(source.len());
+        let mut rest = source;
+        while let Some(start) = rest.find("{{") {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after.find("}}").ok_or_else(|| {
+                anyhow!("Unclosed `{{{{` in {}", file.display())
+            })?;
+            let tag = after[..end].trim();
+            let value = self
+                .evaluate(tag, context, frame)
+                .map_err(|error| match error {
+                    EvalError::UnknownVariable(name) => {
+                        let mut diagnostic =
+                            diagnostics::unknown_template_variable(
+                                file,
+                                source,
+                                &name,
+                                &known_variables(context),
+                            );
+                        diagnostic.severity = Severity::Error;
+                        anyhow::Error::new(diagnostic)
+                    }
+                    EvalError::Other(error) => error,
+                })
+                .with_context(|| {
+                    format!(
+                        "Failed to render `{{{{ {} }}}}` in {}",
+                        tag,
+                        file.display()
+                    )
+                })?;
+            output.push_str(&to_text(&value));
+            rest = &after[end + 2..];
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+
+    /// Returns a hash of the parsed form of `source`.
+    ///
+    /// Whitespace inside tags does not change the hash, so `{{title}}`
+    /// and `{{ title }}` fingerprint the same. Used as the template half
+    /// of a render cache key, so the globals the template reads and the
+    /// [state](Self::set_function_state) of the functions it calls are
+    /// hashed too: changing the site data a template reads invalidates
+    /// its cached renders, while templates that do not read it keep
+    /// theirs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a tag is unclosed or cannot be tokenized.
+    pub fn fingerprint(&self, source: &str) -> Result<String> {
+        let mut hasher = Sha256::new();
+        let mut names = BTreeSet::new();
+        let mut rest = source;
+        while let Some(start) = rest.find("{{") {
+            hasher.update(b"T");
+            hasher.update(&rest.as_bytes()[..start]);
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| anyhow!("Unclosed `{{{{`"))?;
+            let tokens = tokenize(after[..end].trim())?;
+            hasher.update(b"E");
+            hasher.update(format!("{:?}", tokens).as_bytes());
+            for token in tokens {
+                if let Token::Ident(path) = token {
+                    let name =
+                        path.split('.').next().unwrap_or_default();
+                    let _ = names.insert(name.to_string());
+                }
+            }
+            rest = &after[end + 2..];
+        }
+        hasher.update(b"T");
+        hasher.update(rest.as_bytes());
+        for name in &names {
+            if let Some(value) = self.globals.get(name) {
+                hasher.update(b"G");
+                hasher.update(name.as_bytes());
+                hasher.update(serde_json::to_vec(value)?);
+            }
+            if let Some(state) = self.states.get(name) {
+                hasher.update(b"F");
+                hasher.update(name.as_bytes());
+                hasher.update(state.as_bytes());
+            }
+        }
+        Ok(hex(&hasher.finalize()))
+    }
+
+    /// Evaluates the expression inside a `{{ ... }}` tag.
+    fn evaluate(
+        &self,
+        expression: &str,
+        context: &Value,
+        frame: Frame<'_>,
+    ) -> Result<Value, EvalError> {
+        let tokens = tokenize(expression)?;
+        let mut parser = TokenParser {
+            tokens,
+            position: 0,
+        };
+        let (head, filters) = parser.expression()?;
+
+        let mut value = match self.term(&head, context, frame)? {
+            Some(value) => value,
+            None => match (&head, filters.first()) {
+                (_, Some((name, _))) if name == "default" => {
+                    Value::Null
+                }
+                (Term::Variable(name), _) => {
+                    return Err(EvalError::UnknownVariable(
+                        name.clone(),
+                    ))
+                }
+                _ => Value::Null,
+            },
+        };
+        for (name, args) in &filters {
+            let filter = self
+                .filters
+                .get(name)
+                .ok_or_else(|| anyhow!("unknown filter `{}`", name))?;
+            let args = args
+                .iter()
+                .map(|arg| self.argument(arg, context, frame))
+                .collect::<Result<Vec<_>, _>>()?;
+            value = filter(&value, &args)
+                .with_context(|| format!("filter `{}` failed", name))?;
+        }
+        Ok(value)
+    }
+
+    /// Evaluates a term; `None` means a variable without a value.
+    fn term(
+        &self,
+        term: &Term,
+        context: &Value,
+        frame: Frame<'_>,
+    ) -> Result<Option<Value>, EvalError> {
+        match term {
+            Term::Literal(value) => Ok(Some(value.clone())),
+            Term::Variable(path) => Ok(lookup(context, path)
+                .or_else(|| self.global(path))
+                .cloned()),
+            Term::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.argument(arg, context, frame))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if let Some(definition) = frame.lookup(name) {
+                    return Ok(Some(Value::String(self.call_macro(
+                        name, definition, &args, frame,
+                    )?)));
+                }
+                let function =
+                    self.functions.get(name).ok_or_else(|| {
+                        anyhow!("unknown function or macro `{}`", name)
+                    })?;
+                Ok(Some(function(&args).with_context(|| {
+                    format!("function `{}` failed", name)
+                })?))
+            }
+        }
+    }
+
+    /// Evaluates a filter or function argument.
+    fn argument(
+        &self,
+        term: &Term,
+        context: &Value,
+        frame: Frame<'_>,
+    ) -> Result<Value, EvalError> {
+        match self.term(term, context, frame)? {
+            Some(value) => Ok(value),
+            None => match term {
+                Term::Variable(name) => {
+                    Err(EvalError::UnknownVariable(name.clone()))
+                }
+                _ => Ok(Value::Null),
+            },
+        }
+    }
+
+    /// Renders the body of the macro `name` with `args` bound to its
+    /// parameters.
+    fn call_macro(
+        &self,
+        name: &str,
+        definition: &Macro,
+        args: &[Value],
+        frame: Frame<'_>,
+    ) -> Result<String> {
+        ensure!(
+            args.len() <= definition.params.len(),
+            "macro `{}` takes {} arguments, got {}",
+            name,
+            definition.params.len(),
+            args.len()
+        );
+        ensure!(
+            frame.depth < MAX_MACRO_DEPTH,
+            "macro `{}` is nested more than {} calls deep",
+            name,
+            MAX_MACRO_DEPTH
+        );
+        let context: Map<String, Value> = definition
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, param)| {
+                (
+                    param.clone(),
+                    args.get(i).cloned().unwrap_or(Value::Null),
+                )
+            })
+            .collect();
+        self.render_text(
+            Path::new(&format!("macro `{}`", name)),
+            &definition.body,
+            &Value::Object(context),
+            Frame {
+                scopes: frame.scopes,
+                scope: definition.scope,
+                depth: frame.depth + 1,
+            },
+        )
+    }
+}
+
+/// A template engine that renders named templates.
+///
+/// Implemented by [`NativeBackend`] and, with the `tera` feature, by
+/// [`tera::TeraBackend`], so themes written for another engine can be
+/// rendered without changes to the rest of the build.
+pub trait TemplateBackend: Send + Sync {
+    /// Short name of the backend, such as `native` or `tera`.
+    fn name(&self) -> &'static str;
+
+    /// Adds or replaces the template `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend rejects the template source.
+    fn add_template(&mut self, name: &str, source: &str) -> Result<()>;
+
+    /// Returns `true` if a template named `name` was added.
+    fn has_template(&self, name: &str) -> bool;
+
+    /// Renders the template `name` with `context`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template does not exist or fails to
+    /// render.
+    fn render(&self, name: &str, context: &Value) -> Result<String>;
+
+    /// Returns a hash of the compiled form of template `name`, if the
+    /// backend can produce one.
+    ///
+    /// Render caches use it to tell whether a template changed. Backends
+    /// returning `None` are treated as changed whenever any template
+    /// source changes.
+    fn fingerprint(&self, name: &str) -> Option<String> {
+        let _ = name;
+        None
+    }
+
+    /// Renders a page body from Markdown to HTML.
+    ///
+    /// The default renders with every [Markdown
+    /// extension](crate::markdown::ExtensionOptions) on.
+    fn render_markdown(&self, markdown: &str) -> String {
+        render_markdown(markdown)
+    }
+
+    /// Adds every `.html` file below `dir`, named by its path relative
+    /// to `dir` with `/` separators (`partials/header.html`).
+    ///
+    /// Returns the number of templates added.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read or is rejected.
+    fn add_dir(&mut self, dir: &Path) -> Result<usize> {
+        self.add_dir_from(&RealFs, dir)
+    }
+
+    /// Adds every `.html` file below `dir` in the file system `vfs`,
+    /// like [`add_dir`](Self::add_dir).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read or is rejected.
+    fn add_dir_from(
+        &mut self,
+        vfs: &dyn Vfs,
+        dir: &Path,
+    ) -> Result<usize> {
+        let templates = read_templates(vfs, dir)?;
+        let count = templates.len();
+        for (name, source) in templates {
+            self.add_template(&name, &source)?;
+        }
+        Ok(count)
+    }
+}
+
+/// The built-in backend: named templates rendered by an [`Engine`].
+#[derive(Debug, Default)]
+pub struct NativeBackend {
+    engine: Engine,
+    templates: HashMap<String, String>,
+    extensions: ExtensionOptions,
+}
+
+impl NativeBackend {
+    /// Creates a backend rendering with `engine`.
+    pub fn new(engine: Engine) -> Self {
+        Self {
+            engine,
+            templates: HashMap::new(),
+            extensions: ExtensionOptions::default(),
+        }
+    }
+
+    /// Renders page bodies and the `markdown` filter with the Markdown
+    /// `extensions`.
+    pub fn set_markdown_extensions(
+        &mut self,
+        extensions: ExtensionOptions,
+    ) {
+        self.extensions = extensions;
+        self.engine.register_filter("markdown", move |value, _| {
+            Ok(Value::String(render_markdown_with(
+                &to_text(value),
+                &extensions,
+            )))
+        });
+    }
+
+    /// The engine, for checking its filters and functions.
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// The engine, for registering filters and functions.
+    pub fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+}
+
+impl TemplateBackend for NativeBackend {
+    fn name(&self) -> &'static str {
+        "native"
+    }
+
+    fn add_template(&mut self, name: &str, source: &str) -> Result<()> {
+        let _ =
+            self.templates.insert(name.to_string(), source.to_string());
+        Ok(())
+    }
+
+    fn has_template(&self, name: &str) -> bool {
+        self.templates.contains_key(name)
+    }
+
+    fn render(&self, name: &str, context: &Value) -> Result<String> {
+        let source = self
+            .templates
+            .get(name)
+            .ok_or_else(|| anyhow!("Template `{}` not found", name))?;
+        self.engine.render_with_templates(
+            Path::new(name),
+            source,
+            context,
+            &self.templates,
+        )
+    }
+
+    fn fingerprint(&self, name: &str) -> Option<String> {
+        self.fingerprint_with_imports(name, &mut Vec::new())
+    }
+
+    fn render_markdown(&self, markdown: &str) -> String {
+        render_markdown_with(markdown, &self.extensions)
+    }
+}
+
+impl NativeBackend {
+    /// Fingerprints template `name` together with the templates it
+    /// imports macros from, so editing a macro changes its users too.
+    fn fingerprint_with_imports(
+        &self,
+        name: &str,
+        seen: &mut Vec<String>,
+    ) -> Option<String> {
+        let source = self.templates.get(name)?;
+        let fingerprint = self.engine.fingerprint(source).ok()?;
+        let imports = parse_blocks(source).ok()?.imports;
+        if imports.is_empty() {
+            return Some(fingerprint);
+        }
+        seen.push(name.to_string());
+        let mut hasher = Sha256::new();
+        hasher.update(fingerprint.as_bytes());
+        for (path, _) in imports {
+            if !seen.contains(&path) {
+                hasher.update(
+                    self.fingerprint_with_imports(&path, seen)?
+                        .as_bytes(),
+                );
+            }
+        }
+        Some(hex(&hasher.finalize()))
+    }
+}
+
+/// Formats bytes as lowercase hex.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
+
+/// Reads the `.html` templates below `dir` in `vfs`, sorted by name.
+pub(crate) fn read_templates(
+    vfs: &dyn Vfs,
+    dir: &Path,
+) -> Result<Vec<(String, String)>> {
+    let files = vfs.files(dir)?;
+
+    let mut templates = Vec::new();
+    for file in files {
+        if file.extension().and_then(|ext| ext.to_str()) != Some("html")
+        {
+            continue;
+        }
+        let source = vfs.read_to_string(&file)?;
+        let name = file
+            .strip_prefix(dir)
+            .unwrap_or(&file)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        templates.push((name, source));
+    }
+    Ok(templates)
+}
+
+/// Looks up a dotted path such as `page.tags.0` in `context`.
+pub(crate) fn lookup<'a>(
+    context: &'a Value,
+    path: &str,
+) -> Option<&'a Value> {
+    path.split('.').try_fold(context, |value, key| match value {
+        Value::Object(map) => map.get(key),
+        Value::Array(items) => {
+            key.parse::<usize>().ok().and_then(|i| items.get(i))
+        }
+        _ => None,
+    })
+}
+
+/// A macro defined with `{% macro %}`.
+#[derive(Debug, Clone)]
+struct Macro {
+    params: Vec<String>,
+    body: String,
+    /// Scope of the template defining the macro, whose macros the body
+    /// can call.
+    scope: usize,
+}
+
+/// Macros callable from each loaded template, by call name.
+#[derive(Debug, Default)]
+struct Scopes {
+    scopes: Vec<HashMap<String, Macro>>,
+    /// Scopes of imported templates, by template name.
+    imported: HashMap<String, usize>,
+}
+
+impl Scopes {
+    /// Loads the macros and imports of `source` into a new scope and
+    /// returns the remaining text with the scope.
+    fn load(
+        &mut self,
+        source: &str,
+        templates: &HashMap<String, String>,
+        stack: &mut Vec<String>,
+    ) -> Result<(String, usize)> {
+        let blocks = parse_blocks(source)?;
+        let scope = self.scopes.len();
+        self.scopes.push(HashMap::new());
+
+        let mut macros = HashMap::new();
+        for (path, namespace) in &blocks.imports {
+            let imported = self.import(path, templates, stack)?;
+            for (name, definition) in &self.scopes[imported] {
+                if !name.contains('.') {
+                    let _ = macros.insert(
+                        format!("{}.{}", namespace, name),
+                        definition.clone(),
+                    );
+                }
+            }
+        }
+        for (name, params, body) in blocks.macros {
+            let _ = macros.insert(
+                name,
+                Macro {
+                    params,
+                    body,
+                    scope,
+                },
+            );
+        }
+        self.scopes[scope] = macros;
+        Ok((blocks.text, scope))
+    }
+
+    /// Loads the template `path` once and returns its scope.
+    fn import(
+        &mut self,
+        path: &str,
+        templates: &HashMap<String, String>,
+        stack: &mut Vec<String>,
+    ) -> Result<usize> {
+        if let Some(&scope) = self.imported.get(path) {
+            return Ok(scope);
+        }
+        ensure!(
+            !stack.iter().any(|name| name == path),
+            "`{}` imports itself through {}",
+            path,
+            stack.join(" -> ")
+        );
+        let source = templates.get(path).ok_or_else(|| {
+            anyhow!("imported template `{}` not found", path)
+        })?;
+        stack.push(path.to_string());
+        let (_, scope) = self
+            .load(source, templates, stack)
+            .with_context(|| format!("Failed to import `{}`", path))?;
+        let _ = stack.pop();
+        let _ = self.imported.insert(path.to_string(), scope);
+        Ok(scope)
+    }
+}
+
+/// The macros in reach of the tag being rendered.
+#[derive(Debug, Clone, Copy)]
+struct Frame<'a> {
+    scopes: &'a Scopes,
+    scope: usize,
+    depth: usize,
+}
+
+impl Frame<'_> {
+    fn lookup(&self, name: &str) -> Option<&Macro> {
+        self.scopes.scopes.get(self.scope)?.get(name)
+    }
+}
+
+/// Template text with its `{% ... %}` blocks taken out.
+#[derive(Debug, Default)]
+struct Blocks {
+    text: String,
+    /// Name, parameters and body of each macro.
+    macros: Vec<(String, Vec<String>, String)>,
+    /// Template and namespace of each import.
+    imports: Vec<(String, String)>,
+}
+
+/// Splits the macro definitions and imports from the text of `source`.
+fn parse_blocks(source: &str) -> Result<Blocks> {
+    let mut blocks = Blocks::default();
+    let mut rest = source;
+    while let Some(start) = rest.find("{%") {
+        blocks.text.push_str(&rest[..start]);
+        let (tag, after) = block_tag(&rest[start..])?;
+        if let Some(caps) = MACRO_RE.captures(tag) {
+            let name = caps[1].to_string();
+            ensure!(
+                blocks
+                    .macros
+                    .iter()
+                    .all(|(defined, _, _)| *defined != name),
+                "macro `{}` is defined twice",
+                name
+            );
+            let mut params = Vec::new();
+            let list = caps[2].trim();
+            for param in list.split(',').map(str::trim) {
+                if list.is_empty() {
+                    break;
+                }
+                ensure!(
+                    is_identifier(param)
+                        && !params.iter().any(|p| p == param),
+                    "invalid parameter `{}` of macro `{}`",
+                    param,
+                    name
+                );
+                params.push(param.to_string());
+            }
+            let end = after.find("{%").ok_or_else(|| {
+                anyhow!("macro `{}` has no `{{% endmacro %}}`", name)
+            })?;
+            let (end_tag, next) = block_tag(&after[end..])?;
+            ensure!(
+                end_tag == "endmacro",
+                "`{{% {} %}}` is not allowed inside macro `{}`",
+                end_tag,
+                name
+            );
+            blocks.macros.push((
+                name,
+                params,
+                after[..end].to_string(),
+            ));
+            rest = next;
+        } else if let Some(caps) = IMPORT_RE.captures(tag) {
+            blocks
+                .imports
+                .push((caps[1].to_string(), caps[2].to_string()));
+            rest = after;
+        } else {
+            bail!("unknown tag `{{% {} %}}`", tag);
+        }
+    }
+    blocks.text.push_str(rest);
+    Ok(blocks)
+}
+
+/// Splits the `{% ... %}` tag at the start of `source` from the text
+/// after it, dropping the line break that ends the tag's line.
+fn block_tag(source: &str) -> Result<(&str, &str)> {
+    let end =
+        source.find("%}").ok_or_else(|| anyhow!("Unclosed `{{%`"))?;
+    let after = &source[end + 2..];
+    let after = after
+        .strip_prefix("\r\n")
+        .or_else(|| after.strip_prefix('\n'))
+        .unwrap_or(after);
+    Ok((source[2..end].trim(), after))
+}
+
+/// Returns `true` if `name` can name a macro parameter.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Top-level keys of `context`, used for suggestions.
+fn known_variables(context: &Value) -> Vec<&str> {
+    context
+        .as_object()
+        .map(|map| map.keys().map(String::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// Error raised while evaluating a tag.
+#[derive(Debug)]
+enum EvalError {
+    UnknownVariable(String),
+    Other(anyhow::Error),
+}
+
+impl From<anyhow::Error> for EvalError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::Other(error)
+    }
+}
+
+/// A value-producing part of an expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    Literal(Value),
+    Variable(String),
+    Call(String, Vec<Term>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Literal(Value),
+    Pipe,
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                let _ = chars.next();
+            }
+            '|' | '(' | ')' | ',' => {
+                let _ = chars.next();
+                tokens.push(match c {
+                    '|' => Token::Pipe,
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Comma,
+                });
+            }
+            '"' | '\'' => {
+                let _ = chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(ch) if ch == c => break,
+                        Some('\\') => {
+                            if let Some(escaped) = chars.next() {
+                                text.push(escaped);
+                            }
+                        }
+                        Some(ch) => text.push(ch),
+                        None => bail!("unterminated string"),
+                    }
+                }
+                tokens.push(Token::Literal(Value::String(text)));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut number = String::new();
+                while let Some(&d) = chars.peek() {
+                    if d.is_ascii_digit() || d == '.' || d == '-' {
+                        number.push(d);
+                        let _ = chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let value: Value = serde_json::from_str(&number)
+                    .map_err(|_| {
+                        anyhow!("invalid number `{}`", number)
+                    })?;
+                tokens.push(Token::Literal(value));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&d) = chars.peek() {
+                    if d.is_alphanumeric()
+                        || matches!(d, '_' | '-' | '.')
+                    {
+                        ident.push(d);
+                        let _ = chars.next();
+                    } else if d == ':' {
+                        let _ = chars.next();
+                        if chars.next() != Some(':') {
+                            bail!("expected `::` after `{}`", ident);
+                        }
+                        ident.push('.');
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(match ident.as_str() {
+                    "true" => Token::Literal(Value::Bool(true)),
+                    "false" => Token::Literal(Value::Bool(false)),
+                    "null" => Token::Literal(Value::Null),
+                    _ => Token::Ident(ident),
+                });
+            }
+            other => bail!("unexpected character `{}`", other),
+        }
+    }
+    Ok(tokens)
+}
+
+/// A filter name and its arguments.
+type FilterCall = (String, Vec<Term>);
+
+struct TokenParser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl TokenParser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn expression(&mut self) -> Result<(Term, Vec<FilterCall>)> {
+        let head = self.term()?;
+        let mut filters = Vec::new();
+        while let Some(token) = self.next() {
+            if token != Token::Pipe {
+                bail!("expected `|`, found {:?}", token);
+            }
+            let name = match self.next() {
+                Some(Token::Ident(name)) => name,
+                _ => bail!("expected a filter name after `|`"),
+            };
+            let args = if self.peek() == Some(&Token::Open) {
+                self.arguments()?
+            } else {
+                Vec::new()
+            };
+            filters.push((name, args));
+        }
+        Ok((head, filters))
+    }
+
+    fn term(&mut self) -> Result<Term> {
+        match self.next() {
+            Some(Token::Literal(value)) => Ok(Term::Literal(value)),
+            Some(Token::Ident(name)) => {
+                if self.peek() == Some(&Token::Open) {
+                    Ok(Term::Call(name, self.arguments()?))
+                } else {
+                    Ok(Term::Variable(name))
+                }
+            }
+            Some(token) => bail!("unexpected {:?}", token),
+            None => bail!("empty expression"),
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Term>> {
+        let _ = self.next();
+        let mut args = Vec::new();
+        if self.peek() == Some(&Token::Close) {
+            let _ = self.next();
+            return Ok(args);
+        }
+        loop {
+            args.push(self.term()?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::Close) => return Ok(args),
+                _ => bail!("expected `,` or `)` in argument list"),
+            }
+        }
+    }
+}
+
+fn str_arg(args: &[Value], index: usize) -> Option<&str> {
+    args.get(index).and_then(Value::as_str)
+}
+
+/// Converts a value to the text inserted into the output.
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+/// Formats an RFC 3339 or `YYYY-MM-DD` date.
+fn format_date(date: &str, format: &str) -> Result<String> {
+    let parsed = DateTime::parse(date.trim())
+        .map_err(|_| anyhow!("`{}` is not a valid date", date))?;
+    parsed
+        .format(format)
+        .map_err(|_| anyhow!("invalid date format `{}`", format))
+}
+
+/// Escapes `&`, `<`, `>`, `"` and `'`.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Turns text into a lowercase, hyphen-separated URL slug.
+///
+/// # Examples
+///
+/// ```
+/// use ssg::template::slugify;
+///
+/// assert_eq!(slugify("Hello, World! 2025"), "hello-world-2025");
+/// ```
+pub fn slugify(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Shortens `text` to at most `length` characters plus `suffix`,
+/// cutting at a word boundary where possible.
+pub fn truncate(text: &str, length: usize, suffix: &str) -> String {
+    if text.chars().count() <= length {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(length).collect();
+    let cut = match cut.rfind(' ') {
+        Some(space) if space > 0 => cut[..space].trim_end(),
+        _ => cut.as_str(),
+    };
+    format!("{}{}", cut, suffix)
+}
+
+/// Renders Markdown to HTML with tables and every
+/// [extension](crate::markdown::ExtensionOptions) on.
+pub fn render_markdown(markdown: &str) -> String {
+    render_markdown_with(markdown, &ExtensionOptions::default())
+}
+
+/// Renders Markdown to HTML with tables and the `extensions` that are
+/// on, with footnotes and task lists in the markup of
+/// [`crate::markdown`].
+///
+/// ```
+/// use ssg::{markdown::ExtensionOptions, template::render_markdown_with};
+///
+/// let options = ExtensionOptions {
+///     strikethrough: false,
+///     ..ExtensionOptions::default()
+/// };
+/// assert_eq!(render_markdown_with("~~old~~", &options), "<p>~~old~~</p>\n");
+/// ```
+pub fn render_markdown_with(
+    markdown: &str,
+    extensions: &ExtensionOptions,
+) -> String {
+    let mut options = Options::ENABLE_TABLES;
+    options.set(Options::ENABLE_FOOTNOTES, extensions.footnotes);
+    options.set(Options::ENABLE_TASKLISTS, extensions.task_lists);
+    options.set(
+        Options::ENABLE_DEFINITION_LIST,
+        extensions.definition_lists,
+    );
+    options
+        .set(Options::ENABLE_STRIKETHROUGH, extensions.strikethrough);
+    let events =
+        markdown::render_extensions(Parser::new_ext(markdown, options));
+    let mut output = String::new();
+    html::push_html(&mut output, events.into_iter());
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    fn context() -> Value {
+        json!({
+            "title": "Hello, World",
+            "date": "2025-01-15",
+            "tags": ["rust", "static sites"],
+            "page": { "author": { "name": "Ada" } },
+            "summary": "",
+        })
+    }
+
+    #[test]
+    fn test_variables_and_paths() -> Result<()> {
+        let engine = Engine::new();
+        let html = engine.render(
+            "<h1>{{title}}</h1> by {{ page.author.name }} on {{ tags.1 }}",
+            &context(),
+        )?;
+        assert_eq!(
+            html,
+            "<h1>Hello, World</h1> by Ada on static sites"
+        );
+        let html = engine.render(
+            "{{ theme-color }}",
+            &json!({"theme-color": "#000"}),
+        )?;
+        assert_eq!(html, "#000");
+        Ok(())
+    }
+
+    #[test]
+    fn test_builtin_filters() -> Result<()> {
+        let engine = Engine::new();
+        let render = |source: &str| engine.render(source, &context());
+
+        assert_eq!(render("{{ title | slugify }}")?, "hello-world");
+        assert_eq!(render("{{ title | upper }}")?, "HELLO, WORLD");
+        assert_eq!(
+            render("{{ title | urlencode }}")?,
+            "Hello%2C%20World"
+        );
+        assert_eq!(
+            render("{{ date | date(\"[day]/[month]/[year]\") }}")?,
+            "15/01/2025"
+        );
+        assert_eq!(render("{{ title | truncate(8) }}")?, "Hello,…");
+        assert_eq!(
+            render("{{ title | truncate(8, '...') }}")?,
+            "Hello,..."
+        );
+        assert_eq!(
+            render("{{ tags | join(' / ') }}")?,
+            "rust / static sites"
+        );
+        assert_eq!(render("{{ tags | length }}")?, "2");
+        assert_eq!(render("{{ summary | default('None') }}")?, "None");
+        assert_eq!(
+            render("{{ missing | default(title) }}")?,
+            "Hello, World"
+        );
+        assert_eq!(
+            render("{{ '**bold** <b>' | markdown | trim }}")?,
+            "<p><strong>bold</strong> <b></p>"
+        );
+        assert_eq!(
+            render("{{ '<a href=\"x\">' | escape }}")?,
+            "&lt;a href=&quot;x&quot;&gt;"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_filters_and_functions() -> Result<()> {
+        let mut engine = Engine::empty();
+        engine.register_filter("repeat", |value, args| {
+            let times =
+                args.first().and_then(Value::as_u64).unwrap_or(2);
+            Ok(Value::String(to_text(value).repeat(times as usize)))
+        });
+        engine.register_function("add", |args| {
+            Ok(Value::from(
+                args.iter().filter_map(Value::as_i64).sum::<i64>(),
+            ))
+        });
+
+        assert!(engine.has_filter("repeat"));
+        assert!(!engine.has_filter("upper"));
+        assert!(engine.has_function("add"));
+        assert_eq!(
+            engine.render(
+                "{{ 'ab' | repeat(3) }} {{ add(1, 2, -4) }}",
+                &json!({})
+            )?,
+            "ababab -1"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_builtin_functions() -> Result<()> {
+        let engine = Engine::new();
+        let html = engine.render(
+            "{{ concat('/tags/', tags.0, '/') }}",
+            &context(),
+        )?;
+        assert_eq!(html, "/tags/rust/");
+        assert_eq!(
+            engine.render("{{ now('[year]') }}", &json!({}))?.len(),
+            4
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_backend() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        fs::create_dir_all(temp_dir.path().join("partials"))?;
+        fs::write(
+            temp_dir.path().join("page.html"),
+            "<h1>{{ title | upper }}</h1>",
+        )?;
+        fs::write(temp_dir.path().join("partials/nav.html"), "nav")?;
+        fs::write(temp_dir.path().join("notes.txt"), "skipped")?;
+
+        let mut backend = NativeBackend::default();
+        assert_eq!(backend.add_dir(temp_dir.path())?, 2);
+        assert!(backend.has_template("partials/nav.html"));
+        assert_eq!(backend.name(), "native");
+
+        backend.engine_mut().register_filter("shout", |value, _| {
+            Ok(Value::String(format!("{}!", to_text(value))))
+        });
+        backend.add_template("inline", "{{ title | shout }}")?;
+        assert_eq!(
+            backend.render("page.html", &context())?,
+            "<h1>HELLO, WORLD</h1>"
+        );
+        assert_eq!(
+            backend.render("inline", &context())?,
+            "Hello, World!"
+        );
+        assert!(backend.render("missing.html", &context()).is_err());
+
+        // Page bodies and the `markdown` filter follow the extensions.
+        assert_eq!(
+            backend.render_markdown("~~x~~"),
+            "<p><del>x</del></p>\n"
+        );
+        backend.set_markdown_extensions(ExtensionOptions {
+            strikethrough: false,
+            ..ExtensionOptions::default()
+        });
+        backend.add_template("struck", "{{ title | markdown }}")?;
+        assert_eq!(backend.render_markdown("~~x~~"), "<p>~~x~~</p>\n");
+        assert_eq!(
+            backend.render("struck", &json!({ "title": "~~x~~" }))?,
+            "<p>~~x~~</p>\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_macros_and_imports() -> Result<()> {
+        let engine = Engine::new();
+        assert_eq!(
+            engine.render(
+                "{% macro badge(text) %}<b>{{ text | upper }}</b>{% endmacro %}\n\
+                 {{ badge(tags.0) }} {{ badge() }}",
+                &context(),
+            )?,
+            "<b>RUST</b> <b></b>"
+        );
+
+        let mut backend = NativeBackend::default();
+        backend.add_template(
+            "macros/ui.html",
+            "{% macro button(text, url) %}\n\
+             <a class=\"button\" href=\"{{ url }}\">{{ icon(text) }}</a>\
+             {% endmacro %}\n\
+             {% macro icon(name) %}[{{ name }}]{% endmacro %}\n",
+        )?;
+        backend.add_template(
+            "page.html",
+            "{% import \"macros/ui.html\" as ui %}\n\
+             <p>{{ ui.button(title, '/start/') }} {{ ui::icon('x') }}</p>",
+        )?;
+        assert_eq!(
+            backend.render("page.html", &context())?,
+            "<p><a class=\"button\" href=\"/start/\">[Hello, World]</a> [x]</p>"
+        );
+
+        let before = backend.fingerprint("page.html");
+        backend.add_template(
+            "macros/ui.html",
+            "{% macro button(text, url) %}{{ text }}{% endmacro %}",
+        )?;
+        assert_ne!(backend.fingerprint("page.html"), before);
+        assert!(backend.render("page.html", &context()).is_err());
+
+        backend.add_template(
+            "loop.html",
+            "{% import 'loop.html' as me %}",
+        )?;
+        assert!(backend.render("loop.html", &context()).is_err());
+        backend.add_template(
+            "deep.html",
+            "{% macro down(n) %}{{ down(n) }}{% endmacro %}{{ down(1) }}",
+        )?;
+        assert!(backend.render("deep.html", &context()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_tag_whitespace() -> Result<()> {
+        let engine = Engine::new();
+        let a = engine.fingerprint("<h1>{{title|upper}}</h1>")?;
+        assert_eq!(
+            a,
+            engine.fingerprint("<h1>{{ title | upper }}</h1>")?
+        );
+        assert_ne!(a, engine.fingerprint("<h1>{{ title }}</h1>")?);
+        assert_ne!(
+            a,
+            engine.fingerprint("<h2>{{ title | upper }}</h2>")?
+        );
+        assert!(engine.fingerprint("{{ title").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fingerprint_hashes_read_state() -> Result<()> {
+        let mut engine = Engine::new();
+        let source = "{{ data.name }} {{ t(\"home\") }}";
+        let before = engine.fingerprint(source)?;
+        engine.set_global("build", json!({ "id": 1 }));
+        assert_eq!(before, engine.fingerprint(source)?);
+
+        engine.set_global("data", json!({ "name": "a" }));
+        let with_data = engine.fingerprint(source)?;
+        assert_ne!(before, with_data);
+        engine.set_function_state("t", "en".to_string());
+        assert_ne!(with_data, engine.fingerprint(source)?);
+        engine.register_function("t", |_| Ok(Value::Null));
+        assert_eq!(with_data, engine.fingerprint(source)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let engine = Engine::new();
+        let error = engine
+            .render_file(
+                Path::new("page.html"),
+                "<p>\n{{ titel | upper }}</p>",
+                &context(),
+            )
+            .unwrap_err();
+        let report = diagnostics::report(&error, false);
+        assert!(
+            report.contains("error: unknown template variable `titel`")
+        );
+        assert!(report.contains("page.html:2:1"));
+        assert!(report.contains("did you mean `title`?"));
+
+        for source in [
+            "{{ title | nope }}",
+            "{{ nope() }}",
+            "{{ title",
+            "{{ 'open }}",
+            "{{ title | date }}",
+            "{{ }}",
+            "{% if title %}",
+            "{% macro a( %}{% endmacro %}",
+            "{% macro a() %}unclosed",
+            "{% macro a() %}{% macro b() %}{% endmacro %}",
+            "{% macro a() %}{% endmacro %}{{ a(1) }}",
+            "{% import 'macros.html' as ui %}",
+        ] {
+            assert!(
+                engine.render(source, &context()).is_err(),
+                "{}",
+                source
+            );
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Tera Backend
+//!
+//! Renders themes written for [Tera](https://keats.github.io/tera/)
+//! through [`TemplateBackend`]. Enable with the `tera` feature:
+//!
+//! ```toml
+//! ssg = { version = "0.0.32", features = ["tera"] }
+//! ```
+//!
+//! Templates keep their Tera syntax, including inheritance (`{% extends
+//! %}`), includes and macros. The page context is passed as-is, so a
+//! template can use `{{ title }}` or `{% for tag in tags %}` directly.
+
+use ::tera::{Context as TeraContext, Tera};
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::{collections::HashMap, path::Path};
+
+use super::{read_templates, TemplateBackend};
+use crate::vfs::Vfs;
+
+/// A [`TemplateBackend`] backed by Tera.
+#[derive(Debug, Default)]
+pub struct TeraBackend {
+    tera: Tera,
+}
+
+impl TeraBackend {
+    /// Creates a backend without templates.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The underlying Tera instance, for registering filters, functions
+    /// and testers.
+    pub fn tera_mut(&mut self) -> &mut Tera {
+        &mut self.tera
+    }
+
+    /// Registers a filter written for the native engine with Tera.
+    ///
+    /// Named Tera arguments are passed in alphabetical order of their
+    /// names.
+    pub fn register_filter<F>(&mut self, name: &str, filter: F)
+    where
+        F: Fn(&Value, &[Value]) -> Result<Value>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.tera.register_filter(
+            name,
+            move |value: &Value, args: &HashMap<String, Value>| {
+                let mut named: Vec<_> = args.iter().collect();
+                named.sort_by(|a, b| a.0.cmp(b.0));
+                let args: Vec<Value> =
+                    named.into_iter().map(|(_, v)| v.clone()).collect();
+                filter(value, &args)
+                    .map_err(|e| ::tera::Error::msg(e.to_string()))
+            },
+        );
+    }
+}
+
+impl TemplateBackend for TeraBackend {
+    fn name(&self) -> &'static str {
+        "tera"
+    }
+
+    fn add_template(&mut self, name: &str, source: &str) -> Result<()> {
+        self.tera.add_raw_template(name, source).map_err(|e| {
+            anyhow!("Invalid Tera template `{}`: {}", name, e)
+        })
+    }
+
+    fn has_template(&self, name: &str) -> bool {
+        self.tera.get_template_names().any(|known| known == name)
+    }
+
+    fn render(&self, name: &str, context: &Value) -> Result<String> {
+        let context = TeraContext::from_serialize(context)
+            .map_err(|e| anyhow!("Invalid template context: {}", e))?;
+        self.tera.render(name, &context).map_err(|e| {
+            // Tera keeps the useful detail in the error source.
+            let mut message = e.to_string();
+            let mut source = std::error::Error::source(&e);
+            while let Some(cause) = source {
+                message.push_str(&format!(": {}", cause));
+                source = cause.source();
+            }
+            anyhow!(message)
+        })
+    }
+
+    /// Adds all templates in one batch so `{% extends %}` works
+    /// regardless of file order.
+    fn add_dir_from(
+        &mut self,
+        vfs: &dyn Vfs,
+        dir: &Path,
+    ) -> Result<usize> {
+        let templates = read_templates(vfs, dir)?;
+        let count = templates.len();
+        self.tera
+            .add_raw_templates(templates)
+            .map_err(|e| anyhow!("Invalid Tera template: {}", e))?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn test_tera_backend_renders_inheritance() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        fs::write(
+            temp_dir.path().join("base.html"),
+            "<title>{% block title %}{% endblock %}</title>",
+        )?;
+        fs::write(
+            temp_dir.path().join("page.html"),
+            "{% extends \"base.html\" %}{% block title %}{{ title | shout }}{% endblock %}",
+        )?;
+
+        let mut backend = TeraBackend::new();
+        backend.register_filter("shout", |value, _| {
+            Ok(Value::String(format!(
+                "{}!",
+                value.as_str().unwrap_or_default()
+            )))
+        });
+        assert_eq!(backend.add_dir(temp_dir.path())?, 2);
+        assert!(backend.has_template("page.html"));
+        assert_eq!(
+            backend.render("page.html", &json!({ "title": "Hi" }))?,
+            "<title>Hi!</title>"
+        );
+        assert!(backend.render("missing.html", &json!({})).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Template Tests
+//!
+//! Backs `ssg test-templates`, which renders templates against fixture
+//! contexts and compares the output with HTML snapshots, so theme
+//! changes can be tested without building a site.
+//!
+//! Each case is a YAML file in `tests/templates/`:
+//!
+//! ```yaml
+//! # tests/templates/post-with-tags.yaml
+//! template: post.html
+//! context:
+//!   title: Hello
+//!   tags: [rust, ssg]
+//! ```
+//!
+//! The expected output is the snapshot next to it,
+//! `tests/templates/post-with-tags.html`. Run with `--update` to write
+//! missing or changed snapshots after reviewing the differences.
+//! Trailing whitespace at the end of the output is ignored.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::template::TemplateBackend;
+
+/// Default directory of template test cases, relative to the project
+/// root.
+pub const TEMPLATE_TESTS_DIR: &str = "tests/templates";
+
+/// Number of differing lines shown for a failed case.
+const MAX_DIFF_LINES: usize = 20;
+
+/// A template test case.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TemplateCase {
+    /// Name of the template to render, e.g. `post.html`.
+    pub template: String,
+    /// Context the template is rendered with.
+    #[serde(default)]
+    pub context: Value,
+}
+
+/// How a case ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The output matched the snapshot.
+    Passed,
+    /// The snapshot was written or replaced by `--update`.
+    Updated,
+    /// The case has no snapshot.
+    Missing,
+    /// The output differs from the snapshot; holds the differing lines.
+    Failed(String),
+    /// The case could not be read or rendered; holds the error.
+    Error(String),
+}
+
+/// The result of one case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseResult {
+    /// Case file.
+    pub case: PathBuf,
+    /// How the case ended.
+    pub outcome: Outcome,
+}
+
+impl fmt::Display for CaseResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.case.display();
+        match &self.outcome {
+            Outcome::Passed => write!(f, "ok       {}", name),
+            Outcome::Updated => write!(f, "updated  {}", name),
+            Outcome::Missing => write!(
+                f,
+                "missing  {} (no snapshot; run with --update)",
+                name
+            ),
+            Outcome::Failed(diff) => {
+                write!(f, "FAILED   {}\n{}", name, diff)
+            }
+            Outcome::Error(error) => {
+                write!(f, "ERROR    {}: {}", name, error)
+            }
+        }
+    }
+}
+
+/// Results of a template test run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestReport {
+    /// Results in case file order.
+    pub results: Vec<CaseResult>,
+}
+
+impl TestReport {
+    /// Returns the number of cases that passed or were updated.
+    pub fn passed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| {
+                matches!(
+                    result.outcome,
+                    Outcome::Passed | Outcome::Updated
+                )
+            })
+            .count()
+    }
+
+    /// Returns the number of cases that failed, errored or have no
+    /// snapshot.
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+}
+
+/// Runs every case in `dir` against `backend`. With `update`, missing
+/// and differing snapshots are written instead of failing.
+///
+/// A case that cannot be read or rendered is reported as
+/// [`Outcome::Error`] and does not stop the run.
+///
+/// # Errors
+///
+/// Returns an error if `dir` cannot be read or a snapshot cannot be
+/// written.
+pub fn run_template_tests(
+    dir: &Path,
+    backend: &dyn TemplateBackend,
+    update: bool,
+) -> Result<TestReport> {
+    let mut cases: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("yaml" | "yml")
+            )
+        })
+        .collect();
+    cases.sort();
+
+    let mut report = TestReport::default();
+    for case in cases {
+        let outcome = match render_case(&case, backend) {
+            Ok(output) => {
+                compare(&case.with_extension("html"), &output, update)?
+            }
+            Err(error) => Outcome::Error(format!("{:#}", error)),
+        };
+        report.results.push(CaseResult { case, outcome });
+    }
+    Ok(report)
+}
+
+fn render_case(
+    case: &Path,
+    backend: &dyn TemplateBackend,
+) -> Result<String> {
+    let yaml = fs::read_to_string(case).with_context(|| {
+        format!("Failed to read {}", case.display())
+    })?;
+    let case: TemplateCase =
+        serde_yaml::from_str(&yaml).with_context(|| {
+            format!("Invalid test case {}", case.display())
+        })?;
+    backend.render(&case.template, &case.context)
+}
+
+fn compare(
+    snapshot: &Path,
+    output: &str,
+    update: bool,
+) -> Result<Outcome> {
+    let expected = fs::read_to_string(snapshot).ok();
+    let matches = expected.as_deref().map_or(false, |expected| {
+        expected.trim_end() == output.trim_end()
+    });
+    if matches {
+        return Ok(Outcome::Passed);
+    }
+    if update {
+        fs::write(snapshot, format!("{}\n", output.trim_end()))
+            .with_context(|| {
+                format!("Failed to write {}", snapshot.display())
+            })?;
+        return Ok(Outcome::Updated);
+    }
+    Ok(match expected {
+        Some(expected) => Outcome::Failed(diff(&expected, output)),
+        None => Outcome::Missing,
+    })
+}
+
+/// Lists the lines that differ between `expected` and `actual`, as
+/// `-` and `+` lines with line numbers.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.trim_end().lines().collect();
+    let actual: Vec<&str> = actual.trim_end().lines().collect();
+    let mut lines = Vec::new();
+    for index in 0..expected.len().max(actual.len()) {
+        let (old, new) = (expected.get(index), actual.get(index));
+        if old == new {
+            continue;
+        }
+        if let Some(old) = old {
+            lines.push(format!("  {:>4} - {}", index + 1, old));
+        }
+        if let Some(new) = new {
+            lines.push(format!("  {:>4} + {}", index + 1, new));
+        }
+    }
+    if lines.len() > MAX_DIFF_LINES {
+        let hidden = lines.len() - MAX_DIFF_LINES;
+        lines.truncate(MAX_DIFF_LINES);
+        lines.push(format!("  ... {} more", hidden));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::NativeBackend;
+    use tempfile::tempdir;
+
+    fn backend() -> Result<NativeBackend> {
+        let mut backend = NativeBackend::default();
+        backend.add_template(
+            "post.html",
+            "<h1>{{ title }}</h1>\n<p>{{ tags | join(\", \") }}</p>",
+        )?;
+        Ok(backend)
+    }
+
+    #[test]
+    fn test_snapshots_pass_fail_and_update() -> Result<()> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("post.yaml"),
+            "template: post.html\ncontext:\n  title: Hello\n  tags: [a, b]\n",
+        )?;
+        fs::write(
+            dir.path().join("broken.yml"),
+            "template: missing.html\n",
+        )?;
+        let backend = backend()?;
+
+        let report = run_template_tests(dir.path(), &backend, false)?;
+        let outcomes: Vec<_> =
+            report.results.iter().map(|r| &r.outcome).collect();
+        assert!(matches!(outcomes[0], Outcome::Error(_)));
+        assert_eq!(outcomes[1], &Outcome::Missing);
+        assert_eq!(report.failed(), 2);
+
+        fs::remove_file(dir.path().join("broken.yml"))?;
+        let report = run_template_tests(dir.path(), &backend, true)?;
+        assert_eq!(report.results[0].outcome, Outcome::Updated);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("post.html"))?,
+            "<h1>Hello</h1>\n<p>a, b</p>\n"
+        );
+
+        let report = run_template_tests(dir.path(), &backend, false)?;
+        assert_eq!((report.passed(), report.failed()), (1, 0));
+
+        fs::write(
+            dir.path().join("post.html"),
+            "<h1>Goodbye</h1>\n<p>a, b</p>\n",
+        )?;
+        let report = run_template_tests(dir.path(), &backend, false)?;
+        assert_eq!(
+            report.results[0].outcome,
+            Outcome::Failed(
+                "     1 - <h1>Goodbye</h1>\n     1 + <h1>Hello</h1>"
+                    .to_string()
+            )
+        );
+        Ok(())
+    }
+}
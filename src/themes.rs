@@ -0,0 +1,601 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Themes
+//!
+//! A theme is a reusable set of templates and assets, so a site does not
+//! have to carry a full copy of its templates. Themes live in
+//! `themes/<name>/`:
+//!
+//! ```text
+//! themes/minimal/
+//! ├── theme.toml        # manifest
+//! ├── templates/        # page templates
+//! └── assets/           # CSS, fonts, images copied to the site root
+//! ```
+//!
+//! The manifest names the theme and may describe it:
+//!
+//! ```toml
+//! name = "minimal"
+//! version = "1.0.0"
+//! description = "A small, fast theme"
+//! author = "Jane Doe"
+//! ```
+//!
+//! A theme may also declare what it needs from the generator, so a site
+//! on an older or smaller build gets a clear error when the theme is
+//! loaded rather than a render failure halfway through the build:
+//!
+//! ```toml
+//! [requires]
+//! ssg = "0.0.32"                # oldest ssg version the theme works with
+//! features = ["search", "i18n"] # see FEATURES and optional_features()
+//! template = ["macros", "imports"]
+//! filters = ["markdown", "slugify"]
+//! functions = ["t"]
+//! ```
+//!
+//! Site templates override theme templates with the same path: the
+//! [`TemplateResolver`] layers the site template directory over the
+//! theme's. Site files also win over theme assets. A theme is selected
+//! with `theme = "minimal"` in the config file or `--theme minimal`.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    collect_files_recursive, diagnostics::suggest, template::Engine,
+};
+
+/// Default directory containing themes, relative to the project root.
+pub const THEMES_DIR: &str = "themes";
+
+/// File name of the theme manifest.
+pub const THEME_MANIFEST: &str = "theme.toml";
+
+/// Features of every build that a theme can require.
+pub const FEATURES: &[&str] = &[
+    "accessibility",
+    "compress",
+    "consent",
+    "embeds",
+    "i18n",
+    "images",
+    "media",
+    "minify",
+    "permalinks",
+    "pwa",
+    "redirects",
+    "search",
+    "security_headers",
+    "structured_data",
+    "taxonomies",
+];
+
+/// Template syntax of the native engine that a theme can require.
+pub const TEMPLATE_CAPABILITIES: &[&str] =
+    &["filters", "functions", "imports", "macros"];
+
+/// Returns the Cargo features this build of ssg was compiled with that
+/// a theme can require.
+pub fn optional_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "fetch") {
+        features.push("fetch");
+    }
+    if cfg!(feature = "tera") {
+        features.push("tera");
+    }
+    features
+}
+
+/// The `[requires]` table of a theme manifest.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct ThemeRequirements {
+    /// Oldest ssg version the theme works with, e.g. `0.0.32`.
+    pub ssg: Option<String>,
+    /// Generator features the theme relies on.
+    pub features: Vec<String>,
+    /// Template syntax the theme's templates use.
+    pub template: Vec<String>,
+    /// Template filters the theme's templates use.
+    pub filters: Vec<String>,
+    /// Template functions the theme's templates call.
+    pub functions: Vec<String>,
+}
+
+impl ThemeRequirements {
+    /// Returns `true` if the theme declares no requirements.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Returns one message per requirement that this build or `engine`
+    /// does not meet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg::template::Engine;
+    /// use ssg::themes::ThemeRequirements;
+    ///
+    /// let requires = ThemeRequirements {
+    ///     ssg: Some("99.0.0".to_string()),
+    ///     filters: vec!["slugify".to_string(), "sluggify".to_string()],
+    ///     ..ThemeRequirements::default()
+    /// };
+    /// let problems = requires.unmet(&Engine::new());
+    /// assert_eq!(problems.len(), 2);
+    /// assert!(problems[0].starts_with("requires ssg 99.0.0 or later"));
+    /// assert!(problems[1].contains("`sluggify` filter"));
+    /// ```
+    pub fn unmet(&self, engine: &Engine) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some(required) = &self.ssg {
+            let current = env!("CARGO_PKG_VERSION");
+            match (parse_version(required), parse_version(current)) {
+                (Some(required_version), Some(current_version))
+                    if required_version > current_version =>
+                {
+                    problems.push(format!(
+                        "requires ssg {} or later, but this is ssg {}",
+                        required, current
+                    ));
+                }
+                (None, _) => problems.push(format!(
+                    "requires ssg `{}`, which is not a version",
+                    required
+                )),
+                _ => {}
+            }
+        }
+
+        let optional = optional_features();
+        for feature in &self.features {
+            if FEATURES.contains(&feature.as_str()) {
+                continue;
+            }
+            if feature == "fetch" || feature == "tera" {
+                if !optional.contains(&feature.as_str()) {
+                    problems.push(format!(
+                        "requires the `{0}` feature; install ssg with \
+                         `--features {0}`",
+                        feature
+                    ));
+                }
+                continue;
+            }
+            let known = FEATURES
+                .iter()
+                .copied()
+                .chain(["fetch", "tera"].iter().copied());
+            problems.push(match suggest(feature, known) {
+                Some(close) => format!(
+                    "requires unknown feature `{}`; did you mean `{}`?",
+                    feature, close
+                ),
+                None => {
+                    format!("requires unknown feature `{}`", feature)
+                }
+            });
+        }
+        for capability in &self.template {
+            if !TEMPLATE_CAPABILITIES.contains(&capability.as_str()) {
+                problems.push(format!(
+                    "uses template syntax `{}`, which the template \
+                     engine does not support",
+                    capability
+                ));
+            }
+        }
+        for filter in &self.filters {
+            if !engine.has_filter(filter) {
+                problems.push(format!(
+                    "uses the `{}` filter, which is not available",
+                    filter
+                ));
+            }
+        }
+        for function in &self.functions {
+            if !engine.has_function(function) {
+                problems.push(format!(
+                    "calls the `{}` function, which is not available",
+                    function
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// Parses a `major.minor.patch` version, ignoring any pre-release or
+/// build suffix. Missing components count as zero.
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+    let core = version
+        .trim()
+        .trim_start_matches(">=")
+        .trim_start()
+        .split(['-', '+'])
+        .next()?;
+    let mut parts = [0; 3];
+    for (index, part) in core.split('.').enumerate() {
+        *parts.get_mut(index)? = part.parse().ok()?;
+    }
+    Some(parts)
+}
+
+/// The `theme.toml` manifest of a theme.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThemeManifest {
+    /// Theme name.
+    pub name: String,
+    /// Theme version.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Short description.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Theme author.
+    #[serde(default)]
+    pub author: Option<String>,
+    /// What the theme needs from the generator.
+    #[serde(
+        default,
+        skip_serializing_if = "ThemeRequirements::is_empty"
+    )]
+    pub requires: ThemeRequirements,
+}
+
+/// An installed theme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    /// Directory of the theme, e.g. `themes/minimal`.
+    pub root: PathBuf,
+    /// The parsed manifest.
+    pub manifest: ThemeManifest,
+}
+
+impl Theme {
+    /// Loads the theme `name` from `themes_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the theme is not installed, suggesting the
+    /// closest installed theme, or if its manifest is missing or invalid.
+    pub fn load(themes_dir: &Path, name: &str) -> Result<Self> {
+        let root = themes_dir.join(name);
+        if !root.is_dir() {
+            let installed = available_themes(themes_dir);
+            match suggest(name, installed.iter().map(String::as_str)) {
+                Some(close) => bail!(
+                    "Theme `{}` not found in {}; did you mean `{}`?",
+                    name,
+                    themes_dir.display(),
+                    close
+                ),
+                None => bail!(
+                    "Theme `{}` not found in {}",
+                    name,
+                    themes_dir.display()
+                ),
+            }
+        }
+
+        let manifest_path = root.join(THEME_MANIFEST);
+        let source =
+            fs::read_to_string(&manifest_path).with_context(|| {
+                format!("Failed to read {}", manifest_path.display())
+            })?;
+        let manifest: ThemeManifest = toml::from_str(&source)
+            .with_context(|| {
+                format!(
+                    "Invalid theme manifest {}",
+                    manifest_path.display()
+                )
+            })?;
+        Ok(Self { root, manifest })
+    }
+
+    /// Checks that this build of ssg and `engine` meet the theme's
+    /// `[requires]` table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing every unmet requirement.
+    pub fn check_compatibility(&self, engine: &Engine) -> Result<()> {
+        let problems = self.manifest.requires.unmet(engine);
+        if problems.is_empty() {
+            return Ok(());
+        }
+        bail!(
+            "Theme `{}`{} is not compatible with this build:\n  - {}",
+            self.manifest.name,
+            self.manifest.version.as_ref().map_or(
+                String::new(),
+                |version| format!(" {}", version)
+            ),
+            problems.join("\n  - ")
+        )
+    }
+
+    /// Directory of the theme templates.
+    pub fn templates_dir(&self) -> PathBuf {
+        self.root.join("templates")
+    }
+
+    /// Directory of the theme assets.
+    pub fn assets_dir(&self) -> PathBuf {
+        self.root.join("assets")
+    }
+
+    /// Returns the files of the theme assets, sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the assets directory cannot be read.
+    pub fn asset_files(&self) -> Result<Vec<PathBuf>> {
+        let assets = self.assets_dir();
+        if !assets.is_dir() {
+            return Ok(Vec::new());
+        }
+        files_below(&assets)
+    }
+
+    /// Copies the theme assets into `site_dir`.
+    ///
+    /// Files that already exist in `site_dir` are kept, so site files
+    /// override theme assets. Returns the number of copied files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an asset cannot be read or written.
+    pub fn copy_assets(&self, site_dir: &Path) -> Result<usize> {
+        let assets = self.assets_dir();
+        let mut copied = 0;
+        for file in self.asset_files()? {
+            let target = site_dir
+                .join(file.strip_prefix(&assets).unwrap_or(&file));
+            if target.exists() {
+                continue;
+            }
+            copy_file(&file, &target)?;
+            copied += 1;
+        }
+        Ok(copied)
+    }
+}
+
+/// Returns the names of the themes installed in `themes_dir`, sorted.
+///
+/// A theme is any subdirectory containing a `theme.toml`.
+pub fn available_themes(themes_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(themes_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().join(THEME_MANIFEST).is_file())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Looks templates up in the site template directory first, then in the
+/// theme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateResolver {
+    /// Template directories, highest precedence first.
+    pub layers: Vec<PathBuf>,
+}
+
+impl TemplateResolver {
+    /// Creates a resolver for `site_templates` layered over `theme`.
+    pub fn new(site_templates: &Path, theme: Option<&Theme>) -> Self {
+        let mut layers = vec![site_templates.to_path_buf()];
+        layers.extend(theme.map(Theme::templates_dir));
+        Self { layers }
+    }
+
+    /// Returns the file providing the template at `relative`, if any.
+    pub fn resolve(&self, relative: &Path) -> Option<PathBuf> {
+        self.layers
+            .iter()
+            .map(|layer| layer.join(relative))
+            .find(|path| path.is_file())
+    }
+
+    /// Writes the effective template set to `dest`: theme templates
+    /// first, then site templates over them.
+    ///
+    /// Returns the number of templates written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a template cannot be read or written.
+    pub fn merge_into(&self, dest: &Path) -> Result<usize> {
+        fs::create_dir_all(dest).with_context(|| {
+            format!("Failed to create {}", dest.display())
+        })?;
+        let mut written = BTreeSet::new();
+        for layer in self.layers.iter().rev() {
+            if !layer.is_dir() {
+                continue;
+            }
+            for file in files_below(layer)? {
+                let relative = file
+                    .strip_prefix(layer)
+                    .unwrap_or(&file)
+                    .to_path_buf();
+                copy_file(&file, &dest.join(&relative))?;
+                let _ = written.insert(relative);
+            }
+        }
+        Ok(written.len())
+    }
+}
+
+fn files_below(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_files_recursive(dir, &mut files)
+        .with_context(|| format!("Failed to read {}", dir.display()))?;
+    files.sort();
+    Ok(files)
+}
+
+fn copy_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            format!("Failed to create {}", parent.display())
+        })?;
+    }
+    let _ = fs::copy(from, to).with_context(|| {
+        format!("Failed to copy {} to {}", from.display(), to.display())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn install_theme(themes_dir: &Path) {
+        let root = themes_dir.join("minimal");
+        write(
+            &root.join(THEME_MANIFEST),
+            "name = \"minimal\"\nversion = \"1.0.0\"",
+        );
+        write(&root.join("templates/index.html"), "theme index");
+        write(&root.join("templates/page.html"), "theme page");
+        write(&root.join("assets/css/theme.css"), "body {}");
+        write(&root.join("assets/favicon.ico"), "theme icon");
+    }
+
+    #[test]
+    fn test_load_theme_and_suggest() -> Result<()> {
+        let temp_dir = tempdir()?;
+        install_theme(temp_dir.path());
+        fs::create_dir_all(temp_dir.path().join("not-a-theme"))?;
+
+        let theme = Theme::load(temp_dir.path(), "minimal")?;
+        assert_eq!(theme.manifest.version.as_deref(), Some("1.0.0"));
+        assert_eq!(available_themes(temp_dir.path()), vec!["minimal"]);
+
+        let error =
+            Theme::load(temp_dir.path(), "minimol").unwrap_err();
+        assert!(error.to_string().contains("did you mean `minimal`?"));
+        assert!(Theme::load(temp_dir.path(), "not-a-theme").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_compatibility() -> Result<()> {
+        let temp_dir = tempdir()?;
+        install_theme(temp_dir.path());
+        let manifest =
+            temp_dir.path().join("minimal").join(THEME_MANIFEST);
+        let engine = Engine::new();
+        let theme = Theme::load(temp_dir.path(), "minimal")?;
+        assert!(theme.check_compatibility(&engine).is_ok());
+
+        write(
+            &manifest,
+            "name = \"minimal\"\nversion = \"2.0.0\"\n\n\
+             [requires]\nssg = \"0.0.1\"\n\
+             features = [\"search\", \"serach\"]\n\
+             template = [\"macros\", \"blocks\"]\n\
+             functions = [\"now\", \"t\"]\n",
+        );
+        let theme = Theme::load(temp_dir.path(), "minimal")?;
+        let error =
+            theme.check_compatibility(&engine).unwrap_err().to_string();
+        assert!(error.starts_with(
+            "Theme `minimal` 2.0.0 is not compatible with this build:"
+        ));
+        assert!(error.contains("`serach`; did you mean `search`?"));
+        assert!(error.contains("template syntax `blocks`"));
+        assert!(error.contains("`t` function"));
+        assert!(!error.contains("`now`"));
+        assert!(!error.contains("requires ssg"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.0.32"), Some([0, 0, 32]));
+        assert_eq!(parse_version(">= 1.2"), Some([1, 2, 0]));
+        assert_eq!(parse_version("1.0.0-beta.1"), Some([1, 0, 0]));
+        assert_eq!(parse_version("1.x"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+        assert!(parse_version("0.1.0") > parse_version("0.0.99"));
+    }
+
+    #[test]
+    fn test_site_templates_override_theme() -> Result<()> {
+        let temp_dir = tempdir()?;
+        install_theme(&temp_dir.path().join("themes"));
+        let site_templates = temp_dir.path().join("templates");
+        write(&site_templates.join("page.html"), "site page");
+        write(&site_templates.join("extra.html"), "site extra");
+
+        let theme =
+            Theme::load(&temp_dir.path().join("themes"), "minimal")?;
+        let resolver =
+            TemplateResolver::new(&site_templates, Some(&theme));
+        assert_eq!(
+            resolver.resolve(Path::new("page.html")),
+            Some(site_templates.join("page.html"))
+        );
+        assert_eq!(
+            resolver.resolve(Path::new("index.html")),
+            Some(theme.templates_dir().join("index.html"))
+        );
+        assert_eq!(resolver.resolve(Path::new("missing.html")), None);
+
+        let merged = temp_dir.path().join("merged");
+        assert_eq!(resolver.merge_into(&merged)?, 3);
+        assert_eq!(
+            fs::read_to_string(merged.join("page.html"))?,
+            "site page"
+        );
+        assert_eq!(
+            fs::read_to_string(merged.join("index.html"))?,
+            "theme index"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_assets_keeps_site_files() -> Result<()> {
+        let temp_dir = tempdir()?;
+        install_theme(temp_dir.path());
+        let site = temp_dir.path().join("public");
+        write(&site.join("favicon.ico"), "site icon");
+
+        let theme = Theme::load(temp_dir.path(), "minimal")?;
+        assert_eq!(theme.copy_assets(&site)?, 1);
+        assert_eq!(
+            fs::read_to_string(site.join("css/theme.css"))?,
+            "body {}"
+        );
+        assert_eq!(
+            fs::read_to_string(site.join("favicon.ico"))?,
+            "site icon"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Virtual File System
+//!
+//! Puts the file reads and writes of the content pipeline behind the
+//! [`Vfs`] trait, so a site can be loaded, rendered and written without
+//! touching the disk. [`RealFs`] is the file system the CLI builds on;
+//! [`MemoryFs`] keeps files in a map, for fast tests and for embedding
+//! the generator where there is no disk, such as a WebAssembly preview.
+//!
+//! The stages that take a file system are:
+//!
+//! | Stage | Entry point |
+//! |-------|-------------|
+//! | Loading content | [`ContentSet::load_from`](crate::content::ContentSet::load_from) |
+//! | Loading templates | [`TemplateBackend::add_dir_from`](crate::template::TemplateBackend::add_dir_from) |
+//! | Writing pages | [`RenderedSite::write_to`](crate::pipeline::RenderedSite::write_to), [`stream_pages_to`](crate::pipeline::stream_pages_to) |
+//!
+//! The post-processing stages of a full build still work on the site
+//! directory on disk.
+//!
+//! ```rust
+//! use ssg::{
+//!     content::ContentSet,
+//!     pipeline::render_pages,
+//!     template::{NativeBackend, TemplateBackend},
+//!     vfs::{MemoryFs, Vfs},
+//! };
+//!
+//! let fs = MemoryFs::new()
+//!     .with_file("content/about.md", "---\ntitle: About\n---\nHello")
+//!     .with_file("templates/page.html", "<h1>{{ title }}</h1>");
+//! let content = ContentSet::load_from(&fs, "content".as_ref())?;
+//! let mut backend = NativeBackend::default();
+//! let _ = backend.add_dir_from(&fs, "templates".as_ref())?;
+//!
+//! render_pages(&content, &backend)?.write_to(&fs, "public".as_ref())?;
+//! assert_eq!(
+//!     fs.read_to_string("public/about/index.html".as_ref())?,
+//!     "<h1>About</h1>"
+//! );
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use crate::collect_files_recursive;
+
+/// File operations of the content pipeline.
+///
+/// Stages are run in parallel, so implementations must be
+/// `Send + Sync`.
+pub trait Vfs: Debug + Send + Sync {
+    /// Reads the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file does not exist or cannot be read.
+    fn read(&self, path: &Path) -> Result<Vec<u8>>;
+
+    /// Writes `contents` to `path`, creating its parent directories and
+    /// replacing any file already there.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()>;
+
+    /// Returns `true` if `path` is a file or a directory.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Returns the paths of every file below `dir`, sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read.
+    fn files(&self, dir: &Path) -> Result<Vec<PathBuf>>;
+
+    /// Reads the file at `path` as UTF-8 text.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not UTF-8.
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        String::from_utf8(self.read(path)?).with_context(|| {
+            format!("{} is not valid UTF-8", path.display())
+        })
+    }
+}
+
+/// The file system of the machine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RealFs;
+
+impl Vfs for RealFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        fs::read(path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        fs::write(path, contents).with_context(|| {
+            format!("Failed to write {}", path.display())
+        })
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        collect_files_recursive(dir, &mut files).with_context(
+            || format!("Failed to read {}", dir.display()),
+        )?;
+        files.sort();
+        Ok(files)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        fs::read_to_string(path).with_context(|| {
+            format!("Failed to read {}", path.display())
+        })
+    }
+}
+
+/// A file system held in memory. Directories exist while they hold a
+/// file.
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+    files: RwLock<BTreeMap<PathBuf, Vec<u8>>>,
+}
+
+impl MemoryFs {
+    /// Creates an empty file system.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, returning the file system for chaining.
+    pub fn with_file(
+        self,
+        path: impl Into<PathBuf>,
+        contents: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.insert(path, contents);
+        self
+    }
+
+    /// Adds or replaces a file.
+    pub fn insert(
+        &self,
+        path: impl Into<PathBuf>,
+        contents: impl Into<Vec<u8>>,
+    ) {
+        let _ = self
+            .files
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.into(), contents.into());
+    }
+
+    /// Removes a file, returning its contents.
+    pub fn remove(&self, path: &Path) -> Option<Vec<u8>> {
+        self.files
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(path)
+    }
+
+    /// Returns the paths of every file, sorted.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.files
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect()
+    }
+}
+
+impl Vfs for MemoryFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        self.files
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(path)
+            .cloned()
+            .ok_or_else(|| anyhow!("Failed to read {}", path.display()))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        self.insert(path, contents);
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .any(|file| file.starts_with(path))
+    }
+
+    fn files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let files: Vec<PathBuf> = self
+            .files
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .filter(|file| file.starts_with(dir) && *file != dir)
+            .cloned()
+            .collect();
+        if files.is_empty() && !self.exists(dir) {
+            return Err(anyhow!("Failed to read {}", dir.display()));
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_memory_fs() -> Result<()> {
+        let fs = MemoryFs::new()
+            .with_file("site/blog/a.md", "a")
+            .with_file("site/index.md", "home")
+            .with_file("sitemap.xml", "");
+        fs.write(Path::new("site/blog/b.md"), b"b")?;
+
+        assert!(fs.exists(Path::new("site/blog")));
+        assert!(!fs.exists(Path::new("site/blo")));
+        assert_eq!(
+            fs.read_to_string(Path::new("site/blog/b.md"))?,
+            "b"
+        );
+        assert_eq!(
+            fs.files(Path::new("site"))?,
+            [
+                PathBuf::from("site/blog/a.md"),
+                PathBuf::from("site/blog/b.md"),
+                PathBuf::from("site/index.md"),
+            ]
+        );
+        assert!(fs.files(Path::new("missing")).is_err());
+
+        assert_eq!(
+            fs.remove(Path::new("site/index.md")),
+            Some(b"home".to_vec())
+        );
+        assert!(fs.read(Path::new("site/index.md")).is_err());
+        fs.insert("invalid.txt", vec![0xff]);
+        assert!(fs.read_to_string(Path::new("invalid.txt")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_real_fs() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("public/blog/index.html");
+        RealFs.write(&file, b"<p>Hi</p>")?;
+        assert!(RealFs.exists(&dir.path().join("public/blog")));
+        assert_eq!(RealFs.read_to_string(&file)?, "<p>Hi</p>");
+        assert_eq!(RealFs.files(dir.path())?, [file]);
+        assert!(RealFs.read(&dir.path().join("missing")).is_err());
+        Ok(())
+    }
+}
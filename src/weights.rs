@@ -0,0 +1,682 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Asset Weights
+//!
+//! Computes how many bytes each page of the built site loads, without a
+//! browser or HAR capture: the page's HTML, then every local stylesheet,
+//! script, image, media file and font it references, following `url()`
+//! and `@import` references from stylesheets. Each file is counted once
+//! per page.
+//!
+//! The report is written as `asset-weights.json` and a readable
+//! `asset-weights/index.html`, and checked against the budgets of the
+//! `[performance]` table of the configuration file:
+//!
+//! ```toml
+//! [performance]
+//! report = true
+//! enforce = false
+//!
+//! [performance.budgets]
+//! total = 1024  # KiB per page
+//! js = 300
+//! images = 500
+//! ```
+//!
+//! Pages over budget are logged as warnings, or fail the build with
+//! `enforce = true`. Remote files are counted as external requests only,
+//! since their size cannot be known statically.
+
+use anyhow::{bail, Context, Result};
+use log::warn;
+use once_cell::sync::Lazy;
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::BTreeSet,
+    fmt::Write as _,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{collect_files_recursive, template::escape_html};
+
+/// Path of the JSON report, relative to the site directory.
+pub const WEIGHTS_JSON_PATH: &str = "asset-weights.json";
+
+/// Path of the report page, relative to the site directory.
+pub const WEIGHTS_PAGE_PATH: &str = "asset-weights/index.html";
+
+/// Matches a tag that can load a file.
+static TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)<(link|script|img|source|video|audio)\b[^>]*>")
+        .unwrap_or_else(|e| panic!("Invalid tag regex: {}", e))
+});
+
+/// Matches an attribute of a tag that can load a file.
+static ATTR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)\s(src|href|poster|rel)\s*=\s*(?:"([^"]*)"|'([^']*)')"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid attribute regex: {}", e))
+});
+
+/// Matches a `url()` or `@import` reference in a stylesheet.
+static CSS_REF_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)url\(\s*(?:"([^"]*)"|'([^']*)'|([^)"'\s]*))\s*\)|@import\s+(?:"([^"]*)"|'([^']*)')"#,
+    )
+    .unwrap_or_else(|e| panic!("Invalid CSS reference regex: {}", e))
+});
+
+/// Kind of a loaded file.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    /// The page itself.
+    Html,
+    /// Stylesheets.
+    Css,
+    /// Scripts.
+    Js,
+    /// Images, including SVG and icons.
+    Images,
+    /// Web fonts.
+    Fonts,
+    /// Anything else, such as video and audio.
+    Other,
+}
+
+impl AssetKind {
+    /// Every kind, in report order.
+    pub const ALL: [Self; 6] = [
+        Self::Html,
+        Self::Css,
+        Self::Js,
+        Self::Images,
+        Self::Fonts,
+        Self::Other,
+    ];
+
+    /// Returns the kind of the file at `path`, from its extension.
+    pub fn of(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "html" | "htm" => Self::Html,
+            "css" => Self::Css,
+            "js" | "mjs" => Self::Js,
+            "png" | "jpg" | "jpeg" | "gif" | "webp" | "avif"
+            | "svg" | "ico" => Self::Images,
+            "woff" | "woff2" | "ttf" | "otf" | "eot" => Self::Fonts,
+            _ => Self::Other,
+        }
+    }
+
+    /// Returns the lowercase name used in the report and budgets.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Css => "css",
+            Self::Js => "js",
+            Self::Images => "images",
+            Self::Fonts => "fonts",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Per-page budgets in KiB. A missing budget is not checked.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct Budgets {
+    /// Budget of everything a page loads.
+    pub total: Option<u64>,
+    /// Budget of the page's HTML.
+    pub html: Option<u64>,
+    /// Budget of stylesheets.
+    pub css: Option<u64>,
+    /// Budget of scripts.
+    pub js: Option<u64>,
+    /// Budget of images.
+    pub images: Option<u64>,
+    /// Budget of fonts.
+    pub fonts: Option<u64>,
+}
+
+impl Budgets {
+    fn budget(&self, kind: AssetKind) -> Option<u64> {
+        match kind {
+            AssetKind::Html => self.html,
+            AssetKind::Css => self.css,
+            AssetKind::Js => self.js,
+            AssetKind::Images => self.images,
+            AssetKind::Fonts => self.fonts,
+            AssetKind::Other => None,
+        }
+    }
+
+    /// Returns the budgets `page` exceeds.
+    pub fn check(&self, page: &PageWeight) -> Vec<BudgetViolation> {
+        let mut checks: Vec<(&str, u64, Option<u64>)> = AssetKind::ALL
+            .iter()
+            .map(|&kind| {
+                (kind.name(), page.bytes(kind), self.budget(kind))
+            })
+            .collect();
+        checks.push(("total", page.total, self.total));
+        checks
+            .into_iter()
+            .filter_map(|(budget, bytes, kib)| {
+                let limit = kib? * 1024;
+                (bytes > limit).then(|| BudgetViolation {
+                    page: page.page.clone(),
+                    budget: budget.to_string(),
+                    bytes,
+                    limit,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `[performance]` options.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(default)]
+pub struct PerformanceOptions {
+    /// Writes the asset weight report and checks the budgets.
+    pub report: bool,
+    /// Fails the build when a page is over budget.
+    pub enforce: bool,
+    /// Per-page budgets.
+    pub budgets: Budgets,
+}
+
+/// Bytes loaded by one page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PageWeight {
+    /// Page path relative to the site directory, with `/` separators.
+    pub page: String,
+    /// Size of the page's HTML.
+    pub html: u64,
+    /// Size of its stylesheets.
+    pub css: u64,
+    /// Size of its scripts.
+    pub js: u64,
+    /// Size of its images.
+    pub images: u64,
+    /// Size of its fonts.
+    pub fonts: u64,
+    /// Size of its other files.
+    pub other: u64,
+    /// Sum of the sizes above.
+    pub total: u64,
+    /// Number of remote files, whose size is unknown.
+    pub external: usize,
+    /// Local references with no file in the site.
+    pub missing: Vec<String>,
+}
+
+impl PageWeight {
+    /// Returns the bytes of `kind`.
+    pub fn bytes(&self, kind: AssetKind) -> u64 {
+        match kind {
+            AssetKind::Html => self.html,
+            AssetKind::Css => self.css,
+            AssetKind::Js => self.js,
+            AssetKind::Images => self.images,
+            AssetKind::Fonts => self.fonts,
+            AssetKind::Other => self.other,
+        }
+    }
+
+    fn add(&mut self, kind: AssetKind, bytes: u64) {
+        let field = match kind {
+            AssetKind::Html => &mut self.html,
+            AssetKind::Css => &mut self.css,
+            AssetKind::Js => &mut self.js,
+            AssetKind::Images => &mut self.images,
+            AssetKind::Fonts => &mut self.fonts,
+            AssetKind::Other => &mut self.other,
+        };
+        *field += bytes;
+        self.total += bytes;
+    }
+}
+
+/// A page over one of its budgets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BudgetViolation {
+    /// Page path relative to the site directory.
+    pub page: String,
+    /// Exceeded budget: `total` or an asset kind.
+    pub budget: String,
+    /// Bytes loaded.
+    pub bytes: u64,
+    /// Budget in bytes.
+    pub limit: u64,
+}
+
+/// Asset weights of every page of a site.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WeightReport {
+    /// Pages, sorted by path.
+    pub pages: Vec<PageWeight>,
+    /// Budgets exceeded, in page order.
+    pub violations: Vec<BudgetViolation>,
+}
+
+impl WeightReport {
+    /// Measures every HTML page below `site_dir` and checks `budgets`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the site or one of its files cannot be read.
+    pub fn build(site_dir: &Path, budgets: &Budgets) -> Result<Self> {
+        let mut files = Vec::new();
+        collect_files_recursive(site_dir, &mut files).with_context(
+            || format!("Failed to read {}", site_dir.display()),
+        )?;
+        files.sort();
+
+        let mut report = Self::default();
+        for path in files {
+            if AssetKind::of(&path) != AssetKind::Html {
+                continue;
+            }
+            let page = site_path(site_dir, &path);
+            if page == WEIGHTS_PAGE_PATH {
+                continue;
+            }
+            let weight = measure_page(site_dir, &path, page)?;
+            report.violations.extend(budgets.check(&weight));
+            report.pages.push(weight);
+        }
+        Ok(report)
+    }
+
+    /// Writes `asset-weights.json` and `asset-weights/index.html`,
+    /// returning their paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be written.
+    pub fn write(&self, site_dir: &Path) -> Result<Vec<PathBuf>> {
+        let json = site_dir.join(WEIGHTS_JSON_PATH);
+        let serialized = serde_json::to_string_pretty(self)
+            .context("Failed to serialize the asset weight report")?;
+        fs::write(&json, serialized).with_context(|| {
+            format!("Failed to write {}", json.display())
+        })?;
+
+        let page = site_dir.join(WEIGHTS_PAGE_PATH);
+        if let Some(parent) = page.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create {}", parent.display())
+            })?;
+        }
+        fs::write(&page, self.to_html()).with_context(|| {
+            format!("Failed to write {}", page.display())
+        })?;
+        Ok(vec![json, page])
+    }
+
+    /// Renders the report page, heaviest pages first.
+    pub fn to_html(&self) -> String {
+        let mut pages: Vec<&PageWeight> = self.pages.iter().collect();
+        pages.sort_by_key(|page| Reverse(page.total));
+
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>Asset weights</title>\n</head>\n<body>\n<main>\n\
+             <h1>Asset weights</h1>\n",
+        );
+        if self.violations.is_empty() {
+            html.push_str("<p>Every page is within budget.</p>\n");
+        } else {
+            html.push_str("<h2>Over budget</h2>\n<ul>\n");
+            for violation in &self.violations {
+                let _ = writeln!(
+                    html,
+                    "<li><a href=\"/{0}\">{0}</a>: {1} {2} of {3}</li>",
+                    escape_html(&violation.page),
+                    escape_html(&violation.budget),
+                    kib(violation.bytes),
+                    kib(violation.limit)
+                );
+            }
+            html.push_str("</ul>\n");
+        }
+
+        html.push_str(
+            "<table>\n<thead><tr><th scope=\"col\">Page</th>",
+        );
+        for kind in AssetKind::ALL {
+            let _ =
+                write!(html, "<th scope=\"col\">{}</th>", kind.name());
+        }
+        html.push_str(
+            "<th scope=\"col\">total</th><th scope=\"col\">external</th>\
+             </tr></thead>\n<tbody>\n",
+        );
+        for page in pages {
+            let _ = write!(
+                html,
+                "<tr><th scope=\"row\"><a href=\"/{0}\">{0}</a></th>",
+                escape_html(&page.page)
+            );
+            for kind in AssetKind::ALL {
+                let _ =
+                    write!(html, "<td>{}</td>", kib(page.bytes(kind)));
+            }
+            let _ = writeln!(
+                html,
+                "<td>{}</td><td>{}</td></tr>",
+                kib(page.total),
+                page.external
+            );
+        }
+        html.push_str(
+            "</tbody>\n</table>\n</main>\n</body>\n</html>\n",
+        );
+        html
+    }
+}
+
+/// Formats `bytes` in KiB with one decimal.
+fn kib(bytes: u64) -> String {
+    format!("{:.1} KiB", bytes as f64 / 1024.0)
+}
+
+/// Returns `path` relative to `site_dir` with `/` separators.
+fn site_path(site_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(site_dir)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Measures the page at `path` and everything it loads.
+fn measure_page(
+    site_dir: &Path,
+    path: &Path,
+    page: String,
+) -> Result<PageWeight> {
+    let html = fs::read_to_string(path).with_context(|| {
+        format!("Failed to read {}", path.display())
+    })?;
+    let mut weight = PageWeight {
+        page,
+        ..PageWeight::default()
+    };
+    weight.add(AssetKind::Html, html.len() as u64);
+
+    let mut seen = BTreeSet::new();
+    let mut queue: Vec<(PathBuf, String)> = html_references(&html)
+        .into_iter()
+        .map(|reference| (path.to_path_buf(), reference))
+        .collect();
+    while let Some((from, reference)) = queue.pop() {
+        let target = match resolve(site_dir, &from, &reference) {
+            Some(Some(target)) => target,
+            Some(None) => {
+                weight.external += 1;
+                continue;
+            }
+            None => continue,
+        };
+        if !seen.insert(target.clone()) {
+            continue;
+        }
+        let metadata = match fs::metadata(&target) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => {
+                weight.missing.push(reference);
+                continue;
+            }
+        };
+        let kind = AssetKind::of(&target);
+        weight.add(kind, metadata.len());
+        if kind == AssetKind::Css {
+            let css =
+                fs::read_to_string(&target).with_context(|| {
+                    format!("Failed to read {}", target.display())
+                })?;
+            queue.extend(
+                references(&CSS_REF_RE, &css)
+                    .into_iter()
+                    .map(|reference| (target.clone(), reference)),
+            );
+        }
+    }
+    weight.missing.sort();
+    weight.missing.dedup();
+    Ok(weight)
+}
+
+/// Returns the files loaded by the tags of `html`: stylesheets, preloads
+/// and icons of `<link>`, and the sources of scripts, images and media.
+fn html_references(html: &str) -> Vec<String> {
+    let mut references = Vec::new();
+    for tag in TAG_RE.captures_iter(html) {
+        let mut rel = None;
+        let mut urls = Vec::new();
+        for attr in ATTR_RE.captures_iter(&tag[0]) {
+            let value = attr.get(2).or_else(|| attr.get(3));
+            let value = value.map_or("", |m| m.as_str()).trim();
+            match attr[1].to_ascii_lowercase().as_str() {
+                "rel" => rel = Some(value.to_ascii_lowercase()),
+                name => urls.push((name.to_string(), value)),
+            }
+        }
+        let is_link = tag[1].eq_ignore_ascii_case("link");
+        let loads = rel.as_deref().map_or(false, |rel| {
+            rel.split_whitespace().any(|token| {
+                matches!(token, "stylesheet" | "preload" | "icon")
+            })
+        });
+        references.extend(
+            urls.into_iter()
+                .filter(|(name, _)| match name.as_str() {
+                    "href" => is_link && loads,
+                    _ => !is_link,
+                })
+                .filter(|(_, value)| !value.is_empty())
+                .map(|(_, value)| value.replace("&amp;", "&")),
+        );
+    }
+    references
+}
+
+/// Returns the non-empty references captured by `regex`.
+fn references(regex: &Regex, text: &str) -> Vec<String> {
+    regex
+        .captures_iter(text)
+        .filter_map(|caps| {
+            caps.iter()
+                .skip(1)
+                .flatten()
+                .next()
+                .map(|m| m.as_str().trim().replace("&amp;", "&"))
+        })
+        .filter(|reference| !reference.is_empty())
+        .collect()
+}
+
+/// Resolves `reference`, found in the file `from`, to a file of the
+/// site. Returns `Some(None)` for a remote file and `None` for inline
+/// data or a reference outside the site.
+fn resolve(
+    site_dir: &Path,
+    from: &Path,
+    reference: &str,
+) -> Option<Option<PathBuf>> {
+    if reference.starts_with("http://")
+        || reference.starts_with("https://")
+        || reference.starts_with("//")
+    {
+        return Some(None);
+    }
+    if reference.starts_with("data:") || reference.starts_with('#') {
+        return None;
+    }
+    let path = reference.split(&['?', '#'][..]).next()?;
+    let path = percent_decode_str(path).decode_utf8().ok()?;
+    let base = match path.strip_prefix('/') {
+        Some(_) => site_dir.to_path_buf(),
+        None => from.parent()?.to_path_buf(),
+    };
+
+    let mut target = base;
+    for component in
+        Path::new(path.trim_start_matches('/')).components()
+    {
+        match component {
+            Component::Normal(part) => target.push(part),
+            Component::ParentDir if !target.pop() => return None,
+            _ => {}
+        }
+    }
+    target.starts_with(site_dir).then(|| Some(target))
+}
+
+/// Writes the asset weight report of `site_dir` and checks its budgets,
+/// when the report is enabled.
+///
+/// Returns the report, or `None` when it is disabled.
+///
+/// # Errors
+///
+/// Returns an error if the site cannot be read, the report cannot be
+/// written, or a page is over budget with `enforce` set.
+pub fn write_weights(
+    site_dir: &Path,
+    options: &PerformanceOptions,
+) -> Result<Option<WeightReport>> {
+    if !options.report || !site_dir.exists() {
+        return Ok(None);
+    }
+    let report = WeightReport::build(site_dir, &options.budgets)?;
+    let _ = report.write(site_dir)?;
+
+    for violation in &report.violations {
+        warn!(
+            "{} is over its {} budget: {} of {}",
+            violation.page,
+            violation.budget,
+            kib(violation.bytes),
+            kib(violation.limit)
+        );
+    }
+    if options.enforce && !report.violations.is_empty() {
+        bail!(
+            "{} performance budget{} exceeded; see {}",
+            report.violations.len(),
+            if report.violations.len() == 1 {
+                ""
+            } else {
+                "s"
+            },
+            WEIGHTS_PAGE_PATH
+        );
+    }
+    Ok(Some(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn site() -> Result<tempfile::TempDir> {
+        let site = tempdir()?;
+        fs::create_dir_all(site.path().join("css/fonts"))?;
+        fs::create_dir_all(site.path().join("blog"))?;
+        fs::write(
+            site.path().join("css/site.css"),
+            "@font-face { src: url('fonts/a.woff2'); }\n\
+             body { background: url(\"/bg.png?v=1\"); }",
+        )?;
+        fs::write(site.path().join("css/fonts/a.woff2"), [0; 2048])?;
+        fs::write(site.path().join("bg.png"), [0; 1000])?;
+        fs::write(site.path().join("app.js"), [0; 300])?;
+        fs::write(
+            site.path().join("blog/index.html"),
+            "<link rel=\"stylesheet\" href=\"/css/site.css\">\
+             <script src=\"../app.js\"></script>\
+             <img src=\"../bg.png\">\
+             <img src=\"/missing.jpg\">\
+             <script src=\"https://cdn.example.com/x.js\"></script>",
+        )?;
+        Ok(site)
+    }
+
+    #[test]
+    fn test_measure_page() -> Result<()> {
+        let site = site()?;
+        let report =
+            WeightReport::build(site.path(), &Budgets::default())?;
+        assert_eq!(report.pages.len(), 1);
+
+        let page = &report.pages[0];
+        assert_eq!(page.page, "blog/index.html");
+        assert_eq!(page.js, 300);
+        assert_eq!(page.images, 1000);
+        assert_eq!(page.fonts, 2048);
+        assert_eq!(page.external, 1);
+        assert_eq!(page.missing, ["/missing.jpg"]);
+        assert_eq!(
+            page.total,
+            page.html + page.css + page.js + page.images + page.fonts
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_budgets() -> Result<()> {
+        let site = site()?;
+        let options = PerformanceOptions {
+            report: true,
+            enforce: false,
+            budgets: Budgets {
+                fonts: Some(1),
+                js: Some(1),
+                ..Budgets::default()
+            },
+        };
+        let report = write_weights(site.path(), &options)?.unwrap();
+        let budgets: Vec<&str> = report
+            .violations
+            .iter()
+            .map(|violation| violation.budget.as_str())
+            .collect();
+        assert_eq!(budgets, ["fonts"]);
+        assert!(site.path().join(WEIGHTS_JSON_PATH).exists());
+        assert!(fs::read_to_string(
+            site.path().join(WEIGHTS_PAGE_PATH)
+        )?
+        .contains("blog/index.html</a>: fonts 2.0 KiB of 1.0 KiB"));
+
+        let enforced = PerformanceOptions {
+            enforce: true,
+            ..options
+        };
+        assert!(write_weights(site.path(), &enforced).is_err());
+        Ok(())
+    }
+}
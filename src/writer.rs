@@ -0,0 +1,293 @@
+// Copyright © 2025 Shokunin Static Site Generator (SSG). All rights reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! # Output Write Queue
+//!
+//! A single writer task that owns all writes to the output tree. Rendering
+//! and copying can stay parallel (rayon workers or tokio tasks) and hand
+//! their results to the queue, which performs the actual filesystem writes
+//! one at a time. This removes races on shared directories and memoises
+//! directory creation so each parent is only created once.
+//!
+//! ```rust,no_run
+//! use ssg::writer::WriteQueue;
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let queue = WriteQueue::spawn();
+//!     queue.write("public/index.html", "<h1>Hello</h1>")?;
+//!     queue.copy("static/logo.svg", "public/logo.svg")?;
+//!
+//!     let stats = queue.finish().await?;
+//!     println!("Wrote {} files", stats.files_written);
+//!     Ok(())
+//! }
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+};
+
+/// A unit of work for the writer task.
+#[derive(Debug)]
+enum WriteJob {
+    /// Write the given bytes to `path`, replacing any existing file.
+    Write { path: PathBuf, contents: Vec<u8> },
+    /// Copy the file at `src` to `dst`.
+    Copy { src: PathBuf, dst: PathBuf },
+}
+
+/// Statistics reported once the queue has been drained.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteStats {
+    /// Number of files written or copied.
+    pub files_written: usize,
+    /// Total number of bytes written or copied.
+    pub bytes_written: u64,
+    /// Number of directories created by the queue.
+    pub directories_created: usize,
+    /// Paths that were written more than once during this run.
+    ///
+    /// The last write wins; a non-empty list usually means two pages map to
+    /// the same output path.
+    pub overwritten: Vec<PathBuf>,
+}
+
+/// Handle to the single writer task.
+///
+/// The handle is cheap to share by reference across threads: enqueueing
+/// never blocks and never touches the filesystem.
+#[derive(Debug)]
+pub struct WriteQueue {
+    sender: UnboundedSender<WriteJob>,
+    handle: JoinHandle<Result<WriteStats>>,
+}
+
+impl WriteQueue {
+    /// Spawns the writer task on the current tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn spawn() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let handle = tokio::spawn(run_writer(receiver));
+        Self { sender, handle }
+    }
+
+    /// Queues `contents` to be written to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the writer task has already stopped, which
+    /// happens after a previous write failed.
+    pub fn write(
+        &self,
+        path: impl Into<PathBuf>,
+        contents: impl Into<Vec<u8>>,
+    ) -> Result<()> {
+        self.send(WriteJob::Write {
+            path: path.into(),
+            contents: contents.into(),
+        })
+    }
+
+    /// Queues a copy of the file at `src` to `dst`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the writer task has already stopped.
+    pub fn copy(
+        &self,
+        src: impl Into<PathBuf>,
+        dst: impl Into<PathBuf>,
+    ) -> Result<()> {
+        self.send(WriteJob::Copy {
+            src: src.into(),
+            dst: dst.into(),
+        })
+    }
+
+    fn send(&self, job: WriteJob) -> Result<()> {
+        self.sender
+            .send(job)
+            .map_err(|_| anyhow!("Output writer has stopped"))
+    }
+
+    /// Closes the queue, waits for all pending writes and returns the stats.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first write error encountered by the writer task.
+    pub async fn finish(self) -> Result<WriteStats> {
+        drop(self.sender);
+        self.handle
+            .await
+            .map_err(|e| anyhow!("Output writer task failed: {}", e))?
+    }
+}
+
+/// Drains the queue, writing each job in arrival order.
+async fn run_writer(
+    mut receiver: UnboundedReceiver<WriteJob>,
+) -> Result<WriteStats> {
+    let mut stats = WriteStats::default();
+    let mut created_dirs = HashSet::new();
+    let mut seen = HashSet::new();
+
+    while let Some(job) = receiver.recv().await {
+        let (target, bytes) = match job {
+            WriteJob::Write { path, contents } => {
+                ensure_parent(&path, &mut created_dirs, &mut stats)
+                    .await?;
+                tokio::fs::write(&path, &contents).await.with_context(
+                    || format!("Failed to write {}", path.display()),
+                )?;
+                (path, contents.len() as u64)
+            }
+            WriteJob::Copy { src, dst } => {
+                ensure_parent(&dst, &mut created_dirs, &mut stats)
+                    .await?;
+                let bytes = tokio::fs::copy(&src, &dst)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "Failed to copy {} to {}",
+                            src.display(),
+                            dst.display()
+                        )
+                    })?;
+                (dst, bytes)
+            }
+        };
+
+        stats.files_written += 1;
+        stats.bytes_written += bytes;
+        if !seen.insert(target.clone()) {
+            stats.overwritten.push(target);
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Creates the parent directory of `path` unless it was already created.
+async fn ensure_parent(
+    path: &Path,
+    created_dirs: &mut HashSet<PathBuf>,
+    stats: &mut WriteStats,
+) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => return Ok(()),
+    };
+    if created_dirs.contains(parent) {
+        return Ok(());
+    }
+
+    tokio::fs::create_dir_all(parent).await.with_context(|| {
+        format!("Failed to create directory {}", parent.display())
+    })?;
+    let _ = created_dirs.insert(parent.to_path_buf());
+    stats.directories_created += 1;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_write_queue_writes_and_memoizes_dirs() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let out = temp_dir.path().join("public");
+
+        let queue = WriteQueue::spawn();
+        queue.write(out.join("blog/a.html"), "a")?;
+        queue.write(out.join("blog/b.html"), "bb")?;
+        queue.write(out.join("index.html"), "index")?;
+        let stats = queue.finish().await?;
+
+        assert_eq!(stats.files_written, 3);
+        assert_eq!(stats.bytes_written, 8);
+        assert_eq!(stats.directories_created, 2);
+        assert!(stats.overwritten.is_empty());
+        assert_eq!(fs::read_to_string(out.join("blog/b.html"))?, "bb");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_queue_parallel_producers() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let out = temp_dir.path().to_path_buf();
+
+        let queue = WriteQueue::spawn();
+        (0..200).into_par_iter().try_for_each(|i| {
+            queue.write(
+                out.join(format!("section-{}/page-{}.html", i % 7, i)),
+                format!("page {}", i),
+            )
+        })?;
+        let stats = queue.finish().await?;
+
+        assert_eq!(stats.files_written, 200);
+        assert_eq!(stats.directories_created, 7);
+        assert_eq!(
+            fs::read_to_string(out.join("section-3/page-10.html"))?,
+            "page 10"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_queue_reports_overwrites() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("same.html");
+
+        let queue = WriteQueue::spawn();
+        queue.write(&path, "first")?;
+        queue.write(&path, "second")?;
+        let stats = queue.finish().await?;
+
+        assert_eq!(stats.overwritten, vec![path.clone()]);
+        assert_eq!(fs::read_to_string(&path)?, "second");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_queue_copy() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let src = temp_dir.path().join("logo.svg");
+        fs::write(&src, "<svg/>")?;
+
+        let queue = WriteQueue::spawn();
+        queue.copy(&src, temp_dir.path().join("out/logo.svg"))?;
+        let stats = queue.finish().await?;
+
+        assert_eq!(stats.bytes_written, 6);
+        assert!(temp_dir.path().join("out/logo.svg").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_queue_reports_first_error() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let queue = WriteQueue::spawn();
+        queue.copy(
+            temp_dir.path().join("missing.txt"),
+            temp_dir.path().join("out.txt"),
+        )?;
+
+        assert!(queue.finish().await.is_err());
+        Ok(())
+    }
+}